@@ -0,0 +1,146 @@
+use {
+    bencher::{benchmark_group, benchmark_main, black_box, Bencher},
+    makepad_code_editor::{
+        decoration::DecorationSet,
+        history::NewGroup,
+        selection::Affinity,
+        session::SelectionMode,
+        text::{Change, Length, Position, Text},
+        Document, Session,
+    },
+};
+
+const LINE_COUNT: usize = 1_000_000;
+
+fn large_string() -> String {
+    (0..LINE_COUNT)
+        .map(|index| {
+            format!(
+                "    let value_{} = compute({}) + 1; // comment\n",
+                index, index
+            )
+        })
+        .collect()
+}
+
+fn from_str(bencher: &mut Bencher) {
+    let string = large_string();
+    bencher.iter(|| Text::from(black_box(string.as_str())));
+}
+
+fn insert_char_at_start(bencher: &mut Bencher) {
+    let mut text = Text::from(large_string());
+    bencher.iter(|| {
+        text.apply_change(Change::Insert(Position::zero(), Text::from('x')));
+        text.apply_change(Change::Delete(
+            Position::zero(),
+            Length {
+                line_count: 0,
+                byte_count: 1,
+            },
+        ));
+    });
+}
+
+fn insert_line_at_start(bencher: &mut Bencher) {
+    let mut text = Text::from(large_string());
+    bencher.iter(|| {
+        text.apply_change(Change::Insert(Position::zero(), Text::newline()));
+        text.apply_change(Change::Delete(
+            Position::zero(),
+            Length {
+                line_count: 1,
+                byte_count: 0,
+            },
+        ));
+    });
+}
+
+fn delete_lines_in_middle(bencher: &mut Bencher) {
+    let text = Text::from(large_string());
+    let start = Position {
+        line_index: LINE_COUNT / 2,
+        byte_index: 0,
+    };
+    let length = Length {
+        line_count: 1000,
+        byte_count: 0,
+    };
+    bencher.iter(|| {
+        let mut text = text.clone();
+        text.apply_change(Change::Delete(start, length));
+        text
+    });
+}
+
+fn clone_and_edit(bencher: &mut Bencher) {
+    let text = Text::from(large_string());
+    let position = Position {
+        line_index: LINE_COUNT / 2,
+        byte_index: 4,
+    };
+    bencher.iter(|| {
+        let mut text = text.clone();
+        text.apply_change(Change::Insert(position, Text::from('x')));
+        text
+    });
+}
+
+fn line_lookup(bencher: &mut Bencher) {
+    let text = Text::from(large_string());
+    bencher.iter(|| {
+        let mut byte_count = 0;
+        for line_index in (0..LINE_COUNT).step_by(997) {
+            byte_count += text.as_lines()[line_index].len();
+        }
+        byte_count
+    });
+}
+
+fn iterate_lines(bencher: &mut Bencher) {
+    let text = Text::from(large_string());
+    bencher.iter(|| text.as_lines().iter().map(|line| line.len()).sum::<usize>());
+}
+
+fn open_document(bencher: &mut Bencher) {
+    let string = large_string();
+    bencher.iter(|| {
+        let session = Session::new(Document::new(
+            Text::from(string.as_str()),
+            DecorationSet::new(),
+        ));
+        let height = session.layout().height();
+        height
+    });
+}
+
+fn type_at_start_of_document(bencher: &mut Bencher) {
+    let session = Session::new(Document::new(
+        Text::from(large_string()),
+        DecorationSet::new(),
+    ));
+    session.set_selection(
+        Position::zero(),
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    bencher.iter(|| {
+        session.insert(Text::from('x'));
+        session.backspace();
+        session.handle_changes();
+    });
+}
+
+benchmark_group!(
+    text,
+    from_str,
+    insert_char_at_start,
+    insert_line_at_start,
+    delete_lines_in_middle,
+    clone_and_edit,
+    line_lookup,
+    iterate_lines
+);
+benchmark_group!(session, open_document, type_at_start_of_document);
+benchmark_main!(text, session);
@@ -1,23 +1,46 @@
 use {
     crate::{
+        completion::Completion,
         decoration::{Decoration, DecorationType},
+        keymap::{Command, KeyChord, Keymap, Resolution},
         layout::{BlockElement, WrappedElement},
+        merge::ConflictResolution,
         selection::Affinity,
         session::{SelectionMode, Session},
         history::{NewGroup},
+        provider::{
+            is_word_char, CompletionProvider, HoverProvider, SignatureHelp,
+            SignatureHelpProvider,
+        },
+        regex::Regex,
+        search::SearchQuery,
         settings::Settings,
         str::StrExt,
         text::Position,
         token::TokenKind,
+        vim::{Vim, VimKey, VimMode},
+        word_provider::WordProvider,
         Line, Selection, Token,
     },
-    makepad_widgets::*,
+    makepad_widgets::{markdown::MarkdownWidgetExt, *},
     std::fmt::Write,
-    std::{mem, slice::Iter},
+    std::{mem, rc::Rc, slice::Iter},
 };
 
+const SEARCH_LINES_PER_FRAME: usize = 2000;
+const COMPLETION_VISIBLE_ITEM_COUNT: usize = 10;
+// The number of word chars that must be typed before the completion popup opens by itself.
+const COMPLETION_MIN_WORD_LEN: usize = 2;
+const HOVER_DELAY: f64 = 0.5;
+// The size of a character in the minimap.
+const MINIMAP_ROW_HEIGHT: f64 = 2.0;
+const MINIMAP_COLUMN_WIDTH: f64 = 1.0;
+// Keeps the minimap clear of the vertical scroll bar.
+const MINIMAP_MARGIN_RIGHT: f64 = 10.0;
+
 live_design! {
     import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
     import makepad_widgets::theme_desktop_dark::*;
 
     TokenColors = {{TokenColors}} {
@@ -26,6 +49,13 @@ live_design! {
         delimiter_highlight: #f,
         error_decoration: #f00,
         warning_decoration: #0f0,
+        search_match: #ffffff22,
+        active_search_match: #e8a33d66,
+        conflict_marker: #ffffff18,
+        conflict_ours: #3d8fe833,
+        conflict_theirs: #4fc14f33,
+        remote_selection: #c678dd33,
+        remote_cursor: #c678dd,
         
         unknown: #C0C0C0,
         branch_keyword: #C485BE,
@@ -150,6 +180,111 @@ live_design! {
         draw_selection: {
            // draw_depth: 3.0,
         }
+        draw_search_match: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0.0, 0.0, self.rect_size.x, self.rect_size.y, 2.0);
+                return sdf.fill(self.color);
+            }
+        }
+
+        minimap_width: 100.0,
+        draw_minimap_bg: {
+            color: (THEME_COLOR_BG_CONTAINER),
+        }
+        draw_minimap_viewport: {
+            color: #ffffff14,
+        }
+        draw_sticky_scroll_bg: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.rect(0.0, 0.0, self.rect_size.x, self.rect_size.y);
+                sdf.fill(self.color);
+                sdf.rect(0.0, self.rect_size.y - 1.0, self.rect_size.x, 1.0);
+                sdf.fill(THEME_COLOR_U_2);
+                return sdf.result;
+            }
+            color: (THEME_COLOR_BG_CONTAINER),
+        }
+
+        find_bar: <RoundedView> {
+            width: 440, height: Fit,
+            margin: {top: 4.0}
+            padding: <THEME_MSPACE_1> {}
+            flow: Down, spacing: (THEME_SPACE_1),
+            optimize: DrawList,
+            draw_bg: { color: (THEME_COLOR_BG_APP), radius: 4.0 }
+
+            find_row = <View> {
+                width: Fill, height: Fit,
+                align: {y: 0.5}, spacing: (THEME_SPACE_1),
+                query_input = <TextInput> { width: Fill, empty_message: "Find" }
+                case_toggle = <CheckBox> { text: "Aa" }
+                word_toggle = <CheckBox> { text: "W" }
+                regex_toggle = <CheckBox> { text: ".*" }
+                match_count = <Label> { width: 70, text: "" }
+                prev_button = <ButtonFlat> { text: "<" }
+                next_button = <ButtonFlat> { text: ">" }
+            }
+            replace_row = <View> {
+                width: Fill, height: Fit,
+                align: {y: 0.5}, spacing: (THEME_SPACE_1),
+                replace_input = <TextInput> { width: Fill, empty_message: "Replace" }
+                replace_button = <ButtonFlat> { text: "Replace" }
+                replace_all_button = <ButtonFlat> { text: "All" }
+            }
+        }
+
+        draw_conflict_action: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_META),
+        }
+
+        draw_completion_bg: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0.0, 0.0, self.rect_size.x, self.rect_size.y, 4.0);
+                sdf.fill_keep(self.color);
+                sdf.stroke(THEME_COLOR_U_2, 1.0);
+                return sdf.result;
+            }
+            color: (THEME_COLOR_BG_APP),
+        }
+        draw_completion_selection: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0.0, 0.0, self.rect_size.x, self.rect_size.y, 2.0);
+                return sdf.fill(self.color);
+            }
+            color: (THEME_COLOR_CTRL_SELECTED),
+        }
+        draw_completion_text: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_DEFAULT),
+        }
+        draw_completion_match: {
+            text_style: <THEME_FONT_CODE> {},
+            color: #e8a33d,
+        }
+        draw_completion_detail: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_META),
+        }
+
+        hover_tooltip: <RoundedView> {
+            width: 480, height: Fit,
+            padding: <THEME_MSPACE_1> {}
+            optimize: DrawList,
+            draw_bg: { color: (THEME_COLOR_BG_APP), radius: 4.0 }
+            markdown = <Markdown> {}
+        }
+        signature_tooltip: <RoundedView> {
+            width: 480, height: Fit,
+            padding: <THEME_MSPACE_1> {}
+            optimize: DrawList,
+            draw_bg: { color: (THEME_COLOR_BG_APP), radius: 4.0 }
+            markdown = <Markdown> {}
+        }
 
         draw_cursor: {
           //  draw_depth: 4.0,
@@ -230,9 +365,16 @@ pub struct CodeEditor {
     #[live] draw_indent_guide: DrawIndentGuide,
     #[live] draw_decoration: DrawDecoration,
     #[live] draw_selection: DrawSelection,
+    #[live] draw_search_match: DrawColor,
+    #[live] draw_conflict_action: DrawText,
     #[live] draw_cursor: DrawColor,
     #[live] draw_cursor_bg: DrawColor,
     #[live] draw_bg: DrawColor,
+    #[live] draw_minimap_bg: DrawColor,
+    #[live] draw_minimap: DrawColor,
+    #[live] draw_minimap_viewport: DrawColor,
+    #[live] draw_sticky_scroll_bg: DrawColor,
+    #[live] minimap_width: f64,
     #[rust(KeepCursorInView::Off)] keep_cursor_in_view: KeepCursorInView,
     #[rust] last_cursor_screen_pos: Option<DVec2>,
 
@@ -250,6 +392,88 @@ pub struct CodeEditor {
     #[animator] animator: Animator,
 
     #[rust] blink_timer: Timer,
+
+    #[live] find_bar: View,
+    #[rust] find_bar_is_open: bool,
+    #[rust] search_query: SearchQuery,
+    #[rust] search_error: Option<String>,
+    #[rust] search_next_frame: Option<NextFrame>,
+
+    // The clickable resolutions drawn after the start marker of each visible conflict.
+    #[rust] conflict_actions: Vec<(Rect, usize, ConflictResolution)>,
+
+    // The minimap and the sticky scroll headers are drawn on top of the text.
+    #[rust(DrawList2d::new(cx))] overlay_draw_list: DrawList2d,
+    #[rust] minimap_rect: Rect,
+    // The part of the minimap that shows the lines in view.
+    #[rust] minimap_viewport_rect: Rect,
+    // How far the viewport of the minimap moves when the editor scrolls by one pixel.
+    #[rust] minimap_scale: f64,
+    // Where the viewport of the minimap is held while it is dragged, relative to its top.
+    #[rust] minimap_drag_offset: Option<f64>,
+    // The sticky scroll headers that are drawn, and the lines that they show.
+    #[rust] sticky_headers: Vec<(Rect, usize)>,
+
+    #[live] draw_completion_bg: DrawColor,
+    #[live] draw_completion_selection: DrawColor,
+    #[live] draw_completion_text: DrawText,
+    #[live] draw_completion_match: DrawText,
+    #[live] draw_completion_detail: DrawText,
+    #[rust(DrawList2d::new(cx))] completion_draw_list: DrawList2d,
+    #[rust] completion: Option<Completion>,
+    #[rust(Rc::new(WordProvider::default()) as Rc<dyn CompletionProvider>)]
+    completion_provider: Rc<dyn CompletionProvider>,
+
+    #[live] signature_tooltip: View,
+    #[rust] signature_help: Option<SignatureHelp>,
+    #[rust(Rc::new(WordProvider::default()) as Rc<dyn SignatureHelpProvider>)]
+    signature_help_provider: Rc<dyn SignatureHelpProvider>,
+
+    #[live] hover_tooltip: View,
+    #[rust] hover_timer: Timer,
+    // The position under the finger and where it is on the screen.
+    #[rust] hover_position: Option<(Position, DVec2)>,
+    #[rust] hover_is_open: bool,
+    #[rust(Rc::new(WordProvider::default()) as Rc<dyn HoverProvider>)]
+    hover_provider: Rc<dyn HoverProvider>,
+
+    // Where an Alt+drag column selection started.
+    #[rust] column_selection_anchor: Option<(Position, Affinity)>,
+
+    #[rust] keymap: Rc<Keymap>,
+    // The chords typed so far of a key sequence that is bound to a command.
+    #[rust] pending_chords: Vec<KeyChord>,
+    #[rust] vim: Vim,
+}
+
+// Returns the key that the Vim emulation sees for a key that does not produce text input.
+fn vim_key(event: &KeyEvent) -> Option<VimKey> {
+    let KeyModifiers {
+        shift,
+        control,
+        alt,
+        logo,
+    } = event.modifiers;
+    if alt || logo {
+        return None;
+    }
+    if control {
+        let name = KeyChord::new(event.key_code, KeyModifiers::default()).key_name()?;
+        let mut chars = name.chars();
+        let char = chars.next().filter(|char| char.is_ascii_alphabetic())?;
+        return chars.next().is_none().then_some(VimKey::Ctrl(char));
+    }
+    Some(match event.key_code {
+        KeyCode::Escape => VimKey::Escape,
+        KeyCode::ReturnKey => VimKey::Enter,
+        KeyCode::Backspace => VimKey::Backspace,
+        KeyCode::Tab if !shift => VimKey::Tab,
+        KeyCode::ArrowLeft => VimKey::Left,
+        KeyCode::ArrowRight => VimKey::Right,
+        KeyCode::ArrowUp => VimKey::Up,
+        KeyCode::ArrowDown => VimKey::Down,
+        _ => return None,
+    })
 }
 
 enum KeepCursorInView {
@@ -469,6 +693,13 @@ impl CodeEditor {
         self.viewport_rect.pos += pad_left_top;
         self.viewport_rect.size -= pad_left_top;
 
+        // A Fit height editor never scrolls, so it has no use for a minimap or sticky headers.
+        let show_minimap = session.settings().show_minimap && !height_is_fit;
+        let sticky_scroll = session.settings().sticky_scroll && !height_is_fit;
+        if show_minimap {
+            self.viewport_rect.size.x -= self.minimap_width + MINIMAP_MARGIN_RIGHT;
+        }
+
         session.set_wrap_column(if self.word_wrap {
             Some((self.viewport_rect.size.x / self.cell_size.x) as usize)
         } else {
@@ -483,16 +714,42 @@ impl CodeEditor {
         self.line_end = session.layout().find_first_line_starting_after_y(
             (scroll_pos.y + self.viewport_rect.size.y) / self.cell_size.y,
         );
+        // Laying out the visible lines can change their height, so find the visible lines again.
+        session.lay_out_lines(self.line_start..self.line_end);
+        self.line_start = session
+            .layout()
+            .find_first_line_ending_after_y(scroll_pos.y / self.cell_size.y - self.cell_size.y);
+        self.line_end = session.layout().find_first_line_starting_after_y(
+            (scroll_pos.y + self.viewport_rect.size.y) / self.cell_size.y,
+        );
+        session.lay_out_lines(self.line_start..self.line_end);
         self.unscrolled_rect = cx.turtle().unscrolled_rect();
         self.draw_bg.draw_abs(cx, cx.turtle().unscrolled_rect());
 
         self.draw_gutter(cx, session);
         self.draw_selection_layer(cx, session);
+        self.draw_search_layer(cx, session);
+        self.draw_conflict_layer(cx, session);
+        self.draw_remote_selection_layer(cx, session);
         self.draw_text_layer(cx, session);
         self.draw_indent_guide_layer(cx, session);
         self.draw_decoration_layer(cx, session);
         self.draw_selection_layer(cx, session);
 
+        self.overlay_draw_list.begin_always(cx);
+        if show_minimap {
+            self.draw_minimap(cx, session);
+        } else {
+            self.minimap_rect = Rect::default();
+            self.minimap_drag_offset = None;
+        }
+        if sticky_scroll {
+            self.draw_sticky_headers(cx, session);
+        } else {
+            self.sticky_headers.clear();
+        }
+        self.overlay_draw_list.end(cx);
+
         // Get the last added selection.
         // Get the normalized cursor position. To go from normalized to screen position, multiply by
         // the cell size, then shift by the viewport origin.
@@ -506,6 +763,12 @@ impl CodeEditor {
         //println!("{} {}", session.layout().height() * self.cell_size.y, (self.viewport_rect.size.y));
 
         self.scroll_bars.end(cx);
+        if self.find_bar_is_open {
+            self.draw_find_bar(cx, session);
+        }
+        self.draw_hover_tooltip(cx);
+        self.draw_signature_tooltip(cx, session);
+        self.draw_completion_popup(cx, session);
         if session.update_folds() {
             self.scroll_bars.area().redraw(cx);
         } else if self.keep_cursor_in_view.is_locked() {
@@ -513,6 +776,18 @@ impl CodeEditor {
         }
     }
 
+    pub fn set_completion_provider(&mut self, provider: Rc<dyn CompletionProvider>) {
+        self.completion_provider = provider;
+    }
+
+    pub fn set_signature_help_provider(&mut self, provider: Rc<dyn SignatureHelpProvider>) {
+        self.signature_help_provider = provider;
+    }
+
+    pub fn set_hover_provider(&mut self, provider: Rc<dyn HoverProvider>) {
+        self.hover_provider = provider;
+    }
+
     pub fn set_key_focus(&mut self, cx: &mut Cx) {
         cx.set_key_focus(self.scroll_bars.area());
     }
@@ -575,10 +850,28 @@ impl CodeEditor {
 
         session.handle_changes();
 
+        if self.find_bar_is_open {
+            let find_bar_actions = cx.capture_actions(|cx| self.find_bar.handle_event(cx, event, scope));
+            if self.handle_find_bar_actions(cx, &find_bar_actions, session) {
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+        }
+        if let Some(next_frame) = self.search_next_frame {
+            if next_frame.is_event(event).is_some() {
+                self.search_next_frame = None;
+                session.search_step(SEARCH_LINES_PER_FRAME);
+                self.redraw(cx);
+            }
+        }
+
         if self.scroll_bars.handle_event(cx, event, scope).len()>0{
             self.redraw(cx);
         };
         
+        if self.hover_timer.is_event(event).is_some() {
+            self.open_hover(cx, session);
+        }
+
         if self.blink_timer.is_event(event).is_some() {
             if self.animator_in_state(cx, id!(blink.off)) {
                 self.animator_play(cx, id!(blink.on));
@@ -588,9 +881,24 @@ impl CodeEditor {
             self.blink_timer = cx.start_timeout(self.blink_speed)
         }
         let mut keyboard_moved_cursor = false;
-        match event.hits(cx, self.scroll_bars.area()) {
+        let mut hit = event.hits(cx, self.scroll_bars.area());
+        if let Hit::KeyDown(_) | Hit::TextInput(_) | Hit::FingerDown(_) = hit {
+            self.close_hover(cx);
+        }
+        if self.handle_completion_keys(cx, &hit, session, &mut actions) {
+            keyboard_moved_cursor = true;
+            hit = Hit::Nothing;
+        }
+        if self.handle_bound_keys(cx, &hit, session, &mut actions) {
+            keyboard_moved_cursor = true;
+            hit = Hit::Nothing;
+        }
+        match hit {
             Hit::KeyFocusLost(_) => {
                 self.animator_play(cx, id!(focus.off));
+                self.close_completion(cx);
+                self.close_signature_help(cx);
+                self.close_hover(cx);
             }
             Hit::KeyFocus(_) => {
                 self.animator_play(cx, id!(focus.on));
@@ -616,159 +924,13 @@ impl CodeEditor {
                 }
                 self.redraw(cx);
             }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Minus,
-                modifiers: KeyModifiers { control, logo, .. },
-                ..
-            }) => {
-                if control || logo {
-                    self.decrease_font_size();
-                    self.redraw(cx);
-                }
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Key0,
-                modifiers: KeyModifiers { control, logo, .. },
-                ..
-            }) => {
-                if control || logo {
-                    self.reset_font_size();
-                    self.redraw(cx);
-                }
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Equals,
-                modifiers: KeyModifiers { control, logo, .. },
-                ..
-            }) => {
-                if control || logo {
-                    self.increase_font_size();
-                    self.redraw(cx);
-                }
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::KeyW,
-                modifiers: KeyModifiers { control, logo, .. },
-                ..
-            }) => {
-                if control || logo {
-                    self.word_wrap = !self.word_wrap;
-                    self.redraw(cx);
-                }
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::KeyA,
-                modifiers: KeyModifiers {control, logo, ..},
-                ..
-            }) => {
-                if control || logo {
-                    //session.select_all();
-                    self.redraw(cx);
-                }
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::ArrowLeft,
-                modifiers:
-                    KeyModifiers {
-                        shift,
-                        control,
-                        logo,
-                        ..
-                    },
-                ..
-            }) => {
-                if control || logo {
-                    //session.move_to_start_of_line(!shift);
-                } else {
-                    session.move_left(!shift);
-                }
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::ArrowRight,
-                modifiers:
-                    KeyModifiers {
-                        shift,
-                        control,
-                        logo,
-                        ..
-                    },
-                ..
-            }) => {
-                if control || logo {
-                    //session.move_to_end_of_line(!shift);
-                } else {
-                    session.move_right(!shift);
-                }
-
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::ArrowUp,
-                modifiers: KeyModifiers { shift, .. },
-                ..
-            }) => {
-                session.move_up(!shift);
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::ArrowDown,
-                modifiers: KeyModifiers { shift, .. },
-                ..
-            }) => {
-                session.move_down(!shift);
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Home,
-                modifiers: KeyModifiers { shift, .. },
-                ..
-            }) => {
-                session.home(!shift);
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::End,
-                modifiers: KeyModifiers { shift, .. },
-                ..
-            }) => {
-                session.end(!shift);
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::PageUp,
-                modifiers: KeyModifiers { shift, .. },
-                ..
-            }) => {
-                for _ in 0..self.line_end - self.line_start - 3 {
-                    session.move_up(!shift);
-                }
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::PageDown,
-                modifiers: KeyModifiers { shift, .. },
-                ..
-            }) => {
-                for _ in 0..self.line_end - self.line_start - 3 {
-                    session.move_down(!shift);
-                }
-                keyboard_moved_cursor = true;
-                self.redraw(cx);
-            }
             Hit::TextInput(TextInputEvent {
                 ref input,
                 was_paste: false,
                 ..
             }) if input.len() > 0 => {
                 session.insert(input.into());
+                self.handle_typed_text(cx, session, input);
                 self.redraw(cx);
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
@@ -783,53 +945,6 @@ impl CodeEditor {
                 keyboard_moved_cursor = true;
                 actions.push(CodeEditorAction::TextDidChange);
             }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::ReturnKey,
-                ..
-            }) => {
-                session.enter();
-                self.redraw(cx);
-                keyboard_moved_cursor = true;
-                actions.push(CodeEditorAction::TextDidChange);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Tab,
-                modifiers: KeyModifiers { shift: false, .. },
-                ..
-            }) => {
-                session.indent();
-                self.redraw(cx);
-                keyboard_moved_cursor = true;
-                actions.push(CodeEditorAction::TextDidChange);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Tab,
-                modifiers: KeyModifiers { shift: true, .. },
-                ..
-            }) => {
-                session.outdent();
-                self.redraw(cx);
-                keyboard_moved_cursor = true;
-                actions.push(CodeEditorAction::TextDidChange);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Delete,
-                ..
-            }) => {
-                session.delete();
-                self.redraw(cx);
-                keyboard_moved_cursor = true;
-                actions.push(CodeEditorAction::TextDidChange);
-            }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::Backspace,
-                ..
-            }) => {
-                session.backspace();
-                self.redraw(cx);
-                keyboard_moved_cursor = true;
-                actions.push(CodeEditorAction::TextDidChange);
-            }
             Hit::TextCopy(ce) => {
                 *ce.response.borrow_mut() = Some(session.copy());
                 keyboard_moved_cursor = true;
@@ -840,37 +955,40 @@ impl CodeEditor {
                 keyboard_moved_cursor = true;
                 self.redraw(cx);
             }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::KeyZ,
-                modifiers:
-                    KeyModifiers {
-                        logo: true,
-                        shift: false,
-                        ..
-                    },
-                ..
-            }) => {
-                if session.undo() {
-                    cx.redraw_all();
-                    actions.push(CodeEditorAction::TextDidChange);
-                    keyboard_moved_cursor = true;
-                }
+            Hit::FingerDown(FingerDownEvent { abs, .. }) if self.minimap_rect.contains(abs) => {
+                let viewport_rect = self.minimap_viewport_rect;
+                self.minimap_drag_offset = Some(if viewport_rect.contains(abs) {
+                    abs.y - viewport_rect.pos.y
+                } else {
+                    viewport_rect.size.y / 2.0
+                });
+                self.drag_minimap_to(cx, abs);
             }
-            Hit::KeyDown(KeyEvent {
-                key_code: KeyCode::KeyZ,
-                modifiers:
-                    KeyModifiers {
-                        logo: true,
-                        shift: true,
-                        ..
+            Hit::FingerMove(FingerMoveEvent { abs, .. }) if self.minimap_drag_offset.is_some() => {
+                self.drag_minimap_to(cx, abs);
+            }
+            Hit::FingerDown(FingerDownEvent { abs, .. })
+                if self.sticky_header_at(abs).is_some() =>
+            {
+                let line_index = self.sticky_header_at(abs).unwrap();
+                self.animator_play(cx, id!(focus.on));
+                cx.set_key_focus(self.scroll_bars.area());
+                self.set_cursor_and_scroll(
+                    cx,
+                    Position {
+                        line_index,
+                        byte_index: 0,
                     },
-                ..
-            }) => {
-                if session.redo() {
-                    self.redraw(cx);
-                    actions.push(CodeEditorAction::TextDidChange);
-                    keyboard_moved_cursor = true;
-                }
+                    session,
+                );
+            }
+            Hit::FingerDown(FingerDownEvent { abs, .. })
+                if self.conflict_action_at(abs).is_some() =>
+            {
+                let (index, resolution) = self.conflict_action_at(abs).unwrap();
+                session.resolve_conflict(index, resolution);
+                self.redraw(cx);
+                actions.push(CodeEditorAction::TextDidChange);
             }
             Hit::FingerDown(FingerDownEvent {
                 abs,
@@ -933,49 +1051,665 @@ impl CodeEditor {
                         }
                     },
                 );
+                if !is_in_gutter && tap_count == 1 {
+                    self.column_selection_anchor = Some((cursor, affinity));
+                }
                 self.reset_cursor_blinker(cx);
                 self.keep_cursor_in_view = KeepCursorInView::Always(abs, cx.new_next_frame());
                 self.redraw(cx);
             }
-            Hit::FingerUp(_) => {
-                self.reset_cursor_blinker(cx);
-                self.keep_cursor_in_view = KeepCursorInView::Off;
+            Hit::FingerUp(_) => {
+                self.reset_cursor_blinker(cx);
+                self.keep_cursor_in_view = KeepCursorInView::Off;
+                self.column_selection_anchor = None;
+                self.minimap_drag_offset = None;
+            }
+            Hit::FingerHoverIn(FingerHoverEvent { abs, .. })
+            | Hit::FingerHoverOver(FingerHoverEvent { abs, .. })
+                if self.minimap_rect.contains(abs) || self.sticky_header_at(abs).is_some() =>
+            {
+                cx.set_cursor(MouseCursor::Default);
+                self.close_hover(cx);
+            }
+            Hit::FingerHoverIn(FingerHoverEvent { abs, .. })
+            | Hit::FingerHoverOver(FingerHoverEvent { abs, .. }) => {
+                cx.set_cursor(MouseCursor::Text);
+                self.move_hover(cx, session, abs);
+            }
+            Hit::FingerHoverOut(_) => {
+                self.close_hover(cx);
+            }
+            Hit::FingerDown(FingerDownEvent {
+                abs,
+                modifiers: KeyModifiers { shift: true, .. },
+                ..
+            })
+            | Hit::FingerMove(FingerMoveEvent { abs, .. }) => {
+                self.reset_cursor_blinker(cx);
+                if let KeepCursorInView::Always(old_abs, _) = &mut self.keep_cursor_in_view {
+                    *old_abs = abs;
+                }
+                cx.set_cursor(MouseCursor::Text);
+                self.drag_to(session, abs);
+                // alright how are we going to do scrolling
+                self.redraw(cx);
+            }
+            _ => {}
+        }
+        if keyboard_moved_cursor {
+            self.keep_cursor_in_view = KeepCursorInView::Once;
+            self.reset_cursor_blinker(cx);
+        }
+        if self.completion.is_some() {
+            self.update_completion(cx, session);
+        }
+        if keyboard_moved_cursor && self.signature_help.is_some() {
+            self.update_signature_help(cx, session);
+        }
+        if let KeepCursorInView::Always(abs, next) = &mut self.keep_cursor_in_view {
+            if next.is_event(event).is_some() {
+                *next = cx.new_next_frame();
+                let abs = *abs;
+                self.drag_to(session, abs);
+                self.redraw(cx);
+            }
+        }
+        // Edits invalidate the lines they touch, so keep searching until the matches are current.
+        if self.search_next_frame.is_none() && session.is_search_pending() {
+            self.search_next_frame = Some(cx.new_next_frame());
+        }
+        actions
+    }
+
+    pub fn set_keymap(&mut self, keymap: Rc<Keymap>) {
+        if !Rc::ptr_eq(&self.keymap, &keymap) {
+            self.keymap = keymap;
+            self.pending_chords.clear();
+        }
+    }
+
+    /// Returns the mode of the Vim emulation, or `None` if it is turned off.
+    pub fn vim_mode(&self) -> Option<VimMode> {
+        self.keymap.vim_mode.then(|| self.vim.mode())
+    }
+
+    /// Executes a command as if the keys bound to it were pressed. Returns whether the command
+    /// can have changed the text.
+    pub fn execute_command(&mut self, cx: &mut Cx, session: &mut Session, command: Command) -> bool {
+        match command {
+            Command::MovePageUp | Command::SelectPageUp => {
+                for _ in 0..self.page_line_count() {
+                    session.move_up(command == Command::MovePageUp);
+                }
+            }
+            Command::MovePageDown | Command::SelectPageDown => {
+                for _ in 0..self.page_line_count() {
+                    session.move_down(command == Command::MovePageDown);
+                }
+            }
+            Command::Find => self.open_find_bar(cx, session, false),
+            Command::Replace => self.open_find_bar(cx, session, true),
+            Command::FindNext => self.select_search_match(cx, session, true),
+            Command::FindPrev => self.select_search_match(cx, session, false),
+            Command::IncreaseFontSize => self.increase_font_size(),
+            Command::DecreaseFontSize => self.decrease_font_size(),
+            Command::ResetFontSize => self.reset_font_size(),
+            Command::ToggleWordWrap => self.word_wrap = !self.word_wrap,
+            Command::ToggleMinimap => {
+                let mut settings = Settings::clone(session.settings());
+                settings.show_minimap = !settings.show_minimap;
+                session.set_settings(Rc::new(settings));
+            }
+            Command::ToggleStickyScroll => {
+                let mut settings = Settings::clone(session.settings());
+                settings.sticky_scroll = !settings.sticky_scroll;
+                session.set_settings(Rc::new(settings));
+            }
+            command => {
+                session.execute_command(command);
+            }
+        }
+        self.redraw(cx);
+        command.edits_text()
+    }
+
+    fn page_line_count(&self) -> usize {
+        (self.line_end - self.line_start).saturating_sub(3)
+    }
+
+    /// Handles the keys that are bound to a command in the keymap, and in Vim mode the keys that
+    /// the Vim emulation uses. Returns whether the hit was handled.
+    fn handle_bound_keys(
+        &mut self,
+        cx: &mut Cx,
+        hit: &Hit,
+        session: &mut Session,
+        actions: &mut Vec<CodeEditorAction>,
+    ) -> bool {
+        if self.keymap.vim_mode && self.handle_vim_keys(cx, hit, session, actions) {
+            return true;
+        }
+        let Hit::KeyDown(event) = hit else {
+            return false;
+        };
+        if let KeyCode::Shift | KeyCode::Control | KeyCode::Alt | KeyCode::Logo = event.key_code {
+            return false;
+        }
+        self.pending_chords.push(KeyChord::from_key_event(event));
+        match self.keymap.resolve(&self.pending_chords) {
+            Resolution::Command(command) => {
+                self.pending_chords.clear();
+                if self.execute_command(cx, session, command) {
+                    actions.push(CodeEditorAction::TextDidChange);
+                }
+                true
+            }
+            Resolution::Pending => true,
+            Resolution::Unbound => {
+                // A chord that does not continue a sequence is dropped together with it.
+                let was_pending = self.pending_chords.len() > 1;
+                self.pending_chords.clear();
+                was_pending
+            }
+        }
+    }
+
+    fn handle_vim_keys(
+        &mut self,
+        cx: &mut Cx,
+        hit: &Hit,
+        session: &mut Session,
+        actions: &mut Vec<CodeEditorAction>,
+    ) -> bool {
+        let keys = match hit {
+            Hit::KeyDown(event) => match vim_key(event) {
+                Some(key) => vec![key],
+                None => return false,
+            },
+            Hit::TextInput(TextInputEvent {
+                input,
+                was_paste: false,
+                ..
+            }) => input.chars().map(VimKey::Char).collect(),
+            _ => return false,
+        };
+        let mut is_handled = false;
+        for key in keys {
+            if let Some(text_changed) = self.vim.handle_key(session, key) {
+                is_handled = true;
+                if text_changed {
+                    actions.push(CodeEditorAction::TextDidChange);
+                }
+            }
+        }
+        if !is_handled {
+            return false;
+        }
+        if let (VimMode::Insert, Hit::TextInput(TextInputEvent { input, .. })) =
+            (self.vim.mode(), hit)
+        {
+            self.handle_typed_text(cx, session, input);
+        }
+        self.redraw(cx);
+        true
+    }
+
+    pub fn open_find_bar(&mut self, cx: &mut Cx, session: &mut Session, show_replace: bool) {
+        self.find_bar_is_open = true;
+        self.find_bar.view(id!(replace_row)).set_visible(show_replace);
+        let selection = session.selections()[session.last_added_selection_index().unwrap()];
+        let query_input = self.find_bar.text_input(id!(query_input));
+        if !selection.is_empty() && selection.start().line_index == selection.end().line_index {
+            let text = session.document().as_text().as_lines()[selection.start().line_index]
+                [selection.start().byte_index..selection.end().byte_index]
+                .to_string();
+            self.search_query.pattern = if self.search_query.is_regex {
+                Regex::escape(&text)
+            } else {
+                text
+            };
+            query_input.set_text(&self.search_query.pattern);
+        }
+        query_input.set_key_focus(cx);
+        query_input.set_cursor(self.search_query.pattern.len(), 0);
+        self.update_search(cx, session);
+    }
+
+    pub fn close_find_bar(&mut self, cx: &mut Cx, session: &mut Session) {
+        self.find_bar_is_open = false;
+        self.search_next_frame = None;
+        session.clear_search();
+        self.set_key_focus(cx);
+        self.redraw(cx);
+    }
+
+    /// Returns whether the actions changed the text.
+    fn handle_find_bar_actions(
+        &mut self,
+        cx: &mut Cx,
+        actions: &Actions,
+        session: &mut Session,
+    ) -> bool {
+        let query_input = self.find_bar.text_input(id!(query_input));
+        let replace_input = self.find_bar.text_input(id!(replace_input));
+        let is_escape = |input: &TextInputRef| {
+            matches!(
+                actions.find_widget_action_cast(input.widget_uid()),
+                TextInputAction::Escape
+            )
+        };
+        if is_escape(&query_input) || is_escape(&replace_input) {
+            self.close_find_bar(cx, session);
+            return false;
+        }
+        let mut query_changed = false;
+        if let Some(pattern) = query_input.changed(actions) {
+            self.search_query.pattern = pattern;
+            query_changed = true;
+        }
+        if let Some(is_case_sensitive) = self.find_bar.check_box(id!(case_toggle)).changed(actions) {
+            self.search_query.is_case_sensitive = is_case_sensitive;
+            query_changed = true;
+        }
+        if let Some(is_whole_word) = self.find_bar.check_box(id!(word_toggle)).changed(actions) {
+            self.search_query.is_whole_word = is_whole_word;
+            query_changed = true;
+        }
+        if let Some(is_regex) = self.find_bar.check_box(id!(regex_toggle)).changed(actions) {
+            self.search_query.is_regex = is_regex;
+            query_changed = true;
+        }
+        if query_changed {
+            self.update_search(cx, session);
+        }
+        if query_input.returned(actions).is_some()
+            || self.find_bar.button(id!(next_button)).clicked(actions)
+        {
+            self.select_search_match(cx, session, true);
+        }
+        if self.find_bar.button(id!(prev_button)).clicked(actions) {
+            self.select_search_match(cx, session, false);
+        }
+        if replace_input.returned(actions).is_some()
+            || self.find_bar.button(id!(replace_button)).clicked(actions)
+        {
+            let is_replaced = session.replace_search_match(&replace_input.text());
+            self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+            self.redraw(cx);
+            return is_replaced;
+        }
+        if self.find_bar.button(id!(replace_all_button)).clicked(actions) {
+            let count = session.replace_all_search_matches(&replace_input.text());
+            self.redraw(cx);
+            return count > 0;
+        }
+        false
+    }
+
+    fn update_search(&mut self, cx: &mut Cx, session: &mut Session) {
+        self.search_error = session.set_search_query(&self.search_query).err();
+        if self.search_error.is_some() {
+            session.clear_search();
+        }
+        self.search_next_frame = session
+            .is_search_pending()
+            .then(|| cx.new_next_frame());
+        self.redraw(cx);
+    }
+
+    fn select_search_match(&mut self, cx: &mut Cx, session: &mut Session, is_forward: bool) {
+        let is_found = if is_forward {
+            session.select_next_search_match()
+        } else {
+            session.select_prev_search_match()
+        };
+        if is_found {
+            self.keep_cursor_in_view = KeepCursorInView::JumpToPosition;
+            self.redraw(cx);
+        }
+    }
+
+    fn draw_find_bar(&mut self, cx: &mut Cx2d, session: &Session) {
+        let status = if let Some(error) = &self.search_error {
+            error.clone()
+        } else if self.search_query.pattern.is_empty() {
+            String::new()
+        } else {
+            let count = session.search_match_count();
+            let pending = if session.is_search_pending() { "+" } else { "" };
+            match session.active_search_match_index() {
+                Some(index) => format!("{} of {}{}", index + 1, count, pending),
+                None if count == 0 && pending.is_empty() => "No results".to_string(),
+                None => format!("{}{} found", count, pending),
+            }
+        };
+        self.find_bar.label(id!(match_count)).set_text(&status);
+        let mut walk = self.find_bar.walk(cx);
+        let width = match walk.width {
+            Size::Fixed(width) => width,
+            _ => 0.0,
+        };
+        walk.abs_pos = Some(dvec2(
+            self.unscrolled_rect.pos.x + self.unscrolled_rect.size.x - width - 16.0,
+            self.unscrolled_rect.pos.y,
+        ));
+        self.find_bar.draw_walk_all(cx, &mut Scope::empty(), walk);
+    }
+
+    /// Handles the keys that open and navigate the completion popup, and that move between the
+    /// tab stops of an inserted snippet. Returns whether the hit was handled.
+    fn handle_completion_keys(
+        &mut self,
+        cx: &mut Cx,
+        hit: &Hit,
+        session: &mut Session,
+        actions: &mut Vec<CodeEditorAction>,
+    ) -> bool {
+        let Hit::KeyDown(KeyEvent {
+            key_code,
+            modifiers,
+            ..
+        }) = hit
+        else {
+            return false;
+        };
+        let is_open = self.completion.is_some();
+        match key_code {
+            KeyCode::Space if modifiers.control => {
+                session.handle_changes();
+                self.open_completion(cx, session, 0);
+            }
+            KeyCode::ArrowUp if is_open => self.select_completion(cx, -1),
+            KeyCode::ArrowDown if is_open => self.select_completion(cx, 1),
+            KeyCode::PageUp if is_open => {
+                self.select_completion(cx, -(COMPLETION_VISIBLE_ITEM_COUNT as isize))
             }
-            Hit::FingerHoverIn(_) | Hit::FingerHoverOver(_) => {
-                cx.set_cursor(MouseCursor::Text);
+            KeyCode::PageDown if is_open => {
+                self.select_completion(cx, COMPLETION_VISIBLE_ITEM_COUNT as isize)
             }
-            Hit::FingerDown(FingerDownEvent {
-                abs,
-                modifiers: KeyModifiers { shift: true, .. },
-                ..
-            })
-            | Hit::FingerMove(FingerMoveEvent { abs, .. }) => {
-                self.reset_cursor_blinker(cx);
-                if let KeepCursorInView::Always(old_abs, _) = &mut self.keep_cursor_in_view {
-                    *old_abs = abs;
+            KeyCode::ReturnKey | KeyCode::Tab if is_open && !modifiers.shift => {
+                self.accept_completion(cx, session);
+                actions.push(CodeEditorAction::TextDidChange);
+            }
+            KeyCode::Escape if is_open || self.signature_help.is_some() => {
+                self.close_completion(cx);
+                self.close_signature_help(cx);
+            }
+            KeyCode::Escape if session.has_active_snippet() => session.clear_snippet(),
+            KeyCode::Tab if session.has_active_snippet() => {
+                if modifiers.shift {
+                    session.prev_snippet_tab_stop();
+                } else {
+                    session.next_snippet_tab_stop();
                 }
-                cx.set_cursor(MouseCursor::Text);
-                let ((cursor, affinity), _) = self.pick(session, abs);
-                session.move_to(cursor, affinity, NewGroup::Yes);
-                // alright how are we going to do scrolling
                 self.redraw(cx);
             }
-            _ => {}
+            _ => return false,
         }
-        if keyboard_moved_cursor {
-            self.keep_cursor_in_view = KeepCursorInView::Once;
-            self.reset_cursor_blinker(cx);
+        true
+    }
+
+    /// Opens the completion popup or signature help when the typed text calls for it.
+    fn handle_typed_text(&mut self, cx: &mut Cx, session: &mut Session, input: &str) {
+        session.handle_changes();
+        if input.chars().all(is_word_char) {
+            if self.completion.is_none() {
+                self.open_completion(cx, session, COMPLETION_MIN_WORD_LEN);
+            }
+        } else {
+            self.close_completion(cx);
         }
-        if let KeepCursorInView::Always(abs, next) = &mut self.keep_cursor_in_view {
-            if next.is_event(event).is_some() {
-                *next = cx.new_next_frame();
-                let abs = *abs;
-                let ((cursor, affinity), _) = self.pick(session, abs);
-                session.move_to(cursor, affinity, NewGroup::Yes);
-                self.redraw(cx);
+        if input.contains(['(', ',']) {
+            self.update_signature_help(cx, session);
+        }
+    }
+
+    fn cursor_position(session: &Session) -> Position {
+        session.selections()[session.last_added_selection_index().unwrap()]
+            .cursor
+            .position
+    }
+
+    /// Opens the completion popup for the word before the cursor, if at least `min_word_len`
+    /// chars of it were typed.
+    fn open_completion(&mut self, cx: &mut Cx, session: &Session, min_word_len: usize) {
+        self.completion = Completion::open(&*self.completion_provider, session, min_word_len);
+        self.redraw(cx);
+    }
+
+    /// Filters the completion items again if the word before the cursor changed, and closes the
+    /// popup if the cursor left the word.
+    fn update_completion(&mut self, cx: &mut Cx, session: &Session) {
+        let Some(completion) = &mut self.completion else {
+            return;
+        };
+        if !completion.update(session) {
+            self.completion = None;
+        }
+        self.redraw(cx);
+    }
+
+    fn select_completion(&mut self, cx: &mut Cx, delta: isize) {
+        let Some(completion) = &mut self.completion else {
+            return;
+        };
+        completion.select(delta, COMPLETION_VISIBLE_ITEM_COUNT);
+        self.redraw(cx);
+    }
+
+    /// Replaces the word before the cursor with the selected completion item, as a single
+    /// undoable edit.
+    fn accept_completion(&mut self, cx: &mut Cx, session: &mut Session) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        completion.accept(session);
+        self.update_signature_help(cx, session);
+        self.redraw(cx);
+    }
+
+    fn close_completion(&mut self, cx: &mut Cx) {
+        if self.completion.take().is_some() {
+            self.redraw(cx);
+        }
+    }
+
+    fn update_signature_help(&mut self, cx: &mut Cx, session: &Session) {
+        session.handle_changes();
+        let position = Self::cursor_position(session);
+        self.signature_help = self
+            .signature_help_provider
+            .signature_help(session.document(), position);
+        if let Some(signature_help) = &self.signature_help {
+            self.signature_tooltip
+                .markdown(id!(markdown))
+                .set_text(&signature_help.to_markdown());
+        }
+        self.redraw(cx);
+    }
+
+    fn close_signature_help(&mut self, cx: &mut Cx) {
+        if self.signature_help.take().is_some() {
+            self.redraw(cx);
+        }
+    }
+
+    /// Restarts the hover delay when the finger moves to another position.
+    fn move_hover(&mut self, cx: &mut Cx, session: &Session, abs: DVec2) {
+        let ((position, _), is_in_gutter) = self.pick(session, abs);
+        if self
+            .hover_position
+            .is_some_and(|(hover_position, _)| hover_position == position)
+        {
+            return;
+        }
+        self.close_hover(cx);
+        if !is_in_gutter {
+            self.hover_position = Some((position, abs));
+            self.hover_timer = cx.start_timeout(HOVER_DELAY);
+        }
+    }
+
+    fn open_hover(&mut self, cx: &mut Cx, session: &Session) {
+        let Some((position, _)) = self.hover_position else {
+            return;
+        };
+        if let Some(hover) = self.hover_provider.hover(session.document(), position) {
+            self.hover_tooltip
+                .markdown(id!(markdown))
+                .set_text(&hover.markdown);
+            self.hover_is_open = true;
+            self.redraw(cx);
+        }
+    }
+
+    fn close_hover(&mut self, cx: &mut Cx) {
+        cx.stop_timer(self.hover_timer);
+        self.hover_position = None;
+        if self.hover_is_open {
+            self.hover_is_open = false;
+            self.redraw(cx);
+        }
+    }
+
+    fn draw_hover_tooltip(&mut self, cx: &mut Cx2d) {
+        if !self.hover_is_open {
+            return;
+        }
+        let Some((_, abs)) = self.hover_position else {
+            return;
+        };
+        let mut walk = self.hover_tooltip.walk(cx);
+        walk.abs_pos = Some(abs + dvec2(0.0, self.cell_size.y));
+        self.hover_tooltip
+            .draw_walk_all(cx, &mut Scope::empty(), walk);
+    }
+
+    fn draw_signature_tooltip(&mut self, cx: &mut Cx2d, session: &Session) {
+        if self.signature_help.is_none() {
+            return;
+        }
+        // The tooltip goes above the cursor, so use its height from the previous draw, and draw
+        // again if that height turns out to be wrong.
+        let height = self.signature_tooltip.area().rect(cx).size.y;
+        let mut walk = self.signature_tooltip.walk(cx);
+        walk.abs_pos = Some(self.cursor_screen_pos(session) - dvec2(0.0, height));
+        self.signature_tooltip
+            .draw_walk_all(cx, &mut Scope::empty(), walk);
+        if self.signature_tooltip.area().rect(cx).size.y != height {
+            self.scroll_bars.area().redraw(cx);
+        }
+    }
+
+    /// Returns the screen position of the top left of the last added cursor.
+    fn cursor_screen_pos(&self, session: &Session) -> DVec2 {
+        let selection = session.selections()[session.last_added_selection_index().unwrap()];
+        let (x, y) = session
+            .layout()
+            .logical_to_normalized_position(selection.cursor.position, selection.cursor.affinity);
+        self.viewport_rect.pos + dvec2(x, y) * self.cell_size
+    }
+
+    fn draw_completion_popup(&mut self, cx: &mut Cx2d, session: &Session) {
+        let Some(completion) = &self.completion else {
+            return;
+        };
+        const PADDING: f64 = 4.0;
+        const MAX_DETAIL_COLUMN_COUNT: usize = 40;
+
+        let font_size = self.draw_text.text_style.font_size;
+        self.draw_completion_text.text_style.font_size = font_size;
+        self.draw_completion_match.text_style.font_size = font_size;
+        self.draw_completion_detail.text_style.font_size = font_size;
+        let visible_matches: Vec<_> = completion
+            .matches()
+            .skip(completion.first_visible_index())
+            .take(COMPLETION_VISIBLE_ITEM_COUNT)
+            .collect();
+        let tag_column_count = 5;
+        let label_column_count = visible_matches
+            .iter()
+            .map(|(item, _)| item.label.column_count())
+            .max()
+            .unwrap_or(0);
+        let detail_column_count = visible_matches
+            .iter()
+            .filter_map(|(item, _)| item.detail.as_ref())
+            .map(|detail| detail.column_count().min(MAX_DETAIL_COLUMN_COUNT) + 2)
+            .max()
+            .unwrap_or(0);
+        let row_height = self.cell_size.y * 1.2;
+        let size = dvec2(
+            (tag_column_count + label_column_count + detail_column_count) as f64 * self.cell_size.x,
+            visible_matches.len() as f64 * row_height,
+        ) + dvec2(2.0 * PADDING, 2.0 * PADDING);
+
+        // Put the popup below the word, or above it if there is no room below.
+        let (x, y) = session
+            .layout()
+            .logical_to_normalized_position(completion.word_start(), Affinity::After);
+        let word_pos = self.viewport_rect.pos + dvec2(x, y) * self.cell_size;
+        let mut pos = word_pos
+            + dvec2(
+                -PADDING - tag_column_count as f64 * self.cell_size.x,
+                self.cell_size.y,
+            );
+        if pos.y + size.y > self.unscrolled_rect.pos.y + self.unscrolled_rect.size.y
+            && word_pos.y - size.y >= self.unscrolled_rect.pos.y
+        {
+            pos.y = word_pos.y - size.y;
+        }
+
+        self.completion_draw_list.begin_always(cx);
+        self.draw_completion_bg.draw_abs(cx, Rect { pos, size });
+        let mut buf = String::new();
+        for (row_index, (item, fuzzy_match)) in visible_matches.iter().enumerate() {
+            let row_pos = pos + dvec2(PADDING, PADDING + row_index as f64 * row_height);
+            let text_pos = row_pos + dvec2(0.0, (row_height - self.cell_size.y) / 2.0);
+            if completion.first_visible_index() + row_index == completion.selected_index() {
+                self.draw_completion_selection.draw_abs(
+                    cx,
+                    Rect {
+                        pos: row_pos,
+                        size: dvec2(size.x - 2.0 * PADDING, row_height),
+                    },
+                );
+            }
+            self.draw_completion_detail
+                .draw_abs(cx, text_pos, item.kind.tag());
+            for (column_index, (byte_index, char)) in item.label.char_indices().enumerate() {
+                buf.clear();
+                buf.push(char);
+                let char_pos = text_pos
+                    + dvec2(
+                        (tag_column_count + column_index) as f64 * self.cell_size.x,
+                        0.0,
+                    );
+                if fuzzy_match
+                    .ranges
+                    .iter()
+                    .any(|range| range.contains(&byte_index))
+                {
+                    self.draw_completion_match.draw_abs(cx, char_pos, &buf);
+                } else {
+                    self.draw_completion_text.draw_abs(cx, char_pos, &buf);
+                }
+            }
+            if let Some(detail) = &item.detail {
+                buf.clear();
+                buf.extend(detail.chars().take(MAX_DETAIL_COLUMN_COUNT));
+                self.draw_completion_detail.draw_abs(
+                    cx,
+                    text_pos
+                        + dvec2(
+                            (tag_column_count + label_column_count + 2) as f64 * self.cell_size.x,
+                            0.0,
+                        ),
+                    &buf,
+                );
             }
         }
-        actions
+        self.completion_draw_list.end(cx);
     }
 
     fn draw_gutter(&mut self, cx: &mut Cx2d, session: &Session) {
@@ -1061,24 +1795,7 @@ impl CodeEditor {
                                     };
                                     let (text_0, text_1) = text.split_at(token.len);
                                     text = text_1;
-                                    self.draw_text.color = match token.kind {
-                                        TokenKind::Unknown => self.token_colors.unknown,
-                                        TokenKind::BranchKeyword => {
-                                            self.token_colors.branch_keyword
-                                        }
-                                        TokenKind::Comment => self.token_colors.comment,
-                                        TokenKind::Constant => self.token_colors.constant,
-                                        TokenKind::Delimiter => self.token_colors.delimiter,
-                                        TokenKind::Identifier => self.token_colors.identifier,
-                                        TokenKind::LoopKeyword => self.token_colors.loop_keyword,
-                                        TokenKind::Number => self.token_colors.number,
-                                        TokenKind::OtherKeyword => self.token_colors.other_keyword,
-                                        TokenKind::Punctuator => self.token_colors.punctuator,
-                                        TokenKind::String => self.token_colors.string,
-                                        TokenKind::Function => self.token_colors.function,
-                                        TokenKind::Typename => self.token_colors.typename,
-                                        TokenKind::Whitespace => self.token_colors.whitespace,
-                                    };
+                                    self.draw_text.color = self.token_colors.color(token.kind);
                                     self.draw_text.outline = 0.0;
                                     if let TokenKind::Delimiter = token.kind {
                                         if highlighted_delimiter_positions.contains(&Position {
@@ -1200,6 +1917,275 @@ impl CodeEditor {
         .draw_decoration_layer(cx, session)
     }
 
+    fn draw_search_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        let decorations = session.search_decorations(self.line_start..self.line_end);
+        DrawDecorationLayer {
+            code_editor: self,
+            active_decoration: None,
+            decorations: decorations.iter(),
+        }
+        .draw_decoration_layer(cx, session)
+    }
+
+    fn draw_minimap(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        let unscrolled_rect = self.unscrolled_rect;
+        let rect = Rect {
+            pos: dvec2(
+                unscrolled_rect.pos.x + unscrolled_rect.size.x
+                    - self.minimap_width
+                    - MINIMAP_MARGIN_RIGHT,
+                unscrolled_rect.pos.y,
+            ),
+            size: dvec2(self.minimap_width, unscrolled_rect.size.y),
+        };
+        self.minimap_rect = rect;
+        self.draw_minimap_bg.draw_abs(cx, rect);
+
+        // A minimap that is taller than the editor scrolls along with it, so that its top and
+        // bottom line up with those of the text.
+        let layout = session.layout();
+        let row_scale = MINIMAP_ROW_HEIGHT / self.cell_size.y;
+        let minimap_height = layout.height() * MINIMAP_ROW_HEIGHT;
+        self.minimap_scale = row_scale * (rect.size.y / minimap_height).min(1.0);
+        let scroll_y = self.scroll_bars.get_scroll_pos().y;
+        let offset = scroll_y * (row_scale - self.minimap_scale);
+
+        let line_start = layout.find_first_line_ending_after_y(offset / MINIMAP_ROW_HEIGHT);
+        let line_end =
+            layout.find_first_line_starting_after_y((offset + rect.size.y) / MINIMAP_ROW_HEIGHT);
+        for line in layout.lines(line_start, line_end) {
+            let y = rect.pos.y + line.y() * MINIMAP_ROW_HEIGHT - offset;
+            let height = line.scale() * MINIMAP_ROW_HEIGHT;
+            let mut text = line.text();
+            let mut x = 0.0;
+            for token in line.tokens() {
+                if text.is_empty() || x >= rect.size.x {
+                    break;
+                }
+                let (text_0, text_1) = text.split_at(token.len.min(text.len()));
+                text = text_1;
+                let width = text_0.column_count() as f64 * MINIMAP_COLUMN_WIDTH;
+                if !matches!(token.kind, TokenKind::Whitespace) {
+                    let mut color = self.token_colors.color(token.kind);
+                    color.w *= 0.6;
+                    self.draw_minimap.color = color;
+                    self.draw_minimap.draw_abs(
+                        cx,
+                        Rect {
+                            pos: dvec2(rect.pos.x + x, y),
+                            size: dvec2(width.min(rect.size.x - x), height),
+                        },
+                    );
+                }
+                x += width;
+            }
+        }
+
+        self.minimap_viewport_rect = Rect {
+            pos: dvec2(rect.pos.x, rect.pos.y + scroll_y * self.minimap_scale),
+            size: dvec2(rect.size.x, self.viewport_rect.size.y * row_scale),
+        };
+        self.draw_minimap_viewport
+            .draw_abs(cx, self.minimap_viewport_rect);
+    }
+
+    fn drag_minimap_to(&mut self, cx: &mut Cx, abs: DVec2) {
+        let Some(drag_offset) = self.minimap_drag_offset else {
+            return;
+        };
+        let viewport_y = abs.y - drag_offset - self.minimap_rect.pos.y;
+        let scroll_pos = self.scroll_bars.get_scroll_pos();
+        self.scroll_bars.set_scroll_pos(
+            cx,
+            dvec2(scroll_pos.x, (viewport_y / self.minimap_scale).max(0.0)),
+        );
+        self.redraw(cx);
+    }
+
+    fn draw_sticky_headers(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        self.sticky_headers.clear();
+        let layout = session.layout();
+        let top_y = (self.unscrolled_rect.pos.y - self.viewport_rect.pos.y) / self.cell_size.y;
+
+        // The headers cover the first lines in view, so take the headers of the first line below
+        // them instead. A header is only pinned once its own line has scrolled out of its slot.
+        let line_index = layout.find_first_line_ending_after_y(top_y);
+        let header_count = session.sticky_header_lines(line_index).len();
+        let line_index = layout
+            .find_first_line_ending_after_y(top_y + header_count as f64)
+            .min(layout.as_text().as_lines().len() - 1);
+        let header_lines = session.sticky_header_lines(line_index);
+        let header_lines = header_lines
+            .iter()
+            .enumerate()
+            .take_while(|&(slot_index, &line_index)| {
+                layout.line(line_index).y() < top_y + slot_index as f64
+            })
+            .map(|(_, &line_index)| line_index);
+
+        // The headers span the editor up to the minimap, if there is one.
+        let width = if self.minimap_rect.size.x > 0.0 {
+            self.minimap_rect.pos.x - self.unscrolled_rect.pos.x
+        } else {
+            self.unscrolled_rect.size.x
+        };
+        let mut buf = String::new();
+        for (slot_index, line_index) in header_lines.enumerate() {
+            let line = layout.line(line_index);
+            let y = self.unscrolled_rect.pos.y + slot_index as f64 * self.cell_size.y;
+            let rect = Rect {
+                pos: dvec2(self.unscrolled_rect.pos.x, y),
+                size: dvec2(width, self.cell_size.y),
+            };
+            self.draw_sticky_scroll_bg.draw_abs(cx, rect);
+            self.sticky_headers.push((rect, line_index));
+
+            buf.clear();
+            let _ = write!(buf, "{: >4}", line_index + 1);
+            self.draw_gutter.font_scale = 1.0;
+            self.draw_gutter
+                .draw_abs(cx, dvec2(self.gutter_rect.pos.x, y), &buf);
+
+            self.draw_text.font_scale = 1.0;
+            self.draw_text.outline = 0.0;
+            let mut text = line.text();
+            let mut column_index = 0;
+            let mut tokens = line.tokens().iter();
+            while !text.is_empty() {
+                let token = tokens.next().copied().unwrap_or(Token {
+                    len: text.len(),
+                    kind: TokenKind::Unknown,
+                });
+                let (text_0, text_1) = text.split_at(token.len.min(text.len()));
+                text = text_1;
+                self.draw_text.color = self.token_colors.color(token.kind);
+                for grapheme in text_0.graphemes() {
+                    self.draw_text.draw_abs(
+                        cx,
+                        dvec2(
+                            self.viewport_rect.pos.x + column_index as f64 * self.cell_size.x,
+                            y,
+                        ),
+                        grapheme,
+                    );
+                    column_index += grapheme.column_count();
+                }
+            }
+        }
+    }
+
+    fn sticky_header_at(&self, abs: DVec2) -> Option<usize> {
+        self.sticky_headers
+            .iter()
+            .find(|(rect, _)| rect.contains(abs))
+            .map(|&(_, line_index)| line_index)
+    }
+
+    fn conflict_action_at(&self, abs: DVec2) -> Option<(usize, ConflictResolution)> {
+        self.conflict_actions
+            .iter()
+            .find(|(rect, _, _)| rect.contains(abs))
+            .map(|&(_, index, resolution)| (index, resolution))
+    }
+
+    fn draw_conflict_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        self.conflict_actions.clear();
+        let decorations = session.conflict_decorations(self.line_start..self.line_end);
+        let mut decorations = decorations.iter();
+        let mut active_decoration = None;
+        if decorations.as_slice().first().is_some_and(|decoration| {
+            decoration.start().line_index < self.line_start
+        }) {
+            active_decoration = Some(ActiveDecoration {
+                decoration: *decorations.next().unwrap(),
+                start_x: 0.0,
+            });
+        }
+        DrawDecorationLayer {
+            code_editor: self,
+            active_decoration,
+            decorations,
+        }
+        .draw_decoration_layer(cx, session);
+
+        let conflicts = session.conflicts();
+        for (index, conflict) in conflicts.iter().enumerate() {
+            if !(self.line_start..self.line_end).contains(&conflict.start_line) {
+                continue;
+            }
+            let layout = session.layout();
+            let line = layout.line(conflict.start_line);
+            self.draw_conflict_action.font_scale = line.scale();
+            let mut position = DVec2 {
+                x: (line.column_count() + 2) as f64,
+                y: line.y(),
+            } * self.cell_size
+                + self.viewport_rect.pos;
+            for (label, resolution) in [
+                ("Accept Current", ConflictResolution::Ours),
+                ("Accept Incoming", ConflictResolution::Theirs),
+                ("Accept Both", ConflictResolution::Both),
+            ] {
+                let size = DVec2 {
+                    x: label.len() as f64,
+                    y: line.scale(),
+                } * self.cell_size;
+                self.draw_conflict_action.draw_abs(cx, position, label);
+                self.conflict_actions
+                    .push((Rect { pos: position, size }, index, resolution));
+                position.x += size.x + 2.0 * self.cell_size.x;
+            }
+        }
+    }
+
+    // Draws the selections and cursors of the other peers editing the document.
+    fn draw_remote_selection_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
+        let decorations = session.remote_selection_decorations(self.line_start..self.line_end);
+        let mut decorations = decorations.iter();
+        let mut active_decoration = None;
+        if decorations.as_slice().first().is_some_and(|decoration| {
+            decoration.start().line_index < self.line_start
+        }) {
+            active_decoration = Some(ActiveDecoration {
+                decoration: *decorations.next().unwrap(),
+                start_x: 0.0,
+            });
+        }
+        DrawDecorationLayer {
+            code_editor: self,
+            active_decoration,
+            decorations,
+        }
+        .draw_decoration_layer(cx, session);
+
+        let layout = session.layout();
+        self.draw_search_match.color = self.token_colors.remote_cursor;
+        for selection in session.document().remote_selections().iter() {
+            let position = selection.cursor;
+            if !(self.line_start..self.line_end).contains(&position.line_index) {
+                continue;
+            }
+            let line = layout.line(position.line_index);
+            let (row_index, column_index) =
+                line.logical_to_grid_position(position.byte_index, Affinity::Before);
+            let (x, y) = line.grid_to_normalized_position(row_index, column_index);
+            self.draw_search_match.draw_abs(
+                cx,
+                Rect {
+                    pos: DVec2 {
+                        x,
+                        y: line.y() + y,
+                    } * self.cell_size
+                        + self.viewport_rect.pos,
+                    size: DVec2 {
+                        x: 2.0,
+                        y: line.scale() * self.cell_size.y,
+                    },
+                },
+            );
+        }
+    }
+
     fn draw_selection_layer(&mut self, cx: &mut Cx2d<'_>, session: &Session) {
         let mut active_selection = None;
         let selections = session.selections();
@@ -1225,6 +2211,16 @@ impl CodeEditor {
         .draw_selection_layer(cx, session)
     }
 
+    // Extends the selection being dragged to the position under the finger.
+    fn drag_to(&self, session: &Session, abs: DVec2) {
+        let ((cursor, affinity), _) = self.pick(session, abs);
+        if let Some((anchor, anchor_affinity)) = self.column_selection_anchor {
+            session.set_column_selection(anchor, anchor_affinity, cursor, affinity);
+        } else {
+            session.move_to(cursor, affinity, NewGroup::Yes);
+        }
+    }
+
     fn pick(&self, session: &Session, position: DVec2) -> ((Position, Affinity), bool) {
         let position = (position - self.viewport_rect.pos) / self.cell_size;
         
@@ -1609,26 +2605,52 @@ impl<'a> DrawDecorationLayer<'a> {
     ) {
         let start_x = mem::take(&mut self.active_decoration.as_mut().unwrap().start_x);
         let (x, y) = line.grid_to_normalized_position(row_index, column_index);
-        self.code_editor.draw_decoration.color =
-            match self.active_decoration.as_mut().unwrap().decoration.ty {
-                DecorationType::Warning => self.code_editor.token_colors.warning_decoration,
-                DecorationType::Error => self.code_editor.token_colors.error_decoration,
-            };
-
-        self.code_editor.draw_decoration.draw_abs(
-            cx,
-            Rect {
-                pos: DVec2 {
-                    x: start_x,
-                    y: origin_y + y,
-                } * self.code_editor.cell_size
-                    + self.code_editor.viewport_rect.pos,
-                size: DVec2 {
-                    x: x - start_x,
-                    y: line.scale(),
-                } * self.code_editor.cell_size,
-            },
-        );
+        let rect = Rect {
+            pos: DVec2 {
+                x: start_x,
+                y: origin_y + y,
+            } * self.code_editor.cell_size
+                + self.code_editor.viewport_rect.pos,
+            size: DVec2 {
+                x: x - start_x,
+                y: line.scale(),
+            } * self.code_editor.cell_size,
+        };
+        let token_colors = &self.code_editor.token_colors;
+        match self.active_decoration.as_mut().unwrap().decoration.ty {
+            DecorationType::Warning => {
+                self.code_editor.draw_decoration.color = token_colors.warning_decoration;
+                self.code_editor.draw_decoration.draw_abs(cx, rect);
+            }
+            DecorationType::Error => {
+                self.code_editor.draw_decoration.color = token_colors.error_decoration;
+                self.code_editor.draw_decoration.draw_abs(cx, rect);
+            }
+            DecorationType::SearchMatch => {
+                self.code_editor.draw_search_match.color = token_colors.search_match;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+            }
+            DecorationType::ActiveSearchMatch => {
+                self.code_editor.draw_search_match.color = token_colors.active_search_match;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+            }
+            DecorationType::ConflictMarker => {
+                self.code_editor.draw_search_match.color = token_colors.conflict_marker;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+            }
+            DecorationType::ConflictOurs => {
+                self.code_editor.draw_search_match.color = token_colors.conflict_ours;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+            }
+            DecorationType::ConflictTheirs => {
+                self.code_editor.draw_search_match.color = token_colors.conflict_theirs;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+            }
+            DecorationType::RemoteSelection => {
+                self.code_editor.draw_search_match.color = token_colors.remote_selection;
+                self.code_editor.draw_search_match.draw_abs(cx, rect);
+            }
+        }
     }
 }
 
@@ -1888,7 +2910,7 @@ struct ActiveSelection {
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
-struct TokenColors {
+pub struct TokenColors {
     #[live]
     unknown: Vec4,
     #[live]
@@ -1923,6 +2945,41 @@ struct TokenColors {
     error_decoration: Vec4,
     #[live]
     warning_decoration: Vec4,
+    #[live]
+    search_match: Vec4,
+    #[live]
+    active_search_match: Vec4,
+    #[live]
+    conflict_marker: Vec4,
+    #[live]
+    conflict_ours: Vec4,
+    #[live]
+    conflict_theirs: Vec4,
+    #[live]
+    remote_selection: Vec4,
+    #[live]
+    remote_cursor: Vec4,
+}
+
+impl TokenColors {
+    pub(crate) fn color(&self, kind: TokenKind) -> Vec4 {
+        match kind {
+            TokenKind::Unknown => self.unknown,
+            TokenKind::BranchKeyword => self.branch_keyword,
+            TokenKind::Comment => self.comment,
+            TokenKind::Constant => self.constant,
+            TokenKind::Delimiter => self.delimiter,
+            TokenKind::Identifier => self.identifier,
+            TokenKind::LoopKeyword => self.loop_keyword,
+            TokenKind::Number => self.number,
+            TokenKind::OtherKeyword => self.other_keyword,
+            TokenKind::Punctuator => self.punctuator,
+            TokenKind::String => self.string,
+            TokenKind::Function => self.function,
+            TokenKind::Typename => self.typename,
+            TokenKind::Whitespace => self.whitespace,
+        }
+    }
 }
 
 #[derive(Live, LiveHook, LiveRegister)]
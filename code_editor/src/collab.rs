@@ -0,0 +1,858 @@
+//! Real-time collaborative editing of a document by several peers.
+//!
+//! The peers are kept in sync with operational transformation, in the client/server style of
+//! Jupiter and Google Wave. Each peer sends its edits to a central server, together with the
+//! revision of the server's text they were made on. The server transforms them against the
+//! operations it received since that revision, applies them, and broadcasts them to the other
+//! peers. A peer has at most one operation in flight, and transforms the operations it receives
+//! against the edits the server has not acknowledged yet.
+//!
+//! Nothing in here does any I/O. Messages are passed in and out as values, so the transport is up
+//! to the caller.
+
+use {
+    crate::{
+        diff::{diff_lines, DiffAlgorithm},
+        document::Document,
+        selection::Selection,
+        text::{Change, Drift, Edit, Length, Position, Text},
+    },
+    makepad_widgets::makepad_micro_serde::*,
+    std::{collections::HashMap, mem},
+};
+
+/// Identifies a peer within a shared document. Site ids are handed out by the server.
+pub type SiteId = u64;
+
+/// A sequence of edits, applied one after the other.
+pub type Operation = Vec<Edit>;
+
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub enum ClientMessage {
+    /// Joins the shared document. If no other peer is editing it, `text` becomes its text.
+    Join { text: Text },
+    /// An operation on the server's text at `revision`.
+    Operation { revision: usize, edits: Operation },
+    /// The selections of the peer in the server's text at `revision`, as (anchor, cursor) pairs.
+    Selections {
+        revision: usize,
+        selections: Vec<(Position, Position)>,
+    },
+    /// Tells the server that the peer has seen every operation up to `revision`, so that the
+    /// server can forget them.
+    Ack { revision: usize },
+}
+
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub enum ServerMessage {
+    Joined {
+        site_id: SiteId,
+        revision: usize,
+        text: Text,
+    },
+    Operation {
+        site_id: SiteId,
+        edits: Operation,
+    },
+    /// Acknowledges the operation the peer sent last.
+    Ack,
+    Selections {
+        site_id: SiteId,
+        selections: Vec<(Position, Position)>,
+    },
+    Left {
+        site_id: SiteId,
+    },
+}
+
+/// A selection of another peer, drawn by the editor.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RemoteSelection {
+    pub site_id: SiteId,
+    pub anchor: Position,
+    pub cursor: Position,
+}
+
+impl RemoteSelection {
+    pub fn is_empty(self) -> bool {
+        self.anchor == self.cursor
+    }
+
+    pub fn start(self) -> Position {
+        self.anchor.min(self.cursor)
+    }
+
+    pub fn end(self) -> Position {
+        self.anchor.max(self.cursor)
+    }
+
+    pub fn apply_edit(self, edit: &Edit) -> Self {
+        Self {
+            anchor: self.anchor.apply_edit(edit),
+            cursor: self.cursor.apply_edit(edit),
+            ..self
+        }
+    }
+}
+
+/// Transforms the operations `a` and `b`, which were made on the same text, into `a'` and `b'`,
+/// so that applying `a` then `b'` gives the same text as applying `b` then `a'`.
+///
+/// Where both insert at the same position, the text of `a` ends up first if `a_first` is true.
+/// Text that one inserts inside a range the other deletes is kept.
+pub fn transform(a: &[Edit], b: &[Edit], a_first: bool) -> (Operation, Operation) {
+    match (a.len(), b.len()) {
+        (0, _) | (_, 0) => (a.to_vec(), b.to_vec()),
+        (1, 1) => (
+            transform_edit(&a[0], &b[0], a_first),
+            transform_edit(&b[0], &a[0], !a_first),
+        ),
+        (1, _) => {
+            let (b_0, b_1) = b.split_at(b.len() / 2);
+            let (a, b_0) = transform(a, b_0, a_first);
+            let (a, b_1) = transform(&a, b_1, a_first);
+            (a, [b_0, b_1].concat())
+        }
+        _ => {
+            let (a_0, a_1) = a.split_at(a.len() / 2);
+            let (a_0, b) = transform(a_0, b, a_first);
+            let (a_1, b) = transform(a_1, &b, a_first);
+            ([a_0, a_1].concat(), b)
+        }
+    }
+}
+
+// Transforms `edit` so that it applies after `other`, which was made on the same text.
+fn transform_edit(edit: &Edit, other: &Edit, is_first: bool) -> Operation {
+    match edit.change {
+        Change::Insert(position, ref text) => {
+            if text.is_empty() {
+                return Vec::new();
+            }
+            let drift = if is_first {
+                Drift::After
+            } else {
+                Drift::Before
+            };
+            vec![Edit {
+                change: Change::Insert(
+                    transform_position(position, &other.change, drift),
+                    text.clone(),
+                ),
+                drift: edit.drift,
+            }]
+        }
+        Change::Delete(start, length) => {
+            if length == Length::zero() {
+                return Vec::new();
+            }
+            let end = start + length;
+            if let Change::Insert(position, ref text) = other.change {
+                if start < position && position < end {
+                    // Keep the inserted text, and delete the parts before and after it. The part
+                    // after it goes first, so that the part before it does not move.
+                    return vec![
+                        Edit {
+                            change: Change::Delete(position + text.length(), end - position),
+                            drift: edit.drift,
+                        },
+                        Edit {
+                            change: Change::Delete(start, position - start),
+                            drift: edit.drift,
+                        },
+                    ];
+                }
+            }
+            let start = transform_position(start, &other.change, Drift::Before);
+            let end = transform_position(end, &other.change, Drift::After);
+            if start >= end {
+                return Vec::new();
+            }
+            vec![Edit {
+                change: Change::Delete(start, end - start),
+                drift: edit.drift,
+            }]
+        }
+    }
+}
+
+// Like `Position::apply_edit`, but with the drift of `position` rather than that of the edit.
+fn transform_position(position: Position, change: &Change, drift: Drift) -> Position {
+    match *change {
+        Change::Insert(point, ref text) => {
+            if position < point || (position == point && drift == Drift::After) {
+                position
+            } else {
+                point + text.length() + (position - point)
+            }
+        }
+        Change::Delete(start, length) => {
+            let end = start + length;
+            if position < start {
+                position
+            } else {
+                start + (position - end.min(position))
+            }
+        }
+    }
+}
+
+fn transform_selections(selections: &mut [(Position, Position)], edits: &[Edit]) {
+    for edit in edits {
+        for (anchor, cursor) in selections.iter_mut() {
+            *anchor = transform_position(*anchor, &edit.change, Drift::Before);
+            *cursor = transform_position(*cursor, &edit.change, Drift::Before);
+        }
+    }
+}
+
+/// The state of a peer, as seen by the protocol.
+#[derive(Clone, Debug)]
+pub struct CollabClient {
+    site_id: SiteId,
+    revision: usize,
+    // The operation that was sent to the server, but not acknowledged yet.
+    outstanding: Option<Operation>,
+    // The edits made while waiting for the acknowledgement, which are sent when it arrives.
+    buffer: Operation,
+    // The selections to send once the server has acknowledged all operations.
+    pending_selections: Option<Vec<(Position, Position)>>,
+    // The revision of the last message that was sent.
+    sent_revision: usize,
+}
+
+impl CollabClient {
+    pub fn new(site_id: SiteId, revision: usize) -> Self {
+        Self {
+            site_id,
+            revision,
+            outstanding: None,
+            buffer: Vec::new(),
+            pending_selections: None,
+            sent_revision: revision,
+        }
+    }
+
+    pub fn site_id(&self) -> SiteId {
+        self.site_id
+    }
+
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Returns the operation that was sent to the server, but not acknowledged yet.
+    pub fn outstanding(&self) -> Option<&Operation> {
+        self.outstanding.as_ref()
+    }
+
+    /// Returns true if the server has acknowledged every local edit.
+    pub fn is_synchronized(&self) -> bool {
+        self.outstanding.is_none()
+    }
+
+    /// Registers edits made locally, and returns the message to send for them, if any.
+    pub fn apply_local_edits(&mut self, edits: Operation) -> Option<ClientMessage> {
+        if edits.is_empty() {
+            return None;
+        }
+        if self.outstanding.is_some() {
+            self.buffer.extend(edits);
+            return None;
+        }
+        self.outstanding = Some(edits.clone());
+        self.sent_revision = self.revision;
+        Some(ClientMessage::Operation {
+            revision: self.revision,
+            edits,
+        })
+    }
+
+    /// Registers the local selections, and returns the message to send for them, if any.
+    ///
+    /// Selections can only be sent in terms of a revision of the server's text, so while there
+    /// are unacknowledged edits they are held back.
+    pub fn set_selections(
+        &mut self,
+        selections: Vec<(Position, Position)>,
+    ) -> Option<ClientMessage> {
+        if self.outstanding.is_some() {
+            self.pending_selections = Some(selections);
+            return None;
+        }
+        self.sent_revision = self.revision;
+        Some(ClientMessage::Selections {
+            revision: self.revision,
+            selections,
+        })
+    }
+
+    /// Returns the message that tells the server which operations have been seen, if that has
+    /// changed since the last message.
+    pub fn ack(&mut self) -> Option<ClientMessage> {
+        if self.revision == self.sent_revision {
+            return None;
+        }
+        self.sent_revision = self.revision;
+        Some(ClientMessage::Ack {
+            revision: self.revision,
+        })
+    }
+
+    /// Handles the acknowledgement of the outstanding operation, and returns the messages to send
+    /// next.
+    pub fn handle_ack(&mut self) -> Option<ClientMessage> {
+        self.revision += 1;
+        self.outstanding = None;
+        if !self.buffer.is_empty() {
+            let edits = mem::take(&mut self.buffer);
+            return self.apply_local_edits(edits);
+        }
+        let selections = self.pending_selections.take()?;
+        self.set_selections(selections)
+    }
+
+    /// Transforms an operation of another peer, as broadcast by the server, against the local
+    /// edits the server did not know of when it applied it. Returns the edits to apply locally.
+    pub fn handle_remote_operation(&mut self, site_id: SiteId, edits: Operation) -> Operation {
+        self.revision += 1;
+        let Some(outstanding) = self.outstanding.take() else {
+            return edits;
+        };
+        let is_first = self.site_id < site_id;
+        let (outstanding, edits) = transform(&outstanding, &edits, is_first);
+        let (buffer, edits) = transform(&self.buffer, &edits, is_first);
+        self.outstanding = Some(outstanding);
+        self.buffer = buffer;
+        if let Some(selections) = &mut self.pending_selections {
+            transform_selections(selections, &edits);
+        }
+        edits
+    }
+
+    /// Maps a position in the server's text at the current revision to the local text.
+    pub fn transform_remote_position(&self, position: Position) -> Position {
+        self.outstanding
+            .iter()
+            .flatten()
+            .chain(&self.buffer)
+            .fold(position, |position, edit| {
+                transform_position(position, &edit.change, Drift::Before)
+            })
+    }
+}
+
+/// The server side of a shared document.
+///
+/// Peers are added with `connect`. Each call returns the messages to send, with the site ids of
+/// the peers to send them to.
+#[derive(Clone, Debug, Default)]
+pub struct CollabServer {
+    text: Text,
+    // The operations since revision `log_start`. Older operations are dropped once every peer has
+    // sent a message for a later revision, since no peer can refer to them anymore.
+    log: Vec<(SiteId, Operation)>,
+    log_start: usize,
+    sites: HashMap<SiteId, Site>,
+    next_site_id: SiteId,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Site {
+    // The revision of the last message of the peer. Its later messages are for this revision or a
+    // later one.
+    revision: usize,
+    // The selections of the peer, in the text at the current revision.
+    selections: Vec<(Position, Position)>,
+}
+
+impl CollabServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> &Text {
+        &self.text
+    }
+
+    pub fn revision(&self) -> usize {
+        self.log_start + self.log.len()
+    }
+
+    /// Returns the number of operations that are kept to transform late messages against.
+    pub fn log_len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Returns true if no peer has joined.
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+
+    /// Returns the site id for a new peer, which joins by sending `ClientMessage::Join`.
+    pub fn connect(&mut self) -> SiteId {
+        self.next_site_id += 1;
+        self.next_site_id
+    }
+
+    pub fn disconnect(&mut self, site_id: SiteId) -> Vec<(SiteId, ServerMessage)> {
+        if self.sites.remove(&site_id).is_none() {
+            return Vec::new();
+        }
+        self.truncate_log();
+        self.broadcast(site_id, ServerMessage::Left { site_id })
+    }
+
+    /// Handles a message of the peer `site_id`. Messages that are invalid, such as edits outside
+    /// the text, are ignored.
+    pub fn handle_message(
+        &mut self,
+        site_id: SiteId,
+        message: ClientMessage,
+    ) -> Vec<(SiteId, ServerMessage)> {
+        match message {
+            ClientMessage::Join { text } => {
+                if self.sites.is_empty() {
+                    self.text = text;
+                    self.log.clear();
+                    self.log_start = 0;
+                }
+                let mut messages = vec![(
+                    site_id,
+                    ServerMessage::Joined {
+                        site_id,
+                        revision: self.revision(),
+                        text: self.text.clone(),
+                    },
+                )];
+                for (&other_site_id, site) in &self.sites {
+                    messages.push((
+                        site_id,
+                        ServerMessage::Selections {
+                            site_id: other_site_id,
+                            selections: site.selections.clone(),
+                        },
+                    ));
+                }
+                self.sites.insert(
+                    site_id,
+                    Site {
+                        revision: self.revision(),
+                        selections: Vec::new(),
+                    },
+                );
+                messages
+            }
+            ClientMessage::Operation {
+                revision,
+                mut edits,
+            } => {
+                if !self.is_valid_revision(site_id, revision) {
+                    return Vec::new();
+                }
+                for (other_site_id, other_edits) in &self.log[revision - self.log_start..] {
+                    edits = transform(&edits, other_edits, site_id < *other_site_id).0;
+                }
+                let mut text = self.text.clone();
+                for edit in &edits {
+                    if !is_valid_change(&text, &edit.change) {
+                        return Vec::new();
+                    }
+                    text.apply_change(edit.change.clone());
+                }
+                self.text = text;
+                for site in self.sites.values_mut() {
+                    transform_selections(&mut site.selections, &edits);
+                }
+                self.log.push((site_id, edits.clone()));
+                self.set_site_revision(site_id, revision);
+                let mut messages =
+                    self.broadcast(site_id, ServerMessage::Operation { site_id, edits });
+                messages.push((site_id, ServerMessage::Ack));
+                messages
+            }
+            ClientMessage::Selections {
+                revision,
+                mut selections,
+            } => {
+                if !self.is_valid_revision(site_id, revision) {
+                    return Vec::new();
+                }
+                for (_, edits) in &self.log[revision - self.log_start..] {
+                    transform_selections(&mut selections, edits);
+                }
+                if !selections.iter().all(|&(anchor, cursor)| {
+                    is_valid_position(&self.text, anchor) && is_valid_position(&self.text, cursor)
+                }) {
+                    return Vec::new();
+                }
+                self.sites.get_mut(&site_id).unwrap().selections = selections.clone();
+                self.set_site_revision(site_id, revision);
+                self.broadcast(
+                    site_id,
+                    ServerMessage::Selections {
+                        site_id,
+                        selections,
+                    },
+                )
+            }
+            ClientMessage::Ack { revision } => {
+                if self.is_valid_revision(site_id, revision) {
+                    self.set_site_revision(site_id, revision);
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    // A peer can only send messages for revisions that it has seen, and that are still in the
+    // log.
+    fn is_valid_revision(&self, site_id: SiteId, revision: usize) -> bool {
+        self.sites
+            .get(&site_id)
+            .is_some_and(|site| site.revision <= revision)
+            && revision >= self.log_start
+            && revision <= self.revision()
+    }
+
+    fn set_site_revision(&mut self, site_id: SiteId, revision: usize) {
+        self.sites.get_mut(&site_id).unwrap().revision = revision;
+        self.truncate_log();
+    }
+
+    // Drops the operations that every peer has seen.
+    fn truncate_log(&mut self) {
+        let revision = self
+            .sites
+            .values()
+            .map(|site| site.revision)
+            .min()
+            .unwrap_or(self.revision());
+        let count = revision - self.log_start;
+        self.log.drain(..count);
+        self.log_start = revision;
+    }
+
+    // Returns `message` for every peer but `site_id`.
+    fn broadcast(&self, site_id: SiteId, message: ServerMessage) -> Vec<(SiteId, ServerMessage)> {
+        self.sites
+            .keys()
+            .filter(|&&other_site_id| other_site_id != site_id)
+            .map(|&other_site_id| (other_site_id, message.clone()))
+            .collect()
+    }
+}
+
+fn is_valid_position(text: &Text, position: Position) -> bool {
+    position.line_index < text.as_lines().len()
+        && text.as_lines()[position.line_index].is_char_boundary(position.byte_index)
+}
+
+fn is_valid_change(text: &Text, change: &Change) -> bool {
+    match *change {
+        Change::Insert(position, _) => is_valid_position(text, position),
+        Change::Delete(start, length) => {
+            is_valid_position(text, start) && is_valid_position(text, start + length)
+        }
+    }
+}
+
+/// Shares a document with the other peers connected to the same server.
+///
+/// Local edits are picked up from the document by `update`, and the edits of other peers are
+/// applied to it as they arrive. Edits of other peers are not added to the undo history, so undo
+/// only reverts the edits made here.
+#[derive(Debug)]
+pub struct Collab {
+    document: Document,
+    client: Option<CollabClient>,
+    // The text that the document and the server's text are merged from when joining.
+    base: Text,
+    // The server's text at the revision of the client.
+    synced_text: Text,
+    // The local selections that were sent last, kept in sync with the text.
+    selections: Vec<(Position, Position)>,
+    messages: Vec<ClientMessage>,
+}
+
+impl Collab {
+    /// Shares `document`, assuming that it has no changes that another peer may not have.
+    pub fn new(document: Document) -> Self {
+        let base = document.as_text().clone();
+        Self::new_with_base(document, base)
+    }
+
+    /// Shares `document`, whose changes since `base`, such as unsaved edits, are merged with the
+    /// text of the peers that are editing it already.
+    pub fn new_with_base(document: Document, base: Text) -> Self {
+        document.start_sharing();
+        let text = document.as_text().clone();
+        Self {
+            document,
+            client: None,
+            base,
+            synced_text: Text::new(),
+            selections: Vec::new(),
+            messages: vec![ClientMessage::Join { text }],
+        }
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    pub fn site_id(&self) -> Option<SiteId> {
+        self.client.as_ref().map(|client| client.site_id())
+    }
+
+    /// Returns the server's text at the last revision that was seen, which is the base to merge
+    /// from when joining again after the connection was lost.
+    pub fn synced_text(&self) -> &Text {
+        &self.synced_text
+    }
+
+    /// Picks up the edits made to the document since the last call, and the local selections if
+    /// they changed.
+    pub fn update(&mut self, selections: &[Selection]) {
+        self.flush_local_edits();
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        let selections: Vec<_> = selections
+            .iter()
+            .map(|selection| (selection.anchor, selection.cursor.position))
+            .collect();
+        if selections != self.selections {
+            self.selections = selections.clone();
+            self.messages.extend(client.set_selections(selections));
+        }
+        self.messages.extend(client.ack());
+    }
+
+    pub fn handle_message(&mut self, message: ServerMessage) {
+        self.flush_local_edits();
+        match message {
+            ServerMessage::Joined {
+                site_id,
+                revision,
+                text,
+            } => {
+                // The document may have changed since it was shared, and other peers may have
+                // changed the text of the server, so merge both.
+                let (local_edits, remote_edits) =
+                    merge_texts(&self.base, &self.document.as_text(), &text);
+                self.document.apply_remote_edits(&remote_edits);
+                let mut client = CollabClient::new(site_id, revision);
+                self.messages.extend(client.apply_local_edits(local_edits));
+                self.synced_text = text;
+                self.selections.clear();
+                self.client = Some(client);
+            }
+            ServerMessage::Operation { site_id, edits } => {
+                let Some(client) = &mut self.client else {
+                    return;
+                };
+                for edit in &edits {
+                    self.synced_text.apply_change(edit.change.clone());
+                }
+                let edits = client.handle_remote_operation(site_id, edits);
+                transform_selections(&mut self.selections, &edits);
+                self.document.apply_remote_edits(&edits);
+            }
+            ServerMessage::Ack => {
+                let Some(client) = &mut self.client else {
+                    return;
+                };
+                for edit in client.outstanding().into_iter().flatten() {
+                    self.synced_text.apply_change(edit.change.clone());
+                }
+                self.messages.extend(client.handle_ack());
+            }
+            ServerMessage::Selections {
+                site_id,
+                selections,
+            } => {
+                let Some(client) = &self.client else {
+                    return;
+                };
+                self.document.set_remote_selections(
+                    site_id,
+                    selections
+                        .into_iter()
+                        .map(|(anchor, cursor)| RemoteSelection {
+                            site_id,
+                            anchor: client.transform_remote_position(anchor),
+                            cursor: client.transform_remote_position(cursor),
+                        })
+                        .collect(),
+                );
+            }
+            ServerMessage::Left { site_id } => self.document.remove_remote_selections(site_id),
+        }
+    }
+
+    /// Returns the messages to send to the server, in order.
+    pub fn take_messages(&mut self) -> Vec<ClientMessage> {
+        mem::take(&mut self.messages)
+    }
+
+    fn flush_local_edits(&mut self) {
+        let edits = self.document.take_local_edits();
+        // Until the server answers, the edits are only in the document, which is merged with the
+        // text of the server as a whole.
+        if let Some(client) = &mut self.client {
+            self.messages.extend(client.apply_local_edits(edits));
+        }
+    }
+}
+
+// Merges the changes from `base` to `local` and from `base` to `remote`. Returns the edits that
+// turn `remote` into the merged text, and the edits that turn `local` into it.
+//
+// Changes that both sides made, such as local edits that reached the server before the connection
+// was lost, are only made once.
+fn merge_texts(base: &Text, local: &Text, remote: &Text) -> (Operation, Operation) {
+    let base_lines: Vec<&str> = base.as_lines().iter().map(|line| line.as_str()).collect();
+    let local_lines: Vec<&str> = local.as_lines().iter().map(|line| line.as_str()).collect();
+    let remote_lines: Vec<&str> = remote.as_lines().iter().map(|line| line.as_str()).collect();
+    let local_hunks = diff_lines(&base_lines, &local_lines, DiffAlgorithm::Myers);
+    let remote_hunks = diff_lines(&base_lines, &remote_lines, DiffAlgorithm::Myers);
+    let mut common_lines = Vec::new();
+    let mut line_index = 0;
+    for (old_range, new_range) in &local_hunks {
+        if remote_hunks
+            .iter()
+            .any(|(remote_old_range, remote_new_range)| {
+                remote_old_range == old_range
+                    && remote_lines[remote_new_range.clone()] == local_lines[new_range.clone()]
+            })
+        {
+            common_lines.extend_from_slice(&base_lines[line_index..old_range.start]);
+            common_lines.extend_from_slice(&local_lines[new_range.clone()]);
+            line_index = old_range.end;
+        }
+    }
+    common_lines.extend_from_slice(&base_lines[line_index..]);
+    let local_edits = line_edits(&common_lines, &local_lines);
+    let remote_edits = line_edits(&common_lines, &remote_lines);
+    // The text of the server was there first, so it goes first where both insert.
+    transform(&local_edits, &remote_edits, false)
+}
+
+// Returns the edits that turn `old_lines` into `new_lines`, one for each changed run of lines.
+fn line_edits(old_lines: &[&str], new_lines: &[&str]) -> Operation {
+    let mut edits = Vec::new();
+    // Edit from the bottom up, so that each edit applies to lines that the edits before it did not
+    // move.
+    for (old_range, new_range) in diff_lines(old_lines, new_lines, DiffAlgorithm::Myers)
+        .into_iter()
+        .rev()
+    {
+        let lines = &new_lines[new_range];
+        let line_count = old_lines.len();
+        let (start, end, text) = if old_range.end < line_count {
+            let mut text = lines.join("\n");
+            if !lines.is_empty() {
+                text.push('\n');
+            }
+            (
+                Position {
+                    line_index: old_range.start,
+                    byte_index: 0,
+                },
+                Position {
+                    line_index: old_range.end,
+                    byte_index: 0,
+                },
+                text,
+            )
+        } else if old_range.start > 0 {
+            // There is no line after the range to anchor the edit to, so replace the line break
+            // before the range instead.
+            let mut text = String::new();
+            for line in lines {
+                text.push('\n');
+                text.push_str(line);
+            }
+            (
+                Position {
+                    line_index: old_range.start - 1,
+                    byte_index: old_lines[old_range.start - 1].len(),
+                },
+                Position {
+                    line_index: line_count - 1,
+                    byte_index: old_lines[line_count - 1].len(),
+                },
+                text,
+            )
+        } else {
+            (
+                Position::zero(),
+                Position {
+                    line_index: line_count - 1,
+                    byte_index: old_lines[line_count - 1].len(),
+                },
+                lines.join("\n"),
+            )
+        };
+        // Only replace the part of the run that changed, so that the edits of two peers to
+        // neighbouring lines do not overlap.
+        let old_text = text_between(old_lines, start, end);
+        let prefix_len = common_prefix_len(&old_text, &text);
+        let suffix_len = common_suffix_len(&old_text[prefix_len..], &text[prefix_len..]);
+        let start = start + Text::from(&old_text[..prefix_len]).length();
+        let deleted_length =
+            Text::from(&old_text[prefix_len..old_text.len() - suffix_len]).length();
+        let text = &text[prefix_len..text.len() - suffix_len];
+        if deleted_length != Length::zero() {
+            edits.push(Edit {
+                change: Change::Delete(start, deleted_length),
+                drift: Drift::Before,
+            });
+        }
+        if !text.is_empty() {
+            edits.push(Edit {
+                change: Change::Insert(start, Text::from(text)),
+                drift: Drift::Before,
+            });
+        }
+    }
+    edits
+}
+
+fn text_between(lines: &[&str], start: Position, end: Position) -> String {
+    if start.line_index == end.line_index {
+        return lines[start.line_index][start.byte_index..end.byte_index].to_string();
+    }
+    let mut text = lines[start.line_index][start.byte_index..].to_string();
+    for line in &lines[start.line_index + 1..end.line_index] {
+        text.push('\n');
+        text.push_str(line);
+    }
+    text.push('\n');
+    text.push_str(&lines[end.line_index][..end.byte_index]);
+    text
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(char_a, char_b)| char_a == char_b)
+        .map(|(char, _)| char.len_utf8())
+        .sum()
+}
+
+fn common_suffix_len(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(char_a, char_b)| char_a == char_b)
+        .map(|(char, _)| char.len_utf8())
+        .sum()
+}
+
+impl Drop for Collab {
+    fn drop(&mut self) {
+        self.document.stop_sharing();
+    }
+}
@@ -0,0 +1,167 @@
+use {
+    crate::{
+        provider::{is_word_char, word_range, CompletionItem, CompletionProvider},
+        snippet::Snippet,
+        text::Position,
+        Session,
+    },
+    makepad_widgets::combo_box::{fuzzy_match, FuzzyMatch},
+};
+
+/// The state of the completion popup: the items a provider returned for the word before the
+/// cursor, and the ones that match what was typed of that word so far.
+///
+/// This does not draw anything, so that `CodeEditor` only has to open, update and close it in
+/// response to keys, and draw the visible matches.
+#[derive(Clone, Debug)]
+pub struct Completion {
+    // The start of the word that is completed.
+    word_start: Position,
+    // The part of the word before the cursor that `matches` were computed for.
+    word: String,
+    items: Vec<CompletionItem>,
+    // The indices of the items that match the word, and how they match, best match first.
+    matches: Vec<(usize, FuzzyMatch)>,
+    selected_index: usize,
+    first_visible_index: usize,
+}
+
+impl Completion {
+    /// Asks `provider` for the completions of the word before the cursor, if at least
+    /// `min_word_len` chars of it were typed. Returns `None` if there is nothing to complete.
+    pub fn open(
+        provider: &dyn CompletionProvider,
+        session: &Session,
+        min_word_len: usize,
+    ) -> Option<Self> {
+        session.handle_changes();
+        let position = cursor_position(session);
+        let text = session.document().as_text();
+        let line = &text.as_lines()[position.line_index];
+        let word_start = word_range(line, position.byte_index).start;
+        if line[word_start..position.byte_index].chars().count() < min_word_len {
+            return None;
+        }
+        drop(text);
+        let mut completion = Self {
+            word_start: Position {
+                line_index: position.line_index,
+                byte_index: word_start,
+            },
+            word: String::new(),
+            items: provider.completions(session.document(), position),
+            matches: Vec::new(),
+            selected_index: 0,
+            first_visible_index: 0,
+        };
+        completion.filter(session).then_some(completion)
+    }
+
+    /// Filters the items again if the word before the cursor changed. Returns `false` if the
+    /// popup should close, because the cursor left the word or nothing matches it anymore.
+    pub fn update(&mut self, session: &Session) -> bool {
+        session.handle_changes();
+        let position = cursor_position(session);
+        let text = session.document().as_text();
+        let word = (position.line_index == self.word_start.line_index)
+            .then(|| {
+                text.as_lines()[position.line_index]
+                    .get(self.word_start.byte_index..position.byte_index)
+            })
+            .flatten();
+        match word {
+            Some(word) if word.chars().all(is_word_char) => {
+                let is_changed = word != self.word;
+                drop(text);
+                !is_changed || self.filter(session)
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the selection by `delta` matches. Moving by one wraps around, moving by more stops at
+    /// the first or last match. The selected match is kept among the `visible_item_count` matches
+    /// that are shown.
+    pub fn select(&mut self, delta: isize, visible_item_count: usize) {
+        let count = self.matches.len() as isize;
+        let index = self.selected_index as isize + delta;
+        self.selected_index = if delta.abs() == 1 {
+            index.rem_euclid(count)
+        } else {
+            index.clamp(0, count - 1)
+        } as usize;
+        if self.selected_index < self.first_visible_index {
+            self.first_visible_index = self.selected_index;
+        } else if self.selected_index >= self.first_visible_index + visible_item_count {
+            self.first_visible_index = self.selected_index + 1 - visible_item_count;
+        }
+    }
+
+    /// Replaces the word before the cursor with the selected item, as a single undoable edit.
+    pub fn accept(&self, session: &Session) {
+        let item = self.selected_item();
+        session.insert_snippet(self.word.len(), &Snippet::parse(&item.insert_text));
+    }
+
+    pub fn word_start(&self) -> Position {
+        self.word_start
+    }
+
+    /// Returns the matching items and how they match, best match first.
+    pub fn matches(&self) -> impl Iterator<Item = (&CompletionItem, &FuzzyMatch)> {
+        self.matches
+            .iter()
+            .map(|(index, fuzzy_match)| (&self.items[*index], fuzzy_match))
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn selected_item(&self) -> &CompletionItem {
+        &self.items[self.matches[self.selected_index].0]
+    }
+
+    pub fn first_visible_index(&self) -> usize {
+        self.first_visible_index
+    }
+
+    // Returns whether anything matches the word, other than the word itself.
+    fn filter(&mut self, session: &Session) -> bool {
+        let position = cursor_position(session);
+        self.word = session.document().as_text().as_lines()[position.line_index]
+            [self.word_start.byte_index..position.byte_index]
+            .to_string();
+        let items = &self.items;
+        let mut matches: Vec<_> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                fuzzy_match(&self.word, &item.label).map(|fuzzy_match| (index, fuzzy_match))
+            })
+            .collect();
+        matches.sort_by(|(index_0, match_0), (index_1, match_1)| {
+            match_1
+                .score
+                .cmp(&match_0.score)
+                .then_with(|| items[*index_0].label.cmp(&items[*index_1].label))
+        });
+        if matches.is_empty() || matches.len() == 1 && items[matches[0].0].label == self.word {
+            return false;
+        }
+        self.matches = matches;
+        self.selected_index = 0;
+        self.first_visible_index = 0;
+        true
+    }
+}
+
+fn cursor_position(session: &Session) -> Position {
+    session.selections()[session.last_added_selection_index().unwrap()]
+        .cursor
+        .position
+}
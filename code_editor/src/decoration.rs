@@ -7,6 +7,12 @@ use {
 pub enum DecorationType {
     Error,
     Warning,
+    SearchMatch,
+    ActiveSearchMatch,
+    ConflictMarker,
+    ConflictOurs,
+    ConflictTheirs,
+    RemoteSelection,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
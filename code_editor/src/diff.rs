@@ -0,0 +1,574 @@
+use {
+    crate::text::Text,
+    std::{collections::HashMap, hash::Hash, iter, ops::Range},
+};
+
+/// Lines longer than this many chars are not diffed char by char, but highlighted as a whole.
+const MAX_CHAR_DIFF_LEN: usize = 1000;
+
+/// The smallest number of edits after which the search for a minimal diff is cut short.
+const MIN_MAX_COST: usize = 256;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DiffAlgorithm {
+    #[default]
+    Myers,
+    /// Matches lines that occur exactly once in both texts first, and then diffs the lines between
+    /// them with Myers. This tends to line up hunks with the structure of the code, such as
+    /// function boundaries, rather than with the nearest closing brace.
+    Patience,
+}
+
+/// The line diff between an old and a new text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff {
+    old_line_count: usize,
+    new_line_count: usize,
+    hunks: Vec<Hunk>,
+}
+
+impl Diff {
+    pub fn new(old: &Text, new: &Text, algorithm: DiffAlgorithm) -> Self {
+        let old_lines: Vec<&str> = old.as_lines().iter().map(|line| line.as_str()).collect();
+        let new_lines: Vec<&str> = new.as_lines().iter().map(|line| line.as_str()).collect();
+        Self {
+            old_line_count: old_lines.len(),
+            new_line_count: new_lines.len(),
+            hunks: diff_lines(&old_lines, &new_lines, algorithm)
+                .into_iter()
+                .map(|(old_range, new_range)| {
+                    Hunk::new(
+                        &old_lines[old_range.clone()],
+                        &new_lines[new_range.clone()],
+                        old_range,
+                        new_range,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn old_line_count(&self) -> usize {
+        self.old_line_count
+    }
+
+    pub fn new_line_count(&self) -> usize {
+        self.new_line_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    /// Returns the diff as a sequence of regions, in which runs of unchanged lines that are more
+    /// than `context_line_count` lines away from any hunk are folded.
+    pub fn regions(&self, context_line_count: usize) -> Vec<Region> {
+        let mut regions = Vec::new();
+        let mut old_line = 0;
+        let mut new_line = 0;
+        for (hunk_index, hunk) in self.hunks.iter().enumerate() {
+            push_unchanged_regions(
+                &mut regions,
+                old_line..hunk.old_range.start,
+                new_line,
+                context_line_count,
+                hunk_index == 0,
+                false,
+            );
+            regions.push(Region::Hunk(hunk_index));
+            old_line = hunk.old_range.end;
+            new_line = hunk.new_range.end;
+        }
+        push_unchanged_regions(
+            &mut regions,
+            old_line..self.old_line_count,
+            new_line,
+            context_line_count,
+            self.hunks.is_empty(),
+            true,
+        );
+        regions
+    }
+}
+
+/// A run of lines that differs between the old and the new text. Either range can be empty, in
+/// which case lines were only inserted or only deleted.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Hunk {
+    pub old_range: Range<usize>,
+    pub new_range: Range<usize>,
+    /// For each old line, the byte ranges that differ from the new line at the same offset in the
+    /// hunk. Empty for lines without a counterpart, which differ as a whole.
+    pub old_char_ranges: Vec<Vec<Range<usize>>>,
+    /// For each new line, the byte ranges that differ from the old line at the same offset in the
+    /// hunk.
+    pub new_char_ranges: Vec<Vec<Range<usize>>>,
+}
+
+impl Hunk {
+    fn new(
+        old_lines: &[&str],
+        new_lines: &[&str],
+        old_range: Range<usize>,
+        new_range: Range<usize>,
+    ) -> Self {
+        let mut old_char_ranges: Vec<_> = old_lines.iter().map(|_| Vec::new()).collect();
+        let mut new_char_ranges: Vec<_> = new_lines.iter().map(|_| Vec::new()).collect();
+        for (index, (old_line, new_line)) in old_lines.iter().zip(new_lines).enumerate() {
+            let (old_ranges, new_ranges) = diff_chars(old_line, new_line);
+            old_char_ranges[index] = old_ranges;
+            new_char_ranges[index] = new_ranges;
+        }
+        Self {
+            old_range,
+            new_range,
+            old_char_ranges,
+            new_char_ranges,
+        }
+    }
+
+    pub fn is_insertion(&self) -> bool {
+        self.old_range.is_empty()
+    }
+
+    pub fn is_deletion(&self) -> bool {
+        self.new_range.is_empty()
+    }
+}
+
+/// A part of a diff, as returned by `Diff::regions`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Region {
+    /// Lines that are the same in both texts.
+    Unchanged {
+        old_range: Range<usize>,
+        new_start: usize,
+    },
+    /// Unchanged lines that are hidden until the fold is expanded.
+    Folded {
+        old_range: Range<usize>,
+        new_start: usize,
+    },
+    /// The hunk with the given index.
+    Hunk(usize),
+}
+
+fn push_unchanged_regions(
+    regions: &mut Vec<Region>,
+    old_range: Range<usize>,
+    new_start: usize,
+    context_line_count: usize,
+    is_first: bool,
+    is_last: bool,
+) {
+    if old_range.is_empty() {
+        return;
+    }
+    // Context is only kept on the sides of the run that border a hunk.
+    let context_before = if is_first { 0 } else { context_line_count };
+    let context_after = if is_last { 0 } else { context_line_count };
+    let len = old_range.len();
+    // Folding a single line would not save any space, as the fold itself takes up a line.
+    if len <= context_before + context_after + 1 {
+        regions.push(Region::Unchanged {
+            old_range,
+            new_start,
+        });
+        return;
+    }
+    let fold_start = old_range.start + context_before;
+    let fold_end = old_range.end - context_after;
+    if context_before > 0 {
+        regions.push(Region::Unchanged {
+            old_range: old_range.start..fold_start,
+            new_start,
+        });
+    }
+    regions.push(Region::Folded {
+        old_range: fold_start..fold_end,
+        new_start: new_start + context_before,
+    });
+    if context_after > 0 {
+        regions.push(Region::Unchanged {
+            old_range: fold_end..old_range.end,
+            new_start: new_start + (fold_end - old_range.start),
+        });
+    }
+}
+
+/// Diffs two sequences of lines, returning the old and new line ranges of each hunk, in order.
+pub fn diff_lines(
+    old_lines: &[&str],
+    new_lines: &[&str],
+    algorithm: DiffAlgorithm,
+) -> Vec<(Range<usize>, Range<usize>)> {
+    // Comparing interned ids is a lot cheaper than comparing the lines themselves.
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut old_ids = Vec::with_capacity(old_lines.len());
+    let mut new_ids = Vec::with_capacity(new_lines.len());
+    for (lines, line_ids) in [(old_lines, &mut old_ids), (new_lines, &mut new_ids)] {
+        for &line in lines {
+            let next_id = ids.len();
+            line_ids.push(*ids.entry(line).or_insert(next_id));
+        }
+    }
+    let mut matches = Vec::new();
+    match algorithm {
+        DiffAlgorithm::Myers => myers(&old_ids, &new_ids, 0, 0, &mut matches),
+        DiffAlgorithm::Patience => patience(&old_ids, &new_ids, 0, 0, &mut matches),
+    }
+    matches_to_hunks(&matches, old_ids.len(), new_ids.len())
+}
+
+/// Diffs two lines char by char, returning the byte ranges in each line that are not shared by
+/// the other.
+pub fn diff_chars(old_line: &str, new_line: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_chars: Vec<(usize, char)> = old_line.char_indices().collect();
+    let new_chars: Vec<(usize, char)> = new_line.char_indices().collect();
+    if old_chars.len() > MAX_CHAR_DIFF_LEN || new_chars.len() > MAX_CHAR_DIFF_LEN {
+        return (
+            iter::once(0..old_line.len()).collect(),
+            iter::once(0..new_line.len()).collect(),
+        );
+    }
+    let old: Vec<char> = old_chars.iter().map(|&(_, char)| char).collect();
+    let new: Vec<char> = new_chars.iter().map(|&(_, char)| char).collect();
+    let mut matches = Vec::new();
+    myers(&old, &new, 0, 0, &mut matches);
+    let byte_range = |chars: &[(usize, char)], len: usize, range: Range<usize>| {
+        let start = chars.get(range.start).map_or(len, |&(index, _)| index);
+        let end = chars.get(range.end).map_or(len, |&(index, _)| index);
+        start..end
+    };
+    let mut old_ranges = Vec::new();
+    let mut new_ranges = Vec::new();
+    for (old_range, new_range) in matches_to_hunks(&matches, old.len(), new.len()) {
+        if !old_range.is_empty() {
+            old_ranges.push(byte_range(&old_chars, old_line.len(), old_range));
+        }
+        if !new_range.is_empty() {
+            new_ranges.push(byte_range(&new_chars, new_line.len(), new_range));
+        }
+    }
+    (old_ranges, new_ranges)
+}
+
+// A run of `len` equal items starting at `old_start` and `new_start`.
+#[derive(Clone, Copy, Debug)]
+struct Match {
+    old_start: usize,
+    new_start: usize,
+    len: usize,
+}
+
+fn matches_to_hunks(
+    matches: &[Match],
+    old_len: usize,
+    new_len: usize,
+) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut hunks = Vec::new();
+    let mut old_index = 0;
+    let mut new_index = 0;
+    for m in matches.iter().copied().chain(Some(Match {
+        old_start: old_len,
+        new_start: new_len,
+        len: 0,
+    })) {
+        if old_index < m.old_start || new_index < m.new_start {
+            hunks.push((old_index..m.old_start, new_index..m.new_start));
+        }
+        old_index = m.old_start + m.len;
+        new_index = m.new_start + m.len;
+    }
+    hunks
+}
+
+fn common_prefix_len<T: Eq>(old: &[T], new: &[T]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix_len<T: Eq>(old: &[T], new: &[T]) -> usize {
+    old.iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+// Myers' O(ND) diff, in its linear space variant that recursively splits both sequences at the
+// middle snake of an optimal edit script.
+fn myers<T: Eq>(
+    old: &[T],
+    new: &[T],
+    old_offset: usize,
+    new_offset: usize,
+    matches: &mut Vec<Match>,
+) {
+    let prefix_len = common_prefix_len(old, new);
+    if prefix_len > 0 {
+        matches.push(Match {
+            old_start: old_offset,
+            new_start: new_offset,
+            len: prefix_len,
+        });
+    }
+    let old = &old[prefix_len..];
+    let new = &new[prefix_len..];
+    let old_offset = old_offset + prefix_len;
+    let new_offset = new_offset + prefix_len;
+    let suffix_len = common_suffix_len(old, new);
+    let old = &old[..old.len() - suffix_len];
+    let new = &new[..new.len() - suffix_len];
+    if !old.is_empty() && !new.is_empty() {
+        let (old_start, new_start, old_end, new_end) = middle_snake(old, new);
+        myers(
+            &old[..old_start],
+            &new[..new_start],
+            old_offset,
+            new_offset,
+            matches,
+        );
+        if old_end > old_start {
+            matches.push(Match {
+                old_start: old_offset + old_start,
+                new_start: new_offset + new_start,
+                len: old_end - old_start,
+            });
+        }
+        myers(
+            &old[old_end..],
+            &new[new_end..],
+            old_offset + old_end,
+            new_offset + new_end,
+            matches,
+        );
+    }
+    if suffix_len > 0 {
+        matches.push(Match {
+            old_start: old_offset + old.len(),
+            new_start: new_offset + new.len(),
+            len: suffix_len,
+        });
+    }
+}
+
+// Finds the middle snake of an optimal edit script between two non-empty sequences, returning
+// its start and end as (old_start, new_start, old_end, new_end).
+//
+// Like xdiff, the search gives up once the edit script costs more than `max_cost(n + m)`, as
+// finding the optimal one is quadratic in the number of differences. It then splits the
+// sequences at a point that the forward or backward search reached, as an empty snake, trading
+// a minimal diff for one that is found in bounded time.
+fn middle_snake<T: Eq>(old: &[T], new: &[T]) -> (usize, usize, usize, usize) {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let delta = n - m;
+    let is_odd = delta & 1 != 0;
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d + 1;
+    let len = (2 * offset + 1) as usize;
+    let max_cost = max_cost((n + m) as usize) as isize;
+    // The furthest reaching x on each diagonal k = x - y, for the forward and backward searches.
+    // The backward search runs over the reversed sequences.
+    let mut forward = vec![0isize; len];
+    let mut backward = vec![0isize; len];
+    for d in 0..=max_d {
+        let mut k = -d;
+        while k <= d {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+            // The backward diagonal that corresponds to this forward diagonal.
+            let c = delta - k;
+            if is_odd && c > -d && c < d && x + backward[(c + offset) as usize] >= n {
+                return (x_start as usize, y_start as usize, x as usize, y as usize);
+            }
+            k += 2;
+        }
+        let mut c = -d;
+        while c <= d {
+            let index = (c + offset) as usize;
+            let mut x = if c == -d || (c != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y = x - c;
+            let (x_end, y_end) = (x, y);
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index] = x;
+            let k = delta - c;
+            if !is_odd && k >= -d && k <= d && x + forward[(k + offset) as usize] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x_end) as usize,
+                    (m - y_end) as usize,
+                );
+            }
+            c += 2;
+        }
+        if d >= max_cost {
+            // Split at the point reached by either search that is closest to the middle. Points
+            // off the edit graph or at its corners are skipped, so that both halves are smaller
+            // than what they split.
+            let mut best = None;
+            let mut k = -d;
+            while k <= d {
+                let index = (k + offset) as usize;
+                for (x, y) in [
+                    (forward[index], forward[index] - k),
+                    (n - backward[index], m - (backward[index] - k)),
+                ] {
+                    let is_inside = (0..=n).contains(&x) && (0..=m).contains(&y);
+                    let progress = x + y;
+                    if is_inside && 0 < progress && progress < n + m {
+                        let distance = progress.min(n + m - progress);
+                        if best.is_none_or(|(_, _, best_distance)| distance > best_distance) {
+                            best = Some((x as usize, y as usize, distance));
+                        }
+                    }
+                }
+                k += 2;
+            }
+            if let Some((x, y, _)) = best {
+                return (x, y, x, y);
+            }
+        }
+    }
+    unreachable!()
+}
+
+// The number of edits after which `middle_snake` stops looking for an optimal edit script
+// between sequences with `len` items in total. This is the square root of `len`, as in xdiff,
+// but never less than `MIN_MAX_COST`, so that the diffs of everyday edits stay minimal.
+fn max_cost(len: usize) -> usize {
+    let mut cost = 1;
+    while cost * cost < len {
+        cost *= 2;
+    }
+    cost.max(MIN_MAX_COST)
+}
+
+fn patience<T: Eq + Hash>(
+    old: &[T],
+    new: &[T],
+    old_offset: usize,
+    new_offset: usize,
+    matches: &mut Vec<Match>,
+) {
+    let prefix_len = common_prefix_len(old, new);
+    if prefix_len > 0 {
+        matches.push(Match {
+            old_start: old_offset,
+            new_start: new_offset,
+            len: prefix_len,
+        });
+    }
+    let old = &old[prefix_len..];
+    let new = &new[prefix_len..];
+    let old_offset = old_offset + prefix_len;
+    let new_offset = new_offset + prefix_len;
+    let suffix_len = common_suffix_len(old, new);
+    let old = &old[..old.len() - suffix_len];
+    let new = &new[..new.len() - suffix_len];
+    let anchors = unique_common_items(old, new);
+    if anchors.is_empty() {
+        myers(old, new, old_offset, new_offset, matches);
+    } else {
+        let mut old_index = 0;
+        let mut new_index = 0;
+        for (old_anchor, new_anchor) in anchors {
+            patience(
+                &old[old_index..old_anchor],
+                &new[new_index..new_anchor],
+                old_offset + old_index,
+                new_offset + new_index,
+                matches,
+            );
+            matches.push(Match {
+                old_start: old_offset + old_anchor,
+                new_start: new_offset + new_anchor,
+                len: 1,
+            });
+            old_index = old_anchor + 1;
+            new_index = new_anchor + 1;
+        }
+        patience(
+            &old[old_index..],
+            &new[new_index..],
+            old_offset + old_index,
+            new_offset + new_index,
+            matches,
+        );
+    }
+    if suffix_len > 0 {
+        matches.push(Match {
+            old_start: old_offset + old.len(),
+            new_start: new_offset + new.len(),
+            len: suffix_len,
+        });
+    }
+}
+
+// Returns the longest increasing sequence of (old_index, new_index) pairs of items that occur
+// exactly once in both sequences.
+fn unique_common_items<T: Eq + Hash>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    let mut counts: HashMap<&T, (usize, usize, usize)> = HashMap::new();
+    for (index, item) in old.iter().enumerate() {
+        let count = counts.entry(item).or_insert((0, 0, 0));
+        count.0 += 1;
+        count.2 = index;
+    }
+    for item in new {
+        if let Some(count) = counts.get_mut(item) {
+            count.1 += 1;
+        }
+    }
+    let mut pairs = Vec::new();
+    for (new_index, item) in new.iter().enumerate() {
+        if let Some(&(1, 1, old_index)) = counts.get(item) {
+            pairs.push((old_index, new_index));
+        }
+    }
+    // The pairs are ordered by new index, so find the longest run that is increasing in old
+    // index as well, by patience sorting.
+    let mut pile_tops: Vec<usize> = Vec::new();
+    let mut prev_indices = vec![None; pairs.len()];
+    for (index, &(old_index, _)) in pairs.iter().enumerate() {
+        let pile_index = pile_tops.partition_point(|&top| pairs[top].0 < old_index);
+        if pile_index > 0 {
+            prev_indices[index] = Some(pile_tops[pile_index - 1]);
+        }
+        if pile_index == pile_tops.len() {
+            pile_tops.push(index);
+        } else {
+            pile_tops[pile_index] = index;
+        }
+    }
+    let mut sequence = Vec::new();
+    let mut index = pile_tops.last().copied();
+    while let Some(current_index) = index {
+        sequence.push(pairs[current_index]);
+        index = prev_indices[current_index];
+    }
+    sequence.reverse();
+    sequence
+}
@@ -0,0 +1,728 @@
+use {
+    crate::{
+        code_editor::TokenColors,
+        diff::{Diff, DiffAlgorithm, Region},
+        str::StrExt,
+        text::Text,
+        token::TokenKind,
+        Session, Token,
+    },
+    makepad_widgets::*,
+    std::{collections::HashSet, fmt::Write, ops::Range},
+};
+
+const PADDING: f64 = 10.0;
+
+live_design! {
+    import makepad_draw::shader::std::*;
+    import makepad_widgets::base::*;
+    import makepad_widgets::theme_desktop_dark::*;
+
+    DiffColors = {{DiffColors}} {
+        inserted_line: #3fb95026,
+        deleted_line: #f8514926,
+        inserted_chars: #3fb95066,
+        deleted_chars: #f8514966,
+        filler: #ffffff08,
+        hunk_header: #58a6ff1a,
+        fold: #ffffff0d,
+    }
+
+    DiffView = {{DiffView}} {
+        height: Fill, width: Fill,
+        margin: 0,
+
+        scroll_bars: <ScrollBars> {}
+        draw_bg: { color: (THEME_COLOR_BG_CONTAINER) }
+        draw_gutter: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_META),
+        }
+        draw_text: {
+            text_style: <THEME_FONT_CODE> {},
+        }
+        draw_header: {
+            text_style: <THEME_FONT_CODE> {},
+            color: (THEME_COLOR_TEXT_META),
+        }
+        draw_action: {
+            text_style: <THEME_FONT_CODE> {},
+            color: #e8a33d,
+        }
+    }
+}
+
+/// How a `DiffView` lays out the two texts. `SideBySide` shows the old text on the left and the
+/// new text on the right, with the lines of each hunk aligned. `Inline` shows the deleted lines of
+/// each hunk followed by the inserted lines, in a single column.
+#[derive(Copy, Clone, Debug, PartialEq, Live, LiveHook)]
+#[live_ignore]
+pub enum DiffMode {
+    #[pick]
+    SideBySide,
+    Inline,
+}
+
+/// Shows the difference between the texts of two sessions. Both sides scroll together, since
+/// they are laid out as one sequence of aligned rows.
+///
+/// Accepting a hunk copies its new lines into the old document, reverting a hunk copies its old
+/// lines back into the new document. Either way the hunk disappears from the diff.
+#[derive(Live, LiveRegister)]
+pub struct DiffView {
+    #[walk]
+    walk: Walk,
+    #[live]
+    scroll_bars: ScrollBars,
+    #[live]
+    draw_bg: DrawColor,
+    #[live]
+    draw_gutter: DrawText,
+    #[live]
+    draw_text: DrawText,
+    #[live]
+    draw_header: DrawText,
+    #[live]
+    draw_action: DrawText,
+    #[live]
+    draw_line_bg: DrawColor,
+    #[live]
+    token_colors: TokenColors,
+    #[live]
+    diff_colors: DiffColors,
+    #[live]
+    mode: DiffMode,
+    /// The number of unchanged lines that stay visible around each hunk when folding.
+    #[live(3usize)]
+    context_line_count: usize,
+
+    #[rust]
+    algorithm: DiffAlgorithm,
+    // The revisions of the old and new documents that `diff` was computed for.
+    #[rust]
+    revisions: Option<(u64, u64)>,
+    #[rust]
+    diff: Diff,
+    #[rust]
+    rows: Vec<Row>,
+    #[rust]
+    rows_are_dirty: bool,
+    #[rust]
+    max_column_count: usize,
+    // The folds that were expanded, by the first old line they hide.
+    #[rust]
+    expanded_folds: HashSet<usize>,
+    #[rust]
+    hits: Vec<(Rect, DiffViewHit)>,
+    #[rust]
+    cell_size: DVec2,
+}
+
+impl LiveHook for DiffView {}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum DiffViewAction {
+    /// The hunk with the given index was applied to the old document.
+    HunkAccepted(usize),
+    /// The hunk with the given index was undone in the new document.
+    HunkReverted(usize),
+    None,
+}
+
+#[derive(Clone, Debug)]
+enum Row {
+    Line {
+        old_line: Option<usize>,
+        new_line: Option<usize>,
+        hunk_index: Option<usize>,
+    },
+    HunkHeader(usize),
+    Fold {
+        old_range: Range<usize>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum DiffViewHit {
+    Fold(usize),
+    Accept(usize),
+    Revert(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Pane {
+    // The x coordinates of the line numbers, the text, and the right edge of the pane, relative to
+    // the left edge of the view.
+    gutter_x: f64,
+    text_x: f64,
+    right_x: f64,
+}
+
+impl Pane {
+    /// The rect of the text column of the pane for a row whose left edge is at `origin`.
+    fn text_rect(&self, origin: DVec2, height: f64) -> Rect {
+        Rect {
+            pos: dvec2(origin.x + self.text_x, origin.y),
+            size: dvec2(self.right_x - self.text_x, height),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Old,
+    New,
+}
+
+impl DiffView {
+    pub fn redraw(&mut self, cx: &mut Cx) {
+        self.scroll_bars.redraw(cx);
+    }
+
+    pub fn area(&self) -> Area {
+        self.scroll_bars.area()
+    }
+
+    pub fn walk(&self, _cx: &mut Cx) -> Walk {
+        self.walk
+    }
+
+    pub fn diff(&self) -> &Diff {
+        &self.diff
+    }
+
+    pub fn mode(&self) -> DiffMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, cx: &mut Cx, mode: DiffMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.rows_are_dirty = true;
+            self.redraw(cx);
+        }
+    }
+
+    pub fn set_algorithm(&mut self, cx: &mut Cx, algorithm: DiffAlgorithm) {
+        if self.algorithm != algorithm {
+            self.algorithm = algorithm;
+            self.revisions = None;
+            self.redraw(cx);
+        }
+    }
+
+    pub fn expand_all_folds(&mut self, cx: &mut Cx) {
+        for row in &self.rows {
+            if let Row::Fold { old_range } = row {
+                self.expanded_folds.insert(old_range.start);
+            }
+        }
+        self.rows_are_dirty = true;
+        self.redraw(cx);
+    }
+
+    pub fn collapse_all_folds(&mut self, cx: &mut Cx) {
+        self.expanded_folds.clear();
+        self.rows_are_dirty = true;
+        self.redraw(cx);
+    }
+
+    pub fn draw_walk_diff(&mut self, cx: &mut Cx2d, old: &Session, new: &Session, walk: Walk) {
+        old.handle_changes();
+        new.handle_changes();
+        self.update_diff(old, new);
+
+        self.cell_size =
+            self.draw_text.text_style.font_size * self.draw_text.get_monospace_base(cx);
+        self.hits.clear();
+
+        self.scroll_bars.begin(cx, walk, Layout::default());
+        let turtle_rect = cx.turtle().rect();
+        let visible_rect = cx.turtle().unscrolled_rect();
+        self.draw_bg.draw_abs(cx, visible_rect);
+
+        let panes = self.panes(visible_rect.size.x);
+        let text_width = panes
+            .iter()
+            .map(|pane| pane.right_x - pane.text_x)
+            .fold(f64::INFINITY, f64::min);
+        let scroll_pos = self.scroll_bars.get_scroll_pos();
+        let row_start = ((scroll_pos.y - PADDING) / self.cell_size.y).max(0.0) as usize;
+        let row_end = (((scroll_pos.y + visible_rect.size.y) / self.cell_size.y).ceil() as usize
+            + 1)
+        .min(self.rows.len());
+        for row_index in row_start.min(row_end)..row_end {
+            let y = turtle_rect.pos.y + PADDING + row_index as f64 * self.cell_size.y;
+            self.draw_row(
+                cx,
+                old,
+                new,
+                row_index,
+                dvec2(visible_rect.pos.x, y),
+                &panes,
+            );
+        }
+
+        cx.turtle_mut().set_used(
+            visible_rect.size.x
+                + (self.max_column_count as f64 * self.cell_size.x - text_width + PADDING).max(0.0),
+            self.rows.len() as f64 * self.cell_size.y + 2.0 * PADDING,
+        );
+        self.scroll_bars.end(cx);
+    }
+
+    pub fn handle_event(
+        &mut self,
+        cx: &mut Cx,
+        event: &Event,
+        scope: &mut Scope,
+        old: &Session,
+        new: &Session,
+    ) -> Vec<DiffViewAction> {
+        let mut actions = Vec::new();
+        if !self.scroll_bars.handle_event(cx, event, scope).is_empty() {
+            self.redraw(cx);
+        }
+        match event.hits(cx, self.scroll_bars.area()) {
+            Hit::FingerHoverIn(FingerHoverEvent { abs, .. })
+            | Hit::FingerHoverOver(FingerHoverEvent { abs, .. }) => {
+                cx.set_cursor(if self.hit(abs).is_some() {
+                    MouseCursor::Hand
+                } else {
+                    MouseCursor::Default
+                });
+            }
+            Hit::FingerDown(FingerDownEvent { abs, .. }) => match self.hit(abs) {
+                Some(DiffViewHit::Fold(old_start)) => {
+                    self.expanded_folds.insert(old_start);
+                    self.rows_are_dirty = true;
+                    self.redraw(cx);
+                }
+                Some(DiffViewHit::Accept(hunk_index)) => {
+                    let hunk = &self.diff.hunks()[hunk_index];
+                    let lines = lines_in_range(&new.document().as_text(), hunk.new_range.clone());
+                    old.replace_lines(hunk.old_range.clone(), &lines);
+                    // The hits refer to the old diff until the next draw.
+                    self.hits.clear();
+                    actions.push(DiffViewAction::HunkAccepted(hunk_index));
+                    self.redraw(cx);
+                }
+                Some(DiffViewHit::Revert(hunk_index)) => {
+                    let hunk = &self.diff.hunks()[hunk_index];
+                    let lines = lines_in_range(&old.document().as_text(), hunk.old_range.clone());
+                    new.replace_lines(hunk.new_range.clone(), &lines);
+                    self.hits.clear();
+                    actions.push(DiffViewAction::HunkReverted(hunk_index));
+                    self.redraw(cx);
+                }
+                None => {}
+            },
+            _ => {}
+        }
+        actions
+    }
+
+    fn hit(&self, abs: DVec2) -> Option<DiffViewHit> {
+        self.hits
+            .iter()
+            .find(|(rect, _)| rect.contains(abs))
+            .map(|&(_, hit)| hit)
+    }
+
+    fn update_diff(&mut self, old: &Session, new: &Session) {
+        let revisions = (old.document().revision(), new.document().revision());
+        if self.revisions != Some(revisions) {
+            self.diff = Diff::new(
+                &old.document().as_text(),
+                &new.document().as_text(),
+                self.algorithm,
+            );
+            self.revisions = Some(revisions);
+            self.rows_are_dirty = true;
+        }
+        if self.rows_are_dirty {
+            self.rows_are_dirty = false;
+            self.update_rows(&old.document().as_text(), &new.document().as_text());
+        }
+    }
+
+    fn update_rows(&mut self, old_text: &Text, new_text: &Text) {
+        let mut rows = Vec::new();
+        let push_unchanged = |rows: &mut Vec<Row>, old_range: Range<usize>, new_start: usize| {
+            for (offset, old_line) in old_range.enumerate() {
+                rows.push(Row::Line {
+                    old_line: Some(old_line),
+                    new_line: Some(new_start + offset),
+                    hunk_index: None,
+                });
+            }
+        };
+        for region in self.diff.regions(self.context_line_count) {
+            match region {
+                Region::Unchanged {
+                    old_range,
+                    new_start,
+                } => push_unchanged(&mut rows, old_range, new_start),
+                Region::Folded {
+                    old_range,
+                    new_start,
+                } => {
+                    if self.expanded_folds.contains(&old_range.start) {
+                        push_unchanged(&mut rows, old_range, new_start);
+                    } else {
+                        rows.push(Row::Fold { old_range });
+                    }
+                }
+                Region::Hunk(hunk_index) => {
+                    let hunk = &self.diff.hunks()[hunk_index];
+                    rows.push(Row::HunkHeader(hunk_index));
+                    match self.mode {
+                        DiffMode::SideBySide => {
+                            for offset in 0..hunk.old_range.len().max(hunk.new_range.len()) {
+                                rows.push(Row::Line {
+                                    old_line: Some(hunk.old_range.start + offset)
+                                        .filter(|line| *line < hunk.old_range.end),
+                                    new_line: Some(hunk.new_range.start + offset)
+                                        .filter(|line| *line < hunk.new_range.end),
+                                    hunk_index: Some(hunk_index),
+                                });
+                            }
+                        }
+                        DiffMode::Inline => {
+                            for line in hunk.old_range.clone() {
+                                rows.push(Row::Line {
+                                    old_line: Some(line),
+                                    new_line: None,
+                                    hunk_index: Some(hunk_index),
+                                });
+                            }
+                            for line in hunk.new_range.clone() {
+                                rows.push(Row::Line {
+                                    old_line: None,
+                                    new_line: Some(line),
+                                    hunk_index: Some(hunk_index),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let old_lines = old_text.as_lines();
+        let new_lines = new_text.as_lines();
+        self.max_column_count = rows
+            .iter()
+            .filter_map(|row| match *row {
+                Row::Line {
+                    old_line, new_line, ..
+                } => Some(
+                    old_line
+                        .map_or(0, |line| old_lines[line].column_count())
+                        .max(new_line.map_or(0, |line| new_lines[line].column_count())),
+                ),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        self.rows = rows;
+    }
+
+    fn line_number_column_count(&self) -> usize {
+        self.diff
+            .old_line_count()
+            .max(self.diff.new_line_count())
+            .to_string()
+            .len()
+    }
+
+    fn panes(&self, width: f64) -> Vec<Pane> {
+        let number_width = self.line_number_column_count() as f64 * self.cell_size.x;
+        match self.mode {
+            DiffMode::SideBySide => {
+                let half_width = width / 2.0;
+                let gutter_width = number_width + 2.0 * self.cell_size.x;
+                vec![
+                    Pane {
+                        gutter_x: PADDING,
+                        text_x: PADDING + gutter_width,
+                        right_x: half_width,
+                    },
+                    Pane {
+                        gutter_x: half_width + PADDING,
+                        text_x: half_width + PADDING + gutter_width,
+                        right_x: width,
+                    },
+                ]
+            }
+            DiffMode::Inline => {
+                // Room for the old and the new line number, and a marker for the kind of change.
+                let gutter_width = 2.0 * number_width + 4.0 * self.cell_size.x;
+                vec![Pane {
+                    gutter_x: PADDING,
+                    text_x: PADDING + gutter_width,
+                    right_x: width,
+                }]
+            }
+        }
+    }
+
+    fn draw_row(
+        &mut self,
+        cx: &mut Cx2d,
+        old: &Session,
+        new: &Session,
+        row_index: usize,
+        origin: DVec2,
+        panes: &[Pane],
+    ) {
+        let (origin_x, y) = (origin.x, origin.y);
+        let row_width = panes.last().unwrap().right_x;
+        match self.rows[row_index].clone() {
+            Row::Fold { old_range } => {
+                let rect = Rect {
+                    pos: dvec2(origin_x, y),
+                    size: dvec2(row_width, self.cell_size.y),
+                };
+                self.draw_line_bg.color = self.diff_colors.fold;
+                self.draw_line_bg.draw_abs(cx, rect);
+                let mut label = String::new();
+                let _ = write!(label, "⋯ {} unchanged lines", old_range.len());
+                self.draw_header
+                    .draw_abs(cx, dvec2(origin_x + panes[0].text_x, y), &label);
+                self.hits.push((rect, DiffViewHit::Fold(old_range.start)));
+            }
+            Row::HunkHeader(hunk_index) => {
+                self.draw_line_bg.color = self.diff_colors.hunk_header;
+                self.draw_line_bg.draw_abs(
+                    cx,
+                    Rect {
+                        pos: dvec2(origin_x, y),
+                        size: dvec2(row_width, self.cell_size.y),
+                    },
+                );
+                let hunk = &self.diff.hunks()[hunk_index];
+                let mut label = String::new();
+                let _ = write!(
+                    label,
+                    "@@ -{},{} +{},{} @@",
+                    hunk.old_range.start + 1,
+                    hunk.old_range.len(),
+                    hunk.new_range.start + 1,
+                    hunk.new_range.len()
+                );
+                let mut x = origin_x + panes[0].gutter_x;
+                self.draw_header.draw_abs(cx, dvec2(x, y), &label);
+                x += (label.column_count() + 2) as f64 * self.cell_size.x;
+                for (text, hit) in [
+                    ("Accept", DiffViewHit::Accept(hunk_index)),
+                    ("Revert", DiffViewHit::Revert(hunk_index)),
+                ] {
+                    self.draw_action.draw_abs(cx, dvec2(x, y), text);
+                    let width = text.column_count() as f64 * self.cell_size.x;
+                    self.hits.push((
+                        Rect {
+                            pos: dvec2(x, y),
+                            size: dvec2(width, self.cell_size.y),
+                        },
+                        hit,
+                    ));
+                    x += width + 2.0 * self.cell_size.x;
+                }
+            }
+            Row::Line {
+                old_line,
+                new_line,
+                hunk_index,
+            } => match self.mode {
+                DiffMode::SideBySide => {
+                    for (pane, side, line) in [
+                        (panes[0], Side::Old, old_line),
+                        (panes[1], Side::New, new_line),
+                    ] {
+                        let pane_rect = Rect {
+                            pos: dvec2(origin_x + pane.gutter_x - PADDING, y),
+                            size: dvec2(pane.right_x - pane.gutter_x + PADDING, self.cell_size.y),
+                        };
+                        match (line, hunk_index) {
+                            (None, _) => {
+                                self.draw_line_bg.color = self.diff_colors.filler;
+                                self.draw_line_bg.draw_abs(cx, pane_rect);
+                            }
+                            (Some(line), hunk_index) => {
+                                if hunk_index.is_some() {
+                                    self.draw_line_bg.color = self.line_color(side);
+                                    self.draw_line_bg.draw_abs(cx, pane_rect);
+                                }
+                                self.draw_line_number(cx, dvec2(origin_x + pane.gutter_x, y), line);
+                                let session = match side {
+                                    Side::Old => old,
+                                    Side::New => new,
+                                };
+                                let clip_rect = pane.text_rect(origin, self.cell_size.y);
+                                self.draw_line(cx, session, side, line, hunk_index, clip_rect);
+                            }
+                        }
+                    }
+                }
+                DiffMode::Inline => {
+                    let pane = panes[0];
+                    let number_width = self.line_number_column_count() as f64 * self.cell_size.x;
+                    let (session, side, line, marker) = match (old_line, new_line, hunk_index) {
+                        (Some(line), _, None) => (new, Side::New, new_line.unwrap_or(line), " "),
+                        (Some(line), None, Some(_)) => (old, Side::Old, line, "-"),
+                        (_, Some(line), _) => (new, Side::New, line, "+"),
+                        (None, None, _) => return,
+                    };
+                    if hunk_index.is_some() {
+                        self.draw_line_bg.color = self.line_color(side);
+                        self.draw_line_bg.draw_abs(
+                            cx,
+                            Rect {
+                                pos: dvec2(origin_x, y),
+                                size: dvec2(row_width, self.cell_size.y),
+                            },
+                        );
+                    }
+                    let gutter_x = origin_x + pane.gutter_x;
+                    if let Some(old_line) = old_line {
+                        self.draw_line_number(cx, dvec2(gutter_x, y), old_line);
+                    }
+                    if let Some(new_line) = new_line {
+                        self.draw_line_number(
+                            cx,
+                            dvec2(gutter_x + number_width + self.cell_size.x, y),
+                            new_line,
+                        );
+                    }
+                    self.draw_gutter.draw_abs(
+                        cx,
+                        dvec2(gutter_x + 2.0 * (number_width + self.cell_size.x), y),
+                        marker,
+                    );
+                    let clip_rect = pane.text_rect(origin, self.cell_size.y);
+                    self.draw_line(cx, session, side, line, hunk_index, clip_rect);
+                }
+            },
+        }
+    }
+
+    fn line_color(&self, side: Side) -> Vec4 {
+        match side {
+            Side::Old => self.diff_colors.deleted_line,
+            Side::New => self.diff_colors.inserted_line,
+        }
+    }
+
+    fn draw_line_number(&mut self, cx: &mut Cx2d, pos: DVec2, line: usize) {
+        let mut buf = String::new();
+        let _ = write!(
+            buf,
+            "{: >width$}",
+            line + 1,
+            width = self.line_number_column_count()
+        );
+        self.draw_gutter.draw_abs(cx, pos, &buf);
+    }
+
+    fn draw_line(
+        &mut self,
+        cx: &mut Cx2d,
+        session: &Session,
+        side: Side,
+        line_index: usize,
+        hunk_index: Option<usize>,
+        clip_rect: Rect,
+    ) {
+        let document_text = session.document().as_text();
+        let line = &document_text.as_lines()[line_index];
+        let clip_start = clip_rect.pos.x;
+        let clip_end = clip_rect.pos.x + clip_rect.size.x;
+        let text_x = clip_start - self.scroll_bars.get_scroll_pos().x;
+        let y = clip_rect.pos.y;
+
+        if let Some(hunk_index) = hunk_index {
+            let hunk = &self.diff.hunks()[hunk_index];
+            let (char_ranges, color) = match side {
+                Side::Old => (
+                    &hunk.old_char_ranges[line_index - hunk.old_range.start],
+                    self.diff_colors.deleted_chars,
+                ),
+                Side::New => (
+                    &hunk.new_char_ranges[line_index - hunk.new_range.start],
+                    self.diff_colors.inserted_chars,
+                ),
+            };
+            self.draw_line_bg.color = color;
+            for range in char_ranges {
+                let start_x = (text_x
+                    + line[..range.start].column_count() as f64 * self.cell_size.x)
+                    .max(clip_start);
+                let end_x = (text_x + line[..range.end].column_count() as f64 * self.cell_size.x)
+                    .min(clip_end);
+                if start_x < end_x {
+                    self.draw_line_bg.draw_abs(
+                        cx,
+                        Rect {
+                            pos: dvec2(start_x, y),
+                            size: dvec2(end_x - start_x, self.cell_size.y),
+                        },
+                    );
+                }
+            }
+        }
+
+        let layout = session.document().layout();
+        let mut tokens = layout.tokens[line_index].iter().copied();
+        let mut token_slot = tokens.next();
+        let mut column_index = 0;
+        let mut text = line.as_str();
+        while !text.is_empty() {
+            let token = match token_slot {
+                Some(token) if token.len <= text.len() => {
+                    token_slot = tokens.next();
+                    token
+                }
+                _ => Token {
+                    len: text.len(),
+                    kind: TokenKind::Unknown,
+                },
+            };
+            let (text_0, text_1) = text.split_at(token.len);
+            text = text_1;
+            self.draw_text.color = self.token_colors.color(token.kind);
+            for grapheme in text_0.graphemes() {
+                let x = text_x + column_index as f64 * self.cell_size.x;
+                column_index += grapheme.column_count();
+                if x < clip_start {
+                    continue;
+                }
+                if x + self.cell_size.x > clip_end {
+                    return;
+                }
+                self.draw_text.draw_abs(cx, dvec2(x, y), grapheme);
+            }
+        }
+    }
+}
+
+fn lines_in_range(text: &Text, range: Range<usize>) -> Vec<String> {
+    text.as_lines().range(range).cloned().collect()
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+struct DiffColors {
+    #[live]
+    inserted_line: Vec4,
+    #[live]
+    deleted_line: Vec4,
+    #[live]
+    inserted_chars: Vec4,
+    #[live]
+    deleted_chars: Vec4,
+    #[live]
+    filler: Vec4,
+    #[live]
+    hunk_header: Vec4,
+    #[live]
+    fold: Vec4,
+}
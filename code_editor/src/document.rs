@@ -1,10 +1,14 @@
 use {
     crate::{
         char::CharExt,
+        collab::{RemoteSelection, SiteId},
         decoration::{Decoration, DecorationSet},
         history::{EditKind, History},
         inlays::{BlockInlay, InlineInlay},
         iter::IteratorExt,
+        language::Language,
+        languages::Rust,
+        rope::{Rope, RopeLine},
         selection::SelectionSet,
         session::SessionId,
         settings::Settings,
@@ -17,21 +21,42 @@ use {
         cell::{Ref, RefCell},
         cmp::Ordering,
         collections::HashMap,
-        iter,
+        iter, mem,
         ops::Range,
         rc::Rc,
         sync::mpsc::Sender,
     },
 };
 
+/// Documents larger than this many bytes are opened in large-file mode, in which they are not
+/// tokenized by their language and cannot be folded.
+pub const LARGE_FILE_BYTE_COUNT: usize = 8 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub struct Document(Rc<DocumentInner>);
 
 impl Document {
     pub fn new(text: Text, decorations: DecorationSet) -> Self {
+        Self::new_with_language(text, decorations, &Rust)
+    }
+
+    pub fn new_with_language(
+        text: Text,
+        decorations: DecorationSet,
+        language: &'static dyn Language,
+    ) -> Self {
         let line_count = text.as_lines().len();
-        let tokens: Vec<_> = (0..line_count)
-            .map(|line| tokenize(&text.as_lines()[line]).collect::<Vec<_>>())
+        let is_large_file = text.byte_count() > LARGE_FILE_BYTE_COUNT;
+        let tokens: Rope<_> = text
+            .as_lines()
+            .iter()
+            .map(|line| {
+                if is_large_file {
+                    tokenize_as_single_token(line)
+                } else {
+                    tokenize(line).collect::<Vec<_>>()
+                }
+            })
             .collect();
         let inner = Self(Rc::new(DocumentInner {
             history: RefCell::new(History::from(text)),
@@ -41,50 +66,69 @@ impl Document {
                 inline_inlays: (0..line_count).map(|_| Vec::new()).collect(),
                 block_inlays: Vec::new(),
             }),
-            tokenizer: RefCell::new(Tokenizer::new(line_count)),
+            tokenizer: RefCell::new(Tokenizer::new(language, line_count)),
             decorations: RefCell::new(decorations),
             edit_senders: RefCell::new(HashMap::new()),
+            local_edits: RefCell::new(None),
+            remote_selections: RefCell::new(Vec::new()),
+            is_large_file,
         }));
         inner.update_indent_state();
-        inner.0.tokenizer.borrow_mut().update(
-            &inner.0.history.borrow().as_text(),
-            &mut inner.0.layout.borrow_mut().tokens,
-        );
+        if !is_large_file {
+            inner.0.tokenizer.borrow_mut().update(
+                inner.0.history.borrow().as_text(),
+                &mut inner.0.layout.borrow_mut().tokens,
+            );
+        }
         inner
     }
-    
+
+    pub fn is_large_file(&self) -> bool {
+        self.0.is_large_file
+    }
+
+    pub fn language(&self) -> &'static dyn Language {
+        self.0.tokenizer.borrow().language()
+    }
+
     pub fn replace(&self, origin_id: SessionId, new_text: Text) {
         let mut history = self.0.history.borrow_mut();
-        
+
         // Create an edit that deletes the entire existing text.
         let text_length = history.as_text().length();
         let delete_edit = Edit {
             change: Change::Delete(Position::zero(), text_length),
             drift: Drift::Before,
         };
-        
+
         // Create an edit that inserts the new text at position zero.
         let insert_edit = Edit {
             change: Change::Insert(Position::zero(), new_text),
             drift: Drift::Before,
         };
-        
+
         // Apply the edits to history, starting a new group for undo.
         history.force_new_group(); // Start a new undo group.
         history.apply_edit(delete_edit.clone());
         history.apply_edit(insert_edit.clone());
-        
+
         drop(history);
-        
+
         // Now, update the document state after the edits.
         let edits = vec![delete_edit, insert_edit];
-        self.update_after_edit(origin_id, None, &edits);
+        self.update_after_edit(Some(origin_id), None, &edits);
     }
 
     pub fn as_text(&self) -> Ref<'_, Text> {
         Ref::map(self.0.history.borrow(), |history| history.as_text())
     }
 
+    /// Identifies the current text of the document, to tell whether anything derived from it is
+    /// out of date without comparing texts.
+    pub fn revision(&self) -> u64 {
+        self.0.history.borrow().revision()
+    }
+
     pub fn layout(&self) -> Ref<'_, DocumentLayout> {
         self.0.layout.borrow()
     }
@@ -118,7 +162,7 @@ impl Document {
             let edit_start = edits.len();
             f(
                 Editor {
-                    history: &mut *history,
+                    history: &mut history,
                     edits: &mut edits,
                 },
                 adjusted_start,
@@ -163,7 +207,7 @@ impl Document {
         }
         drop(history);
         self.autoindent(&line_ranges, settings.tab_column_count, &mut edits);
-        self.update_after_edit(session_id, None, &edits);
+        self.update_after_edit(Some(session_id), None, &edits);
     }
 
     pub fn edit_linewise(
@@ -191,7 +235,7 @@ impl Document {
             for line_index in line_range {
                 f(
                     Editor {
-                        history: &mut *history,
+                        history: &mut history,
                         edits: &mut edits,
                     },
                     line_index,
@@ -199,7 +243,26 @@ impl Document {
             }
         }
         drop(history);
-        self.update_after_edit(origin_id, None, &edits);
+        self.update_after_edit(Some(origin_id), None, &edits);
+    }
+
+    /// Applies the edits made by `f` as is, without autoindenting them.
+    pub fn edit(
+        &self,
+        origin_id: SessionId,
+        kind: EditKind,
+        selections: &SelectionSet,
+        f: impl FnOnce(Editor<'_>),
+    ) {
+        let mut history = self.0.history.borrow_mut();
+        history.push_or_extend_group(origin_id, kind, selections);
+        let mut edits = Vec::new();
+        f(Editor {
+            history: &mut history,
+            edits: &mut edits,
+        });
+        drop(history);
+        self.update_after_edit(Some(origin_id), None, &edits);
     }
 
     pub fn add_decoration(&mut self, decoration: Decoration) {
@@ -210,6 +273,56 @@ impl Document {
         self.0.decorations.borrow_mut().clear()
     }
 
+    /// Starts recording the edits made in the sessions of this document, so that they can be
+    /// sent to the other peers editing it.
+    pub fn start_sharing(&self) {
+        *self.0.local_edits.borrow_mut() = Some(Vec::new());
+    }
+
+    pub fn stop_sharing(&self) {
+        *self.0.local_edits.borrow_mut() = None;
+        self.0.remote_selections.borrow_mut().clear();
+    }
+
+    /// Returns the edits made in the sessions of this document since the last call.
+    pub fn take_local_edits(&self) -> Vec<Edit> {
+        self.0
+            .local_edits
+            .borrow_mut()
+            .as_mut()
+            .map_or_else(Vec::new, mem::take)
+    }
+
+    /// Applies edits made by another peer. They are not added to the undo history, and they are
+    /// not recorded as local edits.
+    pub fn apply_remote_edits(&self, edits: &[Edit]) {
+        let mut history = self.0.history.borrow_mut();
+        for edit in edits {
+            history.apply_remote_edit(edit.clone());
+        }
+        drop(history);
+        self.update_after_edit(None, None, edits);
+    }
+
+    pub fn remote_selections(&self) -> Ref<'_, [RemoteSelection]> {
+        Ref::map(self.0.remote_selections.borrow(), |remote_selections| {
+            remote_selections.as_slice()
+        })
+    }
+
+    pub fn set_remote_selections(&self, site_id: SiteId, selections: Vec<RemoteSelection>) {
+        let mut remote_selections = self.0.remote_selections.borrow_mut();
+        remote_selections.retain(|selection| selection.site_id != site_id);
+        remote_selections.extend(selections);
+    }
+
+    pub fn remove_remote_selections(&self, site_id: SiteId) {
+        self.0
+            .remote_selections
+            .borrow_mut()
+            .retain(|selection| selection.site_id != site_id);
+    }
+
     pub fn add_session(
         &mut self,
         session_id: SessionId,
@@ -267,12 +380,14 @@ impl Document {
                 }
             })
         {
-            let mut desired_indentation_column_count = self.as_text().as_lines()
-                [..line_range.start]
-                .iter()
+            let text = self.as_text();
+            let mut desired_indentation_column_count = (0..line_range.start)
                 .rev()
-                .find_map(|line| next_line_indent_column_count(line, indent_column_count))
+                .find_map(|line| {
+                    next_line_indent_column_count(&text.as_lines()[line], indent_column_count)
+                })
                 .unwrap_or(0);
+            drop(text);
             for line in line_range {
                 if self.as_text().as_lines()[line]
                     .chars()
@@ -287,7 +402,8 @@ impl Document {
                     })
                     .unwrap_or(false)
                 {
-                    desired_indentation_column_count -= 4;
+                    desired_indentation_column_count =
+                        desired_indentation_column_count.saturating_sub(4);
                 }
                 self.edit_lines_internal(line, edits, |line| {
                     crate::session::reindent(line, |_| desired_indentation_column_count)
@@ -351,7 +467,7 @@ impl Document {
         let mut changes = Vec::new();
         let selections = self.0.history.borrow_mut().undo(selections, &mut changes);
         if let Some(selections) = selections {
-            self.update_after_edit(origin_id, Some(selections), &changes);
+            self.update_after_edit(Some(origin_id), Some(selections), &changes);
             true
         } else {
             false
@@ -362,7 +478,7 @@ impl Document {
         let mut changes = Vec::new();
         let selections = self.0.history.borrow_mut().redo(selections, &mut changes);
         if let Some(selections) = selections {
-            self.update_after_edit(origin_id, Some(selections), &changes);
+            self.update_after_edit(Some(origin_id), Some(selections), &changes);
             true
         } else {
             false
@@ -371,7 +487,7 @@ impl Document {
 
     fn update_after_edit(
         &self,
-        origin_id: SessionId,
+        origin_id: Option<SessionId>,
         selections: Option<SelectionSet>,
         edits: &[Edit],
     ) {
@@ -379,7 +495,9 @@ impl Document {
         for edit in edits {
             match edit.change {
                 Change::Insert(position, ref text) => {
-                    layout.indent_state[position.line_index] = None;
+                    layout
+                        .indent_state
+                        .update_line(position.line_index, |indent_state| *indent_state = None);
                     let line_count = text.length().line_count;
                     if line_count > 0 {
                         let line_index = position.line_index + 1;
@@ -389,11 +507,15 @@ impl Document {
                     }
                 }
                 Change::Delete(start, length) => {
-                    layout.indent_state[start.line_index] = None;
+                    layout
+                        .indent_state
+                        .update_line(start.line_index, |indent_state| *indent_state = None);
                     if length.line_count > 0 {
                         let line_start = start.line_index + 1;
                         let line_end = line_start + length.line_count;
-                        layout.indent_state.drain(line_start..line_end);
+                        layout
+                            .indent_state
+                            .splice(line_start..line_end, iter::empty());
                     }
                 }
             }
@@ -402,20 +524,34 @@ impl Document {
         for edit in edits {
             self.apply_change_to_tokens(&edit.change);
             self.apply_change_to_inline_inlays(&edit.change, edit.drift);
-            self.0.tokenizer.borrow_mut().apply_change(&edit.change);
+            if !self.0.is_large_file {
+                self.0.tokenizer.borrow_mut().apply_change(&edit.change);
+            }
         }
         self.update_indent_state();
-        self.0.tokenizer.borrow_mut().update(
-            self.0.history.borrow().as_text(),
-            &mut self.0.layout.borrow_mut().tokens,
-        );
+        if !self.0.is_large_file {
+            self.0.tokenizer.borrow_mut().update(
+                self.0.history.borrow().as_text(),
+                &mut self.0.layout.borrow_mut().tokens,
+            );
+        }
         let mut decorations = self.0.decorations.borrow_mut();
         for edit in edits {
             decorations.apply_edit(edit);
         }
         drop(decorations);
+        for remote_selection in self.0.remote_selections.borrow_mut().iter_mut() {
+            for edit in edits {
+                *remote_selection = remote_selection.apply_edit(edit);
+            }
+        }
+        if origin_id.is_some() {
+            if let Some(local_edits) = self.0.local_edits.borrow_mut().as_mut() {
+                local_edits.extend(edits.iter().cloned());
+            }
+        }
         for (&session_id, edit_sender) in &*self.0.edit_senders.borrow() {
-            if session_id == origin_id {
+            if Some(session_id) == origin_id {
                 edit_sender
                     .send((selections.clone(), edits.to_vec()))
                     .unwrap();
@@ -442,117 +578,32 @@ impl Document {
         let tokens = &mut layout.tokens;
         match *change {
             Change::Insert(point, ref text) => {
-                let mut byte = 0;
-                let mut index = tokens[point.line_index]
+                let mut new_tokens = text
+                    .as_lines()
                     .iter()
-                    .position(|token| {
-                        if byte + token.len > point.byte_index {
-                            return true;
-                        }
-                        byte += token.len;
-                        false
-                    })
-                    .unwrap_or(tokens[point.line_index].len());
-                if byte != point.byte_index {
-                    let token = tokens[point.line_index][index];
-                    let mid = point.byte_index - byte;
-                    tokens[point.line_index][index] = Token {
-                        len: mid,
-                        kind: token.kind,
-                    };
-                    index += 1;
-                    tokens[point.line_index].insert(
-                        index,
-                        Token {
-                            len: token.len - mid,
-                            kind: token.kind,
-                        },
-                    );
-                }
-                if text.length().line_count == 0 {
-                    tokens[point.line_index]
-                        .splice(index..index, tokenize(text.as_lines().first().unwrap()));
-                } else {
-                    let mut new_tokens = (0..text.as_lines().len())
-                        .map(|line| tokenize(&text.as_lines()[line]).collect::<Vec<_>>())
-                        .collect::<Vec<_>>();
-                    new_tokens
-                        .first_mut()
-                        .unwrap()
-                        .splice(..0, tokens[point.line_index][..index].iter().copied());
-                    new_tokens
-                        .last_mut()
-                        .unwrap()
-                        .splice(..0, tokens[point.line_index][index..].iter().copied());
-                    tokens.splice(point.line_index..point.line_index + 1, new_tokens);
-                }
+                    .map(|line| tokenize(line).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                let mut line_tokens = tokens[point.line_index].clone();
+                let index = split_tokens_at(&mut line_tokens, point.byte_index);
+                let tail_tokens = line_tokens.split_off(index);
+                new_tokens.first_mut().unwrap().splice(..0, line_tokens);
+                new_tokens.last_mut().unwrap().extend(tail_tokens);
+                tokens.splice(point.line_index..point.line_index + 1, new_tokens);
             }
             Change::Delete(start, length) => {
                 let end = start + length;
-                let mut byte = 0;
-                let mut start_token = tokens[start.line_index]
-                    .iter()
-                    .position(|token| {
-                        if byte + token.len > start.byte_index {
-                            return true;
-                        }
-                        byte += token.len;
-                        false
-                    })
-                    .unwrap_or(tokens[start.line_index].len());
-                if byte != start.byte_index {
-                    let token = tokens[start.line_index][start_token];
-                    let mid = start.byte_index - byte;
-                    tokens[start.line_index][start_token] = Token {
-                        len: mid,
-                        kind: token.kind,
-                    };
-                    start_token += 1;
-                    tokens[start.line_index].insert(
-                        start_token,
-                        Token {
-                            len: token.len - mid,
-                            kind: token.kind,
-                        },
-                    );
-                }
-                let mut byte = 0;
-                let mut end_token = tokens[end.line_index]
-                    .iter()
-                    .position(|token| {
-                        if byte + token.len > end.byte_index {
-                            return true;
-                        }
-                        byte += token.len;
-                        false
-                    })
-                    .unwrap_or(tokens[end.line_index].len());
-                if byte != end.byte_index {
-                    let token = tokens[end.line_index][end_token];
-                    let mid = end.byte_index - byte;
-                    tokens[end.line_index][end_token] = Token {
-                        len: mid,
-                        kind: token.kind,
-                    };
-                    end_token += 1;
-                    tokens[end.line_index].insert(
-                        end_token,
-                        Token {
-                            len: token.len - mid,
-                            kind: token.kind,
-                        },
-                    );
-                }
+                let mut new_tokens = tokens[start.line_index].clone();
+                let start_index = split_tokens_at(&mut new_tokens, start.byte_index);
                 if length.line_count == 0 {
-                    tokens[start.line_index].drain(start_token..end_token);
+                    let end_index = split_tokens_at(&mut new_tokens, end.byte_index);
+                    new_tokens.drain(start_index..end_index);
                 } else {
-                    let mut new_tokens = tokens[start.line_index][..start_token]
-                        .iter()
-                        .copied()
-                        .collect::<Vec<_>>();
-                    new_tokens.extend(tokens[end.line_index][end_token..].iter().copied());
-                    tokens.splice(start.line_index..end.line_index + 1, iter::once(new_tokens));
+                    new_tokens.truncate(start_index);
+                    let mut end_tokens = tokens[end.line_index].clone();
+                    let end_index = split_tokens_at(&mut end_tokens, end.byte_index);
+                    new_tokens.extend(end_tokens.drain(end_index..));
                 }
+                tokens.splice(start.line_index..end.line_index + 1, iter::once(new_tokens));
             }
         }
     }
@@ -562,7 +613,8 @@ impl Document {
         let inline_inlays = &mut layout.inline_inlays;
         match *change {
             Change::Insert(point, ref text) => {
-                let index = inline_inlays[point.line_index]
+                let mut line_inline_inlays = inline_inlays[point.line_index].clone();
+                let index = line_inline_inlays
                     .iter()
                     .position(|(byte, _)| match byte.cmp(&point.byte_index) {
                         Ordering::Less => false,
@@ -572,62 +624,66 @@ impl Document {
                         },
                         Ordering::Greater => true,
                     })
-                    .unwrap_or(inline_inlays[point.line_index].len());
+                    .unwrap_or(line_inline_inlays.len());
                 if text.length().line_count == 0 {
-                    for (byte, _) in &mut inline_inlays[point.line_index][index..] {
+                    for (byte, _) in &mut line_inline_inlays[index..] {
                         *byte += text.length().byte_count;
                     }
+                    inline_inlays.update_line(point.line_index, |inline_inlays| {
+                        *inline_inlays = line_inline_inlays
+                    });
                 } else {
                     let mut new_inline_inlays = (0..text.as_lines().len())
                         .map(|_| Vec::new())
                         .collect::<Vec<_>>();
-                    new_inline_inlays
-                        .first_mut()
-                        .unwrap()
-                        .splice(..0, inline_inlays[point.line_index].drain(..index));
-                    new_inline_inlays.last_mut().unwrap().splice(
-                        ..0,
-                        inline_inlays[point.line_index]
-                            .drain(..)
+                    // The inlays after the insertion end up on its last line, after the inserted
+                    // text.
+                    new_inline_inlays.last_mut().unwrap().extend(
+                        line_inline_inlays
+                            .drain(index..)
                             .map(|(byte, inline_inlay)| {
-                                (byte + text.length().byte_count, inline_inlay)
+                                (
+                                    byte - point.byte_index + text.length().byte_count,
+                                    inline_inlay,
+                                )
                             }),
                     );
+                    new_inline_inlays
+                        .first_mut()
+                        .unwrap()
+                        .splice(..0, line_inline_inlays);
                     inline_inlays.splice(point.line_index..point.line_index + 1, new_inline_inlays);
                 }
             }
             Change::Delete(start, length) => {
                 let end = start + length;
-                let start_inlay = inline_inlays[start.line_index]
+                let mut new_inline_inlays = inline_inlays[start.line_index].clone();
+                let start_inlay = new_inline_inlays
                     .iter()
                     .position(|&(byte, _)| byte >= start.byte_index)
-                    .unwrap_or(inline_inlays[start.line_index].len());
-                let end_inlay = inline_inlays[end.line_index]
+                    .unwrap_or(new_inline_inlays.len());
+                let mut end_inline_inlays = if length.line_count == 0 {
+                    new_inline_inlays.split_off(start_inlay)
+                } else {
+                    new_inline_inlays.truncate(start_inlay);
+                    inline_inlays[end.line_index].clone()
+                };
+                let end_inlay = end_inline_inlays
                     .iter()
                     .position(|&(byte, _)| byte >= end.byte_index)
-                    .unwrap_or(inline_inlays[end.line_index].len());
-                if length.line_count == 0 {
-                    inline_inlays[start.line_index].drain(start_inlay..end_inlay);
-                    for (byte, _) in &mut inline_inlays[start.line_index][start_inlay..] {
-                        *byte = start.byte_index + (*byte - end.byte_index.min(*byte));
-                    }
-                } else {
-                    let mut new_inline_inlays = inline_inlays[start.line_index]
-                        .drain(..start_inlay)
-                        .collect::<Vec<_>>();
-                    new_inline_inlays.extend(inline_inlays[end.line_index].drain(end_inlay..).map(
-                        |(byte, inline_inlay)| {
-                            (
-                                start.byte_index + byte - end.byte_index.min(byte),
-                                inline_inlay,
-                            )
-                        },
-                    ));
-                    inline_inlays.splice(
-                        start.line_index..end.line_index + 1,
-                        iter::once(new_inline_inlays),
-                    );
-                }
+                    .unwrap_or(end_inline_inlays.len());
+                new_inline_inlays.extend(end_inline_inlays.drain(end_inlay..).map(
+                    |(byte, inline_inlay)| {
+                        (
+                            start.byte_index + byte - end.byte_index.min(byte),
+                            inline_inlay,
+                        )
+                    },
+                ));
+                inline_inlays.splice(
+                    start.line_index..end.line_index + 1,
+                    iter::once(new_inline_inlays),
+                );
             }
         }
     }
@@ -638,51 +694,73 @@ impl Document {
         let history = self.0.history.borrow();
         let lines = history.as_text().as_lines();
         let mut current_indent_column_count = 0;
-        for line_index in 0..lines.len() {
-            match indent_state[line_index] {
+        let mut changed_lines = Vec::new();
+        for (line_index, (line, &line_indent_state)) in
+            lines.iter().zip(indent_state.iter()).enumerate()
+        {
+            let new_indent_state = match line_indent_state {
                 Some(IndentState::NonEmpty(_, next_indent_column_count)) => {
                     current_indent_column_count = next_indent_column_count;
+                    continue;
                 }
-                _ => {
-                    indent_state[line_index] = Some(match lines[line_index].indent() {
-                        Some(indent) => {
-                            let indent_column_count = indent.column_count();
-                            let mut next_indent_column_count = indent_column_count;
-                            if lines[line_index]
-                                .chars()
-                                .rev()
-                                .find_map(|char| {
-                                    if char.is_opening_delimiter() {
-                                        return Some(true);
-                                    }
-                                    if char.is_closing_delimiter() {
-                                        return Some(false);
-                                    }
-                                    None
-                                })
-                                .unwrap_or(false)
-                            {
-                                next_indent_column_count += 4;
-                            }
-                            current_indent_column_count = next_indent_column_count;
-                            IndentState::NonEmpty(indent_column_count, next_indent_column_count)
+                _ => match line.indent() {
+                    Some(indent) => {
+                        let indent_column_count = indent.column_count();
+                        let mut next_indent_column_count = indent_column_count;
+                        if line
+                            .chars()
+                            .rev()
+                            .find_map(|char| {
+                                if char.is_opening_delimiter() {
+                                    return Some(true);
+                                }
+                                if char.is_closing_delimiter() {
+                                    return Some(false);
+                                }
+                                None
+                            })
+                            .unwrap_or(false)
+                        {
+                            next_indent_column_count += 4;
                         }
-                        None => IndentState::Empty(current_indent_column_count),
-                    })
-                }
+                        current_indent_column_count = next_indent_column_count;
+                        IndentState::NonEmpty(indent_column_count, next_indent_column_count)
+                    }
+                    None => IndentState::Empty(current_indent_column_count),
+                },
+            };
+            if line_indent_state != Some(new_indent_state) {
+                changed_lines.push((line_index, new_indent_state));
             }
         }
+        // Only the lines whose state changed are written back, since updating a line of a rope
+        // copies the nodes along its path.
+        for (line_index, new_indent_state) in changed_lines {
+            indent_state.update_line(line_index, |indent_state| {
+                *indent_state = Some(new_indent_state)
+            });
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct DocumentLayout {
-    pub indent_state: Vec<Option<IndentState>>,
-    pub tokens: Vec<Vec<Token>>,
-    pub inline_inlays: Vec<Vec<(usize, InlineInlay)>>,
+    pub indent_state: Rope<Option<IndentState>>,
+    pub tokens: Rope<Vec<Token>>,
+    pub inline_inlays: Rope<Vec<(usize, InlineInlay)>>,
     pub block_inlays: Vec<(usize, BlockInlay)>,
 }
 
+impl RopeLine for Option<IndentState> {}
+
+impl RopeLine for Vec<Token> {
+    fn byte_count(&self) -> usize {
+        self.iter().map(|token| token.len).sum()
+    }
+}
+
+impl RopeLine for Vec<(usize, InlineInlay)> {}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum IndentState {
     Empty(usize),
@@ -713,6 +791,20 @@ struct DocumentInner {
     tokenizer: RefCell<Tokenizer>,
     decorations: RefCell<DecorationSet>,
     edit_senders: RefCell<HashMap<SessionId, Sender<(Option<SelectionSet>, Vec<Edit>)>>>,
+    // The local edits not yet sent to the other peers, if the document is shared.
+    local_edits: RefCell<Option<Vec<Edit>>>,
+    remote_selections: RefCell<Vec<RemoteSelection>>,
+    is_large_file: bool,
+}
+
+fn tokenize_as_single_token(text: &str) -> Vec<Token> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    vec![Token {
+        len: text.len(),
+        kind: TokenKind::Unknown,
+    }]
 }
 
 fn tokenize(text: &str) -> impl Iterator<Item = Token> + '_ {
@@ -725,3 +817,36 @@ fn tokenize(text: &str) -> impl Iterator<Item = Token> + '_ {
         },
     })
 }
+
+/// Splits the token that contains `byte_index`, if any, so that a token starts there, and returns
+/// the index of that token.
+fn split_tokens_at(tokens: &mut Vec<Token>, byte_index: usize) -> usize {
+    let mut byte = 0;
+    let index = tokens
+        .iter()
+        .position(|token| {
+            if byte + token.len > byte_index {
+                return true;
+            }
+            byte += token.len;
+            false
+        })
+        .unwrap_or(tokens.len());
+    if byte == byte_index {
+        return index;
+    }
+    let token = tokens[index];
+    let mid = byte_index - byte;
+    tokens[index] = Token {
+        len: mid,
+        kind: token.kind,
+    };
+    tokens.insert(
+        index + 1,
+        Token {
+            len: token.len - mid,
+            kind: token.kind,
+        },
+    );
+    index + 1
+}
@@ -1,12 +1,25 @@
-use crate::{
-    selection::SelectionSet,
-    session::SessionId,
-    text::{Edit, Text},
+use {
+    crate::{
+        collab::transform,
+        selection::SelectionSet,
+        session::SessionId,
+        text::{Edit, Text},
+    },
+    std::sync::atomic::{AtomicU64, Ordering},
 };
 
+/// The last revision handed out, shared by all histories so that a revision also identifies the
+/// history it came from.
+static LAST_REVISION: AtomicU64 = AtomicU64::new(0);
+
+fn next_revision() -> u64 {
+    LAST_REVISION.fetch_add(1, Ordering::Relaxed) + 1
+}
+
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub struct History {
     text: Text,
+    revision: u64,
     current_desc: Option<GroupDesc>,
     undo_stack: Stack,
     redo_stack: Stack,
@@ -27,6 +40,12 @@ impl History {
         &self.text
     }
 
+    /// Changes whenever the text does. Two histories only have the same revision if they are
+    /// clones with the same text.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     pub fn force_new_group(&mut self) {
         self.current_desc = None;
     }
@@ -53,10 +72,22 @@ impl History {
     pub fn apply_edit(&mut self, edit: Edit) {
         let inverted_edit = edit.clone().invert(&self.text);
         self.text.apply_change(edit.change);
+        self.revision = next_revision();
         self.undo_stack.push_edit(inverted_edit);
         self.redo_stack.clear();
     }
 
+    /// Applies an edit made by another user. It is not added to the history, but the edits on the
+    /// undo and redo stacks are transformed against it, so that undo and redo only revert the
+    /// edits of this user.
+    pub fn apply_remote_edit(&mut self, edit: Edit) {
+        self.undo_stack.transform(&edit);
+        self.redo_stack.transform(&edit);
+        self.text.apply_change(edit.change);
+        self.revision = next_revision();
+        self.current_desc = None;
+    }
+
     pub fn undo(
         &mut self,
         selections: &SelectionSet,
@@ -69,6 +100,7 @@ impl History {
                 self.text.apply_change(edit.change.clone());
                 self.redo_stack.push_edit(inverted_edit);
             }
+            self.revision = next_revision();
             self.current_desc = None;
             Some(new_selections)
         } else {
@@ -88,6 +120,7 @@ impl History {
                 self.text.apply_change(edit.change.clone());
                 self.undo_stack.push_edit(inverted_edit);
             }
+            self.revision = next_revision();
             self.current_desc = None;
             Some(new_selections)
         } else {
@@ -104,6 +137,7 @@ impl From<Text> for History {
     fn from(text: Text) -> Self {
         Self {
             text,
+            revision: next_revision(),
             ..Self::default()
         }
     }
@@ -169,6 +203,34 @@ impl Stack {
         }
     }
 
+    // Transforms the groups on the stack against `edit`, which was made on the text the top group
+    // applies to.
+    fn transform(&mut self, edit: &Edit) {
+        let mut other = vec![edit.clone()];
+        let mut edit_end = self.edits.len();
+        let mut group_edits = Vec::with_capacity(self.groups.len());
+        for group in self.groups.iter_mut().rev() {
+            // The edits of a group are applied in reverse order.
+            let edits: Vec<_> = self.edits[group.edit_start..edit_end]
+                .iter()
+                .rev()
+                .cloned()
+                .collect();
+            edit_end = group.edit_start;
+            let (edits, next_other) = transform(&edits, &other, false);
+            other = next_other;
+            for edit in &other {
+                group.selections.apply_edit(edit, None);
+            }
+            group_edits.push(edits);
+        }
+        self.edits.clear();
+        for (group, edits) in self.groups.iter_mut().zip(group_edits.into_iter().rev()) {
+            group.edit_start = self.edits.len();
+            self.edits.extend(edits.into_iter().rev());
+        }
+    }
+
     fn clear(&mut self) {
         self.groups.clear();
         self.edits.clear();
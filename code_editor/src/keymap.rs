@@ -0,0 +1,486 @@
+use {
+    makepad_widgets::{KeyCode, KeyEvent, KeyModifiers},
+    std::fmt,
+};
+
+/// A named editor command that a key sequence can be bound to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Command {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    MoveToLineStart,
+    MoveToLineEnd,
+    MoveToFileStart,
+    MoveToFileEnd,
+    MovePageUp,
+    MovePageDown,
+    SelectLeft,
+    SelectRight,
+    SelectUp,
+    SelectDown,
+    SelectToLineStart,
+    SelectToLineEnd,
+    SelectToFileStart,
+    SelectToFileEnd,
+    SelectPageUp,
+    SelectPageDown,
+    SelectAll,
+    SelectNextOccurrence,
+    SelectAllOccurrences,
+    SplitSelectionIntoLines,
+    AddCursorAbove,
+    AddCursorBelow,
+    Enter,
+    Indent,
+    Outdent,
+    Delete,
+    Backspace,
+    Undo,
+    Redo,
+    Find,
+    Replace,
+    FindNext,
+    FindPrev,
+    IncreaseFontSize,
+    DecreaseFontSize,
+    ResetFontSize,
+    ToggleWordWrap,
+    ToggleMinimap,
+    ToggleStickyScroll,
+}
+
+const COMMAND_NAMES: &[(&str, Command)] = &[
+    ("move_left", Command::MoveLeft),
+    ("move_right", Command::MoveRight),
+    ("move_up", Command::MoveUp),
+    ("move_down", Command::MoveDown),
+    ("move_to_line_start", Command::MoveToLineStart),
+    ("move_to_line_end", Command::MoveToLineEnd),
+    ("move_to_file_start", Command::MoveToFileStart),
+    ("move_to_file_end", Command::MoveToFileEnd),
+    ("move_page_up", Command::MovePageUp),
+    ("move_page_down", Command::MovePageDown),
+    ("select_left", Command::SelectLeft),
+    ("select_right", Command::SelectRight),
+    ("select_up", Command::SelectUp),
+    ("select_down", Command::SelectDown),
+    ("select_to_line_start", Command::SelectToLineStart),
+    ("select_to_line_end", Command::SelectToLineEnd),
+    ("select_to_file_start", Command::SelectToFileStart),
+    ("select_to_file_end", Command::SelectToFileEnd),
+    ("select_page_up", Command::SelectPageUp),
+    ("select_page_down", Command::SelectPageDown),
+    ("select_all", Command::SelectAll),
+    ("select_next_occurrence", Command::SelectNextOccurrence),
+    ("select_all_occurrences", Command::SelectAllOccurrences),
+    ("split_selection_into_lines", Command::SplitSelectionIntoLines),
+    ("add_cursor_above", Command::AddCursorAbove),
+    ("add_cursor_below", Command::AddCursorBelow),
+    ("enter", Command::Enter),
+    ("indent", Command::Indent),
+    ("outdent", Command::Outdent),
+    ("delete", Command::Delete),
+    ("backspace", Command::Backspace),
+    ("undo", Command::Undo),
+    ("redo", Command::Redo),
+    ("find", Command::Find),
+    ("replace", Command::Replace),
+    ("find_next", Command::FindNext),
+    ("find_prev", Command::FindPrev),
+    ("increase_font_size", Command::IncreaseFontSize),
+    ("decrease_font_size", Command::DecreaseFontSize),
+    ("reset_font_size", Command::ResetFontSize),
+    ("toggle_word_wrap", Command::ToggleWordWrap),
+    ("toggle_minimap", Command::ToggleMinimap),
+    ("toggle_sticky_scroll", Command::ToggleStickyScroll),
+];
+
+impl Command {
+    /// The name of the command in keymap files, such as `"move_left"`.
+    pub fn name(self) -> &'static str {
+        COMMAND_NAMES
+            .iter()
+            .find(|(_, command)| *command == self)
+            .unwrap()
+            .0
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        COMMAND_NAMES
+            .iter()
+            .find(|(command_name, _)| *command_name == name)
+            .map(|(_, command)| *command)
+    }
+
+    /// Returns `true` if the command can change the text of the document.
+    pub fn edits_text(self) -> bool {
+        matches!(
+            self,
+            Self::Enter
+                | Self::Indent
+                | Self::Outdent
+                | Self::Delete
+                | Self::Backspace
+                | Self::Undo
+                | Self::Redo
+        )
+    }
+}
+
+/// A key together with the modifiers that must be held down, such as `ctrl+shift+z`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyChord {
+    pub key_code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("a", KeyCode::KeyA),
+    ("b", KeyCode::KeyB),
+    ("c", KeyCode::KeyC),
+    ("d", KeyCode::KeyD),
+    ("e", KeyCode::KeyE),
+    ("f", KeyCode::KeyF),
+    ("g", KeyCode::KeyG),
+    ("h", KeyCode::KeyH),
+    ("i", KeyCode::KeyI),
+    ("j", KeyCode::KeyJ),
+    ("k", KeyCode::KeyK),
+    ("l", KeyCode::KeyL),
+    ("m", KeyCode::KeyM),
+    ("n", KeyCode::KeyN),
+    ("o", KeyCode::KeyO),
+    ("p", KeyCode::KeyP),
+    ("q", KeyCode::KeyQ),
+    ("r", KeyCode::KeyR),
+    ("s", KeyCode::KeyS),
+    ("t", KeyCode::KeyT),
+    ("u", KeyCode::KeyU),
+    ("v", KeyCode::KeyV),
+    ("w", KeyCode::KeyW),
+    ("x", KeyCode::KeyX),
+    ("y", KeyCode::KeyY),
+    ("z", KeyCode::KeyZ),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("f1", KeyCode::F1),
+    ("f2", KeyCode::F2),
+    ("f3", KeyCode::F3),
+    ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5),
+    ("f6", KeyCode::F6),
+    ("f7", KeyCode::F7),
+    ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9),
+    ("f10", KeyCode::F10),
+    ("f11", KeyCode::F11),
+    ("f12", KeyCode::F12),
+    ("escape", KeyCode::Escape),
+    ("tab", KeyCode::Tab),
+    ("space", KeyCode::Space),
+    ("enter", KeyCode::ReturnKey),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("left", KeyCode::ArrowLeft),
+    ("right", KeyCode::ArrowRight),
+    ("up", KeyCode::ArrowUp),
+    ("down", KeyCode::ArrowDown),
+    ("minus", KeyCode::Minus),
+    ("equals", KeyCode::Equals),
+    ("backtick", KeyCode::Backtick),
+    ("lbracket", KeyCode::LBracket),
+    ("rbracket", KeyCode::RBracket),
+    ("semicolon", KeyCode::Semicolon),
+    ("quote", KeyCode::Quote),
+    ("backslash", KeyCode::Backslash),
+    ("comma", KeyCode::Comma),
+    ("period", KeyCode::Period),
+    ("slash", KeyCode::Slash),
+];
+
+impl KeyChord {
+    pub fn new(key_code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            key_code,
+            modifiers,
+        }
+    }
+
+    pub fn from_key_event(event: &KeyEvent) -> Self {
+        Self::new(event.key_code, event.modifiers)
+    }
+
+    /// Returns the name of the key as used by `parse`, such as `"z"` or `"pageup"`.
+    pub fn key_name(&self) -> Option<&'static str> {
+        KEY_NAMES
+            .iter()
+            .find(|(_, key_code)| *key_code == self.key_code)
+            .map(|(name, _)| *name)
+    }
+
+    /// Parses a chord such as `"ctrl+shift+z"`. Modifiers are `shift`, `ctrl`, `alt` and `cmd`,
+    /// and keys are named as in `KEY_NAMES`.
+    pub fn parse(chord: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::default();
+        let mut parts = chord.split('+').map(|part| part.trim()).peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                let key_code = KEY_NAMES
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(part))
+                    .map(|(_, key_code)| *key_code)
+                    .ok_or_else(|| format!("unknown key `{}`", part))?;
+                return Ok(Self::new(key_code, modifiers));
+            }
+            match part.to_ascii_lowercase().as_str() {
+                "shift" => modifiers.shift = true,
+                "ctrl" | "control" => modifiers.control = true,
+                "alt" | "option" => modifiers.alt = true,
+                "cmd" | "logo" | "super" => modifiers.logo = true,
+                _ => return Err(format!("unknown modifier `{}`", part)),
+            }
+        }
+        Err("empty key chord".to_string())
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.shift {
+            write!(f, "shift+")?;
+        }
+        if self.modifiers.control {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.logo {
+            write!(f, "cmd+")?;
+        }
+        match self.key_name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self.key_code),
+        }
+    }
+}
+
+/// A sequence of key chords, such as `ctrl+k ctrl+c`, bound to a command.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    pub keys: Vec<KeyChord>,
+    pub command: Command,
+}
+
+/// What a sequence of key chords that was pressed resolves to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    Command(Command),
+    /// The keys are the start of a longer binding, so wait for the next chord.
+    Pending,
+    /// The keys are not bound.
+    Unbound,
+}
+
+/// Maps key sequences to commands.
+///
+/// Later bindings win over earlier ones. Binding a key sequence removes the bindings for the
+/// same sequence, and for any sequence that starts with it or that it starts with, so that every
+/// sequence of chords resolves to at most one command. This lets a keymap file override the
+/// defaults without having to unbind them first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    /// Whether keys are handled by the Vim emulation before they are looked up in the keymap.
+    pub vim_mode: bool,
+}
+
+impl Keymap {
+    /// Creates a keymap without any bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            vim_mode: false,
+        }
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    pub fn bind(&mut self, keys: Vec<KeyChord>, command: Command) {
+        self.unbind(&keys);
+        self.bindings.push(Binding { keys, command });
+    }
+
+    /// Removes the bindings that conflict with the given key sequence.
+    pub fn unbind(&mut self, keys: &[KeyChord]) {
+        self.bindings
+            .retain(|binding| !binding.keys.starts_with(keys) && !keys.starts_with(&binding.keys));
+    }
+
+    pub fn resolve(&self, keys: &[KeyChord]) -> Resolution {
+        let mut resolution = Resolution::Unbound;
+        for binding in &self.bindings {
+            if binding.keys == keys {
+                return Resolution::Command(binding.command);
+            }
+            if binding.keys.starts_with(keys) {
+                resolution = Resolution::Pending;
+            }
+        }
+        resolution
+    }
+
+    /// Adds the bindings in `source` to the keymap.
+    ///
+    /// Each line binds a space separated sequence of chords to a command, as in
+    /// `ctrl+k ctrl+u = undo`. Binding to `none` removes the bindings for a sequence, and
+    /// `vim = on` or `vim = off` turns the Vim emulation on or off. Lines starting with `#` are
+    /// comments.
+    pub fn load(&mut self, source: &str) -> Result<(), String> {
+        for (line_index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", line_index + 1, message);
+            let Some((keys, command)) = line.rsplit_once('=') else {
+                return Err(error("expected `keys = command`".to_string()));
+            };
+            let (keys, command) = (keys.trim(), command.trim());
+            if keys == "vim" {
+                self.vim_mode = match command {
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        return Err(error(format!(
+                            "expected `on` or `off`, found `{}`",
+                            command
+                        )))
+                    }
+                };
+                continue;
+            }
+            let keys = keys
+                .split_whitespace()
+                .map(KeyChord::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error)?;
+            if keys.is_empty() {
+                return Err(error("expected keys".to_string()));
+            }
+            if command == "none" {
+                self.unbind(&keys);
+                continue;
+            }
+            let command = Command::from_name(command)
+                .ok_or_else(|| error(format!("unknown command `{}`", command)))?;
+            self.bind(keys, command);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Keymap {
+    /// The standard bindings. Shortcuts with `ctrl` are also bound with `cmd`.
+    fn default() -> Self {
+        let mut keymap = Self::new();
+        keymap
+            .load(
+                "
+                left = move_left
+                right = move_right
+                up = move_up
+                down = move_down
+                home = move_to_line_start
+                end = move_to_line_end
+                pageup = move_page_up
+                pagedown = move_page_down
+                shift+left = select_left
+                shift+right = select_right
+                shift+up = select_up
+                shift+down = select_down
+                shift+home = select_to_line_start
+                shift+end = select_to_line_end
+                shift+pageup = select_page_up
+                shift+pagedown = select_page_down
+                ctrl+left = move_to_line_start
+                ctrl+right = move_to_line_end
+                ctrl+home = move_to_file_start
+                ctrl+end = move_to_file_end
+                shift+ctrl+left = select_to_line_start
+                shift+ctrl+right = select_to_line_end
+                shift+ctrl+home = select_to_file_start
+                shift+ctrl+end = select_to_file_end
+                cmd+left = move_to_line_start
+                cmd+right = move_to_line_end
+                cmd+up = move_to_file_start
+                cmd+down = move_to_file_end
+                shift+cmd+left = select_to_line_start
+                shift+cmd+right = select_to_line_end
+                shift+cmd+up = select_to_file_start
+                shift+cmd+down = select_to_file_end
+                enter = enter
+                tab = indent
+                shift+tab = outdent
+                delete = delete
+                shift+delete = delete
+                backspace = backspace
+                shift+backspace = backspace
+                shift+enter = enter
+                ctrl+a = select_all
+                cmd+a = select_all
+                ctrl+d = select_next_occurrence
+                cmd+d = select_next_occurrence
+                shift+ctrl+l = select_all_occurrences
+                shift+cmd+l = select_all_occurrences
+                shift+alt+i = split_selection_into_lines
+                ctrl+alt+up = add_cursor_above
+                ctrl+alt+down = add_cursor_below
+                alt+cmd+up = add_cursor_above
+                alt+cmd+down = add_cursor_below
+                ctrl+z = undo
+                cmd+z = undo
+                shift+ctrl+z = redo
+                shift+cmd+z = redo
+                ctrl+y = redo
+                ctrl+f = find
+                cmd+f = find
+                ctrl+h = replace
+                cmd+h = replace
+                f3 = find_next
+                shift+f3 = find_prev
+                ctrl+g = find_next
+                cmd+g = find_next
+                shift+ctrl+g = find_prev
+                shift+cmd+g = find_prev
+                ctrl+equals = increase_font_size
+                cmd+equals = increase_font_size
+                ctrl+minus = decrease_font_size
+                cmd+minus = decrease_font_size
+                ctrl+0 = reset_font_size
+                cmd+0 = reset_font_size
+                ctrl+w = toggle_word_wrap
+                cmd+w = toggle_word_wrap
+                ",
+            )
+            .unwrap();
+        keymap
+    }
+}
@@ -0,0 +1,94 @@
+use {
+    crate::{
+        languages::{
+            Glsl, JavaScript, Json, LiveDesign, Markdown, PlainText, Rust, Shader, Toml,
+        },
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+    std::{
+        fmt,
+        hash::{Hash, Hasher},
+        path::Path,
+    },
+};
+
+/// A grammar that splits lines into tokens for highlighting.
+///
+/// Tokenizing is resumable: everything a language needs to carry from one line to the next,
+/// such as being inside a block comment, must be stored in the `LexState` it is given.
+pub trait Language: Sync {
+    /// A unique name for the language, such as `"rust"`.
+    fn name(&self) -> &'static str;
+
+    /// The file extensions, without the leading dot, this language is used for.
+    fn file_extensions(&self) -> &'static [&'static str];
+
+    /// Reads a single non-empty token at the cursor and returns its kind.
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind;
+
+    /// Returns the language that the next `{ ... }` block is written in, given a token that was
+    /// just read and the cursor right after it.
+    fn embedded_language(&self, _token: &str, _cursor: &Cursor<'_>) -> Option<&'static dyn Language> {
+        None
+    }
+}
+
+/// A reference to a language that is compared and hashed by name, so it can be part of the
+/// tokenizer state.
+#[derive(Clone, Copy)]
+pub struct LanguageRef(pub &'static dyn Language);
+
+impl fmt::Debug for LanguageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LanguageRef").field(&self.0.name()).finish()
+    }
+}
+
+impl PartialEq for LanguageRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.name() == other.0.name()
+    }
+}
+
+impl Eq for LanguageRef {}
+
+impl Hash for LanguageRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.name().hash(state);
+    }
+}
+
+pub static BUILTIN_LANGUAGES: &[&dyn Language] = &[
+    &Rust,
+    &Toml,
+    &Json,
+    &Markdown,
+    &Glsl,
+    &Shader,
+    &LiveDesign,
+    &JavaScript,
+    &PlainText,
+];
+
+pub fn language_for_name(name: &str) -> Option<&'static dyn Language> {
+    BUILTIN_LANGUAGES
+        .iter()
+        .copied()
+        .find(|language| language.name() == name)
+}
+
+/// Picks a language by file extension, falling back to plain text.
+pub fn language_for_path(path: impl AsRef<Path>) -> &'static dyn Language {
+    let extension = path
+        .as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    BUILTIN_LANGUAGES
+        .iter()
+        .copied()
+        .find(|language| language.file_extensions().contains(&extension.as_str()))
+        .unwrap_or(&PlainText)
+}
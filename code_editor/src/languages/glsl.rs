@@ -0,0 +1,60 @@
+use {
+    super::c_like_token,
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Glsl;
+
+impl Language for Glsl {
+    fn name(&self) -> &'static str {
+        "glsl"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["glsl", "vert", "frag", "comp", "geom"]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        // Preprocessor directives take up the rest of the line.
+        if state.mode == 0 && cursor.peek(0) == '#' && cursor.before().trim().is_empty() {
+            cursor.skip_to_end();
+            return TokenKind::OtherKeyword;
+        }
+        c_like_token(state, cursor, false, &['"'], keyword)
+    }
+}
+
+pub(super) fn glsl_type_keyword(string: &str) -> bool {
+    matches!(
+        string,
+        "void" | "bool" | "int" | "uint" | "float" | "double"
+            | "vec2" | "vec3" | "vec4" | "bvec2" | "bvec3" | "bvec4"
+            | "ivec2" | "ivec3" | "ivec4" | "uvec2" | "uvec3" | "uvec4"
+            | "dvec2" | "dvec3" | "dvec4"
+            | "mat2" | "mat3" | "mat4" | "mat2x2" | "mat2x3" | "mat2x4"
+            | "mat3x2" | "mat3x3" | "mat3x4" | "mat4x2" | "mat4x3" | "mat4x4"
+            | "sampler2D" | "sampler3D" | "samplerCube" | "sampler2DArray"
+            | "isampler2D" | "usampler2D" | "texture2D" | "texture3D" | "textureCube"
+            | "texture2DArray" | "texture2DInt" | "texture2DUint"
+    )
+}
+
+fn keyword(string: &str) -> Option<TokenKind> {
+    Some(match string {
+        "if" | "else" | "switch" | "case" | "default" | "return" | "discard" => {
+            TokenKind::BranchKeyword
+        }
+        "for" | "while" | "do" | "break" | "continue" => TokenKind::LoopKeyword,
+        "attribute" | "const" | "uniform" | "varying" | "buffer" | "shared" | "layout"
+        | "centroid" | "flat" | "smooth" | "noperspective" | "in" | "out" | "inout"
+        | "precision" | "highp" | "mediump" | "lowp" | "struct" | "invariant" | "true"
+        | "false" => TokenKind::OtherKeyword,
+        string if glsl_type_keyword(string) => TokenKind::Typename,
+        _ => return None,
+    })
+}
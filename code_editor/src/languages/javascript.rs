@@ -0,0 +1,39 @@
+use {
+    super::c_like_token,
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct JavaScript;
+
+impl Language for JavaScript {
+    fn name(&self) -> &'static str {
+        "javascript"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["js", "mjs", "cjs", "jsx", "ts", "tsx"]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        c_like_token(state, cursor, false, &['"', '\'', '`'], keyword)
+    }
+}
+
+fn keyword(string: &str) -> Option<TokenKind> {
+    Some(match string {
+        "if" | "else" | "switch" | "case" | "default" | "return" | "throw" | "try" | "catch"
+        | "finally" => TokenKind::BranchKeyword,
+        "for" | "while" | "do" | "break" | "continue" | "of" => TokenKind::LoopKeyword,
+        "async" | "await" | "class" | "const" | "delete" | "export" | "extends" | "from"
+        | "function" | "get" | "import" | "in" | "instanceof" | "let" | "new" | "set"
+        | "static" | "super" | "this" | "typeof" | "var" | "void" | "yield" | "as"
+        | "interface" | "type" | "enum" => TokenKind::OtherKeyword,
+        "true" | "false" | "null" | "undefined" | "NaN" | "Infinity" => TokenKind::Constant,
+        _ => return None,
+    })
+}
@@ -0,0 +1,59 @@
+use {
+    super::{
+        block_comment_tail, delimiter_or_punctuator, number, string_tail, whitespace,
+        MODE_BLOCK_COMMENT,
+    },
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{CharExt, Cursor, LexState},
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Json;
+
+impl Language for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["json", "jsonc", "ron"]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        if state.mode == MODE_BLOCK_COMMENT {
+            return block_comment_tail(state, cursor, false);
+        }
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '/') => super::line_comment(cursor),
+            ('/', '*') => {
+                cursor.skip(2);
+                block_comment_tail(state, cursor, false)
+            }
+            ('"', _) => {
+                cursor.skip(1);
+                string_tail(state, cursor, '"', false);
+                // Object keys are strings followed by a colon.
+                if cursor.rest().trim_start().starts_with(':') {
+                    TokenKind::Identifier
+                } else {
+                    TokenKind::String
+                }
+            }
+            ('-', char) | (char, _) if char.is_ascii_digit() => {
+                if cursor.peek(0) == '-' {
+                    cursor.skip(1);
+                }
+                number(cursor)
+            }
+            (char, _) if char.is_identifier_start() => {
+                cursor.skip_while(|char| char.is_identifier_continue());
+                TokenKind::Constant
+            }
+            (char, _) if char.is_whitespace() => whitespace(cursor),
+            _ => delimiter_or_punctuator(cursor),
+        }
+    }
+}
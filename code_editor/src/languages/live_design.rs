@@ -0,0 +1,55 @@
+use {
+    super::{c_like_token, number, Shader},
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+};
+
+/// The DSL used inside `live_design!` blocks.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LiveDesign;
+
+impl Language for LiveDesign {
+    fn name(&self) -> &'static str {
+        "live_design"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        if state.mode == 0 && cursor.peek(0) == '#' && cursor.peek(1).is_ascii_hexdigit() {
+            // A color such as `#f00` or `#ffffffff`.
+            cursor.skip(1);
+            cursor.skip_while(|char| char.is_ascii_alphanumeric());
+            return TokenKind::Number;
+        }
+        if state.mode == 0 && cursor.peek(0) == '-' && cursor.peek(1).is_ascii_digit() {
+            cursor.skip(1);
+            return number(cursor);
+        }
+        c_like_token(state, cursor, true, &['"'], keyword)
+    }
+
+    fn embedded_language(&self, token: &str, _cursor: &Cursor<'_>) -> Option<&'static dyn Language> {
+        // The bodies of functions in the DSL are shader code.
+        if token == "fn" {
+            Some(&Shader)
+        } else {
+            None
+        }
+    }
+}
+
+fn keyword(string: &str) -> Option<TokenKind> {
+    match string {
+        "fn" | "import" | "instance" | "pub" | "texture" | "uniform" | "use" | "varying" => {
+            Some(TokenKind::OtherKeyword)
+        }
+        "true" | "false" => Some(TokenKind::Constant),
+        _ => None,
+    }
+}
@@ -0,0 +1,107 @@
+use {
+    super::whitespace,
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+};
+
+/// Inside a fenced code block. The fence character is kept in `data`.
+const MODE_FENCED_CODE: u8 = 1;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Markdown;
+
+impl Language for Markdown {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["md", "markdown"]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        let is_line_start = cursor.before().trim().is_empty();
+        if state.mode == MODE_FENCED_CODE {
+            let fence = char::from_u32(state.data as u32).unwrap_or('`');
+            let closing: String = [fence; 3].iter().collect();
+            if is_line_start && cursor.rest().trim_start().starts_with(&closing) {
+                *state = LexState::default();
+                cursor.skip_to_end();
+                return TokenKind::Punctuator;
+            }
+            cursor.skip_to_end();
+            return TokenKind::String;
+        }
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            (char, _, _) if char.is_whitespace() => whitespace(cursor),
+            (fence @ ('`' | '~'), second, third)
+                if is_line_start && second == fence && third == fence =>
+            {
+                // The rest of the line is the fence and its info string.
+                state.mode = MODE_FENCED_CODE;
+                state.data = fence as u16;
+                cursor.skip_to_end();
+                TokenKind::Punctuator
+            }
+            ('#', _, _) if is_line_start => {
+                cursor.skip_to_end();
+                TokenKind::Typename
+            }
+            ('>', _, _) if is_line_start => {
+                cursor.skip_to_end();
+                TokenKind::Comment
+            }
+            ('-' | '*' | '+', ' ', _) if is_line_start => {
+                cursor.skip(1);
+                TokenKind::Punctuator
+            }
+            ('`', _, _) => {
+                let ticks = count_run(cursor, '`');
+                cursor.skip(ticks);
+                let closing = "`".repeat(ticks);
+                match cursor.rest().find(&closing) {
+                    Some(index) => {
+                        let char_count = cursor.rest()[..index].chars().count();
+                        cursor.skip(char_count + ticks);
+                    }
+                    None => cursor.skip_to_end(),
+                }
+                TokenKind::String
+            }
+            (char @ ('*' | '_' | '~'), _, _) => {
+                cursor.skip_while(|other| other == char);
+                TokenKind::Punctuator
+            }
+            ('[' | ']' | '(' | ')', _, _) => {
+                cursor.skip(1);
+                TokenKind::Delimiter
+            }
+            (char, _, _) if char.is_ascii_digit() => {
+                cursor.skip_while(|char| char.is_ascii_digit());
+                if is_line_start && (cursor.peek(0) == '.' || cursor.peek(0) == ')') {
+                    cursor.skip(1);
+                    TokenKind::Punctuator
+                } else {
+                    TokenKind::Number
+                }
+            }
+            (char, _, _) if char.is_ascii_punctuation() => {
+                cursor.skip(1);
+                TokenKind::Punctuator
+            }
+            _ => {
+                cursor.skip_while(|char| {
+                    !char.is_whitespace() && !matches!(char, '`' | '*' | '_' | '~' | '[' | ']' | '(' | ')')
+                });
+                TokenKind::Identifier
+            }
+        }
+    }
+}
+
+fn count_run(cursor: &Cursor, char: char) -> usize {
+    cursor.rest().chars().take_while(|&other| other == char).count()
+}
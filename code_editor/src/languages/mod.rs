@@ -0,0 +1,187 @@
+//! The built-in grammars, along with lexing helpers shared by the C-like ones.
+
+mod glsl;
+mod javascript;
+mod json;
+mod live_design;
+mod markdown;
+mod plain_text;
+mod rust;
+mod shader;
+mod toml;
+
+pub use self::{
+    glsl::Glsl, javascript::JavaScript, json::Json, live_design::LiveDesign, markdown::Markdown,
+    plain_text::PlainText, rust::Rust, shader::Shader, toml::Toml,
+};
+
+use crate::{
+    token::TokenKind,
+    tokenizer::{CharExt, Cursor, LexState},
+};
+
+/// The modes shared by the C-like grammars. The default mode is 0.
+const MODE_BLOCK_COMMENT: u8 = 1;
+const MODE_STRING: u8 = 2;
+
+fn whitespace(cursor: &mut Cursor) -> TokenKind {
+    debug_assert!(cursor.peek(0).is_whitespace());
+    cursor.skip_while(|char| char.is_whitespace());
+    TokenKind::Whitespace
+}
+
+fn line_comment(cursor: &mut Cursor) -> TokenKind {
+    cursor.skip_to_end();
+    TokenKind::Comment
+}
+
+/// Skips the rest of a block comment whose opening `/*` has already been skipped. The nesting
+/// depth of nested comments is kept in `state.data`.
+fn block_comment_tail(state: &mut LexState, cursor: &mut Cursor, is_nested: bool) -> TokenKind {
+    loop {
+        match (cursor.peek(0), cursor.peek(1)) {
+            ('/', '*') if is_nested => {
+                cursor.skip(2);
+                state.data += 1;
+            }
+            ('*', '/') => {
+                cursor.skip(2);
+                if state.data == 0 {
+                    *state = LexState::default();
+                    break;
+                }
+                state.data -= 1;
+            }
+            ('\0', _) => {
+                state.mode = MODE_BLOCK_COMMENT;
+                break;
+            }
+            _ => cursor.skip(1),
+        }
+    }
+    TokenKind::Comment
+}
+
+/// Skips the rest of a string whose opening quote has already been skipped. If
+/// `is_multiline` is set, a string that is not closed on this line continues on the next one,
+/// with the quote kept in `state.data`.
+fn string_tail(state: &mut LexState, cursor: &mut Cursor, quote: char, is_multiline: bool) -> TokenKind {
+    loop {
+        match cursor.peek(0) {
+            '\0' => {
+                if is_multiline {
+                    state.mode = MODE_STRING;
+                    state.data = quote as u16;
+                }
+                break;
+            }
+            '\\' => cursor.skip(2),
+            char if char == quote => {
+                cursor.skip(1);
+                *state = LexState::default();
+                break;
+            }
+            _ => cursor.skip(1),
+        }
+    }
+    TokenKind::String
+}
+
+fn number(cursor: &mut Cursor) -> TokenKind {
+    let radix = match (cursor.peek(0), cursor.peek(1)) {
+        ('0', 'b' | 'B') => 2,
+        ('0', 'o' | 'O') => 8,
+        ('0', 'x' | 'X') => 16,
+        _ => 10,
+    };
+    if radix != 10 {
+        cursor.skip(2);
+        if !cursor.skip_digits(radix) {
+            return TokenKind::Unknown;
+        }
+    } else {
+        cursor.skip_digits(10);
+        if cursor.peek(0) == '.' && cursor.peek(1).is_ascii_digit() {
+            cursor.skip(1);
+            cursor.skip_digits(10);
+        } else if cursor.peek(0) == '.' && !cursor.peek(1).is_identifier_start() && cursor.peek(1) != '.' {
+            cursor.skip(1);
+        }
+        if matches!(cursor.peek(0), 'e' | 'E') && !cursor.skip_exponent() {
+            return TokenKind::Unknown;
+        }
+    }
+    cursor.skip_suffix();
+    TokenKind::Number
+}
+
+fn identifier<'a>(cursor: &mut Cursor<'a>) -> &'a str {
+    debug_assert!(cursor.peek(0).is_identifier_start());
+    let start = cursor.index();
+    cursor.skip(1);
+    cursor.skip_while(|char| char.is_identifier_continue());
+    &cursor.before()[start..]
+}
+
+/// Classifies an identifier that is not a keyword by its casing and whether it is called.
+fn classify_identifier(string: &str, cursor: &Cursor) -> TokenKind {
+    let mut chars = string.chars();
+    if chars.next().unwrap().is_uppercase() {
+        match chars.next() {
+            Some(char) if char.is_uppercase() => TokenKind::Constant,
+            _ => TokenKind::Typename,
+        }
+    } else if cursor.peek(0) == '(' {
+        TokenKind::Function
+    } else {
+        TokenKind::Identifier
+    }
+}
+
+fn delimiter_or_punctuator(cursor: &mut Cursor) -> TokenKind {
+    let char = cursor.peek(0);
+    cursor.skip(1);
+    match char {
+        '(' | ')' | '[' | ']' | '{' | '}' => TokenKind::Delimiter,
+        char if char.is_ascii_punctuation() => TokenKind::Punctuator,
+        _ => TokenKind::Unknown,
+    }
+}
+
+/// Reads a token in a language with C-like comments, strings, numbers and identifiers,
+/// classifying identifiers with `keyword`.
+fn c_like_token(
+    state: &mut LexState,
+    cursor: &mut Cursor,
+    has_nested_comments: bool,
+    quotes: &[char],
+    keyword: impl Fn(&str) -> Option<TokenKind>,
+) -> TokenKind {
+    match state.mode {
+        MODE_BLOCK_COMMENT => return block_comment_tail(state, cursor, has_nested_comments),
+        MODE_STRING => {
+            let quote = char::from_u32(state.data as u32).unwrap_or('"');
+            return string_tail(state, cursor, quote, true);
+        }
+        _ => {}
+    }
+    match (cursor.peek(0), cursor.peek(1)) {
+        ('/', '/') => line_comment(cursor),
+        ('/', '*') => {
+            cursor.skip(2);
+            block_comment_tail(state, cursor, has_nested_comments)
+        }
+        (char, _) if quotes.contains(&char) => {
+            cursor.skip(1);
+            string_tail(state, cursor, char, char == '`')
+        }
+        ('.', char) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_ascii_digit() => number(cursor),
+        (char, _) if char.is_identifier_start() => {
+            let string = identifier(cursor);
+            keyword(string).unwrap_or_else(|| classify_identifier(string, cursor))
+        }
+        (char, _) if char.is_whitespace() => whitespace(cursor),
+        _ => delimiter_or_punctuator(cursor),
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{
+    language::Language,
+    token::TokenKind,
+    tokenizer::{Cursor, LexState},
+};
+
+/// Used for files without a known language, splitting lines into words and whitespace.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PlainText;
+
+impl Language for PlainText {
+    fn name(&self) -> &'static str {
+        "plain_text"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["txt"]
+    }
+
+    fn next_token(&self, _state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        if cursor.peek(0).is_whitespace() {
+            cursor.skip_while(|char| char.is_whitespace());
+            TokenKind::Whitespace
+        } else {
+            cursor.skip_while(|char| !char.is_whitespace());
+            TokenKind::Unknown
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use crate::{
+    language::Language,
+    languages::LiveDesign,
+    token::TokenKind,
+    tokenizer::{CharExt, Cursor, LexState},
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Rust;
+
+impl Language for Rust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["rs"]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        let (next_state, kind) = match RustState::from_lex_state(*state) {
+            RustState::Initial(state) => state.next(cursor),
+            RustState::BlockCommentTail(state) => state.next(cursor),
+            RustState::DoubleQuotedStringTail(state) => state.next(cursor),
+            RustState::RawDoubleQuotedStringTail(state) => state.next(cursor),
+        };
+        *state = next_state.to_lex_state();
+        kind
+    }
+
+    fn embedded_language(&self, token: &str, cursor: &Cursor<'_>) -> Option<&'static dyn Language> {
+        if token == "live_design" && cursor.peek(0) == '!' {
+            Some(&LiveDesign)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum RustState {
+    Initial(InitialState),
+    BlockCommentTail(BlockCommentTailState),
+    DoubleQuotedStringTail(DoubleQuotedStringTailState),
+    RawDoubleQuotedStringTail(RawDoubleQuotedStringTailState),
+}
+
+impl RustState {
+    fn from_lex_state(state: LexState) -> Self {
+        match state.mode {
+            1 => Self::BlockCommentTail(BlockCommentTailState {
+                depth: state.data as usize,
+            }),
+            2 => Self::DoubleQuotedStringTail(DoubleQuotedStringTailState),
+            3 => Self::RawDoubleQuotedStringTail(RawDoubleQuotedStringTailState {
+                start_hash_count: state.data as usize,
+            }),
+            _ => Self::Initial(InitialState),
+        }
+    }
+
+    fn to_lex_state(self) -> LexState {
+        match self {
+            Self::Initial(_) => LexState::default(),
+            Self::BlockCommentTail(state) => LexState {
+                mode: 1,
+                data: state.depth as u16,
+            },
+            Self::DoubleQuotedStringTail(_) => LexState { mode: 2, data: 0 },
+            Self::RawDoubleQuotedStringTail(state) => LexState {
+                mode: 3,
+                data: state.start_hash_count as u16,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct InitialState;
+
+impl InitialState {
+    fn next(self, cursor: &mut Cursor<'_>) -> (RustState, TokenKind) {
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            ('r', '#', '"') | ('r', '#', '#') => self.raw_string(cursor),
+            ('b', 'r', '"') | ('b', 'r', '#') => self.raw_byte_string(cursor),
+            ('/', '/', _) => self.line_comment(cursor),
+            ('/', '*', _) => self.block_comment(cursor),
+            ('b', '\'', _) => self.byte(cursor),
+            ('b', '"', _) => self.byte_string(cursor),
+            ('!', '=', _)
+            | ('%', '=', _)
+            | ('&', '&', _)
+            | ('&', '=', _)
+            | ('*', '=', _)
+            | ('+', '=', _)
+            | ('-', '=', _)
+            | ('-', '>', _)
+            | ('.', '.', _)
+            | ('/', '=', _)
+            | (':', ':', _)
+            | ('<', '<', _)
+            | ('<', '=', _)
+            | ('=', '=', _)
+            | ('=', '>', _)
+            | ('>', '=', _)
+            | ('>', '>', _)
+            | ('^', '=', _)
+            | ('|', '=', _)
+            | ('|', '|', _) => {
+                cursor.skip(2);
+                (RustState::Initial(InitialState), TokenKind::Punctuator)
+            }
+            ('\'', _, _) => self.char_or_lifetime(cursor),
+            ('"', _, _) => self.string(cursor),
+            ('(', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Delimiter)
+            }
+            (')', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Delimiter)
+            }
+            ('[', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Delimiter)
+            }
+            (']', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Delimiter)
+            }
+            ('{', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Delimiter)
+            }
+            ('}', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Delimiter)
+            }
+            ('.', char, _) if char.is_ascii_digit() => self.number(cursor),
+            ('!', _, _)
+            | ('#', _, _)
+            | ('$', _, _)
+            | ('%', _, _)
+            | ('&', _, _)
+            | ('*', _, _)
+            | ('+', _, _)
+            | (',', _, _)
+            | ('-', _, _)
+            | ('.', _, _)
+            | ('/', _, _)
+            | (':', _, _)
+            | (';', _, _)
+            | ('<', _, _)
+            | ('=', _, _)
+            | ('>', _, _)
+            | ('?', _, _)
+            | ('@', _, _)
+            | ('^', _, _)
+            | ('_', _, _)
+            | ('|', _, _) => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Punctuator)
+            }
+            (char, _, _) if char.is_identifier_start() => self.identifier_or_keyword(cursor),
+            (char, _, _) if char.is_ascii_digit() => self.number(cursor),
+            (char, _, _) if char.is_whitespace() => self.whitespace(cursor),
+            _ => {
+                cursor.skip(1);
+                (RustState::Initial(InitialState), TokenKind::Unknown)
+            }
+        }
+    }
+
+    fn line_comment(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == '/' && cursor.peek(1) == '/');
+        cursor.skip(2);
+        while cursor.skip_if(|ch| ch != '\0') {}
+        (RustState::Initial(InitialState), TokenKind::Comment)
+    }
+
+    fn block_comment(self, cursor: &mut Cursor<'_>) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == '/' && cursor.peek(1) == '*');
+        cursor.skip(2);
+        BlockCommentTailState { depth: 0 }.next(cursor)
+    }
+
+    fn identifier_or_keyword(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0).is_identifier_start());
+        let start = cursor.index();
+        cursor.skip(1);
+        while cursor.skip_if(|char| char.is_identifier_continue()) {}
+        let string = &cursor.before()[start..];
+        (
+            RustState::Initial(InitialState),
+            match string {
+                "else" | "if" | "match" | "return" => TokenKind::BranchKeyword,
+                "break" | "continue" | "for" | "loop" | "while" => TokenKind::LoopKeyword,
+                "Self" | "as" | "async" | "await" | "const" | "crate" | "dyn" | "enum"
+                | "extern" | "false" | "fn" | "impl" | "in" | "let" | "mod" | "move" | "mut"
+                | "pub" | "ref" | "self" | "static" | "struct" | "super" | "trait" | "true"
+                | "type" | "unsafe" | "use" | "where" | "usize" | "isize" | "u8" | "u16"
+                | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "vec2" | "vec3" | "vec4"
+                | "bool" | "f32" | "f64" => TokenKind::OtherKeyword,
+                _ => {
+                    let mut chars = string.chars();
+                    if chars.next().unwrap().is_uppercase() {
+                        match chars.next() {
+                            Some(char) if char.is_uppercase() => TokenKind::Constant,
+                            _ => TokenKind::Typename,
+                        }
+                    } else if cursor.peek(0) == '(' {
+                        TokenKind::Function
+                    } else {
+                        TokenKind::Identifier
+                    }
+                }
+            },
+        )
+    }
+
+    fn number(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        let is_valid = match (cursor.peek(0), cursor.peek(1)) {
+            ('0', 'b') => {
+                cursor.skip(2);
+                cursor.skip_digits(2)
+            }
+            ('0', 'o') => {
+                cursor.skip(2);
+                cursor.skip_digits(8)
+            }
+            ('0', 'x') => {
+                cursor.skip(2);
+                cursor.skip_digits(16)
+            }
+            _ => {
+                cursor.skip_digits(10);
+                match cursor.peek(0) {
+                    '.' if cursor.peek(1) != '.' && !cursor.peek(0).is_identifier_start() => {
+                        cursor.skip(1);
+                        if cursor.skip_digits(10)
+                            && (cursor.peek(0) == 'E' || cursor.peek(0) == 'e')
+                            && !cursor.skip_exponent()
+                        {
+                            return (RustState::Initial(InitialState), TokenKind::Unknown);
+                        }
+                        cursor.skip_suffix();
+                        true
+                    }
+                    'E' | 'e' => {
+                        if !cursor.skip_exponent() {
+                            return (RustState::Initial(InitialState), TokenKind::Unknown);
+                        }
+                        cursor.skip_suffix();
+                        true
+                    }
+                    _ => {
+                        cursor.skip_suffix();
+                        true
+                    }
+                }
+            }
+        };
+        (
+            RustState::Initial(InitialState),
+            if is_valid {
+                TokenKind::Number
+            } else {
+                TokenKind::Unknown
+            },
+        )
+    }
+
+    fn char_or_lifetime(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        if cursor.peek(1).is_identifier_start() && cursor.peek(2) != '\'' {
+            debug_assert!(cursor.peek(0) == '\'');
+            cursor.skip(2);
+            while cursor.skip_if(|ch| ch.is_identifier_continue()) {}
+            if cursor.peek(0) == '\'' {
+                cursor.skip(1);
+                cursor.skip_suffix();
+                (RustState::Initial(InitialState), TokenKind::String)
+            } else {
+                (RustState::Initial(InitialState), TokenKind::String)
+            }
+        } else {
+            self.single_quoted_string(cursor)
+        }
+    }
+
+    fn byte(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'b');
+        cursor.skip(1);
+        self.single_quoted_string(cursor)
+    }
+
+    fn string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        self.double_quoted_string(cursor)
+    }
+
+    fn byte_string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'b');
+        cursor.skip(1);
+        self.double_quoted_string(cursor)
+    }
+
+    fn raw_string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'r');
+        cursor.skip(1);
+        self.raw_double_quoted_string(cursor)
+    }
+
+    fn raw_byte_string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == 'b' && cursor.peek(1) == 'r');
+        cursor.skip(2);
+        self.raw_double_quoted_string(cursor)
+    }
+
+    fn single_quoted_string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == '\'');
+        cursor.skip(1);
+        loop {
+            match (cursor.peek(0), cursor.peek(1)) {
+                ('\'', _) => {
+                    cursor.skip(1);
+                    cursor.skip_suffix();
+                    break;
+                }
+                ('\0', _) => return (RustState::Initial(InitialState), TokenKind::Unknown),
+                ('\\', '\'') | ('\\', '\\') => cursor.skip(2),
+                _ => cursor.skip(1),
+            }
+        }
+        (RustState::Initial(InitialState), TokenKind::String)
+    }
+
+    fn double_quoted_string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0) == '"');
+        cursor.skip(1);
+        DoubleQuotedStringTailState.next(cursor)
+    }
+
+    fn raw_double_quoted_string(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        let mut start_hash_count = 0;
+        while cursor.skip_if(|ch| ch == '#') {
+            start_hash_count += 1;
+        }
+        RawDoubleQuotedStringTailState { start_hash_count }.next(cursor)
+    }
+
+    fn whitespace(self, cursor: &mut Cursor) -> (RustState, TokenKind) {
+        debug_assert!(cursor.peek(0).is_whitespace());
+        cursor.skip(1);
+        while cursor.skip_if(|char| char.is_whitespace()) {}
+        (RustState::Initial(InitialState), TokenKind::Whitespace)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct BlockCommentTailState {
+    depth: usize,
+}
+
+impl BlockCommentTailState {
+    fn next(self, cursor: &mut Cursor<'_>) -> (RustState, TokenKind) {
+        let mut state = self;
+        loop {
+            match (cursor.peek(0), cursor.peek(1)) {
+                ('/', '*') => {
+                    cursor.skip(2);
+                    state.depth += 1;
+                }
+                ('*', '/') => {
+                    cursor.skip(2);
+                    if state.depth == 0 {
+                        break (RustState::Initial(InitialState), TokenKind::Comment);
+                    }
+                    state.depth -= 1;
+                }
+                ('\0', _) => {
+                    break (RustState::BlockCommentTail(state), TokenKind::Comment);
+                }
+                _ => cursor.skip(1),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct DoubleQuotedStringTailState;
+
+impl DoubleQuotedStringTailState {
+    fn next(self, cursor: &mut Cursor<'_>) -> (RustState, TokenKind) {
+        loop {
+            match (cursor.peek(0), cursor.peek(1)) {
+                ('"', _) => {
+                    cursor.skip(1);
+                    cursor.skip_suffix();
+                    break (RustState::Initial(InitialState), TokenKind::String);
+                }
+                ('\0', _) => {
+                    break (
+                        RustState::DoubleQuotedStringTail(DoubleQuotedStringTailState),
+                        TokenKind::String,
+                    );
+                }
+                ('\\', '"') | ('\\', '\\') => cursor.skip(2),
+                _ => cursor.skip(1),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct RawDoubleQuotedStringTailState {
+    start_hash_count: usize,
+}
+
+impl RawDoubleQuotedStringTailState {
+    fn next(self, cursor: &mut Cursor<'_>) -> (RustState, TokenKind) {
+        loop {
+            match cursor.peek(0) {
+                '"' => {
+                    cursor.skip(1);
+                    let mut end_hash_count = 0;
+                    while end_hash_count < self.start_hash_count && cursor.skip_if(|ch| ch == '#') {
+                        end_hash_count += 1;
+                    }
+                    if end_hash_count == self.start_hash_count {
+                        cursor.skip_suffix();
+                        break (RustState::Initial(InitialState), TokenKind::String);
+                    }
+                }
+                '\0' => {
+                    break (
+                        RustState::RawDoubleQuotedStringTail(self),
+                        TokenKind::String,
+                    );
+                }
+                _ => cursor.skip(1),
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use {
+    super::{c_like_token, glsl::glsl_type_keyword},
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+};
+
+/// The Rust-like shader language used in the bodies of `fn`s inside `live_design!` blocks.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Shader;
+
+impl Language for Shader {
+    fn name(&self) -> &'static str {
+        "shader"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        if state.mode == 0 && cursor.peek(0) == '#' && cursor.peek(1).is_ascii_hexdigit() {
+            // A color such as `#f00`, as in the DSL around the shader.
+            cursor.skip(1);
+            cursor.skip_while(|char| char.is_ascii_alphanumeric());
+            return TokenKind::Number;
+        }
+        c_like_token(state, cursor, true, &['"'], keyword)
+    }
+}
+
+fn keyword(string: &str) -> Option<TokenKind> {
+    Some(match string {
+        "if" | "else" | "match" | "return" => TokenKind::BranchKeyword,
+        "for" | "while" | "loop" | "break" | "continue" => TokenKind::LoopKeyword,
+        "fn" | "let" | "var" | "mut" | "const" | "self" | "Self" | "struct" | "impl" | "in"
+        | "as" | "true" | "false" | "instance" | "uniform" | "varying" | "texture" => {
+            TokenKind::OtherKeyword
+        }
+        "f32" | "i32" | "u32" | "Sdf2d" | "Pal" | "Math" | "GaussShadow" => TokenKind::Typename,
+        string if glsl_type_keyword(string) => TokenKind::Typename,
+        _ => return None,
+    })
+}
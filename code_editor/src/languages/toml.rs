@@ -0,0 +1,119 @@
+use {
+    super::{delimiter_or_punctuator, whitespace, MODE_STRING},
+    crate::{
+        language::Language,
+        token::TokenKind,
+        tokenizer::{Cursor, LexState},
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Toml;
+
+impl Language for Toml {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["toml"]
+    }
+
+    fn next_token(&self, state: &mut LexState, cursor: &mut Cursor<'_>) -> TokenKind {
+        if state.mode == MODE_STRING {
+            let quote = char::from_u32(state.data as u32).unwrap_or('"');
+            return multiline_string_tail(state, cursor, quote);
+        }
+        let is_line_start = cursor.before().trim().is_empty();
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            ('#', _, _) => super::line_comment(cursor),
+            ('[', _, _) if is_line_start => {
+                // A table header such as `[dependencies]` or `[[bin]]`.
+                cursor.skip_while(|char| char != ']');
+                cursor.skip_while(|char| char == ']');
+                TokenKind::Typename
+            }
+            (quote @ ('"' | '\''), second, third) if second == quote && third == quote => {
+                cursor.skip(3);
+                multiline_string_tail(state, cursor, quote)
+            }
+            (quote @ ('"' | '\''), _, _) => {
+                cursor.skip(1);
+                loop {
+                    match cursor.peek(0) {
+                        '\0' => break,
+                        '\\' if quote == '"' => cursor.skip(2),
+                        char if char == quote => {
+                            cursor.skip(1);
+                            break;
+                        }
+                        _ => cursor.skip(1),
+                    }
+                }
+                if is_key(cursor) {
+                    TokenKind::Identifier
+                } else {
+                    TokenKind::String
+                }
+            }
+            (char, _, _) if is_bare_key_char(char) || char == '+' => {
+                let start = cursor.index();
+                cursor.skip(1);
+                cursor.skip_while(|char| {
+                    is_bare_key_char(char) || matches!(char, ':' | '.' | '+')
+                });
+                if is_key(cursor) {
+                    TokenKind::Identifier
+                } else {
+                    match &cursor.before()[start..] {
+                        "true" | "false" => TokenKind::Constant,
+                        "inf" | "nan" | "+inf" | "-inf" | "+nan" | "-nan" => TokenKind::Number,
+                        string if string.starts_with(|char: char| {
+                            char.is_ascii_digit() || char == '+' || char == '-'
+                        }) =>
+                        {
+                            TokenKind::Number
+                        }
+                        _ => TokenKind::Unknown,
+                    }
+                }
+            }
+            (char, _, _) if char.is_whitespace() => whitespace(cursor),
+            _ => delimiter_or_punctuator(cursor),
+        }
+    }
+}
+
+/// Skips the rest of a `"""` or `'''` string, which may span multiple lines.
+fn multiline_string_tail(state: &mut LexState, cursor: &mut Cursor, quote: char) -> TokenKind {
+    loop {
+        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
+            ('\0', _, _) => {
+                state.mode = MODE_STRING;
+                state.data = quote as u16;
+                break;
+            }
+            ('\\', _, _) if quote == '"' => cursor.skip(2),
+            (first, second, third) if first == quote && second == quote && third == quote => {
+                cursor.skip(3);
+                // Up to two more quotes are part of the string.
+                cursor.skip_if(|char| char == quote);
+                cursor.skip_if(|char| char == quote);
+                *state = LexState::default();
+                break;
+            }
+            _ => cursor.skip(1),
+        }
+    }
+    TokenKind::String
+}
+
+fn is_bare_key_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_' || char == '-'
+}
+
+/// Keys are followed by `=`, or by `.` in dotted keys.
+fn is_key(cursor: &Cursor) -> bool {
+    let rest = cursor.rest().trim_start();
+    rest.starts_with('=') || rest.starts_with('.')
+}
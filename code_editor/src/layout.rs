@@ -2,6 +2,7 @@ use {
     crate::{
         document::{DocumentLayout, IndentState},
         inlays::{BlockInlay, InlineInlay},
+        rope,
         selection::Affinity,
         session::SessionLayout,
         str::StrExt,
@@ -26,10 +27,14 @@ impl<'a> Layout<'a> {
     }
 
     pub fn width(&self) -> f64 {
+        if let Some(width) = self.session_layout.width.get() {
+            return width;
+        }
         let mut width: f64 = 0.0;
         for line in self.lines(0, self.as_text().as_lines().len()) {
             width = width.max(line.width());
         }
+        self.session_layout.width.set(Some(width));
         width
     }
 
@@ -89,10 +94,10 @@ impl<'a> Layout<'a> {
             column_count: self.session_layout.column_count[start..end].iter(),
             fold: self.session_layout.fold_column[start..end].iter(),
             scale: self.session_layout.scale[start..end].iter(),
-            text: self.text.as_lines()[start..end].iter(),
-            indent_state: self.document_layout.indent_state[start..end].iter(),
-            tokens: self.document_layout.tokens[start..end].iter(),
-            inline_inlays: self.document_layout.inline_inlays[start..end].iter(),
+            text: self.text.as_lines().range(start..end),
+            indent_state: self.document_layout.indent_state.range(start..end),
+            tokens: self.document_layout.tokens.range(start..end),
+            inline_inlays: self.document_layout.inline_inlays.range(start..end),
             wrap_data: self.session_layout.wrap_data[start..end].iter(),
         }
     }
@@ -120,10 +125,10 @@ pub struct Lines<'a> {
     column_count: Iter<'a, Option<usize>>,
     fold: Iter<'a, usize>,
     scale: Iter<'a, f64>,
-    text: Iter<'a, String>,
-    indent_state: Iter<'a, Option<IndentState>>,
-    tokens: Iter<'a, Vec<Token>>,
-    inline_inlays: Iter<'a, Vec<(usize, InlineInlay)>>,
+    text: rope::Iter<'a>,
+    indent_state: rope::Iter<'a, Option<IndentState>>,
+    tokens: rope::Iter<'a, Vec<Token>>,
+    inline_inlays: rope::Iter<'a, Vec<(usize, InlineInlay)>>,
     wrap_data: Iter<'a, Option<WrapData>>,
 }
 
@@ -164,12 +169,16 @@ impl<'a> Line<'a> {
         self.y.unwrap()
     }
 
+    /// Returns the number of rows the line is wrapped into. Lines that have not been laid out
+    /// yet are assumed not to wrap.
     pub fn row_count(&self) -> usize {
-        self.wrap_data.unwrap().wraps.len() + 1
+        self.wrap_data
+            .map_or(1, |wrap_data| wrap_data.wraps.len() + 1)
     }
 
     pub fn column_count(&self) -> usize {
-        self.column_count.unwrap()
+        self.column_count
+            .unwrap_or_else(|| self.text.column_count())
     }
 
     pub fn width(&self) -> f64 {
@@ -307,7 +316,8 @@ impl<'a> Line<'a> {
     }
 
     pub fn wrap_indent_column_count(self) -> usize {
-        self.wrap_data.unwrap().indent_column_count
+        self.wrap_data
+            .map_or(0, |wrap_data| wrap_data.indent_column_count)
     }
 
     pub fn text(&self) -> &str {
@@ -338,7 +348,9 @@ impl<'a> Line<'a> {
         WrappedElements {
             element: elements.next(),
             elements,
-            wraps: self.wrap_data.unwrap().wraps.iter(),
+            wraps: self
+                .wrap_data
+                .map_or([].iter(), |wrap_data| wrap_data.wraps.iter()),
             position: 0,
         }
     }
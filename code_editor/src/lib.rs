@@ -3,27 +3,45 @@ use makepad_widgets::*;
 
 pub mod char;
 pub mod code_editor;
+pub mod collab;
+pub mod completion;
 pub mod decoration;
+pub mod diff;
+pub mod diff_view;
 pub mod document;
 pub mod history;
 pub mod inlays;
 pub mod iter;
+pub mod keymap;
+pub mod language;
+pub mod languages;
 pub mod layout;
+pub mod merge;
+pub mod provider;
+pub mod regex;
+pub mod rope;
+pub mod search;
 pub mod selection;
 pub mod session;
 pub mod settings;
+pub mod snippet;
 pub mod str;
 pub mod text;
 pub mod token;
 pub mod tokenizer;
+pub mod vim;
 pub mod widgets;
+pub mod word_provider;
 pub mod wrap;
 
 pub use self::{
-    code_editor::CodeEditor, document::Document, history::History, layout::Line,
-    selection::Selection, session::Session, settings::Settings, token::Token, tokenizer::Tokenizer,
+    code_editor::CodeEditor, diff_view::DiffView, document::Document, history::History, language::Language,
+    layout::Line,
+    search::SearchQuery, selection::Selection, session::Session, settings::Settings,
+    snippet::Snippet, token::Token, tokenizer::Tokenizer, word_provider::WordProvider,
 };
 
 pub fn live_design(cx: &mut Cx) {
     crate::code_editor::live_design(cx);
+    crate::diff_view::live_design(cx);
 }
@@ -0,0 +1,169 @@
+use {
+    crate::{
+        diff::{diff_lines, DiffAlgorithm},
+        text::Text,
+    },
+    std::ops::Range,
+};
+
+pub const CONFLICT_START_MARKER: &str = "<<<<<<<";
+pub const CONFLICT_SEPARATOR_MARKER: &str = "=======";
+pub const CONFLICT_END_MARKER: &str = ">>>>>>>";
+
+/// The result of a three-way merge.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Merge {
+    /// The merged text, with conflict markers around each conflict.
+    pub text: Text,
+    pub conflict_count: usize,
+}
+
+/// Merges the changes from `base` to `ours` and from `base` to `theirs`.
+///
+/// Changes that touch different lines are both taken. Changes to the same or adjacent lines are a
+/// conflict, unless they are identical, and are written out between git style conflict markers,
+/// labeled with `ours_label` and `theirs_label`.
+pub fn merge(
+    base: &Text,
+    ours: &Text,
+    theirs: &Text,
+    ours_label: &str,
+    theirs_label: &str,
+) -> Merge {
+    let base_lines: Vec<&str> = base.as_lines().iter().map(|line| line.as_str()).collect();
+    let our_lines: Vec<&str> = ours.as_lines().iter().map(|line| line.as_str()).collect();
+    let their_lines: Vec<&str> = theirs.as_lines().iter().map(|line| line.as_str()).collect();
+    let our_hunks = diff_lines(&base_lines, &our_lines, DiffAlgorithm::Patience);
+    let their_hunks = diff_lines(&base_lines, &their_lines, DiffAlgorithm::Patience);
+
+    let mut lines: Vec<&str> = Vec::new();
+    let mut conflict_count = 0;
+    let start_marker = format!("{} {}", CONFLICT_START_MARKER, ours_label);
+    let end_marker = format!("{} {}", CONFLICT_END_MARKER, theirs_label);
+    let mut base_line = 0;
+    let mut our_index = 0;
+    let mut their_index = 0;
+    loop {
+        // Find the next run of base lines that is changed by either side. Changes that overlap or
+        // touch are grouped into the same run.
+        let start = match (our_hunks.get(our_index), their_hunks.get(their_index)) {
+            (Some(ours), Some(theirs)) => ours.0.start.min(theirs.0.start),
+            (Some(ours), None) => ours.0.start,
+            (None, Some(theirs)) => theirs.0.start,
+            (None, None) => break,
+        };
+        let mut end = start;
+        let our_start = our_index;
+        let their_start = their_index;
+        loop {
+            if let Some(hunk) = our_hunks.get(our_index).filter(|hunk| hunk.0.start <= end) {
+                end = end.max(hunk.0.end);
+                our_index += 1;
+            } else if let Some(hunk) = their_hunks.get(their_index).filter(|hunk| hunk.0.start <= end)
+            {
+                end = end.max(hunk.0.end);
+                their_index += 1;
+            } else {
+                break;
+            }
+        }
+        lines.extend_from_slice(&base_lines[base_line..start]);
+        base_line = end;
+        let our_range = side_range(&our_hunks[our_start..our_index], start..end);
+        let their_range = side_range(&their_hunks[their_start..their_index], start..end);
+        match (our_range, their_range) {
+            (Some(our_range), None) => lines.extend_from_slice(&our_lines[our_range]),
+            (None, Some(their_range)) => lines.extend_from_slice(&their_lines[their_range]),
+            (Some(our_range), Some(their_range)) => {
+                if our_lines[our_range.clone()] == their_lines[their_range.clone()] {
+                    lines.extend_from_slice(&our_lines[our_range]);
+                } else {
+                    conflict_count += 1;
+                    lines.push(&start_marker);
+                    lines.extend_from_slice(&our_lines[our_range]);
+                    lines.push(CONFLICT_SEPARATOR_MARKER);
+                    lines.extend_from_slice(&their_lines[their_range]);
+                    lines.push(&end_marker);
+                }
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    lines.extend_from_slice(&base_lines[base_line..]);
+    Merge {
+        text: Text::from(lines.join("\n")),
+        conflict_count,
+    }
+}
+
+// Returns the lines of one side that correspond to the given base lines, or `None` if that side
+// did not change them.
+fn side_range(
+    hunks: &[(Range<usize>, Range<usize>)],
+    base_range: Range<usize>,
+) -> Option<Range<usize>> {
+    let first = hunks.first()?;
+    let last = hunks.last().unwrap();
+    Some(
+        first.1.start - (first.0.start - base_range.start)
+            ..last.1.end + (base_range.end - last.0.end),
+    )
+}
+
+/// A conflict between conflict markers in a text.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Conflict {
+    /// The line of the start marker. The lines after it up to the separator are ours.
+    pub start_line: usize,
+    /// The line of the separator marker. The lines after it up to the end marker are theirs.
+    pub separator_line: usize,
+    /// The line of the end marker.
+    pub end_line: usize,
+}
+
+impl Conflict {
+    pub fn our_lines(self) -> Range<usize> {
+        self.start_line + 1..self.separator_line
+    }
+
+    pub fn their_lines(self) -> Range<usize> {
+        self.separator_line + 1..self.end_line
+    }
+
+    pub fn line_range(self) -> Range<usize> {
+        self.start_line..self.end_line + 1
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    Both,
+}
+
+/// Finds the conflicts in a text, such as the ones written by `merge` or by git.
+pub fn find_conflicts(text: &Text) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut start_line = None;
+    let mut separator_line = None;
+    for (line_index, line) in text.as_lines().iter().enumerate() {
+        if line.starts_with(CONFLICT_START_MARKER) {
+            start_line = Some(line_index);
+            separator_line = None;
+        } else if line.starts_with(CONFLICT_SEPARATOR_MARKER) && start_line.is_some() {
+            separator_line = Some(line_index);
+        } else if line.starts_with(CONFLICT_END_MARKER) {
+            if let (Some(start_line), Some(separator_line)) = (start_line, separator_line) {
+                conflicts.push(Conflict {
+                    start_line,
+                    separator_line,
+                    end_line: line_index,
+                });
+            }
+            start_line = None;
+            separator_line = None;
+        }
+    }
+    conflicts
+}
@@ -0,0 +1,125 @@
+use {
+    crate::{text::Position, Document},
+    std::ops::Range,
+};
+
+/// Provides the items shown in the completion popup.
+pub trait CompletionProvider {
+    /// Returns the completion items for the word that ends at `position`. The editor filters and
+    /// sorts the items against that word, so a provider may return every item that applies.
+    fn completions(&self, document: &Document, position: Position) -> Vec<CompletionItem>;
+}
+
+/// Provides the signature shown while typing the arguments of a call.
+pub trait SignatureHelpProvider {
+    fn signature_help(&self, document: &Document, position: Position) -> Option<SignatureHelp>;
+}
+
+/// Provides the tooltip shown while hovering over the text.
+pub trait HoverProvider {
+    fn hover(&self, document: &Document, position: Position) -> Option<Hover>;
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<String>,
+    /// The text that replaces the word being completed, in the syntax parsed by `Snippet`.
+    pub insert_text: String,
+}
+
+impl CompletionItem {
+    pub fn new(label: impl Into<String>, kind: CompletionKind) -> Self {
+        let label = label.into();
+        Self {
+            insert_text: label.replace('\\', "\\\\").replace('$', "\\$"),
+            label,
+            kind,
+            detail: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CompletionKind {
+    Word,
+    Keyword,
+    Function,
+    Type,
+    Variable,
+    Snippet,
+}
+
+impl CompletionKind {
+    /// A short tag shown next to the label in the completion popup.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::Word => "abc",
+            Self::Keyword => "key",
+            Self::Function => "fn",
+            Self::Type => "type",
+            Self::Variable => "var",
+            Self::Snippet => "snip",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SignatureHelp {
+    /// The signature, for instance `fn add(a: u32, b: u32) -> u32`.
+    pub label: String,
+    /// The byte ranges of the parameters in `label`.
+    pub parameters: Vec<Range<usize>>,
+    pub active_parameter: Option<usize>,
+}
+
+impl SignatureHelp {
+    /// Returns the signature as markdown, with the active parameter in bold.
+    pub fn to_markdown(&self) -> String {
+        let Some(range) = self
+            .active_parameter
+            .and_then(|index| self.parameters.get(index))
+            .filter(|range| self.label.get(range.start..range.end).is_some())
+        else {
+            return format!("`{}`", self.label);
+        };
+        let code = |text: &str| {
+            if text.is_empty() {
+                String::new()
+            } else {
+                format!("`{}`", text)
+            }
+        };
+        format!(
+            "{}**{}**{}",
+            code(&self.label[..range.start]),
+            code(&self.label[range.clone()]),
+            code(&self.label[range.end..])
+        )
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Hover {
+    pub markdown: String,
+}
+
+/// Returns whether the given char can be part of a word that is completed.
+pub fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+/// Returns the byte range of the word in `line` that contains or ends at `byte_index`.
+pub fn word_range(line: &str, byte_index: usize) -> Range<usize> {
+    let start = line[..byte_index]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, char)| is_word_char(char))
+        .last()
+        .map_or(byte_index, |(index, _)| index);
+    let end = line[byte_index..]
+        .find(|char: char| !is_word_char(char))
+        .map_or(line.len(), |index| byte_index + index);
+    start..end
+}
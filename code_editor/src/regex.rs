@@ -0,0 +1,651 @@
+//! A small regular expression engine for searching within a single line.
+//!
+//! Patterns are compiled to a program that is run by a Pike VM, so matching takes time linear in
+//! the length of the line regardless of the pattern. Supported are literals, `.`, character
+//! classes, the `\d \w \s` shorthands and their negations, `^ $ \b \B`, capturing and
+//! non-capturing groups, alternation, and greedy and lazy `* + ? {n,m}` repetition.
+
+use std::mem;
+
+const MAX_REPEAT: u32 = 1000;
+
+#[derive(Clone, Debug)]
+pub struct Regex {
+    insts: Vec<Inst>,
+    slot_count: usize,
+}
+
+impl Regex {
+    pub fn new(pattern: &str, case_insensitive: bool) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            index: 0,
+            capture_count: 0,
+        };
+        let node = parser.parse_alternation()?;
+        if parser.index < parser.chars.len() {
+            return Err(format!("unmatched `)` at {}", parser.index));
+        }
+        let mut compiler = Compiler {
+            insts: Vec::new(),
+            case_insensitive,
+        };
+        compiler.push(Inst::Save(0));
+        compiler.compile(&node);
+        compiler.push(Inst::Save(1));
+        compiler.push(Inst::Match);
+        Ok(Self {
+            insts: compiler.insts,
+            slot_count: (parser.capture_count + 1) * 2,
+        })
+    }
+
+    /// Escapes all characters that have a special meaning in a pattern.
+    pub fn escape(string: &str) -> String {
+        let mut escaped = String::with_capacity(string.len());
+        for char in string.chars() {
+            if "\\.+*?()|[]{}^$".contains(char) {
+                escaped.push('\\');
+            }
+            escaped.push(char);
+        }
+        escaped
+    }
+
+    /// Finds the leftmost match that starts at or after byte `start`.
+    pub fn find_at(&self, text: &str, start: usize) -> Option<Captures> {
+        let mut current = Threads::new(self.insts.len());
+        let mut next = Threads::new(self.insts.len());
+        let mut matched = None;
+        let mut position = start;
+        loop {
+            if matched.is_none() {
+                self.add_thread(&mut current, 0, position, vec![None; self.slot_count], text);
+            }
+            if current.threads.is_empty() && matched.is_some() {
+                break;
+            }
+            let char = text[position..].chars().next();
+            let next_position = position + char.map_or(0, |char| char.len_utf8());
+            for (pc, slots) in current.threads.drain(..) {
+                let is_match = match &self.insts[pc] {
+                    Inst::Match => {
+                        matched = Some(slots);
+                        // Threads after this one have a lower priority.
+                        break;
+                    }
+                    Inst::Char(expected) => {
+                        char.is_some_and(|char| char == *expected || fold_case(char) == *expected)
+                    }
+                    Inst::CharCaseSensitive(expected) => char == Some(*expected),
+                    Inst::Any => char.is_some(),
+                    Inst::Class(class) => char.is_some_and(|char| class.matches(char)),
+                    _ => unreachable!(),
+                };
+                if is_match {
+                    self.add_thread(&mut next, pc + 1, next_position, slots, text);
+                }
+            }
+            current.visited.clear();
+            mem::swap(&mut current, &mut next);
+            if char.is_none() {
+                break;
+            }
+            position = next_position;
+        }
+        matched.map(|slots| Captures { slots })
+    }
+
+    fn add_thread(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        position: usize,
+        mut slots: Vec<Option<usize>>,
+        text: &str,
+    ) {
+        if !threads.visited.insert(pc) {
+            return;
+        }
+        match self.insts[pc] {
+            Inst::Jump(target) => self.add_thread(threads, target, position, slots, text),
+            Inst::Split(first, second) => {
+                self.add_thread(threads, first, position, slots.clone(), text);
+                self.add_thread(threads, second, position, slots, text);
+            }
+            Inst::Save(slot) => {
+                slots[slot] = Some(position);
+                self.add_thread(threads, pc + 1, position, slots, text);
+            }
+            Inst::Assert(assertion) => {
+                if assertion.holds(text, position) {
+                    self.add_thread(threads, pc + 1, position, slots, text);
+                }
+            }
+            _ => threads.threads.push((pc, slots)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Captures {
+    slots: Vec<Option<usize>>,
+}
+
+impl Captures {
+    pub fn start(&self) -> usize {
+        self.slots[0].unwrap()
+    }
+
+    pub fn end(&self) -> usize {
+        self.slots[1].unwrap()
+    }
+
+    /// Returns the byte range of capture group `index`, with 0 being the entire match.
+    pub fn get(&self, index: usize) -> Option<(usize, usize)> {
+        match (self.slots.get(index * 2)?, self.slots.get(index * 2 + 1)?) {
+            (Some(start), Some(end)) => Some((*start, *end)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Inst {
+    Char(char),
+    CharCaseSensitive(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    Save(usize),
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+impl Assertion {
+    fn holds(self, text: &str, position: usize) -> bool {
+        match self {
+            Self::Start => position == 0,
+            Self::End => position == text.len(),
+            Self::WordBoundary | Self::NotWordBoundary => {
+                let before = text[..position]
+                    .chars()
+                    .next_back()
+                    .is_some_and(is_word_char);
+                let after = text[position..].chars().next().is_some_and(is_word_char);
+                (before != after) == (self == Self::WordBoundary)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Class {
+    items: Vec<ClassItem>,
+    is_negated: bool,
+    case_insensitive: bool,
+}
+
+impl Class {
+    fn matches(&self, char: char) -> bool {
+        let contains = |char| self.items.iter().any(|item| item.matches(char));
+        let mut is_match = contains(char);
+        if !is_match && self.case_insensitive {
+            is_match = char.to_lowercase().chain(char.to_uppercase()).any(contains);
+        }
+        is_match != self.is_negated
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(self, char: char) -> bool {
+        match self {
+            Self::Range(start, end) => start <= char && char <= end,
+            Self::Digit(is_negated) => char.is_ascii_digit() != is_negated,
+            Self::Word(is_negated) => is_word_char(char) != is_negated,
+            Self::Space(is_negated) => char.is_whitespace() != is_negated,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        is_greedy: bool,
+    },
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    capture_count: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn eat(&mut self, char: char) -> bool {
+        if self.peek() == Some(char) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alternate(alternatives)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(char) = self.peek() {
+            if char == '|' || char == ')' {
+                break;
+            }
+            let node = self.parse_atom()?;
+            nodes.push(self.parse_repeat(node)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        let start = self.index;
+        let char = self.peek().unwrap();
+        self.index += 1;
+        Ok(match char {
+            '(' => {
+                let capture_index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(format!("unsupported group syntax at {}", start));
+                    }
+                    None
+                } else {
+                    self.capture_count += 1;
+                    Some(self.capture_count)
+                };
+                let node = self.parse_alternation()?;
+                if !self.eat(')') {
+                    return Err(format!("unclosed `(` at {}", start));
+                }
+                Node::Group(Box::new(node), capture_index)
+            }
+            '[' => self.parse_class(start)?,
+            '.' => Node::Any,
+            '^' => Node::Assert(Assertion::Start),
+            '$' => Node::Assert(Assertion::End),
+            '\\' => match self.parse_escape()? {
+                Escape::Char(char) => Node::Char(char),
+                Escape::Item(item) => Node::Class(vec![item], false),
+                Escape::Assert(assertion) => Node::Assert(assertion),
+            },
+            '*' | '+' | '?' => return Err(format!("nothing to repeat at {}", start)),
+            '{' if self.parse_bounds().is_some() => {
+                return Err(format!("nothing to repeat at {}", start))
+            }
+            char => Node::Char(char),
+        })
+    }
+
+    fn parse_escape(&mut self) -> Result<Escape, String> {
+        let Some(char) = self.peek() else {
+            return Err("pattern ends with a `\\`".to_string());
+        };
+        self.index += 1;
+        Ok(match char {
+            'd' => Escape::Item(ClassItem::Digit(false)),
+            'D' => Escape::Item(ClassItem::Digit(true)),
+            'w' => Escape::Item(ClassItem::Word(false)),
+            'W' => Escape::Item(ClassItem::Word(true)),
+            's' => Escape::Item(ClassItem::Space(false)),
+            'S' => Escape::Item(ClassItem::Space(true)),
+            'b' => Escape::Assert(Assertion::WordBoundary),
+            'B' => Escape::Assert(Assertion::NotWordBoundary),
+            't' => Escape::Char('\t'),
+            'n' => Escape::Char('\n'),
+            'r' => Escape::Char('\r'),
+            '0'..='9' => return Err("backreferences are not supported".to_string()),
+            char if char.is_alphanumeric() => return Err(format!("unknown escape `\\{}`", char)),
+            char => Escape::Char(char),
+        })
+    }
+
+    fn parse_class(&mut self, start: usize) -> Result<Node, String> {
+        let is_negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut is_first = true;
+        loop {
+            let Some(char) = self.peek() else {
+                return Err(format!("unclosed `[` at {}", start));
+            };
+            self.index += 1;
+            if char == ']' && !is_first {
+                break;
+            }
+            is_first = false;
+            let first = if char == '\\' {
+                match self.parse_escape()? {
+                    Escape::Char(char) => char,
+                    Escape::Item(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                    Escape::Assert(_) => return Err("`\\b` is not allowed in a class".to_string()),
+                }
+            } else {
+                char
+            };
+            if self.peek() == Some('-')
+                && self
+                    .chars
+                    .get(self.index + 1)
+                    .is_some_and(|&char| char != ']')
+            {
+                self.index += 1;
+                let mut last = self.chars[self.index];
+                self.index += 1;
+                if last == '\\' {
+                    match self.parse_escape()? {
+                        Escape::Char(char) => last = char,
+                        _ => return Err(format!("invalid class range at {}", self.index)),
+                    }
+                }
+                if last < first {
+                    return Err(format!("invalid class range `{}-{}`", first, last));
+                }
+                items.push(ClassItem::Range(first, last));
+            } else {
+                items.push(ClassItem::Range(first, first));
+            }
+        }
+        Ok(Node::Class(items, is_negated))
+    }
+
+    fn parse_repeat(&mut self, node: Node) -> Result<Node, String> {
+        let mut node = node;
+        loop {
+            let start = self.index;
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => match self.parse_bounds() {
+                    Some(bounds) => bounds,
+                    None => return Ok(node),
+                },
+                _ => return Ok(node),
+            };
+            if self.index == start {
+                self.index += 1;
+            }
+            if max.map_or(min, |max| max) > MAX_REPEAT {
+                return Err(format!(
+                    "repetition count above {} at {}",
+                    MAX_REPEAT, start
+                ));
+            }
+            if max.is_some_and(|max| max < min) {
+                return Err(format!("invalid repetition range at {}", start));
+            }
+            let is_greedy = !self.eat('?');
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                is_greedy,
+            };
+        }
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`, leaving the index untouched if there is no valid bound,
+    /// in which case the `{` is a literal.
+    fn parse_bounds(&mut self) -> Option<(u32, Option<u32>)> {
+        let start = self.index;
+        let bounds = (|| {
+            if !self.eat('{') {
+                return None;
+            }
+            let min = self.parse_number()?;
+            let max = if self.eat(',') {
+                if self.peek() == Some('}') {
+                    None
+                } else {
+                    Some(self.parse_number()?)
+                }
+            } else {
+                Some(min)
+            };
+            if !self.eat('}') {
+                return None;
+            }
+            Some((min, max))
+        })();
+        if bounds.is_none() {
+            self.index = start;
+        }
+        bounds
+    }
+
+    fn parse_number(&mut self) -> Option<u32> {
+        let start = self.index;
+        while self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            self.index += 1;
+        }
+        self.chars[start..self.index]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+}
+
+enum Escape {
+    Char(char),
+    Item(ClassItem),
+    Assert(Assertion),
+}
+
+struct Compiler {
+    insts: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> usize {
+        self.insts.push(inst);
+        self.insts.len() - 1
+    }
+
+    fn compile(&mut self, node: &Node) {
+        match node {
+            Node::Empty => {}
+            Node::Char(char) => {
+                let inst = if self.case_insensitive {
+                    Inst::Char(fold_case(*char))
+                } else {
+                    Inst::CharCaseSensitive(*char)
+                };
+                self.push(inst);
+            }
+            Node::Any => {
+                self.push(Inst::Any);
+            }
+            Node::Class(items, is_negated) => {
+                self.push(Inst::Class(Class {
+                    items: items.clone(),
+                    is_negated: *is_negated,
+                    case_insensitive: self.case_insensitive,
+                }));
+            }
+            Node::Assert(assertion) => {
+                self.push(Inst::Assert(*assertion));
+            }
+            Node::Group(node, capture_index) => match capture_index {
+                Some(capture_index) => {
+                    self.push(Inst::Save(capture_index * 2));
+                    self.compile(node);
+                    self.push(Inst::Save(capture_index * 2 + 1));
+                }
+                None => self.compile(node),
+            },
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node);
+                }
+            }
+            Node::Alternate(nodes) => {
+                let mut jumps = Vec::new();
+                for (index, node) in nodes.iter().enumerate() {
+                    if index + 1 == nodes.len() {
+                        self.compile(node);
+                    } else {
+                        let split = self.push(Inst::Split(0, 0));
+                        self.compile(node);
+                        jumps.push(self.push(Inst::Jump(0)));
+                        let next = self.insts.len();
+                        self.insts[split] = Inst::Split(split + 1, next);
+                    }
+                }
+                let end = self.insts.len();
+                for jump in jumps {
+                    self.insts[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                is_greedy,
+            } => {
+                for _ in 0..*min {
+                    self.compile(node);
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0));
+                        self.compile(node);
+                        self.push(Inst::Jump(split));
+                        let end = self.insts.len();
+                        self.insts[split] = self.split(split + 1, end, *is_greedy);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0)));
+                            self.compile(node);
+                        }
+                        let end = self.insts.len();
+                        for split in splits {
+                            self.insts[split] = self.split(split + 1, end, *is_greedy);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn split(&self, body: usize, exit: usize, is_greedy: bool) -> Inst {
+        if is_greedy {
+            Inst::Split(body, exit)
+        } else {
+            Inst::Split(exit, body)
+        }
+    }
+}
+
+struct Threads {
+    threads: Vec<(usize, Vec<Option<usize>>)>,
+    visited: SparseSet,
+}
+
+impl Threads {
+    fn new(capacity: usize) -> Self {
+        Self {
+            threads: Vec::new(),
+            visited: SparseSet::new(capacity),
+        }
+    }
+}
+
+/// A set of instruction indices that can be cleared in constant time.
+struct SparseSet {
+    dense: Vec<usize>,
+    sparse: Vec<usize>,
+}
+
+impl SparseSet {
+    fn new(capacity: usize) -> Self {
+        Self {
+            dense: Vec::with_capacity(capacity),
+            sparse: vec![0; capacity],
+        }
+    }
+
+    fn insert(&mut self, value: usize) -> bool {
+        let index = self.sparse[value];
+        if index < self.dense.len() && self.dense[index] == value {
+            return false;
+        }
+        self.sparse[value] = self.dense.len();
+        self.dense.push(value);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+fn fold_case(char: char) -> char {
+    let mut lower = char.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => char,
+    }
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
@@ -0,0 +1,736 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+    mem,
+    ops::{Index, Range},
+    rc::Rc,
+    slice,
+};
+
+const MAX_LEAF_LINE_COUNT: usize = 64;
+const MAX_CHILD_COUNT: usize = 16;
+
+/// A sequence of lines stored as a B-tree, so that looking up, inserting, and removing lines
+/// takes O(log n) time regardless of where in the sequence they are.
+///
+/// Nodes are reference counted and copied on write, so cloning a rope is O(1) and a clone only
+/// copies the nodes along the paths that are changed afterwards.
+///
+/// The lines of a text are strings, but a rope can also hold data that is kept for each line of a
+/// text, such as its tokens, so that it can be edited along with the text just as cheaply.
+#[derive(Clone)]
+pub struct Rope<T = String> {
+    root: Child<T>,
+}
+
+/// A line of a rope.
+pub trait RopeLine: Clone {
+    /// The length of the line in bytes. Lines that are not text have no length.
+    fn byte_count(&self) -> usize {
+        0
+    }
+}
+
+impl RopeLine for String {
+    fn byte_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: RopeLine> Rope<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_lines(lines: Vec<T>) -> Self {
+        Self {
+            root: build(leaves(lines)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.line_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the sum of the lengths of all lines in bytes, not counting line breaks.
+    pub fn byte_count(&self) -> usize {
+        self.root.byte_count
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut node = &*self.root.node;
+        let mut index = index;
+        loop {
+            match node {
+                Node::Leaf(lines) => return lines.get(index),
+                Node::Internal(children) => {
+                    let (child_index, child_start) = find_child(children, index);
+                    node = &children[child_index].node;
+                    index -= child_start;
+                }
+            }
+        }
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.range(0..self.len())
+    }
+
+    /// Returns an iterator over the lines in the given range.
+    pub fn range(&self, range: Range<usize>) -> Iter<'_, T> {
+        assert!(range.start <= range.end && range.end <= self.len());
+        let mut stack = Vec::new();
+        let mut node = &*self.root.node;
+        let mut index = range.start;
+        let leaf = loop {
+            match node {
+                Node::Leaf(lines) => break lines[index.min(lines.len())..].iter(),
+                Node::Internal(children) => {
+                    let (child_index, child_start) = find_child(children, index);
+                    stack.push(children[child_index + 1..].iter());
+                    node = &children[child_index].node;
+                    index -= child_start;
+                }
+            }
+        };
+        Iter {
+            stack,
+            leaf,
+            remaining: range.end - range.start,
+        }
+    }
+
+    /// Calls `f` with a mutable reference to the line at `index`.
+    pub fn update_line<F>(&mut self, index: usize, f: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        assert!(index < self.len());
+        update_line(&mut self.root, index, f);
+    }
+
+    /// Replaces the lines in `range` with `lines`.
+    pub fn splice<I>(&mut self, range: Range<usize>, lines: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        assert!(range.start <= range.end && range.end <= self.len());
+        let mut lines: Vec<_> = lines.into_iter().collect();
+        let root = mem::take(&mut self.root);
+        let children = splice(root.node, range.start, range.end, &mut lines);
+        let mut root = build(children);
+        while let Node::Internal(children) = &*root.node {
+            if children.len() != 1 {
+                break;
+            }
+            root = children[0].clone();
+        }
+        self.root = root;
+    }
+}
+
+impl<T: RopeLine> Default for Rope<T> {
+    fn default() -> Self {
+        Self {
+            root: Child::new(Node::Leaf(Vec::new())),
+        }
+    }
+}
+
+impl<T: RopeLine + fmt::Debug> fmt::Debug for Rope<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: RopeLine + Eq> Eq for Rope<T> {}
+
+impl<T: RopeLine + PartialEq> PartialEq for Rope<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.byte_count() == other.byte_count()
+            && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: RopeLine + Hash> Hash for Rope<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for line in self.iter() {
+            line.hash(state);
+        }
+    }
+}
+
+impl<T: RopeLine> Index<usize> for Rope<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match self.get(index) {
+            Some(line) => line,
+            None => panic!(
+                "line index {} out of range for rope with {} lines",
+                index,
+                self.len()
+            ),
+        }
+    }
+}
+
+impl<'a, T: RopeLine> IntoIterator for &'a Rope<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: RopeLine> FromIterator<T> for Rope<T> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        Self::from_lines(iter.into_iter().collect())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T = String> {
+    stack: Vec<slice::Iter<'a, Child<T>>>,
+    leaf: slice::Iter<'a, T>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        loop {
+            if let Some(line) = self.leaf.next() {
+                self.remaining -= 1;
+                return Some(line);
+            }
+            loop {
+                match self.stack.last_mut()?.next() {
+                    Some(child) => match &*child.node {
+                        Node::Leaf(lines) => {
+                            self.leaf = lines.iter();
+                            break;
+                        }
+                        Node::Internal(children) => self.stack.push(children.iter()),
+                    },
+                    None => {
+                        self.stack.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+#[derive(Clone, Debug)]
+enum Node<T = String> {
+    Leaf(Vec<T>),
+    Internal(Vec<Child<T>>),
+}
+
+impl<T> Node<T> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Leaf(lines) => lines.len(),
+            Self::Internal(children) => children.len(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match self {
+            Self::Leaf(_) => MAX_LEAF_LINE_COUNT,
+            Self::Internal(_) => MAX_CHILD_COUNT,
+        }
+    }
+
+    fn append(&mut self, other: Node<T>) {
+        match (self, other) {
+            (Self::Leaf(lines), Self::Leaf(other_lines)) => lines.extend(other_lines),
+            (Self::Internal(children), Self::Internal(other_children)) => {
+                children.extend(other_children)
+            }
+            _ => panic!("cannot append nodes of different heights"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Child<T = String> {
+    line_count: usize,
+    byte_count: usize,
+    node: Rc<Node<T>>,
+}
+
+impl<T: RopeLine> Child<T> {
+    fn new(node: Node<T>) -> Self {
+        let (line_count, byte_count) = match &node {
+            Node::Leaf(lines) => (
+                lines.len(),
+                lines.iter().map(|line| line.byte_count()).sum(),
+            ),
+            Node::Internal(children) => children.iter().fold((0, 0), |(lines, bytes), child| {
+                (lines + child.line_count, bytes + child.byte_count)
+            }),
+        };
+        Self {
+            line_count,
+            byte_count,
+            node: Rc::new(node),
+        }
+    }
+}
+
+impl<T: RopeLine> Default for Child<T> {
+    fn default() -> Self {
+        Self::new(Node::Leaf(Vec::new()))
+    }
+}
+
+/// Finds the child that contains the line at `index`, along with the index of its first line.
+/// An index past the end maps to the last child.
+fn find_child<T>(children: &[Child<T>], index: usize) -> (usize, usize) {
+    let mut start = 0;
+    for (child_index, child) in children.iter().enumerate() {
+        if index < start + child.line_count || child_index == children.len() - 1 {
+            return (child_index, start);
+        }
+        start += child.line_count;
+    }
+    unreachable!()
+}
+
+fn update_line<T: RopeLine, F>(child: &mut Child<T>, index: usize, f: F)
+where
+    F: FnOnce(&mut T),
+{
+    match Rc::make_mut(&mut child.node) {
+        Node::Leaf(lines) => {
+            let line = &mut lines[index];
+            let old_byte_count = line.byte_count();
+            f(line);
+            child.byte_count = child.byte_count - old_byte_count + line.byte_count();
+        }
+        Node::Internal(children) => {
+            let (child_index, child_start) = find_child(children, index);
+            let grandchild = &mut children[child_index];
+            let old_byte_count = grandchild.byte_count;
+            update_line(grandchild, index - child_start, f);
+            child.byte_count = child.byte_count - old_byte_count + grandchild.byte_count;
+        }
+    }
+}
+
+/// Replaces the lines in `start..end` of `node` with `lines`, and returns the nodes, with the
+/// same height as `node`, that should take its place. The lines are moved out of `lines` by the
+/// first leaf they are inserted into.
+fn splice<T: RopeLine>(
+    mut node: Rc<Node<T>>,
+    start: usize,
+    end: usize,
+    lines: &mut Vec<T>,
+) -> Vec<Child<T>> {
+    match Rc::make_mut(&mut node) {
+        Node::Leaf(leaf_lines) => {
+            let mut leaf_lines = mem::take(leaf_lines);
+            leaf_lines.splice(start..end, lines.drain(..));
+            leaves(leaf_lines)
+        }
+        Node::Internal(children) => {
+            let children = mem::take(children);
+            let child_count = children.len();
+            let mut new_children = Vec::with_capacity(child_count);
+            let mut child_start = 0;
+            for (child_index, child) in children.into_iter().enumerate() {
+                let child_end = child_start + child.line_count;
+                let is_last = child_index == child_count - 1;
+                let is_affected = if start == end {
+                    !lines.is_empty() && start >= child_start && (start < child_end || is_last)
+                } else {
+                    start < child_end && end > child_start
+                };
+                if !is_affected {
+                    new_children.push(child);
+                } else if !(start <= child_start && end >= child_end && lines.is_empty()) {
+                    new_children.extend(splice(
+                        child.node,
+                        start.max(child_start) - child_start,
+                        end.min(child_end).max(child_start) - child_start,
+                        lines,
+                    ));
+                }
+                child_start = child_end;
+            }
+            group(merge_underfull(new_children))
+        }
+    }
+}
+
+/// Merges adjacent nodes that together fit in a single node.
+fn merge_underfull<T: RopeLine>(children: Vec<Child<T>>) -> Vec<Child<T>> {
+    let mut merged: Vec<Child<T>> = Vec::with_capacity(children.len());
+    for child in children {
+        if let Some(prev) = merged.last_mut() {
+            if prev.node.len() + child.node.len() <= prev.node.capacity() {
+                let mut prev_node = mem::take(prev).node;
+                Rc::make_mut(&mut prev_node).append(Rc::unwrap_or_clone(child.node));
+                *prev = Child::new(Rc::unwrap_or_clone(prev_node));
+                continue;
+            }
+        }
+        merged.push(child);
+    }
+    merged
+}
+
+fn leaves<T: RopeLine>(lines: Vec<T>) -> Vec<Child<T>> {
+    if lines.is_empty() {
+        return Vec::new();
+    }
+    if lines.len() <= MAX_LEAF_LINE_COUNT {
+        return vec![Child::new(Node::Leaf(lines))];
+    }
+    let mut leaves = Vec::with_capacity(lines.len().div_ceil(MAX_LEAF_LINE_COUNT));
+    let mut lines = lines.into_iter();
+    loop {
+        let chunk: Vec<_> = lines.by_ref().take(MAX_LEAF_LINE_COUNT).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        leaves.push(Child::new(Node::Leaf(chunk)));
+    }
+    leaves
+}
+
+/// Groups nodes into parent nodes with at most `MAX_CHILD_COUNT` children each.
+fn group<T: RopeLine>(children: Vec<Child<T>>) -> Vec<Child<T>> {
+    if children.is_empty() {
+        return Vec::new();
+    }
+    if children.len() <= MAX_CHILD_COUNT {
+        return vec![Child::new(Node::Internal(children))];
+    }
+    let mut parents = Vec::with_capacity(children.len().div_ceil(MAX_CHILD_COUNT));
+    let mut children = children.into_iter();
+    loop {
+        let chunk: Vec<_> = children.by_ref().take(MAX_CHILD_COUNT).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        parents.push(Child::new(Node::Internal(chunk)));
+    }
+    parents
+}
+
+/// Builds a single root from nodes of the same height, adding levels as needed.
+fn build<T: RopeLine>(mut children: Vec<Child<T>>) -> Child<T> {
+    loop {
+        match children.len() {
+            0 => return Child::default(),
+            1 => return children.pop().unwrap(),
+            _ => children = group(children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::text::{Change, Length, Position, Text},
+    };
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        /// Picks a count close to where leaves and internal nodes fill up, most of the time.
+        fn count(&mut self, max: usize) -> usize {
+            const COUNTS: &[usize] = &[
+                0,
+                1,
+                2,
+                MAX_LEAF_LINE_COUNT - 1,
+                MAX_LEAF_LINE_COUNT,
+                MAX_LEAF_LINE_COUNT + 1,
+                2 * MAX_LEAF_LINE_COUNT,
+                MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT - 1,
+                MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT,
+                MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT + 1,
+            ];
+            let count = if self.below(4) == 0 {
+                self.below(max + 1)
+            } else {
+                COUNTS[self.below(COUNTS.len())]
+            };
+            count.min(max)
+        }
+    }
+
+    fn lines(start: usize, count: usize) -> Vec<String> {
+        (start..start + count)
+            .map(|index| "x".repeat(index % 7))
+            .collect()
+    }
+
+    /// Returns the height of the tree below `child`, after checking that its cached counts are
+    /// right, that no node is empty or over capacity, and that all leaves are at the same depth.
+    fn check_node(child: &Child) -> usize {
+        let node = &*child.node;
+        assert!(
+            node.len() <= node.capacity(),
+            "node with {} entries",
+            node.len()
+        );
+        match node {
+            Node::Leaf(lines) => {
+                assert_eq!(child.line_count, lines.len());
+                assert_eq!(
+                    child.byte_count,
+                    lines.iter().map(|line| line.len()).sum::<usize>()
+                );
+                0
+            }
+            Node::Internal(children) => {
+                assert!(!children.is_empty());
+                let heights: Vec<_> = children
+                    .iter()
+                    .inspect(|child| assert!(child.line_count > 0, "empty node"))
+                    .map(check_node)
+                    .collect();
+                assert!(
+                    heights.iter().all(|height| *height == heights[0]),
+                    "uneven leaves"
+                );
+                let expected = Child::new(node.clone());
+                assert_eq!(child.line_count, expected.line_count);
+                assert_eq!(child.byte_count, expected.byte_count);
+                heights[0] + 1
+            }
+        }
+    }
+
+    fn check(rope: &Rope, model: &[String], rng: &mut Rng) -> usize {
+        let height = check_node(&rope.root);
+        if let Node::Internal(children) = &*rope.root.node {
+            assert!(children.len() > 1, "root with a single child");
+        }
+        assert_eq!(rope.len(), model.len());
+        assert_eq!(
+            rope.byte_count(),
+            model.iter().map(|line| line.len()).sum::<usize>()
+        );
+        assert!(rope.iter().eq(model.iter()));
+        assert_eq!(rope.first(), model.first());
+        assert_eq!(rope.last(), model.last());
+        assert_eq!(rope.get(model.len()), None);
+        for _ in 0..8 {
+            if model.is_empty() {
+                break;
+            }
+            let index = rng.below(model.len());
+            assert_eq!(rope[index], model[index]);
+            let end = index + rng.below(model.len() - index + 1);
+            let range = rope.range(index..end);
+            assert_eq!(range.len(), end - index);
+            assert!(range.eq(model[index..end].iter()));
+        }
+        height
+    }
+
+    #[test]
+    fn splices_behave_like_a_vec() {
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        let max_len = 4 * MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT;
+        for _ in 0..20 {
+            let mut model = lines(0, rng.count(max_len));
+            let mut rope = Rope::from_lines(model.clone());
+            check(&rope, &model, &mut rng);
+            for step in 0..50 {
+                let start = rng.below(model.len() + 1);
+                let end = start + rng.count(model.len() - start);
+                let insert = lines(step, rng.count(max_len - (model.len() - (end - start))));
+                let before = (rope.clone(), model.clone());
+
+                rope.splice(start..end, insert.clone());
+                model.splice(start..end, insert);
+                check(&rope, &model, &mut rng);
+                // the clone shares nodes with the rope, but didn't change with it
+                check(&before.0, &before.1, &mut rng);
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_split_and_merge_at_their_capacity() {
+        let mut rng = Rng(1);
+        let mut model = lines(0, MAX_LEAF_LINE_COUNT);
+        let mut rope = Rope::from_lines(model.clone());
+        assert_eq!(check(&rope, &model, &mut rng), 0);
+
+        // one more line splits the full leaf
+        rope.splice(10..10, lines(0, 1));
+        model.splice(10..10, lines(0, 1));
+        assert_eq!(check(&rope, &model, &mut rng), 1);
+
+        // and removing it merges the halves back into a single leaf
+        rope.splice(10..11, []);
+        model.splice(10..11, []);
+        assert_eq!(check(&rope, &model, &mut rng), 0);
+        assert!(matches!(&*rope.root.node, Node::Leaf(_)));
+
+        // a root with more than the maximum number of children gets a level added
+        let count = MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT;
+        rope.splice(0..model.len(), lines(0, count));
+        model = lines(0, count);
+        assert_eq!(check(&rope, &model, &mut rng), 1);
+        rope.splice(count..count, lines(0, 1));
+        model.splice(count..count, lines(0, 1));
+        assert_eq!(check(&rope, &model, &mut rng), 2);
+
+        // and removed again once its children fit into one node
+        rope.splice(0..MAX_LEAF_LINE_COUNT, []);
+        model.splice(0..MAX_LEAF_LINE_COUNT, []);
+        assert_eq!(check(&rope, &model, &mut rng), 1);
+
+        rope.splice(0..model.len(), []);
+        assert!(rope.is_empty());
+        check(&rope, &[], &mut rng);
+    }
+
+    #[test]
+    fn updated_lines_keep_the_byte_count() {
+        let mut rng = Rng(2);
+        let mut model = lines(0, 3 * MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT);
+        let mut rope = Rope::from_lines(model.clone());
+        for _ in 0..200 {
+            let index = rng.below(model.len());
+            let suffix = "y".repeat(rng.below(4));
+            rope.update_line(index, |line| {
+                line.truncate(line.len() / 2);
+                line.push_str(&suffix);
+            });
+            let line = &mut model[index];
+            line.truncate(line.len() / 2);
+            line.push_str(&suffix);
+        }
+        check(&rope, &model, &mut rng);
+    }
+
+    /// Applies a change to lines the obvious way.
+    fn apply(model: &mut Vec<String>, change: &Change) {
+        match change {
+            Change::Insert(position, text) => {
+                let mut inserted = text.clone().into_lines();
+                let line = &model[position.line_index];
+                inserted[0].insert_str(0, &line[..position.byte_index]);
+                inserted
+                    .last_mut()
+                    .unwrap()
+                    .push_str(&line[position.byte_index..]);
+                model.splice(position.line_index..position.line_index + 1, inserted);
+            }
+            Change::Delete(start, length) => {
+                let end = *start + *length;
+                let line = format!(
+                    "{}{}",
+                    &model[start.line_index][..start.byte_index],
+                    &model[end.line_index][end.byte_index..]
+                );
+                model.splice(start.line_index..end.line_index + 1, [line]);
+            }
+        }
+    }
+
+    fn random_position(model: &[String], rng: &mut Rng) -> Position {
+        let line_index = rng.below(model.len());
+        Position {
+            line_index,
+            byte_index: rng.below(model[line_index].len() + 1),
+        }
+    }
+
+    #[test]
+    fn text_changes_behave_like_a_vec() {
+        let mut rng = Rng(3);
+        let mut model = lines(0, MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT);
+        let mut text = Text::from(model.join("\n"));
+        for _ in 0..500 {
+            let change = if rng.below(2) == 0 {
+                let count = rng.count(MAX_LEAF_LINE_COUNT * MAX_CHILD_COUNT) + 1;
+                Change::Insert(
+                    random_position(&model, &mut rng),
+                    Text::from(lines(0, count).join("\n")),
+                )
+            } else {
+                let start = random_position(&model, &mut rng);
+                let end_line_index =
+                    start.line_index + rng.count(model.len() - 1 - start.line_index);
+                let min_byte_index = if end_line_index == start.line_index {
+                    start.byte_index
+                } else {
+                    0
+                };
+                let end = Position {
+                    line_index: end_line_index,
+                    byte_index: min_byte_index
+                        + rng.below(model[end_line_index].len() - min_byte_index + 1),
+                };
+                Change::Delete(start, end - start)
+            };
+            apply(&mut model, &change);
+            text.apply_change(change);
+            assert_eq!(
+                text.length(),
+                Length {
+                    line_count: model.len() - 1,
+                    byte_count: model.last().unwrap().len(),
+                }
+            );
+            check(text.as_lines(), &model, &mut rng);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use {
+    crate::regex::{Captures, Regex},
+    std::ops::Range,
+};
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    pub is_case_sensitive: bool,
+    pub is_whole_word: bool,
+    pub is_regex: bool,
+}
+
+/// A compiled `SearchQuery`. Matches never span more than a single line.
+#[derive(Clone, Debug)]
+pub struct Searcher {
+    matcher: Matcher,
+    is_whole_word: bool,
+    is_regex: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Literal {
+        needle: String,
+        is_case_sensitive: bool,
+    },
+    Regex(Regex),
+}
+
+impl Searcher {
+    pub fn new(query: &SearchQuery) -> Result<Self, String> {
+        let matcher = if query.is_regex {
+            Matcher::Regex(Regex::new(&query.pattern, !query.is_case_sensitive)?)
+        } else if query.is_case_sensitive || query.pattern.is_ascii() {
+            Matcher::Literal {
+                needle: if query.is_case_sensitive {
+                    query.pattern.clone()
+                } else {
+                    query.pattern.to_ascii_lowercase()
+                },
+                is_case_sensitive: query.is_case_sensitive,
+            }
+        } else {
+            // Case folding non-ASCII text can change its length, so leave that to the regex engine.
+            Matcher::Regex(Regex::new(&Regex::escape(&query.pattern), true)?)
+        };
+        Ok(Self {
+            matcher,
+            is_whole_word: query.is_whole_word,
+            is_regex: query.is_regex,
+        })
+    }
+
+    /// Returns the byte ranges of all non-empty, non-overlapping matches in `line`.
+    pub fn find_all(&self, line: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let lowercase_line;
+        let haystack = match &self.matcher {
+            Matcher::Literal {
+                is_case_sensitive: false,
+                ..
+            } => {
+                lowercase_line = line.to_ascii_lowercase();
+                &lowercase_line
+            }
+            _ => line,
+        };
+        let mut start = 0;
+        while start <= line.len() {
+            let Some(range) = self.find_at(haystack, start) else {
+                break;
+            };
+            if range.is_empty() || !self.is_whole_word_match(line, range.clone()) {
+                start = next_char_boundary(line, range.start);
+                continue;
+            }
+            start = range.end;
+            ranges.push(range);
+        }
+        ranges
+    }
+
+    /// Returns the text that should replace the match at `range` in `line`, expanding `$n`,
+    /// `${n}` and `$$` as well as the `\n`, `\t` and `\\` escapes when searching by regex.
+    pub fn replacement(&self, line: &str, range: Range<usize>, replacement: &str) -> String {
+        let captures = match &self.matcher {
+            Matcher::Regex(regex) if self.is_regex => regex
+                .find_at(line, range.start)
+                .filter(|captures| captures.start() == range.start && captures.end() == range.end),
+            _ => return replacement.to_string(),
+        };
+        expand_replacement(line, captures.as_ref(), replacement)
+    }
+
+    fn find_at(&self, haystack: &str, start: usize) -> Option<Range<usize>> {
+        match &self.matcher {
+            Matcher::Literal { needle, .. } => {
+                if needle.is_empty() {
+                    return None;
+                }
+                haystack[start..]
+                    .find(needle.as_str())
+                    .map(|index| start + index..start + index + needle.len())
+            }
+            Matcher::Regex(regex) => regex
+                .find_at(haystack, start)
+                .map(|captures| captures.start()..captures.end()),
+        }
+    }
+
+    fn is_whole_word_match(&self, line: &str, range: Range<usize>) -> bool {
+        !self.is_whole_word
+            || !line[..range.start]
+                .chars()
+                .next_back()
+                .is_some_and(is_word_char)
+                && !line[range.end..].chars().next().is_some_and(is_word_char)
+    }
+}
+
+fn expand_replacement(line: &str, captures: Option<&Captures>, replacement: &str) -> String {
+    let group = |index: usize| {
+        captures
+            .and_then(|captures| captures.get(index))
+            .map_or("", |(start, end)| &line[start..end])
+    };
+    let mut expanded = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '$' => match chars.peek().copied() {
+                Some('$') => {
+                    chars.next();
+                    expanded.push('$');
+                }
+                Some('{') => {
+                    let name: String = chars
+                        .clone()
+                        .skip(1)
+                        .take_while(|&char| char != '}')
+                        .collect();
+                    match name.parse::<usize>() {
+                        Ok(index) if chars.clone().nth(name.len() + 1) == Some('}') => {
+                            chars.nth(name.len() + 1);
+                            expanded.push_str(group(index));
+                        }
+                        _ => expanded.push('$'),
+                    }
+                }
+                Some(digit @ '0'..='9') => {
+                    chars.next();
+                    expanded.push_str(group(digit.to_digit(10).unwrap() as usize));
+                }
+                _ => expanded.push('$'),
+            },
+            '\\' => match chars.peek().copied() {
+                Some('n') => {
+                    chars.next();
+                    expanded.push('\n');
+                }
+                Some('t') => {
+                    chars.next();
+                    expanded.push('\t');
+                }
+                Some('\\') => {
+                    chars.next();
+                    expanded.push('\\');
+                }
+                _ => expanded.push('\\'),
+            },
+            char => expanded.push(char),
+        }
+    }
+    expanded
+}
+
+fn next_char_boundary(line: &str, index: usize) -> usize {
+    index
+        + line[index..]
+            .chars()
+            .next()
+            .map_or(1, |char| char.len_utf8())
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
@@ -1,6 +1,7 @@
 use {
     crate::{
         layout::Layout,
+        rope::Rope,
         str::StrExt,
         text::{Edit, Length, Position},
     },
@@ -265,7 +266,7 @@ impl Cursor {
         self.position.byte_index == 0
     }
 
-    pub fn is_at_end_of_line(self, lines: &[String]) -> bool {
+    pub fn is_at_end_of_line(self, lines: &Rope) -> bool {
         self.position.byte_index == lines[self.position.line_index].len()
     }
 
@@ -282,7 +283,7 @@ impl Cursor {
         row == line.row_count() - 1
     }
 
-    pub fn move_left(self, lines: &[String]) -> Self {
+    pub fn move_left(self, lines: &Rope) -> Self {
         if !self.is_at_start_of_line() {
             return self.move_to_prev_grapheme(lines);
         }
@@ -292,7 +293,7 @@ impl Cursor {
         self
     }
 
-    pub fn move_right(self, lines: &[String]) -> Self {
+    pub fn move_right(self, lines: &Rope) -> Self {
         if !self.is_at_end_of_line(lines) {
             return self.move_to_next_grapheme(lines);
         }
@@ -322,7 +323,7 @@ impl Cursor {
         self.move_to_end_of_line(layout.as_text().as_lines())
     }
 
-    pub fn home(self, lines: &[String]) -> Self {
+    pub fn home(self, lines: &Rope) -> Self {
         if !self.is_at_start_of_line() {
             let indent_len = lines[self.position.line_index].indent().unwrap_or("").len();
             if self.position.byte_index <= indent_len {
@@ -341,7 +342,7 @@ impl Cursor {
         self
     }
 
-    pub fn end(self, lines: &[String]) -> Self {
+    pub fn end(self, lines: &Rope) -> Self {
         if !self.is_at_end_of_line(lines) {
             let indent_len = lines[self.position.line_index].indent().unwrap_or("").len();
             if self.position.byte_index >= indent_len {
@@ -360,7 +361,7 @@ impl Cursor {
         self
     }
 
-    pub fn move_to_end_of_line(self, lines: &[String]) -> Self {
+    pub fn move_to_end_of_line(self, lines: &Rope) -> Self {
         let mut me = self.clone();
         while !me.is_at_end_of_line(lines) {
             me = me.move_to_next_grapheme(lines);
@@ -390,7 +391,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_file_end(self, lines: &[String]) -> Self {
+    pub fn move_to_file_end(self, lines: &Rope) -> Self {
         Self {
             position: Position {
                 line_index: lines.len() - 1,
@@ -401,7 +402,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_prev_grapheme(self, lines: &[String]) -> Self {
+    pub fn move_to_prev_grapheme(self, lines: &Rope) -> Self {
         Self {
             position: Position {
                 line_index: self.position.line_index,
@@ -416,7 +417,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_next_grapheme(self, lines: &[String]) -> Self {
+    pub fn move_to_next_grapheme(self, lines: &Rope) -> Self {
         let line = &lines[self.position.line_index];
         Self {
             position: Position {
@@ -432,7 +433,7 @@ impl Cursor {
         }
     }
 
-    pub fn move_to_end_of_prev_line(self, lines: &[String]) -> Self {
+    pub fn move_to_end_of_prev_line(self, lines: &Rope) -> Self {
         let prev_line_index = self.position.line_index - 1;
         Self {
             position: Position {
@@ -1,10 +1,18 @@
 use {
     crate::{
         char::CharExt,
-        document::Document,
+        diff::{diff_lines, DiffAlgorithm},
+        document::{Document, Editor},
+        decoration::{Decoration, DecorationType},
         history::{EditKind,NewGroup},
+        keymap::Command,
         layout::{BlockElement, Layout, WrappedElement},
+        merge::{find_conflicts, Conflict, ConflictResolution},
+        provider::{is_word_char, word_range},
+        rope::Rope,
+        search::{SearchQuery, Searcher},
         selection::{Affinity, Cursor, SelectionSet},
+        snippet::Snippet,
         str::StrExt,
         text::{Change, Drift, Edit, Length, Position, Text},
         wrap,
@@ -16,6 +24,7 @@ use {
         collections::HashSet,
         fmt::Write,
         iter, mem,
+        ops::Range,
         rc::Rc,
         sync::{atomic, atomic::AtomicUsize, mpsc, mpsc::Receiver},
     },
@@ -30,6 +39,10 @@ pub struct Session {
     selection_state: RefCell<SelectionState>,
     wrap_column: Cell<Option<usize>>,
     fold_state: RefCell<FoldState>,
+    search_state: RefCell<Option<SearchState>>,
+    snippet_state: RefCell<Option<SnippetState>>,
+    // The conflicts between conflict markers, found lazily and cleared on every edit.
+    conflicts: RefCell<Option<Vec<Conflict>>>,
     edit_receiver: Receiver<(Option<SelectionSet>, Vec<Edit>)>,
 }
 
@@ -49,6 +62,7 @@ impl Session {
                 fold_column: (0..line_count).map(|_| 0).collect(),
                 scale: (0..line_count).map(|_| 1.0).collect(),
                 wrap_data: (0..line_count).map(|_| None).collect(),
+                width: Cell::new(None),
             }),
             selection_state: RefCell::new(SelectionState {
                 mode: SelectionMode::Simple,
@@ -63,11 +77,11 @@ impl Session {
                 folded_lines: HashSet::new(),
                 unfolding_lines: HashSet::new(),
             }),
+            search_state: RefCell::new(None),
+            snippet_state: RefCell::new(None),
+            conflicts: RefCell::new(None),
             edit_receiver,
         };
-        for line in 0..line_count {
-            session.update_wrap_data(line);
-        }
         session.update_y();
         session.document.add_session(session.id, edit_sender);
         session
@@ -81,6 +95,10 @@ impl Session {
         &self.settings
     }
 
+    pub fn set_settings(&mut self, settings: Rc<Settings>) {
+        self.settings = settings;
+    }
+
     pub fn document(&self) -> &Document {
         &self.document
     }
@@ -118,14 +136,37 @@ impl Session {
             return;
         }
         self.wrap_column.set(wrap_column);
-        let line_count = self.document.as_text().as_lines().len();
-        for line in 0..line_count {
-            self.update_wrap_data(line);
+        let mut layout = self.layout.borrow_mut();
+        for wrap_data in &mut layout.wrap_data {
+            *wrap_data = None;
+        }
+        for column_count in &mut layout.column_count {
+            *column_count = None;
+        }
+        layout.y.clear();
+        layout.width.set(None);
+        drop(layout);
+        self.update_y();
+    }
+
+    /// Computes the wrap data for the lines in `line_range` that have not been laid out yet.
+    ///
+    /// Lines are laid out lazily, so that opening or rewrapping a large file only has to wrap the
+    /// lines that are visible. Until then, a line is assumed to fit on a single row.
+    pub fn lay_out_lines(&self, line_range: Range<usize>) {
+        let line_end = line_range.end.min(self.document.as_text().as_lines().len());
+        for line in line_range.start..line_end {
+            if self.layout.borrow().wrap_data[line].is_none() {
+                self.update_wrap_data(line);
+            }
         }
         self.update_y();
     }
 
     pub fn fold(&self) {
+        if self.document.is_large_file() {
+            return;
+        }
         let mut fold_state = self.fold_state.borrow_mut();
         let line_count = self.document().as_text().as_lines().len();
         for line_index in 0..line_count {
@@ -185,12 +226,52 @@ impl Session {
             layout.y.truncate(line + 1);
         }
         fold_state_ref.unfolding_lines = new_unfolding_lines;
+        layout.width.set(None);
         drop(layout);
         drop(fold_state_ref);
         self.update_y();
         true
     }
 
+    /// Returns the header lines of the blocks that enclose the line at `line_index`, outermost
+    /// first, such as the `impl` and `fn` lines around a statement.
+    ///
+    /// The blocks are found by indentation. Only blocks below the fold level are considered, so the
+    /// headers are among the lines that remain visible when the session is folded.
+    pub fn sticky_header_lines(&self, line_index: usize) -> Vec<usize> {
+        let text = self.document.as_text();
+        let lines = text.as_lines();
+        let line = &lines[line_index];
+        let mut max_indent_column_count = match line.indent() {
+            // A closing delimiter belongs to the block that it closes.
+            Some(indent) if line[indent.len()..].starts_with(['}', ')', ']']) => {
+                indent.column_count() + 1
+            }
+            Some(indent) => indent.column_count(),
+            None => usize::MAX,
+        }
+        .min(self.settings.fold_level * self.settings.tab_column_count);
+        let mut header_lines = Vec::new();
+        for index in (0..line_index).rev() {
+            if max_indent_column_count == 0 {
+                break;
+            }
+            let Some(indent) = lines[index].indent() else {
+                continue;
+            };
+            let indent_column_count = indent.column_count();
+            if indent_column_count >= max_indent_column_count {
+                continue;
+            }
+            max_indent_column_count = indent_column_count;
+            if is_sticky_header(&lines[index][indent.len()..]) {
+                header_lines.push(index);
+            }
+        }
+        header_lines.reverse();
+        header_lines
+    }
+
     pub fn set_selection(&self, position: Position, affinity: Affinity, mode: SelectionMode, new_group:NewGroup) {
         let selection = grow_selection(
             Selection::from(Cursor {
@@ -202,6 +283,7 @@ impl Session {
             mode,
             &self.settings.word_separators,
         );
+        self.clear_snippet();
         let mut selection_state = self.selection_state.borrow_mut();
         selection_state.mode = mode;
         selection_state.selections.set_selection(selection);
@@ -225,6 +307,7 @@ impl Session {
             mode,
             &self.settings.word_separators,
         );
+        self.clear_snippet();
         let mut selection_state = self.selection_state.borrow_mut();
         selection_state.mode = mode;
         selection_state.last_added_selection_index =
@@ -299,6 +382,277 @@ impl Session {
         });
     }
 
+    pub fn move_to_file_start(&self, reset_anchor: bool) {
+        self.modify_selections(reset_anchor, |selection, _| {
+            selection.update_cursor(|cursor| cursor.move_to_file_start())
+        });
+    }
+
+    pub fn move_to_file_end(&self, reset_anchor: bool) {
+        self.modify_selections(reset_anchor, |selection, layout| {
+            selection.update_cursor(|cursor| cursor.move_to_file_end(layout.as_text().as_lines()))
+        });
+    }
+
+    pub fn select_all(&self) {
+        self.set_selection(
+            Position::zero(),
+            Affinity::Before,
+            SelectionMode::All,
+            NewGroup::Yes,
+        );
+    }
+
+    /// Adds a selection for the next occurrence of the text of the last added selection, wrapping
+    /// around at the end of the document. If the last added selection is empty, the word under its
+    /// cursor is selected instead. Returns whether the selections changed.
+    pub fn select_next_occurrence(&self) -> bool {
+        let index = self.last_added_selection_index().unwrap();
+        let selection = self.selections()[index];
+        if selection.is_empty() {
+            return self.select_word_at_cursor(index);
+        }
+        let occurrences = self.find_occurrences_of(selection);
+        let selections = self.selections();
+        let is_selected = |(start, end): &(Position, Position)| {
+            selections
+                .iter()
+                .any(|selection| selection.start() == *start && selection.end() == *end)
+        };
+        let next = occurrences
+            .iter()
+            .filter(|occurrence| !is_selected(occurrence))
+            .find(|(start, _)| *start >= selection.end())
+            .or_else(|| occurrences.iter().find(|occurrence| !is_selected(occurrence)))
+            .copied();
+        drop(selections);
+        let Some((start, end)) = next else {
+            return false;
+        };
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.last_added_selection_index =
+            Some(selection_state.selections.add_selection(Selection {
+                anchor: start,
+                cursor: Cursor::from(end),
+            }));
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        true
+    }
+
+    /// Selects every occurrence of the text of the last added selection, or of the word under its
+    /// cursor if it is empty.
+    pub fn select_all_occurrences(&self) {
+        let index = self.last_added_selection_index().unwrap();
+        if self.selections()[index].is_empty() && !self.select_word_at_cursor(index) {
+            return;
+        }
+        let index = self.last_added_selection_index().unwrap();
+        let selection = self.selections()[index];
+        let selections = self
+            .find_occurrences_of(selection)
+            .into_iter()
+            .map(|(start, end)| Selection {
+                anchor: start,
+                cursor: Cursor::from(end),
+            })
+            .collect::<Vec<_>>();
+        let last_added_selection_index = selections
+            .iter()
+            .position(|occurrence| occurrence.start() == selection.start())
+            .unwrap_or(0);
+        self.set_selections(selections, last_added_selection_index);
+    }
+
+    /// Replaces the selections with a rectangular block of selections, one for each row between
+    /// the anchor and the cursor, that spans the columns between them. Soft wrapped lines
+    /// contribute a selection for each row, and columns are counted as they are laid out, so
+    /// the block stays rectangular on screen.
+    pub fn set_column_selection(
+        &self,
+        anchor: Position,
+        anchor_affinity: Affinity,
+        cursor: Position,
+        cursor_affinity: Affinity,
+    ) {
+        let layout = self.layout();
+        let (anchor_row_index, anchor_column_index) = layout
+            .line(anchor.line_index)
+            .logical_to_grid_position(anchor.byte_index, anchor_affinity);
+        let (cursor_row_index, cursor_column_index) = layout
+            .line(cursor.line_index)
+            .logical_to_grid_position(cursor.byte_index, cursor_affinity);
+        let start = (anchor.line_index, anchor_row_index).min((cursor.line_index, cursor_row_index));
+        let end = (anchor.line_index, anchor_row_index).max((cursor.line_index, cursor_row_index));
+        let mut selections = Vec::new();
+        let mut last_added_selection_index = 0;
+        for line_index in start.0..=end.0 {
+            let line = layout.line(line_index);
+            let start_row_index = if line_index == start.0 { start.1 } else { 0 };
+            let end_row_index = if line_index == end.0 {
+                end.1
+            } else {
+                line.row_count() - 1
+            };
+            for row_index in start_row_index..=end_row_index {
+                // Rows after a wrap start at the wrap indentation.
+                let min_column_index = if row_index > 0 {
+                    line.wrap_indent_column_count()
+                } else {
+                    0
+                };
+                let (anchor_byte_index, _) = line
+                    .grid_to_logical_position(row_index, anchor_column_index.max(min_column_index));
+                let (cursor_byte_index, affinity) = line
+                    .grid_to_logical_position(row_index, cursor_column_index.max(min_column_index));
+                if (line_index, row_index) == (cursor.line_index, cursor_row_index) {
+                    last_added_selection_index = selections.len();
+                }
+                selections.push(Selection {
+                    anchor: Position {
+                        line_index,
+                        byte_index: anchor_byte_index,
+                    },
+                    cursor: Cursor {
+                        position: Position {
+                            line_index,
+                            byte_index: cursor_byte_index,
+                        },
+                        affinity,
+                        preferred_column_index: Some(cursor_column_index),
+                    },
+                });
+            }
+        }
+        drop(layout);
+        self.set_selections(selections, last_added_selection_index);
+    }
+
+    /// Splits every selection that spans several lines into a selection for each line.
+    pub fn split_selection_into_lines(&self) {
+        let text = self.document.as_text();
+        let lines = text.as_lines();
+        let last_added_selection_index = self.last_added_selection_index();
+        let mut selections = Vec::new();
+        let mut new_last_added_selection_index = 0;
+        for (index, selection) in self.selections().iter().enumerate() {
+            if Some(index) == last_added_selection_index {
+                new_last_added_selection_index = selections.len();
+            }
+            let start = selection.start();
+            let end = selection.end();
+            if start.line_index == end.line_index {
+                selections.push(*selection);
+                continue;
+            }
+            for line_index in start.line_index..=end.line_index {
+                // A selection that ends at the start of a line does not select anything on it.
+                if line_index == end.line_index && end.byte_index == 0 {
+                    break;
+                }
+                let start_byte_index = if line_index == start.line_index {
+                    start.byte_index
+                } else {
+                    0
+                };
+                let end_byte_index = if line_index == end.line_index {
+                    end.byte_index
+                } else {
+                    lines[line_index].len()
+                };
+                selections.push(Selection {
+                    anchor: Position {
+                        line_index,
+                        byte_index: start_byte_index,
+                    },
+                    cursor: Cursor::from(Position {
+                        line_index,
+                        byte_index: end_byte_index,
+                    }),
+                });
+            }
+        }
+        drop(text);
+        self.set_selections(selections, new_last_added_selection_index);
+    }
+
+    /// Adds a cursor on the row above the cursor of the last added selection. Returns `false` if
+    /// that cursor is already on the first row.
+    pub fn add_cursor_above(&self) -> bool {
+        self.add_cursor_vertically(true)
+    }
+
+    /// Adds a cursor on the row below the cursor of the last added selection. Returns `false` if
+    /// that cursor is already on the last row.
+    pub fn add_cursor_below(&self) -> bool {
+        self.add_cursor_vertically(false)
+    }
+
+    /// Executes a command that a key can be bound to. Returns `false` if the command depends on
+    /// the view, such as scrolling by a page or opening the find bar, and should be executed by
+    /// the code editor instead.
+    pub fn execute_command(&self, command: Command) -> bool {
+        match command {
+            Command::MoveLeft => self.move_left(true),
+            Command::MoveRight => self.move_right(true),
+            Command::MoveUp => self.move_up(true),
+            Command::MoveDown => self.move_down(true),
+            Command::MoveToLineStart => self.home(true),
+            Command::MoveToLineEnd => self.end(true),
+            Command::MoveToFileStart => self.move_to_file_start(true),
+            Command::MoveToFileEnd => self.move_to_file_end(true),
+            Command::SelectLeft => self.move_left(false),
+            Command::SelectRight => self.move_right(false),
+            Command::SelectUp => self.move_up(false),
+            Command::SelectDown => self.move_down(false),
+            Command::SelectToLineStart => self.home(false),
+            Command::SelectToLineEnd => self.end(false),
+            Command::SelectToFileStart => self.move_to_file_start(false),
+            Command::SelectToFileEnd => self.move_to_file_end(false),
+            Command::SelectAll => self.select_all(),
+            Command::SelectNextOccurrence => {
+                self.select_next_occurrence();
+            }
+            Command::SelectAllOccurrences => self.select_all_occurrences(),
+            Command::SplitSelectionIntoLines => self.split_selection_into_lines(),
+            Command::AddCursorAbove => {
+                self.add_cursor_above();
+            }
+            Command::AddCursorBelow => {
+                self.add_cursor_below();
+            }
+            Command::Enter => self.enter(),
+            Command::Indent => self.indent(),
+            Command::Outdent => self.outdent(),
+            Command::Delete => self.delete(),
+            Command::Backspace => self.backspace(),
+            Command::Undo => {
+                self.undo();
+            }
+            Command::Redo => {
+                self.redo();
+            }
+            Command::MovePageUp
+            | Command::MovePageDown
+            | Command::SelectPageUp
+            | Command::SelectPageDown
+            | Command::Find
+            | Command::Replace
+            | Command::FindNext
+            | Command::FindPrev
+            | Command::IncreaseFontSize
+            | Command::DecreaseFontSize
+            | Command::ResetFontSize
+            | Command::ToggleWordWrap
+            | Command::ToggleMinimap
+            | Command::ToggleStickyScroll => return false,
+        }
+        true
+    }
+
     pub fn insert(&self, text: Text) {
 
         let mut edit_kind = EditKind::Insert;
@@ -785,6 +1139,7 @@ impl Session {
     }
 
     pub fn undo(&self) -> bool {
+        self.clear_snippet();
         self.selection_state
             .borrow_mut()
             .injected_char_stack
@@ -794,6 +1149,7 @@ impl Session {
     }
 
     pub fn redo(&self) -> bool {
+        self.clear_snippet();
         self.selection_state
             .borrow_mut()
             .injected_char_stack
@@ -802,12 +1158,731 @@ impl Session {
             .redo(self.id, &self.selection_state.borrow().selections)
     }
 
-    pub fn handle_changes(&mut self) {
+    /// Replaces the selections and the `replace_byte_count` bytes of the word before each cursor
+    /// with the given snippet as a single undoable edit, then selects its first tab stop.
+    pub fn insert_snippet(&self, replace_byte_count: usize, snippet: &Snippet) {
+        self.selection_state
+            .borrow_mut()
+            .injected_char_stack
+            .clear();
+        let text = Text::from(snippet.text.as_str());
+        *self.snippet_state.borrow_mut() = Some(SnippetState {
+            pending: Some((snippet.clone(), text.clone())),
+            tab_stops: vec![Vec::new(); snippet.tab_stops.len()],
+            active_index: None,
+        });
+        self.document.edit_selections(
+            self.id,
+            EditKind::Other,
+            &self.selection_state.borrow().selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                let line = &editor.as_text().as_lines()[position.line_index];
+                let word_start = word_range(line, position.byte_index).start;
+                let mut byte_index =
+                    position.byte_index - replace_byte_count.min(position.byte_index - word_start);
+                if !line.is_char_boundary(byte_index) {
+                    byte_index = word_start;
+                }
+                let start = Position {
+                    line_index: position.line_index,
+                    byte_index,
+                };
+                editor.apply_edit(Edit {
+                    change: Change::Delete(start, position - start),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(start, text.clone()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+        self.handle_changes();
+        if let Some(snippet_state) = self.snippet_state.borrow_mut().as_mut() {
+            snippet_state.pending = None;
+        }
+        self.select_snippet_tab_stop(0);
+    }
+
+    /// Returns whether a snippet with tab stops that have not been visited yet was inserted.
+    pub fn has_active_snippet(&self) -> bool {
+        self.snippet_state.borrow().is_some()
+    }
+
+    /// Selects the next tab stop of the active snippet. Selecting the final tab stop ends the
+    /// snippet. Returns whether there was an active snippet.
+    pub fn next_snippet_tab_stop(&self) -> bool {
+        let Some(index) = self
+            .snippet_state
+            .borrow()
+            .as_ref()
+            .map(|snippet_state| snippet_state.active_index.map_or(0, |index| index + 1))
+        else {
+            return false;
+        };
+        self.select_snippet_tab_stop(index)
+    }
+
+    /// Selects the previous tab stop of the active snippet. Returns whether there was an active
+    /// snippet.
+    pub fn prev_snippet_tab_stop(&self) -> bool {
+        let Some(index) = self
+            .snippet_state
+            .borrow()
+            .as_ref()
+            .map(|snippet_state| snippet_state.active_index.unwrap_or(0).saturating_sub(1))
+        else {
+            return false;
+        };
+        self.select_snippet_tab_stop(index)
+    }
+
+    pub fn clear_snippet(&self) {
+        *self.snippet_state.borrow_mut() = None;
+    }
+
+    /// Starts a new search. Matches are found incrementally by calling `search_step`.
+    pub fn set_search_query(&self, query: &SearchQuery) -> Result<(), String> {
+        if query.pattern.is_empty() {
+            self.clear_search();
+            return Ok(());
+        }
+        let searcher = Searcher::new(query)?;
+        let line_count = self.document.as_text().as_lines().len();
+        *self.search_state.borrow_mut() = Some(SearchState {
+            searcher,
+            line_matches: (0..line_count).map(|_| None).collect(),
+            first_unsearched_line: 0,
+        });
+        Ok(())
+    }
+
+    pub fn clear_search(&self) {
+        *self.search_state.borrow_mut() = None;
+    }
+
+    pub fn is_search_pending(&self) -> bool {
+        self.search_state
+            .borrow()
+            .as_ref()
+            .is_some_and(|search_state| {
+                search_state.first_unsearched_line < search_state.line_matches.len()
+            })
+    }
+
+    /// Searches at most `max_line_count` lines that have not been searched yet, returning
+    /// whether any lines are left.
+    pub fn search_step(&self, max_line_count: usize) -> bool {
+        let mut search_state = self.search_state.borrow_mut();
+        let Some(search_state) = search_state.as_mut() else {
+            return false;
+        };
+        let text = self.document.as_text();
+        let lines = text.as_lines();
+        let mut line_count = 0;
+        while search_state.first_unsearched_line < lines.len() && line_count < max_line_count {
+            let line = search_state.first_unsearched_line;
+            if search_state.line_matches[line].is_none() {
+                search_state.line_matches[line] = Some(search_state.searcher.find_all(&lines[line]));
+                line_count += 1;
+            }
+            search_state.first_unsearched_line += 1;
+        }
+        search_state.first_unsearched_line < lines.len()
+    }
+
+    pub fn search_match_count(&self) -> usize {
+        self.search_state
+            .borrow()
+            .as_ref()
+            .map_or(0, |search_state| {
+                search_state
+                    .line_matches
+                    .iter()
+                    .flatten()
+                    .map(|matches| matches.len())
+                    .sum()
+            })
+    }
+
+    /// Returns the index of the match covered by the last added selection, if any.
+    pub fn active_search_match_index(&self) -> Option<usize> {
+        let selection = self.selections()[self.last_added_selection_index()?];
+        let line = selection.start().line_index;
+        if selection.end().line_index != line {
+            return None;
+        }
+        let index = self.search_line(line).iter().position(|range| {
+            range.start == selection.start().byte_index && range.end == selection.end().byte_index
+        })?;
+        // The matches are counted from the start of the document, so the lines before this one
+        // have to be searched now if the incremental search hasn't gotten to them yet.
+        let mut search_state = self.search_state.borrow_mut();
+        let SearchState {
+            searcher,
+            line_matches,
+            ..
+        } = search_state.as_mut()?;
+        let text = self.document.as_text();
+        Some(
+            line_matches[..line]
+                .iter_mut()
+                .zip(text.as_lines())
+                .map(|(matches, line)| matches.get_or_insert_with(|| searcher.find_all(line)).len())
+                .sum::<usize>()
+                + index,
+        )
+    }
+
+    /// Returns the matches found so far that start in the given line range.
+    pub fn search_decorations(&self, line_range: Range<usize>) -> Vec<Decoration> {
+        let search_state = self.search_state.borrow();
+        let Some(search_state) = search_state.as_ref() else {
+            return Vec::new();
+        };
+        let selections = self.selections();
+        let mut decorations = Vec::new();
+        for line in line_range {
+            let Some(Some(matches)) = search_state.line_matches.get(line) else {
+                continue;
+            };
+            for range in matches {
+                let start = Position {
+                    line_index: line,
+                    byte_index: range.start,
+                };
+                let end = Position {
+                    line_index: line,
+                    byte_index: range.end,
+                };
+                let is_selected = selections
+                    .iter()
+                    .any(|selection| selection.start() == start && selection.end() == end);
+                decorations.push(Decoration::new(
+                    0,
+                    start,
+                    end,
+                    if is_selected {
+                        DecorationType::ActiveSearchMatch
+                    } else {
+                        DecorationType::SearchMatch
+                    },
+                ));
+            }
+        }
+        decorations
+    }
+
+    /// Selects the first match after the last added selection, wrapping around at the end of the
+    /// document.
+    pub fn select_next_search_match(&self) -> bool {
+        let from = self.selections()[self.last_added_selection_index().unwrap()].end();
+        let line_count = self.document.as_text().as_lines().len();
+        for offset in 0..=line_count {
+            let line = (from.line_index + offset) % line_count;
+            let matches = self.search_line(line);
+            let range = matches
+                .iter()
+                .find(|range| offset > 0 || range.start >= from.byte_index);
+            if let Some(range) = range {
+                self.select_search_match(line, range.clone());
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Selects the last match before the last added selection, wrapping around at the start of
+    /// the document.
+    pub fn select_prev_search_match(&self) -> bool {
+        let from = self.selections()[self.last_added_selection_index().unwrap()].start();
+        let line_count = self.document.as_text().as_lines().len();
+        for offset in 0..=line_count {
+            let line = (from.line_index + line_count * 2 - offset) % line_count;
+            let matches = self.search_line(line);
+            let range = matches
+                .iter()
+                .rev()
+                .find(|range| offset > 0 || range.end <= from.byte_index);
+            if let Some(range) = range {
+                self.select_search_match(line, range.clone());
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Replaces the match covered by the last added selection, then selects the next match.
+    pub fn replace_search_match(&self, replacement: &str) -> bool {
+        let selection = self.selections()[self.last_added_selection_index().unwrap()];
+        let line = selection.start().line_index;
+        let is_match = selection.end().line_index == line
+            && self.search_line(line).iter().any(|range| {
+                range.start == selection.start().byte_index
+                    && range.end == selection.end().byte_index
+            });
+        if is_match {
+            let text = self.search_state.borrow().as_ref().unwrap().searcher.replacement(
+                &self.document.as_text().as_lines()[line],
+                selection.start().byte_index..selection.end().byte_index,
+                replacement,
+            );
+            let mut selections = SelectionSet::new();
+            selections.set_selection(selection);
+            self.replace_selections(&selections, vec![text]);
+            self.handle_changes();
+        }
+        self.select_next_search_match();
+        is_match
+    }
+
+    /// Replaces every match in the document as a single undoable edit, returning the number of
+    /// replaced matches.
+    pub fn replace_all_search_matches(&self, replacement: &str) -> usize {
+        self.search_step(usize::MAX);
+        let search_state_ref = self.search_state.borrow();
+        let Some(search_state) = search_state_ref.as_ref() else {
+            return 0;
+        };
+        let text = self.document.as_text();
+        let mut selections = SelectionSet::new();
+        let mut texts = Vec::new();
+        for (line, matches) in search_state.line_matches.iter().enumerate() {
+            for range in matches.iter().flatten() {
+                let selection = Selection {
+                    anchor: Position {
+                        line_index: line,
+                        byte_index: range.start,
+                    },
+                    cursor: Cursor {
+                        position: Position {
+                            line_index: line,
+                            byte_index: range.end,
+                        },
+                        affinity: Affinity::Before,
+                        preferred_column_index: None,
+                    },
+                };
+                if texts.is_empty() {
+                    selections.set_selection(selection);
+                } else {
+                    selections.add_selection(selection);
+                }
+                texts.push(
+                    search_state
+                        .searcher
+                        .replacement(&text.as_lines()[line], range.clone(), replacement),
+                );
+            }
+        }
+        drop(text);
+        drop(search_state_ref);
+        let count = texts.len();
+        if count > 0 {
+            self.replace_selections(&selections, texts);
+            self.handle_changes();
+        }
+        count
+    }
+
+    /// Replaces the text between `start` and `end` with `text`. Consecutive edits of the same
+    /// `EditKind::Group` are undone together.
+    pub fn replace_range(&self, kind: EditKind, start: Position, end: Position, text: &str) {
+        let selections = self.selection_state.borrow().selections.clone();
+        self.document
+            .edit(self.id, kind, &selections, |mut editor| {
+                if end > start {
+                    editor.apply_edit(Edit {
+                        change: Change::Delete(start, end - start),
+                        drift: Drift::Before,
+                    });
+                }
+                if !text.is_empty() {
+                    editor.apply_edit(Edit {
+                        change: Change::Insert(start, text.into()),
+                        drift: Drift::Before,
+                    });
+                }
+            });
+        self.handle_changes();
+    }
+
+    /// Replaces the lines in `line_range` with `lines` as a single undoable edit. The range can be
+    /// empty to insert lines, and `lines` can be empty to delete them.
+    pub fn replace_lines(&self, line_range: Range<usize>, lines: &[String]) {
+        let selections = self.selection_state.borrow().selections.clone();
+        self.document
+            .edit(self.id, EditKind::Other, &selections, |mut editor| {
+                replace_lines(&mut editor, line_range, lines);
+            });
+        self.handle_changes();
+    }
+
+    /// Replaces the text of the document with `text`, for instance after the file was changed on
+    /// disk. Only the lines that differ are edited, so selections on other lines stay where they
+    /// are, and the reload can be undone as a single step.
+    pub fn reload(&self, text: Text) {
+        let (hunks, new_lines) = {
+            let old_text = self.document.as_text();
+            let old_lines: Vec<&str> = old_text.as_lines().iter().map(|line| line.as_str()).collect();
+            let new_lines: Vec<&str> = text.as_lines().iter().map(|line| line.as_str()).collect();
+            (
+                diff_lines(&old_lines, &new_lines, DiffAlgorithm::Myers),
+                text.as_lines().iter().cloned().collect::<Vec<_>>(),
+            )
+        };
+        if hunks.is_empty() {
+            return;
+        }
+        let selections = self.selection_state.borrow().selections.clone();
+        self.document.force_new_group();
+        self.document
+            .edit(self.id, EditKind::Other, &selections, |mut editor| {
+                // Edit from the bottom up, so that the line ranges of the remaining hunks stay
+                // valid.
+                for (old_range, new_range) in hunks.into_iter().rev() {
+                    replace_lines(&mut editor, old_range, &new_lines[new_range]);
+                }
+            });
+        self.document.force_new_group();
+        self.handle_changes();
+    }
+
+    /// Returns the conflicts between conflict markers in the document.
+    pub fn conflicts(&self) -> Ref<'_, [Conflict]> {
+        if self.conflicts.borrow().is_none() {
+            *self.conflicts.borrow_mut() = Some(find_conflicts(&self.document.as_text()));
+        }
+        Ref::map(self.conflicts.borrow(), |conflicts| {
+            conflicts.as_deref().unwrap()
+        })
+    }
+
+    /// Resolves the conflict with the given index by replacing it, markers included, with our
+    /// lines, their lines, or both.
+    pub fn resolve_conflict(&self, index: usize, resolution: ConflictResolution) {
+        let Some(conflict) = self.conflicts().get(index).copied() else {
+            return;
+        };
+        let lines = {
+            let text = self.document.as_text();
+            let text_lines = text.as_lines();
+            match resolution {
+                ConflictResolution::Ours => text_lines
+                    .range(conflict.our_lines())
+                    .cloned()
+                    .collect::<Vec<String>>(),
+                ConflictResolution::Theirs => text_lines
+                    .range(conflict.their_lines())
+                    .cloned()
+                    .collect::<Vec<String>>(),
+                ConflictResolution::Both => text_lines
+                    .range(conflict.our_lines())
+                    .chain(text_lines.range(conflict.their_lines()))
+                    .cloned()
+                    .collect::<Vec<String>>(),
+            }
+        };
+        self.document.force_new_group();
+        self.replace_lines(conflict.line_range(), &lines);
+        self.document.force_new_group();
+    }
+
+    /// Returns decorations for the markers and both sides of the conflicts that overlap the given
+    /// line range.
+    pub fn conflict_decorations(&self, line_range: Range<usize>) -> Vec<Decoration> {
+        let text = self.document.as_text();
+        let text_lines = text.as_lines();
+        let line_end = |line_index: usize| Position {
+            line_index,
+            byte_index: text_lines[line_index].len(),
+        };
+        let line_start = |line_index: usize| Position {
+            line_index,
+            byte_index: 0,
+        };
+        let mut decorations = Vec::new();
+        for conflict in self.conflicts().iter() {
+            if conflict.end_line < line_range.start || conflict.start_line >= line_range.end {
+                continue;
+            }
+            let marker = |line| {
+                Decoration::new(
+                    0,
+                    line_start(line),
+                    line_end(line),
+                    DecorationType::ConflictMarker,
+                )
+            };
+            decorations.push(marker(conflict.start_line));
+            if !conflict.our_lines().is_empty() {
+                decorations.push(Decoration::new(
+                    0,
+                    line_start(conflict.our_lines().start),
+                    line_end(conflict.separator_line - 1),
+                    DecorationType::ConflictOurs,
+                ));
+            }
+            decorations.push(marker(conflict.separator_line));
+            if !conflict.their_lines().is_empty() {
+                decorations.push(Decoration::new(
+                    0,
+                    line_start(conflict.their_lines().start),
+                    line_end(conflict.end_line - 1),
+                    DecorationType::ConflictTheirs,
+                ));
+            }
+            decorations.push(marker(conflict.end_line));
+        }
+        decorations
+    }
+
+    /// Returns the selections of the other peers editing the document that are on the lines in
+    /// `line_range`, as decorations. Overlapping selections are merged.
+    pub fn remote_selection_decorations(&self, line_range: Range<usize>) -> Vec<Decoration> {
+        let mut selections: Vec<_> = self
+            .document
+            .remote_selections()
+            .iter()
+            .copied()
+            .filter(|selection| {
+                !selection.is_empty()
+                    && selection.end().line_index >= line_range.start
+                    && selection.start().line_index < line_range.end
+            })
+            .collect();
+        selections.sort_by_key(|selection| selection.start());
+        let mut decorations: Vec<Decoration> = Vec::new();
+        for selection in selections {
+            match decorations.last_mut() {
+                Some(decoration) if decoration.end() > selection.start() => {
+                    *decoration = Decoration::new(
+                        0,
+                        decoration.start(),
+                        decoration.end().max(selection.end()),
+                        DecorationType::RemoteSelection,
+                    );
+                }
+                _ => decorations.push(Decoration::new(
+                    0,
+                    selection.start(),
+                    selection.end(),
+                    DecorationType::RemoteSelection,
+                )),
+            }
+        }
+        decorations
+    }
+
+    pub fn handle_changes(&self) {
         while let Ok((selections, edits)) = self.edit_receiver.try_recv() {
             self.update_after_edit(selections, &edits);
         }
     }
 
+    fn search_line(&self, line: usize) -> Vec<Range<usize>> {
+        let mut search_state = self.search_state.borrow_mut();
+        let Some(search_state) = search_state.as_mut() else {
+            return Vec::new();
+        };
+        let searcher = &search_state.searcher;
+        search_state.line_matches[line]
+            .get_or_insert_with(|| searcher.find_all(&self.document.as_text().as_lines()[line]))
+            .clone()
+    }
+
+    fn select_snippet_tab_stop(&self, index: usize) -> bool {
+        let mut snippet_state_ref = self.snippet_state.borrow_mut();
+        let Some(snippet_state) = snippet_state_ref.as_mut() else {
+            return false;
+        };
+        let Some(ranges) = snippet_state.tab_stops.get(index).cloned() else {
+            *snippet_state_ref = None;
+            return false;
+        };
+        snippet_state.active_index = Some(index);
+        if index + 1 == snippet_state.tab_stops.len() {
+            *snippet_state_ref = None;
+        }
+        drop(snippet_state_ref);
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        for (range_index, range) in ranges.into_iter().enumerate() {
+            let selection = Selection {
+                anchor: range.start,
+                cursor: Cursor {
+                    position: range.end,
+                    affinity: Affinity::Before,
+                    preferred_column_index: None,
+                },
+            };
+            if range_index == 0 {
+                selection_state.selections.set_selection(selection);
+                selection_state.last_added_selection_index = Some(0);
+            } else {
+                selection_state.last_added_selection_index =
+                    Some(selection_state.selections.add_selection(selection));
+            }
+        }
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        true
+    }
+
+    // Selects the word under the cursor of the selection at `index`, if there is one.
+    fn select_word_at_cursor(&self, index: usize) -> bool {
+        let position = self.selections()[index].cursor.position;
+        let text = self.document.as_text();
+        let range = word_range(&text.as_lines()[position.line_index], position.byte_index);
+        drop(text);
+        if range.is_empty() {
+            return false;
+        }
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.last_added_selection_index =
+            Some(selection_state.selections.update_selection(index, |_| Selection {
+                anchor: Position {
+                    line_index: position.line_index,
+                    byte_index: range.start,
+                },
+                cursor: Cursor::from(Position {
+                    line_index: position.line_index,
+                    byte_index: range.end,
+                }),
+            }));
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        true
+    }
+
+    // Finds the occurrences of the text of a selection. Text within a line that is a whole word
+    // only matches whole words, so that selecting `i` does not select every `i` in the document.
+    fn find_occurrences_of(&self, selection: Selection) -> Vec<(Position, Position)> {
+        let text = self.document.as_text();
+        let needle = text.slice(selection.start(), selection.length());
+        let is_whole_word = selection.start().line_index == selection.end().line_index && {
+            let line = &text.as_lines()[selection.start().line_index];
+            let range = selection.start().byte_index..selection.end().byte_index;
+            line[range.clone()].chars().all(is_word_char)
+                && !line[..range.start].chars().next_back().is_some_and(is_word_char)
+                && !line[range.end..].chars().next().is_some_and(is_word_char)
+        };
+        find_occurrences(text.as_lines(), needle.as_lines(), is_whole_word)
+    }
+
+    // Replaces the selections, which must be in order. Empty and overlapping selections are
+    // merged as usual.
+    fn set_selections(&self, selections: Vec<Selection>, last_added_selection_index: usize) {
+        let Some(&first_selection) = selections.first() else {
+            return;
+        };
+        let last_added_selection = selections[last_added_selection_index];
+        let mut selection_set = SelectionSet::new();
+        selection_set.set_selection(first_selection);
+        for &selection in &selections[1..] {
+            selection_set.add_selection(selection);
+        }
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.last_added_selection_index = Some(
+            selection_set
+                .iter()
+                .position(|selection| selection.cursor.position == last_added_selection.cursor.position)
+                .unwrap_or(selection_set.len() - 1),
+        );
+        selection_state.selections = selection_set;
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.clear_snippet();
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+    }
+
+    fn add_cursor_vertically(&self, is_above: bool) -> bool {
+        let layout = self.layout();
+        let cursor = self.selections()[self.last_added_selection_index().unwrap()].cursor;
+        let is_at_edge = if is_above {
+            cursor.is_at_first_line() && cursor.is_at_first_row_of_line(&layout)
+        } else {
+            cursor.is_at_last_line(layout.as_text().as_lines().len())
+                && cursor.is_at_last_row_of_line(&layout)
+        };
+        if is_at_edge {
+            return false;
+        }
+        let cursor = if is_above {
+            cursor.move_up(&layout)
+        } else {
+            cursor.move_down(&layout)
+        };
+        drop(layout);
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.last_added_selection_index = Some(
+            selection_state
+                .selections
+                .add_selection(Selection::from(cursor)),
+        );
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+        true
+    }
+
+    fn select_search_match(&self, line: usize, range: Range<usize>) {
+        let mut selection_state = self.selection_state.borrow_mut();
+        selection_state.mode = SelectionMode::Simple;
+        selection_state.selections.set_selection(Selection {
+            anchor: Position {
+                line_index: line,
+                byte_index: range.start,
+            },
+            cursor: Cursor {
+                position: Position {
+                    line_index: line,
+                    byte_index: range.end,
+                },
+                affinity: Affinity::Before,
+                preferred_column_index: None,
+            },
+        });
+        selection_state.last_added_selection_index = Some(0);
+        selection_state.injected_char_stack.clear();
+        drop(selection_state);
+        self.update_highlighted_delimiter_positions();
+        self.document().force_new_group();
+    }
+
+    fn replace_selections(&self, selections: &SelectionSet, texts: Vec<String>) {
+        let mut texts = texts.into_iter();
+        self.document.edit_selections(
+            self.id,
+            EditKind::Other,
+            selections,
+            &self.settings,
+            |mut editor, position, length| {
+                editor.apply_edit(Edit {
+                    change: Change::Delete(position, length),
+                    drift: Drift::Before,
+                });
+                editor.apply_edit(Edit {
+                    change: Change::Insert(position, texts.next().unwrap().into()),
+                    drift: Drift::Before,
+                });
+            },
+        );
+    }
+
     fn modify_selections(
         &self,
         reset_anchor: bool,
@@ -837,12 +1912,20 @@ impl Session {
     }
 
     fn update_after_edit(&self, selections: Option<SelectionSet>, edits: &[Edit]) {
+        *self.conflicts.borrow_mut() = None;
+        // The lines that were edited in place are laid out again right away, while inserted lines
+        // are laid out lazily.
+        let mut edited_lines: Vec<usize> = Vec::new();
         for edit in edits {
             match edit.change {
                 Change::Insert(point, ref text) => {
-                    self.layout.borrow_mut().column_count[point.line_index] = None;
-                    self.layout.borrow_mut().wrap_data[point.line_index] = None;
                     let line_count = text.length().line_count;
+                    for line in &mut edited_lines {
+                        if *line > point.line_index {
+                            *line += line_count;
+                        }
+                    }
+                    edited_lines.push(point.line_index);
                     if line_count > 0 {
                         let line = point.line_index + 1;
                         self.layout.borrow_mut().y.truncate(line);
@@ -863,11 +1946,26 @@ impl Session {
                             .wrap_data
                             .splice(line..line, (0..line_count).map(|_| None));
                     }
+                    if let Some(search_state) = self.search_state.borrow_mut().as_mut() {
+                        search_state.line_matches[point.line_index] = None;
+                        let line = point.line_index + 1;
+                        search_state
+                            .line_matches
+                            .splice(line..line, (0..line_count).map(|_| None));
+                        search_state.first_unsearched_line =
+                            search_state.first_unsearched_line.min(point.line_index);
+                    }
                 }
                 Change::Delete(start, length) => {
-                    self.layout.borrow_mut().column_count[start.line_index] = None;
-                    self.layout.borrow_mut().wrap_data[start.line_index] = None;
                     let line_count = length.line_count;
+                    for line in &mut edited_lines {
+                        if *line > start.line_index + line_count {
+                            *line -= line_count;
+                        } else if *line > start.line_index {
+                            *line = start.line_index;
+                        }
+                    }
+                    edited_lines.push(start.line_index);
                     if line_count > 0 {
                         let start_line = start.line_index + 1;
                         let end_line = start_line + line_count;
@@ -886,15 +1984,27 @@ impl Session {
                             .wrap_data
                             .drain(start_line..end_line);
                     }
+                    if let Some(search_state) = self.search_state.borrow_mut().as_mut() {
+                        search_state.line_matches[start.line_index] = None;
+                        search_state
+                            .line_matches
+                            .drain(start.line_index + 1..start.line_index + 1 + line_count);
+                        search_state.first_unsearched_line =
+                            search_state.first_unsearched_line.min(start.line_index);
+                    }
                 }
             }
         }
-        let line_count = self.document.as_text().as_lines().len();
-        for line in 0..line_count {
-            if self.layout.borrow().wrap_data[line].is_none() {
-                self.update_wrap_data(line);
+        if let Some(snippet_state) = self.snippet_state.borrow_mut().as_mut() {
+            for edit in edits {
+                snippet_state.apply_edit(edit);
             }
         }
+        edited_lines.sort_unstable();
+        edited_lines.dedup();
+        for line in edited_lines {
+            self.update_wrap_data(line);
+        }
         self.update_y();
         let mut selection_state = self.selection_state.borrow_mut();
         if let Some(selections) = selections {
@@ -963,6 +2073,11 @@ impl Session {
         }
         drop(layout);
         self.layout.borrow_mut().column_count[index] = Some(column_count.max(column));
+        let layout = self.layout();
+        if let Some(width) = layout.session_layout.width.get() {
+            let line_width = layout.line(index).width();
+            layout.session_layout.width.set(Some(width.max(line_width)));
+        }
     }
 
     fn update_wrap_data(&self, line: usize) {
@@ -974,8 +2089,17 @@ impl Session {
             }
             None => WrapData::default(),
         };
-        self.layout.borrow_mut().wrap_data[line] = Some(wrap_data);
-        self.layout.borrow_mut().y.truncate(line + 1);
+        let mut layout = self.layout.borrow_mut();
+        // Lines that have not been laid out yet are assumed to fit on a single row, so the
+        // positions of the lines after this one only change if its row count does.
+        let old_row_count = layout.wrap_data[line]
+            .as_ref()
+            .map_or(1, |wrap_data| wrap_data.wraps.len() + 1);
+        if wrap_data.wraps.len() + 1 != old_row_count {
+            layout.y.truncate(line + 1);
+        }
+        layout.wrap_data[line] = Some(wrap_data);
+        drop(layout);
         self.update_column_count(line);
     }
 
@@ -1018,6 +2142,8 @@ pub struct SessionLayout {
     pub fold_column: Vec<usize>,
     pub scale: Vec<f64>,
     pub wrap_data: Vec<Option<WrapData>>,
+    /// The width of the widest line, computed on demand.
+    pub width: Cell<Option<f64>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -1037,6 +2163,45 @@ struct SelectionState {
     highlighted_delimiter_positions: HashSet<Position>,
 }
 
+#[derive(Debug)]
+struct SearchState {
+    searcher: Searcher,
+    // The matches on each line, or `None` if the line has not been searched yet.
+    line_matches: Vec<Option<Vec<Range<usize>>>>,
+    // No line before this one needs to be searched.
+    first_unsearched_line: usize,
+}
+
+#[derive(Debug)]
+struct SnippetState {
+    // The snippet that is being inserted, until its text shows up in an edit.
+    pending: Option<(Snippet, Text)>,
+    // The ranges of each tab stop, for every place the snippet was inserted.
+    tab_stops: Vec<Vec<Range<Position>>>,
+    active_index: Option<usize>,
+}
+
+impl SnippetState {
+    fn apply_edit(&mut self, edit: &Edit) {
+        for range in self.tab_stops.iter_mut().flatten() {
+            range.start = range.start.apply_edit(edit);
+            range.end = range.end.apply_edit(edit);
+        }
+        if let (Change::Insert(point, text), Some((snippet, snippet_text))) =
+            (&edit.change, &self.pending)
+        {
+            if text == snippet_text {
+                for (tab_stop, ranges) in self.tab_stops.iter_mut().zip(&snippet.tab_stops) {
+                    tab_stop.extend(ranges.iter().map(|range| {
+                        let start = *point + snippet.length_to(range.start);
+                        start..*point + snippet.length_to(range.end)
+                    }));
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct FoldState {
     folding_lines: HashSet<usize>,
@@ -1059,7 +2224,7 @@ pub fn reindent(string: &str, f: impl FnOnce(usize) -> usize) -> (usize, usize,
 
 fn grow_selection(
     selection: Selection,
-    lines: &[String],
+    lines: &Rope,
     mode: SelectionMode,
     word_separators: &[char],
 ) -> Selection {
@@ -1178,12 +2343,35 @@ fn grow_selection(
     }
 }
 
+// Returns whether a line starts an item that is worth keeping in view while its body is scrolled,
+// such as a function or an impl block.
+fn is_sticky_header(line: &str) -> bool {
+    const MODIFIERS: &[&str] = &[
+        "pub", "crate", "super", "self", "in", "async", "const", "unsafe", "extern", "default",
+    ];
+    const KEYWORDS: &[&str] = &[
+        "fn",
+        "impl",
+        "mod",
+        "trait",
+        "struct",
+        "enum",
+        "union",
+        "macro_rules",
+    ];
+
+    line.split(|char: char| !char.is_alphanumeric() && char != '_')
+        .filter(|word| !word.is_empty())
+        .find(|word| !MODIFIERS.contains(word))
+        .is_some_and(|word| KEYWORDS.contains(&word))
+}
+
 fn new_indentation(column_count: usize) -> String {
     iter::repeat(' ').take(column_count).collect()
 }
 
 fn find_highlighted_delimiter_pair(
-    lines: &[String],
+    lines: &Rope,
     position: Position,
 ) -> Option<(Position, Position)> {
     // Cursor is before an opening delimiter
@@ -1257,7 +2445,7 @@ fn find_highlighted_delimiter_pair(
 }
 
 fn find_opening_delimiter(
-    lines: &[String],
+    lines: &Rope,
     position: Position,
     closing_delimiter: char,
 ) -> Option<Position> {
@@ -1291,7 +2479,7 @@ fn find_opening_delimiter(
 }
 
 fn find_closing_delimiter(
-    lines: &[String],
+    lines: &Rope,
     position: Position,
     opening_delimiter: char,
 ) -> Option<Position> {
@@ -1320,3 +2508,130 @@ fn find_closing_delimiter(
         position.byte_index = 0;
     }
 }
+
+// Replaces the lines in `line_range` with `lines`.
+fn replace_lines(editor: &mut Editor<'_>, line_range: Range<usize>, lines: &[String]) {
+    let text_lines = editor.as_text().as_lines();
+    let line_count = text_lines.len();
+    let (start, end, text) = if line_range.end < line_count {
+        let mut text = lines.join("\n");
+        if !lines.is_empty() {
+            text.push('\n');
+        }
+        (
+            Position {
+                line_index: line_range.start,
+                byte_index: 0,
+            },
+            Position {
+                line_index: line_range.end,
+                byte_index: 0,
+            },
+            text,
+        )
+    } else if line_range.start > 0 {
+        // There is no line after the range to anchor the edit to, so replace the line
+        // break before the range instead.
+        let mut text = String::new();
+        for line in lines {
+            text.push('\n');
+            text.push_str(line);
+        }
+        (
+            Position {
+                line_index: line_range.start - 1,
+                byte_index: text_lines[line_range.start - 1].len(),
+            },
+            Position {
+                line_index: line_count - 1,
+                byte_index: text_lines[line_count - 1].len(),
+            },
+            text,
+        )
+    } else {
+        (
+            Position::zero(),
+            Position {
+                line_index: line_count - 1,
+                byte_index: text_lines[line_count - 1].len(),
+            },
+            lines.join("\n"),
+        )
+    };
+    if end > start {
+        editor.apply_edit(Edit {
+            change: Change::Delete(start, end - start),
+            drift: Drift::Before,
+        });
+    }
+    if !text.is_empty() {
+        editor.apply_edit(Edit {
+            change: Change::Insert(start, text.into()),
+            drift: Drift::Before,
+        });
+    }
+}
+
+// Returns the start and end of every occurrence of `needle` in `lines`, in order and without
+// overlaps. A needle of several lines must match the end of its first line and the start of its
+// last line.
+fn find_occurrences(lines: &Rope, needle: &Rope, is_whole_word: bool) -> Vec<(Position, Position)> {
+    let mut occurrences = Vec::new();
+    if needle.len() == 1 {
+        if needle[0].is_empty() {
+            return occurrences;
+        }
+        let searcher = Searcher::new(&SearchQuery {
+            pattern: needle[0].clone(),
+            is_case_sensitive: true,
+            is_whole_word,
+            is_regex: false,
+        })
+        .unwrap();
+        for (line_index, line) in lines.iter().enumerate() {
+            for range in searcher.find_all(line) {
+                occurrences.push((
+                    Position {
+                        line_index,
+                        byte_index: range.start,
+                    },
+                    Position {
+                        line_index,
+                        byte_index: range.end,
+                    },
+                ));
+            }
+        }
+        return occurrences;
+    }
+    let first = &needle[0];
+    let last = &needle[needle.len() - 1];
+    let mut min_byte_index = 0;
+    let mut line_index = 0;
+    while line_index + needle.len() <= lines.len() {
+        let line = &lines[line_index];
+        let is_match = line.ends_with(first.as_str())
+            && line.len() - first.len() >= min_byte_index
+            && (1..needle.len() - 1).all(|index| lines[line_index + index] == needle[index])
+            && lines[line_index + needle.len() - 1].starts_with(last.as_str());
+        if is_match {
+            occurrences.push((
+                Position {
+                    line_index,
+                    byte_index: line.len() - first.len(),
+                },
+                Position {
+                    line_index: line_index + needle.len() - 1,
+                    byte_index: last.len(),
+                },
+            ));
+            // The next occurrence can start on the line this one ends on.
+            line_index += needle.len() - 1;
+            min_byte_index = last.len();
+        } else {
+            line_index += 1;
+            min_byte_index = 0;
+        }
+    }
+    occurrences
+}
@@ -3,6 +3,8 @@ pub struct Settings {
     pub tab_column_count: usize,
     pub fold_level: usize,
     pub word_separators: Vec<char>,
+    pub show_minimap: bool,
+    pub sticky_scroll: bool,
 }
 
 impl Default for Settings {
@@ -14,6 +16,8 @@ impl Default for Settings {
                 ' ', '`', '~', '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '-', '=', '+',
                 '[', '{', ']', '}', '\\', '|', ';', ':', '\'', '"', '.', '<', '>', '/', '?', ',',
             ],
+            show_minimap: true,
+            sticky_scroll: true,
         }
     }
 }
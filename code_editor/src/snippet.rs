@@ -0,0 +1,115 @@
+use {
+    crate::text::Length,
+    std::{iter::Peekable, ops::Range, str::CharIndices},
+};
+
+/// A snippet parsed from the `$1`, `${1}`, `${1:placeholder}` and `$0` syntax used by completion
+/// items. A `\` escapes the next character.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Snippet {
+    pub text: String,
+    /// The tab stops in the order they are visited. Each tab stop has a range for every place it
+    /// occurs in the text. The final tab stop is `$0`, or the end of the text if there is none.
+    pub tab_stops: Vec<Vec<Range<usize>>>,
+}
+
+impl Snippet {
+    pub fn parse(string: &str) -> Self {
+        let mut parser = Parser {
+            chars: string.char_indices().peekable(),
+            text: String::new(),
+            tab_stops: Vec::new(),
+        };
+        parser.parse_until(None);
+        let mut tab_stops = parser.tab_stops;
+        tab_stops.sort_by_key(|&(index, _)| if index == 0 { usize::MAX } else { index });
+        let mut snippet = Self {
+            text: parser.text,
+            tab_stops: Vec::new(),
+        };
+        let mut prev_index = None;
+        for (index, range) in tab_stops {
+            if prev_index == Some(index) {
+                snippet.tab_stops.last_mut().unwrap().push(range);
+            } else {
+                snippet.tab_stops.push(vec![range]);
+            }
+            prev_index = Some(index);
+        }
+        if prev_index != Some(0) && !snippet.tab_stops.is_empty() {
+            let len = snippet.text.len();
+            snippet.tab_stops.push(vec![Range { start: len, end: len }]);
+        }
+        snippet
+    }
+
+    /// Returns the length of the text up to the given byte offset.
+    pub fn length_to(&self, byte_index: usize) -> Length {
+        let text = &self.text[..byte_index];
+        match text.rfind('\n') {
+            Some(newline_index) => Length {
+                line_count: text.matches('\n').count(),
+                byte_count: text.len() - newline_index - 1,
+            },
+            None => Length {
+                line_count: 0,
+                byte_count: text.len(),
+            },
+        }
+    }
+}
+
+impl From<&str> for Snippet {
+    fn from(string: &str) -> Self {
+        Self::parse(string)
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    text: String,
+    tab_stops: Vec<(usize, Range<usize>)>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_until(&mut self, terminator: Option<char>) {
+        while let Some((_, char)) = self.chars.next() {
+            match char {
+                '\\' => {
+                    if let Some((_, char)) = self.chars.next() {
+                        self.text.push(char);
+                    }
+                }
+                '$' => self.parse_tab_stop(),
+                char if Some(char) == terminator => return,
+                char => self.text.push(char),
+            }
+        }
+    }
+
+    fn parse_tab_stop(&mut self) {
+        let is_braced = self.chars.next_if(|&(_, char)| char == '{').is_some();
+        let Some(index) = self.parse_index() else {
+            self.text.push('$');
+            if is_braced {
+                self.text.push('{');
+            }
+            return;
+        };
+        let start = self.text.len();
+        if is_braced && self.chars.next_if(|&(_, char)| char == ':').is_some() {
+            self.parse_until(Some('}'));
+        } else if is_braced {
+            self.chars.next_if(|&(_, char)| char == '}');
+        }
+        self.tab_stops.push((index, start..self.text.len()));
+    }
+
+    fn parse_index(&mut self) -> Option<usize> {
+        let mut index = None;
+        while let Some((_, char)) = self.chars.next_if(|(_, char)| char.is_ascii_digit()) {
+            index = Some(index.unwrap_or(0) * 10 + char.to_digit(10).unwrap() as usize);
+        }
+        index
+    }
+}
@@ -1,14 +1,22 @@
-use std::{
-    cmp::Ordering,
-    fmt, io,
-    io::BufRead,
-    iter,
-    ops::{Add, AddAssign, Sub, SubAssign},
+use {
+    crate::rope::Rope,
+    makepad_widgets::makepad_micro_serde::*,
+    std::{
+        cmp::Ordering,
+        fmt, io,
+        io::BufRead,
+        iter,
+        ops::{Add, AddAssign, Sub, SubAssign},
+    },
 };
 
+/// A text as a sequence of lines. There is always at least one line.
+///
+/// The lines are stored in a `Rope`, so that edits and line lookups take O(log n) time and
+/// cloning a text, as the history does for undo, shares the unchanged parts.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Text {
-    lines: Vec<String>,
+    lines: Rope,
 }
 
 impl Text {
@@ -17,18 +25,23 @@ impl Text {
     }
 
     pub fn newline() -> Self {
-        Self {
-            lines: vec![String::new(), String::new()],
-        }
+        Self::from_lines(vec![String::new(), String::new()])
     }
 
     pub fn from_buf_reader<R>(reader: R) -> io::Result<Self>
     where
         R: BufRead,
     {
-        Ok(Self {
-            lines: reader.lines().collect::<Result<_, _>>()?,
-        })
+        Ok(Self::from_lines(reader.lines().collect::<Result<_, _>>()?))
+    }
+
+    fn from_lines(lines: Vec<String>) -> Self {
+        if lines.is_empty() {
+            return Self::default();
+        }
+        Self {
+            lines: Rope::from_lines(lines),
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -42,6 +55,11 @@ impl Text {
         }
     }
 
+    /// Returns the size of the text in bytes, counting each line break as one byte.
+    pub fn byte_count(&self) -> usize {
+        self.lines.byte_count() + self.lines.len() - 1
+    }
+
     pub fn to_single_char(&self) -> Option<char> {
         if self.lines.len() > 1 {
             return None;
@@ -53,7 +71,7 @@ impl Text {
         }
     }
 
-    pub fn as_lines(&self) -> &[String] {
+    pub fn as_lines(&self) -> &Rope {
         &self.lines
     }
 
@@ -66,13 +84,13 @@ impl Text {
             lines.reserve(end.line_index - start.line_index + 1);
             lines.push(self.lines[start.line_index][start.byte_index..].to_string());
             lines.extend(
-                self.lines[start.line_index + 1..end.line_index]
-                    .iter()
+                self.lines
+                    .range(start.line_index + 1..end.line_index)
                     .cloned(),
             );
             lines.push(self.lines[end.line_index][..end.byte_index].to_string());
         }
-        Text { lines }
+        Self::from_lines(lines)
     }
 
     pub fn apply_change(&mut self, change: Change) {
@@ -83,33 +101,39 @@ impl Text {
     }
 
     pub fn into_lines(self) -> Vec<String> {
-        self.lines
+        self.lines.iter().cloned().collect()
     }
 
-    fn insert(&mut self, point: Position, mut text: Self) {
+    fn insert(&mut self, point: Position, text: Self) {
         if text.length().line_count == 0 {
-            self.lines[point.line_index].replace_range(
-                point.byte_index..point.byte_index,
-                text.lines.first().unwrap(),
-            );
+            self.lines.update_line(point.line_index, |line| {
+                line.replace_range(
+                    point.byte_index..point.byte_index,
+                    text.lines.first().unwrap(),
+                )
+            });
         } else {
-            text.lines
+            let mut lines = text.into_lines();
+            let line = &self.lines[point.line_index];
+            lines
                 .first_mut()
                 .unwrap()
-                .replace_range(..0, &self.lines[point.line_index][..point.byte_index]);
-            text.lines
+                .replace_range(..0, &line[..point.byte_index]);
+            lines
                 .last_mut()
                 .unwrap()
-                .push_str(&self.lines[point.line_index][point.byte_index..]);
+                .push_str(&line[point.byte_index..]);
             self.lines
-                .splice(point.line_index..point.line_index + 1, text.lines);
+                .splice(point.line_index..point.line_index + 1, lines);
         }
     }
 
     fn delete(&mut self, start: Position, length: Length) {
         let end = start + length;
         if start.line_index == end.line_index {
-            self.lines[start.line_index].replace_range(start.byte_index..end.byte_index, "");
+            self.lines.update_line(start.line_index, |line| {
+                line.replace_range(start.byte_index..end.byte_index, "")
+            });
         } else {
             let mut line = self.lines[start.line_index][..start.byte_index].to_string();
             line.push_str(&self.lines[end.line_index][end.byte_index..]);
@@ -119,37 +143,49 @@ impl Text {
     }
 }
 
+impl SerBin for Text {
+    fn ser_bin(&self, s: &mut Vec<u8>) {
+        self.lines.len().ser_bin(s);
+        for line in self.lines.iter() {
+            line.ser_bin(s);
+        }
+    }
+}
+
+impl DeBin for Text {
+    fn de_bin(o: &mut usize, d: &[u8]) -> Result<Self, DeBinErr> {
+        let lines: Vec<String> = DeBin::de_bin(o, d)?;
+        Ok(Self::from_lines(lines))
+    }
+}
+
 impl Default for Text {
     fn default() -> Self {
         Self {
-            lines: vec![String::new()],
+            lines: Rope::from_lines(vec![String::new()]),
         }
     }
 }
 
 impl fmt::Display for Text {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let (last_line, remaining_lines) = self.lines.split_last().unwrap();
-        for line in remaining_lines {
+        let last_index = self.lines.len() - 1;
+        for line in self.lines.range(0..last_index) {
             writeln!(f, "{}", line)?;
         }
-        write!(f, "{}", last_line)
+        write!(f, "{}", self.lines[last_index])
     }
 }
 
 impl From<char> for Text {
     fn from(char: char) -> Self {
-        Self {
-            lines: vec![String::from(char)],
-        }
+        Self::from_lines(vec![String::from(char)])
     }
 }
 
 impl From<&str> for Text {
     fn from(string: &str) -> Self {
-        Self {
-            lines: string.split('\n').map(|string| string.to_owned()).collect(),
-        }
+        Self::from_lines(string.split('\n').map(|string| string.to_owned()).collect())
     }
 }
 
@@ -174,7 +210,7 @@ impl FromIterator<char> for Text {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub struct Edit {
     pub change: Change,
     pub drift: Drift,
@@ -189,7 +225,7 @@ impl Edit {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub enum Change {
     Insert(Position, Text),
     Delete(Position, Length),
@@ -204,7 +240,7 @@ impl Change {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
 pub struct Position {
     pub line_index: usize,
     pub byte_index: usize,
@@ -279,7 +315,7 @@ impl Sub for Position {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
 pub struct Length {
     pub line_count: usize,
     pub byte_count: usize,
@@ -339,7 +375,7 @@ impl SubAssign for Length {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
 pub enum Drift {
     Before,
     After,
@@ -1,25 +1,40 @@
-use crate::{
-    text::{Change, Text},
-    token::TokenKind,
-    Token,
+use {
+    crate::{
+        language::{Language, LanguageRef},
+        rope::{Rope, RopeLine},
+        text::{Change, Text},
+        token::TokenKind,
+        Token,
+    },
+    std::iter,
 };
 
+/// The maximum number of languages that can be nested inside each other, such as shader code
+/// inside DSL code inside Rust code.
+const MAX_EMBED_DEPTH: usize = 2;
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Tokenizer {
-    state: Vec<Option<(State, State)>>,
+    language: LanguageRef,
+    state: Rope<Option<(State, State)>>,
 }
 
 impl Tokenizer {
-    pub fn new(line_count: usize) -> Self {
+    pub fn new(language: &'static dyn Language, line_count: usize) -> Self {
         Self {
+            language: LanguageRef(language),
             state: (0..line_count).map(|_| None).collect(),
         }
     }
 
+    pub fn language(&self) -> &'static dyn Language {
+        self.language.0
+    }
+
     pub fn apply_change(&mut self, change: &Change) {
         match *change {
             Change::Insert(point, ref text) => {
-                self.state[point.line_index] = None;
+                self.state.update_line(point.line_index, |state| *state = None);
                 let line_count = text.length().line_count;
                 if line_count > 0 {
                     let line = point.line_index + 1;
@@ -27,447 +42,128 @@ impl Tokenizer {
                 }
             }
             Change::Delete(start, length) => {
-                self.state[start.line_index] = None;
+                self.state.update_line(start.line_index, |state| *state = None);
                 let line_count = length.line_count;
                 if line_count > 0 {
                     let start_line = start.line_index + 1;
                     let end_line = start_line + line_count;
-                    self.state.drain(start_line..end_line);
+                    self.state.splice(start_line..end_line, iter::empty());
                 }
             }
         }
     }
 
-    pub fn update(&mut self, text: &Text, tokens: &mut [Vec<Token>]) {
+    pub fn update(&mut self, text: &Text, tokens: &mut Rope<Vec<Token>>) {
         let mut state = State::default();
-        for line in 0..text.as_lines().len() {
-            match self.state[line] {
+        let mut changed_lines = Vec::new();
+        for (line_index, (line, line_state)) in
+            text.as_lines().iter().zip(self.state.iter()).enumerate()
+        {
+            match *line_state {
                 Some((start_state, end_state)) if state == start_state => {
                     state = end_state;
                 }
                 _ => {
                     let start_state = state;
                     let mut new_tokens = Vec::new();
-                    let mut cursor = Cursor::new(&text.as_lines()[line]);
-                    loop {
-                        let (next_state, token) = state.next(&mut cursor);
-                        state = next_state;
-                        match token {
-                            Some(token) => new_tokens.push(token),
-                            None => break,
-                        }
+                    let mut cursor = Cursor::new(line);
+                    while let Some(token) = state.next(self.language, &mut cursor) {
+                        new_tokens.push(token);
                     }
-                    self.state[line] = Some((start_state, state));
-                    tokens[line] = new_tokens;
+                    changed_lines.push((line_index, (start_state, state), new_tokens));
                 }
             }
         }
+        // Only the lines that were tokenized again are written back, since updating a line of a
+        // rope copies the nodes along its path.
+        for (line_index, line_state, new_tokens) in changed_lines {
+            self.state
+                .update_line(line_index, |state| *state = Some(line_state));
+            tokens.update_line(line_index, |tokens| *tokens = new_tokens);
+        }
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum State {
-    Initial(InitialState),
-    BlockCommentTail(BlockCommentTailState),
-    DoubleQuotedStringTail(DoubleQuotedStringTailState),
-    RawDoubleQuotedStringTail(RawDoubleQuotedStringTailState),
-}
+impl RopeLine for Option<(State, State)> {}
 
-impl Default for State {
-    fn default() -> State {
-        State::Initial(InitialState)
-    }
+/// The state of the tokenizer at the start or end of a line.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct State {
+    /// The state of the innermost language.
+    pub lex: LexState,
+    /// The language that will be embedded in the next `{ ... }` block.
+    pending_embed: Option<LanguageRef>,
+    embeds: [Option<Embed>; MAX_EMBED_DEPTH],
 }
 
 impl State {
-    pub fn next(self, cursor: &mut Cursor) -> (State, Option<Token>) {
+    pub fn next(&mut self, language: LanguageRef, cursor: &mut Cursor) -> Option<Token> {
         if cursor.peek(0) == '\0' {
-            return (self, None);
+            return None;
         }
+        let language = self
+            .embeds
+            .iter()
+            .rev()
+            .flatten()
+            .next()
+            .map_or(language, |embed| embed.language);
         let start = cursor.index;
-        let (next_state, kind) = match self {
-            State::Initial(state) => state.next(cursor),
-            State::BlockCommentTail(state) => state.next(cursor),
-            State::DoubleQuotedStringTail(state) => state.next(cursor),
-            State::RawDoubleQuotedStringTail(state) => state.next(cursor),
-        };
+        let kind = language.0.next_token(&mut self.lex, cursor);
         let end = cursor.index;
         assert!(start < end);
-        (
-            next_state,
-            Some(Token {
-                len: end - start,
-                kind,
-            }),
-        )
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct InitialState;
-
-impl InitialState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        match (cursor.peek(0), cursor.peek(1), cursor.peek(2)) {
-            ('r', '#', '"') | ('r', '#', '#') => self.raw_string(cursor),
-            ('b', 'r', '"') | ('b', 'r', '#') => self.raw_byte_string(cursor),
-            ('/', '/', _) => self.line_comment(cursor),
-            ('/', '*', _) => self.block_comment(cursor),
-            ('b', '\'', _) => self.byte(cursor),
-            ('b', '"', _) => self.byte_string(cursor),
-            ('!', '=', _)
-            | ('%', '=', _)
-            | ('&', '&', _)
-            | ('&', '=', _)
-            | ('*', '=', _)
-            | ('+', '=', _)
-            | ('-', '=', _)
-            | ('-', '>', _)
-            | ('.', '.', _)
-            | ('/', '=', _)
-            | (':', ':', _)
-            | ('<', '<', _)
-            | ('<', '=', _)
-            | ('=', '=', _)
-            | ('=', '>', _)
-            | ('>', '=', _)
-            | ('>', '>', _)
-            | ('^', '=', _)
-            | ('|', '=', _)
-            | ('|', '|', _) => {
-                cursor.skip(2);
-                (State::Initial(InitialState), TokenKind::Punctuator)
-            }
-            ('\'', _, _) => self.char_or_lifetime(cursor),
-            ('"', _, _) => self.string(cursor),
-            ('(', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            (')', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('[', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            (']', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('{', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('}', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Delimiter)
-            }
-            ('.', char, _) if char.is_digit(10) => self.number(cursor),
-            ('!', _, _)
-            | ('#', _, _)
-            | ('$', _, _)
-            | ('%', _, _)
-            | ('&', _, _)
-            | ('*', _, _)
-            | ('+', _, _)
-            | (',', _, _)
-            | ('-', _, _)
-            | ('.', _, _)
-            | ('/', _, _)
-            | (':', _, _)
-            | (';', _, _)
-            | ('<', _, _)
-            | ('=', _, _)
-            | ('>', _, _)
-            | ('?', _, _)
-            | ('@', _, _)
-            | ('^', _, _)
-            | ('_', _, _)
-            | ('|', _, _) => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Punctuator)
-            }
-            (char, _, _) if char.is_identifier_start() => self.identifier_or_keyword(cursor),
-            (char, _, _) if char.is_digit(10) => self.number(cursor),
-            (char, _, _) if char.is_whitespace() => self.whitespace(cursor),
-            _ => {
-                cursor.skip(1);
-                (State::Initial(InitialState), TokenKind::Unknown)
-            }
-        }
-    }
-
-    fn line_comment(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '/' && cursor.peek(1) == '/');
-        cursor.skip(2);
-        while cursor.skip_if(|ch| ch != '\0') {}
-        (State::Initial(InitialState), TokenKind::Comment)
-    }
-
-    fn block_comment(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '/' && cursor.peek(1) == '*');
-        cursor.skip(2);
-        BlockCommentTailState { depth: 0 }.next(cursor)
-    }
-
-    fn identifier_or_keyword(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0).is_identifier_start());
-        let start = cursor.index;
-        cursor.skip(1);
-        while cursor.skip_if(|char| char.is_identifier_continue()) {}
-        let end = cursor.index;
         let string = &cursor.string[start..end];
-        (
-            State::Initial(InitialState),
-            match string {
-                "else" | "if" | "match" | "return" => TokenKind::BranchKeyword,
-                "break" | "continue" | "for" | "loop" | "while" => TokenKind::LoopKeyword,
-                "Self" | "as" | "async" | "await" | "const" | "crate" | "dyn" | "enum"
-                | "extern" | "false" | "fn" | "impl" | "in" | "let" | "mod" | "move" | "mut"
-                | "pub" | "ref" | "self" | "static" | "struct" | "super" | "trait" | "true"
-                | "type" | "unsafe" | "use" | "where" | "usize" | "isize" | "u8" | "u16"
-                | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" | "vec2" | "vec3" | "vec4"
-                | "bool" | "f32" | "f64" => TokenKind::OtherKeyword,
-                _ => {
-                    let mut chars = string.chars();
-                    if chars.next().unwrap().is_uppercase() {
-                        match chars.next() {
-                            Some(char) if char.is_uppercase() => TokenKind::Constant,
-                            _ => TokenKind::Typename,
-                        }
-                    } else if cursor.peek(0) == '(' {
-                        TokenKind::Function
-                    } else {
-                        TokenKind::Identifier
+        match (kind, string) {
+            (TokenKind::Delimiter, "{") => {
+                if let Some(language) = self.pending_embed.take() {
+                    if let Some(slot) = self.embeds.iter_mut().find(|embed| embed.is_none()) {
+                        *slot = Some(Embed { language, depth: 0 });
+                        self.lex = LexState::default();
+                        return Some(Token { len: end - start, kind });
                     }
                 }
-            },
-        )
-    }
-
-    fn number(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        match (cursor.peek(0), cursor.peek(1)) {
-            ('0', 'b') => {
-                cursor.skip(2);
-                if !cursor.skip_digits(2) {
-                    return (State::Initial(InitialState), TokenKind::Unknown);
-                }
-                return (State::Initial(InitialState), TokenKind::Number);
-            }
-            ('0', 'o') => {
-                cursor.skip(2);
-                if !cursor.skip_digits(8) {
-                    return (State::Initial(InitialState), TokenKind::Unknown);
-                }
-                return (State::Initial(InitialState), TokenKind::Number);
-            }
-            ('0', 'x') => {
-                cursor.skip(2);
-                if !cursor.skip_digits(16) {
-                    return (State::Initial(InitialState), TokenKind::Unknown);
+                if let Some(embed) = self.embeds.iter_mut().rev().flatten().next() {
+                    embed.depth += 1;
                 }
-                return (State::Initial(InitialState), TokenKind::Number);
             }
-            _ => {
-                cursor.skip_digits(10);
-                match cursor.peek(0) {
-                    '.' if cursor.peek(1) != '.' && !cursor.peek(0).is_identifier_start() => {
-                        cursor.skip(1);
-                        if cursor.skip_digits(10) {
-                            if cursor.peek(0) == 'E' || cursor.peek(0) == 'e' {
-                                if !cursor.skip_exponent() {
-                                    return (State::Initial(InitialState), TokenKind::Unknown);
-                                }
-                            }
-                        }
-                        cursor.skip_suffix();
-                        return (State::Initial(InitialState), TokenKind::Number);
-                    }
-                    'E' | 'e' => {
-                        if !cursor.skip_exponent() {
-                            return (State::Initial(InitialState), TokenKind::Unknown);
-                        }
-                        cursor.skip_suffix();
-                        return (State::Initial(InitialState), TokenKind::Number);
-                    }
-                    _ => {
-                        cursor.skip_suffix();
-                        return (State::Initial(InitialState), TokenKind::Number);
+            (TokenKind::Delimiter, "}") => {
+                if let Some(slot) = self.embeds.iter_mut().rev().find(|embed| embed.is_some()) {
+                    let embed = slot.as_mut().unwrap();
+                    if embed.depth == 0 {
+                        *slot = None;
+                        self.lex = LexState::default();
+                    } else {
+                        embed.depth -= 1;
                     }
                 }
             }
-        };
-    }
-
-    fn char_or_lifetime(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        if cursor.peek(1).is_identifier_start() && cursor.peek(2) != '\'' {
-            debug_assert!(cursor.peek(0) == '\'');
-            cursor.skip(2);
-            while cursor.skip_if(|ch| ch.is_identifier_continue()) {}
-            if cursor.peek(0) == '\'' {
-                cursor.skip(1);
-                cursor.skip_suffix();
-                (State::Initial(InitialState), TokenKind::String)
-            } else {
-                (State::Initial(InitialState), TokenKind::String)
-            }
-        } else {
-            self.single_quoted_string(cursor)
-        }
-    }
-
-    fn byte(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'b');
-        cursor.skip(1);
-        self.single_quoted_string(cursor)
-    }
-
-    fn string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        self.double_quoted_string(cursor)
-    }
-
-    fn byte_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'b');
-        cursor.skip(1);
-        self.double_quoted_string(cursor)
-    }
-
-    fn raw_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'r');
-        cursor.skip(1);
-        self.raw_double_quoted_string(cursor)
-    }
-
-    fn raw_byte_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == 'b' && cursor.peek(1) == 'r');
-        cursor.skip(2);
-        self.raw_double_quoted_string(cursor)
-    }
-
-    fn single_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '\'');
-        cursor.skip(1);
-        loop {
-            match (cursor.peek(0), cursor.peek(1)) {
-                ('\'', _) => {
-                    cursor.skip(1);
-                    cursor.skip_suffix();
-                    break;
-                }
-                ('\0', _) => return (State::Initial(InitialState), TokenKind::Unknown),
-                ('\\', '\'') | ('\\', '\\') => cursor.skip(2),
-                _ => cursor.skip(1),
-            }
-        }
-        (State::Initial(InitialState), TokenKind::String)
-    }
-
-    fn double_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0) == '"');
-        cursor.skip(1);
-        DoubleQuotedStringTailState.next(cursor)
-    }
-
-    fn raw_double_quoted_string(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        let mut start_hash_count = 0;
-        while cursor.skip_if(|ch| ch == '#') {
-            start_hash_count += 1;
-        }
-        RawDoubleQuotedStringTailState { start_hash_count }.next(cursor)
-    }
-
-    fn whitespace(self, cursor: &mut Cursor) -> (State, TokenKind) {
-        debug_assert!(cursor.peek(0).is_whitespace());
-        cursor.skip(1);
-        while cursor.skip_if(|char| char.is_whitespace()) {}
-        (State::Initial(InitialState), TokenKind::Whitespace)
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct BlockCommentTailState {
-    depth: usize,
-}
-
-impl BlockCommentTailState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        let mut state = self;
-        loop {
-            match (cursor.peek(0), cursor.peek(1)) {
-                ('/', '*') => {
-                    cursor.skip(2);
-                    state.depth += 1;
-                }
-                ('*', '/') => {
-                    cursor.skip(2);
-                    if state.depth == 0 {
-                        break (State::Initial(InitialState), TokenKind::Comment);
-                    }
-                    state.depth -= 1;
-                }
-                ('\0', _) => {
-                    break (State::BlockCommentTail(state), TokenKind::Comment);
+            (TokenKind::Punctuator, ";") => self.pending_embed = None,
+            (TokenKind::Whitespace | TokenKind::Comment, _) => {}
+            _ => {
+                if let Some(embedded_language) = language.0.embedded_language(string, cursor) {
+                    self.pending_embed = Some(LanguageRef(embedded_language));
                 }
-                _ => cursor.skip(1),
             }
         }
+        Some(Token {
+            len: end - start,
+            kind,
+        })
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct DoubleQuotedStringTailState;
-
-impl DoubleQuotedStringTailState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        loop {
-            match (cursor.peek(0), cursor.peek(1)) {
-                ('"', _) => {
-                    cursor.skip(1);
-                    cursor.skip_suffix();
-                    break (State::Initial(InitialState), TokenKind::String);
-                }
-                ('\0', _) => {
-                    break (
-                        State::DoubleQuotedStringTail(DoubleQuotedStringTailState),
-                        TokenKind::String,
-                    );
-                }
-                ('\\', '"') | ('\\', '\\') => cursor.skip(2),
-                _ => cursor.skip(1),
-            }
-        }
-    }
+/// The part of the tokenizer state that is owned by a language. What `mode` and `data` mean is
+/// up to the language, with the default value meaning that no token is in progress.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LexState {
+    pub mode: u8,
+    pub data: u16,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct RawDoubleQuotedStringTailState {
-    start_hash_count: usize,
-}
-
-impl RawDoubleQuotedStringTailState {
-    fn next(self, cursor: &mut Cursor<'_>) -> (State, TokenKind) {
-        loop {
-            match cursor.peek(0) {
-                '"' => {
-                    cursor.skip(1);
-                    let mut end_hash_count = 0;
-                    while end_hash_count < self.start_hash_count && cursor.skip_if(|ch| ch == '#') {
-                        end_hash_count += 1;
-                    }
-                    if end_hash_count == self.start_hash_count {
-                        cursor.skip_suffix();
-                        break (State::Initial(InitialState), TokenKind::String);
-                    }
-                }
-                '\0' => {
-                    break (State::RawDoubleQuotedStringTail(self), TokenKind::String);
-                }
-                _ => cursor.skip(1),
-            }
-        }
-    }
+struct Embed {
+    language: LanguageRef,
+    depth: usize,
 }
 
 #[derive(Debug)]
@@ -481,18 +177,32 @@ impl<'a> Cursor<'a> {
         Cursor { string, index: 0 }
     }
 
-    fn peek(&self, index: usize) -> char {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the text from the start of the line up to the cursor.
+    pub fn before(&self) -> &'a str {
+        &self.string[..self.index]
+    }
+
+    /// Returns the text from the cursor up to the end of the line.
+    pub fn rest(&self) -> &'a str {
+        &self.string[self.index..]
+    }
+
+    pub fn peek(&self, index: usize) -> char {
         self.string[self.index..].chars().nth(index).unwrap_or('\0')
     }
 
-    fn skip(&mut self, count: usize) {
+    pub fn skip(&mut self, count: usize) {
         self.index = self.string[self.index..]
             .char_indices()
             .nth(count)
             .map_or(self.string.len(), |(index, _)| self.index + index);
     }
 
-    fn skip_if<P>(&mut self, predicate: P) -> bool
+    pub fn skip_if<P>(&mut self, predicate: P) -> bool
     where
         P: FnOnce(char) -> bool,
     {
@@ -504,7 +214,18 @@ impl<'a> Cursor<'a> {
         }
     }
 
-    fn skip_exponent(&mut self) -> bool {
+    pub fn skip_while<P>(&mut self, predicate: P)
+    where
+        P: Fn(char) -> bool,
+    {
+        while self.peek(0) != '\0' && self.skip_if(&predicate) {}
+    }
+
+    pub fn skip_to_end(&mut self) {
+        self.index = self.string.len();
+    }
+
+    pub fn skip_exponent(&mut self) -> bool {
         debug_assert!(self.peek(0) == 'E' || self.peek(0) == 'e');
         self.skip(1);
         if self.peek(0) == '+' || self.peek(0) == '-' {
@@ -513,7 +234,7 @@ impl<'a> Cursor<'a> {
         self.skip_digits(10)
     }
 
-    fn skip_digits(&mut self, radix: u32) -> bool {
+    pub fn skip_digits(&mut self, radix: u32) -> bool {
         let mut has_skip_digits = false;
         loop {
             match self.peek(0) {
@@ -530,7 +251,7 @@ impl<'a> Cursor<'a> {
         has_skip_digits
     }
 
-    fn skip_suffix(&mut self) -> bool {
+    pub fn skip_suffix(&mut self) -> bool {
         if self.peek(0).is_identifier_start() {
             self.skip(1);
             while self.skip_if(|char| char.is_identifier_continue()) {}
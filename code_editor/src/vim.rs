@@ -0,0 +1,1605 @@
+use {
+    crate::{
+        history::{EditKind, NewGroup},
+        rope::Rope,
+        selection::Affinity,
+        session::{SelectionMode, Session},
+        text::{Position, Text},
+    },
+    std::collections::HashMap,
+};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+/// A key as seen by the Vim emulation. Printable keys are `Char`s, so that they are independent
+/// of the keyboard layout.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum VimKey {
+    Char(char),
+    Ctrl(char),
+    Escape,
+    Enter,
+    Backspace,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl VimKey {
+    /// Parses keys written in Vim notation, such as `"ciwfoo<Esc>"`. Special keys are `<Esc>`,
+    /// `<CR>`, `<BS>`, `<Tab>`, `<Left>`, `<Right>`, `<Up>`, `<Down>` and `<C-x>`. A `<` that
+    /// does not start one of these is taken literally.
+    pub fn parse_keys(keys: &str) -> Vec<Self> {
+        let mut parsed = Vec::new();
+        let mut rest = keys;
+        while let Some(char) = rest.chars().next() {
+            if char == '<' {
+                if let Some((key, len)) = rest[1..].find('>').and_then(|end| {
+                    let key = match &rest[1..end + 1] {
+                        "Esc" => Self::Escape,
+                        "CR" | "Enter" => Self::Enter,
+                        "BS" => Self::Backspace,
+                        "Tab" => Self::Tab,
+                        "Left" => Self::Left,
+                        "Right" => Self::Right,
+                        "Up" => Self::Up,
+                        "Down" => Self::Down,
+                        "lt" => Self::Char('<'),
+                        name => {
+                            let mut chars = name.strip_prefix("C-")?.chars();
+                            let char = chars.next()?;
+                            if chars.next().is_some() {
+                                return None;
+                            }
+                            Self::Ctrl(char)
+                        }
+                    };
+                    Some((key, end + 2))
+                }) {
+                    parsed.push(key);
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+            parsed.push(Self::Char(char));
+            rest = &rest[char.len_utf8()..];
+        }
+        parsed
+    }
+}
+
+/// The contents of a register.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Register {
+    pub text: String,
+    /// Whether the text was yanked or deleted as whole lines, in which case it is put on lines
+    /// of its own. The text does not end with a line break.
+    pub is_linewise: bool,
+}
+
+/// A modal Vim emulation on top of a `Session`.
+///
+/// Keys are fed to `handle_key` one at a time. In normal and visual mode they are collected until
+/// they form a complete command, such as `"a3dw`, which is then executed. Commands that change
+/// the text are recorded, including the text typed in insert mode afterwards, so that `.` can
+/// replay them. Each command is a single undo step.
+#[derive(Clone, Debug, Default)]
+pub struct Vim {
+    mode: VimMode,
+    // The keys of the command that is being typed.
+    pending_keys: Vec<VimKey>,
+    registers: HashMap<char, Register>,
+    // The keys of the last change, and of the change that is being recorded while in insert mode.
+    last_change: Vec<VimKey>,
+    recording: Option<Vec<VimKey>>,
+    // The ends of the selection in visual mode. The cursor is on the last char of the selection,
+    // rather than after it like the cursor of the session.
+    visual_anchor: Position,
+    visual_cursor: Position,
+    // The column that vertical motions try to stay in, in chars.
+    preferred_column: Option<usize>,
+    undo_group: u64,
+    text_changed: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordStart {
+        is_big: bool,
+    },
+    WordEnd {
+        is_big: bool,
+    },
+    WordBack {
+        is_big: bool,
+    },
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FileStart,
+    FileEnd,
+    FindChar {
+        char: char,
+        is_forward: bool,
+        is_till: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MotionKind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Target {
+    Motion(Motion),
+    // The operator was doubled, as in `dd`.
+    Lines,
+    Word { is_big: bool, is_around: bool },
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum InsertAt {
+    Cursor,
+    AfterCursor,
+    FirstNonBlank,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Action {
+    Move(Motion),
+    Operate(Operator, Target),
+    Insert(InsertAt),
+    Put { is_before: bool },
+    ReplaceChar(char),
+    Undo,
+    Redo,
+    Repeat,
+    EnterVisual(VimMode),
+    // Only in visual mode.
+    OperateOnSelection(Operator),
+    SwapAnchor,
+}
+
+impl Action {
+    fn is_change(self) -> bool {
+        match self {
+            Self::Operate(Operator::Yank, _) => false,
+            Self::Operate(..) | Self::Insert(_) | Self::Put { .. } | Self::ReplaceChar(_) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Command {
+    register: Option<char>,
+    count: Option<usize>,
+    action: Action,
+}
+
+enum Parse<T> {
+    Complete(T),
+    Incomplete,
+    Invalid,
+}
+
+// Text is edited in one of two ways: as a range of chars, or as whole lines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Span {
+    Chars(Position, Position),
+    Lines(usize, usize),
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Returns the keys of the command that has been typed so far.
+    pub fn pending_keys(&self) -> &[VimKey] {
+        &self.pending_keys
+    }
+
+    pub fn register(&self, name: char) -> Option<&Register> {
+        self.registers.get(&name)
+    }
+
+    /// Handles a key. Returns `None` if the key has no meaning in the current mode, so that it can
+    /// be handled as usual, or else whether the text was changed.
+    pub fn handle_key(&mut self, session: &Session, key: VimKey) -> Option<bool> {
+        self.text_changed = false;
+        let is_handled = match self.mode {
+            VimMode::Insert => self.handle_insert_key(session, key),
+            VimMode::Normal | VimMode::Visual | VimMode::VisualLine => {
+                self.handle_command_key(session, key)
+            }
+        };
+        if is_handled {
+            Some(self.text_changed)
+        } else {
+            None
+        }
+    }
+
+    /// Handles keys written in Vim notation, as for `VimKey::parse_keys`. Returns whether the text
+    /// was changed.
+    pub fn handle_keys(&mut self, session: &Session, keys: &str) -> bool {
+        let mut text_changed = false;
+        for key in VimKey::parse_keys(keys) {
+            text_changed |= self.handle_key(session, key).unwrap_or(false);
+        }
+        text_changed
+    }
+
+    fn handle_insert_key(&mut self, session: &Session, key: VimKey) -> bool {
+        let cursor = self.cursor(session);
+        match key {
+            VimKey::Escape => {
+                if let Some(recording) = self.recording.take() {
+                    self.last_change = recording;
+                    self.last_change.push(VimKey::Escape);
+                }
+                self.mode = VimMode::Normal;
+                self.undo_group += 1;
+                let text = session.document().as_text();
+                let position = prev_char_in_line(&text.as_lines()[cursor.line_index], cursor);
+                drop(text);
+                self.set_cursor(session, position);
+                return true;
+            }
+            VimKey::Char(char) => {
+                let mut buf = [0; 4];
+                self.insert(session, cursor, char.encode_utf8(&mut buf));
+            }
+            VimKey::Tab => {
+                let indent = " ".repeat(session.settings().tab_column_count);
+                self.insert(session, cursor, &indent);
+            }
+            VimKey::Enter => {
+                let text = session.document().as_text();
+                let line = &text.as_lines()[cursor.line_index];
+                let indent = format!(
+                    "\n{}",
+                    &line[..first_non_blank(line).min(cursor.byte_index)]
+                );
+                drop(text);
+                self.insert(session, cursor, &indent);
+            }
+            VimKey::Backspace => {
+                let text = session.document().as_text();
+                let Some(start) = prev_position(text.as_lines(), cursor) else {
+                    return true;
+                };
+                drop(text);
+                self.replace(session, start, cursor, "");
+                self.set_cursor(session, start);
+            }
+            VimKey::Left | VimKey::Right | VimKey::Up | VimKey::Down => {
+                let text = session.document().as_text();
+                let lines = text.as_lines();
+                let motion = match key {
+                    VimKey::Left => Motion::Left,
+                    VimKey::Right => Motion::Right,
+                    VimKey::Up => Motion::Up,
+                    _ => Motion::Down,
+                };
+                let target = self.motion_target(lines, cursor, motion, None);
+                drop(text);
+                if let Some((position, _)) = target {
+                    if !matches!(motion, Motion::Up | Motion::Down) {
+                        self.preferred_column = None;
+                    }
+                    self.set_cursor(session, position);
+                }
+                // Moving around ends the change that `.` repeats.
+                if let Some(recording) = self.recording.take() {
+                    self.last_change = recording;
+                    self.last_change.push(VimKey::Escape);
+                }
+                self.undo_group += 1;
+                return true;
+            }
+            VimKey::Ctrl(_) => return false,
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.push(key);
+        }
+        true
+    }
+
+    fn handle_command_key(&mut self, session: &Session, key: VimKey) -> bool {
+        if key == VimKey::Escape && self.pending_keys.is_empty() {
+            if self.mode != VimMode::Normal {
+                self.exit_visual(session);
+            }
+            return true;
+        }
+        self.pending_keys.push(key);
+        let parse = if self.mode == VimMode::Normal {
+            parse_normal_command(&self.pending_keys)
+        } else {
+            parse_visual_command(&self.pending_keys)
+        };
+        match parse {
+            Parse::Incomplete => {}
+            Parse::Invalid => {
+                // A special key that does not start a command, such as `<C-f>`, is left for the
+                // editor to handle.
+                let is_first_key = self.pending_keys.len() == 1;
+                self.pending_keys.clear();
+                if is_first_key && !matches!(key, VimKey::Char(_)) {
+                    return false;
+                }
+            }
+            Parse::Complete(command) => {
+                let keys = std::mem::take(&mut self.pending_keys);
+                let is_change = self.mode == VimMode::Normal && command.action.is_change();
+                self.execute(session, command);
+                self.undo_group += 1;
+                if is_change {
+                    if self.mode == VimMode::Insert {
+                        self.recording = Some(keys);
+                    } else {
+                        self.last_change = keys;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn execute(&mut self, session: &Session, command: Command) {
+        let count = command.count.unwrap_or(1);
+        match command.action {
+            Action::Move(motion) => {
+                let cursor = self.cursor(session);
+                let text = session.document().as_text();
+                let target = self.motion_target(text.as_lines(), cursor, motion, command.count);
+                drop(text);
+                if let Some((position, _)) = target {
+                    self.update_preferred_column(session, motion);
+                    if self.mode == VimMode::Normal {
+                        self.set_cursor(session, position);
+                    } else {
+                        self.select_visual(session, position);
+                    }
+                }
+            }
+            Action::Operate(operator, target) => {
+                let Some(span) = self.target_span(session, operator, target, command.count) else {
+                    return;
+                };
+                self.operate(session, operator, span, command.register);
+            }
+            Action::OperateOnSelection(operator) => {
+                let cursor = self.cursor(session);
+                let start = self.visual_anchor.min(cursor);
+                let end = self.visual_anchor.max(cursor);
+                let span = if self.mode == VimMode::VisualLine {
+                    Span::Lines(start.line_index, end.line_index)
+                } else {
+                    let text = session.document().as_text();
+                    let end = inclusive_end(&text.as_lines()[end.line_index], end);
+                    Span::Chars(start, end)
+                };
+                self.mode = VimMode::Normal;
+                self.operate(session, operator, span, command.register);
+            }
+            Action::Insert(insert_at) => self.start_insert(session, insert_at),
+            Action::Put { is_before } => {
+                for _ in 0..count {
+                    self.put(session, command.register, is_before);
+                }
+            }
+            Action::ReplaceChar(char) => {
+                let cursor = self.cursor(session);
+                let text = session.document().as_text();
+                let line = &text.as_lines()[cursor.line_index];
+                let mut end = cursor.byte_index;
+                for _ in 0..count {
+                    let Some(next) = line[end..].chars().next() else {
+                        // Like Vim, do nothing if there are not enough chars.
+                        return;
+                    };
+                    end += next.len_utf8();
+                }
+                let end = Position {
+                    line_index: cursor.line_index,
+                    byte_index: end,
+                };
+                drop(text);
+                let replacement = char.to_string().repeat(count);
+                self.replace(session, cursor, end, &replacement);
+                self.set_cursor(
+                    session,
+                    Position {
+                        line_index: cursor.line_index,
+                        byte_index: cursor.byte_index + replacement.len() - char.len_utf8(),
+                    },
+                );
+            }
+            Action::Undo => {
+                // Stop once the history runs out, so that a large count does not spin.
+                for _ in 0..count {
+                    if !session.undo() {
+                        break;
+                    }
+                    self.text_changed = true;
+                }
+                session.handle_changes();
+                self.set_cursor(session, self.cursor(session));
+            }
+            Action::Redo => {
+                for _ in 0..count {
+                    if !session.redo() {
+                        break;
+                    }
+                    self.text_changed = true;
+                }
+                session.handle_changes();
+                self.set_cursor(session, self.cursor(session));
+            }
+            Action::Repeat => {
+                let mut keys = self.last_change.clone();
+                if keys.is_empty() {
+                    return;
+                }
+                if let Some(count) = command.count {
+                    // A count replaces the count of the change.
+                    let start = if keys[0] == VimKey::Char('"') { 2 } else { 0 };
+                    let digit_count = keys[start..]
+                        .iter()
+                        .take_while(
+                            |key| matches!(key, VimKey::Char(char) if char.is_ascii_digit()),
+                        )
+                        .count();
+                    keys.splice(
+                        start..start + digit_count,
+                        count.to_string().chars().map(VimKey::Char),
+                    );
+                }
+                let mut text_changed = false;
+                for key in keys {
+                    text_changed |= self.handle_key(session, key).unwrap_or(false);
+                }
+                self.text_changed = text_changed;
+            }
+            Action::EnterVisual(mode) => {
+                if self.mode == mode {
+                    self.exit_visual(session);
+                } else {
+                    let cursor = self.cursor(session);
+                    if self.mode == VimMode::Normal {
+                        self.visual_anchor = cursor;
+                    }
+                    self.mode = mode;
+                    self.select_visual(session, cursor);
+                }
+            }
+            Action::SwapAnchor => {
+                let cursor = self.cursor(session);
+                let anchor = self.visual_anchor;
+                self.visual_anchor = cursor;
+                self.select_visual(session, anchor);
+            }
+        }
+    }
+
+    fn operate(
+        &mut self,
+        session: &Session,
+        operator: Operator,
+        span: Span,
+        register: Option<char>,
+    ) {
+        let text = session.document().as_text();
+        let lines = text.as_lines();
+        let (start, end) = span_range(lines, span);
+        let yanked = match span {
+            Span::Chars(start, end) => Register {
+                text: text.slice(start, end - start).to_string(),
+                is_linewise: false,
+            },
+            Span::Lines(first, last) => Register {
+                text: lines
+                    .range(first..last + 1)
+                    .map(|line| line.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                is_linewise: true,
+            },
+        };
+        match operator {
+            Operator::Yank => {
+                // The cursor moves to the start of what was yanked, but stays put for `yy`.
+                let cursor = self.cursor(session);
+                let position = match span {
+                    Span::Chars(start, _) => start,
+                    Span::Lines(first, _) if first < cursor.line_index => Position {
+                        line_index: first,
+                        byte_index: first_non_blank(&lines[first]),
+                    },
+                    Span::Lines(..) => cursor,
+                };
+                drop(text);
+                self.set_register(register, yanked, true);
+                self.set_cursor(session, position);
+            }
+            Operator::Delete => {
+                drop(text);
+                self.set_register(register, yanked, false);
+                self.replace(session, start, end, "");
+                match span {
+                    Span::Chars(start, _) => self.set_cursor(session, start),
+                    Span::Lines(first, _) => self.move_to_first_non_blank(session, first),
+                }
+            }
+            Operator::Change => match span {
+                Span::Chars(start, end) => {
+                    drop(text);
+                    self.set_register(register, yanked, false);
+                    self.replace(session, start, end, "");
+                    self.mode = VimMode::Insert;
+                    session.set_selection(
+                        start,
+                        Affinity::Before,
+                        SelectionMode::Simple,
+                        NewGroup::No,
+                    );
+                }
+                Span::Lines(first, last) => {
+                    // Keep a single line with the indentation of the first one.
+                    let indent = lines[first][..first_non_blank(&lines[first])].to_string();
+                    let end = Position {
+                        line_index: last,
+                        byte_index: lines[last].len(),
+                    };
+                    drop(text);
+                    self.set_register(register, yanked, false);
+                    let start = Position {
+                        line_index: first,
+                        byte_index: 0,
+                    };
+                    self.replace(session, start, end, &indent);
+                    self.mode = VimMode::Insert;
+                    session.set_selection(
+                        Position {
+                            line_index: first,
+                            byte_index: indent.len(),
+                        },
+                        Affinity::Before,
+                        SelectionMode::Simple,
+                        NewGroup::No,
+                    );
+                }
+            },
+            Operator::Indent | Operator::Outdent => {
+                let (first, last) = match span {
+                    Span::Chars(start, end) => (start.line_index, end.line_index),
+                    Span::Lines(first, last) => (first, last),
+                };
+                let tab_column_count = session.settings().tab_column_count;
+                let mut edits = Vec::new();
+                for line_index in first..=last {
+                    let line = &lines[line_index];
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let start = Position {
+                        line_index,
+                        byte_index: 0,
+                    };
+                    if operator == Operator::Indent {
+                        edits.push((start, start, " ".repeat(tab_column_count)));
+                    } else {
+                        let byte_count = line
+                            .bytes()
+                            .take(tab_column_count)
+                            .take_while(|&byte| byte == b' ')
+                            .count();
+                        let byte_count = if byte_count == 0 && line.starts_with('\t') {
+                            1
+                        } else {
+                            byte_count
+                        };
+                        if byte_count > 0 {
+                            let end = Position {
+                                line_index,
+                                byte_index: byte_count,
+                            };
+                            edits.push((start, end, String::new()));
+                        }
+                    }
+                }
+                drop(text);
+                for (start, end, text) in edits {
+                    self.replace(session, start, end, &text);
+                }
+                self.move_to_first_non_blank(session, first);
+            }
+        }
+    }
+
+    fn start_insert(&mut self, session: &Session, insert_at: InsertAt) {
+        let cursor = self.cursor(session);
+        let text = session.document().as_text();
+        let line = &text.as_lines()[cursor.line_index];
+        let at_line = |byte_index| Position {
+            line_index: cursor.line_index,
+            byte_index,
+        };
+        let indent = line[..first_non_blank(line)].to_string();
+        let (position, insertion) = match insert_at {
+            InsertAt::Cursor => (cursor, None),
+            InsertAt::AfterCursor => (at_line(inclusive_end(line, cursor).byte_index), None),
+            InsertAt::FirstNonBlank => (at_line(first_non_blank(line)), None),
+            InsertAt::LineEnd => (at_line(line.len()), None),
+            InsertAt::LineBelow => (
+                Position {
+                    line_index: cursor.line_index + 1,
+                    byte_index: indent.len(),
+                },
+                Some((at_line(line.len()), format!("\n{}", indent))),
+            ),
+            InsertAt::LineAbove => (
+                at_line(indent.len()),
+                Some((at_line(0), format!("{}\n", indent))),
+            ),
+        };
+        drop(text);
+        if let Some((at, text)) = insertion {
+            self.replace(session, at, at, &text);
+        }
+        self.mode = VimMode::Insert;
+        session.set_selection(
+            position,
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::No,
+        );
+    }
+
+    fn put(&mut self, session: &Session, register: Option<char>, is_before: bool) {
+        let Some(register) = self.registers.get(&register.unwrap_or('"')).cloned() else {
+            return;
+        };
+        let cursor = self.cursor(session);
+        let text = session.document().as_text();
+        let line = &text.as_lines()[cursor.line_index];
+        if register.is_linewise {
+            let (at, insertion, line_index) = if is_before {
+                (
+                    Position {
+                        line_index: cursor.line_index,
+                        byte_index: 0,
+                    },
+                    format!("{}\n", register.text),
+                    cursor.line_index,
+                )
+            } else {
+                (
+                    Position {
+                        line_index: cursor.line_index,
+                        byte_index: line.len(),
+                    },
+                    format!("\n{}", register.text),
+                    cursor.line_index + 1,
+                )
+            };
+            drop(text);
+            self.replace(session, at, at, &insertion);
+            self.move_to_first_non_blank(session, line_index);
+        } else {
+            let at = if is_before {
+                cursor
+            } else {
+                inclusive_end(line, cursor)
+            };
+            drop(text);
+            self.replace(session, at, at, &register.text);
+            // Put the cursor on the last char that was put.
+            let text = session.document().as_text();
+            let end = at + Text::from(register.text.as_str()).length();
+            let position = prev_position(text.as_lines(), end).unwrap_or(end).max(at);
+            drop(text);
+            self.set_cursor(session, position);
+        }
+    }
+
+    fn set_register(&mut self, name: Option<char>, register: Register, is_yank: bool) {
+        let register = match name {
+            Some('_') => return,
+            Some(name) if name.is_ascii_uppercase() => {
+                let name = name.to_ascii_lowercase();
+                let register = match self.registers.get(&name) {
+                    Some(existing) => Register {
+                        text: if existing.is_linewise || register.is_linewise {
+                            format!("{}\n{}", existing.text, register.text)
+                        } else {
+                            format!("{}{}", existing.text, register.text)
+                        },
+                        is_linewise: existing.is_linewise || register.is_linewise,
+                    },
+                    None => register,
+                };
+                self.registers.insert(name, register.clone());
+                register
+            }
+            Some(name) => {
+                self.registers.insert(name, register.clone());
+                register
+            }
+            None => {
+                if is_yank {
+                    self.registers.insert('0', register.clone());
+                }
+                register
+            }
+        };
+        self.registers.insert('"', register);
+    }
+
+    // Returns where a motion from `cursor` ends, or `None` if the motion fails.
+    fn motion_target(
+        &self,
+        lines: &Rope,
+        cursor: Position,
+        motion: Motion,
+        count: Option<usize>,
+    ) -> Option<(Position, MotionKind)> {
+        let n = count.unwrap_or(1);
+        let line = &lines[cursor.line_index];
+        let at_line = |byte_index| Position {
+            line_index: cursor.line_index,
+            byte_index,
+        };
+        Some(match motion {
+            Motion::Left => {
+                let mut byte_index = cursor.byte_index;
+                for _ in 0..n {
+                    let Some(char) = line[..byte_index].chars().next_back() else {
+                        break;
+                    };
+                    byte_index -= char.len_utf8();
+                }
+                (at_line(byte_index), MotionKind::Exclusive)
+            }
+            Motion::Right => {
+                let mut byte_index = cursor.byte_index;
+                for _ in 0..n {
+                    let Some(char) = line[byte_index..].chars().next() else {
+                        break;
+                    };
+                    byte_index += char.len_utf8();
+                }
+                (at_line(byte_index), MotionKind::Exclusive)
+            }
+            Motion::Up | Motion::Down => {
+                // Like Vim, a count that goes past the first or last line stops there, and the
+                // motion only fails if the cursor is there already.
+                let line_index = if motion == Motion::Up {
+                    cursor.line_index.saturating_sub(n)
+                } else {
+                    cursor.line_index.saturating_add(n).min(lines.len() - 1)
+                };
+                if line_index == cursor.line_index {
+                    return None;
+                }
+                let column = self
+                    .preferred_column
+                    .unwrap_or_else(|| line[..cursor.byte_index].chars().count());
+                let line = &lines[line_index];
+                (
+                    Position {
+                        line_index,
+                        byte_index: byte_index_of_column(line, column),
+                    },
+                    MotionKind::Linewise,
+                )
+            }
+            Motion::WordStart { is_big } => {
+                let mut position = cursor;
+                for _ in 0..n {
+                    let next = word_start(lines, position, is_big);
+                    if next == position {
+                        break;
+                    }
+                    position = next;
+                }
+                (position, MotionKind::Exclusive)
+            }
+            Motion::WordEnd { is_big } => {
+                let mut position = cursor;
+                for _ in 0..n {
+                    let next = word_end(lines, position, is_big);
+                    if next == position {
+                        break;
+                    }
+                    position = next;
+                }
+                (position, MotionKind::Inclusive)
+            }
+            Motion::WordBack { is_big } => {
+                let mut position = cursor;
+                for _ in 0..n {
+                    let next = word_back(lines, position, is_big);
+                    if next == position {
+                        break;
+                    }
+                    position = next;
+                }
+                (position, MotionKind::Exclusive)
+            }
+            Motion::LineStart => (at_line(0), MotionKind::Exclusive),
+            Motion::FirstNonBlank => (at_line(first_non_blank(line)), MotionKind::Exclusive),
+            Motion::LineEnd => {
+                let line_index = cursor.line_index.saturating_add(n - 1).min(lines.len() - 1);
+                let line = &lines[line_index];
+                (
+                    Position {
+                        line_index,
+                        byte_index: last_char(line),
+                    },
+                    MotionKind::Inclusive,
+                )
+            }
+            Motion::FileStart | Motion::FileEnd => {
+                let line_index = match count {
+                    Some(count) => count.max(1).min(lines.len()) - 1,
+                    None if motion == Motion::FileStart => 0,
+                    None => lines.len() - 1,
+                };
+                (
+                    Position {
+                        line_index,
+                        byte_index: first_non_blank(&lines[line_index]),
+                    },
+                    MotionKind::Linewise,
+                )
+            }
+            Motion::FindChar {
+                char,
+                is_forward,
+                is_till,
+            } => {
+                // The char under the cursor is never a match.
+                let byte_index = if is_forward {
+                    let start =
+                        cursor.byte_index + line[cursor.byte_index..].chars().next()?.len_utf8();
+                    let (index, _) = line[start..]
+                        .char_indices()
+                        .filter(|&(_, c)| c == char)
+                        .nth(n - 1)?;
+                    start + index
+                } else {
+                    let (index, _) = line[..cursor.byte_index]
+                        .char_indices()
+                        .rev()
+                        .filter(|&(_, c)| c == char)
+                        .nth(n - 1)?;
+                    index
+                };
+                if is_forward {
+                    let byte_index = if is_till {
+                        prev_char_in_line(line, at_line(byte_index)).byte_index
+                    } else {
+                        byte_index
+                    };
+                    (at_line(byte_index), MotionKind::Inclusive)
+                } else {
+                    let byte_index = if is_till {
+                        byte_index + char.len_utf8()
+                    } else {
+                        byte_index
+                    };
+                    (at_line(byte_index), MotionKind::Exclusive)
+                }
+            }
+        })
+    }
+
+    fn target_span(
+        &self,
+        session: &Session,
+        operator: Operator,
+        target: Target,
+        count: Option<usize>,
+    ) -> Option<Span> {
+        let cursor = self.cursor(session);
+        let text = session.document().as_text();
+        let lines = text.as_lines();
+        let n = count.unwrap_or(1);
+        Some(match target {
+            Target::Lines => {
+                let last = cursor.line_index + n - 1;
+                if last >= lines.len() {
+                    return None;
+                }
+                Span::Lines(cursor.line_index, last)
+            }
+            Target::Word { is_big, is_around } => {
+                let line = &lines[cursor.line_index];
+                let (mut start, mut end) = word_object(line, cursor.byte_index, is_big);
+                if is_around {
+                    let trailing = line[end..].len() - line[end..].trim_start().len();
+                    if trailing > 0 {
+                        end += trailing;
+                    } else {
+                        start -= line[..start].len() - line[..start].trim_end().len();
+                    }
+                }
+                Span::Chars(
+                    Position {
+                        line_index: cursor.line_index,
+                        byte_index: start,
+                    },
+                    Position {
+                        line_index: cursor.line_index,
+                        byte_index: end,
+                    },
+                )
+            }
+            Target::Motion(motion) => {
+                // Like Vim, `cw` on a word changes to the end of the word, which can be the char
+                // under the cursor.
+                let (target, kind) = match motion {
+                    Motion::WordStart { is_big }
+                        if operator == Operator::Change
+                            && char_at(lines, cursor).is_some_and(|char| !char.is_whitespace()) =>
+                    {
+                        let class = char_class(char_at(lines, cursor).unwrap(), is_big);
+                        let mut position = cursor;
+                        while let Some(next) = next_position(lines, position) {
+                            match char_at(lines, next) {
+                                Some(char) if char_class(char, is_big) == class => position = next,
+                                _ => break,
+                            }
+                        }
+                        for _ in 1..n {
+                            position = word_end(lines, position, is_big);
+                        }
+                        (position, MotionKind::Inclusive)
+                    }
+                    motion => self.motion_target(lines, cursor, motion, count)?,
+                };
+                let start = cursor.min(target);
+                let end = cursor.max(target);
+                match kind {
+                    MotionKind::Linewise => Span::Lines(start.line_index, end.line_index),
+                    MotionKind::Inclusive => {
+                        Span::Chars(start, inclusive_end(&lines[end.line_index], end))
+                    }
+                    MotionKind::Exclusive => {
+                        // An exclusive motion that ends at the start of a later line ends at the
+                        // end of the line before it instead. For `w` this includes ending after
+                        // the indentation of a later line, so that `dw` stops at the line end.
+                        let is_at_line_start = match motion {
+                            Motion::WordStart { .. } => {
+                                lines[end.line_index][..end.byte_index].trim().is_empty()
+                            }
+                            _ => end.byte_index == 0,
+                        };
+                        if is_at_line_start && end.line_index > start.line_index {
+                            let line_index = end.line_index - 1;
+                            Span::Chars(
+                                start,
+                                Position {
+                                    line_index,
+                                    byte_index: lines[line_index].len(),
+                                },
+                            )
+                        } else {
+                            Span::Chars(start, end)
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn update_preferred_column(&mut self, session: &Session, motion: Motion) {
+        match motion {
+            Motion::Up | Motion::Down => {
+                if self.preferred_column.is_none() {
+                    let cursor = self.cursor(session);
+                    let text = session.document().as_text();
+                    self.preferred_column = Some(
+                        text.as_lines()[cursor.line_index][..cursor.byte_index]
+                            .chars()
+                            .count(),
+                    );
+                }
+            }
+            Motion::LineEnd => self.preferred_column = Some(usize::MAX),
+            _ => self.preferred_column = None,
+        }
+    }
+
+    fn exit_visual(&mut self, session: &Session) {
+        let cursor = self.cursor(session);
+        self.mode = VimMode::Normal;
+        self.set_cursor(session, cursor);
+    }
+
+    // Selects from the visual anchor up to and including the char at `position`, which becomes
+    // the cursor.
+    fn select_visual(&mut self, session: &Session, position: Position) {
+        let text = session.document().as_text();
+        let lines = text.as_lines();
+        let position = clamp_to_char(lines, position);
+        let (anchor, cursor) = if self.mode == VimMode::VisualLine {
+            if position < self.visual_anchor {
+                (
+                    Position {
+                        line_index: self.visual_anchor.line_index,
+                        byte_index: lines[self.visual_anchor.line_index].len(),
+                    },
+                    Position {
+                        line_index: position.line_index,
+                        byte_index: 0,
+                    },
+                )
+            } else {
+                (
+                    Position {
+                        line_index: self.visual_anchor.line_index,
+                        byte_index: 0,
+                    },
+                    Position {
+                        line_index: position.line_index,
+                        byte_index: lines[position.line_index].len(),
+                    },
+                )
+            }
+        } else if position < self.visual_anchor {
+            (
+                inclusive_end(&lines[self.visual_anchor.line_index], self.visual_anchor),
+                position,
+            )
+        } else {
+            (
+                self.visual_anchor,
+                inclusive_end(&lines[position.line_index], position),
+            )
+        };
+        drop(text);
+        session.set_selection(
+            anchor,
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::No,
+        );
+        session.move_to(cursor, Affinity::Before, NewGroup::No);
+        self.visual_cursor = position;
+    }
+
+    fn move_to_first_non_blank(&mut self, session: &Session, line_index: usize) {
+        let text = session.document().as_text();
+        let lines = text.as_lines();
+        let line_index = line_index.min(lines.len() - 1);
+        let position = Position {
+            line_index,
+            byte_index: first_non_blank(&lines[line_index]),
+        };
+        drop(text);
+        self.set_cursor(session, position);
+    }
+
+    fn set_cursor(&mut self, session: &Session, position: Position) {
+        let position = if self.mode == VimMode::Insert {
+            position
+        } else {
+            let text = session.document().as_text();
+            clamp_to_char(text.as_lines(), position)
+        };
+        session.set_selection(
+            position,
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::No,
+        );
+    }
+
+    fn cursor(&self, session: &Session) -> Position {
+        match self.mode {
+            VimMode::Visual | VimMode::VisualLine => self.visual_cursor,
+            VimMode::Normal | VimMode::Insert => session_cursor(session),
+        }
+    }
+
+    fn insert(&mut self, session: &Session, position: Position, text: &str) {
+        self.replace(session, position, position, text);
+        let end = position + Text::from(text).length();
+        session.set_selection(end, Affinity::Before, SelectionMode::Simple, NewGroup::No);
+    }
+
+    fn replace(&mut self, session: &Session, start: Position, end: Position, text: &str) {
+        session.replace_range(EditKind::Group(self.undo_group), start, end, text);
+        self.text_changed = true;
+    }
+}
+
+fn parse_count(keys: &[VimKey], mut index: usize) -> (Option<usize>, usize) {
+    let mut count = None;
+    while let Some(VimKey::Char(char)) = keys.get(index) {
+        let Some(digit) = char.to_digit(10) else {
+            break;
+        };
+        if digit == 0 && count.is_none() {
+            break;
+        }
+        count = Some(
+            count
+                .unwrap_or(0usize)
+                .saturating_mul(10)
+                .saturating_add(digit as usize),
+        );
+        index += 1;
+    }
+    (count, index)
+}
+
+fn parse_register(keys: &[VimKey]) -> Parse<(Option<char>, usize)> {
+    match keys {
+        [VimKey::Char('"')] => Parse::Incomplete,
+        [VimKey::Char('"'), VimKey::Char(name), ..] => Parse::Complete((Some(*name), 2)),
+        [VimKey::Char('"'), ..] => Parse::Invalid,
+        _ => Parse::Complete((None, 0)),
+    }
+}
+
+fn parse_motion(keys: &[VimKey]) -> Parse<(Motion, usize)> {
+    let Some(&key) = keys.first() else {
+        return Parse::Incomplete;
+    };
+    let motion = match key {
+        VimKey::Char('h') | VimKey::Left | VimKey::Backspace => Motion::Left,
+        VimKey::Char('l') | VimKey::Right | VimKey::Char(' ') => Motion::Right,
+        VimKey::Char('j') | VimKey::Down | VimKey::Enter => Motion::Down,
+        VimKey::Char('k') | VimKey::Up => Motion::Up,
+        VimKey::Char('w') => Motion::WordStart { is_big: false },
+        VimKey::Char('W') => Motion::WordStart { is_big: true },
+        VimKey::Char('e') => Motion::WordEnd { is_big: false },
+        VimKey::Char('E') => Motion::WordEnd { is_big: true },
+        VimKey::Char('b') => Motion::WordBack { is_big: false },
+        VimKey::Char('B') => Motion::WordBack { is_big: true },
+        VimKey::Char('0') => Motion::LineStart,
+        VimKey::Char('^') => Motion::FirstNonBlank,
+        VimKey::Char('$') => Motion::LineEnd,
+        VimKey::Char('G') => Motion::FileEnd,
+        VimKey::Char('g') => {
+            return match keys.get(1) {
+                None => Parse::Incomplete,
+                Some(VimKey::Char('g')) => Parse::Complete((Motion::FileStart, 2)),
+                Some(_) => Parse::Invalid,
+            }
+        }
+        VimKey::Char(find @ ('f' | 't' | 'F' | 'T')) => {
+            return match keys.get(1) {
+                None => Parse::Incomplete,
+                Some(VimKey::Char(char)) => Parse::Complete((
+                    Motion::FindChar {
+                        char: *char,
+                        is_forward: find == 'f' || find == 't',
+                        is_till: find == 't' || find == 'T',
+                    },
+                    2,
+                )),
+                Some(_) => Parse::Invalid,
+            }
+        }
+        _ => return Parse::Invalid,
+    };
+    Parse::Complete((motion, 1))
+}
+
+fn parse_operator(key: VimKey) -> Option<Operator> {
+    match key {
+        VimKey::Char('d') => Some(Operator::Delete),
+        VimKey::Char('c') => Some(Operator::Change),
+        VimKey::Char('y') => Some(Operator::Yank),
+        VimKey::Char('>') => Some(Operator::Indent),
+        VimKey::Char('<') => Some(Operator::Outdent),
+        _ => None,
+    }
+}
+
+// Parses `["x][count]command`, where the command is a motion, an operator followed by a target,
+// or one of the other normal mode commands.
+fn parse_normal_command(keys: &[VimKey]) -> Parse<Command> {
+    let (register, index) = match parse_register(keys) {
+        Parse::Complete(register) => register,
+        Parse::Incomplete => return Parse::Incomplete,
+        Parse::Invalid => return Parse::Invalid,
+    };
+    let (count, index) = parse_count(keys, index);
+    let Some(&key) = keys.get(index) else {
+        return Parse::Incomplete;
+    };
+    let rest = &keys[index + 1..];
+    let complete = |action| {
+        Parse::Complete(Command {
+            register,
+            count,
+            action,
+        })
+    };
+    let operate = |operator, motion| complete(Action::Operate(operator, Target::Motion(motion)));
+    if let Some(operator) = parse_operator(key) {
+        let (motion_count, index) = parse_count(rest, 0);
+        let count = match (count, motion_count) {
+            (None, None) => None,
+            (count, motion_count) => {
+                Some(count.unwrap_or(1).saturating_mul(motion_count.unwrap_or(1)))
+            }
+        };
+        let complete = |target| {
+            Parse::Complete(Command {
+                register,
+                count,
+                action: Action::Operate(operator, target),
+            })
+        };
+        return match &rest[index..] {
+            [] => Parse::Incomplete,
+            [target] if *target == key => complete(Target::Lines),
+            [VimKey::Char('i' | 'a')] => Parse::Incomplete,
+            [VimKey::Char(object @ ('i' | 'a')), VimKey::Char(word @ ('w' | 'W'))] => {
+                complete(Target::Word {
+                    is_big: *word == 'W',
+                    is_around: *object == 'a',
+                })
+            }
+            [VimKey::Char('i' | 'a'), ..] => Parse::Invalid,
+            motion_keys => match parse_motion(motion_keys) {
+                Parse::Complete((motion, _)) => complete(Target::Motion(motion)),
+                Parse::Incomplete => Parse::Incomplete,
+                Parse::Invalid => Parse::Invalid,
+            },
+        };
+    }
+    match key {
+        VimKey::Char('x') => operate(Operator::Delete, Motion::Right),
+        VimKey::Char('X') => operate(Operator::Delete, Motion::Left),
+        VimKey::Char('D') => operate(Operator::Delete, Motion::LineEnd),
+        VimKey::Char('C') => operate(Operator::Change, Motion::LineEnd),
+        VimKey::Char('s') => operate(Operator::Change, Motion::Right),
+        VimKey::Char('Y') => complete(Action::Operate(Operator::Yank, Target::Lines)),
+        VimKey::Char('S') => complete(Action::Operate(Operator::Change, Target::Lines)),
+        VimKey::Char('i') => complete(Action::Insert(InsertAt::Cursor)),
+        VimKey::Char('a') => complete(Action::Insert(InsertAt::AfterCursor)),
+        VimKey::Char('I') => complete(Action::Insert(InsertAt::FirstNonBlank)),
+        VimKey::Char('A') => complete(Action::Insert(InsertAt::LineEnd)),
+        VimKey::Char('o') => complete(Action::Insert(InsertAt::LineBelow)),
+        VimKey::Char('O') => complete(Action::Insert(InsertAt::LineAbove)),
+        VimKey::Char('p') => complete(Action::Put { is_before: false }),
+        VimKey::Char('P') => complete(Action::Put { is_before: true }),
+        VimKey::Char('u') => complete(Action::Undo),
+        VimKey::Ctrl('r') => complete(Action::Redo),
+        VimKey::Char('.') => complete(Action::Repeat),
+        VimKey::Char('v') => complete(Action::EnterVisual(VimMode::Visual)),
+        VimKey::Char('V') => complete(Action::EnterVisual(VimMode::VisualLine)),
+        VimKey::Char('r') => match rest.first() {
+            None => Parse::Incomplete,
+            Some(VimKey::Char(char)) => complete(Action::ReplaceChar(*char)),
+            Some(_) => Parse::Invalid,
+        },
+        _ => match parse_motion(&keys[index..]) {
+            Parse::Complete((motion, _)) => complete(Action::Move(motion)),
+            Parse::Incomplete => Parse::Incomplete,
+            Parse::Invalid => Parse::Invalid,
+        },
+    }
+}
+
+fn parse_visual_command(keys: &[VimKey]) -> Parse<Command> {
+    let (register, index) = match parse_register(keys) {
+        Parse::Complete(register) => register,
+        Parse::Incomplete => return Parse::Incomplete,
+        Parse::Invalid => return Parse::Invalid,
+    };
+    let (count, index) = parse_count(keys, index);
+    let Some(&key) = keys.get(index) else {
+        return Parse::Incomplete;
+    };
+    let action = match key {
+        VimKey::Char('x') => Action::OperateOnSelection(Operator::Delete),
+        VimKey::Char('s') => Action::OperateOnSelection(Operator::Change),
+        VimKey::Char('o') => Action::SwapAnchor,
+        VimKey::Char('v') => Action::EnterVisual(VimMode::Visual),
+        VimKey::Char('V') => Action::EnterVisual(VimMode::VisualLine),
+        key => match parse_operator(key) {
+            Some(operator) => Action::OperateOnSelection(operator),
+            None => match parse_motion(&keys[index..]) {
+                Parse::Complete((motion, _)) => Action::Move(motion),
+                Parse::Incomplete => return Parse::Incomplete,
+                Parse::Invalid => return Parse::Invalid,
+            },
+        },
+    };
+    Parse::Complete(Command {
+        register,
+        count,
+        action,
+    })
+}
+
+fn session_cursor(session: &Session) -> Position {
+    let selections = session.selections();
+    selections[session.last_added_selection_index().unwrap_or(0)]
+        .cursor
+        .position
+}
+
+// Returns the range of text that a span covers. Deleting whole lines also deletes a line break,
+// which is the one after the lines, or the one before them if they are at the end of the text.
+fn span_range(lines: &Rope, span: Span) -> (Position, Position) {
+    match span {
+        Span::Chars(start, end) => (start, end),
+        Span::Lines(first, last) => {
+            if last + 1 < lines.len() {
+                (
+                    Position {
+                        line_index: first,
+                        byte_index: 0,
+                    },
+                    Position {
+                        line_index: last + 1,
+                        byte_index: 0,
+                    },
+                )
+            } else {
+                let end = Position {
+                    line_index: last,
+                    byte_index: lines[last].len(),
+                };
+                if first > 0 {
+                    (
+                        Position {
+                            line_index: first - 1,
+                            byte_index: lines[first - 1].len(),
+                        },
+                        end,
+                    )
+                } else {
+                    (Position::zero(), end)
+                }
+            }
+        }
+    }
+}
+
+// Returns the char at the given position, where the end of every line but the last is a line
+// break.
+fn char_at(lines: &Rope, position: Position) -> Option<char> {
+    let line = &lines[position.line_index];
+    if position.byte_index < line.len() {
+        line[position.byte_index..].chars().next()
+    } else if position.line_index + 1 < lines.len() {
+        Some('\n')
+    } else {
+        None
+    }
+}
+
+fn next_position(lines: &Rope, position: Position) -> Option<Position> {
+    let line = &lines[position.line_index];
+    if let Some(char) = line[position.byte_index..].chars().next() {
+        Some(Position {
+            line_index: position.line_index,
+            byte_index: position.byte_index + char.len_utf8(),
+        })
+    } else if position.line_index + 1 < lines.len() {
+        Some(Position {
+            line_index: position.line_index + 1,
+            byte_index: 0,
+        })
+    } else {
+        None
+    }
+}
+
+fn prev_position(lines: &Rope, position: Position) -> Option<Position> {
+    let line = &lines[position.line_index];
+    if let Some(char) = line[..position.byte_index].chars().next_back() {
+        Some(Position {
+            line_index: position.line_index,
+            byte_index: position.byte_index - char.len_utf8(),
+        })
+    } else if position.line_index > 0 {
+        Some(Position {
+            line_index: position.line_index - 1,
+            byte_index: lines[position.line_index - 1].len(),
+        })
+    } else {
+        None
+    }
+}
+
+fn prev_char_in_line(line: &str, position: Position) -> Position {
+    Position {
+        line_index: position.line_index,
+        byte_index: position.byte_index
+            - line[..position.byte_index]
+                .chars()
+                .next_back()
+                .map_or(0, |char| char.len_utf8()),
+    }
+}
+
+// Returns the position after the char at `position`, if there is one on the same line.
+fn inclusive_end(line: &str, position: Position) -> Position {
+    Position {
+        line_index: position.line_index,
+        byte_index: position.byte_index
+            + line[position.byte_index..]
+                .chars()
+                .next()
+                .map_or(0, |char| char.len_utf8()),
+    }
+}
+
+// In normal mode the cursor is on a char, so it cannot be after the last char of a line.
+fn clamp_to_char(lines: &Rope, position: Position) -> Position {
+    let line_index = position.line_index.min(lines.len() - 1);
+    let line = &lines[line_index];
+    Position {
+        line_index,
+        byte_index: position.byte_index.min(last_char(line)),
+    }
+}
+
+fn last_char(line: &str) -> usize {
+    line.char_indices()
+        .next_back()
+        .map_or(0, |(index, _)| index)
+}
+
+fn first_non_blank(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn byte_index_of_column(line: &str, column: usize) -> usize {
+    line.char_indices()
+        .nth(column)
+        .map_or(last_char(line), |(index, _)| index)
+}
+
+fn char_class(char: char, is_big: bool) -> u8 {
+    if char.is_whitespace() {
+        0
+    } else if is_big || char.is_alphanumeric() || char == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn word_start(lines: &Rope, mut position: Position, is_big: bool) -> Position {
+    if let Some(char) = char_at(lines, position) {
+        let class = char_class(char, is_big);
+        if class != 0 {
+            while let Some(char) = char_at(lines, position) {
+                if char_class(char, is_big) != class {
+                    break;
+                }
+                position = next_position(lines, position).unwrap();
+            }
+        }
+    }
+    // Skip whitespace, but stop at empty lines, which count as words.
+    while let Some(char) = char_at(lines, position) {
+        if char_class(char, is_big) != 0 {
+            break;
+        }
+        position = next_position(lines, position).unwrap();
+        if char == '\n' && lines[position.line_index].is_empty() {
+            break;
+        }
+    }
+    position
+}
+
+fn word_end(lines: &Rope, position: Position, is_big: bool) -> Position {
+    let Some(mut position) = next_position(lines, position) else {
+        return position;
+    };
+    while let Some(char) = char_at(lines, position) {
+        if char_class(char, is_big) != 0 {
+            break;
+        }
+        match next_position(lines, position) {
+            Some(next) => position = next,
+            None => break,
+        }
+    }
+    let Some(char) = char_at(lines, position) else {
+        return position;
+    };
+    let class = char_class(char, is_big);
+    while let Some(next) = next_position(lines, position) {
+        match char_at(lines, next) {
+            Some(char) if char_class(char, is_big) == class => position = next,
+            _ => break,
+        }
+    }
+    position
+}
+
+fn word_back(lines: &Rope, mut position: Position, is_big: bool) -> Position {
+    let class = loop {
+        let Some(prev) = prev_position(lines, position) else {
+            return position;
+        };
+        position = prev;
+        let char = char_at(lines, position).unwrap();
+        if char == '\n' && position.byte_index == 0 {
+            // An empty line.
+            return position;
+        }
+        let class = char_class(char, is_big);
+        if class != 0 {
+            break class;
+        }
+    };
+    while let Some(prev) = prev_position(lines, position) {
+        if prev.line_index != position.line_index
+            || char_class(char_at(lines, prev).unwrap(), is_big) != class
+        {
+            break;
+        }
+        position = prev;
+    }
+    position
+}
+
+// Returns the byte range of the word, or the run of whitespace, at `byte_index`.
+fn word_object(line: &str, byte_index: usize, is_big: bool) -> (usize, usize) {
+    let Some(char) = line[byte_index..].chars().next() else {
+        return (byte_index, byte_index);
+    };
+    let class = char_class(char, is_big);
+    let start = line[..byte_index]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, char)| char_class(char, is_big) == class)
+        .last()
+        .map_or(byte_index, |(index, _)| index);
+    let end = line[byte_index..]
+        .char_indices()
+        .find(|&(_, char)| char_class(char, is_big) != class)
+        .map_or(line.len(), |(index, _)| byte_index + index);
+    (start, end)
+}
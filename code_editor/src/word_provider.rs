@@ -0,0 +1,304 @@
+use {
+    crate::{
+        provider::{
+            is_word_char, word_range, CompletionItem, CompletionKind, CompletionProvider, Hover,
+            HoverProvider, SignatureHelp, SignatureHelpProvider,
+        },
+        rope::Rope,
+        text::Position,
+        Document,
+    },
+    std::{collections::HashMap, fmt::Write, ops::Range},
+};
+
+/// A provider that only looks at the words in the document, so that completion, signature help
+/// and hover work without a language server. Definitions are recognized by the keyword in front of
+/// them, such as `fn`, `struct` or `let`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct WordProvider {
+    /// The number of lines above and below the position that are scanned for words.
+    pub line_window: usize,
+}
+
+impl WordProvider {
+    fn line_range(&self, lines: &Rope, position: Position) -> Range<usize> {
+        position.line_index.saturating_sub(self.line_window)
+            ..(position.line_index + self.line_window + 1).min(lines.len())
+    }
+
+    fn find_definition(
+        &self,
+        lines: &Rope,
+        position: Position,
+        name: &str,
+    ) -> Option<(usize, Definition)> {
+        let line_range = self.line_range(lines, position);
+        let start = line_range.start;
+        lines
+            .range(line_range)
+            .enumerate()
+            .find_map(|(index, line)| {
+                find_definitions(line)
+                    .find(|definition| &line[definition.name.clone()] == name)
+                    .map(|definition| (start + index, definition))
+            })
+    }
+}
+
+impl Default for WordProvider {
+    fn default() -> Self {
+        Self { line_window: 5000 }
+    }
+}
+
+impl CompletionProvider for WordProvider {
+    fn completions(&self, document: &Document, position: Position) -> Vec<CompletionItem> {
+        let text = document.as_text();
+        let lines = text.as_lines();
+        let line_range = self.line_range(lines, position);
+        let start = line_range.start;
+        let mut items = HashMap::new();
+        for (index, line) in lines.range(line_range).enumerate() {
+            let current_word = if start + index == position.line_index {
+                word_range(line, position.byte_index)
+            } else {
+                0..0
+            };
+            for word in words(line) {
+                if word.len() > 1 && word != current_word {
+                    items
+                        .entry(&line[word.clone()])
+                        .or_insert_with(|| CompletionItem::new(&line[word], CompletionKind::Word));
+                }
+            }
+            for definition in find_definitions(line) {
+                let name = &line[definition.name.clone()];
+                if definition.name == current_word {
+                    continue;
+                }
+                let mut item = CompletionItem::new(name, definition.kind);
+                item.detail = Some(definition.label(line).to_string());
+                if let Some(parameters) = definition.parameters(line) {
+                    item.insert_text = format!("{}(", item.insert_text);
+                    for (index, parameter) in parameters.iter().enumerate() {
+                        if index > 0 {
+                            item.insert_text.push_str(", ");
+                        }
+                        write!(
+                            item.insert_text,
+                            "${{{}:{}}}",
+                            index + 1,
+                            parameter_name(&line[parameter.clone()])
+                                .replace('\\', "\\\\")
+                                .replace('}', "\\}")
+                                .replace('$', "\\$")
+                        )
+                        .unwrap();
+                    }
+                    item.insert_text
+                        .push_str(if parameters.is_empty() { ")" } else { ")$0" });
+                }
+                items.insert(name, item);
+            }
+        }
+        let mut items: Vec<_> = items.into_values().collect();
+        items.sort_by(|item_0, item_1| item_0.label.cmp(&item_1.label));
+        items
+    }
+}
+
+impl SignatureHelpProvider for WordProvider {
+    fn signature_help(&self, document: &Document, position: Position) -> Option<SignatureHelp> {
+        const MAX_LINE_COUNT: usize = 32;
+
+        let text = document.as_text();
+        let lines = text.as_lines();
+        let mut depth = 0;
+        let mut active_parameter = 0;
+        let mut call = None;
+        'outer: for line_index in
+            (position.line_index.saturating_sub(MAX_LINE_COUNT)..=position.line_index).rev()
+        {
+            let line = &lines[line_index];
+            let end = if line_index == position.line_index {
+                position.byte_index
+            } else {
+                line.len()
+            };
+            for (byte_index, char) in line[..end].char_indices().rev() {
+                match char {
+                    ')' | ']' => depth += 1,
+                    '(' | '[' if depth > 0 => depth -= 1,
+                    '(' => {
+                        call = Some((line_index, byte_index));
+                        break 'outer;
+                    }
+                    ',' if depth == 0 => active_parameter += 1,
+                    '[' | '{' | '}' | ';' => break 'outer,
+                    _ => {}
+                }
+            }
+        }
+        let (line_index, byte_index) = call?;
+        let line = &lines[line_index];
+        let name_end = line[..byte_index].trim_end().len();
+        let name = &line[word_range(line, name_end)];
+        if name.is_empty() {
+            return None;
+        }
+        let (definition_line_index, definition) = self.find_definition(lines, position, name)?;
+        let definition_line = &lines[definition_line_index];
+        let parameters = definition.parameters(definition_line)?;
+        let label = definition.label(definition_line);
+        let offset = label.as_ptr() as usize - definition_line.as_ptr() as usize;
+        Some(SignatureHelp {
+            label: label.to_string(),
+            active_parameter: (active_parameter < parameters.len()).then_some(active_parameter),
+            parameters: parameters
+                .into_iter()
+                .map(|parameter| parameter.start - offset..parameter.end - offset)
+                .collect(),
+        })
+    }
+}
+
+impl HoverProvider for WordProvider {
+    fn hover(&self, document: &Document, position: Position) -> Option<Hover> {
+        let text = document.as_text();
+        let lines = text.as_lines();
+        let line = lines.get(position.line_index)?;
+        let name = &line[word_range(line, position.byte_index)];
+        if name.is_empty() {
+            return None;
+        }
+        let (line_index, definition) = self.find_definition(lines, position, name)?;
+        let mut markdown = format!("```\n{}\n```\n", definition.label(&lines[line_index]));
+        let mut doc_start = line_index;
+        while doc_start > 0 && lines[doc_start - 1].trim_start().starts_with("///") {
+            doc_start -= 1;
+        }
+        if doc_start < line_index {
+            markdown.push('\n');
+            for line in lines.range(doc_start..line_index) {
+                let doc_line = line.trim_start().strip_prefix("///").unwrap();
+                markdown.push_str(doc_line.strip_prefix(' ').unwrap_or(doc_line));
+                markdown.push('\n');
+            }
+        }
+        Some(Hover { markdown })
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Definition {
+    kind: CompletionKind,
+    keyword: Range<usize>,
+    name: Range<usize>,
+}
+
+impl Definition {
+    /// Returns the definition up to its body, for instance `fn add(a: u32, b: u32) -> u32`.
+    fn label<'a>(&self, line: &'a str) -> &'a str {
+        let (body_start, terminators) = match self.parameter_list(line) {
+            Some((_, end)) => (end, &['{', ';'][..]),
+            None => (self.name.end, &['{', ';', '='][..]),
+        };
+        let end = line[body_start..]
+            .find(terminators)
+            .map_or(line.len(), |index| body_start + index);
+        line[self.keyword.start..end].trim_end()
+    }
+
+    /// Returns the byte ranges of the parameters of a function, or `None` if this is not a
+    /// function or its parameters do not fit on one line.
+    fn parameters(&self, line: &str) -> Option<Vec<Range<usize>>> {
+        self.parameter_list(line).map(|(parameters, _)| parameters)
+    }
+
+    /// Returns the byte ranges of the parameters of a function, and the byte index after the
+    /// closing parenthesis.
+    fn parameter_list(&self, line: &str) -> Option<(Vec<Range<usize>>, usize)> {
+        if self.kind != CompletionKind::Function {
+            return None;
+        }
+        let list_start = self.name.end + line[self.name.end..].find('(')? + 1;
+        let mut start = list_start;
+        let mut parameters = Vec::new();
+        let mut depth = 0;
+        let mut prev_char = None;
+        for (byte_index, char) in line[list_start..].char_indices() {
+            let byte_index = list_start + byte_index;
+            match char {
+                '(' | '[' | '{' | '<' => depth += 1,
+                ')' if depth == 0 => {
+                    push_parameter(line, start..byte_index, &mut parameters);
+                    return Some((parameters, byte_index + 1));
+                }
+                '>' if prev_char == Some('-') => {}
+                ')' | ']' | '}' | '>' => depth -= 1,
+                ',' if depth == 0 => {
+                    push_parameter(line, start..byte_index, &mut parameters);
+                    start = byte_index + 1;
+                }
+                _ => {}
+            }
+            prev_char = Some(char);
+        }
+        None
+    }
+}
+
+fn push_parameter(line: &str, range: Range<usize>, parameters: &mut Vec<Range<usize>>) {
+    let parameter = line[range.clone()].trim();
+    if parameter.is_empty() || parameter.ends_with("self") && !parameter.contains(':') {
+        return;
+    }
+    let start = range.start + line[range].find(parameter).unwrap();
+    parameters.push(start..start + parameter.len());
+}
+
+fn parameter_name(parameter: &str) -> &str {
+    let name = parameter.split([':', '=']).next().unwrap().trim();
+    name.strip_prefix("mut ").unwrap_or(name)
+}
+
+fn find_definitions(line: &str) -> impl Iterator<Item = Definition> + '_ {
+    let mut words = words(line).peekable();
+    std::iter::from_fn(move || loop {
+        let keyword = words.next()?;
+        let kind = match &line[keyword.clone()] {
+            "fn" | "function" => CompletionKind::Function,
+            "struct" | "enum" | "union" | "trait" | "type" | "class" => CompletionKind::Type,
+            "let" | "const" | "static" | "var" => CompletionKind::Variable,
+            _ => continue,
+        };
+        let mut name = words.next()?;
+        if &line[name.clone()] == "mut" {
+            name = words.next()?;
+        }
+        if line[keyword.end..name.start].trim().is_empty() {
+            return Some(Definition {
+                kind,
+                keyword,
+                name,
+            });
+        }
+    })
+}
+
+/// Returns the byte ranges of the words in the given line.
+fn words(line: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        let word_start = start + line[start..].find(is_word_start_char)?;
+        let word_end = line[word_start..]
+            .find(|char: char| !is_word_char(char))
+            .map_or(line.len(), |index| word_start + index);
+        start = word_end;
+        Some(word_start..word_end)
+    })
+}
+
+fn is_word_start_char(char: char) -> bool {
+    char.is_alphabetic() || char == '_'
+}
@@ -0,0 +1,371 @@
+use {
+    makepad_code_editor::{
+        collab::{ClientMessage, Collab, CollabServer, ServerMessage, SiteId},
+        decoration::DecorationSet,
+        history::NewGroup,
+        selection::Affinity,
+        session::SelectionMode,
+        text::{Position, Text},
+        Document, Session,
+    },
+    makepad_widgets::makepad_micro_serde::*,
+    std::collections::{HashMap, VecDeque},
+};
+
+// A small deterministic random number generator, so that failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+struct Peer {
+    session: Session,
+    collab: Collab,
+    site_id: SiteId,
+    // The messages from the relay that have not been delivered yet.
+    inbox: VecDeque<Vec<u8>>,
+}
+
+impl Peer {
+    fn new(relay: &mut Relay, text: &str) -> Self {
+        Self::new_with_base(relay, text, text)
+    }
+
+    fn new_with_base(relay: &mut Relay, base: &str, text: &str) -> Self {
+        let document = Document::new(Text::from(text), DecorationSet::new());
+        let session = Session::new(document.clone());
+        let collab = Collab::new_with_base(document, Text::from(base));
+        Self {
+            session,
+            collab,
+            site_id: relay.server.connect(),
+            inbox: VecDeque::new(),
+        }
+    }
+
+    fn text(&self) -> String {
+        self.session.document().as_text().to_string()
+    }
+
+    // Sends the pending messages of the peer to the relay, serialized as they are on the wire.
+    fn send(&mut self, relay: &mut Relay) {
+        self.collab.update(&self.session.selections());
+        for message in self.collab.take_messages() {
+            relay
+                .incoming
+                .push_back((self.site_id, message.serialize_bin()));
+        }
+    }
+
+    // Delivers the next message from the relay, if any.
+    fn receive(&mut self) -> bool {
+        let Some(data) = self.inbox.pop_front() else {
+            return false;
+        };
+        // Like Studio, the session handles its own edits before any remote ones are applied.
+        self.session.handle_changes();
+        self.collab
+            .handle_message(ServerMessage::deserialize_bin(&data).unwrap());
+        self.session.handle_changes();
+        true
+    }
+
+    fn random_position(&self, rng: &mut Rng) -> Position {
+        let text = self.session.document().as_text();
+        let lines = text.as_lines();
+        let line_index = rng.below(lines.len());
+        let line = &lines[line_index];
+        let mut byte_index = rng.below(line.len() + 1);
+        while !line.is_char_boundary(byte_index) {
+            byte_index -= 1;
+        }
+        Position {
+            line_index,
+            byte_index,
+        }
+    }
+
+    fn random_edit(&mut self, rng: &mut Rng) {
+        self.session.handle_changes();
+        let start = self.random_position(rng);
+        self.session.set_selection(
+            start,
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::Yes,
+        );
+        match rng.below(5) {
+            0 | 1 => {
+                const INSERTS: &[&str] = &["a", "bc", "\n", "é", "x\ny", "{\n    z\n}"];
+                self.session
+                    .insert(Text::from(INSERTS[rng.below(INSERTS.len())]));
+            }
+            2 => {
+                let end = self.random_position(rng);
+                self.session.move_to(end, Affinity::Before, NewGroup::No);
+                self.session.delete();
+            }
+            3 => {
+                self.session.undo();
+            }
+            _ => {
+                self.session.redo();
+            }
+        }
+    }
+}
+
+// Stands in for the relay of Studio: it passes the messages of every peer through a server.
+struct Relay {
+    server: CollabServer,
+    incoming: VecDeque<(SiteId, Vec<u8>)>,
+}
+
+impl Relay {
+    fn new() -> Self {
+        Self {
+            server: CollabServer::new(),
+            incoming: VecDeque::new(),
+        }
+    }
+
+    // Handles the next message from a peer, if any.
+    fn step(&mut self, peers: &mut [Peer]) -> bool {
+        let Some((site_id, data)) = self.incoming.pop_front() else {
+            return false;
+        };
+        let message = ClientMessage::deserialize_bin(&data).unwrap();
+        for (site_id, message) in self.server.handle_message(site_id, message) {
+            let peer = peers
+                .iter_mut()
+                .find(|peer| peer.site_id == site_id)
+                .unwrap();
+            peer.inbox.push_back(message.serialize_bin());
+        }
+        true
+    }
+}
+
+// Delivers every message until nothing is in flight anymore.
+fn settle(relay: &mut Relay, peers: &mut [Peer]) {
+    loop {
+        let mut has_progressed = false;
+        for peer in peers.iter_mut() {
+            peer.send(relay);
+        }
+        while relay.step(peers) {
+            has_progressed = true;
+        }
+        for peer in peers.iter_mut() {
+            while peer.receive() {
+                has_progressed = true;
+            }
+        }
+        if !has_progressed {
+            break;
+        }
+    }
+}
+
+fn assert_converged(relay: &Relay, peers: &[Peer]) {
+    let text = relay.server.text().to_string();
+    for peer in peers {
+        assert_eq!(peer.text(), text);
+    }
+}
+
+#[test]
+fn two_peers_converge_under_random_interleavings() {
+    for seed in 1..=300u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let mut relay = Relay::new();
+        let mut peers = vec![
+            Peer::new(&mut relay, "fn main() {\n    let x = 1;\n}"),
+            Peer::new(&mut relay, "fn main() {\n    let x = 1;\n}"),
+        ];
+        settle(&mut relay, &mut peers);
+        assert_converged(&relay, &peers);
+        for _ in 0..40 {
+            // Edit, send, relay and receive in a random order, so that operations cross each
+            // other in flight.
+            match rng.below(4) {
+                0 => {
+                    let index = rng.below(peers.len());
+                    peers[index].random_edit(&mut rng);
+                }
+                1 => {
+                    let index = rng.below(peers.len());
+                    peers[index].send(&mut relay);
+                }
+                2 => {
+                    relay.step(&mut peers);
+                }
+                _ => {
+                    let index = rng.below(peers.len());
+                    peers[index].receive();
+                }
+            }
+        }
+        settle(&mut relay, &mut peers);
+        assert_converged(&relay, &peers);
+    }
+}
+
+#[test]
+fn concurrent_inserts_at_the_same_position_keep_both() {
+    let mut relay = Relay::new();
+    let mut peers = vec![Peer::new(&mut relay, "ab"), Peer::new(&mut relay, "ab")];
+    settle(&mut relay, &mut peers);
+    for (peer, text) in peers.iter_mut().zip(["1", "2"]) {
+        peer.session.set_selection(
+            Position {
+                line_index: 0,
+                byte_index: 1,
+            },
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::Yes,
+        );
+        peer.session.insert(Text::from(text));
+        peer.send(&mut relay);
+    }
+    settle(&mut relay, &mut peers);
+    assert_converged(&relay, &peers);
+    assert_eq!(peers[0].text(), "a12b");
+}
+
+#[test]
+fn joining_merges_unsaved_edits_with_the_shared_text() {
+    let mut relay = Relay::new();
+    let mut peers = vec![Peer::new(&mut relay, "one\ntwo\nthree")];
+    settle(&mut relay, &mut peers);
+    peers[0].session.set_selection(
+        Position {
+            line_index: 0,
+            byte_index: 3,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    peers[0].session.insert(Text::from("!"));
+    settle(&mut relay, &mut peers);
+
+    // The second peer changed the last line before sharing, and types while joining.
+    let peer = Peer::new_with_base(&mut relay, "one\ntwo\nthree", "one\ntwo\nTHREE");
+    peers.push(peer);
+    peers[1].send(&mut relay);
+    peers[1].session.set_selection(
+        Position {
+            line_index: 1,
+            byte_index: 3,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    peers[1].session.insert(Text::from("?"));
+    settle(&mut relay, &mut peers);
+    assert_converged(&relay, &peers);
+    assert_eq!(peers[0].text(), "one!\ntwo?\nTHREE");
+}
+
+#[test]
+fn joining_again_does_not_duplicate_edits() {
+    let mut relay = Relay::new();
+    let mut peers = vec![
+        Peer::new(&mut relay, "one\ntwo"),
+        Peer::new(&mut relay, "one\ntwo"),
+    ];
+    settle(&mut relay, &mut peers);
+    peers[1].session.set_selection(
+        Position {
+            line_index: 1,
+            byte_index: 3,
+        },
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    peers[1].session.insert(Text::from("\nthree"));
+    settle(&mut relay, &mut peers);
+
+    // The connection of the second peer is lost, and it joins again from the text it was in sync
+    // with.
+    let lost = peers.pop().unwrap();
+    relay.server.disconnect(lost.site_id);
+    let base = lost.collab.synced_text().clone();
+    let document = lost.collab.document().clone();
+    drop(lost.collab);
+    let collab = Collab::new_with_base(document, base);
+    peers.push(Peer {
+        session: lost.session,
+        collab,
+        site_id: relay.server.connect(),
+        inbox: VecDeque::new(),
+    });
+    settle(&mut relay, &mut peers);
+    assert_converged(&relay, &peers);
+    assert_eq!(peers[0].text(), "one\ntwo\nthree");
+}
+
+#[test]
+fn server_forgets_operations_that_every_peer_has_seen() {
+    let mut relay = Relay::new();
+    let mut peers = vec![Peer::new(&mut relay, ""), Peer::new(&mut relay, "")];
+    settle(&mut relay, &mut peers);
+    for _ in 0..100 {
+        peers[0].session.insert(Text::from("a"));
+        settle(&mut relay, &mut peers);
+    }
+    assert_converged(&relay, &peers);
+    assert_eq!(relay.server.revision(), 100);
+    assert_eq!(relay.server.log_len(), 0);
+
+    // A message for a revision that was dropped from the log is ignored.
+    let messages = relay.server.handle_message(
+        peers[1].site_id,
+        ClientMessage::Operation {
+            revision: 0,
+            edits: Vec::new(),
+        },
+    );
+    assert!(messages.is_empty());
+}
+
+#[test]
+fn peers_see_each_others_selections() {
+    let mut relay = Relay::new();
+    let mut peers = vec![
+        Peer::new(&mut relay, "hello\nworld"),
+        Peer::new(&mut relay, "hello\nworld"),
+    ];
+    settle(&mut relay, &mut peers);
+    let position = Position {
+        line_index: 1,
+        byte_index: 2,
+    };
+    peers[0].session.set_selection(
+        position,
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    settle(&mut relay, &mut peers);
+    let remote_selections = peers[1].session.document().remote_selections();
+    let remote_selections: HashMap<_, _> = remote_selections
+        .iter()
+        .map(|selection| (selection.site_id, selection.cursor))
+        .collect();
+    assert_eq!(remote_selections.get(&peers[0].site_id), Some(&position));
+}
@@ -0,0 +1,200 @@
+use {
+    makepad_code_editor::{
+        completion::Completion,
+        decoration::DecorationSet,
+        history::NewGroup,
+        provider::{CompletionItem, CompletionKind, CompletionProvider},
+        selection::Affinity,
+        session::SelectionMode,
+        text::{Position, Text},
+        Document, Session,
+    },
+    std::cell::RefCell,
+};
+
+// Stands in for a language server: it returns the same items wherever it is asked, and remembers
+// where that was.
+struct FakeProvider {
+    items: Vec<CompletionItem>,
+    requests: RefCell<Vec<Position>>,
+}
+
+impl FakeProvider {
+    fn new() -> Self {
+        let mut call = CompletionItem::new("format_args", CompletionKind::Function);
+        call.insert_text = "format_args(${1:fmt}, ${2:args})$0".to_string();
+        call.detail = Some("macro".to_string());
+        Self {
+            items: vec![
+                CompletionItem::new("format", CompletionKind::Function),
+                call,
+                CompletionItem::new("for", CompletionKind::Keyword),
+                CompletionItem::new("from_utf8", CompletionKind::Function),
+                CompletionItem::new("Formatter", CompletionKind::Type),
+            ],
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl CompletionProvider for FakeProvider {
+    fn completions(&self, _document: &Document, position: Position) -> Vec<CompletionItem> {
+        self.requests.borrow_mut().push(position);
+        self.items.clone()
+    }
+}
+
+fn session(text: &str, cursor: Position) -> Session {
+    let session = Session::new(Document::new(Text::from(text), DecorationSet::new()));
+    session.set_selection(
+        cursor,
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    session
+}
+
+fn type_text(session: &Session, text: &str) {
+    session.insert(Text::from(text));
+    session.handle_changes();
+}
+
+fn labels(completion: &Completion) -> Vec<&str> {
+    completion
+        .matches()
+        .map(|(item, _)| item.label.as_str())
+        .collect()
+}
+
+fn text(session: &Session) -> String {
+    session.document().as_text().to_string()
+}
+
+#[test]
+fn typing_filters_and_accepting_replaces_the_word() {
+    let provider = FakeProvider::new();
+    let session = session(
+        "let s = \n",
+        Position {
+            line_index: 0,
+            byte_index: 8,
+        },
+    );
+    type_text(&session, "f");
+    // A single char is not enough to open the popup by itself.
+    assert!(Completion::open(&provider, &session, 2).is_none());
+    type_text(&session, "o");
+    let mut completion = Completion::open(&provider, &session, 2).unwrap();
+    assert_eq!(
+        *provider.requests.borrow(),
+        [Position {
+            line_index: 0,
+            byte_index: 10,
+        }]
+    );
+    assert_eq!(
+        labels(&completion),
+        ["for", "format", "format_args", "Formatter", "from_utf8"]
+    );
+
+    type_text(&session, "rm");
+    assert!(completion.update(&session));
+    assert_eq!(labels(&completion), ["format", "format_args", "Formatter"]);
+    completion.select(1, 10);
+    assert_eq!(completion.selected_item().label, "format_args");
+    completion.accept(&session);
+    session.handle_changes();
+    assert_eq!(text(&session), "let s = format_args(fmt, args)\n");
+
+    // The first tab stop of the snippet is selected, and tab moves on to the next one.
+    let selection = session.selections()[0];
+    assert_eq!(
+        (selection.start(), selection.end()),
+        (
+            Position {
+                line_index: 0,
+                byte_index: 20,
+            },
+            Position {
+                line_index: 0,
+                byte_index: 23,
+            }
+        )
+    );
+    type_text(&session, "\"{}\"");
+    assert!(session.next_snippet_tab_stop());
+    type_text(&session, "x");
+    assert!(session.next_snippet_tab_stop());
+    assert_eq!(text(&session), "let s = format_args(\"{}\", x)\n");
+    assert!(!session.has_active_snippet());
+
+    // Accepting is a single undo step.
+    session.undo();
+    session.undo();
+    session.undo();
+    session.handle_changes();
+    assert_eq!(text(&session), "let s = form\n");
+}
+
+#[test]
+fn leaving_the_word_closes_the_popup() {
+    let provider = FakeProvider::new();
+    let session = session(
+        "fo",
+        Position {
+            line_index: 0,
+            byte_index: 2,
+        },
+    );
+    let mut completion = Completion::open(&provider, &session, 0).unwrap();
+    type_text(&session, " ");
+    assert!(!completion.update(&session));
+}
+
+#[test]
+fn nothing_to_complete_closes_the_popup() {
+    let provider = FakeProvider::new();
+    let session = session(
+        "fo",
+        Position {
+            line_index: 0,
+            byte_index: 2,
+        },
+    );
+    let mut completion = Completion::open(&provider, &session, 0).unwrap();
+    type_text(&session, "q");
+    assert!(!completion.update(&session));
+
+    // Nor does a popup open for a word that is complete already.
+    let session = self::session(
+        "from_utf8",
+        Position {
+            line_index: 0,
+            byte_index: 9,
+        },
+    );
+    assert!(Completion::open(&provider, &session, 0).is_none());
+}
+
+#[test]
+fn selecting_wraps_around_and_scrolls() {
+    let provider = FakeProvider::new();
+    let session = session(
+        "",
+        Position {
+            line_index: 0,
+            byte_index: 0,
+        },
+    );
+    let mut completion = Completion::open(&provider, &session, 0).unwrap();
+    assert_eq!(completion.match_count(), 5);
+    completion.select(-1, 2);
+    assert_eq!(completion.selected_index(), 4);
+    assert_eq!(completion.first_visible_index(), 3);
+    completion.select(1, 2);
+    assert_eq!(completion.selected_index(), 0);
+    assert_eq!(completion.first_visible_index(), 0);
+    completion.select(10, 2);
+    assert_eq!(completion.selected_index(), 4);
+}
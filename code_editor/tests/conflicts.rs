@@ -0,0 +1,62 @@
+use makepad_code_editor::{
+    decoration::DecorationSet, merge::ConflictResolution, text::Text, Document, Session,
+};
+
+const CONFLICTED: &str = "\
+fn main() {
+<<<<<<< ours
+    println!(\"ours\");
+=======
+    println!(\"theirs\");
+    println!(\"more\");
+>>>>>>> theirs
+}";
+
+fn resolve(resolution: ConflictResolution) -> String {
+    let session = Session::new(Document::new(Text::from(CONFLICTED), DecorationSet::new()));
+    assert_eq!(session.conflicts().len(), 1);
+    session.resolve_conflict(0, resolution);
+    assert!(session.conflicts().is_empty());
+    let text = session.document().as_text().to_string();
+    text
+}
+
+#[test]
+fn resolve_ours() {
+    assert_eq!(
+        resolve(ConflictResolution::Ours),
+        "fn main() {\n    println!(\"ours\");\n}"
+    );
+}
+
+#[test]
+fn resolve_theirs() {
+    assert_eq!(
+        resolve(ConflictResolution::Theirs),
+        "fn main() {\n    println!(\"theirs\");\n    println!(\"more\");\n}"
+    );
+}
+
+#[test]
+fn resolve_both() {
+    assert_eq!(
+        resolve(ConflictResolution::Both),
+        "fn main() {\n    println!(\"ours\");\n    println!(\"theirs\");\n    println!(\"more\");\n}"
+    );
+}
+
+#[test]
+fn resolve_is_undone_in_one_step() {
+    let session = Session::new(Document::new(Text::from(CONFLICTED), DecorationSet::new()));
+    session.resolve_conflict(0, ConflictResolution::Theirs);
+    session.undo();
+    assert_eq!(session.document().as_text().to_string(), CONFLICTED);
+    assert_eq!(session.conflicts().len(), 1);
+}
+
+#[test]
+fn resolve_out_of_range_does_nothing() {
+    let session = Session::new(Document::new(Text::from(CONFLICTED), DecorationSet::new()));
+    session.resolve_conflict(1, ConflictResolution::Ours);
+    assert_eq!(session.document().as_text().to_string(), CONFLICTED);
+}
@@ -0,0 +1,237 @@
+use {
+    makepad_code_editor::{
+        diff::{diff_chars, diff_lines, Diff, DiffAlgorithm, Region},
+        text::Text,
+    },
+    std::ops::Range,
+};
+
+const ALGORITHMS: [DiffAlgorithm; 2] = [DiffAlgorithm::Myers, DiffAlgorithm::Patience];
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Lines drawn from the first `alphabet` letters, so that most of them occur more than once.
+    fn lines(&mut self, len: usize, alphabet: usize) -> Vec<String> {
+        (0..len)
+            .map(|_| ((b'a' + self.below(alphabet) as u8) as char).to_string())
+            .collect()
+    }
+}
+
+fn as_strs(lines: &[String]) -> Vec<&str> {
+    lines.iter().map(|line| line.as_str()).collect()
+}
+
+/// Checks that the hunks are ordered and separated by unchanged lines, and that the lines outside
+/// of them are the same in both texts. Returns the number of lines inserted or deleted.
+fn check_hunks(old: &[&str], new: &[&str], hunks: &[(Range<usize>, Range<usize>)]) -> usize {
+    let mut old_index = 0;
+    let mut new_index = 0;
+    let mut cost = 0;
+    for (old_range, new_range) in hunks {
+        assert!(!old_range.is_empty() || !new_range.is_empty(), "empty hunk");
+        assert!(old_range.start >= old_index && new_range.start >= new_index);
+        assert_eq!(old_range.start - old_index, new_range.start - new_index);
+        assert_eq!(
+            old[old_index..old_range.start],
+            new[new_index..new_range.start]
+        );
+        if old_index > 0 || new_index > 0 {
+            assert!(old_range.start > old_index, "adjacent hunks weren't merged");
+        }
+        cost += old_range.len() + new_range.len();
+        old_index = old_range.end;
+        new_index = new_range.end;
+    }
+    assert_eq!(old[old_index..], new[new_index..]);
+    cost
+}
+
+/// The number of lines inserted or deleted by a minimal diff, from the longest common subsequence.
+fn minimal_cost(old: &[&str], new: &[&str]) -> usize {
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in 0..old.len() {
+        for j in 0..new.len() {
+            lcs[i + 1][j + 1] = if old[i] == new[j] {
+                lcs[i][j] + 1
+            } else {
+                lcs[i][j + 1].max(lcs[i + 1][j])
+            };
+        }
+    }
+    old.len() + new.len() - 2 * lcs[old.len()][new.len()]
+}
+
+#[test]
+fn empty_and_identical_texts_have_no_hunks() {
+    let lines = ["fn main() {", "}"];
+    for algorithm in ALGORITHMS {
+        assert!(diff_lines(&[], &[], algorithm).is_empty());
+        assert!(diff_lines(&lines, &lines, algorithm).is_empty());
+        assert_eq!(diff_lines(&[], &lines, algorithm), [(0..0, 0..2)]);
+        assert_eq!(diff_lines(&lines, &[], algorithm), [(0..2, 0..0)]);
+    }
+    let text = Text::from("fn main() {\n}");
+    let diff = Diff::new(&text, &text, DiffAlgorithm::Myers);
+    assert!(diff.is_empty());
+    // without hunks there is no context to keep, so everything folds
+    assert_eq!(
+        diff.regions(3),
+        [Region::Folded {
+            old_range: 0..2,
+            new_start: 0
+        }]
+    );
+    assert_eq!(diff_chars("same", "same"), (vec![], vec![]));
+    let (old_ranges, new_ranges) = diff_chars("", "new");
+    assert!(old_ranges.is_empty());
+    assert_eq!(new_ranges, [Range { start: 0, end: 3 }]);
+}
+
+#[test]
+fn myers_diffs_are_minimal() {
+    let mut rng = Rng(0x2545f4914f6cdd1d);
+    for _ in 0..500 {
+        let (old_len, new_len) = (rng.below(24), rng.below(24));
+        let old = rng.lines(old_len, 4);
+        let new = rng.lines(new_len, 4);
+        let (old, new) = (as_strs(&old), as_strs(&new));
+        let cost = check_hunks(&old, &new, &diff_lines(&old, &new, DiffAlgorithm::Myers));
+        assert_eq!(cost, minimal_cost(&old, &new), "{:?} -> {:?}", old, new);
+        // patience gives up minimality for nicer hunks, but must still be a valid diff
+        check_hunks(&old, &new, &diff_lines(&old, &new, DiffAlgorithm::Patience));
+    }
+}
+
+#[test]
+fn char_ranges_are_the_difference_within_a_line() {
+    // byte ranges, so the multibyte chars before the change count for their length
+    let (old_ranges, new_ranges) = diff_chars("let ä = 1;", "let ä = 22;");
+    assert_eq!(old_ranges, [Range { start: 9, end: 10 }]);
+    assert_eq!(new_ranges, [Range { start: 9, end: 11 }]);
+    let (old_ranges, new_ranges) = diff_chars("foo(a, b)", "foo(b)");
+    assert_eq!(old_ranges, [Range { start: 4, end: 7 }]);
+    assert!(new_ranges.is_empty());
+}
+
+#[test]
+fn patience_anchors_on_unique_lines() {
+    // Inserting a function above another one that shares its braces and return statement.
+    let old = "\
+#include <stdio.h>
+
+// Frobs foo heartily
+int frobnitz(int foo)
+{
+    int i;
+    for(i = 0; i < 10; i++)
+    {
+        printf(\"Your answer is: \");
+        printf(\"%d\\n\", foo);
+    }
+}
+
+int fact(int n)
+{
+    if(n > 1)
+    {
+        return fact(n-1) * n;
+    }
+    return 1;
+}
+
+int main(int argc, char **argv)
+{
+    frobnitz(fact(10));
+}";
+    let new = "\
+#include <stdio.h>
+
+int fib(int n)
+{
+    if(n > 2)
+    {
+        return fib(n-1) + fib(n-2);
+    }
+    return 1;
+}
+
+// Frobs foo heartily
+int frobnitz(int foo)
+{
+    int i;
+    for(i = 0; i < 10; i++)
+    {
+        printf(\"%d\\n\", foo);
+    }
+}
+
+int main(int argc, char **argv)
+{
+    frobnitz(fib(10));
+}";
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let patience = diff_lines(&old, &new, DiffAlgorithm::Patience);
+    check_hunks(&old, &new, &patience);
+    // fib goes in as a whole before the unique comment, and fact goes out as a whole
+    assert_eq!(
+        patience,
+        [
+            (2..2, 2..11),
+            (8..9, 17..17),
+            (13..22, 21..21),
+            (24..25, 23..24),
+        ]
+    );
+
+    // A unique line is matched even where keeping the repeated lines around it would be cheaper.
+    let old = ["}", "}", "}", "fn main() {"];
+    let new = ["fn main() {", "}", "}", "}"];
+    assert_eq!(
+        diff_lines(&old, &new, DiffAlgorithm::Patience),
+        [(0..3, 0..0), (4..4, 1..4)]
+    );
+    assert_eq!(
+        diff_lines(&old, &new, DiffAlgorithm::Myers),
+        [(0..0, 0..1), (3..4, 4..4)]
+    );
+}
+
+#[test]
+fn diffs_with_many_changes_are_cut_short() {
+    // Thousands of unrelated changes go well past the edit cost at which the search for a minimal
+    // diff gives up, which must still give a valid one.
+    let mut rng = Rng(0x9e3779b97f4a7c15);
+    let old = rng.lines(20_000, 26);
+    let mut new = old.clone();
+    for _ in 0..5_000 {
+        let index = rng.below(new.len());
+        match rng.below(3) {
+            0 => new[index] = "changed".to_string(),
+            1 => {
+                new.remove(index);
+            }
+            _ => new.insert(index, "inserted".to_string()),
+        }
+    }
+    let (old, new) = (as_strs(&old), as_strs(&new));
+    for algorithm in ALGORITHMS {
+        let hunks = diff_lines(&old, &new, algorithm);
+        let cost = check_hunks(&old, &new, &hunks);
+        // not minimal, but not a wholesale replacement either
+        assert!(cost < old.len(), "{:?} cost {}", algorithm, cost);
+    }
+}
@@ -0,0 +1,112 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    history::NewGroup,
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+// A small deterministic random number generator, so that failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Picks a position on one of the `line_count` lines from `line_index` on.
+    fn position(&mut self, text: &Text, line_index: usize, line_count: usize) -> Position {
+        let line_index = (line_index + self.below(line_count)).min(text.as_lines().len() - 1);
+        let line = &text.as_lines()[line_index];
+        let mut byte_index = self.below(line.len() + 1);
+        while !line.is_char_boundary(byte_index) {
+            byte_index -= 1;
+        }
+        Position {
+            line_index,
+            byte_index,
+        }
+    }
+}
+
+const SNIPPETS: &[&str] = &[
+    "x",
+    " ",
+    "fn f() {\n    let a = 1;\n}\n",
+    "/* ",
+    " */",
+    "\"",
+    "\n",
+    "\n\n\n",
+    "    if a {\n",
+    "}",
+];
+
+/// Checks that the tokens and indent state that `document` keeps up to date as it is edited are
+/// the same as those of a document that is created from its text.
+fn check(document: &Document) {
+    let text = document.as_text().clone();
+    let expected = Document::new(text.clone(), DecorationSet::new());
+    let layout = document.layout();
+    let expected_layout = expected.layout();
+    assert_eq!(layout.tokens.len(), text.as_lines().len());
+    assert_eq!(layout.indent_state.len(), text.as_lines().len());
+    assert_eq!(layout.inline_inlays.len(), text.as_lines().len());
+    assert_eq!(layout.tokens, expected_layout.tokens);
+    assert_eq!(layout.indent_state, expected_layout.indent_state);
+    assert_eq!(layout.tokens.byte_count(), text.as_lines().byte_count());
+}
+
+#[test]
+fn side_tables_stay_in_sync_with_the_text() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let text = (0..300)
+        .map(|index| match index % 5 {
+            0 => "fn main() {",
+            1 => "    let x = \"a b\";",
+            2 => "",
+            3 => "    // c",
+            _ => "}",
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let session = Session::new(Document::new(
+        Text::from(text.as_str()),
+        DecorationSet::new(),
+    ));
+    for step in 0..200 {
+        let text = session.document().as_text().clone();
+        let start = rng.position(&text, 0, text.as_lines().len());
+        let end = rng.position(&text, start.line_index, 3);
+        session.set_selection(
+            start,
+            Affinity::Before,
+            SelectionMode::Simple,
+            NewGroup::Yes,
+        );
+        session.move_to(end, Affinity::Before, NewGroup::Yes);
+        if rng.below(3) == 0 {
+            session.delete();
+        } else {
+            session.insert(Text::from(SNIPPETS[rng.below(SNIPPETS.len())]));
+        }
+        session.handle_changes();
+        if step % 10 == 0 {
+            check(session.document());
+        }
+    }
+    check(session.document());
+    while session.undo() {
+        session.handle_changes();
+    }
+    check(session.document());
+    assert_eq!(session.document().as_text().to_string(), text);
+}
@@ -0,0 +1,85 @@
+use makepad_code_editor::keymap::{Command, KeyChord, Keymap, Resolution};
+
+fn keys(keys: &str) -> Vec<KeyChord> {
+    keys.split_whitespace()
+        .map(|chord| KeyChord::parse(chord).unwrap())
+        .collect()
+}
+
+#[test]
+fn rebinding_a_sequence_replaces_its_command() {
+    let mut keymap = Keymap::default();
+    assert_eq!(
+        keymap.resolve(&keys("ctrl+z")),
+        Resolution::Command(Command::Undo)
+    );
+    keymap.load("ctrl+z = redo").unwrap();
+    assert_eq!(
+        keymap.resolve(&keys("ctrl+z")),
+        Resolution::Command(Command::Redo)
+    );
+    // The other bindings for the command are left alone.
+    assert_eq!(
+        keymap.resolve(&keys("cmd+z")),
+        Resolution::Command(Command::Undo)
+    );
+}
+
+#[test]
+fn binding_a_prefix_removes_the_longer_sequences() {
+    let mut keymap = Keymap::new();
+    keymap
+        .load("ctrl+k ctrl+u = undo\nctrl+k ctrl+r = redo")
+        .unwrap();
+    assert_eq!(keymap.resolve(&keys("ctrl+k")), Resolution::Pending);
+    keymap.load("ctrl+k = find").unwrap();
+    assert_eq!(
+        keymap.resolve(&keys("ctrl+k")),
+        Resolution::Command(Command::Find)
+    );
+    assert_eq!(keymap.resolve(&keys("ctrl+k ctrl+u")), Resolution::Unbound);
+    assert_eq!(keymap.bindings().len(), 1);
+}
+
+#[test]
+fn binding_a_longer_sequence_removes_its_prefix() {
+    let mut keymap = Keymap::new();
+    keymap.load("ctrl+k = find").unwrap();
+    keymap.load("ctrl+k ctrl+u = undo").unwrap();
+    assert_eq!(keymap.resolve(&keys("ctrl+k")), Resolution::Pending);
+    assert_eq!(
+        keymap.resolve(&keys("ctrl+k ctrl+u")),
+        Resolution::Command(Command::Undo)
+    );
+    assert_eq!(keymap.bindings().len(), 1);
+}
+
+#[test]
+fn binding_to_none_unbinds() {
+    let mut keymap = Keymap::default();
+    keymap.load("ctrl+z = none").unwrap();
+    assert_eq!(keymap.resolve(&keys("ctrl+z")), Resolution::Unbound);
+}
+
+#[test]
+fn loading_reports_the_line_of_an_error() {
+    let mut keymap = Keymap::new();
+    let error = keymap
+        .load("# comment\nctrl+z = undo\nctrl+q = frobnicate")
+        .unwrap_err();
+    assert!(error.starts_with("line 3:"), "{}", error);
+    // The lines before the error were loaded.
+    assert_eq!(
+        keymap.resolve(&keys("ctrl+z")),
+        Resolution::Command(Command::Undo)
+    );
+}
+
+#[test]
+fn vim_mode_is_toggled_by_the_keymap() {
+    let mut keymap = Keymap::default();
+    assert!(!keymap.vim_mode);
+    keymap.load("vim = on").unwrap();
+    assert!(keymap.vim_mode);
+    assert!(keymap.load("vim = maybe").is_err());
+}
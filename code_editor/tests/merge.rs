@@ -0,0 +1,74 @@
+use makepad_code_editor::{
+    merge::{self, find_conflicts, Conflict, Merge},
+    text::Text,
+};
+
+fn merge(base: &str, ours: &str, theirs: &str) -> Merge {
+    merge::merge(
+        &Text::from(base),
+        &Text::from(ours),
+        &Text::from(theirs),
+        "ours",
+        "theirs",
+    )
+}
+
+const BASE: &str = "a\nb\nc\nd\ne";
+
+#[test]
+fn changes_to_different_lines_are_both_taken() {
+    let merged = merge(BASE, "a\nB\nc\nd\ne", "a\nb\nc\nD\ne");
+    assert_eq!(merged.text.to_string(), "a\nB\nc\nD\ne");
+    assert_eq!(merged.conflict_count, 0);
+
+    // insertions and deletions on either side
+    let merged = merge(BASE, "start\na\nb\nc\nd\ne", "a\nb\nc\ne\nend");
+    assert_eq!(merged.text.to_string(), "start\na\nb\nc\ne\nend");
+    assert_eq!(merged.conflict_count, 0);
+
+    // a side that didn't change anything takes the other side
+    let merged = merge(BASE, BASE, "x\ny");
+    assert_eq!(merged.text.to_string(), "x\ny");
+    assert_eq!(merged.conflict_count, 0);
+}
+
+#[test]
+fn identical_overlapping_changes_are_taken_once() {
+    let merged = merge(BASE, "a\nB\nC\nd\ne", "a\nB\nC\nd\ne\nf");
+    assert_eq!(merged.text.to_string(), "a\nB\nC\nd\ne\nf");
+    assert_eq!(merged.conflict_count, 0);
+}
+
+#[test]
+fn different_overlapping_changes_conflict() {
+    let merged = merge(BASE, "a\nours\nc\nd\nE", "a\ntheirs\ntheirs\nc\nd\nE");
+    assert_eq!(
+        merged.text.to_string(),
+        "a\n<<<<<<< ours\nours\n=======\ntheirs\ntheirs\n>>>>>>> theirs\nc\nd\nE"
+    );
+    assert_eq!(merged.conflict_count, 1);
+    let conflicts = find_conflicts(&merged.text);
+    assert_eq!(
+        conflicts,
+        [Conflict {
+            start_line: 1,
+            separator_line: 3,
+            end_line: 6,
+        }]
+    );
+    assert_eq!(conflicts[0].our_lines(), 2..3);
+    assert_eq!(conflicts[0].their_lines(), 4..6);
+
+    // changes to adjacent lines are grouped and conflict as well
+    let merged = merge(BASE, "a\nB\nc\nd\ne", "a\nb\nC\nd\ne");
+    assert_eq!(
+        merged.text.to_string(),
+        "a\n<<<<<<< ours\nB\nc\n=======\nb\nC\n>>>>>>> theirs\nd\ne"
+    );
+    assert_eq!(merged.conflict_count, 1);
+
+    // one side deleting lines the other side changed
+    let merged = merge(BASE, "a\nd\ne", "a\nb\nX\nd\ne");
+    assert_eq!(merged.conflict_count, 1);
+    assert_eq!(find_conflicts(&merged.text).len(), 1);
+}
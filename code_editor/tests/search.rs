@@ -0,0 +1,185 @@
+use {
+    makepad_code_editor::{
+        decoration::DecorationSet,
+        regex::Regex,
+        search::{SearchQuery, Searcher},
+        text::Text,
+        Document, Session,
+    },
+    std::ops::Range,
+};
+
+/// The byte range of the leftmost match of `pattern` in `text`.
+fn find(pattern: &str, text: &str) -> Option<(usize, usize)> {
+    let captures = Regex::new(pattern, false).unwrap().find_at(text, 0)?;
+    Some((captures.start(), captures.end()))
+}
+
+fn searcher(
+    pattern: &str,
+    is_case_sensitive: bool,
+    is_whole_word: bool,
+    is_regex: bool,
+) -> Searcher {
+    Searcher::new(&SearchQuery {
+        pattern: pattern.to_string(),
+        is_case_sensitive,
+        is_whole_word,
+        is_regex,
+    })
+    .unwrap()
+}
+
+fn session(text: &str) -> Session {
+    Session::new(Document::new(Text::from(text), DecorationSet::new()))
+}
+
+#[test]
+fn anchors_only_match_at_line_and_word_boundaries() {
+    assert_eq!(find("^fn", "fn main"), Some((0, 2)));
+    assert_eq!(find("^main", "fn main"), None);
+    assert_eq!(find("main$", "fn main"), Some((3, 7)));
+    assert_eq!(find("fn$", "fn main"), None);
+    assert_eq!(find("^$", ""), Some((0, 0)));
+    assert_eq!(find(r"\bin\b", "print in"), Some((6, 8)));
+    assert_eq!(find(r"\Bin\B", "in print"), Some((5, 7)));
+    // a match is only anchored to the start of the line, not to where the search starts
+    let regex = Regex::new("^a", false).unwrap();
+    assert_eq!(regex.find_at("aa", 1), None);
+}
+
+#[test]
+fn classes_match_ranges_shorthands_and_their_negations() {
+    assert_eq!(find("[a-c]+", "xxbcay"), Some((2, 5)));
+    assert_eq!(find("[^a-c ]+", "abc def"), Some((4, 7)));
+    assert_eq!(find(r"\d+", "v12.5"), Some((1, 3)));
+    assert_eq!(find(r"\D+", "12ab3"), Some((2, 4)));
+    assert_eq!(find(r"\w+", "  foo_1 "), Some((2, 7)));
+    assert_eq!(find(r"\s\S", "a  b"), Some((2, 4)));
+    // a `-` at either end of a class and `]` right after the opening bracket are literals
+    assert_eq!(find("[-a]+", "x-a-"), Some((1, 4)));
+    assert_eq!(find("[]x]+", "a]x]"), Some((1, 4)));
+    assert_eq!(find(r"[\d.]+", "v1.25"), Some((1, 5)));
+    for pattern in ["[a", "[z-a]", r"[\b]"] {
+        assert!(
+            Regex::new(pattern, false).is_err(),
+            "{} was accepted",
+            pattern
+        );
+    }
+}
+
+#[test]
+fn alternation_and_repetition_prefer_the_leftmost_match() {
+    assert_eq!(find("cat|dog", "hotdog catalog"), Some((3, 6)));
+    // the first alternative wins when both match at the same position
+    assert_eq!(find("ab|abc", "abc"), Some((0, 2)));
+    assert_eq!(find("(?:ab|abc)d", "abcd"), Some((0, 4)));
+    assert_eq!(find("a.*b", "a1b2b"), Some((0, 5)));
+    assert_eq!(find("a.*?b", "a1b2b"), Some((0, 3)));
+    assert_eq!(find("x{2,3}", "xxxxx"), Some((0, 3)));
+    assert_eq!(find("x{2,}?", "xxxxx"), Some((0, 2)));
+    // a `{` that doesn't start a valid bound is a literal
+    assert_eq!(find("a{,2}", "a{,2}"), Some((0, 5)));
+    for pattern in ["*a", "(a", "a)", "x{3,2}", "x{1001}", r"(a)\1"] {
+        assert!(
+            Regex::new(pattern, false).is_err(),
+            "{} was accepted",
+            pattern
+        );
+    }
+}
+
+#[test]
+fn captures_are_expanded_in_replacements() {
+    let regex = Regex::new(r"(\w+) = (\d+)?", false).unwrap();
+    let captures = regex.find_at("let x = ;", 0).unwrap();
+    assert_eq!(captures.get(1), Some((4, 5)));
+    // a group that didn't take part in the match has no range
+    assert_eq!(captures.get(2), None);
+
+    let line = "let x = 1;";
+    let searcher = searcher(r"(\w+) = (\d+)", true, false, true);
+    let range = searcher.find_all(line)[0].clone();
+    assert_eq!(range, 4..9);
+    assert_eq!(
+        searcher.replacement(line, range.clone(), "$2 = $1"),
+        "1 = x"
+    );
+    assert_eq!(
+        searcher.replacement(line, range.clone(), "${1}0 = $$"),
+        "x0 = $"
+    );
+    assert_eq!(
+        searcher.replacement(line, range.clone(), r"$0\t$3\n"),
+        "x = 1\t\n"
+    );
+    // without a regex the replacement is taken literally
+    let literal = self::searcher("x", true, false, false);
+    assert_eq!(literal.replacement(line, 4..5, "$1"), "$1");
+
+    let session = session("a = 1\nb = 22");
+    session
+        .set_search_query(&SearchQuery {
+            pattern: r"(\w) = (\d+)".to_string(),
+            is_case_sensitive: true,
+            is_whole_word: false,
+            is_regex: true,
+        })
+        .unwrap();
+    assert_eq!(session.replace_all_search_matches("$2 = $1"), 2);
+    assert_eq!(session.document().as_text().to_string(), "1 = a\n22 = b");
+}
+
+#[test]
+fn case_and_whole_word_options_filter_matches() {
+    let line = "Foo foo FOO food";
+    assert_eq!(
+        searcher("foo", true, false, false).find_all(line),
+        [4..7, 12..15]
+    );
+    assert_eq!(
+        searcher("foo", false, false, false).find_all(line),
+        [0..3, 4..7, 8..11, 12..15]
+    );
+    assert_eq!(
+        searcher("foo", false, true, false).find_all(line),
+        [0..3, 4..7, 8..11]
+    );
+    assert_eq!(
+        searcher("fo+", true, true, true).find_all(line),
+        [Range { start: 4, end: 7 }]
+    );
+    assert_eq!(
+        searcher("F.O ", false, false, true).find_all(line),
+        [0..4, 4..8, 8..12]
+    );
+    // non-ASCII patterns are case folded by the regex engine, without regex syntax
+    assert_eq!(
+        searcher("ÄB.", false, false, false).find_all("äb. äbc"),
+        [Range { start: 0, end: 4 }]
+    );
+    // empty matches are skipped rather than reported
+    assert!(searcher("x*", true, false, true).find_all("abc").is_empty());
+}
+
+#[test]
+fn the_active_match_is_counted_from_the_start_of_the_document() {
+    let session = session("foo\nfoo foo\nbar\nfoo");
+    session
+        .set_search_query(&SearchQuery {
+            pattern: "foo".to_string(),
+            ..SearchQuery::default()
+        })
+        .unwrap();
+    // Wrapping around to the last match only searches the first and last lines, so the lines in
+    // between have to be searched to number it.
+    assert!(session.select_prev_search_match());
+    assert_eq!(session.active_search_match_index(), Some(3));
+    assert!(session.select_next_search_match());
+    assert_eq!(session.active_search_match_index(), Some(0));
+    assert!(session.select_next_search_match());
+    assert!(session.select_next_search_match());
+    assert_eq!(session.active_search_match_index(), Some(2));
+    assert_eq!(session.search_match_count(), 4);
+}
@@ -0,0 +1,209 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    history::NewGroup,
+    selection::Affinity,
+    session::SelectionMode,
+    text::{Position, Text},
+    Document, Session,
+};
+
+fn session(text: &str) -> Session {
+    Session::new(Document::new(Text::from(text), DecorationSet::new()))
+}
+
+fn position(line_index: usize, byte_index: usize) -> Position {
+    Position {
+        line_index,
+        byte_index,
+    }
+}
+
+/// The anchor and cursor of every selection, as `(line, byte)` pairs.
+fn selections(session: &Session) -> Vec<((usize, usize), (usize, usize))> {
+    session
+        .selections()
+        .iter()
+        .map(|selection| {
+            (
+                (selection.anchor.line_index, selection.anchor.byte_index),
+                (
+                    selection.cursor.position.line_index,
+                    selection.cursor.position.byte_index,
+                ),
+            )
+        })
+        .collect()
+}
+
+fn select(session: &Session, start: Position, end: Position) {
+    session.set_selection(
+        start,
+        Affinity::Before,
+        SelectionMode::Simple,
+        NewGroup::Yes,
+    );
+    session.move_to(end, Affinity::Before, NewGroup::Yes);
+}
+
+#[test]
+fn select_next_occurrence_selects_the_word_under_an_empty_selection_first() {
+    let session = session("let foo = foo + foo;");
+    select(&session, position(0, 5), position(0, 5));
+    assert!(session.select_next_occurrence());
+    assert_eq!(selections(&session), [((0, 4), (0, 7))]);
+    assert!(session.select_next_occurrence());
+    assert_eq!(selections(&session), [((0, 4), (0, 7)), ((0, 10), (0, 13))]);
+    assert_eq!(session.last_added_selection_index(), Some(1));
+}
+
+#[test]
+fn select_next_occurrence_wraps_around_and_stops_when_everything_is_selected() {
+    let session = session("foo\nfoo\nfoo");
+    select(&session, position(1, 0), position(1, 3));
+    assert!(session.select_next_occurrence());
+    assert!(session.select_next_occurrence());
+    assert_eq!(
+        selections(&session),
+        [((0, 0), (0, 3)), ((1, 0), (1, 3)), ((2, 0), (2, 3))]
+    );
+    assert_eq!(session.last_added_selection_index(), Some(0));
+    assert!(!session.select_next_occurrence());
+    assert_eq!(session.selections().len(), 3);
+}
+
+#[test]
+fn select_next_occurrence_only_matches_whole_words_for_a_whole_word() {
+    let session = session("i in if i");
+    select(&session, position(0, 0), position(0, 1));
+    assert!(session.select_next_occurrence());
+    assert_eq!(selections(&session), [((0, 0), (0, 1)), ((0, 8), (0, 9))]);
+}
+
+#[test]
+fn select_next_occurrence_matches_text_that_spans_lines() {
+    let session = session("a\nb a\nb");
+    select(&session, position(0, 0), position(1, 1));
+    assert!(session.select_next_occurrence());
+    assert_eq!(selections(&session), [((0, 0), (1, 1)), ((1, 2), (2, 1))]);
+}
+
+#[test]
+fn select_all_occurrences_merges_the_selections_it_replaces() {
+    let session = session("foo bar foo");
+    select(&session, position(0, 1), position(0, 1));
+    session.add_selection(position(0, 9), Affinity::Before, SelectionMode::Simple);
+    session.select_all_occurrences();
+    assert_eq!(selections(&session), [((0, 0), (0, 3)), ((0, 8), (0, 11))]);
+    assert_eq!(session.last_added_selection_index(), Some(1));
+}
+
+#[test]
+fn select_all_occurrences_does_nothing_outside_a_word() {
+    let session = session("foo  foo");
+    select(&session, position(0, 4), position(0, 4));
+    session.select_all_occurrences();
+    assert_eq!(selections(&session), [((0, 4), (0, 4))]);
+}
+
+#[test]
+fn set_column_selection_selects_a_block_and_clamps_short_lines() {
+    let session = session("abcdef\nab\n\nabcdef");
+    session.set_column_selection(
+        position(0, 1),
+        Affinity::Before,
+        position(3, 4),
+        Affinity::Before,
+    );
+    assert_eq!(
+        selections(&session),
+        [
+            ((0, 1), (0, 4)),
+            ((1, 1), (1, 2)),
+            ((2, 0), (2, 0)),
+            ((3, 1), (3, 4)),
+        ]
+    );
+    assert_eq!(session.last_added_selection_index(), Some(3));
+}
+
+#[test]
+fn set_column_selection_works_upwards_and_right_to_left() {
+    let session = session("abcdef\nabcdef\nabcdef");
+    session.set_column_selection(
+        position(2, 4),
+        Affinity::Before,
+        position(0, 2),
+        Affinity::Before,
+    );
+    assert_eq!(
+        selections(&session),
+        [((0, 4), (0, 2)), ((1, 4), (1, 2)), ((2, 4), (2, 2))]
+    );
+    assert_eq!(session.last_added_selection_index(), Some(0));
+}
+
+#[test]
+fn split_selection_into_lines_splits_every_multi_line_selection() {
+    let session = session("abc\ndef\nghi\njkl");
+    select(&session, position(0, 1), position(2, 2));
+    session.add_selection(position(3, 1), Affinity::Before, SelectionMode::Simple);
+    session.split_selection_into_lines();
+    assert_eq!(
+        selections(&session),
+        [
+            ((0, 1), (0, 3)),
+            ((1, 0), (1, 3)),
+            ((2, 0), (2, 2)),
+            ((3, 1), (3, 1)),
+        ]
+    );
+    assert_eq!(session.last_added_selection_index(), Some(3));
+}
+
+#[test]
+fn split_selection_into_lines_skips_the_line_a_selection_ends_at_the_start_of() {
+    let session = session("abc\ndef\nghi");
+    select(&session, position(0, 0), position(2, 0));
+    session.split_selection_into_lines();
+    assert_eq!(selections(&session), [((0, 0), (0, 3)), ((1, 0), (1, 3))]);
+}
+
+#[test]
+fn add_cursor_above_and_below_stop_at_the_first_and_last_line() {
+    let session = session("abc\ndef\nghi");
+    select(&session, position(1, 2), position(1, 2));
+    assert!(session.add_cursor_above());
+    assert!(!session.add_cursor_above());
+    assert_eq!(selections(&session), [((0, 2), (0, 2)), ((1, 2), (1, 2))]);
+    select(&session, position(1, 2), position(1, 2));
+    assert!(session.add_cursor_below());
+    assert!(!session.add_cursor_below());
+    assert_eq!(selections(&session), [((1, 2), (1, 2)), ((2, 2), (2, 2))]);
+}
+
+#[test]
+fn add_cursor_merges_with_a_cursor_that_is_already_there() {
+    let session = session("abc\ndef\nghi");
+    select(&session, position(0, 1), position(0, 1));
+    assert!(session.add_cursor_below());
+    assert!(session.add_cursor_below());
+    assert_eq!(session.last_added_selection_index(), Some(2));
+    assert!(session.add_cursor_above());
+    assert_eq!(
+        selections(&session),
+        [((0, 1), (0, 1)), ((1, 1), (1, 1)), ((2, 1), (2, 1))]
+    );
+    assert_eq!(session.last_added_selection_index(), Some(1));
+}
+
+#[test]
+fn add_cursor_keeps_the_column_across_short_lines() {
+    let session = session("abcdef\nab\nabcdef");
+    select(&session, position(0, 5), position(0, 5));
+    assert!(session.add_cursor_below());
+    assert!(session.add_cursor_below());
+    assert_eq!(
+        selections(&session),
+        [((0, 5), (0, 5)), ((1, 2), (1, 2)), ((2, 5), (2, 5))]
+    );
+}
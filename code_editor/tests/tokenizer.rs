@@ -0,0 +1,528 @@
+use makepad_code_editor::{
+    language::{language_for_name, language_for_path, Language},
+    languages::{Glsl, JavaScript, Json, LiveDesign, Markdown, PlainText, Rust, Shader, Toml},
+    rope::Rope,
+    text::{Change, Length, Position, Text},
+    token::TokenKind::{self, *},
+    tokenizer::Tokenizer,
+    Token,
+};
+
+// `String` is a token kind here.
+type Line = Vec<(std::string::String, TokenKind)>;
+
+/// The tokens of each line as their text and kind, leaving out whitespace.
+fn tokens(language: &'static dyn Language, text: &str) -> Vec<Line> {
+    let text = Text::from(text);
+    let mut tokens: Rope<Vec<Token>> = vec![Vec::new(); text.as_lines().len()]
+        .into_iter()
+        .collect();
+    Tokenizer::new(language, tokens.len()).update(&text, &mut tokens);
+    split(&text, &tokens)
+}
+
+fn split(text: &Text, tokens: &Rope<Vec<Token>>) -> Vec<Line> {
+    text.as_lines()
+        .iter()
+        .zip(tokens)
+        .map(|(line, tokens)| {
+            let mut start = 0;
+            let mut line_tokens = Vec::new();
+            for token in tokens {
+                if token.kind != Whitespace {
+                    line_tokens.push((line[start..start + token.len].to_string(), token.kind));
+                }
+                start += token.len;
+            }
+            assert_eq!(start, line.len(), "the tokens of {:?} don't cover it", line);
+            line_tokens
+        })
+        .collect()
+}
+
+fn line(tokens: &[(&str, TokenKind)]) -> Line {
+    tokens
+        .iter()
+        .map(|(text, kind)| (text.to_string(), *kind))
+        .collect()
+}
+
+/// The kind of every token on a single line.
+fn kinds(language: &'static dyn Language, text: &str) -> Vec<TokenKind> {
+    tokens(language, text)[0]
+        .iter()
+        .map(|(_, kind)| *kind)
+        .collect()
+}
+
+#[test]
+fn rust() {
+    let tokens = tokens(
+        &Rust,
+        "fn main() {\n\
+         let x = 0x1F + 1.5e3 + 2u8; // hi\n\
+         /* a /* nested */ still\n\
+         */ 'a' 'b: \"s\\\"t\" r#\"raw\"# Foo FOO for if\n\
+         1. 0b12 1e+ x.0",
+    );
+    assert_eq!(
+        tokens[0],
+        line(&[
+            ("fn", OtherKeyword),
+            ("main", Function),
+            ("(", Delimiter),
+            (")", Delimiter),
+            ("{", Delimiter),
+        ])
+    );
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("let", OtherKeyword),
+            ("x", Identifier),
+            ("=", Punctuator),
+            ("0x1F", Number),
+            ("+", Punctuator),
+            ("1.5e3", Number),
+            ("+", Punctuator),
+            ("2u8", Number),
+            (";", Punctuator),
+            ("// hi", Comment),
+        ])
+    );
+    // Block comments nest, and continue on the next line until the outermost one is closed.
+    assert_eq!(tokens[2], line(&[("/* a /* nested */ still", Comment)]));
+    assert_eq!(
+        tokens[3],
+        line(&[
+            ("*/", Comment),
+            ("'a'", String),
+            ("'b", String),
+            (":", Punctuator),
+            ("\"s\\\"t\"", String),
+            ("r#\"raw\"#", String),
+            ("Foo", Typename),
+            ("FOO", Constant),
+            ("for", LoopKeyword),
+            ("if", BranchKeyword),
+        ])
+    );
+    assert_eq!(
+        tokens[4],
+        line(&[
+            ("1.", Number),
+            ("0b1", Number),
+            ("2", Number),
+            ("1e+", Unknown),
+            ("x", Identifier),
+            (".0", Number),
+        ])
+    );
+}
+
+#[test]
+fn rust_embeds_live_design_and_shaders() {
+    let tokens = tokens(
+        &Rust,
+        "live_design! {\n\
+         A = {color: #f00, fn pixel(self) -> vec4 { return #0f0 }}\n\
+         }\n\
+         let c = #f00;",
+    );
+    // Colors are only a thing in the DSL and in the shaders inside it.
+    assert_eq!(tokens[1][5], ("#f00".to_string(), Number));
+    assert_eq!(tokens[1][16], ("return".to_string(), BranchKeyword));
+    assert_eq!(tokens[1][17], ("#0f0".to_string(), Number));
+    assert_eq!(
+        tokens[3][3..5],
+        line(&[("#", Punctuator), ("f00", Identifier)])
+    );
+}
+
+#[test]
+fn live_design() {
+    let tokens = tokens(
+        &LiveDesign,
+        "Button = <View> {\n\
+         width: Fit, color: #fff, height: -10.0 // c\n\
+         text: \"hi\", visible: true }",
+    );
+    assert_eq!(
+        tokens[0],
+        line(&[
+            ("Button", Typename),
+            ("=", Punctuator),
+            ("<", Punctuator),
+            ("View", Typename),
+            (">", Punctuator),
+            ("{", Delimiter),
+        ])
+    );
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("width", Identifier),
+            (":", Punctuator),
+            ("Fit", Typename),
+            (",", Punctuator),
+            ("color", Identifier),
+            (":", Punctuator),
+            ("#fff", Number),
+            (",", Punctuator),
+            ("height", Identifier),
+            (":", Punctuator),
+            ("-10.0", Number),
+            ("// c", Comment),
+        ])
+    );
+    assert_eq!(
+        tokens[2],
+        line(&[
+            ("text", Identifier),
+            (":", Punctuator),
+            ("\"hi\"", String),
+            (",", Punctuator),
+            ("visible", Identifier),
+            (":", Punctuator),
+            ("true", Constant),
+            ("}", Delimiter),
+        ])
+    );
+}
+
+#[test]
+fn shader() {
+    let tokens = tokens(
+        &Shader,
+        "fn pixel(self) -> vec4 {\n\
+         return mix(#f00, #0f0, self.pos.x) * 1.5;\n\
+         }",
+    );
+    assert_eq!(
+        tokens[0],
+        line(&[
+            ("fn", OtherKeyword),
+            ("pixel", Function),
+            ("(", Delimiter),
+            ("self", OtherKeyword),
+            (")", Delimiter),
+            ("-", Punctuator),
+            (">", Punctuator),
+            ("vec4", Typename),
+            ("{", Delimiter),
+        ])
+    );
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("return", BranchKeyword),
+            ("mix", Function),
+            ("(", Delimiter),
+            ("#f00", Number),
+            (",", Punctuator),
+            ("#0f0", Number),
+            (",", Punctuator),
+            ("self", OtherKeyword),
+            (".", Punctuator),
+            ("pos", Identifier),
+            (".", Punctuator),
+            ("x", Identifier),
+            (")", Delimiter),
+            ("*", Punctuator),
+            ("1.5", Number),
+            (";", Punctuator),
+        ])
+    );
+}
+
+#[test]
+fn glsl() {
+    let tokens = tokens(
+        &Glsl,
+        "#version 330\n\
+         uniform vec4 color; // c\n\
+         void main() { gl_FragColor = vec4(1.0, 0.5f, 0, 1); /* c\n \
+         */ }",
+    );
+    assert_eq!(tokens[0], line(&[("#version 330", OtherKeyword)]));
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("uniform", OtherKeyword),
+            ("vec4", Typename),
+            ("color", Identifier),
+            (";", Punctuator),
+            ("// c", Comment),
+        ])
+    );
+    assert_eq!(
+        kinds(&Glsl, "void main() { x = vec4(1.0, 0.5f); }"),
+        [
+            Typename, Function, Delimiter, Delimiter, Delimiter, Identifier, Punctuator, Typename,
+            Delimiter, Number, Punctuator, Number, Delimiter, Punctuator, Delimiter
+        ]
+    );
+    assert_eq!(tokens[2].last(), Some(&("/* c".to_string(), Comment)));
+    assert_eq!(tokens[3], line(&[(" */", Comment), ("}", Delimiter)]));
+}
+
+#[test]
+fn javascript() {
+    let tokens = tokens(
+        &JavaScript,
+        "const x = `a ${b}\n\
+         c`; // c\n\
+         function f(a) { return 'it\\'s' && 0xff; }\n\
+         /* c\n\
+         */ let y = 1.5e3;",
+    );
+    // Template literals can span lines.
+    assert_eq!(tokens[0][3], ("`a ${b}".to_string(), String));
+    assert_eq!(
+        tokens[1],
+        line(&[("c`", String), (";", Punctuator), ("// c", Comment)])
+    );
+    assert_eq!(
+        tokens[2],
+        line(&[
+            ("function", OtherKeyword),
+            ("f", Function),
+            ("(", Delimiter),
+            ("a", Identifier),
+            (")", Delimiter),
+            ("{", Delimiter),
+            ("return", BranchKeyword),
+            ("'it\\'s'", String),
+            ("&", Punctuator),
+            ("&", Punctuator),
+            ("0xff", Number),
+            (";", Punctuator),
+            ("}", Delimiter),
+        ])
+    );
+    assert_eq!(tokens[3], line(&[("/* c", Comment)]));
+    assert_eq!(
+        tokens[4],
+        line(&[
+            ("*/", Comment),
+            ("let", OtherKeyword),
+            ("y", Identifier),
+            ("=", Punctuator),
+            ("1.5e3", Number),
+            (";", Punctuator),
+        ])
+    );
+}
+
+#[test]
+fn json() {
+    let tokens = tokens(
+        &Json,
+        "{\"a\": [1, -2.5e3, true, null], // c\n \
+         \"b\": \"x\\\"y\" /* c\n \
+         */ }",
+    );
+    assert_eq!(
+        tokens[0],
+        line(&[
+            ("{", Delimiter),
+            ("\"a\"", Identifier),
+            (":", Punctuator),
+            ("[", Delimiter),
+            ("1", Number),
+            (",", Punctuator),
+            ("-2.5e3", Number),
+            (",", Punctuator),
+            ("true", Constant),
+            (",", Punctuator),
+            ("null", Constant),
+            ("]", Delimiter),
+            (",", Punctuator),
+            ("// c", Comment),
+        ])
+    );
+    // Keys are told apart from values by the colon after them.
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("\"b\"", Identifier),
+            (":", Punctuator),
+            ("\"x\\\"y\"", String),
+            ("/* c", Comment),
+        ])
+    );
+    assert_eq!(tokens[2], line(&[(" */", Comment), ("}", Delimiter)]));
+}
+
+#[test]
+fn toml() {
+    let tokens = tokens(
+        &Toml,
+        "[package]\n\
+         name = \"x\" # c\n\
+         [[bin]]\n\
+         version = 1.5\n\
+         date = 1979-05-27\n\
+         multi = \"\"\"\n\
+         line\n\
+         \"\"\"\n\
+         key.sub = 'lit'\n\
+         flag = true",
+    );
+    assert_eq!(tokens[0], line(&[("[package]", Typename)]));
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("name", Identifier),
+            ("=", Punctuator),
+            ("\"x\"", String),
+            ("# c", Comment),
+        ])
+    );
+    assert_eq!(tokens[2], line(&[("[[bin]]", Typename)]));
+    assert_eq!(tokens[3][2], ("1.5".to_string(), Number));
+    assert_eq!(tokens[4][2], ("1979-05-27".to_string(), Number));
+    // Multiline strings continue until their closing quotes.
+    assert_eq!(tokens[5][2], ("\"\"\"".to_string(), String));
+    assert_eq!(tokens[6], line(&[("line", String)]));
+    assert_eq!(tokens[7], line(&[("\"\"\"", String)]));
+    assert_eq!(
+        tokens[8],
+        line(&[
+            ("key.sub", Identifier),
+            ("=", Punctuator),
+            ("'lit'", String)
+        ])
+    );
+    assert_eq!(tokens[9][2], ("true".to_string(), Constant));
+}
+
+#[test]
+fn markdown() {
+    let tokens = tokens(
+        &Markdown,
+        "# Title\n\
+         Some *em* and `code`\n\
+         - item\n\
+         ```rust\n\
+         fn x() {}\n\
+         ```\n\
+         > quote\n\
+         [link](http://x)",
+    );
+    assert_eq!(tokens[0], line(&[("# Title", Typename)]));
+    assert_eq!(
+        tokens[1],
+        line(&[
+            ("Some", Identifier),
+            ("*", Punctuator),
+            ("em", Identifier),
+            ("*", Punctuator),
+            ("and", Identifier),
+            ("`code`", String),
+        ])
+    );
+    assert_eq!(tokens[2], line(&[("-", Punctuator), ("item", Identifier)]));
+    // The lines of a fenced code block are one string, until the fence is closed.
+    assert_eq!(tokens[3], line(&[("```rust", Punctuator)]));
+    assert_eq!(tokens[4], line(&[("fn x() {}", String)]));
+    assert_eq!(tokens[5], line(&[("```", Punctuator)]));
+    assert_eq!(tokens[6], line(&[("> quote", Comment)]));
+    assert_eq!(
+        tokens[7],
+        line(&[
+            ("[", Delimiter),
+            ("link", Identifier),
+            ("]", Delimiter),
+            ("(", Delimiter),
+            ("http://x", Identifier),
+            (")", Delimiter),
+        ])
+    );
+}
+
+#[test]
+fn plain_text() {
+    assert_eq!(
+        kinds(&PlainText, "just text // 123"),
+        [Unknown, Unknown, Unknown, Unknown]
+    );
+}
+
+#[test]
+fn languages_are_picked_by_name_and_extension() {
+    assert_eq!(language_for_path("src/main.rs").name(), "rust");
+    assert_eq!(language_for_path("Cargo.TOML").name(), "toml");
+    assert_eq!(language_for_path("shader.frag").name(), "glsl");
+    assert_eq!(language_for_path("data.jsonc").name(), "json");
+    assert_eq!(language_for_path("README").name(), "plain_text");
+    assert_eq!(
+        language_for_name("javascript").unwrap().name(),
+        "javascript"
+    );
+    assert!(language_for_name("cobol").is_none());
+}
+
+#[test]
+fn changed_lines_are_tokenized_again_along_with_the_lines_after_them() {
+    let text = Text::from("let a = 1;\nlet b = 2;");
+    let mut tokens: Rope<Vec<Token>> = vec![Vec::new(); 2].into_iter().collect();
+    let mut tokenizer = Tokenizer::new(&Rust, 2);
+    tokenizer.update(&text, &mut tokens);
+    assert_eq!(
+        split(&text, &tokens)[1][0],
+        ("let".to_string(), OtherKeyword)
+    );
+
+    // Opening a block comment on the first line turns the second line into a comment too.
+    let text = Text::from("/*let a = 1;\nlet b = 2;");
+    tokenizer.apply_change(&Change::Insert(
+        Position {
+            line_index: 0,
+            byte_index: 0,
+        },
+        Text::from("/*"),
+    ));
+    tokenizer.update(&text, &mut tokens);
+    assert_eq!(
+        split(&text, &tokens),
+        [
+            line(&[("/*let a = 1;", Comment)]),
+            line(&[("let b = 2;", Comment)])
+        ]
+    );
+
+    let text = Text::from("let a = 1;\nlet b = 2;");
+    tokenizer.apply_change(&Change::Delete(
+        Position {
+            line_index: 0,
+            byte_index: 0,
+        },
+        Length {
+            line_count: 0,
+            byte_count: 2,
+        },
+    ));
+    tokenizer.update(&text, &mut tokens);
+    assert_eq!(
+        split(&text, &tokens)[1][0],
+        ("let".to_string(), OtherKeyword)
+    );
+
+    // Inserting lines shifts the state of the lines after them.
+    let text = Text::from("let a = 1;\n\"\n\"\nlet b = 2;");
+    tokenizer.apply_change(&Change::Insert(
+        Position {
+            line_index: 0,
+            byte_index: 10,
+        },
+        Text::from("\n\"\n\""),
+    ));
+    tokens.splice(1..1, [Vec::new(), Vec::new()]);
+    tokenizer.update(&text, &mut tokens);
+    assert_eq!(split(&text, &tokens)[2], line(&[("\"", String)]));
+    assert_eq!(
+        split(&text, &tokens)[3][0],
+        ("let".to_string(), OtherKeyword)
+    );
+}
@@ -0,0 +1,171 @@
+use makepad_code_editor::{
+    decoration::DecorationSet,
+    text::{Position, Text},
+    vim::{Vim, VimMode},
+    Document, Session,
+};
+
+struct Editor {
+    session: Session,
+    vim: Vim,
+}
+
+impl Editor {
+    fn new(text: &str) -> Self {
+        Self {
+            session: Session::new(Document::new(Text::from(text), DecorationSet::new())),
+            vim: Vim::new(),
+        }
+    }
+
+    // Feeds keys in Vim notation, and returns whether the text was changed.
+    fn keys(&mut self, keys: &str) -> bool {
+        let text_changed = self.vim.handle_keys(&self.session, keys);
+        self.session.handle_changes();
+        text_changed
+    }
+
+    fn text(&self) -> String {
+        self.session.document().as_text().to_string()
+    }
+
+    fn cursor(&self) -> (usize, usize) {
+        let Position {
+            line_index,
+            byte_index,
+        } = self.session.selections()[0].cursor.position;
+        (line_index, byte_index)
+    }
+
+    fn selected_text(&self) -> String {
+        let selection = self.session.selections()[0];
+        let text = self.session.document().as_text();
+        let (start, end) = (selection.start(), selection.end());
+        let lines = text.as_lines();
+        if start.line_index == end.line_index {
+            return lines[start.line_index][start.byte_index..end.byte_index].to_string();
+        }
+        let mut selected = lines[start.line_index][start.byte_index..].to_string();
+        for line_index in start.line_index + 1..end.line_index {
+            selected.push('\n');
+            selected.push_str(&lines[line_index]);
+        }
+        selected.push('\n');
+        selected.push_str(&lines[end.line_index][..end.byte_index]);
+        selected
+    }
+}
+
+#[test]
+fn insert_mode_is_entered_and_left() {
+    let mut editor = Editor::new("hello");
+    assert_eq!(editor.vim.mode(), VimMode::Normal);
+    assert!(!editor.keys("ll"));
+    assert!(editor.keys("ifoo"));
+    assert_eq!(editor.vim.mode(), VimMode::Insert);
+    assert_eq!(editor.text(), "hefoollo");
+    editor.keys("<Esc>");
+    assert_eq!(editor.vim.mode(), VimMode::Normal);
+    // Leaving insert mode moves the cursor back onto the last inserted char.
+    assert_eq!(editor.cursor(), (0, 4));
+    editor.keys("A!<Esc>");
+    assert_eq!(editor.text(), "hefoollo!");
+    editor.keys("o bar<Esc>");
+    assert_eq!(editor.text(), "hefoollo!\n bar");
+    assert_eq!(editor.vim.mode(), VimMode::Normal);
+}
+
+#[test]
+fn visual_mode_selects_and_operates() {
+    let mut editor = Editor::new("one two three");
+    editor.keys("wv");
+    assert_eq!(editor.vim.mode(), VimMode::Visual);
+    editor.keys("e");
+    assert_eq!(editor.selected_text(), "two");
+    editor.keys("<Esc>");
+    assert_eq!(editor.vim.mode(), VimMode::Normal);
+    assert_eq!(editor.cursor(), (0, 6));
+    editor.keys("bvex");
+    assert_eq!(editor.vim.mode(), VimMode::Normal);
+    assert_eq!(editor.text(), "one  three");
+}
+
+#[test]
+fn visual_line_mode_operates_on_whole_lines() {
+    let mut editor = Editor::new("a\nb\nc\nd");
+    editor.keys("jVj");
+    assert_eq!(editor.vim.mode(), VimMode::VisualLine);
+    editor.keys("d");
+    assert_eq!(editor.vim.mode(), VimMode::Normal);
+    assert_eq!(editor.text(), "a\nd");
+    assert_eq!(editor.vim.register('"').unwrap().text, "b\nc");
+    assert!(editor.vim.register('"').unwrap().is_linewise);
+}
+
+#[test]
+fn counts_repeat_motions() {
+    let mut editor = Editor::new("a b c d e\nf\ng\nh");
+    editor.keys("3w");
+    assert_eq!(editor.cursor(), (0, 6));
+    editor.keys("2j");
+    assert_eq!(editor.cursor(), (2, 0));
+    // Vertical motions keep to the column the cursor came from.
+    editor.keys("2k");
+    assert_eq!(editor.cursor(), (0, 6));
+}
+
+#[test]
+fn counts_past_the_end_stop_at_the_end() {
+    let mut editor = Editor::new("a b\nc\nd");
+    editor.keys("99999999999999999999999j");
+    assert_eq!(editor.cursor(), (2, 0));
+    editor.keys("99999999999999999999999k");
+    assert_eq!(editor.cursor(), (0, 0));
+    editor.keys("99999999999999999999999w");
+    assert_eq!(editor.cursor(), (2, 0));
+    editor.keys("99999999999999999999999$");
+    assert_eq!(editor.cursor(), (2, 0));
+}
+
+#[test]
+fn counted_undo_stops_when_the_history_runs_out() {
+    let mut editor = Editor::new("abc");
+    editor.keys("x");
+    editor.keys("x");
+    assert_eq!(editor.text(), "c");
+    assert!(editor.keys("99999999999999999999999u"));
+    assert_eq!(editor.text(), "abc");
+    assert!(!editor.keys("u"));
+    assert!(editor.keys("99999999999999999999999<C-r>"));
+    assert_eq!(editor.text(), "c");
+}
+
+#[test]
+fn operators_take_motions_and_counts() {
+    let mut editor = Editor::new("one two three four");
+    editor.keys("dw");
+    assert_eq!(editor.text(), "two three four");
+    editor.keys("2dw");
+    assert_eq!(editor.text(), "four");
+    editor.keys("u");
+    assert_eq!(editor.text(), "two three four");
+    editor.keys("cwTWO<Esc>");
+    assert_eq!(editor.text(), "TWO three four");
+    editor.keys("w.");
+    assert_eq!(editor.text(), "TWO TWO four");
+    editor.keys("yiw$p");
+    assert_eq!(editor.text(), "TWO TWO fourTWO");
+}
+
+#[test]
+fn operators_on_lines() {
+    let mut editor = Editor::new("a\nb\nc");
+    editor.keys("yyjp");
+    assert_eq!(editor.text(), "a\nb\na\nc");
+    editor.keys("2dd");
+    assert_eq!(editor.text(), "a\nb");
+    editor.keys(">>");
+    assert_eq!(editor.text(), "a\n    b");
+    editor.keys("<lt><lt>");
+    assert_eq!(editor.text(), "a\nb");
+}
@@ -409,6 +409,57 @@ impl Vec4 {
     }
     
     
+    /// Converts an sRGB color to OKLCH, with the lightness in x, the chroma in y,
+    /// the hue in degrees in z and the alpha left in w.
+    pub fn to_oklcha(&self) -> Vec4 {
+        fn linear(c: f32) -> f32 {if c <= 0.04045 {c / 12.92} else {((c + 0.055) / 1.055).powf(2.4)}}
+        let (r, g, b) = (linear(self.x), linear(self.y), linear(self.z));
+        let l = (0.41222146 * r + 0.53633255 * g + 0.051445995 * b).cbrt();
+        let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+        let s = (0.08830246 * r + 0.28171885 * g + 0.6299787 * b).cbrt();
+        let lab_l = 0.21045426 * l + 0.7936178 * m - 0.004072047 * s;
+        let lab_a = 1.9779985 * l - 2.4285922 * m + 0.4505937 * s;
+        let lab_b = 0.025904037 * l + 0.78277177 * m - 0.80867577 * s;
+        Vec4 {
+            x: lab_l,
+            y: (lab_a * lab_a + lab_b * lab_b).sqrt(),
+            z: lab_b.atan2(lab_a).to_degrees().rem_euclid(360.0),
+            w: self.w
+        }
+    }
+
+    /// The inverse of `to_oklcha`, clamping colors outside of sRGB.
+    pub fn from_oklcha(lch: Vec4) -> Vec4 {
+        fn srgb(c: f32) -> f32 {
+            let c = if c <= 0.0031308 {c * 12.92} else {1.055 * c.powf(1.0 / 2.4) - 0.055};
+            c.clamp(0.0, 1.0)
+        }
+        let hue = lch.z.to_radians();
+        let (lab_a, lab_b) = (lch.y * hue.cos(), lch.y * hue.sin());
+        let l = lch.x + 0.39633778 * lab_a + 0.21580376 * lab_b;
+        let m = lch.x - 0.105561346 * lab_a - 0.06385417 * lab_b;
+        let s = lch.x - 0.08948418 * lab_a - 1.2914855 * lab_b;
+        let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+        Vec4 {
+            x: srgb(4.0767417 * l - 3.3077116 * m + 0.23096994 * s),
+            y: srgb(-1.268438 * l + 2.6097574 * m - 0.34131938 * s),
+            z: srgb(-0.0041960863 * l - 0.7034186 * m + 1.7076147 * s),
+            w: lch.w
+        }
+    }
+
+    /// The relative luminance of an sRGB color as defined by WCAG.
+    pub fn luminance(&self) -> f32 {
+        fn linear(c: f32) -> f32 {if c <= 0.04045 {c / 12.92} else {((c + 0.055) / 1.055).powf(2.4)}}
+        0.2126 * linear(self.x) + 0.7152 * linear(self.y) + 0.0722 * linear(self.z)
+    }
+
+    /// The WCAG contrast ratio between two colors, from 1 to 21.
+    pub fn contrast_ratio(&self, other: &Vec4) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
     pub fn from_u32(val: u32) -> Vec4 {
         Vec4 {
             x: ((val >> 24) & 0xff) as f32 / 255.0,
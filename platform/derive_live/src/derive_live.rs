@@ -438,9 +438,32 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
         
         tb.add("    fn live_design_with(cx: &mut Cx) {");
         tb.add("<Self as LiveRegister>::live_register(cx);");
+        // what the schema export knows about the type
+        tb.add("cx.live_registry.borrow_mut().type_docs.insert(LiveType::of::<Self>(), || LiveTypeDoc{");
+        tb.add("rust_name:").string(&struct_name).add(", module_path: module_path!(),");
+        gen_doc(tb, &main_attribs);
+        tb.add("fields: vec![");
+        for field in &fields {
+            if let Some(attr) = field.attrs.iter().find( | a | matches!(a.name.as_str(), "live" | "calc" | "animator" | "deref" | "walk" | "layout")) {
+                tb.add("LiveFieldDoc{name:").string(&field.name);
+                tb.add(", rust_type:").string(&type_to_string(&field.ty));
+                tb.add(", attribute:").string(&attr.name).add(",");
+                match &attr.args {
+                    Some(args) if attr.name == "live" || attr.name == "calc" => {
+                        tb.add("default: Some(").string(&args.to_string()).add("),");
+                    }
+                    _ => {
+                        tb.add("default: None,");
+                    }
+                }
+                gen_doc(tb, &field.attrs);
+                tb.add("},");
+            }
+        }
+        tb.add("], variants: Vec::new()});");
         // we need this here for shader enums to register without hassle
         for field in &fields {
-            if  field.attrs.iter().any( | a | a.name == "live" || a.name == "calc" ||a.name == "deref"){
+            if  field.attrs.iter().any( | a | a.name == "live" || a.name == "calc" ||a.name == "deref" || a.name == "walk" || a.name == "layout"){
                 match unwrap_option(field.ty.clone()) {
                     Ok(inside) => {
                         tb.add("<").stream(Some(inside)).add("as LiveNew>::live_design_with(cx);");
@@ -586,7 +609,30 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
         tb.add("    }");
         
         tb.add("    fn live_design_with(cx: &mut Cx) {");
-        
+        tb.add("cx.live_registry.borrow_mut().type_docs.insert(LiveType::of::<Self>(), || LiveTypeDoc{");
+        tb.add("rust_name:").string(&enum_name).add(", module_path: module_path!(),");
+        gen_doc(tb, &main_attribs);
+        tb.add("fields: Vec::new(), variants: vec![");
+        for (index, item) in items.iter().enumerate() {
+            tb.add("LiveVariantDoc{name:").string(&item.name).add(", kind:");
+            tb.string(match item.kind {
+                EnumKind::Bare => "bare",
+                EnumKind::Named(_) => "named",
+                EnumKind::Tuple(_) => "tuple"
+            }).add(",");
+            match item.attributes.iter().find( | a | a.name != "doc").and_then( | a | a.args.as_ref()) {
+                Some(args) if !matches!(item.kind, EnumKind::Bare) => {
+                    tb.add("default: Some(").string(&args.to_string()).add("),");
+                }
+                _ => {
+                    tb.add("default: None,");
+                }
+            }
+            tb.add("pick:").ident(if pick == Some(index) {"true"} else {"false"}).add(",");
+            gen_doc(tb, &item.attributes);
+            tb.add("},");
+        }
+        tb.add("]});");
         
         let is_u32_enum = main_attribs.iter().any( | attr | attr.name == "repr" && attr.args.as_ref().unwrap().to_string().to_lowercase() == "u32");
         if is_u32_enum {
@@ -741,3 +787,32 @@ fn derive_live_impl_inner(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Re
     
 }
 
+/// Writes the `doc:` field of a `LiveTypeDoc`, `LiveFieldDoc` or
+/// `LiveVariantDoc` from the doc comments in `attrs`.
+fn gen_doc(tb: &mut TokenBuilder, attrs: &[Attribute]) {
+    tb.add("doc: vec![");
+    for attr in attrs {
+        if attr.name == "doc" {
+            tb.stream(attr.args.clone()).add(",");
+        }
+    }
+    tb.add("],");
+}
+
+/// A Rust type as it would be written, rather than with a space between
+/// every token.
+fn type_to_string(ty: &TokenStream) -> String {
+    let text = ty.to_string();
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if *c == ' ' {
+            let word = | c: Option<&char> | c.is_some_and( | c | c.is_alphanumeric() || *c == '_');
+            if !(word(chars.get(i.wrapping_sub(1))) && word(chars.get(i + 1))) {
+                continue;
+            }
+        }
+        out.push(*c);
+    }
+    out.replace(',', ", ")
+}
@@ -24,6 +24,9 @@ pub fn derive_live_component_registry_impl(input: TokenStream) -> TokenStream {
             tb.add("    fn get_module_set(&self, set: &mut std::collections::BTreeSet<LiveModuleId>){");
             tb.add("        self.map.values().for_each( | (info, _) | {set.insert(info.module_id);});");
             tb.add("    }");
+            tb.add("    fn component_infos(&self) -> Vec<(LiveType, LiveComponentInfo)> {");
+            tb.add("        self.map.iter().map( | (ty, (info, _)) | (*ty, info.clone())).collect()");
+            tb.add("    }");
             tb.add("    fn get_component_info(&self, name: LiveId) -> Option<LiveComponentInfo> {");
             tb.add("        self.map.values().find( | (info, _) | info.name == name).map( | (info, _) | info.clone())");
             tb.add("    }");
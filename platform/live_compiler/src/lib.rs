@@ -14,8 +14,10 @@ pub mod live_ptr;
 pub mod live_eval;
 pub mod live_component;
 pub mod live_node_cbor;
+pub mod live_node_printer;
 //pub mod live_node_cbor;
 pub mod live_node_reader;
+pub mod live_schema;
 
 pub use makepad_math;
 pub use makepad_derive_live;
@@ -65,9 +67,18 @@ pub use {
             LiveNodeSliceToMsgPack,
             L*iveNodeVecFromMsgPack
         },*/
+        live_node_printer::{
+            LiveNodeSliceToLiveDesign,
+        },
         live_node_reader::{
             LiveNodeReader,
         },
+        live_schema::{
+            LiveTypeDoc,
+            LiveFieldDoc,
+            LiveVariantDoc,
+            live_schema_diff,
+        },
         live_node::{
             LiveImport,
             LiveProp,
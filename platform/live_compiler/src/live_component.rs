@@ -28,6 +28,7 @@ pub trait LiveComponentRegistry {
     fn get_component_info(&self, name: LiveId) -> Option<LiveComponentInfo>;
     fn component_type(&self) -> LiveId;
     fn get_module_set(&self, set: &mut BTreeSet<LiveModuleId>);
+    fn component_infos(&self) -> Vec<(LiveType, LiveComponentInfo)>;
 }
 
 #[derive(Default, Clone)]
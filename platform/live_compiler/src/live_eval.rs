@@ -1,6 +1,7 @@
 pub use {
     std::{
         rc::Rc,
+        sync::Arc,
         any::TypeId,
     },
     crate::{
@@ -43,6 +44,10 @@ impl LiveError {
         Self::eval_error(origin, index, nodes, format!("Expression call not implemented ident:{} with number of args: {}", ident, args))
     }
     
+    fn eval_error_function(origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], ident: LiveId, message: String)->Self {
+        Self::eval_error(origin, index, nodes, format!("{}(): {}", ident, message))
+    }
+    
     fn eval_error_cant_find_target(origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], id: LiveId)->Self {
         Self::eval_error(origin, index, nodes, format!("cant find target: {}", id))
     }
@@ -135,8 +140,34 @@ pub fn live_eval_value(live_registry: &LiveRegistry, index: &mut usize, nodes: &
             }
         }
         LiveValue::ExprCall {ident, args} => {
+            let call_index = *index;
             *index += 1;
             match ident {
+                // `c ? a : b`, only the branch taken is evaluated
+                live_id!(cond) if *args == 3 => {
+                    let c = live_eval_value(live_registry, index, nodes, scope_nodes)?;
+                    let LiveValue::Bool(c) = c else {
+                        return Err(LiveError::eval_error(live_error_origin!(), call_index, nodes, format!("?: expected a bool condition, got {:?}", c)))
+                    };
+                    if !c {
+                        *index = skip_expr(*index, nodes);
+                    }
+                    let v = live_eval_value(live_registry, index, nodes, scope_nodes)?;
+                    if c {
+                        *index = skip_expr(*index, nodes);
+                    }
+                    return Ok(v)
+                }
+                live_id!(min) | live_id!(max) | live_id!(clamp) | live_id!(round) | live_id!(floor) | live_id!(ceil) | live_id!(abs) |
+                live_id!(lighten) | live_id!(darken) | live_id!(saturate) | live_id!(desaturate) | live_id!(alpha) |
+                live_id!(contrast) | live_id!(contrast_pick) | live_id!(format) => {
+                    let mut values = Vec::with_capacity(*args);
+                    for _ in 0..*args {
+                        values.push(live_eval_value(live_registry, index, nodes, scope_nodes)?);
+                    }
+                    return live_eval_function(*ident, &values)
+                        .map_err( | message | LiveError::eval_error_function(live_error_origin!(), call_index, nodes, *ident, message))
+                }
                 live_id!(pow) if *args == 2 => {
                     let a = live_eval_value(live_registry, index, nodes, scope_nodes)?;
                     let b = live_eval_value(live_registry, index, nodes, scope_nodes)?;
@@ -223,6 +254,12 @@ pub fn live_eval_value(live_registry: &LiveRegistry, index: &mut usize, nodes: &
             let a = live_eval_value(live_registry, index, nodes, scope_nodes)?;
             let b = live_eval_value(live_registry, index, nodes, scope_nodes)?;
             
+            // strings concatenate with anything
+            if *op == LiveBinOp::Add && (a.is_str() || b.is_str()) {
+                if let (Some(va), Some(vb)) = (value_to_text(&a), value_to_text(&b)) {
+                    return Ok(LiveValue::String(Arc::new(va + &vb)))
+                }
+            }
             match op {
                 LiveBinOp::Or => match a {
                     LiveValue::Bool(va) => match b {
@@ -502,3 +539,225 @@ pub fn live_eval_value(live_registry: &LiveRegistry, index: &mut usize, nodes: &
         }
     })
 }
+
+/// The functions of the expression language that take their arguments by
+/// value. Errors are the message without the function name.
+fn live_eval_function(ident: LiveId, args: &[LiveValue]) -> Result<LiveValue, String> {
+    fn arity(args: &[LiveValue], count: usize) -> Result<(), String> {
+        if args.len() != count {
+            return Err(format!("expected {} arguments, got {}", count, args.len()))
+        }
+        Ok(())
+    }
+    fn number(args: &[LiveValue], i: usize) -> Result<f64, String> {
+        args[i].as_float().ok_or_else( || format!("expected a number as argument {}, got {:?}", i + 1, args[i]))
+    }
+    fn color(args: &[LiveValue], i: usize) -> Result<Vec4, String> {
+        args[i].as_vec4().ok_or_else( || format!("expected a color as argument {}, got {:?}", i + 1, args[i]))
+    }
+    // integers stay integers as long as all the arguments are
+    fn numeric(args: &[LiveValue], v: f64) -> LiveValue {
+        if args.iter().all( | a | matches!(a, LiveValue::Int64(_))) {
+            LiveValue::Int64(v as i64)
+        }
+        else {
+            LiveValue::Float64(v)
+        }
+    }
+    
+    Ok(match ident {
+        live_id!(min) | live_id!(max) => {
+            arity(args, 2)?;
+            let (a, b) = (number(args, 0)?, number(args, 1)?);
+            numeric(args, if ident == live_id!(min) {a.min(b)} else {a.max(b)})
+        }
+        live_id!(clamp) => {
+            arity(args, 3)?;
+            let (v, lo, hi) = (number(args, 0)?, number(args, 1)?, number(args, 2)?);
+            if lo > hi {
+                return Err(format!("lower bound {} is above upper bound {}", lo, hi))
+            }
+            numeric(args, v.clamp(lo, hi))
+        }
+        live_id!(round) => {
+            if args.len() == 2 {
+                let (v, digits) = (number(args, 0)?, number(args, 1)?);
+                let scale = 10f64.powi(digits as i32);
+                LiveValue::Float64((v * scale).round() / scale)
+            }
+            else {
+                arity(args, 1)?;
+                numeric(args, number(args, 0)?.round())
+            }
+        }
+        live_id!(floor) | live_id!(ceil) | live_id!(abs) => {
+            arity(args, 1)?;
+            let v = number(args, 0)?;
+            numeric(args, match ident {
+                live_id!(floor) => v.floor(),
+                live_id!(ceil) => v.ceil(),
+                _ => v.abs()
+            })
+        }
+        // lightness and chroma are changed in OKLCH, so equal steps look equal
+        live_id!(lighten) | live_id!(darken) | live_id!(saturate) | live_id!(desaturate) => {
+            arity(args, 2)?;
+            let (c, amount) = (color(args, 0)?, number(args, 1)? as f32);
+            let mut lch = c.to_oklcha();
+            match ident {
+                live_id!(lighten) => lch.x = (lch.x + amount).clamp(0.0, 1.0),
+                live_id!(darken) => lch.x = (lch.x - amount).clamp(0.0, 1.0),
+                live_id!(saturate) => lch.y = (lch.y * (1.0 + amount)).max(0.0),
+                _ => lch.y = (lch.y * (1.0 - amount)).max(0.0),
+            }
+            LiveValue::Vec4(Vec4::from_oklcha(lch))
+        }
+        live_id!(alpha) => {
+            arity(args, 2)?;
+            let (mut c, a) = (color(args, 0)?, number(args, 1)?);
+            c.w = a as f32;
+            LiveValue::Vec4(c)
+        }
+        live_id!(contrast) => {
+            arity(args, 2)?;
+            LiveValue::Float64(color(args, 0)?.contrast_ratio(&color(args, 1)?) as f64)
+        }
+        // whichever of the two colors reads best on the first
+        live_id!(contrast_pick) => {
+            arity(args, 3)?;
+            let (bg, a, b) = (color(args, 0)?, color(args, 1)?, color(args, 2)?);
+            LiveValue::Vec4(if bg.contrast_ratio(&b) > bg.contrast_ratio(&a) {b} else {a})
+        }
+        // `format("{} of {}", a, b)`, with `{{` and `}}` for braces
+        live_id!(format) => {
+            let Some(template) = args.first().filter( | t | t.is_str()).and_then(value_to_text) else {
+                return Err(format!("expected a string as argument 1, got {:?}", args.first().unwrap_or(&LiveValue::None)))
+            };
+            let mut out = String::new();
+            let mut next = 1;
+            let mut chars = template.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '{' if chars.peek() == Some(&'{') => {chars.next(); out.push('{')},
+                    '}' if chars.peek() == Some(&'}') => {chars.next(); out.push('}')},
+                    '{' => {
+                        if chars.next() != Some('}') {
+                            return Err("only {} placeholders are supported".to_string())
+                        }
+                        let Some(arg) = args.get(next) else {
+                            return Err(format!("the template has more placeholders than the {} arguments given", args.len() - 1))
+                        };
+                        let Some(text) = value_to_text(arg) else {
+                            return Err(format!("cannot format argument {}: {:?}", next + 1, arg))
+                        };
+                        out.push_str(&text);
+                        next += 1;
+                    }
+                    c => out.push(c)
+                }
+            }
+            if next != args.len() {
+                return Err(format!("the template has {} placeholders but {} arguments were given", next - 1, args.len() - 1))
+            }
+            LiveValue::String(Arc::new(out))
+        }
+        _ => return Err("unknown function".to_string())
+    })
+}
+
+/// The text a value is formatted or concatenated as.
+fn value_to_text(value: &LiveValue) -> Option<String> {
+    Some(match value {
+        LiveValue::Str(v) => v.to_string(),
+        LiveValue::String(v) => v.to_string(),
+        LiveValue::InlineString(v) => v.as_str().to_string(),
+        LiveValue::Bool(v) => v.to_string(),
+        LiveValue::Int64(v) => v.to_string(),
+        LiveValue::Uint64(v) => v.to_string(),
+        LiveValue::Float32(v) => v.to_string(),
+        LiveValue::Float64(v) => v.to_string(),
+        LiveValue::Id(v) => v.to_string(),
+        LiveValue::Color(_) | LiveValue::Vec4(_) => format!("#{:08x}", value.as_vec4()?.to_u32()),
+        _ => return None
+    })
+}
+
+/// The index after the expression starting at `index`, without evaluating it.
+fn skip_expr(index: usize, nodes: &[LiveNode]) -> usize {
+    match &nodes[index].value {
+        LiveValue::ExprBinOp(_) => skip_expr(skip_expr(index + 1, nodes), nodes),
+        LiveValue::ExprUnOp(_) | LiveValue::ExprMember(_) => skip_expr(index + 1, nodes),
+        LiveValue::ExprCall {args, ..} => (0..*args).fold(index + 1, | index, _ | skip_expr(index, nodes)),
+        LiveValue::Expr => nodes.skip_node(index),
+        _ if nodes[index].value.is_open() => nodes.skip_node(index),
+        _ => index + 1
+    }
+}
+
+/// Evaluates the condition of an `if` block in the live DSL. Ids are looked up
+/// in `LiveRegistry::conditions`, and ids that aren't there stand for
+/// themselves, so `os == android` compares two ids. Numbers compare as floats.
+pub fn live_eval_condition(live_registry: &LiveRegistry, index: &mut usize, nodes: &[LiveNode]) -> Result<LiveValue, LiveError> {
+    let v = &nodes[*index].value;
+    Ok(match v {
+        LiveValue::Bool(_) |
+        LiveValue::Int64(_) |
+        LiveValue::Float64(_) |
+        LiveValue::Color(_) |
+        LiveValue::String(_) => {
+            *index += 1;
+            v.clone()
+        }
+        LiveValue::Expr => {
+            *index += 1;
+            return live_eval_condition(live_registry, index, nodes)
+        }
+        LiveValue::Id(id) => {
+            *index += 1;
+            live_registry.conditions.get(id).cloned().unwrap_or(LiveValue::Id(*id))
+        }
+        LiveValue::ExprUnOp(op) => {
+            *index += 1;
+            let a = live_eval_condition(live_registry, index, nodes)?;
+            match (op, &a) {
+                (LiveUnOp::Not, LiveValue::Bool(va)) => LiveValue::Bool(!va),
+                (LiveUnOp::Neg, _) if a.is_number_type() => LiveValue::Float64(-a.as_float().unwrap()),
+                _ => return Err(LiveError::eval_error_unop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a))
+            }
+        }
+        LiveValue::ExprBinOp(op) => {
+            *index += 1;
+            let a = live_eval_condition(live_registry, index, nodes)?;
+            let b = live_eval_condition(live_registry, index, nodes)?;
+            let numbers = a.as_float().zip(b.as_float());
+            match op {
+                LiveBinOp::Or | LiveBinOp::And => match (&a, &b) {
+                    (LiveValue::Bool(va), LiveValue::Bool(vb)) => LiveValue::Bool(if *op == LiveBinOp::Or {*va || *vb} else {*va && *vb}),
+                    _ => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
+                },
+                LiveBinOp::Eq | LiveBinOp::Ne => {
+                    let eq = if let Some((va, vb)) = numbers {va == vb} else {a == b};
+                    LiveValue::Bool(eq == (*op == LiveBinOp::Eq))
+                }
+                _ => {
+                    let Some((va, vb)) = numbers else {
+                        return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
+                    };
+                    match op {
+                        LiveBinOp::Lt => LiveValue::Bool(va < vb),
+                        LiveBinOp::Le => LiveValue::Bool(va <= vb),
+                        LiveBinOp::Gt => LiveValue::Bool(va > vb),
+                        LiveBinOp::Ge => LiveValue::Bool(va >= vb),
+                        LiveBinOp::Add => LiveValue::Float64(va + vb),
+                        LiveBinOp::Sub => LiveValue::Float64(va - vb),
+                        LiveBinOp::Mul => LiveValue::Float64(va * vb),
+                        _ => LiveValue::Float64(va / vb),
+                    }
+                }
+            }
+        }
+        _ => {
+            return Err(LiveError::eval_error_wrong_value_in_expression(live_error_origin!(), *index, nodes, "condition"))
+        }
+    })
+}
@@ -7,7 +7,7 @@ use {
         makepad_live_tokenizer::{live_error_origin, LiveErrorOrigin},
         live_ptr::{LiveFileId, LivePtr, LiveFileGeneration},
         live_error::{LiveError},
-        live_eval::live_eval_value,
+        live_eval::{live_eval_value, live_eval_condition},
         live_document::{LiveOriginal, LiveExpanded},
         live_node::{LiveValue, LiveNode, LiveFieldKind, LivePropType},
         live_node_vec::{LiveNodeSliceApi, LiveNodeVecApi},
@@ -54,6 +54,8 @@ impl<'a> LiveExpander<'a> {
             value: LiveValue::Root {id_resolve: Box::default()}
         });
         let mut current_parent = vec![(LiveId(0), 0usize)];
+        // the closes of the condition blocks we are in, which don't close a parent
+        let mut condition_closes = Vec::new();
         let mut in_index = 1;
         let mut lazy_define_value = None;
         loop {
@@ -74,10 +76,45 @@ impl<'a> LiveExpander<'a> {
             match in_value {
                 
                 LiveValue::Close => {
+                    if condition_closes.last() == Some(&in_index) {
+                        condition_closes.pop();
+                        in_index += 1;
+                        // the rest of the else chain doesn't apply
+                        while let LiveValue::Condition {is_else: true} = in_doc.nodes[in_index].value {
+                            in_index = in_doc.nodes.skip_node(in_index);
+                        }
+                        continue;
+                    }
                     current_parent.pop();
                     in_index += 1;
                     continue;
                 }
+                LiveValue::Condition {..} => {
+                    let mut index = in_index + 1;
+                    let holds = match live_eval_condition(self.live_registry, &mut index, &in_doc.nodes) {
+                        Ok(LiveValue::Bool(holds)) => holds,
+                        Ok(value) => {
+                            self.errors.push(LiveError {
+                                origin: live_error_origin!(),
+                                span: in_doc.token_id_to_span(in_node.origin.token_id().unwrap()).into(),
+                                message: format!("Condition should be a bool, got {:?}", value)
+                            });
+                            false
+                        }
+                        Err(err) => {
+                            self.errors.push(err);
+                            false
+                        }
+                    };
+                    if holds { // the body goes into the current parent
+                        condition_closes.push(in_doc.nodes.skip_node(in_index) - 1);
+                        in_index = in_doc.nodes.skip_node(in_index + 1);
+                    }
+                    else {
+                        in_index = in_doc.nodes.skip_node(in_index);
+                    }
+                    continue;
+                }
                 LiveValue::Import(live_import) => {
                     // lets verify it points anywhere
                     let mut found = false;
@@ -112,6 +112,9 @@ pub enum LiveValue {
         expand_index: Option<u32>
     },
     Import (Box<LiveImport>),
+    // `if cond {..}` or `else if cond {..}` in a class body, the condition is
+    // the first child and the expander replaces the block with its body
+    Condition {is_else: bool},
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -513,6 +516,7 @@ impl LiveValue {
             Self::Clone {..} | // subnodes including this one
             Self::Class {..} | 
             Self::Deref {..} | 
+            Self::Condition {..} |
             Self::Root {..} => true, // subnodes including this one
             _ => false
         }
@@ -618,6 +622,12 @@ impl LiveValue {
         }
     }
     
+    pub fn is_str(&self) -> bool {
+        matches!(self, Self::Str(_) |
+            Self::String(_) |
+            Self::InlineString(_))
+    }
+    
     pub fn is_number_type(&self) -> bool {
         matches!(self, Self::Int64(_) |
             Self::Float32(_) |
@@ -746,6 +756,7 @@ impl LiveValue {
             
             Self::DSL {..} => 31,
             Self::Import {..} => 32,
+            Self::Condition {..} => 33,
             //Self::Registry {..} => 30,
         }
     }
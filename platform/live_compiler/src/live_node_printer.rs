@@ -0,0 +1,454 @@
+use {
+    crate::{
+        makepad_live_id::*,
+        live_node::*,
+        live_registry::LiveRegistry,
+        live_document::LiveOriginal,
+        live_token::{LiveToken, TokenWithSpan},
+    }
+};
+
+/// Prints nodes back to `live_design!` source, the inverse of the parser.
+/// Parsing the output gives the same nodes, origins aside. Class names,
+/// shader functions, design info and property prefixes are looked up in the
+/// registry the nodes came from.
+pub trait LiveNodeSliceToLiveDesign {
+    /// Prints the node at `index` as a property, or the whole document if
+    /// `index` is its root.
+    fn to_live_design(&self, index: usize, live_registry: &LiveRegistry) -> Result<String, String>;
+}
+
+const INDENT: &str = "    ";
+
+impl<T> LiveNodeSliceToLiveDesign for T where T: AsRef<[LiveNode]> {
+    fn to_live_design(&self, index: usize, live_registry: &LiveRegistry) -> Result<String, String> {
+        let nodes = self.as_ref();
+        if index >= nodes.len() {
+            return Err(format!("Node index {} out of range", index))
+        }
+        let mut printer = LivePrinter {nodes, live_registry, out: String::new()};
+        match &nodes[index].value {
+            LiveValue::Root {..} | LiveValue::Object if index == 0 => {
+                printer.items(1, 0) ?;
+            }
+            _ => {
+                printer.item(index, 0) ?;
+            }
+        }
+        Ok(printer.out)
+    }
+}
+
+struct LivePrinter<'a> {
+    nodes: &'a [LiveNode],
+    live_registry: &'a LiveRegistry,
+    out: String,
+}
+
+impl<'a> LivePrinter<'a> {
+    fn indent(&mut self, indent: usize) {
+        for _ in 0..indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    fn original(&self, node: &LiveNode) -> Option<&'a LiveOriginal> {
+        let file_id = node.origin.token_id()?.file_id()?;
+        self.live_registry.live_files.get(file_id.to_index()).map( | file | &file.original)
+    }
+
+    /// Prints the children of an open node one per line, up to and past its close.
+    fn items(&mut self, mut index: usize, indent: usize) -> Result<usize, String> {
+        while index < self.nodes.len() {
+            if self.nodes[index].value.is_close() {
+                return Ok(index + 1)
+            }
+            index = self.item(index, indent) ?;
+        }
+        Ok(index)
+    }
+
+    /// Prints a `{..}` body whose first child is at `index`.
+    fn block(&mut self, index: usize, indent: usize) -> Result<usize, String> {
+        if self.nodes.get(index).is_some_and( | node | node.value.is_close()) {
+            self.out.push_str("{}");
+            return Ok(index + 1)
+        }
+        self.out.push_str("{\n");
+        let index = self.items(index, indent + 1) ?;
+        self.indent(indent);
+        self.out.push('}');
+        Ok(index)
+    }
+
+    fn item(&mut self, index: usize, indent: usize) -> Result<usize, String> {
+        let node = &self.nodes[index];
+        self.indent(indent);
+        let next = match &node.value {
+            LiveValue::Condition {is_else} => {
+                let (expr, _, next) = self.expr(index + 1) ?;
+                let is_plain_else = *is_else && matches!(self.nodes[index + 1..next], [
+                    LiveNode {value: LiveValue::Expr, ..},
+                    LiveNode {value: LiveValue::Bool(true), ..},
+                    LiveNode {value: LiveValue::Close, ..}
+                ]);
+                if is_plain_else {
+                    self.out.push_str("else ");
+                }
+                else {
+                    self.out.push_str(if *is_else {"else if "} else {"if "});
+                    self.out.push_str(&expr);
+                    self.out.push(' ');
+                }
+                self.block(next, indent) ?
+            }
+            LiveValue::Import(import) => {
+                self.out.push_str(&format!("import {}::", import.module_id));
+                if import.import_id.is_empty() {
+                    self.out.push('*');
+                }
+                else {
+                    self.out.push_str(&ident(import.import_id) ?);
+                    if node.id != import.import_id {
+                        self.out.push_str(&format!(" as {}", ident(node.id) ?));
+                    }
+                }
+                index + 1
+            }
+            LiveValue::DSL {token_start, token_count, ..} => {
+                let original = self.original(node).ok_or_else( || format!("No source for the tokens of {}", node.id)) ?;
+                let start = *token_start as usize;
+                let end = start + *token_count as usize;
+                let tokens = original.tokens.get(start..end).ok_or_else( || format!("Tokens of {} out of range", node.id)) ?;
+                self.dsl(tokens, indent) ?;
+                index + 1
+            }
+            LiveValue::Clone {..} if node.id.is_unique() && node.is_instance_prop() => {
+                self.value(index, indent) ?
+            }
+            _ => {
+                if let Some(prefix) = self.live_registry.get_node_prefix(node.origin) {
+                    self.out.push_str(&ident(prefix) ?);
+                    self.out.push(' ');
+                }
+                self.out.push_str(&ident(node.id) ?);
+                self.edit_info(node) ?;
+                self.out.push_str(if node.is_instance_prop() {" = "} else {": "});
+                self.value(index, indent) ?
+            }
+        };
+        self.out.push('\n');
+        Ok(next)
+    }
+
+    fn edit_info(&mut self, node: &LiveNode) -> Result<(), String> {
+        let Some(edit_info) = node.origin.edit_info() else {
+            return Ok(())
+        };
+        let Some(original) = self.original(node) else {
+            return Ok(())
+        };
+        let mut index = edit_info.edit_info_index() + 1;
+        let mut fields = Vec::new();
+        while let Some(node) = original.edit_info.get(index) {
+            if node.value.is_close() {
+                break
+            }
+            fields.push(format!("{}: {}", ident(node.id) ?, literal(&node.value) ?));
+            index += 1;
+        }
+        self.out.push_str(&format!(".{{{}}}", fields.join(", ")));
+        Ok(())
+    }
+
+    /// Prints the value of the node at `index` and returns the index past it.
+    fn value(&mut self, index: usize, indent: usize) -> Result<usize, String> {
+        let node = &self.nodes[index];
+        match &node.value {
+            LiveValue::Expr => {
+                let (expr, _, next) = self.expr(index) ?;
+                self.out.push_str(&format!("({})", expr));
+                Ok(next)
+            }
+            LiveValue::Array => {
+                let mut inline = Vec::new();
+                let mut child = index + 1;
+                while let Some(node) = self.nodes.get(child) {
+                    if node.value.is_close() || node.value.is_open() {
+                        break
+                    }
+                    inline.push(literal(&node.value) ?);
+                    child += 1;
+                }
+                if self.nodes.get(child).is_some_and( | node | node.value.is_close()) {
+                    self.out.push_str(&format!("[{}]", inline.join(", ")));
+                    return Ok(child + 1)
+                }
+                self.out.push_str("[\n");
+                let mut child = index + 1;
+                while child < self.nodes.len() && !self.nodes[child].value.is_close() {
+                    self.indent(indent + 1);
+                    child = self.value(child, indent + 1) ?;
+                    self.out.push_str(",\n");
+                }
+                self.indent(indent);
+                self.out.push(']');
+                Ok(child + 1)
+            }
+            LiveValue::TupleEnum(variant) => {
+                self.out.push_str(&ident(*variant) ?);
+                self.out.push('(');
+                let mut child = index + 1;
+                while child < self.nodes.len() && !self.nodes[child].value.is_close() {
+                    if child != index + 1 {
+                        self.out.push_str(", ");
+                    }
+                    child = self.value(child, indent) ?;
+                }
+                self.out.push(')');
+                Ok(child + 1)
+            }
+            LiveValue::NamedEnum(variant) => {
+                self.out.push_str(&ident(*variant) ?);
+                self.out.push(' ');
+                self.block(index + 1, indent)
+            }
+            LiveValue::Object | LiveValue::Root {..} => {
+                self.block(index + 1, indent)
+            }
+            LiveValue::Clone {clone: live_id!(struct), ..} => {
+                self.out.push_str("struct ");
+                self.block(index + 1, indent)
+            }
+            LiveValue::Clone {clone, design_info} => {
+                self.out.push_str(&format!("<{}{}> ", ident(*clone) ?, self.design_info(node, *design_info)));
+                self.block(index + 1, indent)
+            }
+            LiveValue::Deref {live_type, clone, ..} => {
+                self.out.push_str(&format!("{{{{{}}}}}<{}> ", self.type_name(*live_type) ?, ident(*clone) ?));
+                self.block(index + 1, indent)
+            }
+            LiveValue::Class {live_type, ..} => {
+                self.out.push_str(&format!("{{{{{}}}}} ", self.type_name(*live_type) ?));
+                self.block(index + 1, indent)
+            }
+            value => {
+                self.out.push_str(&literal(value) ?);
+                Ok(index + 1)
+            }
+        }
+    }
+
+    fn type_name(&self, live_type: LiveType) -> Result<String, String> {
+        match self.live_registry.live_type_infos.get(&live_type) {
+            Some(info) => ident(info.type_name),
+            None => Err(format!("Type {:?} isn't registered", live_type))
+        }
+    }
+
+    fn design_info(&self, node: &LiveNode, design_info: LiveDesignInfoIndex) -> String {
+        if design_info.is_invalid() {
+            return String::new()
+        }
+        match self.original(node).and_then( | original | original.design_info.get(design_info.index())) {
+            Some(info) => format!(" {}", info.to_string()),
+            None => String::new()
+        }
+    }
+
+    /// Prints the expression starting at `index` without its outer parens,
+    /// and returns it with its precedence and the index past it.
+    fn expr(&self, index: usize) -> Result<(String, u32, usize), String> {
+        let Some(node) = self.nodes.get(index) else {
+            return Err("Expression runs past the end of the nodes".to_string())
+        };
+        if let LiveValue::Expr = node.value {
+            let (expr, prec, next) = self.expr(index + 1) ?;
+            if !self.nodes.get(next).is_some_and( | node | node.value.is_close()) {
+                return Err(format!("Expected the end of expression {}", expr))
+            }
+            return Ok((expr, prec, next + 1))
+        }
+        Ok(match &node.value {
+            LiveValue::ExprBinOp(op) => {
+                let (op, prec) = match op {
+                    LiveBinOp::Or => ("||", 1),
+                    LiveBinOp::And => ("&&", 2),
+                    LiveBinOp::Eq => ("==", 3),
+                    LiveBinOp::Ne => ("!=", 3),
+                    LiveBinOp::Lt => ("<", 4),
+                    LiveBinOp::Le => ("<=", 4),
+                    LiveBinOp::Gt => (">", 4),
+                    LiveBinOp::Ge => (">=", 4),
+                    LiveBinOp::Add => ("+", 5),
+                    LiveBinOp::Sub => ("-", 5),
+                    LiveBinOp::Mul => ("*", 6),
+                    LiveBinOp::Div => ("/", 6),
+                };
+                // the operators are left associative
+                let (left, left_prec, next) = self.expr(index + 1) ?;
+                let (right, right_prec, next) = self.expr(next) ?;
+                (format!("{} {} {}", paren(left, left_prec < prec), op, paren(right, right_prec <= prec)), prec, next)
+            }
+            LiveValue::ExprUnOp(op) => {
+                let (expr, expr_prec, next) = self.expr(index + 1) ?;
+                let op = match op {LiveUnOp::Not => "!", LiveUnOp::Neg => "-"};
+                (format!("{}{}", op, paren(expr, expr_prec < 7)), 7, next)
+            }
+            LiveValue::ExprMember(member) => {
+                let (expr, expr_prec, next) = self.expr(index + 1) ?;
+                (format!("{}.{}", paren(expr, expr_prec < 8), ident(*member) ?), 8, next)
+            }
+            LiveValue::ExprCall {ident: live_id!(cond), args: 3} => {
+                let (cond, cond_prec, next) = self.expr(index + 1) ?;
+                let (if_true, _, next) = self.expr(next) ?;
+                let (if_false, _, next) = self.expr(next) ?;
+                (format!("{} ? {} : {}", paren(cond, cond_prec < 1), if_true, if_false), 0, next)
+            }
+            LiveValue::ExprCall {ident: name, args} => {
+                let mut next = index + 1;
+                let mut arg_exprs = Vec::new();
+                for _ in 0..*args {
+                    let (arg, _, arg_next) = self.expr(next) ?;
+                    arg_exprs.push(arg);
+                    next = arg_next;
+                }
+                (format!("{}({})", ident(*name) ?, arg_exprs.join(", ")), 9, next)
+            }
+            LiveValue::Int64(v) if *v < 0 => (format!("{}", v), 7, index + 1),
+            LiveValue::Float64(v) if v.is_sign_negative() => (float(*v) ?, 7, index + 1),
+            LiveValue::Id(_) | LiveValue::Bool(_) | LiveValue::Int64(_) | LiveValue::Float64(_)
+                | LiveValue::Color(_) | LiveValue::String(_) | LiveValue::Str(_) | LiveValue::InlineString(_) => {
+                (literal(&node.value) ?, 9, index + 1)
+            }
+            value => return Err(format!("Can't print {:?} in an expression", value))
+        })
+    }
+
+    /// Prints the tokens of a shader function with the line breaks and
+    /// relative indentation they had in the source.
+    fn dsl(&mut self, tokens: &[TokenWithSpan], indent: usize) -> Result<(), String> {
+        let Some(first) = tokens.first() else {
+            return Ok(())
+        };
+        let base_column = first.span.start.column;
+        let mut prev: Option<&TokenWithSpan> = None;
+        for token in tokens {
+            if let Some(prev) = prev {
+                if token.span.start.line > prev.span.end.line {
+                    if token.span.start.line > prev.span.end.line + 1 {
+                        self.out.push('\n');
+                    }
+                    self.out.push('\n');
+                    self.indent(indent);
+                    for _ in base_column..token.span.start.column {
+                        self.out.push(' ');
+                    }
+                }
+                else if token.span.start.column > prev.span.end.column || needs_space(&prev.token, &token.token) {
+                    self.out.push(' ');
+                }
+            }
+            self.out.push_str(&match &token.token {
+                LiveToken::String(v) => string(v),
+                LiveToken::Float(v) => float(*v) ?,
+                LiveToken::Color(v) => color(*v),
+                LiveToken::Eof => return Err("Unexpected end of file in a shader function".to_string()),
+                token => token.to_string(),
+            });
+            prev = Some(token);
+        }
+        Ok(())
+    }
+}
+
+/// Words that would merge into one when printed without a space.
+fn needs_space(prev: &LiveToken, next: &LiveToken) -> bool {
+    let is_word = | token: &LiveToken | matches!(token, LiveToken::Ident(_) | LiveToken::Bool(_) | LiveToken::Int(_) | LiveToken::Float(_));
+    is_word(prev) && is_word(next)
+}
+
+fn paren(expr: String, needs_paren: bool) -> String {
+    if needs_paren {format!("({})", expr)} else {expr}
+}
+
+fn ident(id: LiveId) -> Result<String, String> {
+    id.as_string( | name | name.map( | name | name.to_string())).ok_or_else( || format!("Can't print id {} as it has no name", id))
+}
+
+/// Prints a value that is a single node.
+fn literal(value: &LiveValue) -> Result<String, String> {
+    Ok(match value {
+        LiveValue::Str(v) => string(v),
+        LiveValue::String(v) => string(v),
+        LiveValue::InlineString(v) => string(v.as_str()),
+        LiveValue::Dependency(v) => format!("dep({})", string(v)),
+        LiveValue::Bool(v) => format!("{}", v),
+        LiveValue::Int64(v) => format!("{}", v),
+        LiveValue::Uint64(v) => format!("{}", v),
+        LiveValue::Float32(v) => float(*v as f64) ?,
+        LiveValue::Float64(v) => float(*v) ?,
+        LiveValue::Color(v) => color(*v),
+        LiveValue::Vec2(v) => format!("vec2({}, {})", float32(v.x) ?, float32(v.y) ?),
+        LiveValue::Vec3(v) => format!("vec3({}, {}, {})", float32(v.x) ?, float32(v.y) ?, float32(v.z) ?),
+        LiveValue::Vec4(v) => format!("vec4({}, {}, {}, {})", float32(v.x) ?, float32(v.y) ?, float32(v.z) ?, float32(v.w) ?),
+        LiveValue::Id(id) | LiveValue::BareEnum(id) => ident(*id) ?,
+        LiveValue::IdPath(path) => {
+            path.iter().map( | id | ident(*id)).collect::<Result<Vec<_>, _>>()?.join(".")
+        }
+        value => return Err(format!("Can't print {:?} as a value", value))
+    })
+}
+
+/// Floats always get a dot, so they read back as floats, and never an exponent.
+fn float(v: f64) -> Result<String, String> {
+    if !v.is_finite() {
+        return Err(format!("Can't print {} as a float", v))
+    }
+    Ok(with_dot(format!("{}", v)))
+}
+
+/// Prints the shortest float that reads back as the same `f32`.
+fn float32(v: f32) -> Result<String, String> {
+    if !v.is_finite() {
+        return Err(format!("Can't print {} as a float", v))
+    }
+    Ok(with_dot(format!("{}", v)))
+}
+
+fn with_dot(s: String) -> String {
+    if s.contains('.') {s} else {format!("{}.0", s)}
+}
+
+fn color(v: u32) -> String {
+    let hex = format!("{:08x}", v);
+    // rust reads `#1e..` as a float with an exponent when the macro is tokenized
+    if hex.starts_with( | c: char | c.is_ascii_digit()) && hex.contains('e') {
+        format!("#x{}", hex)
+    }
+    else {
+        format!("#{}", hex)
+    }
+}
+
+fn string(v: &str) -> String {
+    // the tokenizer strips leading spaces from strings, raw strings keep them
+    if v.starts_with(' ') {
+        let mut hashes = String::from("#");
+        while v.contains(&format!("\"{}", hashes)) {
+            hashes.push('#');
+        }
+        return format!("r{}\"{}\"{}", hashes, v, hashes)
+    }
+    let mut out = String::from("\"");
+    for c in v.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -773,6 +773,10 @@ impl<T> LiveNodeSliceApi for T where T: AsRef<[LiveNode]> {
                 LiveValue::Import(live_import) => {
                     writeln!(f, "<Import> {}::{} as {}", live_import.module_id, live_import.import_id, node.id).unwrap();
                 }
+                LiveValue::Condition {is_else} => {
+                    writeln!(f, "{}{} <Condition> is_else:{}", node.id, pt, is_else).unwrap();
+                    stack_depth += 1;
+                }
                 /*LiveValue::Registry(component_id) => {
                     writeln!(f, "<Registry> {}::{}", component_id, node.id).unwrap();
                 }*/
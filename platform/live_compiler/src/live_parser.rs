@@ -856,7 +856,17 @@ impl<'a> LiveParser<'a> {
     
     fn expect_live_class(&mut self, root: bool, prop_id: LiveId, ld: &mut LiveOriginal) -> Result<(), LiveError> {
         let mut nameless_id = 1;
+        self.expect_live_class_items(root, prop_id, &mut nameless_id, ld)
+    }
+    
+    // the body of a condition shares the nameless ids of the class it is in
+    fn expect_live_class_items(&mut self, root: bool, prop_id: LiveId, nameless_id: &mut u64, ld: &mut LiveOriginal) -> Result<(), LiveError> {
+        let mut after_condition = false;
         while self.peek_token() != LiveToken::Eof {
+            let is_condition = matches!(self.peek_token(), LiveToken::Ident(live_id!(if)) | LiveToken::Ident(live_id!(else)));
+            if !is_condition {
+                after_condition = false;
+            }
             match self.peek_token() {
                 LiveToken::Close(Delim::Brace) => {
                     if root {
@@ -880,12 +890,27 @@ impl<'a> LiveParser<'a> {
                     self.expect_token(LiveToken::Open(Delim::Brace))?;
                     ld.nodes.push(LiveNode {
                         origin: LiveNodeOrigin::from_token_id(token_id).with_prop_type(LivePropType::Instance),
-                        id: LiveId(nameless_id),
+                        id: LiveId(*nameless_id),
                         value: LiveValue::Clone{clone:ident, design_info}
                     });
-                    nameless_id += 1;
+                    *nameless_id += 1;
                     self.expect_live_class(false, prop_id, ld) ?;
                 }
+                LiveToken::Ident(live_id!(if)) => {
+                    self.skip_token();
+                    self.expect_condition(false, true, nameless_id, ld) ?;
+                    after_condition = true;
+                }
+                LiveToken::Ident(live_id!(else)) => {
+                    if !after_condition {
+                        return Err(self.error("Unexpected else without if".to_string(), live_error_origin!()))
+                    }
+                    self.skip_token();
+                    // a plain else is an else if that always holds, and ends the chain
+                    let has_condition = self.accept_token(LiveToken::Ident(live_id!(if)));
+                    self.expect_condition(true, has_condition, nameless_id, ld) ?;
+                    after_condition = has_condition;
+                }
                 LiveToken::Ident(prop_id) => {
                     let token_id = self.get_token_id();
                     self.skip_token();
@@ -941,6 +966,24 @@ impl<'a> LiveParser<'a> {
         Err(self.error("Eof in class body".to_string(), live_error_origin!()))
     }
     
+    fn expect_condition(&mut self, is_else: bool, has_condition: bool, nameless_id: &mut u64, ld: &mut LiveOriginal) -> Result<(), LiveError> {
+        let origin = LiveNodeOrigin::from_token_id(self.get_token_id()).with_prop_type(LivePropType::Nameless);
+        ld.nodes.push(LiveNode {
+            origin,
+            id: LiveId::empty(),
+            value: LiveValue::Condition {is_else}
+        });
+        let expr = if has_condition {
+            self.expect_expr() ?
+        }
+        else {
+            Expr::Bool {token_id: self.get_token_id(), v: true}
+        };
+        self.push_expression(expr, LiveId::empty(), origin, ld);
+        self.expect_token(LiveToken::Open(Delim::Brace)) ?;
+        self.expect_live_class_items(false, LiveId::empty(), nameless_id, ld)
+    }
+    
     pub fn expect_prop_type(&mut self)->Result<LivePropType, LiveError>{
         Ok(if self.accept_token(LiveToken::Punct(live_id!(:))){
             LivePropType::Field
@@ -993,7 +1036,11 @@ impl<'a> LiveParser<'a> {
     fn expect_expression(&mut self, prop_id: LiveId, origin: LiveNodeOrigin, ld: &mut LiveOriginal) -> Result<(), LiveError> {
         
         let expr = self.expect_prim_expr() ?;
-        
+        self.push_expression(expr, prop_id, origin, ld);
+        Ok(())
+    }
+    
+    fn push_expression(&mut self, expr: Expr, prop_id: LiveId, origin: LiveNodeOrigin, ld: &mut LiveOriginal) {
         ld.nodes.push(LiveNode {
             origin,
             id: prop_id,
@@ -1072,6 +1119,13 @@ impl<'a> LiveParser<'a> {
                         value: LiveValue::Color(v)
                     });
                 }
+                Expr::String {token_id, v} => {
+                    ld.nodes.push(LiveNode {
+                        origin: LiveNodeOrigin::from_token_id(token_id).with_prop_type(LivePropType::Nameless),
+                        id: LiveId::empty(),
+                        value: LiveValue::String(v)
+                    });
+                }
             }
         }
         
@@ -1082,9 +1136,6 @@ impl<'a> LiveParser<'a> {
             id: prop_id,
             value: LiveValue::Close
         });
-        
-        
-        Ok(())
         /*
         ld.nodes.push(LiveNode {
             token_id: Some(self.get_token_id()),
@@ -1262,6 +1313,11 @@ impl<'a> LiveParser<'a> {
                 let token_id = self.get_token_id();
                 Ok(Expr::Color {token_id, v})
             }
+            LiveToken::String(v) => {
+                self.skip_token();
+                let token_id = self.get_token_id();
+                Ok(Expr::String {token_id, v})
+            }
             LiveToken::Open(Delim::Paren) => {
                 self.skip_token();
                 let expr = self.expect_expr() ?;
@@ -1332,6 +1388,10 @@ enum Expr {
     Color {
         token_id: LiveTokenId,
         v: u32
+    },
+    String {
+        token_id: LiveTokenId,
+        v: Arc<String>
     }
 }
 
@@ -1,6 +1,6 @@
 //use crate::id::Id;
 use {
-    std::collections::{BTreeMap, BTreeSet},
+    std::collections::{BTreeMap, BTreeSet, HashMap},
     crate::{
         makepad_live_id::*,
        // makepad_error_log::*,
@@ -15,7 +15,9 @@ use {
         live_token::{LiveToken, LiveTokenId, TokenWithSpan},
         span::{TextSpan, TextPos},
         live_expander::{LiveExpander},
-        live_component::{LiveComponentRegistries}
+        live_eval::live_eval_condition,
+        live_component::{LiveComponentRegistries},
+        live_schema::LiveTypeDoc,
     }
 };
 
@@ -27,8 +29,12 @@ pub struct LiveFile {
     pub (crate) start_pos: TextPos,
     pub file_name: String,
     pub cargo_manifest_path: String,
+    /// registered with `load_live_document` instead of from a `live_design!`
+    pub (crate) runtime: bool,
     pub (crate) source: String,
     pub (crate) deps: BTreeSet<LiveModuleId>,
+    /// the `if` blocks in the original and whether they held at the last expand
+    pub (crate) conditions: Vec<(usize, bool)>,
     
     pub generation: LiveFileGeneration,
     pub original: LiveOriginal,
@@ -46,7 +52,12 @@ pub struct LiveRegistry {
     //pub ignore_no_dsl: HashSet<LiveId>,
     pub main_module: Option<LiveTypeInfo>,
     pub components: LiveComponentRegistries,
-    pub package_root: Option<String>
+    pub package_root: Option<String>,
+    /// the values the conditions of `if` blocks are evaluated against
+    pub conditions: HashMap<LiveId, LiveValue>,
+    /// what the `Live` derive knows about each type, built only when a schema
+    /// is exported
+    pub type_docs: BTreeMap<LiveType, fn() -> LiveTypeDoc>,
 }
 
 impl Default for LiveRegistry {
@@ -58,7 +69,9 @@ impl Default for LiveRegistry {
             live_files: Vec::new(),
             live_type_infos: Default::default(),
             components: LiveComponentRegistries::default(),
-            package_root: None
+            package_root: None,
+            conditions: Default::default(),
+            type_docs: Default::default(),
         }
     }
 }
@@ -760,8 +773,36 @@ impl LiveRegistry {
             self.live_type_infos.insert(live_type_info.live_type, live_type_info.clone());
         }
         
-        let mut deps = BTreeSet::new();
+        let deps = self.patch_imports_and_collect_deps(own_module_id, &mut original);
         
+        let live_file = LiveFile {
+            cargo_manifest_path: cargo_manifest_path.to_string(),
+            runtime: false,
+            reexpand: true,
+            module_id: own_module_id,
+            file_name: file_name.to_string(),
+            start_pos,
+            deps,
+            conditions: Vec::new(),
+            source,
+            generation: LiveFileGeneration::default(),
+            live_type_infos,
+            original,
+            next_original: None,
+            expanded: LiveExpanded::new()
+        };
+        self.module_id_to_file_id.insert(own_module_id, file_id);
+        
+        self.file_ids.insert(file_name.to_string(), file_id);
+        self.live_files.push(live_file);
+        
+        Ok(file_id)
+    }
+    
+    /// Points `crate` imports at the module itself and returns the modules the
+    /// document depends on.
+    fn patch_imports_and_collect_deps(&self, own_module_id: LiveModuleId, original: &mut LiveOriginal) -> BTreeSet<LiveModuleId> {
+        let mut deps = BTreeSet::new();
         for node in &mut original.nodes {
             match &mut node.value {
                 LiveValue::Import(live_import) => {
@@ -776,21 +817,13 @@ impl LiveRegistry {
                         entry.get_module_set(&mut deps);
                     }
                 }, */
-                LiveValue::Deref {live_type, ..} => { // hold up. this is always own_module_path
-                    let infos = self.live_type_infos.get(live_type).unwrap();
-                    for sub_type in infos.fields.clone() {
-                        let sub_module_id = sub_type.live_type_info.module_id;
-                        if sub_module_id != own_module_id {
-                            deps.insert(sub_module_id);
-                        }
-                    }
-                }
-                LiveValue::Class {live_type, ..} => { // hold up. this is always own_module_path
-                    let infos = self.live_type_infos.get(live_type).unwrap();
-                    for sub_type in infos.fields.clone() {
-                        let sub_module_id = sub_type.live_type_info.module_id;
-                        if sub_module_id != own_module_id {
-                            deps.insert(sub_module_id);
+                LiveValue::Deref {live_type, ..} | LiveValue::Class {live_type, ..} => { // hold up. this is always own_module_path
+                    if let Some(infos) = self.live_type_infos.get(live_type) {
+                        for sub_type in &infos.fields {
+                            let sub_module_id = sub_type.live_type_info.module_id;
+                            if sub_module_id != own_module_id {
+                                deps.insert(sub_module_id);
+                            }
                         }
                     }
                 }
@@ -798,29 +831,149 @@ impl LiveRegistry {
                 }
             }
         }
+        deps
+    }
+    
+    /// Loads a live document at runtime, from the source of a `.live` file
+    /// that is a `live_design!` body without the macro around it.
+    ///
+    /// A module that doesn't exist yet is registered as a new document. An
+    /// existing one has its source replaced, which for a module from a
+    /// `live_design!` overrides it until it is unloaded again; `{{Type}}`
+    /// markers then refer to the Rust types of that `live_design!` in order.
+    /// The document and everything depending on it is marked for expansion,
+    /// which `expand_all_documents` does. Nothing changes when the source
+    /// doesn't parse.
+    pub fn load_live_document(&mut self, file_name: &str, module_id: LiveModuleId, source: &str) -> Result<LiveFileId, LiveFileError> {
+        let existing = self.module_id_to_file_id.get(&module_id).cloned();
+        // a document that was unloaded leaves its slot behind, as live pointers index files
+        let unloaded = self.live_files.iter().position( | file | file.runtime && file.module_id == module_id).map(LiveFileId::new);
+        let file_id = existing.or(unloaded).unwrap_or(LiveFileId::new(self.live_files.len()));
         
-        let live_file = LiveFile {
-            cargo_manifest_path: cargo_manifest_path.to_string(),
+        if let Some(other) = self.file_ids.get(file_name) {
+            if *other != file_id {
+                return Err(LiveFileError {
+                    origin: live_error_origin!(),
+                    file: file_name.to_string(),
+                    span: TextSpan::default(),
+                    message: format!("File {} is already registered for module {}", file_name, self.live_files[other.to_index()].module_id)
+                })
+            }
+        }
+        
+        let tokens = match Self::tokenize_from_str(source, TextPos::default(), file_id) {
+            Err(msg) => return Err(msg.into_live_file_error(file_name)),
+            Ok(lex_result) => lex_result
+        };
+        let no_type_infos = Vec::new();
+        let live_type_infos = match existing {
+            Some(file_id) => &self.live_files[file_id.to_index()].live_type_infos,
+            None => &no_type_infos
+        };
+        let mut parser = LiveParser::new(&tokens, live_type_infos, file_id);
+        let mut original = match parser.parse_live_document() {
+            Err(msg) => return Err(msg.into_live_file_error(file_name)),
+            Ok(ld) => ld
+        };
+        original.tokens = tokens;
+        let deps = self.patch_imports_and_collect_deps(module_id, &mut original);
+        
+        if let Some(file_id) = existing {
+            let live_file = &mut self.live_files[file_id.to_index()];
+            if live_file.runtime && live_file.file_name != file_name {
+                self.file_ids.remove(&live_file.file_name);
+                self.file_ids.insert(file_name.to_string(), file_id);
+                live_file.file_name = file_name.to_string();
+            }
+            if live_file.runtime {
+                live_file.source = source.to_string();
+            }
+            live_file.original = original;
+            live_file.deps = deps;
+            live_file.reexpand = true;
+            live_file.generation.next_gen();
+            return Ok(file_id)
+        }
+        
+        let mut live_file = LiveFile {
+            runtime: true,
             reexpand: true,
-            module_id: own_module_id,
+            module_id,
             file_name: file_name.to_string(),
-            start_pos,
             deps,
-            source,
-            generation: LiveFileGeneration::default(),
-            live_type_infos,
+            source: source.to_string(),
             original,
-            next_original: None,
-            expanded: LiveExpanded::new()
+            ..Default::default()
         };
-        self.module_id_to_file_id.insert(own_module_id, file_id);
-        
+        if let Some(unloaded) = unloaded {
+            let old_file = &mut self.live_files[unloaded.to_index()];
+            live_file.cargo_manifest_path = std::mem::take(&mut old_file.cargo_manifest_path);
+            live_file.generation = old_file.generation;
+            live_file.generation.next_gen();
+            *old_file = live_file;
+        }
+        else {
+            self.live_files.push(live_file);
+        }
+        self.module_id_to_file_id.insert(module_id, file_id);
         self.file_ids.insert(file_name.to_string(), file_id);
-        self.live_files.push(live_file);
-        
         Ok(file_id)
     }
     
+    /// Unloads a document loaded with `load_live_document`. A module from a
+    /// `live_design!` goes back to its compiled source, one that only existed
+    /// at runtime is removed, and the documents importing it will report that
+    /// when they are expanded again.
+    pub fn unload_live_document(&mut self, module_id: LiveModuleId) -> Result<(), LiveFileError> {
+        let Some(file_id) = self.module_id_to_file_id.get(&module_id).cloned() else {
+            return Err(LiveFileError {
+                origin: live_error_origin!(),
+                file: module_id.to_string(),
+                span: TextSpan::default(),
+                message: format!("Module {} is not registered", module_id)
+            })
+        };
+        let live_file = &self.live_files[file_id.to_index()];
+        if live_file.runtime {
+            let file_name = live_file.file_name.clone();
+            self.file_ids.remove(&file_name);
+            self.module_id_to_file_id.remove(&module_id);
+            let live_file = &mut self.live_files[file_id.to_index()];
+            live_file.file_name.clear();
+            live_file.source.clear();
+            live_file.deps.clear();
+            live_file.conditions.clear();
+            live_file.original = LiveOriginal::default();
+            live_file.expanded = LiveExpanded::new();
+            live_file.reexpand = false;
+            live_file.generation.next_gen();
+        }
+        else {
+            let tokens = match Self::tokenize_from_str(&live_file.source, live_file.start_pos, file_id) {
+                Err(msg) => return Err(msg.into_live_file_error(&live_file.file_name)),
+                Ok(lex_result) => lex_result
+            };
+            let mut parser = LiveParser::new(&tokens, &live_file.live_type_infos, file_id);
+            let mut original = match parser.parse_live_document() {
+                Err(msg) => return Err(msg.into_live_file_error(&live_file.file_name)),
+                Ok(ld) => ld
+            };
+            original.tokens = tokens;
+            let deps = self.patch_imports_and_collect_deps(module_id, &mut original);
+            let live_file = &mut self.live_files[file_id.to_index()];
+            live_file.original = original;
+            live_file.deps = deps;
+            live_file.reexpand = true;
+            live_file.generation.next_gen();
+        }
+        for live_file in &mut self.live_files {
+            if live_file.deps.contains(&module_id) {
+                live_file.reexpand = true;
+            }
+        }
+        Ok(())
+    }
+    
     pub fn expand_all_documents(&mut self, errors: &mut Vec<LiveError>) {
         // lets build up all dependencies here
         
@@ -913,7 +1066,39 @@ impl LiveRegistry {
             
             self.live_files[file_id.to_index()].reexpand = false;
             std::mem::swap(&mut out_doc, &mut self.live_files[file_id.to_index()].expanded);
+            
+            let original = &self.live_files[file_id.to_index()].original;
+            let conditions = original.nodes.iter().enumerate()
+                .filter( | (_, node) | matches!(node.value, LiveValue::Condition {..}))
+                .map( | (index, _) | (index, self.condition_holds(original, index)))
+                .collect();
+            self.live_files[file_id.to_index()].conditions = conditions;
+        }
+    }
+    
+    fn condition_holds(&self, original: &LiveOriginal, index: usize) -> bool {
+        let mut index = index + 1;
+        matches!(live_eval_condition(self, &mut index, &original.nodes), Ok(LiveValue::Bool(true)))
+    }
+    
+    /// Sets a value the conditions of `if` blocks can test, like `window_width`.
+    /// Call `reexpand_changed_conditions` after setting them.
+    pub fn set_condition(&mut self, id: LiveId, value: LiveValue) {
+        self.conditions.insert(id, value);
+    }
+    
+    /// Marks the documents with an `if` block that goes the other way now for
+    /// expansion, and returns whether there were any.
+    pub fn reexpand_changed_conditions(&mut self) -> bool {
+        let mut changed = false;
+        for file_index in 0..self.live_files.len() {
+            let live_file = &self.live_files[file_index];
+            if live_file.conditions.iter().any( | (index, holds) | self.condition_holds(&live_file.original, *index) != *holds) {
+                self.live_files[file_index].reexpand = true;
+                changed = true;
+            }
         }
+        changed
     }
 }
 
@@ -0,0 +1,445 @@
+//! The public DSL surface of every registered live type, exported as JSON and
+//! as Markdown reference pages.
+//!
+//! The `Live` derive registers a function building a `LiveTypeDoc` for each
+//! type it is used on, so nothing is built unless a schema is exported. It
+//! holds what `LiveTypeInfo` leaves out: the Rust types of fields, the
+//! `#[live(..)]` defaults, doc comments and enum variants. The exporter joins
+//! these with the registered type infos, the component registries and the
+//! expanded DSL, where the defaults set in `live_design!` and the animator
+//! states live. The output is sorted, so it can be committed and compared.
+
+use {
+    std::collections::BTreeMap,
+    std::fmt::Write,
+    crate::{
+        makepad_live_id::*,
+        live_registry::LiveRegistry,
+        live_node::{LiveValue, LiveType, LiveTypeInfo, LiveFieldKind, LiveIdAsProp},
+        live_node_vec::LiveNodeSliceApi,
+    }
+};
+
+/// What the `Live` derive knows about a type.
+#[derive(Clone, Debug)]
+pub struct LiveTypeDoc {
+    pub rust_name: &'static str,
+    pub module_path: &'static str,
+    pub doc: Vec<&'static str>,
+    pub fields: Vec<LiveFieldDoc>,
+    pub variants: Vec<LiveVariantDoc>,
+}
+
+/// A field with a `live`, `calc`, `animator`, `deref`, `walk` or `layout`
+/// attribute.
+#[derive(Clone, Debug)]
+pub struct LiveFieldDoc {
+    pub name: &'static str,
+    pub rust_type: &'static str,
+    pub attribute: &'static str,
+    /// the arguments of `#[live(..)]` or `#[calc(..)]`
+    pub default: Option<&'static str>,
+    pub doc: Vec<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LiveVariantDoc {
+    pub name: &'static str,
+    /// `bare`, `tuple` or `named`
+    pub kind: &'static str,
+    /// the values a tuple or named variant is created with
+    pub default: Option<&'static str>,
+    pub pick: bool,
+    pub doc: Vec<&'static str>,
+}
+
+struct SchemaType {
+    name: String,
+    module: String,
+    rust_name: String,
+    component: Option<String>,
+    doc: String,
+    fields: Vec<SchemaField>,
+    variants: Vec<SchemaVariant>,
+    animator: Vec<SchemaTrack>,
+}
+
+struct SchemaField {
+    name: String,
+    rust_type: String,
+    attribute: String,
+    default: Option<String>,
+    dsl_default: Option<String>,
+    doc: String,
+}
+
+struct SchemaVariant {
+    name: String,
+    kind: String,
+    default: Option<String>,
+    pick: bool,
+    doc: String,
+}
+
+struct SchemaTrack {
+    name: String,
+    default: Option<String>,
+    states: Vec<String>,
+}
+
+impl LiveRegistry {
+    /// The schema of all registered live types as pretty printed JSON.
+    pub fn live_schema_json(&self) -> String {
+        let types = self.schema_types();
+        let mut out = String::new();
+        out.push_str("{\n  \"types\": [");
+        for (i, ty) in types.iter().enumerate() {
+            out.push_str(if i == 0 {"\n"} else {",\n"});
+            out.push_str("    {\n");
+            let mut props = vec![
+                ("name", json_str(&ty.name)),
+                ("module", json_str(&ty.module)),
+                ("rust_name", json_str(&ty.rust_name)),
+                ("kind", json_str(if ty.variants.is_empty() {"struct"} else {"enum"})),
+            ];
+            if let Some(component) = &ty.component {
+                props.push(("component", json_str(component)));
+            }
+            if !ty.doc.is_empty() {
+                props.push(("doc", json_str(&ty.doc)));
+            }
+            for (name, value) in props {
+                let _ = writeln!(out, "      \"{}\": {},", name, value);
+            }
+            out.push_str("      \"fields\": [");
+            json_list(&mut out, &ty.fields, | f | {
+                let mut props = vec![
+                    ("name", json_str(&f.name)),
+                    ("type", json_str(&f.rust_type)),
+                    ("attribute", json_str(&f.attribute)),
+                ];
+                if let Some(default) = &f.default {
+                    props.push(("default", json_str(default)));
+                }
+                if let Some(dsl_default) = &f.dsl_default {
+                    props.push(("dsl_default", json_str(dsl_default)));
+                }
+                if !f.doc.is_empty() {
+                    props.push(("doc", json_str(&f.doc)));
+                }
+                props
+            });
+            out.push_str(",\n      \"variants\": [");
+            json_list(&mut out, &ty.variants, | v | {
+                let mut props = vec![
+                    ("name", json_str(&v.name)),
+                    ("kind", json_str(&v.kind)),
+                ];
+                if let Some(default) = &v.default {
+                    props.push(("default", json_str(default)));
+                }
+                if v.pick {
+                    props.push(("pick", "true".to_string()));
+                }
+                if !v.doc.is_empty() {
+                    props.push(("doc", json_str(&v.doc)));
+                }
+                props
+            });
+            out.push_str(",\n      \"animator\": [");
+            json_list(&mut out, &ty.animator, | t | {
+                let mut props = vec![("track", json_str(&t.name))];
+                if let Some(default) = &t.default {
+                    props.push(("default", json_str(default)));
+                }
+                let states: Vec<String> = t.states.iter().map( | s | json_str(s)).collect();
+                props.push(("states", format!("[{}]", states.join(", "))));
+                props
+            });
+            out.push_str("\n    }");
+        }
+        out.push_str(if types.is_empty() {"]\n}\n"} else {"\n  ]\n}\n"});
+        out
+    }
+
+    /// A Markdown reference page per type, keyed by a relative path made of the
+    /// module path and the type name, plus a `README.md` index.
+    pub fn live_schema_markdown(&self) -> Vec<(String, String)> {
+        let types = self.schema_types();
+        let mut pages = Vec::new();
+        let mut index = String::from("# Live types\n\n| Type | Module | Kind |\n|---|---|---|\n");
+        for ty in &types {
+            let path = format!("{}/{}.md", ty.module.replace("::", "/"), ty.name);
+            let kind = ty.component.clone().unwrap_or_else( || (if ty.variants.is_empty() {"struct"} else {"enum"}).to_string());
+            let _ = writeln!(index, "| [{}]({}) | `{}` | {} |", ty.name, path, ty.module, kind);
+
+            let mut page = String::new();
+            let _ = writeln!(page, "# {}\n", ty.name);
+            let _ = write!(page, "`{}::{}`", ty.module, ty.rust_name);
+            if let Some(component) = &ty.component {
+                let _ = write!(page, " · {}", component);
+            }
+            page.push_str("\n\n");
+            if !ty.doc.is_empty() {
+                let _ = writeln!(page, "{}\n", ty.doc);
+            }
+            if !ty.fields.is_empty() {
+                page.push_str("## Properties\n\n| Name | Type | Attribute | Default | DSL default | Description |\n|---|---|---|---|---|---|\n");
+                for f in &ty.fields {
+                    let _ = writeln!(page, "| `{}` | `{}` | {} | {} | {} | {} |",
+                        f.name, md_cell(&f.rust_type), f.attribute,
+                        md_code(f.default.as_deref()), md_code(f.dsl_default.as_deref()), md_cell(&f.doc));
+                }
+                page.push('\n');
+            }
+            if !ty.variants.is_empty() {
+                page.push_str("## Variants\n\n| Name | Kind | Default | Description |\n|---|---|---|---|\n");
+                for v in &ty.variants {
+                    let name = if v.pick {format!("`{}` (default)", v.name)} else {format!("`{}`", v.name)};
+                    let _ = writeln!(page, "| {} | {} | {} | {} |", name, v.kind, md_code(v.default.as_deref()), md_cell(&v.doc));
+                }
+                page.push('\n');
+            }
+            if !ty.animator.is_empty() {
+                page.push_str("## Animator\n\n| Track | Default | States |\n|---|---|---|\n");
+                for t in &ty.animator {
+                    let states: Vec<String> = t.states.iter().map( | s | format!("`{}`", s)).collect();
+                    let _ = writeln!(page, "| `{}` | {} | {} |", t.name, md_code(t.default.as_deref()), states.join(", "));
+                }
+                page.push('\n');
+            }
+            pages.push((path, page));
+        }
+        pages.push(("README.md".to_string(), index));
+        pages
+    }
+
+    fn schema_types(&self) -> Vec<SchemaType> {
+        let mut live_types: Vec<LiveType> = self.live_type_infos.keys().cloned().collect();
+        live_types.extend(self.type_docs.keys().filter( | t | !self.live_type_infos.contains_key(t)));
+
+        let mut components = BTreeMap::new();
+        for registry in self.components.0.borrow().values() {
+            for (live_type, _) in registry.component_infos() {
+                components.insert(live_type, registry.component_type().to_string());
+            }
+        }
+
+        let mut types = BTreeMap::new();
+        for live_type in live_types {
+            let info = self.live_type_infos.get(&live_type);
+            let doc = self.type_docs.get(&live_type).map( | type_doc | type_doc());
+            let (name, module) = match (info, &doc) {
+                (Some(info), _) => (info.type_name.to_string(), info.module_id.to_string()),
+                (None, Some(doc)) => (doc.rust_name.to_string(), doc.module_path.to_string()),
+                (None, None) => continue
+            };
+            let class_index = info.and_then( | info | self.class_node(info));
+            let mut ty = SchemaType {
+                rust_name: doc.as_ref().map( | doc | doc.rust_name.to_string()).unwrap_or_else( || name.clone()),
+                component: components.get(&live_type).cloned(),
+                doc: doc.as_ref().map( | doc | join_doc(&doc.doc)).unwrap_or_default(),
+                fields: Vec::new(),
+                variants: Vec::new(),
+                animator: Vec::new(),
+                name,
+                module,
+            };
+            match (&doc, info) {
+                (Some(doc), _) => {
+                    for f in &doc.fields {
+                        ty.fields.push(SchemaField {
+                            name: f.name.to_string(),
+                            rust_type: f.rust_type.to_string(),
+                            attribute: f.attribute.to_string(),
+                            default: f.default.map( | d | d.to_string()),
+                            dsl_default: None,
+                            doc: join_doc(&f.doc),
+                        });
+                    }
+                    for v in &doc.variants {
+                        ty.variants.push(SchemaVariant {
+                            name: v.name.to_string(),
+                            kind: v.kind.to_string(),
+                            default: v.default.map( | d | d.to_string()),
+                            pick: v.pick,
+                            doc: join_doc(&v.doc),
+                        });
+                    }
+                }
+                (None, Some(info)) => {
+                    for f in &info.fields {
+                        ty.fields.push(SchemaField {
+                            name: f.id.to_string(),
+                            rust_type: f.live_type_info.type_name.to_string(),
+                            attribute: match f.live_field_kind {
+                                LiveFieldKind::Calc => "calc",
+                                LiveFieldKind::Deref => "deref",
+                                LiveFieldKind::Animator => "animator",
+                                LiveFieldKind::Live | LiveFieldKind::LiveOption => "live",
+                            }.to_string(),
+                            default: None,
+                            dsl_default: None,
+                            doc: String::new(),
+                        });
+                    }
+                }
+                (None, None) => ()
+            }
+            if let Some((file_index, class_index)) = class_index {
+                let nodes = &self.live_files[file_index].expanded.nodes;
+                for f in &mut ty.fields {
+                    if f.attribute == "walk" || f.attribute == "layout" {
+                        continue;
+                    }
+                    if let Some(index) = nodes.child_by_name(class_index, LiveId::from_str(&f.name).as_field()) {
+                        f.dsl_default = value_text(&nodes[index].value);
+                    }
+                }
+                if let Some(animator) = nodes.child_by_name(class_index, live_id!(animator).as_field()) {
+                    let mut track = nodes.first_child(animator);
+                    while let Some(track_index) = track {
+                        if nodes[track_index].value.is_open() {
+                            let mut t = SchemaTrack {
+                                name: nodes[track_index].id.to_string(),
+                                default: None,
+                                states: Vec::new(),
+                            };
+                            let mut state = nodes.first_child(track_index);
+                            while let Some(state_index) = state {
+                                let node = &nodes[state_index];
+                                if node.id == live_id!(default) {
+                                    t.default = value_text(&node.value);
+                                }
+                                else if node.value.is_open() {
+                                    t.states.push(node.id.to_string());
+                                }
+                                state = nodes.next_child(state_index);
+                            }
+                            ty.animator.push(t);
+                        }
+                        track = nodes.next_child(track_index);
+                    }
+                }
+            }
+            types.insert((ty.module.clone(), ty.name.clone()), ty);
+        }
+        types.into_values().collect()
+    }
+
+    /// The top level `{{Type}}` node defining the DSL defaults of a type.
+    fn class_node(&self, info: &LiveTypeInfo) -> Option<(usize, usize)> {
+        let file_id = self.module_id_to_file_id.get(&info.module_id) ?;
+        let nodes = &self.live_files[file_id.to_index()].expanded.nodes;
+        if nodes.is_empty() {
+            return None
+        }
+        let mut found = None;
+        let mut child = nodes.first_child(0);
+        while let Some(index) = child {
+            if let LiveValue::Class {live_type, ..} = &nodes[index].value {
+                if *live_type == info.live_type {
+                    if nodes[index].id == info.type_name {
+                        return Some((file_id.to_index(), index))
+                    }
+                    found.get_or_insert((file_id.to_index(), index));
+                }
+            }
+            child = nodes.next_child(index);
+        }
+        found
+    }
+}
+
+/// Compares a freshly generated schema with a committed one, returning a
+/// description of the first difference.
+pub fn live_schema_diff(committed: &str, generated: &str) -> Option<String> {
+    if committed == generated {
+        return None
+    }
+    let mut committed_lines = committed.lines();
+    let mut generated_lines = generated.lines();
+    let mut line = 1;
+    loop {
+        match (committed_lines.next(), generated_lines.next()) {
+            (Some(a), Some(b)) if a == b => line += 1,
+            (a, b) => return Some(format!(
+                "live schema differs at line {}:\n- {}\n+ {}",
+                line,
+                a.unwrap_or("<end of file>"),
+                b.unwrap_or("<end of file>")
+            ))
+        }
+    }
+}
+
+fn value_text(value: &LiveValue) -> Option<String> {
+    Some(match value {
+        LiveValue::Str(s) => format!("{:?}", s),
+        LiveValue::String(s) => format!("{:?}", s.as_str()),
+        LiveValue::InlineString(s) => format!("{:?}", s.as_str()),
+        LiveValue::Dependency(s) => format!("dep({:?})", s.as_str()),
+        LiveValue::Bool(v) => v.to_string(),
+        LiveValue::Int64(v) => v.to_string(),
+        LiveValue::Uint64(v) => v.to_string(),
+        LiveValue::Float32(v) => format!("{:?}", v),
+        LiveValue::Float64(v) => format!("{:?}", v),
+        LiveValue::Color(v) => format!("#{:08x}", v),
+        LiveValue::Vec2(v) => format!("vec2({:?}, {:?})", v.x, v.y),
+        LiveValue::Vec3(v) => format!("vec3({:?}, {:?}, {:?})", v.x, v.y, v.z),
+        LiveValue::Vec4(v) => format!("vec4({:?}, {:?}, {:?}, {:?})", v.x, v.y, v.z, v.w),
+        LiveValue::Id(id) | LiveValue::BareEnum(id) => id.to_string(),
+        LiveValue::TupleEnum(id) | LiveValue::NamedEnum(id) => format!("{}(..)", id),
+        _ => return None
+    })
+}
+
+fn join_doc(lines: &[&str]) -> String {
+    let lines: Vec<&str> = lines.iter().map( | l | l.strip_prefix(' ').unwrap_or(l).trim_end()).collect();
+    lines.join("\n").trim().to_string()
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Writes the items of a list of objects after its `[`, and the `]`.
+fn json_list<T>(out: &mut String, items: &[T], props: impl Fn(&T) -> Vec<(&'static str, String)>) {
+    if items.is_empty() {
+        out.push(']');
+        return
+    }
+    for (i, item) in items.iter().enumerate() {
+        out.push_str(if i == 0 {"\n        {"} else {",\n        {"});
+        let props: Vec<String> = props(item).into_iter().map( | (name, value) | format!("\"{}\": {}", name, value)).collect();
+        out.push_str(&props.join(", "));
+        out.push('}');
+    }
+    out.push_str("\n      ]");
+}
+
+fn md_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn md_code(s: Option<&str>) -> String {
+    match s {
+        Some(s) => format!("`{}`", md_cell(s)),
+        None => String::new()
+    }
+}
+
@@ -0,0 +1,115 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    LiveIdAsProp,
+    LiveModuleId,
+    LiveNodeSliceApi,
+    LiveRegistry,
+    LiveValue,
+    TextPos,
+};
+
+const PANEL: &str = "
+    Panel = {
+        width: 300.0
+        if window_width < 800 {
+            width: 100.0
+        }
+        else if dark_mode {
+            width: 200.0
+        }
+        else {
+            width: 400.0
+        }
+        height: 50.0
+    }
+";
+
+/// A registry holding `source` with the given conditions set, expanded once.
+fn registry(source: &str, conditions: &[(LiveId, LiveValue)]) -> LiveRegistry {
+    let mut registry = LiveRegistry::default();
+    for (id, value) in conditions {
+        registry.set_condition(*id, value.clone());
+    }
+    let module_id = LiveModuleId::from_str("test::conditions").unwrap();
+    registry.register_live_file("conditions.rs", "", module_id, source.to_string(), vec![], TextPos::default()).ok().unwrap();
+    expand(&mut registry);
+    registry
+}
+
+fn expand(registry: &mut LiveRegistry) {
+    let mut errors = Vec::new();
+    registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors.iter().map( | e | &e.message).collect::<Vec<_ >> ());
+}
+
+fn value(registry: &LiveRegistry, name: LiveId) -> Option<LiveValue> {
+    let module_id = LiveModuleId::from_str("test::conditions").unwrap();
+    let nodes = registry.module_id_to_expanded_nodes(module_id).unwrap();
+    nodes.child_value_by_path(0, &[live_id!(Panel).as_instance(), name.as_field()]).cloned()
+}
+
+#[test]
+fn the_first_branch_that_holds_is_expanded() {
+    let narrow = [(live_id!(window_width), LiveValue::Float64(640.0)), (live_id!(dark_mode), LiveValue::Bool(true))];
+    let expanded = registry(PANEL, &narrow);
+    assert_eq!(value(&expanded, live_id!(width)), Some(LiveValue::Float64(100.0)));
+    // what comes after the chain still applies
+    assert_eq!(value(&expanded, live_id!(height)), Some(LiveValue::Float64(50.0)));
+
+    let dark = [(live_id!(window_width), LiveValue::Float64(1024.0)), (live_id!(dark_mode), LiveValue::Bool(true))];
+    assert_eq!(value(&registry(PANEL, &dark), live_id!(width)), Some(LiveValue::Float64(200.0)));
+
+    let light = [(live_id!(window_width), LiveValue::Float64(1024.0)), (live_id!(dark_mode), LiveValue::Bool(false))];
+    assert_eq!(value(&registry(PANEL, &light), live_id!(width)), Some(LiveValue::Float64(400.0)));
+
+    // without an else nothing is added when the condition doesn't hold
+    let expanded = registry("Panel = {if os == android {width: 1.0}}", &[(live_id!(os), LiveValue::Id(live_id!(macos)))]);
+    assert_eq!(value(&expanded, live_id!(width)), None);
+    let expanded = registry("Panel = {if os == android && !dark_mode {width: 1.0}}", &[
+        (live_id!(os), LiveValue::Id(live_id!(android))),
+        (live_id!(dark_mode), LiveValue::Bool(false)),
+    ]);
+    assert_eq!(value(&expanded, live_id!(width)), Some(LiveValue::Float64(1.0)));
+}
+
+#[test]
+fn conditions_that_are_not_a_bool_are_errors() {
+    let mut registry = LiveRegistry::default();
+    let module_id = LiveModuleId::from_str("test::conditions").unwrap();
+    registry.register_live_file("conditions.rs", "", module_id, "Panel = {if 1 + 1 {width: 1.0}}".to_string(), vec![], TextPos::default()).ok().unwrap();
+    let mut errors = Vec::new();
+    registry.expand_all_documents(&mut errors);
+    assert!(errors.iter().any( | e | e.message.contains("Condition should be a bool")), "{:?}", errors.iter().map( | e | &e.message).collect::<Vec<_ >> ());
+
+    let source = "Panel = {else {width: 1.0}}".to_string();
+    let mut registry = LiveRegistry::default();
+    let error = registry.register_live_file("conditions.rs", "", module_id, source, vec![], TextPos::default()).err().unwrap();
+    assert!(error.message.contains("else without if"), "{}", error.message);
+}
+
+#[test]
+fn crossing_a_breakpoint_reexpands_the_document() {
+    let mut registry = registry(PANEL, &[(live_id!(window_width), LiveValue::Float64(1024.0)), (live_id!(dark_mode), LiveValue::Bool(false))]);
+    assert_eq!(value(&registry, live_id!(width)), Some(LiveValue::Float64(400.0)));
+
+    // resizing on the same side of the breakpoint changes nothing
+    registry.set_condition(live_id!(window_width), LiveValue::Float64(900.0));
+    assert!(!registry.reexpand_changed_conditions());
+
+    registry.set_condition(live_id!(window_width), LiveValue::Float64(700.0));
+    assert!(registry.reexpand_changed_conditions());
+    expand(&mut registry);
+    assert_eq!(value(&registry, live_id!(width)), Some(LiveValue::Float64(100.0)));
+    // once expanded the document is up to date again
+    assert!(!registry.reexpand_changed_conditions());
+
+    // the theme branch loses to the breakpoint until the window is wide again
+    registry.set_condition(live_id!(dark_mode), LiveValue::Bool(true));
+    assert!(registry.reexpand_changed_conditions());
+    expand(&mut registry);
+    assert_eq!(value(&registry, live_id!(width)), Some(LiveValue::Float64(100.0)));
+    registry.set_condition(live_id!(window_width), LiveValue::Float64(1200.0));
+    assert!(registry.reexpand_changed_conditions());
+    expand(&mut registry);
+    assert_eq!(value(&registry, live_id!(width)), Some(LiveValue::Float64(200.0)));
+}
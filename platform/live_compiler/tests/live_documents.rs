@@ -0,0 +1,108 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    LiveIdAsProp,
+    LiveModuleId,
+    LiveNodeSliceApi,
+    LiveRegistry,
+    LiveValue,
+    TextPos,
+};
+
+const THEME: &str = "
+    Theme = {
+        spacing: 4.0
+    }
+";
+
+const PANEL: &str = "
+    import test::theme::*;
+    Panel = <Theme> {
+        width: 100.0
+    }
+";
+
+fn module(name: &str) -> LiveModuleId {
+    LiveModuleId::from_str(name).unwrap()
+}
+
+fn expand(registry: &mut LiveRegistry) -> Vec<String> {
+    let mut errors = Vec::new();
+    registry.expand_all_documents(&mut errors);
+    errors.into_iter().map( | e | e.message).collect()
+}
+
+fn value(registry: &LiveRegistry, module_name: &str, path: &[LiveId]) -> Option<LiveValue> {
+    let nodes = registry.module_id_to_expanded_nodes(module(module_name))?;
+    let (last, parents) = path.split_last().unwrap();
+    let mut path: Vec<_> = parents.iter().map( | id | id.as_instance()).collect();
+    path.push(last.as_field());
+    nodes.child_value_by_path(0, &path).cloned()
+}
+
+fn spacing(registry: &LiveRegistry, module_name: &str, name: LiveId) -> Option<LiveValue> {
+    value(registry, module_name, &[name, live_id!(spacing)])
+}
+
+#[test]
+fn loading_again_replaces_the_document_and_its_dependents() {
+    let mut registry = LiveRegistry::default();
+    let theme = registry.load_live_document("theme.live", module("test::theme"), THEME).ok().unwrap();
+    registry.load_live_document("panel.live", module("test::panel"), PANEL).ok().unwrap();
+    assert!(expand(&mut registry).is_empty());
+    assert_eq!(spacing(&registry, "test::panel", live_id!(Panel)), Some(LiveValue::Float64(4.0)));
+    assert_eq!(value(&registry, "test::panel", &[live_id!(Panel), live_id!(width)]), Some(LiveValue::Float64(100.0)));
+
+    // the same module keeps its file, and the panel picks up the new theme
+    let reloaded = registry.load_live_document("theme.live", module("test::theme"), "Theme = {spacing: 8.0}").ok().unwrap();
+    assert_eq!(reloaded, theme);
+    assert!(expand(&mut registry).is_empty());
+    assert_eq!(spacing(&registry, "test::theme", live_id!(Theme)), Some(LiveValue::Float64(8.0)));
+    assert_eq!(spacing(&registry, "test::panel", live_id!(Panel)), Some(LiveValue::Float64(8.0)));
+
+    // a source that doesn't parse changes nothing
+    assert!(registry.load_live_document("theme.live", module("test::theme"), "Theme = {spacing: }").is_err());
+    assert!(expand(&mut registry).is_empty());
+    assert_eq!(spacing(&registry, "test::panel", live_id!(Panel)), Some(LiveValue::Float64(8.0)));
+
+    // another module can't take a file name that is in use
+    assert!(registry.load_live_document("theme.live", module("test::other"), THEME).is_err());
+}
+
+#[test]
+fn unloading_removes_runtime_documents_and_restores_compiled_ones() {
+    let mut registry = LiveRegistry::default();
+    registry.register_live_file("theme.rs", "", module("test::theme"), THEME.to_string(), vec![], TextPos::default()).ok().unwrap();
+    let panel = registry.load_live_document("panel.live", module("test::panel"), PANEL).ok().unwrap();
+    assert!(expand(&mut registry).is_empty());
+
+    // a runtime document overrides the one from a `live_design!` until it is unloaded
+    registry.load_live_document("theme.live", module("test::theme"), "Theme = {spacing: 2.0}").ok().unwrap();
+    assert!(expand(&mut registry).is_empty());
+    assert_eq!(spacing(&registry, "test::panel", live_id!(Panel)), Some(LiveValue::Float64(2.0)));
+    registry.unload_live_document(module("test::theme")).ok().unwrap();
+    assert!(expand(&mut registry).is_empty());
+    assert_eq!(spacing(&registry, "test::theme", live_id!(Theme)), Some(LiveValue::Float64(4.0)));
+    assert_eq!(spacing(&registry, "test::panel", live_id!(Panel)), Some(LiveValue::Float64(4.0)));
+
+    // a document that only existed at runtime is gone, but its slot is used again when it comes back
+    registry.unload_live_document(module("test::panel")).ok().unwrap();
+    assert_eq!(registry.module_id_to_file_id(module("test::panel")), None);
+    assert_eq!(registry.file_name_to_file_id("panel.live"), None);
+    assert!(registry.unload_live_document(module("test::panel")).is_err());
+    let reloaded = registry.load_live_document("panel_v2.live", module("test::panel"), PANEL).ok().unwrap();
+    assert_eq!(reloaded, panel);
+    assert!(expand(&mut registry).is_empty());
+    assert_eq!(spacing(&registry, "test::panel", live_id!(Panel)), Some(LiveValue::Float64(4.0)));
+}
+
+#[test]
+fn unloading_an_import_is_reported_by_its_dependents() {
+    let mut registry = LiveRegistry::default();
+    registry.load_live_document("theme.live", module("test::theme"), THEME).ok().unwrap();
+    registry.load_live_document("panel.live", module("test::panel"), PANEL).ok().unwrap();
+    assert!(expand(&mut registry).is_empty());
+    registry.unload_live_document(module("test::theme")).ok().unwrap();
+    let errors = expand(&mut registry);
+    assert!(!errors.is_empty());
+    assert!(errors.iter().any( | e | e.contains("test::theme") || e.contains("Theme")), "{:?}", errors);
+}
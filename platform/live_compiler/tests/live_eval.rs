@@ -0,0 +1,155 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    makepad_math::*,
+    LiveIdAsProp,
+    LiveModuleId,
+    LiveNodeSliceApi,
+    LiveRegistry,
+    LiveValue,
+    TextPos,
+};
+
+/// Expands `A = {v: (<expr>)}` and returns the value of `v` or the first error.
+fn eval(expr: &str) -> Result<LiveValue, String> {
+    eval_with(expr, "")
+}
+
+fn eval_with(expr: &str, defs: &str) -> Result<LiveValue, String> {
+    let mut registry = LiveRegistry::default();
+    let module_id = LiveModuleId::from_str("test::eval").unwrap();
+    let source = format!("{}\nA = {{v: ({})}}", defs, expr);
+    registry.register_live_file("eval.rs", "", module_id, source, vec![], TextPos::default())
+        .map_err( | e | e.message)?;
+    let mut errors = Vec::new();
+    registry.expand_all_documents(&mut errors);
+    if let Some(error) = errors.first() {
+        return Err(error.message.clone())
+    }
+    let nodes = registry.module_id_to_expanded_nodes(module_id).unwrap();
+    Ok(nodes.child_value_by_path(0, &[live_id!(A).as_instance(), live_id!(v).as_field()]).unwrap().clone())
+}
+
+fn float(expr: &str) -> f64 {
+    match eval(expr) {
+        Ok(LiveValue::Float64(v)) => v,
+        other => panic!("{} gave {:?}", expr, other)
+    }
+}
+
+fn color(expr: &str) -> Vec4 {
+    match eval(expr) {
+        Ok(LiveValue::Vec4(v)) => v,
+        other => panic!("{} gave {:?}", expr, other)
+    }
+}
+
+fn string(expr: &str) -> String {
+    match eval(expr) {
+        Ok(LiveValue::String(v)) => v.to_string(),
+        other => panic!("{} gave {:?}", expr, other)
+    }
+}
+
+fn error(expr: &str) -> String {
+    match eval(expr) {
+        Err(message) => message,
+        Ok(v) => panic!("{} gave {:?} instead of an error", expr, v)
+    }
+}
+
+#[test]
+fn min_max() {
+    assert_eq!(eval("min(3, 2)"), Ok(LiveValue::Int64(2)));
+    assert_eq!(eval("max(3, 2)"), Ok(LiveValue::Int64(3)));
+    assert_eq!(float("min(1.5, 2)"), 1.5);
+    assert_eq!(float("max(-1.5, -2.0)"), -1.5);
+}
+
+#[test]
+fn clamp() {
+    assert_eq!(eval("clamp(5, 0, 3)"), Ok(LiveValue::Int64(3)));
+    assert_eq!(float("clamp(-1.0, 0.0, 1.0)"), 0.0);
+    assert_eq!(float("clamp(0.25, 0.0, 1.0)"), 0.25);
+    assert!(error("clamp(1.0, 2.0, 0.0)").contains("lower bound"));
+}
+
+#[test]
+fn rounding() {
+    assert_eq!(float("round(2.5)"), 3.0);
+    assert_eq!(float("round(2.71828, 2)"), 2.72);
+    assert_eq!(float("floor(2.7)"), 2.0);
+    assert_eq!(float("ceil(2.1)"), 3.0);
+    assert_eq!(float("abs(-2.5)"), 2.5);
+    assert_eq!(eval("abs(-4)"), Ok(LiveValue::Int64(4)));
+}
+
+#[test]
+fn lighten_darken() {
+    let base = Vec4::from_u32(0x3366ccff);
+    let lighter = color("lighten(#36c, 0.1)");
+    let darker = color("darken(#36c, 0.1)");
+    assert!((lighter.to_oklcha().x - base.to_oklcha().x - 0.1).abs() < 0.01);
+    assert!((base.to_oklcha().x - darker.to_oklcha().x - 0.1).abs() < 0.01);
+    // the hue stays put
+    assert!((lighter.to_oklcha().z - base.to_oklcha().z).abs() < 1.0);
+    assert!(color("lighten(#fff, 0.5)").is_equal_enough(&vec4(1.0, 1.0, 1.0, 1.0), 0.01));
+    assert!(color("darken(#000, 0.5)").is_equal_enough(&vec4(0.0, 0.0, 0.0, 1.0), 0.01));
+}
+
+#[test]
+fn saturate_desaturate() {
+    let base = Vec4::from_u32(0x668899ff).to_oklcha();
+    assert!(color("saturate(#689, 0.5)").to_oklcha().y > base.y);
+    let grey = color("desaturate(#689, 1.0)");
+    assert!((grey.x - grey.y).abs() < 0.01 && (grey.y - grey.z).abs() < 0.01);
+}
+
+#[test]
+fn alpha() {
+    let c = color("alpha(#f00, 0.5)");
+    assert_eq!((c.x, c.y, c.z, c.w), (1.0, 0.0, 0.0, 0.5));
+}
+
+#[test]
+fn contrast() {
+    assert!((float("contrast(#000, #fff)") - 21.0).abs() < 0.01);
+    assert!((float("contrast(#777, #777)") - 1.0).abs() < 0.01);
+    assert_eq!(color("contrast_pick(#112, #fff, #000)").to_u32(), 0xffffffff);
+    assert_eq!(color("contrast_pick(#eee, #fff, #000)").to_u32(), 0x000000ff);
+}
+
+#[test]
+fn strings() {
+    assert_eq!(string("\"a\" + \"b\""), "ab");
+    assert_eq!(string("\"width: \" + 10"), "width: 10");
+    assert_eq!(string("format(\"{} of {}\", 1, 2.5)"), "1 of 2.5");
+    assert_eq!(string("format(\"{{{}}}\", true)"), "{true}");
+}
+
+#[test]
+fn interpolation_uses_definitions() {
+    assert_eq!(eval_with("format(\"{}px\", gap * 2)", "gap = 4"), Ok(LiveValue::String("8px".to_string().into())));
+}
+
+#[test]
+fn conditional() {
+    assert_eq!(eval("1 < 2 ? 10 : 20"), Ok(LiveValue::Int64(10)));
+    assert_eq!(eval("1 > 2 ? 10 : 20"), Ok(LiveValue::Int64(20)));
+    assert_eq!(eval("false ? 1 : true ? 2 : 3"), Ok(LiveValue::Int64(2)));
+    // the branch not taken isn't evaluated
+    assert_eq!(eval("true ? 1 : missing"), Ok(LiveValue::Int64(1)));
+    assert_eq!(eval("false ? min(1) : 2"), Ok(LiveValue::Int64(2)));
+}
+
+#[test]
+fn type_errors() {
+    assert!(error("min(1, true)").contains("min(): expected a number as argument 2"));
+    assert!(error("clamp(1, 2)").contains("clamp(): expected 3 arguments, got 2"));
+    assert!(error("lighten(1.0, 0.1)").contains("lighten(): expected a color as argument 1"));
+    assert!(error("alpha(#fff, \"half\")").contains("alpha(): expected a number as argument 2"));
+    assert!(error("format(1)").contains("format(): expected a string as argument 1"));
+    assert!(error("format(\"{} {}\", 1)").contains("more placeholders"));
+    assert!(error("format(\"{}\", 1, 2)").contains("placeholders but 2 arguments"));
+    assert!(error("1 ? 2 : 3").contains("?: expected a bool condition"));
+    assert!(error("unknown(1)").contains("not implemented"));
+}
@@ -0,0 +1,160 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    makepad_live_tokenizer::format::{format_live_design, format_live_design_macros},
+    LiveModuleId,
+    LiveNode,
+    LiveRegistry,
+    LiveTypeInfo,
+    LiveValue,
+    TextPos,
+};
+
+struct TypeA;
+
+fn types() -> Vec<LiveTypeInfo> {
+    vec![LiveTypeInfo {
+        live_type: std::any::TypeId::of::<TypeA>(),
+        type_name: live_id!(TypeA),
+        module_id: LiveModuleId::from_str("test::format").unwrap(),
+        live_ignore: false,
+        fields: Vec::new(),
+    }]
+}
+
+/// Sources that are badly formatted in all the ways the formatter fixes.
+const SOURCES: &[&str] = &[
+    "Button = <ButtonBase>{ width:Fit, height : Fit, }",
+    "
+      // the base
+    Base = {{TypeA}}{
+  width:100, // fixed
+      /* a block
+   comment */ height : Fit,
+
+
+    draw_bg:{
+ color:#f00,
+        inner: {a: 1, b: [1,2,3], c:((1+2)*-3)}
+  }
+    fn pixel(self)->vec4{
+            let x = 1.0;
+      if x > 0.5 {
+ return #fff
+ }
+        return   #000
+    }
+}
+",
+    "A = <View>{b = <View>{c = <View>{
+d: {e: {
+f: [1,
+2,
+3]
+}}
+}}}",
+    "Base = {{TypeA}} {\r\n\twidth: 100,\r\n\t// a comment\r\n}\r\n",
+];
+
+fn parse(registry: &mut LiveRegistry, source: &str) -> Vec<LiveNode> {
+    let index = registry.live_files.len();
+    let module_id = LiveModuleId::from_str(&format!("test::format{}", index)).unwrap();
+    let file_id = registry.register_live_file(&format!("format{}.rs", index), "", module_id, source.to_string(), types(), TextPos::default())
+        .unwrap_or_else( | e | panic!("{}\n{}", e.message, source));
+    registry.live_files[file_id.to_index()].original.nodes.clone()
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for source in SOURCES {
+        let formatted = format_live_design(source, "");
+        assert_eq!(format_live_design(&formatted, ""), formatted, "formatting again changed\n{}", formatted);
+        let indented = format_live_design(source, "        ");
+        assert_eq!(format_live_design(&indented, "        "), indented);
+    }
+}
+
+#[test]
+fn formatting_keeps_the_meaning() {
+    for source in SOURCES {
+        let mut registry = LiveRegistry::default();
+        let nodes = parse(&mut registry, source);
+        let formatted = format_live_design(source, "");
+        let reformatted = parse(&mut registry, &formatted);
+        assert_eq!(nodes.len(), reformatted.len(), "node count differs for\n{}", formatted);
+        for (a, b) in nodes.iter().zip(&reformatted) {
+            assert_eq!(a.id, b.id, "in\n{}", formatted);
+            match (&a.value, &b.value) {
+                // shader code keeps its tokens, only their spans move
+                (LiveValue::DSL {..}, LiveValue::DSL {..}) => (),
+                (a, b) => assert_eq!(a, b, "in\n{}", formatted),
+            }
+        }
+    }
+}
+
+#[test]
+fn comments_stay_where_they_were() {
+    assert_eq!(format_live_design(SOURCES[1], ""), "\
+// the base
+Base = {{TypeA}} {
+    width: 100 // fixed
+    /* a block
+   comment */ height: Fit
+
+    draw_bg: {
+        color: #f00
+        inner: {a: 1, b: [1, 2, 3], c: ((1 + 2) * -3)}
+    }
+    fn pixel(self) -> vec4 {
+        let x = 1.0;
+        if x > 0.5 {
+            return #fff
+        }
+        return   #000
+    }
+}");
+    assert_eq!(format_live_design(SOURCES[3], ""), "\
+Base = {{TypeA}} {
+    width: 100
+    // a comment
+}");
+}
+
+#[test]
+fn nested_blocks_indent_once_per_line() {
+    // braces opened on the same line only indent what follows by one level
+    assert_eq!(format_live_design(SOURCES[2], "    "), "    \
+    A = <View> {b = <View> {c = <View> {
+        d: {e: {
+            f: [1,
+                2,
+                3]
+        }}
+    }}}");
+}
+
+#[test]
+fn only_live_design_bodies_are_formatted() {
+    let source = "\
+use makepad_widgets::*;
+
+live_design!{
+    // buttons
+    Button = <View>{ label = <Label>{text:\"hi\"}, }
+}
+
+fn main()   {   }
+";
+    let formatted = format_live_design_macros(source);
+    assert_eq!(formatted, "\
+use makepad_widgets::*;
+
+live_design!{
+    // buttons
+    Button = <View> {label = <Label> {text: \"hi\"}}
+}
+
+fn main()   {   }
+");
+    assert_eq!(format_live_design_macros(&formatted), formatted);
+}
@@ -0,0 +1,332 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    LiveModuleId,
+    LiveNode,
+    LiveNodeSliceToLiveDesign,
+    LiveRegistry,
+    LiveTypeInfo,
+    LiveValue,
+    TextPos,
+};
+
+struct TypeA;
+struct TypeB;
+
+fn type_info<T: 'static>(type_name: LiveId) -> LiveTypeInfo {
+    LiveTypeInfo {
+        live_type: std::any::TypeId::of::<T>(),
+        type_name,
+        module_id: LiveModuleId::from_str("test::printer").unwrap(),
+        live_ignore: false,
+        fields: Vec::new(),
+    }
+}
+
+/// Parses `source` as a new file and returns its nodes. `{{..}}` in the
+/// source take the types from `types` in order.
+fn parse(registry: &mut LiveRegistry, source: &str, types: &[LiveTypeInfo]) -> Result<Vec<LiveNode>, String> {
+    let index = registry.live_files.len();
+    let module_id = LiveModuleId::from_str(&format!("test::printer{}", index)).unwrap();
+    let file_id = registry.register_live_file(&format!("printer{}.rs", index), "", module_id, source.to_string(), types.to_vec(), TextPos::default())
+        .map_err( | e | format!("{}\n{}", e.message, source)) ?;
+    Ok(registry.live_files[file_id.to_index()].original.nodes.clone())
+}
+
+fn assert_same_nodes(a: &[LiveNode], b: &[LiveNode], source: &str) {
+    assert_eq!(a.len(), b.len(), "node count differs for\n{}", source);
+    for (a, b) in a.iter().zip(b) {
+        assert_eq!(a.id, b.id, "in\n{}", source);
+        assert_eq!(a.origin.prop_type(), b.origin.prop_type(), "prop type of {} in\n{}", a.id, source);
+        assert_eq!(a.origin.node_has_prefix(), b.origin.node_has_prefix(), "prefix of {} in\n{}", a.id, source);
+        match (&a.value, &b.value) {
+            // the tokens moved, printing them again checks they are the same
+            (LiveValue::DSL {..}, LiveValue::DSL {..}) => (),
+            (a, b) => assert_eq!(a, b, "in\n{}", source),
+        }
+    }
+}
+
+/// Parses, prints, parses the print again and checks both give the same
+/// nodes and printing is stable. Returns the print.
+fn round_trip_with(source: &str, types: &[LiveTypeInfo]) -> String {
+    let mut registry = LiveRegistry::default();
+    let nodes = parse(&mut registry, source, types).unwrap();
+    let printed = nodes.to_live_design(0, &registry).unwrap();
+    let reparsed = parse(&mut registry, &printed, types).unwrap();
+    assert_same_nodes(&nodes, &reparsed, &printed);
+    assert_eq!(reparsed.to_live_design(0, &registry).unwrap(), printed);
+    printed
+}
+
+fn round_trip(source: &str) -> String {
+    round_trip_with(source, &[])
+}
+
+#[test]
+fn classes_and_instances() {
+    let types = [type_info::<TypeA>(live_id!(TypeA)), type_info::<TypeB>(live_id!(TypeB))];
+    let printed = round_trip_with("
+        Base = {{TypeA}} {width: 100, height: Fit}
+        Button = {{TypeB}}<Base> {
+            draw_bg: {color: #f00}
+            instance hover: 0.0
+            label = <Label dx:10.0 dy:-20.5 dw:300.0 dh:40.0> {text: \"Hi\"}
+            <View> {}
+            <View> {spacing: 2}
+            config: struct {enabled: true}
+        }
+    ", &types);
+    assert_eq!(printed, "\
+Base = {{TypeA}} {
+    width: 100
+    height: Fit
+}
+Button = {{TypeB}}<Base> {
+    draw_bg: {
+        color: #ff0000ff
+    }
+    instance hover: 0.0
+    label = <Label dx:10.0 dy:-20.5 dw:300.0 dh:40.0> {
+        text: \"Hi\"
+    }
+    <View> {}
+    <View> {
+        spacing: 2
+    }
+    config: struct {
+        enabled: true
+    }
+}
+");
+}
+
+#[test]
+fn values() {
+    let printed = round_trip("
+        A = {
+            int: -3, float: 1.0, small: 0.0000001, negative: -2.5
+            color: #336699ff, hex: #1e5
+            dep: dep(\"crate://self/resources/icon.svg\")
+            v2: vec2(1.0, -0.5), v3: vec3(0.1, 0.2, 0.3), v4: vec4(1, 2, 3, 4)
+            text: \"quote \\\" slash \\\\ line \\n end\"
+            padded: r#\"  leading spaces\"#
+            id: some_id, path: a.b.c, bare: Fill
+            tuple: Fixed(10.0), named: Flow {spacing: 1.5, wrap: true}
+            list: [1, 2.5, \"three\", Four]
+            nested: [<Button> {text: \"a\"}, {x: 1}, Fixed(2)]
+            empty: {}
+        }
+    ");
+    assert!(printed.contains("small: 0.0000001\n"));
+    assert!(printed.contains("color: #336699ff\n"));
+    assert!(printed.contains("hex: #x11ee55ff\n"));
+    assert!(printed.contains("dep: dep(\"crate://self/resources/icon.svg\")\n"));
+    assert!(printed.contains("v4: vec4(1.0, 2.0, 3.0, 4.0)\n"));
+    assert!(printed.contains("padded: r#\"  leading spaces\"#\n"));
+    assert!(printed.contains("list: [1, 2.5, \"three\", Four]\n"));
+    assert!(printed.contains("nested: [\n        <Button> {\n            text: \"a\"\n        },\n"));
+    assert!(printed.contains("named: Flow {\n        spacing: 1.5\n        wrap: true\n    }\n"));
+}
+
+#[test]
+fn expressions() {
+    let printed = round_trip("
+        A = {
+            a: (1 + 2 * 3)
+            b: ((1 + 2) * 3)
+            c: (10 - (4 - 3))
+            d: (-(gap + 1) / 2)
+            e: (!visible && (x < 3 || y >= 4))
+            f: (theme.space.gap * 2)
+            g: (min(a, max(b, 2)) + lighten(#fff, 0.1))
+            h: (wide ? (compact ? 1 : 2) : 3)
+            i: ((wide ? 1 : 2) + 1)
+            j: (\"size: \" + format(\"{}px\", gap))
+        }
+    ");
+    assert!(printed.contains("a: (1 + 2 * 3)\n"));
+    assert!(printed.contains("b: ((1 + 2) * 3)\n"));
+    assert!(printed.contains("c: (10 - (4 - 3))\n"));
+    assert!(printed.contains("d: (-(gap + 1) / 2)\n"));
+    assert!(printed.contains("e: (!visible && (x < 3 || y >= 4))\n"));
+    assert!(printed.contains("h: (wide ? compact ? 1 : 2 : 3)\n"));
+    assert!(printed.contains("i: ((wide ? 1 : 2) + 1)\n"));
+}
+
+#[test]
+fn conditions() {
+    let printed = round_trip("
+        A = <View> {
+            if window_width < 800 {
+                flow: Down
+                <Label> {}
+            }
+            else if os == android {flow: Right}
+            else {flow: Overlay}
+            <Label> {}
+        }
+    ");
+    assert!(printed.contains("    if window_width < 800 {\n"));
+    assert!(printed.contains("    else if os == android {\n"));
+    assert!(printed.contains("    else {\n        flow: Overlay\n    }\n"));
+}
+
+#[test]
+fn shaders_and_imports() {
+    let printed = round_trip_with("
+        import crate::theme::*
+        import makepad_draw::shader::std::Sdf2d as Sdf
+        DrawBg = {{TypeA}} {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(1., 1., self.rect_size.x - 2.0, 10.0, 4.0);
+
+                return sdf.fill(#f00);
+            }
+            fn get_color(self) -> vec4 {return mix(self.color, #fff, self.hover)}
+        }
+    ", &[type_info::<TypeA>(live_id!(DrawBg))]);
+    assert!(printed.contains("import test::theme::*\n"));
+    assert!(printed.contains("import makepad_draw::shader::std::Sdf2d as Sdf\n"));
+    assert!(printed.contains("    fn pixel(self) -> vec4 {\n        let sdf = Sdf2d::viewport(self.pos * self.rect_size);\n"));
+    assert!(printed.contains("1.0, 1.0, self.rect_size.x - 2.0"));
+    assert!(printed.contains(";\n\n        return sdf.fill(#ff0000ff);\n    }\n"));
+    assert!(printed.contains("    fn get_color(self) -> vec4 {return mix(self.color, #ffffffff, self.hover)}\n"));
+}
+
+#[test]
+fn single_property() {
+    let mut registry = LiveRegistry::default();
+    let nodes = parse(&mut registry, "A = {b: <View> {width: 10}}", &[]).unwrap();
+    assert_eq!(nodes.to_live_design(2, &registry).unwrap(), "b: <View> {\n    width: 10\n}\n");
+}
+
+#[test]
+fn unprintable_values() {
+    let registry = LiveRegistry::default();
+    let nodes = vec![
+        LiveNode::from_value(LiveValue::Object),
+        LiveNode::from_id_value(live_id!(a), LiveValue::Float64(f64::NAN)),
+        LiveNode::from_value(LiveValue::Close),
+    ];
+    assert!(nodes.to_live_design(0, &registry).unwrap_err().contains("NaN"));
+    let nodes = vec![
+        LiveNode::from_value(LiveValue::Object),
+        LiveNode::from_id_value(live_id!(a), LiveValue::None),
+        LiveNode::from_value(LiveValue::Close),
+    ];
+    assert!(nodes.to_live_design(0, &registry).is_err());
+}
+
+/// A small xorshift generator, so the generated documents are the same on every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+const NAMES: [&str; 8] = ["width", "height", "color", "text", "margin", "flow", "visible", "gap"];
+
+fn gen_float(rng: &mut Rng) -> String {
+    let v = (rng.next() >> 11) as f64 / (1u64 << 53) as f64 * 1000.0 - 500.0;
+    if rng.below(2) == 0 {format!("{}.0", v.round())} else {format!("{}", v)}
+}
+
+fn gen_string(rng: &mut Rng) -> String {
+    let parts = ["a", "b c", "\\\"", "\\\\", "\\n", " ", "{}", "é"];
+    let mut s = String::new();
+    for _ in 0..rng.below(5) {
+        s.push_str(rng.pick(&parts));
+    }
+    // leading spaces are only kept in raw strings
+    if s.starts_with(' ') {format!("r#\"{}\"#", s)} else {format!("\"{}\"", s)}
+}
+
+fn gen_expr(rng: &mut Rng, depth: u32) -> String {
+    if depth == 0 || rng.below(3) == 0 {
+        return match rng.below(5) {
+            0 => format!("{}", rng.below(100)),
+            1 => gen_float(rng).trim_start_matches('-').to_string(),
+            2 => rng.pick(&NAMES).to_string(),
+            3 => rng.pick(&["true", "false"]).to_string(),
+            _ => gen_string(rng),
+        }
+    }
+    match rng.below(6) {
+        0 => format!("-{}", gen_expr(rng, depth - 1)),
+        1 => format!("min({}, {})", gen_expr(rng, depth - 1), gen_expr(rng, depth - 1)),
+        2 => format!("({} ? {} : {})", gen_expr(rng, depth - 1), gen_expr(rng, depth - 1), gen_expr(rng, depth - 1)),
+        3 => format!("theme.{}", rng.pick(&NAMES)),
+        _ => {
+            let op = rng.pick(&["+", "-", "*", "/", "<", ">=", "==", "!=", "&&", "||"]);
+            let (a, b) = (gen_expr(rng, depth - 1), gen_expr(rng, depth - 1));
+            if rng.below(2) == 0 {format!("({} {} {})", a, op, b)} else {format!("{} {} {}", a, op, b)}
+        }
+    }
+}
+
+fn gen_value(rng: &mut Rng, depth: u32) -> String {
+    let simple = depth == 0 || rng.below(2) == 0;
+    if simple {
+        return match rng.below(9) {
+            0 => format!("{}", rng.below(2000) as i64 - 1000),
+            1 => gen_float(rng),
+            2 => format!("#{:08x}", rng.next() as u32),
+            3 => gen_string(rng),
+            4 => rng.pick(&["Fit", "Fill", "Down", "Right"]).to_string(),
+            5 => format!("vec2({}, {})", rng.below(10), gen_float(rng)),
+            6 => format!("dep(\"crate://self/{}.png\")", rng.below(10)),
+            7 => rng.pick(&["true", "false", "some_id", "a.b"]).to_string(),
+            _ => format!("({})", gen_expr(rng, 3)),
+        }
+    }
+    match rng.below(6) {
+        0 => format!("[{}]", (0..rng.below(4)).map( | _ | gen_value(rng, depth - 1)).collect::<Vec<_>>().join(", ")),
+        1 => format!("Fixed({})", gen_value(rng, depth - 1)),
+        2 => format!("Flow {{{}}}", gen_fields(rng, depth - 1)),
+        3 => format!("<{}> {{{}}}", rng.pick(&["View", "Button"]), gen_props(rng, depth - 1)),
+        4 => format!("struct {{{}}}", gen_props(rng, depth - 1)),
+        _ => format!("{{{}}}", gen_props(rng, depth - 1)),
+    }
+}
+
+fn gen_props(rng: &mut Rng, depth: u32) -> String {
+    let mut props = Vec::new();
+    for _ in 0..rng.below(4) {
+        let name = rng.pick(&NAMES);
+        props.push(match rng.below(8) {
+            0 => format!("{} = {}", name, gen_value(rng, depth)),
+            1 => format!("<View> {{{}}}", gen_props(rng, depth.saturating_sub(1))),
+            2 => format!("if {} {{{}}} else {{{}}}", gen_expr(rng, 2), gen_props(rng, depth.saturating_sub(1)), gen_props(rng, 0)),
+            _ => format!("{}: {}", name, gen_value(rng, depth)),
+        });
+    }
+    props.join("\n")
+}
+
+/// The body of a named enum, which only holds fields.
+fn gen_fields(rng: &mut Rng, depth: u32) -> String {
+    (0..rng.below(4)).map( | _ | format!("{}: {}", rng.pick(&NAMES), gen_value(rng, depth))).collect::<Vec<_>>().join(", ")
+}
+
+#[test]
+fn generated_documents() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..300 {
+        let source = format!("Doc = {{{}}}", gen_props(&mut rng, 3));
+        round_trip(&source);
+    }
+}
@@ -0,0 +1,86 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    live_schema_diff,
+    LiveFieldDoc,
+    LiveModuleId,
+    LiveRegistry,
+    LiveTypeDoc,
+    LiveTypeInfo,
+    TextPos,
+};
+
+struct TypeA;
+
+fn type_a_doc() -> LiveTypeDoc {
+    LiveTypeDoc {
+        rust_name: "TypeA",
+        module_path: "test::schema",
+        doc: vec![" A type with a width."],
+        fields: vec![LiveFieldDoc {
+            name: "width",
+            rust_type: "f64",
+            attribute: "live",
+            default: Some("50.0"),
+            doc: vec![" The width in pixels."],
+        }],
+        variants: Vec::new(),
+    }
+}
+
+/// The schema of a registry holding `TypeA`, with its doc registered if `with_doc` is set.
+fn schema(source: &str, with_doc: bool) -> String {
+    let module_id = LiveModuleId::from_str("test::schema").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    let live_type = std::any::TypeId::of::<TypeA>();
+    let types = vec![LiveTypeInfo {
+        live_type,
+        type_name: live_id!(TypeA),
+        module_id,
+        live_ignore: false,
+        fields: Vec::new(),
+    }];
+    if with_doc {
+        live_registry.type_docs.insert(live_type, type_a_doc);
+    }
+    live_registry.register_live_file("schema.rs", "", module_id, source.to_string(), types, TextPos::default()).ok().unwrap();
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty());
+    live_registry.live_schema_json()
+}
+
+#[test]
+fn schema_joins_the_type_doc_with_the_dsl_defaults() {
+    let json = schema("TypeA = {{TypeA}} {width: 100}", true);
+    for needle in [
+        "\"name\": \"TypeA\",",
+        "\"doc\": \"A type with a width.\"",
+        "{\"name\": \"width\", \"type\": \"f64\", \"attribute\": \"live\", \"default\": \"50.0\", \"dsl_default\": \"100\", \"doc\": \"The width in pixels.\"}",
+    ] {
+        assert!(json.contains(needle), "missing `{}` in\n{}", needle, json);
+    }
+    // the type is still exported without a doc, from what the type info knows
+    let json = schema("TypeA = {{TypeA}} {width: 100}", false);
+    assert!(json.contains("\"name\": \"TypeA\","), "{}", json);
+    assert!(!json.contains("width"), "{}", json);
+}
+
+#[test]
+fn schema_diff_reports_the_first_differing_line() {
+    let committed = schema("TypeA = {{TypeA}} {width: 100}", true);
+    assert_eq!(live_schema_diff(&committed, &committed), None);
+
+    let generated = schema("TypeA = {{TypeA}} {width: 200}", true);
+    let diff = live_schema_diff(&committed, &generated).unwrap();
+    let line = committed.lines().position( | line | line.contains("\"dsl_default\": \"100\"")).unwrap() + 1;
+    assert!(diff.starts_with(&format!("live schema differs at line {}:\n", line)), "{}", diff);
+    assert!(diff.contains("\n- ") && diff.contains("\"dsl_default\": \"100\""), "{}", diff);
+    assert!(diff.contains("\n+ ") && diff.contains("\"dsl_default\": \"200\""), "{}", diff);
+
+    // a schema that lost its last lines differs where the shorter one ends
+    let truncated: String = committed.lines().take(3).map( | line | format!("{}\n", line)).collect();
+    let diff = live_schema_diff(&truncated, &committed).unwrap();
+    assert!(diff.starts_with("live schema differs at line 4:\n- <end of file>\n+ "), "{}", diff);
+    let diff = live_schema_diff(&committed, &truncated).unwrap();
+    assert!(diff.ends_with("\n+ <end of file>"), "{}", diff);
+}
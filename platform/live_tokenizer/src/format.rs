@@ -0,0 +1,302 @@
+//! A formatter for the body of `live_design!`, which rustfmt leaves alone.
+//!
+//! It keeps the line structure and comments of the source, and normalizes the
+//! rest: indentation follows the nesting of braces, brackets and parens, there
+//! is one space after `:` and around `=` and other operators, `<Class> {..}`
+//! and `{{Type}} {..}` get a single space before the brace, and commas ending a
+//! line or a block of properties are dropped as the parser doesn't need them.
+//! The bodies of shader functions are only reindented. Formatting formatted
+//! source changes nothing.
+
+use crate::{
+    full_token::{Delim, FullToken},
+    tokenizer::{Cursor, State},
+};
+
+const INDENT: &str = "    ";
+
+struct Token {
+    token: FullToken,
+    text: String,
+    /// the whitespace in front of the token on its own line
+    space_before: String,
+    newlines_before: usize,
+}
+
+impl Token {
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.token, FullToken::Punct(_)) && self.text == punct
+    }
+
+    fn is_open(&self) -> bool {
+        matches!(self.token, FullToken::Open(_))
+    }
+
+    fn is_close(&self) -> bool {
+        matches!(self.token, FullToken::Close(_))
+    }
+
+    fn is_line_comment(&self) -> bool {
+        self.token.is_comment() && self.text.starts_with("//")
+    }
+
+    /// Punctuation that has spaces around it.
+    fn is_operator(&self) -> bool {
+        matches!(self.token, FullToken::Punct(_)) && !matches!(self.text.as_str(), "," | ";" | ":" | "::" | "." | "#")
+    }
+}
+
+struct Group {
+    delim: Delim,
+    line: usize,
+    code: bool,
+}
+
+/// Formats the source between the braces of `live_design!`. Every line is
+/// indented with `indent` plus four spaces per level; there is no trailing
+/// newline.
+///
+/// ```
+/// use makepad_live_tokenizer::format::format_live_design;
+///
+/// let source = "Button = <ButtonBase>{ width:Fit, height : Fit, }\n";
+/// let formatted = format_live_design(source, "");
+/// assert_eq!(formatted, "Button = <ButtonBase> {width: Fit, height: Fit}");
+/// assert_eq!(format_live_design(&formatted, ""), formatted);
+/// ```
+pub fn format_live_design(source: &str, indent: &str) -> String {
+    let tokens = tokenize(source);
+    // `<Class>` angle brackets don't get the spaces comparisons would
+    let mut class_open = vec![false; tokens.len()];
+    let mut class_close = vec![false; tokens.len()];
+    for index in 0..tokens.len().saturating_sub(2) {
+        if tokens[index].is_punct("<")
+            && matches!(tokens[index + 1].token, FullToken::Ident(_))
+            && tokens[index + 2].is_punct(">") {
+            class_open[index] = true;
+            class_close[index + 2] = true;
+        }
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut stack: Vec<Group> = Vec::new();
+    let mut fn_pending = false;
+    let mut index = 0;
+    while index < tokens.len() {
+        // one source line
+        let start = index;
+        index += 1;
+        while index < tokens.len() && tokens[index].newlines_before == 0 {
+            index += 1;
+        }
+        let line = &tokens[start..index];
+
+        let leading_closes = line.iter().take_while( | t | t.is_close()).count();
+        let open_len = stack.len().saturating_sub(leading_closes);
+        let mut level = 0;
+        for (i, group) in stack[..open_len].iter().enumerate() {
+            if i == 0 || stack[i - 1].line != group.line {
+                level += 1;
+            }
+        }
+
+        let blank_before = line[0].newlines_before > 1
+            && !lines.is_empty()
+            && leading_closes == 0
+            && !lines.last().is_some_and( | l | l.ends_with(['{', '(', '[']));
+        if blank_before {
+            lines.push(String::new());
+        }
+
+        let mut out = String::new();
+        let mut prev: Option<usize> = None;
+        for (offset, token) in line.iter().enumerate() {
+            let i = start + offset;
+            let code = stack.last().is_some_and( | g | g.code);
+            // commas ending a line or a block of properties
+            if token.is_punct(",") && !code && stack.last().is_none_or( | g | g.delim == Delim::Brace) {
+                let rest = &line[offset + 1..];
+                let next = rest.iter().find( | t | !t.is_line_comment());
+                if next.is_none_or( | t | t.token == FullToken::Close(Delim::Brace)) {
+                    continue;
+                }
+            }
+            if let Some(prev) = prev {
+                if code {
+                    out.push_str(&token.space_before);
+                }
+                else if space_between(&tokens, prev, i, &class_open, &class_close) {
+                    out.push(' ');
+                }
+            }
+            out.push_str(&token.text);
+            prev = Some(i);
+
+            match token.token {
+                FullToken::Ident(_) if token.text == "fn" && !code => fn_pending = true,
+                FullToken::Open(delim) => {
+                    let code = code || (fn_pending && delim == Delim::Brace);
+                    if delim == Delim::Brace {
+                        fn_pending = false;
+                    }
+                    stack.push(Group {delim, line: start, code});
+                }
+                FullToken::Close(_) => {
+                    stack.pop();
+                }
+                _ => ()
+            }
+        }
+        let out = out.trim_end();
+        if out.is_empty() {
+            continue;
+        }
+        let mut text = String::from(indent);
+        for _ in 0..level {
+            text.push_str(INDENT);
+        }
+        text.push_str(out);
+        lines.push(text);
+    }
+    lines.join("\n")
+}
+
+/// Formats the body of every `live_design!` in a Rust source file, leaving
+/// everything else as it is.
+pub fn format_live_design_macros(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut spans = Vec::new();
+    let mut scratch = String::new();
+    let mut cursor = Cursor::new(&chars, &mut scratch);
+    let mut state = State::default();
+    let mut pos = 0;
+    while let (next_state, Some(token)) = state.next(&mut cursor) {
+        spans.push((token.token, pos, pos + token.len));
+        pos += token.len;
+        state = next_state;
+    }
+    let code: Vec<&(FullToken, usize, usize)> = spans.iter().filter( | (t, _, _) | !t.is_whitespace() && !t.is_comment()).collect();
+
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut index = 0;
+    while index + 2 < code.len() {
+        let is_macro = matches!(code[index].0, FullToken::Ident(_))
+            && chars[code[index].1..code[index].2].iter().collect::<String>() == "live_design"
+            && matches!(code[index + 1].0, FullToken::Punct(_))
+            && chars[code[index + 1].1] == '!'
+            && code[index + 2].0 == FullToken::Open(Delim::Brace);
+        if !is_macro {
+            index += 1;
+            continue;
+        }
+        let open = index + 2;
+        let mut depth = 0;
+        let mut close = None;
+        for (i, token) in code.iter().enumerate().skip(open) {
+            match token.0 {
+                FullToken::Open(_) => depth += 1,
+                FullToken::Close(_) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(i);
+                        break
+                    }
+                }
+                _ => ()
+            }
+        }
+        let Some(close) = close else {
+            break
+        };
+        let body_start = code[open].2;
+        let body_end = code[close].1;
+        let line_start = chars[..code[index].1].iter().rposition( | c | *c == '\n').map( | i | i + 1).unwrap_or(0);
+        let base: String = chars[line_start..].iter().take_while( | c | **c == ' ' || **c == '\t').collect();
+        let body: String = chars[body_start..body_end].iter().collect();
+        let formatted = format_live_design(&body, &format!("{}{}", base, INDENT));
+
+        out.extend(&chars[copied..body_start]);
+        if !formatted.is_empty() {
+            out.push('\n');
+            out.push_str(&formatted);
+            out.push('\n');
+            out.push_str(&base);
+        }
+        copied = body_end;
+        index = close + 1;
+    }
+    out.extend(&chars[copied..]);
+    out
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut scratch = String::new();
+    let mut cursor = Cursor::new(&chars, &mut scratch);
+    let mut state = State::default();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut space_before = String::new();
+    let mut newlines_before = 0;
+    while let (next_state, Some(token)) = state.next(&mut cursor) {
+        let text: String = chars[pos..pos + token.len].iter().collect();
+        pos += token.len;
+        state = next_state;
+        if token.token.is_whitespace() {
+            let newlines = text.matches('\n').count();
+            if newlines > 0 {
+                newlines_before += newlines;
+                space_before.clear();
+            }
+            space_before.push_str(text.rsplit('\n').next().unwrap_or("").trim_end_matches('\r'));
+            continue;
+        }
+        // the first token starts a line as well
+        let newlines = if tokens.is_empty() {newlines_before.max(1)} else {newlines_before};
+        tokens.push(Token {
+            token: token.token,
+            text: text.trim_end().to_string(),
+            space_before: std::mem::take(&mut space_before),
+            newlines_before: newlines,
+        });
+        newlines_before = 0;
+    }
+    tokens
+}
+
+/// Whether a space goes between two tokens on a line outside of shader code.
+fn space_between(tokens: &[Token], prev: usize, next: usize, class_open: &[bool], class_close: &[bool]) -> bool {
+    let (a, b) = (&tokens[prev], &tokens[next]);
+    if a.is_open() || b.is_close() {
+        return false
+    }
+    if b.token.is_comment() || a.token.is_comment() {
+        return true
+    }
+    if b.is_punct(",") || b.is_punct(";") || b.is_punct(":") || b.is_punct("::") || b.is_punct(".") {
+        return false
+    }
+    if a.is_punct(",") || a.is_punct(";") || a.is_punct(":") {
+        return true
+    }
+    if a.is_punct("::") || a.is_punct(".") || class_open[prev] || class_close[next] {
+        return false
+    }
+    // unary minus and not
+    if (a.is_punct("-") || a.is_punct("!")) && is_unary(tokens, prev) {
+        return false
+    }
+    match b.token {
+        FullToken::Open(Delim::Paren) | FullToken::Open(Delim::Bracket) => a.is_operator(),
+        _ => true
+    }
+}
+
+fn is_unary(tokens: &[Token], index: usize) -> bool {
+    if tokens[index].is_punct("!") || index == 0 {
+        return true
+    }
+    let prev = &tokens[index - 1];
+    tokens[index].newlines_before > 0 || matches!(prev.token, FullToken::Punct(_) | FullToken::Open(_))
+}
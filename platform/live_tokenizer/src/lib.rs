@@ -3,6 +3,7 @@ pub mod vec4_ext;
 pub mod full_token;
 pub mod tokenizer;
 pub mod colorhex;
+pub mod format;
 
 #[macro_use]
 pub mod live_error_origin;
@@ -329,12 +329,12 @@ impl<'a> DrawShaderAnalyser<'a> {
             DrawShaderFieldKind::Texture {..} => {
                 let ty = self.ty_checker().ty_check_ty_expr(&decl.ty_expr) ?;
                 match ty {
-                    Ty::Texture2D | Ty::TextureOES => {}
+                    Ty::Texture2D | Ty::TextureOES | Ty::TextureCube | Ty::Texture2DArray | Ty::Texture3D => {}
                     _ => {
                         return Err(LiveError {
                             origin: live_error_origin!(),
                             span: decl.span.into(),
-                            message: String::from("texture must be either a texture2D or a textureOES (android)"),
+                            message: String::from("texture must be a texture2D, textureCube, texture2DArray, texture3D or a textureOES (android)"),
                         })
                     }
                 }
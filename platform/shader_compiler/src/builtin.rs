@@ -342,12 +342,21 @@ pub fn generate_builtins()-> HashMap<Ident, Builtin>{
         builtin!(sample2d, [
             (Ty::Texture2D, Ty::Vec2) -> Ty::Vec4
         ]),
+        builtin!(sample2d_array, [
+            (Ty::Texture2DArray, Ty::Vec2, Ty::Float) -> Ty::Vec4
+        ]),
         builtin!(sample2d_rt, [
             (Ty::Texture2D, Ty::Vec2) -> Ty::Vec4
         ]),
         builtin!(sample2dOES, [
             (Ty::TextureOES, Ty::Vec2) -> Ty::Vec4
         ]),
+        builtin!(sample3d, [
+            (Ty::Texture3D, Ty::Vec3) -> Ty::Vec4
+        ]),
+        builtin!(sample_cube, [
+            (Ty::TextureCube, Ty::Vec3) -> Ty::Vec4
+        ]),
         builtin!(sign, [
             (Ty::Float) -> Ty::Float,
             (Ty::Vec2) -> Ty::Vec2,
@@ -122,14 +122,16 @@ pub fn generate_cons_fn(backend_writer:&dyn BackendWriter, string: &mut String,
     if param_tys.len() == 1 {
         let param_ty = &param_tys[0];
         match param_ty {
-            Ty::Bool | Ty::Int | Ty::Float => {
+            Ty::Bool | Ty::Int | Ty::Uint | Ty::Float => {
                 let mut sep = "";
                 for _ in 0..ty.slots() {
                     write!(string, "{}x", sep).unwrap();
                     sep = ", ";
                 }
             }
-            Ty::Vec2 | Ty::Vec3 | Ty::Vec4 => {
+            Ty::Vec2 | Ty::Vec3 | Ty::Vec4
+                | Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4
+                | Ty::Uvec2 | Ty::Uvec3 | Ty::Uvec4 => {
                 write!(string,"x");
             }
             Ty::Mat2 | Ty::Mat3 | Ty::Mat4 => {
@@ -42,6 +42,14 @@ pub fn generate_pixel_shader(draw_shader_def: &DrawShaderDef, const_table: &Draw
     string
 }
 
+/// GLSL ES 1.00 has no unsigned integers and no array or 3D samplers, shaders
+/// using them have to be compiled as GLSL ES 3.00 instead.
+pub fn needs_glsl_es3(source: &str) -> bool {
+    source
+        .split( | c: char | !c.is_ascii_alphanumeric() && c != '_')
+        .any( | token | matches!(token, "uint" | "uvec2" | "uvec3" | "uvec4" | "sampler2DArray" | "sampler3D"))
+}
+
 struct DrawShaderGenerator<'a> {
     draw_shader_def: &'a DrawShaderDef,
     shader_registry: &'a ShaderRegistry,
@@ -65,6 +73,10 @@ impl<'a> DrawShaderGenerator<'a> {
                 Ty::Ivec2 => "ivec2(0)",
                 Ty::Ivec3 => "ivec3(0)",
                 Ty::Ivec4 => "ivec4(0)",
+                Ty::Uint => "0u",
+                Ty::Uvec2 => "uvec2(0u)",
+                Ty::Uvec3 => "uvec3(0u)",
+                Ty::Uvec4 => "uvec4(0u)",
                 Ty::Vec2 => "vec2(0.0)",
                 Ty::Vec3 => "vec3(0.0)",
                 Ty::Vec4 => "vec4(0.0)",
@@ -554,6 +566,10 @@ impl<'a> DrawShaderGenerator<'a> {
             Ty::Ivec2 => write!(self.string, "ivec2(int({0}[{1}]), int({0}[{2}]))", prefix, s, s + 1),
             Ty::Ivec3 => write!(self.string, "ivec3(int({0}[{1}]), int({0}[{2}]), int({0}[{3}]))", prefix, s, s + 1, s + 2),
             Ty::Ivec4 => write!(self.string, "ivec4(int({0}[{1}]), int({0}[{2}]), int({0}[{3}]), int({0}[{4}]))", prefix, s, s + 1, s + 2, s + 3),
+            Ty::Uint => write!(self.string, "uint({}[{}])", prefix, s),
+            Ty::Uvec2 => write!(self.string, "uvec2(uint({0}[{1}]), uint({0}[{2}]))", prefix, s, s + 1),
+            Ty::Uvec3 => write!(self.string, "uvec3(uint({0}[{1}]), uint({0}[{2}]), uint({0}[{3}]))", prefix, s, s + 1, s + 2),
+            Ty::Uvec4 => write!(self.string, "uvec4(uint({0}[{1}]), uint({0}[{2}]), uint({0}[{3}]), uint({0}[{4}]))", prefix, s, s + 1, s + 2, s + 3),
             Ty::Vec2 => write!(self.string, "vec2({0}[{1}], {0}[{2}])", prefix, s, s + 1),
             Ty::Vec3 => write!(self.string, "vec3({0}[{1}], {0}[{2}], {0}[{3}])", prefix, s, s + 1, s + 2),
             Ty::Vec4 => write!(self.string, "vec4({0}[{1}], {0}[{2}], {0}[{3}], {0}[{4}])", prefix, s, s + 1, s + 2, s + 3),
@@ -826,6 +842,11 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Int);
                 write!(string, " {}", ident).unwrap();
             }
+            Ty::Uint => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uint);
+                write!(string, " {}", ident).unwrap();
+            }
             Ty::Float => {
                 prefix(string, sep, is_inout);
                 self.write_ty_lit(string, TyLit::Float);
@@ -861,6 +882,21 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Ivec4);
                 write!(string, " {}", ident).unwrap();
             }
+            Ty::Uvec2 => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uvec2);
+                write!(string, " {}", ident).unwrap();
+            }
+            Ty::Uvec3 => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uvec3);
+                write!(string, " {}", ident).unwrap();
+            }
+            Ty::Uvec4 => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uvec4);
+                write!(string, " {}", ident).unwrap();
+            }
             Ty::Vec2 => {
                 prefix(string, sep, is_inout);
                 self.write_ty_lit(string, TyLit::Vec2);
@@ -901,6 +937,21 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::TextureOES);
                 write!(string, " {}", ident).unwrap();
             }
+            Ty::TextureCube => {
+                write!(string, "{}", sep).unwrap();
+                self.write_ty_lit(string, TyLit::TextureCube);
+                write!(string, " {}", ident).unwrap();
+            }
+            Ty::Texture2DArray => {
+                write!(string, "{}", sep).unwrap();
+                self.write_ty_lit(string, TyLit::Texture2DArray);
+                write!(string, " {}", ident).unwrap();
+            }
+            Ty::Texture3D => {
+                write!(string, "{}", sep).unwrap();
+                self.write_ty_lit(string, TyLit::Texture3D);
+                write!(string, " {}", ident).unwrap();
+            }
             Ty::Array {ref elem_ty, len} => {
                 self.write_var_decl(string, sep, is_inout, is_packed, ident, elem_ty);
                 write!(string, "[{}]", len).unwrap();
@@ -947,6 +998,7 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
             match ty_lit {
                 TyLit::Bool => "bool",
                 TyLit::Int => "int",
+                TyLit::Uint => "uint",
                 TyLit::Float => "float",
                 TyLit::Bvec2 => "bvec2",
                 TyLit::Bvec3 => "bvec3",
@@ -954,6 +1006,9 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
                 TyLit::Ivec2 => "ivec2",
                 TyLit::Ivec3 => "ivec3",
                 TyLit::Ivec4 => "ivec4",
+                TyLit::Uvec2 => "uvec2",
+                TyLit::Uvec3 => "uvec3",
+                TyLit::Uvec4 => "uvec4",
                 TyLit::Vec2 => "vec2",
                 TyLit::Vec3 => "vec3",
                 TyLit::Vec4 => "vec4",
@@ -962,6 +1017,9 @@ impl<'a> BackendWriter for GlslBackendWriter<'a> {
                 TyLit::Mat4 => "mat4",
                 TyLit::Texture2D => "sampler2D",
                 TyLit::TextureOES => "samplerExternalOES",
+                TyLit::TextureCube => "samplerCube",
+                TyLit::Texture2DArray => "sampler2DArray",
+                TyLit::Texture3D => "sampler3D",
             }
         )
             .unwrap();
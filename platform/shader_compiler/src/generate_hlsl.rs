@@ -50,20 +50,27 @@ impl<'a> DrawShaderGenerator<'a> {
             all_constructor_fns.extend(fn_def.constructor_fn_deps.borrow().as_ref().unwrap().iter().cloned());
         }
 
-        for fn_iter in self.draw_shader_def.all_fns.borrow().iter() {
-            let fn_def = self.shader_registry.all_fns.get(fn_iter).unwrap();
-            if fn_def.builtin_deps.borrow().as_ref().unwrap().contains(&Ident(live_id!(sample2d))) {
-                writeln!(self.string, "SamplerState default_texture_sampler{{Filter=MIN_MAX_MIP_LINEAR;AddressU = Wrap;AddressV=Wrap;}};").unwrap();
-                writeln!(self.string, "float4 sample2d(Texture2D tex, float2 pos){{return tex.Sample(default_texture_sampler,pos);}}").unwrap();
-                break;
-            }
-
-            if fn_def.builtin_deps.borrow().as_ref().unwrap().contains(&Ident(live_id!(sample2d_rt))) {
-                writeln!(self.string, "SamplerState default_texture_sampler{{Filter=MIN_MAX_MIP_LINEAR;AddressU = Wrap;AddressV=Wrap;}};").unwrap();
-                writeln!(self.string, "float4 sample2d_rt(Texture2D tex, float2 pos){{return tex.Sample(default_texture_sampler,pos);}}").unwrap();
-                break;
+        let sample_fns = [
+            (live_id!(sample2d), "float4 sample2d(Texture2D tex, float2 pos){return tex.Sample(default_texture_sampler,pos);}"),
+            (live_id!(sample2d_rt), "float4 sample2d_rt(Texture2D tex, float2 pos){return tex.Sample(default_texture_sampler,pos);}"),
+            (live_id!(sample2d_array), "float4 sample2d_array(Texture2DArray tex, float2 pos, float layer){return tex.Sample(default_texture_sampler,float3(pos,layer));}"),
+            (live_id!(sample3d), "float4 sample3d(Texture3D tex, float3 pos){return tex.Sample(default_texture_sampler,pos);}"),
+            (live_id!(sample_cube), "float4 sample_cube(TextureCube tex, float3 dir){return tex.Sample(default_texture_sampler,dir);}"),
+        ];
+        let mut has_sampler = false;
+        for (id, sample_fn) in sample_fns {
+            let is_used = self.draw_shader_def.all_fns.borrow().iter().any( | fn_iter | {
+                let fn_def = self.shader_registry.all_fns.get(fn_iter).unwrap();
+                fn_def.builtin_deps.borrow().as_ref().unwrap().contains(&Ident(id))
+            });
+            if is_used {
+                if !has_sampler {
+                    writeln!(self.string, "SamplerState default_texture_sampler{{Filter=MIN_MAX_MIP_LINEAR;AddressU = Wrap;AddressV=Wrap;}};").unwrap();
+                    has_sampler = true;
+                }
+                writeln!(self.string, "{}", sample_fn).unwrap();
             }
-        };
+        }
         
         self.generate_struct_decls();
         let fields_as_uniform_blocks = self.draw_shader_def.fields_as_uniform_blocks();
@@ -193,9 +200,15 @@ impl<'a> DrawShaderGenerator<'a> {
         for field in &self.draw_shader_def.fields {
             match field.kind {
                 DrawShaderFieldKind::Texture {..} => {
-                    assert_ne!(*field.ty_expr.ty.borrow().as_ref().unwrap(), Ty::TextureOES, "TextureOES is only available on Android");
-                    assert_eq!(*field.ty_expr.ty.borrow().as_ref().unwrap(), Ty::Texture2D);
-                    write!(self.string, "Texture2D {}: register(t{});", DisplayDsIdent(field.ident), index).unwrap();
+                    let texture_ty = match field.ty_expr.ty.borrow().as_ref().unwrap() {
+                        Ty::Texture2D => "Texture2D",
+                        Ty::TextureCube => "TextureCube",
+                        Ty::Texture2DArray => "Texture2DArray",
+                        Ty::Texture3D => "Texture3D",
+                        Ty::TextureOES => panic!("TextureOES is only available on Android"),
+                        ty => panic!("unexpected texture type {}", ty),
+                    };
+                    write!(self.string, "{} {}: register(t{});", texture_ty, DisplayDsIdent(field.ident), index).unwrap();
                     index += 1;
                 }
                 _ => {}
@@ -475,6 +488,10 @@ impl<'a> DrawShaderGenerator<'a> {
             Ty::Ivec2 => write!(self.string, "int2(0,0)").unwrap(),
             Ty::Ivec3 => write!(self.string, "int3(0,0,0)").unwrap(),
             Ty::Ivec4 => write!(self.string, "int4(0,0,0,0)").unwrap(),
+            Ty::Uint => write!(self.string, "0u").unwrap(),
+            Ty::Uvec2 => write!(self.string, "uint2(0u,0u)").unwrap(),
+            Ty::Uvec3 => write!(self.string, "uint3(0u,0u,0u)").unwrap(),
+            Ty::Uvec4 => write!(self.string, "uint4(0u,0u,0u,0u)").unwrap(),
             Ty::Vec2 => write!(self.string, "float2(0.0,0.0)").unwrap(),
             Ty::Vec3 => write!(self.string, "float3(0.0,0.0,0.0)").unwrap(),
             Ty::Vec4 => write!(self.string, "float4(0.0,0.0,0.0,0.0)").unwrap(),
@@ -561,6 +578,11 @@ impl<'a> BackendWriter for HlslBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Int);
                 write!(string, " {}", ident).unwrap();
             }
+            Ty::Uint => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uint);
+                write!(string, " {}", ident).unwrap();
+            }
             Ty::Float => {
                 prefix(string, sep, is_inout);
                 self.write_ty_lit(string, TyLit::Float);
@@ -596,6 +618,21 @@ impl<'a> BackendWriter for HlslBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Ivec4);
                 write!(string, " {}", ident).unwrap();
             }
+            Ty::Uvec2 => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uvec2);
+                write!(string, " {}", ident).unwrap();
+            }
+            Ty::Uvec3 => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uvec3);
+                write!(string, " {}", ident).unwrap();
+            }
+            Ty::Uvec4 => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uvec4);
+                write!(string, " {}", ident).unwrap();
+            }
             Ty::Vec2 => {
                 prefix(string, sep, is_inout);
                 self.write_ty_lit(string, TyLit::Vec2);
@@ -626,7 +663,7 @@ impl<'a> BackendWriter for HlslBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Mat4);
                 write!(string, " {}", ident).unwrap();
             }
-            Ty::Texture2D | Ty::TextureOES => panic!(), // TODO
+            Ty::Texture2D | Ty::TextureOES | Ty::TextureCube | Ty::Texture2DArray | Ty::Texture3D => panic!(), // TODO
             Ty::Array {ref elem_ty, len} => {
                 self.write_var_decl(string, sep, is_inout, is_packed, ident, elem_ty);
                 write!(string, "[{}]", len).unwrap();
@@ -788,6 +825,7 @@ impl<'a> BackendWriter for HlslBackendWriter<'a> {
             match ty_lit {
                 TyLit::Bool => "bool",
                 TyLit::Int => "int",
+                TyLit::Uint => "uint",
                 TyLit::Float => "float",
                 TyLit::Bvec2 => "bool2",
                 TyLit::Bvec3 => "bool3",
@@ -795,13 +833,16 @@ impl<'a> BackendWriter for HlslBackendWriter<'a> {
                 TyLit::Ivec2 => "int2",
                 TyLit::Ivec3 => "int3",
                 TyLit::Ivec4 => "int4",
+                TyLit::Uvec2 => "uint2",
+                TyLit::Uvec3 => "uint3",
+                TyLit::Uvec4 => "uint4",
                 TyLit::Vec2 => "float2",
                 TyLit::Vec3 => "float3",
                 TyLit::Vec4 => "float4",
                 TyLit::Mat2 => "float2x2",
                 TyLit::Mat3 => "float3x3",
                 TyLit::Mat4 => "float4x4",
-                TyLit::Texture2D | TyLit::TextureOES => panic!(), // TODO
+                TyLit::Texture2D | TyLit::TextureOES | TyLit::TextureCube | TyLit::Texture2DArray | TyLit::Texture3D => panic!(), // TODO
             }
         )
             .unwrap();
@@ -57,17 +57,22 @@ impl<'a> DrawShaderGenerator<'a> {
             all_constructor_fns.extend(fn_def.constructor_fn_deps.borrow().as_ref().unwrap().iter().cloned());
         }
         
-        for fn_iter in self.draw_shader_def.all_fns.borrow().iter() {
-            let fn_def = self.shader_registry.all_fns.get(fn_iter).unwrap();
-            if fn_def.builtin_deps.borrow().as_ref().unwrap().contains(&Ident(live_id!(sample2d))) {
-                writeln!(self.string, "float4 sample2d(texture2d<float> tex, float2 pos){{return tex.sample(sampler(mag_filter::linear,min_filter::linear),pos);}}").unwrap();
-                break;
-            }
-            if fn_def.builtin_deps.borrow().as_ref().unwrap().contains(&Ident(live_id!(sample2d_rt))) {
-                writeln!(self.string, "float4 sample2d_rt(texture2d<float> tex, float2 pos){{return tex.sample(sampler(mag_filter::linear,min_filter::linear),pos);}}").unwrap();
-                break;
+        let sample_fns = [
+            (live_id!(sample2d), "float4 sample2d(texture2d<float> tex, float2 pos){return tex.sample(sampler(mag_filter::linear,min_filter::linear),pos);}"),
+            (live_id!(sample2d_rt), "float4 sample2d_rt(texture2d<float> tex, float2 pos){return tex.sample(sampler(mag_filter::linear,min_filter::linear),pos);}"),
+            (live_id!(sample2d_array), "float4 sample2d_array(texture2d_array<float> tex, float2 pos, float layer){return tex.sample(sampler(mag_filter::linear,min_filter::linear),pos,uint(layer));}"),
+            (live_id!(sample3d), "float4 sample3d(texture3d<float> tex, float3 pos){return tex.sample(sampler(mag_filter::linear,min_filter::linear),pos);}"),
+            (live_id!(sample_cube), "float4 sample_cube(texturecube<float> tex, float3 dir){return tex.sample(sampler(mag_filter::linear,min_filter::linear),dir);}"),
+        ];
+        for (id, sample_fn) in sample_fns {
+            let is_used = self.draw_shader_def.all_fns.borrow().iter().any( | fn_iter | {
+                let fn_def = self.shader_registry.all_fns.get(fn_iter).unwrap();
+                fn_def.builtin_deps.borrow().as_ref().unwrap().contains(&Ident(id))
+            });
+            if is_used {
+                writeln!(self.string, "{}", sample_fn).unwrap();
             }
-        };
+        }
         
         self.generate_struct_defs();
         //let fields_as_uniform_blocks = self.draw_shader_def.fields_as_uniform_blocks();
@@ -166,9 +171,14 @@ impl<'a> DrawShaderGenerator<'a> {
         for field in &self.draw_shader_def.fields {
             match field.kind {
                 DrawShaderFieldKind::Texture {..} => {
-                    assert_ne!(*field.ty_expr.ty.borrow().as_ref().unwrap(), Ty::TextureOES, "TextureOES is only available on Android");
-                    assert_eq!(*field.ty_expr.ty.borrow().as_ref().unwrap(), Ty::Texture2D);
-                    write!(self.string, "    texture2d<float> ").unwrap();
+                    match field.ty_expr.ty.borrow().as_ref().unwrap() {
+                        Ty::Texture2D => write!(self.string, "    texture2d<float> ").unwrap(),
+                        Ty::TextureCube => write!(self.string, "    texturecube<float> ").unwrap(),
+                        Ty::Texture2DArray => write!(self.string, "    texture2d_array<float> ").unwrap(),
+                        Ty::Texture3D => write!(self.string, "    texture3d<float> ").unwrap(),
+                        Ty::TextureOES => panic!("TextureOES is only available on Android"),
+                        ty => panic!("unexpected texture type {}", ty),
+                    }
                     write!(self.string, "{}", &DisplayDsIdent(field.ident)).unwrap();
                     write!(self.string, " [[texture({})]];", index).unwrap();
                     index += 1;
@@ -518,6 +528,11 @@ impl<'a> BackendWriter for MetalBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Int);
                 write!(string, " {}{}", ref_prefix, ident).unwrap();
             }
+            Ty::Uint => {
+                prefix(string, sep, is_inout);
+                self.write_ty_lit(string, TyLit::Uint);
+                write!(string, " {}{}", ref_prefix, ident).unwrap();
+            }
             Ty::Float => {
                 prefix(string, sep, is_inout);
                 self.write_ty_lit(string, TyLit::Float);
@@ -557,6 +572,24 @@ impl<'a> BackendWriter for MetalBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Ivec4);
                 write!(string, " {}{}", ref_prefix, ident).unwrap();
             }
+            Ty::Uvec2 => {
+                prefix(string, sep, is_inout);
+                write!(string, "{}", packed_prefix).unwrap();
+                self.write_ty_lit(string, TyLit::Uvec2);
+                write!(string, " {}{}", ref_prefix, ident).unwrap();
+            }
+            Ty::Uvec3 => {
+                prefix(string, sep, is_inout);
+                write!(string, "{}", packed_prefix).unwrap();
+                self.write_ty_lit(string, TyLit::Uvec3);
+                write!(string, " {}{}", ref_prefix, ident).unwrap();
+            }
+            Ty::Uvec4 => {
+                prefix(string, sep, is_inout);
+                write!(string, "{}", packed_prefix).unwrap();
+                self.write_ty_lit(string, TyLit::Uvec4);
+                write!(string, " {}{}", ref_prefix, ident).unwrap();
+            }
             Ty::Vec2 => {
                 prefix(string, sep, is_inout);
                 write!(string, "{}", packed_prefix).unwrap();
@@ -592,7 +625,7 @@ impl<'a> BackendWriter for MetalBackendWriter<'a> {
                 self.write_ty_lit(string, TyLit::Mat4);
                 write!(string, " {}{}", ref_prefix, ident).unwrap();
             }
-            Ty::Texture2D | Ty::TextureOES => panic!(), // TODO
+            Ty::Texture2D | Ty::TextureOES | Ty::TextureCube | Ty::Texture2DArray | Ty::Texture3D => panic!(), // TODO
             Ty::Array {ref elem_ty, len} => {
                 self.write_var_decl(string, sep, is_inout, is_packed, ident, elem_ty);
                 write!(string, "[{}]", len).unwrap();
@@ -779,6 +812,7 @@ impl<'a> BackendWriter for MetalBackendWriter<'a> {
             match ty_lit {
                 TyLit::Bool => "bool",
                 TyLit::Int => "int",
+                TyLit::Uint => "uint",
                 TyLit::Float => "float",
                 TyLit::Bvec2 => "bool2",
                 TyLit::Bvec3 => "bool3",
@@ -786,13 +820,16 @@ impl<'a> BackendWriter for MetalBackendWriter<'a> {
                 TyLit::Ivec2 => "int2",
                 TyLit::Ivec3 => "int3",
                 TyLit::Ivec4 => "int4",
+                TyLit::Uvec2 => "uint2",
+                TyLit::Uvec3 => "uint3",
+                TyLit::Uvec4 => "uint4",
                 TyLit::Vec2 => "float2",
                 TyLit::Vec3 => "float3",
                 TyLit::Vec4 => "float4",
                 TyLit::Mat2 => "float2x2",
                 TyLit::Mat3 => "float3x3",
                 TyLit::Mat4 => "float4x4",
-                TyLit::Texture2D | TyLit::TextureOES => panic!(), // TODO
+                TyLit::Texture2D | TyLit::TextureOES | TyLit::TextureCube | TyLit::Texture2DArray | TyLit::Texture3D => panic!(), // TODO
             }
         )
             .unwrap();
@@ -38,6 +38,13 @@ pub use {
             DrawShaderDef,
             DrawShaderFieldKind,
             DrawShaderFlags,
+            BlendMode,
+            BlendFactor,
+            BlendOp,
+            CompareFunc,
+            StencilOp,
+            DepthState,
+            StencilState,
             DrawShaderConstTable,
             ValuePtr,
         },
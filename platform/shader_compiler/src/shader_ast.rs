@@ -72,6 +72,265 @@ pub struct DrawShaderFlags {
     pub debug: bool,
     pub draw_call_nocompare: bool,
     pub draw_call_always: bool,
+    pub blend: BlendMode,
+    pub depth: DepthState,
+    pub stencil: StencilState,
+}
+
+impl DrawShaderFlags {
+    pub fn render_state_eq(&self, other: &DrawShaderFlags) -> bool {
+        self.blend == other.blend && self.depth == other.depth && self.stencil == other.stencil
+    }
+    
+    pub fn is_render_state_id(id: LiveId) -> bool {
+        matches!(id,
+            live_id!(blend) | live_id!(blend_src) | live_id!(blend_dst) | live_id!(blend_op) |
+            live_id!(depth_test) | live_id!(depth_write) |
+            live_id!(stencil_test) | live_id!(stencil_ref) | live_id!(stencil_read_mask) | live_id!(stencil_write_mask) |
+            live_id!(stencil_fail) | live_id!(stencil_depth_fail) | live_id!(stencil_pass)
+        )
+    }
+    
+    // applies one of the render state properties of a draw shader body:
+    // blend: Additive, blend_src: SrcAlpha, depth_test: Always, depth_write: false,
+    // stencil_test: Equal, stencil_ref: 1, stencil_pass: Replace, etc.
+    // setting blend_src, blend_dst or blend_op turns the blend mode into Custom
+    pub fn set_render_state(&mut self, id: LiveId, value: &LiveValue) -> Result<(), String> {
+        fn variant<T>(id: LiveId, value: &LiveValue, from_id: fn(LiveId) -> Option<T>) -> Result<T, String> {
+            if let LiveValue::BareEnum(variant) | LiveValue::Id(variant) = value {
+                if let Some(v) = from_id(*variant) {
+                    return Ok(v)
+                }
+                return Err(format!("Unknown value {} for {}", variant, id))
+            }
+            Err(format!("Expected an enum value for {}", id))
+        }
+        fn byte(id: LiveId, value: &LiveValue) -> Result<u8, String> {
+            if let LiveValue::Int64(v) = value {
+                if let Ok(v) = u8::try_from(*v) {
+                    return Ok(v)
+                }
+            }
+            Err(format!("Expected an integer between 0 and 255 for {}", id))
+        }
+        let (src, dst, op) = self.blend.factors().unwrap_or((BlendFactor::One, BlendFactor::OneMinusSrcAlpha, BlendOp::Add));
+        match id {
+            live_id!(blend) => self.blend = variant(id, value, BlendMode::from_id)?,
+            live_id!(blend_src) => self.blend = BlendMode::Custom {src: variant(id, value, BlendFactor::from_id)?, dst, op},
+            live_id!(blend_dst) => self.blend = BlendMode::Custom {src, dst: variant(id, value, BlendFactor::from_id)?, op},
+            live_id!(blend_op) => self.blend = BlendMode::Custom {src, dst, op: variant(id, value, BlendOp::from_id)?},
+            live_id!(depth_test) => self.depth.test = variant(id, value, CompareFunc::from_id)?,
+            live_id!(depth_write) => if let LiveValue::Bool(v) = value {
+                self.depth.write = *v
+            }
+            else {
+                return Err(format!("Expected a bool for {}", id))
+            },
+            live_id!(stencil_test) => self.stencil.test = variant(id, value, CompareFunc::from_id)?,
+            live_id!(stencil_ref) => self.stencil.reference = byte(id, value)?,
+            live_id!(stencil_read_mask) => self.stencil.read_mask = byte(id, value)?,
+            live_id!(stencil_write_mask) => self.stencil.write_mask = byte(id, value)?,
+            live_id!(stencil_fail) => self.stencil.fail = variant(id, value, StencilOp::from_id)?,
+            live_id!(stencil_depth_fail) => self.stencil.depth_fail = variant(id, value, StencilOp::from_id)?,
+            live_id!(stencil_pass) => self.stencil.pass = variant(id, value, StencilOp::from_id)?,
+            _ => ()
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum BlendMode {
+    Opaque,
+    #[default]
+    Premultiplied,
+    Additive,
+    Multiply,
+    Screen,
+    Custom {src: BlendFactor, dst: BlendFactor, op: BlendOp},
+}
+
+impl BlendMode {
+    pub fn from_id(id: LiveId) -> Option<Self> {
+        Some(match id {
+            live_id!(Opaque) => Self::Opaque,
+            live_id!(Premultiplied) => Self::Premultiplied,
+            live_id!(Additive) => Self::Additive,
+            live_id!(Multiply) => Self::Multiply,
+            live_id!(Screen) => Self::Screen,
+            live_id!(Custom) => Self::Custom {src: BlendFactor::One, dst: BlendFactor::OneMinusSrcAlpha, op: BlendOp::Add},
+            _ => return None
+        })
+    }
+    
+    // the source and destination factor and the op, used for both color and alpha
+    pub fn factors(&self) -> Option<(BlendFactor, BlendFactor, BlendOp)> {
+        match self {
+            Self::Opaque => None,
+            Self::Premultiplied => Some((BlendFactor::One, BlendFactor::OneMinusSrcAlpha, BlendOp::Add)),
+            Self::Additive => Some((BlendFactor::One, BlendFactor::One, BlendOp::Add)),
+            Self::Multiply => Some((BlendFactor::DstColor, BlendFactor::OneMinusSrcAlpha, BlendOp::Add)),
+            Self::Screen => Some((BlendFactor::One, BlendFactor::OneMinusSrcColor, BlendOp::Add)),
+            Self::Custom {src, dst, op} => Some((*src, *dst, *op)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstColor,
+    OneMinusDstColor,
+    DstAlpha,
+    OneMinusDstAlpha,
+}
+
+impl BlendFactor {
+    pub fn from_id(id: LiveId) -> Option<Self> {
+        Some(match id {
+            live_id!(Zero) => Self::Zero,
+            live_id!(One) => Self::One,
+            live_id!(SrcColor) => Self::SrcColor,
+            live_id!(OneMinusSrcColor) => Self::OneMinusSrcColor,
+            live_id!(SrcAlpha) => Self::SrcAlpha,
+            live_id!(OneMinusSrcAlpha) => Self::OneMinusSrcAlpha,
+            live_id!(DstColor) => Self::DstColor,
+            live_id!(OneMinusDstColor) => Self::OneMinusDstColor,
+            live_id!(DstAlpha) => Self::DstAlpha,
+            live_id!(OneMinusDstAlpha) => Self::OneMinusDstAlpha,
+            _ => return None
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendOp {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendOp {
+    pub fn from_id(id: LiveId) -> Option<Self> {
+        Some(match id {
+            live_id!(Add) => Self::Add,
+            live_id!(Subtract) => Self::Subtract,
+            live_id!(ReverseSubtract) => Self::ReverseSubtract,
+            live_id!(Min) => Self::Min,
+            live_id!(Max) => Self::Max,
+            _ => return None
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareFunc {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunc {
+    pub fn from_id(id: LiveId) -> Option<Self> {
+        Some(match id {
+            live_id!(Never) => Self::Never,
+            live_id!(Less) => Self::Less,
+            live_id!(Equal) => Self::Equal,
+            live_id!(LessEqual) => Self::LessEqual,
+            live_id!(Greater) => Self::Greater,
+            live_id!(NotEqual) => Self::NotEqual,
+            live_id!(GreaterEqual) => Self::GreaterEqual,
+            live_id!(Always) => Self::Always,
+            _ => return None
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    IncrementClamp,
+    DecrementClamp,
+    Invert,
+    IncrementWrap,
+    DecrementWrap,
+}
+
+impl StencilOp {
+    pub fn from_id(id: LiveId) -> Option<Self> {
+        Some(match id {
+            live_id!(Keep) => Self::Keep,
+            live_id!(Zero) => Self::Zero,
+            live_id!(Replace) => Self::Replace,
+            live_id!(IncrementClamp) => Self::IncrementClamp,
+            live_id!(DecrementClamp) => Self::DecrementClamp,
+            live_id!(Invert) => Self::Invert,
+            live_id!(IncrementWrap) => Self::IncrementWrap,
+            live_id!(DecrementWrap) => Self::DecrementWrap,
+            _ => return None
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthState {
+    pub test: CompareFunc,
+    pub write: bool,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {test: CompareFunc::LessEqual, write: true}
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StencilState {
+    pub test: CompareFunc,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    pub fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            test: CompareFunc::Always,
+            reference: 0,
+            read_mask: 0xff,
+            write_mask: 0xff,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+impl StencilState {
+    // a stencil that always passes and keeps the buffer can be left disabled
+    pub fn is_enabled(&self) -> bool {
+        self.test != CompareFunc::Always
+            || self.fail != StencilOp::Keep
+            || self.depth_fail != StencilOp::Keep
+            || self.pass != StencilOp::Keep
+    }
 }
 
 #[derive(Clone, Default, Debug)]
@@ -522,6 +781,7 @@ pub enum ShaderTy {
     Void,
     Bool,
     Int,
+    Uint,
     Float,
     Bvec2,
     Bvec3,
@@ -529,6 +789,9 @@ pub enum ShaderTy {
     Ivec2,
     Ivec3,
     Ivec4,
+    Uvec2,
+    Uvec3,
+    Uvec4,
     Vec2,
     Vec3,
     Vec4,
@@ -537,6 +800,9 @@ pub enum ShaderTy {
     Mat4,
     Texture2D,
     TextureOES,
+    TextureCube,
+    Texture2DArray,
+    Texture3D,
     Array {elem_ty: Rc<ShaderTy>, len: usize},
     Struct(StructPtr),
     Enum(LiveType),
@@ -552,6 +818,7 @@ pub type Ty = ShaderTy;
 pub enum TyLit {
     Bool,
     Int,
+    Uint,
     Float,
     Bvec2,
     Bvec3,
@@ -559,6 +826,9 @@ pub enum TyLit {
     Ivec2,
     Ivec3,
     Ivec4,
+    Uvec2,
+    Uvec3,
+    Uvec4,
     Vec2,
     Vec3,
     Vec4,
@@ -567,6 +837,9 @@ pub enum TyLit {
     Mat4,
     Texture2D,
     TextureOES,
+    TextureCube,
+    Texture2DArray,
+    Texture3D,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -977,6 +1250,7 @@ impl Ty {
             Ty::Void => None,
             Ty::Bool => Some(TyLit::Bool),
             Ty::Int => Some(TyLit::Int),
+            Ty::Uint => Some(TyLit::Uint),
             Ty::Float => Some(TyLit::Float),
             Ty::Bvec2 => Some(TyLit::Bvec2),
             Ty::Bvec3 => Some(TyLit::Bvec3),
@@ -984,6 +1258,9 @@ impl Ty {
             Ty::Ivec2 => Some(TyLit::Ivec2),
             Ty::Ivec3 => Some(TyLit::Ivec3),
             Ty::Ivec4 => Some(TyLit::Ivec4),
+            Ty::Uvec2 => Some(TyLit::Uvec2),
+            Ty::Uvec3 => Some(TyLit::Uvec3),
+            Ty::Uvec4 => Some(TyLit::Uvec4),
             Ty::Vec2 => Some(TyLit::Vec2),
             Ty::Vec3 => Some(TyLit::Vec3),
             Ty::Vec4 => Some(TyLit::Vec4),
@@ -992,6 +1269,9 @@ impl Ty {
             Ty::Mat4 => Some(TyLit::Mat4),
             Ty::Texture2D => Some(TyLit::Bool),
             Ty::TextureOES => Some(TyLit::Bool),
+            Ty::TextureCube => Some(TyLit::Bool),
+            Ty::Texture2DArray => Some(TyLit::Bool),
+            Ty::Texture3D => Some(TyLit::Bool),
             Ty::Array {..} => None,
             Ty::Struct(_) => None,
             Ty::Enum(_) => None,
@@ -1003,7 +1283,7 @@ impl Ty {
     
     pub fn is_scalar(&self) -> bool {
         match self {
-            Ty::Bool | Ty::Int | Ty::Float => true,
+            Ty::Bool | Ty::Int | Ty::Uint | Ty::Float => true,
             _ => false,
         }
     }
@@ -1016,6 +1296,9 @@ impl Ty {
                 | Ty::Ivec2
                 | Ty::Ivec3
                 | Ty::Ivec4
+                | Ty::Uvec2
+                | Ty::Uvec3
+                | Ty::Uvec4
                 | Ty::Vec2
                 | Ty::Vec3
                 | Ty::Vec4 => true,
@@ -1033,14 +1316,17 @@ impl Ty {
     pub fn slots(&self) -> usize {
         match self {
             Ty::Void => 0,
-            Ty::Bool | Ty::Int | Ty::Float => 1,
-            Ty::Bvec2 | Ty::Ivec2 | Ty::Vec2 => 2,
-            Ty::Bvec3 | Ty::Ivec3 | Ty::Vec3 => 3,
-            Ty::Bvec4 | Ty::Ivec4 | Ty::Vec4 | Ty::Mat2 => 4,
+            Ty::Bool | Ty::Int | Ty::Uint | Ty::Float => 1,
+            Ty::Bvec2 | Ty::Ivec2 | Ty::Uvec2 | Ty::Vec2 => 2,
+            Ty::Bvec3 | Ty::Ivec3 | Ty::Uvec3 | Ty::Vec3 => 3,
+            Ty::Bvec4 | Ty::Ivec4 | Ty::Uvec4 | Ty::Vec4 | Ty::Mat2 => 4,
             Ty::Mat3 => 9,
             Ty::Mat4 => 16,
             Ty::Texture2D {..} => panic!(),
             Ty::TextureOES {..} => panic!(),
+            Ty::TextureCube {..} => panic!(),
+            Ty::Texture2DArray {..} => panic!(),
+            Ty::Texture3D {..} => panic!(),
             Ty::Array {elem_ty, len} => elem_ty.slots() * len,
             Ty::Enum(_) => 1,
             Ty::Struct(_) => panic!(),
@@ -1058,6 +1344,7 @@ impl Ty {
                 Ty::Void => panic!(),
                 Ty::Bool => TyExprKind::Lit {ty_lit: TyLit::Bool},
                 Ty::Int => TyExprKind::Lit {ty_lit: TyLit::Int},
+                Ty::Uint => TyExprKind::Lit {ty_lit: TyLit::Uint},
                 Ty::Float => TyExprKind::Lit {ty_lit: TyLit::Float},
                 Ty::Bvec2 => TyExprKind::Lit {ty_lit: TyLit::Bvec2},
                 Ty::Bvec3 => TyExprKind::Lit {ty_lit: TyLit::Bvec3},
@@ -1065,6 +1352,9 @@ impl Ty {
                 Ty::Ivec2 => TyExprKind::Lit {ty_lit: TyLit::Ivec2},
                 Ty::Ivec3 => TyExprKind::Lit {ty_lit: TyLit::Ivec3},
                 Ty::Ivec4 => TyExprKind::Lit {ty_lit: TyLit::Ivec4},
+                Ty::Uvec2 => TyExprKind::Lit {ty_lit: TyLit::Uvec2},
+                Ty::Uvec3 => TyExprKind::Lit {ty_lit: TyLit::Uvec3},
+                Ty::Uvec4 => TyExprKind::Lit {ty_lit: TyLit::Uvec4},
                 Ty::Vec2 => TyExprKind::Lit {ty_lit: TyLit::Vec2},
                 Ty::Vec3 => TyExprKind::Lit {ty_lit: TyLit::Vec3},
                 Ty::Vec4 => TyExprKind::Lit {ty_lit: TyLit::Vec4},
@@ -1073,6 +1363,9 @@ impl Ty {
                 Ty::Mat4 => TyExprKind::Lit {ty_lit: TyLit::Mat4},
                 Ty::Texture2D => TyExprKind::Lit {ty_lit: TyLit::Texture2D},
                 Ty::TextureOES => TyExprKind::Lit {ty_lit: TyLit::TextureOES},
+                Ty::TextureCube => TyExprKind::Lit {ty_lit: TyLit::TextureCube},
+                Ty::Texture2DArray => TyExprKind::Lit {ty_lit: TyLit::Texture2DArray},
+                Ty::Texture3D => TyExprKind::Lit {ty_lit: TyLit::Texture3D},
                 Ty::Array {elem_ty, len} => {
                     TyExprKind::Array {
                         elem_ty_expr: Box::new(elem_ty.to_ty_expr()),
@@ -1121,6 +1414,9 @@ impl Ty {
                 live_id!(vec4) => Self::Vec4,
                 live_id!(texture2d) => Self::Texture2D,
                 live_id!(textureOES) => Self::TextureOES,
+                live_id!(textureCube) => Self::TextureCube,
+                live_id!(texture2DArray) => Self::Texture2DArray,
+                live_id!(texture3D) => Self::Texture3D,
                 _ => {
                     return Err(LiveError {
                         origin: live_error_origin!(),
@@ -1171,6 +1467,7 @@ impl fmt::Display for Ty {
             Ty::Void => write!(f, "void"),
             Ty::Bool => write!(f, "bool"),
             Ty::Int => write!(f, "int"),
+            Ty::Uint => write!(f, "uint"),
             Ty::Float => write!(f, "float"),
             Ty::Bvec2 => write!(f, "bvec2"),
             Ty::Bvec3 => write!(f, "bvec3"),
@@ -1178,6 +1475,9 @@ impl fmt::Display for Ty {
             Ty::Ivec2 => write!(f, "ivec2"),
             Ty::Ivec3 => write!(f, "ivec3"),
             Ty::Ivec4 => write!(f, "ivec4"),
+            Ty::Uvec2 => write!(f, "uvec2"),
+            Ty::Uvec3 => write!(f, "uvec3"),
+            Ty::Uvec4 => write!(f, "uvec4"),
             Ty::Vec2 => write!(f, "vec2"),
             Ty::Vec3 => write!(f, "vec3"),
             Ty::Vec4 => write!(f, "vec4"),
@@ -1186,6 +1486,9 @@ impl fmt::Display for Ty {
             Ty::Mat4 => write!(f, "mat4"),
             Ty::Texture2D => write!(f, "texture2D"),
             Ty::TextureOES => write!(f, "textureOES"),
+            Ty::TextureCube => write!(f, "textureCube"),
+            Ty::Texture2DArray => write!(f, "texture2DArray"),
+            Ty::Texture3D => write!(f, "texture3D"),
             Ty::Array {elem_ty, len} => write!(f, "{}[{}]", elem_ty, len),
             Ty::Struct(struct_ptr) => write!(f, "Struct:{:?}", struct_ptr),
             Ty::DrawShader(shader_ptr) => write!(f, "DrawShader:{:?}", shader_ptr),
@@ -1208,13 +1511,20 @@ impl TyLit {
             live_id!(float) => Some(TyLit::Float),
             live_id!(bool) => Some(TyLit::Bool),
             live_id!(int) => Some(TyLit::Int),
+            live_id!(uint) => Some(TyLit::Uint),
             live_id!(bvec2) => Some(TyLit::Bvec2),
             live_id!(bvec3) => Some(TyLit::Bvec3),
             live_id!(bvec4) => Some(TyLit::Bvec4),
-            live_id!(ivec2) => Some(TyLit::Ivec4),
-            live_id!(ivec3) => Some(TyLit::Ivec4),
+            live_id!(ivec2) => Some(TyLit::Ivec2),
+            live_id!(ivec3) => Some(TyLit::Ivec3),
             live_id!(ivec4) => Some(TyLit::Ivec4),
+            live_id!(uvec2) => Some(TyLit::Uvec2),
+            live_id!(uvec3) => Some(TyLit::Uvec3),
+            live_id!(uvec4) => Some(TyLit::Uvec4),
             live_id!(texture2D) => Some(TyLit::Texture2D),
+            live_id!(textureCube) => Some(TyLit::TextureCube),
+            live_id!(texture2DArray) => Some(TyLit::Texture2DArray),
+            live_id!(texture3D) => Some(TyLit::Texture3D),
             _ => None
         }
     }
@@ -1223,6 +1533,7 @@ impl TyLit {
         match self {
             TyLit::Bool => Ty::Bool,
             TyLit::Int => Ty::Int,
+            TyLit::Uint => Ty::Uint,
             TyLit::Float => Ty::Float,
             TyLit::Bvec2 => Ty::Bvec2,
             TyLit::Bvec3 => Ty::Bvec3,
@@ -1230,6 +1541,9 @@ impl TyLit {
             TyLit::Ivec2 => Ty::Ivec2,
             TyLit::Ivec3 => Ty::Ivec3,
             TyLit::Ivec4 => Ty::Ivec4,
+            TyLit::Uvec2 => Ty::Uvec2,
+            TyLit::Uvec3 => Ty::Uvec3,
+            TyLit::Uvec4 => Ty::Uvec4,
             TyLit::Vec2 => Ty::Vec2,
             TyLit::Vec3 => Ty::Vec3,
             TyLit::Vec4 => Ty::Vec4,
@@ -1238,6 +1552,9 @@ impl TyLit {
             TyLit::Mat4 => Ty::Mat4,
             TyLit::Texture2D => Ty::Texture2D,
             TyLit::TextureOES => Ty::TextureOES,
+            TyLit::TextureCube => Ty::TextureCube,
+            TyLit::Texture2DArray => Ty::Texture2DArray,
+            TyLit::Texture3D => Ty::Texture3D,
         }
     }
     
@@ -1251,6 +1568,7 @@ impl fmt::Display for TyLit {
             match self {
                 TyLit::Bool => "bool",
                 TyLit::Int => "int",
+                TyLit::Uint => "uint",
                 TyLit::Float => "float",
                 TyLit::Bvec2 => "bvec2",
                 TyLit::Bvec3 => "bvec3",
@@ -1258,6 +1576,9 @@ impl fmt::Display for TyLit {
                 TyLit::Ivec2 => "ivec2",
                 TyLit::Ivec3 => "ivec3",
                 TyLit::Ivec4 => "ivec4",
+                TyLit::Uvec2 => "uvec2",
+                TyLit::Uvec3 => "uvec3",
+                TyLit::Uvec4 => "uvec4",
                 TyLit::Vec2 => "vec2",
                 TyLit::Vec3 => "vec3",
                 TyLit::Vec4 => "vec4",
@@ -1266,6 +1587,9 @@ impl fmt::Display for TyLit {
                 TyLit::Mat4 => "mat4",
                 TyLit::Texture2D => "texture2D",
                 TyLit::TextureOES => "textureOES",
+                TyLit::TextureCube => "textureCube",
+                TyLit::Texture2DArray => "texture2DArray",
+                TyLit::Texture3D => "texture3D",
             }
         )
     }
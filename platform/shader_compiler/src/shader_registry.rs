@@ -640,7 +640,14 @@ impl ShaderRegistry {
                                     });
                                 }
                                 None => {
-                                    if let LiveValue::Bool(val) = prop.value {
+                                    if DrawShaderFlags::is_render_state_id(prop.id) {
+                                        draw_shader_def.flags.set_render_state(prop.id, &prop.value).map_err( | message | LiveError {
+                                            origin: live_error_origin!(),
+                                            span: first_def.into(),
+                                            message
+                                        }) ?;
+                                    }
+                                    else if let LiveValue::Bool(val) = prop.value {
                                         match prop.id {
                                             live_id!(debug) => {
                                                 draw_shader_def.flags.debug = val;
@@ -664,6 +671,13 @@ impl ShaderRegistry {
                                 }
                             };
                         }
+                        LiveValue::BareEnum(_) if DrawShaderFlags::is_render_state_id(prop.id) => {
+                            draw_shader_def.flags.set_render_state(prop.id, &prop.value).map_err( | message | LiveError {
+                                origin: live_error_origin!(),
+                                span: prop.origin.token_id().unwrap().into(),
+                                message
+                            }) ?;
+                        }
                         LiveValue::Class {live_type, ..} => {
                             if prop.id == live_id!(geometry) {
                                 ext_self(
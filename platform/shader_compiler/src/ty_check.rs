@@ -223,6 +223,13 @@ impl<'a> TyChecker<'a> {
                 (Ty::Ivec3, Ty::Ivec3) => Some(Ty::Ivec3),
                 (Ty::Ivec4, Ty::Int) => Some(Ty::Ivec4),
                 (Ty::Ivec4, Ty::Ivec4) => Some(Ty::Ivec4),
+                (Ty::Uint, Ty::Uint) => Some(Ty::Uint),
+                (Ty::Uvec2, Ty::Uint) => Some(Ty::Uvec2),
+                (Ty::Uvec2, Ty::Uvec2) => Some(Ty::Uvec2),
+                (Ty::Uvec3, Ty::Uint) => Some(Ty::Uvec3),
+                (Ty::Uvec3, Ty::Uvec3) => Some(Ty::Uvec3),
+                (Ty::Uvec4, Ty::Uint) => Some(Ty::Uvec4),
+                (Ty::Uvec4, Ty::Uvec4) => Some(Ty::Uvec4),
                 (Ty::Vec2, Ty::Float) => Some(Ty::Vec2),
                 (Ty::Vec2, Ty::Vec2) => Some(Ty::Vec2),
                 (Ty::Vec3, Ty::Float) => Some(Ty::Vec3),
@@ -246,6 +253,13 @@ impl<'a> TyChecker<'a> {
                 (Ty::Ivec3, Ty::Ivec3) => Some(Ty::Ivec3),
                 (Ty::Ivec4, Ty::Int) => Some(Ty::Ivec4),
                 (Ty::Ivec4, Ty::Ivec4) => Some(Ty::Ivec4),
+                (Ty::Uint, Ty::Uint) => Some(Ty::Uint),
+                (Ty::Uvec2, Ty::Uint) => Some(Ty::Uvec2),
+                (Ty::Uvec2, Ty::Uvec2) => Some(Ty::Uvec2),
+                (Ty::Uvec3, Ty::Uint) => Some(Ty::Uvec3),
+                (Ty::Uvec3, Ty::Uvec3) => Some(Ty::Uvec3),
+                (Ty::Uvec4, Ty::Uint) => Some(Ty::Uvec4),
+                (Ty::Uvec4, Ty::Uvec4) => Some(Ty::Uvec4),
                 (Ty::Vec2, Ty::Float) => Some(Ty::Vec2),
                 (Ty::Vec2, Ty::Vec2) => Some(Ty::Vec2),
                 (Ty::Vec2, Ty::Mat2) => Some(Ty::Vec2),
@@ -274,6 +288,10 @@ impl<'a> TyChecker<'a> {
                 (Ty::Ivec2, Ty::Ivec2) => Some(Ty::Bool),
                 (Ty::Ivec3, Ty::Ivec3) => Some(Ty::Bool),
                 (Ty::Ivec4, Ty::Ivec4) => Some(Ty::Bool),
+                (Ty::Uint, Ty::Uint) => Some(Ty::Bool),
+                (Ty::Uvec2, Ty::Uvec2) => Some(Ty::Bool),
+                (Ty::Uvec3, Ty::Uvec3) => Some(Ty::Bool),
+                (Ty::Uvec4, Ty::Uvec4) => Some(Ty::Bool),
                 (Ty::Vec2, Ty::Vec2) => Some(Ty::Bool),
                 (Ty::Vec3, Ty::Vec3) => Some(Ty::Bool),
                 (Ty::Vec4, Ty::Vec4) => Some(Ty::Bool),
@@ -284,6 +302,7 @@ impl<'a> TyChecker<'a> {
             },
             BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => match (&left_ty, &right_ty) {
                 (Ty::Int, Ty::Int) => Some(Ty::Bool),
+                (Ty::Uint, Ty::Uint) => Some(Ty::Bool),
                 (Ty::Float, Ty::Float) => Some(Ty::Bool),
                 _ => None,
             },
@@ -302,6 +321,13 @@ impl<'a> TyChecker<'a> {
                 (Ty::Ivec3, Ty::Ivec3) => Some(Ty::Ivec3),
                 (Ty::Ivec4, Ty::Int) => Some(Ty::Ivec4),
                 (Ty::Ivec4, Ty::Ivec4) => Some(Ty::Ivec4),
+                (Ty::Uint, Ty::Uint) => Some(Ty::Uint),
+                (Ty::Uvec2, Ty::Uint) => Some(Ty::Uvec2),
+                (Ty::Uvec2, Ty::Uvec2) => Some(Ty::Uvec2),
+                (Ty::Uvec3, Ty::Uint) => Some(Ty::Uvec3),
+                (Ty::Uvec3, Ty::Uvec3) => Some(Ty::Uvec3),
+                (Ty::Uvec4, Ty::Uint) => Some(Ty::Uvec4),
+                (Ty::Uvec4, Ty::Uvec4) => Some(Ty::Uvec4),
                 (Ty::Vec2, Ty::Float) => Some(Ty::Vec2),
                 (Ty::Vec2, Ty::Vec2) => Some(Ty::Vec2),
                 (Ty::Vec3, Ty::Float) => Some(Ty::Vec3),
@@ -331,6 +357,13 @@ impl<'a> TyChecker<'a> {
                 (Ty::Ivec3, Ty::Ivec3) => Some(Ty::Ivec3),
                 (Ty::Ivec4, Ty::Int) => Some(Ty::Ivec4),
                 (Ty::Ivec4, Ty::Ivec4) => Some(Ty::Ivec4),
+                (Ty::Uint, Ty::Uint) => Some(Ty::Uint),
+                (Ty::Uvec2, Ty::Uint) => Some(Ty::Uvec2),
+                (Ty::Uvec2, Ty::Uvec2) => Some(Ty::Uvec2),
+                (Ty::Uvec3, Ty::Uint) => Some(Ty::Uvec3),
+                (Ty::Uvec3, Ty::Uvec3) => Some(Ty::Uvec3),
+                (Ty::Uvec4, Ty::Uint) => Some(Ty::Uvec4),
+                (Ty::Uvec4, Ty::Uvec4) => Some(Ty::Uvec4),
                 (Ty::Vec2, Ty::Float) => Some(Ty::Vec2),
                 (Ty::Vec2, Ty::Vec2) => Some(Ty::Vec2),
                 (Ty::Vec2, Ty::Mat2) => Some(Ty::Vec2),
@@ -737,6 +770,13 @@ impl<'a> TyChecker<'a> {
                         4 => Ty::Ivec4,
                         _ => panic!(),
                     },
+                    Ty::Uvec2 | Ty::Uvec3 | Ty::Uvec4 => match swizzle.len() {
+                        1 => Ty::Uint,
+                        2 => Ty::Uvec2,
+                        3 => Ty::Uvec3,
+                        4 => Ty::Uvec4,
+                        _ => panic!(),
+                    },
                     Ty::Vec2 | Ty::Vec3 | Ty::Vec4 => match swizzle.len() {
                         1 => Ty::Float,
                         2 => Ty::Vec2,
@@ -780,6 +820,7 @@ impl<'a> TyChecker<'a> {
         let elem_ty = match ty {
             Ty::Bvec2 | Ty::Bvec3 | Ty::Bvec4 => Ty::Bool,
             Ty::Ivec2 | Ty::Ivec3 | Ty::Ivec4 => Ty::Int,
+            Ty::Uvec2 | Ty::Uvec3 | Ty::Uvec4 => Ty::Uint,
             Ty::Vec2 | Ty::Vec3 | Ty::Vec4 => Ty::Float,
             Ty::Mat2 => Ty::Vec2,
             Ty::Mat3 => Ty::Vec3,
@@ -792,7 +833,7 @@ impl<'a> TyChecker<'a> {
                 })
             }
         };
-        if index_ty != Ty::Int {
+        if index_ty != Ty::Int && index_ty != Ty::Uint {
             return Err(LiveError {
                 origin: live_error_origin!(),
                 span:span.into(),
@@ -0,0 +1,158 @@
+use makepad_shader_compiler::{
+    makepad_live_compiler::{LiveModuleId, LiveRegistry, LiveTypeInfo, TextPos},
+    makepad_live_id::*,
+    DrawShaderConstTable,
+    DrawShaderPtr,
+    ShaderRegistry,
+};
+
+struct DrawTest;
+
+/// Samples every texture kind and does some unsigned integer math, so each of them has
+/// to make it through the analyser and the generators.
+const SOURCE: &str = "
+    DrawTest = {{DrawTest}} {
+        texture cube: textureCube
+        texture layers: texture2DArray
+        texture volume: texture3D
+
+        fn count(self, n: uint) -> uvec2 {
+            let v = uvec2(n, n * uint(2));
+            if v.y > v.x {
+                return v + uvec2(1, 1);
+            }
+            return v;
+        }
+
+        fn vertex(self) -> vec4 {
+            return vec4(0.0, 0.0, 0.0, 1.0);
+        }
+
+        fn pixel(self) -> vec4 {
+            let v = self.count(uint(3));
+            return sample_cube(self.cube, vec3(1.0, 0.0, 0.0))
+                + sample2d_array(self.layers, vec2(0.5, 0.5), float(v.x))
+                + sample3d(self.volume, vec3(0.5, 0.5, 0.5));
+        }
+    }
+";
+
+struct Analysed {
+    shader_registry: ShaderRegistry,
+    draw_shader_ptr: DrawShaderPtr,
+    const_table: DrawShaderConstTable,
+}
+
+/// Analyses the `DrawTest` shader in `source`, returning the error message when it doesn't check.
+fn analyse(source: &str) -> Result<Analysed, String> {
+    let module_id = LiveModuleId::from_str("test::generate").unwrap();
+    let mut live_registry = LiveRegistry::default();
+    let types = vec![LiveTypeInfo {
+        live_type: std::any::TypeId::of::<DrawTest>(),
+        type_name: live_id!(DrawTest),
+        module_id,
+        live_ignore: false,
+        fields: Vec::new(),
+    }];
+    live_registry.register_live_file("generate.rs", "", module_id, source.to_string(), types, TextPos::default())
+        .map_err( | e | e.message)?;
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    if let Some(e) = errors.pop() {
+        return Err(e.message)
+    }
+
+    let draw_shader_ptr = DrawShaderPtr(live_registry.module_id_and_name_to_ptr(module_id, live_id!(DrawTest)).unwrap());
+    let mut shader_registry = ShaderRegistry::new(true);
+    shader_registry.analyse_draw_shader(&live_registry, draw_shader_ptr, | _, _, _, _, _, _ | {})
+        .map_err( | e | e.message)?;
+    let const_table = shader_registry.compute_const_table(draw_shader_ptr);
+    Ok(Analysed {shader_registry, draw_shader_ptr, const_table})
+}
+
+fn assert_contains_all(source: &str, needles: &[&str]) {
+    for needle in needles {
+        assert!(source.contains(needle), "missing `{}` in\n{}", needle, source);
+    }
+}
+
+#[test]
+fn texture_declarations_use_the_type_literal_spelling() {
+    assert!(analyse(SOURCE).is_ok());
+    // declarations of unknown types are skipped, so the error shows up where the texture is used
+    for (good, bad, field) in [("texture2DArray", "texture2dArray", "layers"), ("texture3D", "texture3d", "volume")] {
+        let error = analyse(&SOURCE.replace(good, bad)).err().unwrap_or_else( || panic!("{} was accepted", bad));
+        assert!(error.contains(&format!("field `{}` is not defined", field)), "{}", error);
+    }
+}
+
+#[test]
+fn unsigned_math_doesnt_mix_with_signed() {
+    let error = analyse(&SOURCE.replace("n * uint(2)", "n * 2")).err().expect("uint * int was accepted");
+    assert!(error.contains("can't apply binary operator"), "{}", error);
+}
+
+#[cfg(any(target_os = "android", target_os = "linux", target_arch = "wasm32"))]
+#[test]
+fn glsl_declares_samplers_and_unsigned_types() {
+    use makepad_shader_compiler::generate_glsl;
+
+    let Analysed {shader_registry, draw_shader_ptr, const_table} = analyse(SOURCE).unwrap();
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let vertex = generate_glsl::generate_vertex_shader(draw_shader_def, &const_table, &shader_registry);
+    let pixel = generate_glsl::generate_pixel_shader(draw_shader_def, &const_table, &shader_registry);
+    assert_contains_all(&pixel, &[
+        "uniform samplerCube ds_cube;",
+        "uniform sampler2DArray ds_layers;",
+        "uniform sampler3D ds_volume;",
+        "uvec2 fn_0_5_count(uint var_n_0) {",
+        "uvec2 var_v_0 = uvec2(var_n_0, (var_n_0 * uint(",
+        "sample_cube(ds_cube, ",
+        "sample2d_array(ds_layers, ",
+        "sample3d(ds_volume, ",
+    ]);
+    // the sampler and uint types only exist in GLSL ES 3.00
+    assert!(generate_glsl::needs_glsl_es3(&pixel));
+    assert!(generate_glsl::needs_glsl_es3(&vertex));
+    assert!(!generate_glsl::needs_glsl_es3("uniform sampler2D ds_image; float uintensity;"));
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "tvos"))]
+#[test]
+fn metal_declares_samplers_and_unsigned_types() {
+    use makepad_shader_compiler::generate_metal;
+
+    let Analysed {shader_registry, draw_shader_ptr, const_table} = analyse(SOURCE).unwrap();
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let shader = generate_metal::generate_shader(draw_shader_def, &const_table, &shader_registry);
+    assert_contains_all(&shader.mtlsl, &[
+        "texturecube<float> ds_cube [[texture(0)]];",
+        "texture2d_array<float> ds_layers [[texture(1)]];",
+        "texture3d<float> ds_volume [[texture(2)]];",
+        "uint2 fn_0_5_count(",
+        "uint var_n_0",
+        "float4 sample_cube(texturecube<float> tex, float3 dir)",
+        "float4 sample2d_array(texture2d_array<float> tex, float2 pos, float layer)",
+        "float4 sample3d(texture3d<float> tex, float3 pos)",
+    ]);
+}
+
+#[cfg(target_os = "windows")]
+#[test]
+fn hlsl_declares_samplers_and_unsigned_types() {
+    use makepad_shader_compiler::generate_hlsl;
+
+    let Analysed {shader_registry, draw_shader_ptr, const_table} = analyse(SOURCE).unwrap();
+    let draw_shader_def = shader_registry.draw_shader_defs.get(&draw_shader_ptr).unwrap();
+    let shader = generate_hlsl::generate_shader(draw_shader_def, &const_table, &shader_registry);
+    assert_contains_all(&shader, &[
+        "TextureCube ds_cube: register(t0);",
+        "Texture2DArray ds_layers: register(t1);",
+        "Texture3D ds_volume: register(t2);",
+        "uint2 fn_0_5_count(",
+        "uint var_n_0",
+        "float4 sample_cube(TextureCube tex, float3 dir)",
+        "float4 sample2d_array(Texture2DArray tex, float2 pos, float layer)",
+        "float4 sample3d(Texture3D tex, float3 pos)",
+    ]);
+}
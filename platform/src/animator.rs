@@ -9,6 +9,9 @@ use {
             LiveValue,
             LiveTypeInfo,
             LiveTypeField,
+            LiveTypeDoc,
+            LiveFieldDoc,
+            LiveVariantDoc,
             LivePropType,
             LiveFieldKind,
             LiveModuleId,
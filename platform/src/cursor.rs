@@ -5,6 +5,8 @@ use {
         makepad_live_compiler::{
             LiveValue,
             LiveTypeInfo,
+            LiveTypeDoc,
+            LiveVariantDoc,
             LiveModuleId,
             LiveType,
             LiveId,
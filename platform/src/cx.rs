@@ -26,6 +26,7 @@ use {
             DrawEvent,
             CxFingers,
             CxDragDrop,
+            CxReadbacks,
             Event,
             Trigger,
             CxKeyboard,
@@ -39,6 +40,7 @@ use {
         draw_list::CxDrawListPool,
         web_socket::WebSocket,
         pass::CxPassPool,
+        locale::CxLocale,
         texture::{CxTexturePool,TextureFormat,Texture,TextureUpdated},
         geometry::{
             Geometry,
@@ -82,6 +84,7 @@ pub struct Cx {
     pub fingers: CxFingers,
     pub (crate) ime_area: Area,
     pub (crate) drag_drop: CxDragDrop,
+    pub (crate) readbacks: CxReadbacks,
     
     pub (crate) platform_ops: Vec<CxOsOp>,
     
@@ -91,12 +94,16 @@ pub struct Cx {
     
     pub (crate) dependencies: HashMap<String, CxDependency>,
     
+    pub (crate) locale: CxLocale,
+    
     pub (crate) triggers: HashMap<Area, Vec<Trigger >>,
     
     pub live_registry: Rc<RefCell<LiveRegistry >>,
 
     pub (crate) live_file_change_receiver: std::sync::mpsc::Receiver<Vec<LiveFileChange>>,
     pub (crate) live_file_change_sender: std::sync::mpsc::Sender<Vec<LiveFileChange >>,
+    /// set when a live document was loaded or unloaded at runtime
+    pub (crate) live_documents_changed: bool,
     
     pub (crate) action_receiver: std::sync::mpsc::Receiver<Action>,
     
@@ -263,6 +270,7 @@ impl Cx {
             keyboard: Default::default(),
             fingers: Default::default(),
             drag_drop: Default::default(),
+            readbacks: Default::default(),
             ime_area: Default::default(),
             platform_ops: Default::default(),
             studio_web_socket: None,
@@ -271,12 +279,15 @@ impl Cx {
             
             dependencies: Default::default(),
             
+            locale: Default::default(),
+            
             triggers: Default::default(),
             
             live_registry: Rc::new(RefCell::new(LiveRegistry::default())),
             
             live_file_change_receiver,
             live_file_change_sender,
+            live_documents_changed: false,
             action_receiver,
             
             shader_registry: ShaderRegistry::new(true),
@@ -641,6 +641,7 @@ impl DrawVars {
             live_id!(debug) => false,
             live_id!(debug_id) => false,
             live_id!(draw_call_group) => false,
+            id if DrawShaderFlags::is_render_state_id(id) => false,
             _ => true
         };
         
@@ -17,6 +17,7 @@ use {
             designer::*,
             network::*,
             video_playback::*,
+            readback::*,
         },
         action::ActionsBuf,
         animator::Ease,
@@ -136,6 +137,17 @@ pub enum Event {
     WindowClosed(WindowClosedEvent),
     WindowGeomChange(WindowGeomChangeEvent),
     VirtualKeyboard(VirtualKeyboardEvent),
+    /// The system switched between its light and dark theme. It is also sent
+    /// once after `Startup` with the theme the application starts in, and
+    /// switches the `dark_mode` condition of `if` blocks in live documents.
+    ///
+    /// | Platform | Source                                     |
+    /// |----------|--------------------------------------------|
+    /// | macOS    | `AppleInterfaceThemeChangedNotification`   |
+    /// | Windows  | `WM_SETTINGCHANGE` for `ImmersiveColorSet` |
+    /// | Web      | the `prefers-color-scheme` media query     |
+    /// | others   | coming soon...                             |
+    ThemeChange(ThemeChangeEvent),
     ClearAtlasses,
 
     MouseDown(MouseDownEvent),
@@ -174,6 +186,8 @@ pub enum Event {
     VideoPlaybackResourcesReleased(VideoPlaybackResourcesReleasedEvent),
     VideoDecodingError(VideoDecodingErrorEvent),
     TextureHandleReady(TextureHandleReadyEvent),
+    /// The pixels requested with `Texture::read_back` or `Pass::read_back`
+    Readback(ReadbackEvent),
     
     BackPressed,
     #[cfg(target_arch = "wasm32")]
@@ -253,7 +267,9 @@ impl Event{
             #[cfg(target_arch = "wasm32")]
             51=>"ToWasmMsg",
             
-            52=>"DesignerPick",            
+            52=>"DesignerPick",
+            53=>"Readback",
+            54=>"ThemeChange",
             _=>panic!()
         }
     }
@@ -325,6 +341,8 @@ impl Event{
             Self::ToWasmMsg(_)=>51,
             
             Self::DesignerPick(_) =>52,
+            Self::Readback(_) =>53,
+            Self::ThemeChange(_) =>54,
         }
     }
 }
@@ -438,6 +456,11 @@ pub enum VirtualKeyboardEvent{
     DidHide{time:f64},
 }
 
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ThemeChangeEvent {
+    pub dark_mode: bool,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct NextFrameEvent {
     pub frame: u64,
@@ -9,6 +9,9 @@ use {
             LivePropType,
             LiveType,
             LiveTypeField,
+            LiveTypeDoc,
+            LiveFieldDoc,
+            LiveVariantDoc,
             LiveFieldKind,
             LiveNode,
             LiveId,
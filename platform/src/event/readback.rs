@@ -0,0 +1,117 @@
+use crate::{
+    pass::PassId,
+    texture::{Texture, TextureId},
+};
+
+/// Identifies a single readback request, returned by `Texture::read_back` and `Pass::read_back`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReadbackId(pub u64);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadbackSource {
+    Texture(TextureId),
+    Pass(PassId),
+}
+
+/// Pixels read back from the GPU, top row first, packed as 0xAARRGGBB like the image cache uses.
+#[derive(Clone, Debug, Default)]
+pub struct ReadbackImage {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u32>,
+}
+
+impl ReadbackImage {
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            return None
+        }
+        self.data.get(y * self.width + x).copied()
+    }
+
+    /// Converts tightly packed RGBA rows as returned by glReadPixels, flipping them when
+    /// `bottom_up` is set since OpenGL framebuffers start at the bottom row.
+    pub fn from_rgba_u8(rgba: &[u8], width: usize, height: usize, bottom_up: bool) -> Self {
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = if bottom_up {height - 1 - y} else {y};
+            for px in rgba[row * width * 4..(row + 1) * width * 4].chunks_exact(4) {
+                data.push(
+                    ((px[3] as u32) << 24) | ((px[0] as u32) << 16) | ((px[1] as u32) << 8) | (px[2] as u32)
+                );
+            }
+        }
+        Self {width, height, data}
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadbackEvent {
+    pub readback_id: ReadbackId,
+    pub source: ReadbackSource,
+    pub result: Result<ReadbackImage, String>,
+}
+
+pub (crate) enum CxReadbackSource {
+    Texture(Texture),
+    Pass(PassId),
+}
+
+pub (crate) struct CxReadbackRequest {
+    pub readback_id: ReadbackId,
+    pub source: CxReadbackSource,
+}
+
+impl CxReadbackRequest {
+    pub fn source(&self) -> ReadbackSource {
+        match &self.source {
+            CxReadbackSource::Texture(texture) => ReadbackSource::Texture(texture.texture_id()),
+            CxReadbackSource::Pass(pass_id) => ReadbackSource::Pass(*pass_id),
+        }
+    }
+
+    pub fn complete(self, result: Result<ReadbackImage, String>) -> ReadbackEvent {
+        ReadbackEvent {
+            readback_id: self.readback_id,
+            source: self.source(),
+            result,
+        }
+    }
+}
+
+/// Readbacks are queued here and serviced by the backend after it repaints,
+/// the results are sent as `Event::Readback` once the repaint is done.
+/// Backends without readback support fail every request with an `Err` result instead.
+#[derive(Default)]
+pub struct CxReadbacks {
+    last_id: u64,
+    pub (crate) requests: Vec<CxReadbackRequest>,
+    pub (crate) completed: Vec<ReadbackEvent>,
+}
+
+impl CxReadbacks {
+    pub (crate) fn request(&mut self, source: CxReadbackSource) -> ReadbackId {
+        self.last_id += 1;
+        let readback_id = ReadbackId(self.last_id);
+        let request = CxReadbackRequest {readback_id, source};
+        if cfg!(any(target_os = "android", target_os = "linux")) {
+            self.requests.push(request);
+        }
+        else {
+            self.completed.push(request.complete(Err("GPU readback is only implemented for the OpenGL backend".to_string())));
+        }
+        readback_id
+    }
+
+    pub (crate) fn take_pass_requests(&mut self, pass_id: PassId) -> Vec<CxReadbackRequest> {
+        let (taken, rest) = self.requests.drain(..).partition( | r | matches!(r.source, CxReadbackSource::Pass(id) if id == pass_id));
+        self.requests = rest;
+        taken
+    }
+
+    pub (crate) fn take_texture_requests(&mut self) -> Vec<CxReadbackRequest> {
+        let (taken, rest) = self.requests.drain(..).partition( | r | matches!(r.source, CxReadbackSource::Texture(_)));
+        self.requests = rest;
+        taken
+    }
+}
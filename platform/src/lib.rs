@@ -16,6 +16,7 @@ pub mod action;
 pub mod live_traits;
 pub mod live_cx;
 pub mod live_atomic;
+pub mod locale;
 
 pub mod thread;
 pub mod audio;
@@ -99,6 +100,9 @@ pub use {
         LiveTypeInfo,
         LiveTypeField,
         LiveFieldKind,
+        LiveTypeDoc,
+        LiveFieldDoc,
+        LiveVariantDoc,
         LiveComponentInfo,
         LiveComponentRegistry,
         LivePropType,
@@ -182,6 +186,7 @@ pub use {
             FingerScrollEvent,
             WindowGeomChangeEvent,
             WindowMovedEvent,
+            ThemeChangeEvent,
             NextFrameEvent,
             TimerEvent,
             KeyEvent,
@@ -205,6 +210,10 @@ pub use {
             DropHitEvent,
             DesignerPickEvent,
             HitDesigner,
+            ReadbackId,
+            ReadbackSource,
+            ReadbackImage,
+            ReadbackEvent,
         },
         action::{
             Action,
@@ -229,6 +238,7 @@ pub use {
             Texture,
             TextureId,
             TextureFormat,
+            TextureDimension,
             TextureSize,
             TextureUpdated,
         },
@@ -236,6 +246,14 @@ pub use {
             LiveDependency,
             ArcStringMut,
         },
+        locale::{
+            Localization,
+            LocaleResource,
+            LocaleBundle,
+            LocaleFormat,
+            LocaleArg,
+            LocaleError,
+        },
         live_traits::{
             LiveHookDeref,
             LiveBody,
@@ -1,6 +1,7 @@
 use {
     crate::{
         makepad_micro_serde::*,
+        makepad_live_id::live_id,
         makepad_live_tokenizer::{LiveErrorOrigin, live_error_origin},
         makepad_live_compiler::{
             LiveFileChange,
@@ -15,8 +16,12 @@ use {
             LivePtr,
             /*LiveTokenId,*/
             LiveFileId,
+            LiveFileError,
+            LiveRegistry,
+            live_schema_diff,
         },
         studio::{StudioToAppVec,StudioToApp},
+        event::WindowGeom,
         web_socket::WebSocketMessage,
         makepad_live_compiler::LiveTypeInfo,
         /*makepad_math::*,*/
@@ -280,7 +285,9 @@ impl Cx {
             true
         }
         else{
-            false
+            // loading a live document or switching the language re-applies everything like a live edit
+            let documents_changed = std::mem::take(&mut self.live_documents_changed);
+            std::mem::take(&mut self.locale.changed) || documents_changed
         }
     }
     
@@ -288,6 +295,16 @@ impl Cx {
     pub fn live_expand(&mut self) {
         let mut errs = Vec::new();
         let mut live_registry = self.live_registry.borrow_mut();
+        // what `if` blocks can test, the window ones are updated when the window changes
+        for (id, value) in [
+            (live_id!(os), LiveValue::Id(Self::live_os_id())),
+            (live_id!(window_width), LiveValue::Float64(0.0)),
+            (live_id!(window_height), LiveValue::Float64(0.0)),
+            (live_id!(dpi_factor), LiveValue::Float64(1.0)),
+            (live_id!(dark_mode), LiveValue::Bool(false)),
+        ] {
+            live_registry.conditions.entry(id).or_insert(value);
+        }
         /* 
         for file in &live_registry.live_files {
             log!("{}. {}", file.module_id.0, file.module_id.1);        // lets expand the f'er
@@ -321,6 +338,44 @@ impl Cx {
             }
             println!("Error expanding live file {}", live_registry.live_error_to_live_file_error(err));
         }
+        Self::live_schema_export(&live_registry);
+    }
+    
+    /// `--live-schema=<dir>` writes the schema of every live type and its
+    /// Markdown reference pages to a directory, `--live-schema-check=<file>`
+    /// fails when the schema differs from a committed one. Both exit the app.
+    fn live_schema_export(live_registry: &LiveRegistry) {
+        for arg in std::env::args() {
+            if let Some(dir) = arg.strip_prefix("--live-schema=") {
+                let dir = std::path::Path::new(dir);
+                let mut files = vec![("schema.json".to_string(), live_registry.live_schema_json())];
+                files.extend(live_registry.live_schema_markdown());
+                for (path, content) in files {
+                    let path = dir.join(path);
+                    let written = std::fs::create_dir_all(path.parent().unwrap())
+                        .and_then( | _ | std::fs::write(&path, content));
+                    if let Err(err) = written {
+                        eprintln!("Cannot write live schema file {}: {}", path.display(), err);
+                        std::process::exit(1);
+                    }
+                }
+                std::process::exit(0);
+            }
+            if let Some(path) = arg.strip_prefix("--live-schema-check=") {
+                let committed = match std::fs::read_to_string(path) {
+                    Ok(committed) => committed,
+                    Err(err) => {
+                        eprintln!("Cannot read live schema {}: {}", path, err);
+                        std::process::exit(1);
+                    }
+                };
+                if let Some(diff) = live_schema_diff(&committed, &live_registry.live_schema_json()) {
+                    eprintln!("{}\nThe live DSL surface changed, regenerate {} with --live-schema", diff, path);
+                    std::process::exit(1);
+                }
+                std::process::exit(0);
+            }
+        }
     }
     
     pub fn live_scan_dependencies(&mut self) {
@@ -372,6 +427,130 @@ impl Cx {
             }
         }
     }
+    /// Loads a `.live` file from disk as the live module `module_path`, see
+    /// `load_live_str`. Resources referred to as `crate://self/..` are found
+    /// next to the file.
+    pub fn load_live_file(&mut self, module_path: &str, path: impl AsRef<std::path::Path>) -> Result<LiveFileId, Vec<LiveFileError>> {
+        let path = path.as_ref();
+        let file_name = path.to_string_lossy().to_string();
+        let source = std::fs::read_to_string(path).map_err( | err | vec![LiveFileError {
+            origin: live_error_origin!(),
+            file: file_name.clone(),
+            span: Default::default(),
+            message: format!("Cannot read live file: {}", err)
+        }])?;
+        let file_id = self.load_live_str(module_path, &file_name, &source)?;
+        let mut live_registry = self.live_registry.borrow_mut();
+        let live_file = live_registry.file_id_to_file_mut(file_id);
+        if live_file.cargo_manifest_path.is_empty() {
+            if let Some(dir) = path.parent() {
+                live_file.cargo_manifest_path = dir.to_string_lossy().to_string();
+            }
+        }
+        Ok(file_id)
+    }
+    
+    /// Registers a live document as the module `module_path`, or replaces the
+    /// source of that module if it exists. This is how theme overrides and
+    /// other customizations get in at runtime: a new module is picked up by
+    /// the documents importing it, and replacing a module from a `live_design!`
+    /// overrides it until `unload_live_module`. Everything depending on the
+    /// module is expanded again and the app re-applies its live data on the
+    /// next `Event::LiveEdit`.
+    ///
+    /// When the source doesn't parse nothing changes. Errors expanding the
+    /// documents are returned as well, but the document stays loaded.
+    pub fn load_live_str(&mut self, module_path: &str, file_name: &str, source: &str) -> Result<LiveFileId, Vec<LiveFileError>> {
+        let module_id = Self::live_module_id(module_path, file_name)?;
+        let file_id = self.live_registry.borrow_mut().load_live_document(file_name, module_id, source).map_err( | err | vec![err])?;
+        self.expand_live_documents()?;
+        Ok(file_id)
+    }
+    
+    /// Unloads a document loaded with `load_live_file` or `load_live_str`,
+    /// putting back the `live_design!` it replaced if there was one.
+    pub fn unload_live_module(&mut self, module_path: &str) -> Result<(), Vec<LiveFileError>> {
+        let module_id = Self::live_module_id(module_path, module_path)?;
+        self.live_registry.borrow_mut().unload_live_document(module_id).map_err( | err | vec![err])?;
+        self.expand_live_documents()
+    }
+    
+    /// Sets a value the conditions of `if` blocks in live documents can test.
+    /// When one of them goes the other way the documents are expanded again
+    /// and re-applied like a live edit.
+    ///
+    /// ```ignore
+    /// Sidebar = <View> {
+    ///     width: 300
+    ///     if window_width < 800 || os == android {
+    ///         width: Fill
+    ///     }
+    /// }
+    /// ```
+    pub fn set_live_condition(&mut self, id: LiveId, value: LiveValue) {
+        self.live_registry.borrow_mut().set_condition(id, value);
+        self.reexpand_changed_live_conditions();
+    }
+    
+    /// Switches `dark_mode` in the conditions of `if` blocks. Every
+    /// `Event::ThemeChange` from the platform comes through here.
+    pub fn set_dark_mode(&mut self, dark_mode: bool) {
+        self.set_live_condition(live_id!(dark_mode), LiveValue::Bool(dark_mode));
+    }
+    
+    pub (crate) fn update_window_live_conditions(&mut self, geom: &WindowGeom) {
+        {
+            let mut live_registry = self.live_registry.borrow_mut();
+            live_registry.set_condition(live_id!(window_width), LiveValue::Float64(geom.inner_size.x));
+            live_registry.set_condition(live_id!(window_height), LiveValue::Float64(geom.inner_size.y));
+            live_registry.set_condition(live_id!(dpi_factor), LiveValue::Float64(geom.dpi_factor));
+        }
+        self.reexpand_changed_live_conditions();
+    }
+    
+    fn reexpand_changed_live_conditions(&mut self) {
+        if !self.live_registry.borrow_mut().reexpand_changed_conditions() {
+            return
+        }
+        if let Err(errs) = self.expand_live_documents() {
+            for err in errs {
+                error!("Error expanding live file {}", err);
+            }
+        }
+    }
+    
+    fn live_os_id() -> LiveId {
+        if cfg!(target_env = "ohos") {live_id!(open_harmony)}
+        else if cfg!(target_os = "android") {live_id!(android)}
+        else if cfg!(target_os = "ios") {live_id!(ios)}
+        else if cfg!(target_os = "tvos") {live_id!(tvos)}
+        else if cfg!(target_os = "macos") {live_id!(macos)}
+        else if cfg!(target_os = "windows") {live_id!(windows)}
+        else if cfg!(target_arch = "wasm32") {live_id!(web)}
+        else {live_id!(linux)}
+    }
+    
+    fn live_module_id(module_path: &str, file_name: &str) -> Result<LiveModuleId, Vec<LiveFileError>> {
+        LiveModuleId::from_str(module_path).map_err( | err | vec![LiveFileError {
+            origin: live_error_origin!(),
+            file: file_name.to_string(),
+            span: Default::default(),
+            message: format!("Invalid module path {}: {}", module_path, err)
+        }])
+    }
+    
+    fn expand_live_documents(&mut self) -> Result<(), Vec<LiveFileError>> {
+        let mut errs = Vec::new();
+        let mut live_registry = self.live_registry.borrow_mut();
+        live_registry.expand_all_documents(&mut errs);
+        self.draw_shaders.reset_for_live_reload();
+        self.live_documents_changed = true;
+        if errs.is_empty() {
+            return Ok(())
+        }
+        Err(errs.into_iter().map( | err | live_registry.live_error_to_live_file_error(err)).collect())
+    }
+    
     /*
     fn update_buffer_from_live_value(slots: usize, output: &mut [f32], offset: usize, v: &LiveValue) {
         match slots {
@@ -3,6 +3,7 @@ use {
     crate::{
         makepad_live_compiler::*,
         makepad_math::*,
+        makepad_live_id::live_id,
         cx::Cx,
         live_traits::*,
         animator::Animator
@@ -661,6 +662,10 @@ live_primitive!(
                 self.push_str(v.as_str());
                 index + 1
             }
+            LiveValue::TupleEnum(live_id!(tr)) => {
+                *self = cx.apply_tr(index, nodes);
+                nodes.skip_node(index)
+            }
             LiveValue::Expr {..} => {
                 panic!("Expr node found whilst deserialising DSL")
             },
@@ -774,6 +779,10 @@ live_primitive!(
                 *self = ArcStringMut::String(v.as_str().to_string());
                 index + 1
             }
+            LiveValue::TupleEnum(live_id!(tr)) => {
+                *self = ArcStringMut::String(cx.apply_tr(index, nodes));
+                nodes.skip_node(index)
+            }
             LiveValue::Expr {..} => {
                 panic!("Expr node found whilst deserialising DSL")
             },
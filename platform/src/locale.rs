@@ -0,0 +1,1215 @@
+//! Translations for user-facing strings.
+//!
+//! Resources are written in a subset of Fluent (`.ftl`) or gettext (`.po`)
+//! and are usually loaded as live dependencies through `Localization`.
+//! In `live_design!` a string property can be given as `tr("key")`, or
+//! `tr("key", 3)` to pass `$count` for plural selection; these are resolved
+//! against the current language whenever the property is applied. Switching
+//! language with `Cx::set_locale` re-applies the whole application through
+//! the same path as a live edit, so every `tr` picks up the new text.
+
+use {
+    std::{
+        collections::{BTreeSet, HashMap},
+        fmt,
+    },
+    crate::{
+        makepad_derive_live::*,
+        makepad_live_compiler::{
+            LiveValue,
+            LiveNode,
+            LiveNodeSliceApi,
+            LivePropType,
+            LiveType,
+            LiveTypeField,
+            LiveTypeDoc,
+            LiveFieldDoc,
+            LiveFieldKind,
+            LiveModuleId,
+            LiveTypeInfo,
+        },
+        makepad_live_id::*,
+        makepad_live_tokenizer::{LiveErrorOrigin, live_error_origin},
+        live_traits::*,
+        live_prims::LiveDependency,
+        cx::Cx,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LocaleFormat {
+    Fluent,
+    Gettext,
+}
+
+impl LocaleFormat {
+    /// Picks the format from a file extension: `.po`/`.pot` are gettext,
+    /// everything else is read as Fluent.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".po") || path.ends_with(".pot") {
+            Self::Gettext
+        }
+        else {
+            Self::Fluent
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LocaleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A value that can be interpolated into a message with `{ $name }`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LocaleArg {
+    Str(String),
+    Number(f64),
+}
+
+impl fmt::Display for LocaleArg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Str(s) => write!(f, "{}", s),
+            Self::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Self::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl From<&str> for LocaleArg {
+    fn from(v: &str) -> Self {Self::Str(v.to_string())}
+}
+
+impl From<String> for LocaleArg {
+    fn from(v: String) -> Self {Self::Str(v)}
+}
+
+impl From<f64> for LocaleArg {
+    fn from(v: f64) -> Self {Self::Number(v)}
+}
+
+impl From<f32> for LocaleArg {
+    fn from(v: f32) -> Self {Self::Number(v as f64)}
+}
+
+impl From<i64> for LocaleArg {
+    fn from(v: i64) -> Self {Self::Number(v as f64)}
+}
+
+impl From<i32> for LocaleArg {
+    fn from(v: i32) -> Self {Self::Number(v as f64)}
+}
+
+impl From<usize> for LocaleArg {
+    fn from(v: usize) -> Self {Self::Number(v as f64)}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum LocalePart {
+    Text(String),
+    Var(String),
+    Ref(String),
+    /// Picks a variant by the value of `var`: an exact number match first,
+    /// then the plural category of the number, then the string itself.
+    Select {var: String, variants: Vec<(String, LocalePattern)>, default: usize},
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct LocalePattern(Vec<LocalePart>);
+
+/// The rule that maps a number onto a plural variant key.
+#[derive(Clone, Debug)]
+enum LocalePluralRule {
+    /// CLDR cardinal categories (`zero`, `one`, `two`, `few`, `many`, `other`).
+    Cldr(String),
+    /// A gettext `Plural-Forms` expression giving the variant index.
+    Gettext(PluralExpr),
+}
+
+/// All messages for one language.
+#[derive(Clone, Debug)]
+pub struct LocaleBundle {
+    language: String,
+    plural_rule: LocalePluralRule,
+    messages: HashMap<String, LocalePattern>,
+}
+
+impl LocaleBundle {
+    pub fn new(language: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            plural_rule: LocalePluralRule::Cldr(language.to_string()),
+            messages: HashMap::new(),
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn has_message(&self, key: &str) -> bool {
+        self.messages.contains_key(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map( | k | k.as_str())
+    }
+
+    /// Parses `source` and adds its messages, replacing existing ones with
+    /// the same key.
+    pub fn add_resource(&mut self, format: LocaleFormat, source: &str) -> Result<(), LocaleError> {
+        match format {
+            LocaleFormat::Fluent => parse_fluent(source, &mut self.messages),
+            LocaleFormat::Gettext => {
+                if let Some(rule) = parse_gettext(source, &mut self.messages)? {
+                    self.plural_rule = LocalePluralRule::Gettext(rule);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Formats a message, or returns `None` when this bundle doesn't have it.
+    pub fn format(&self, key: &str, args: &[(&str, LocaleArg)]) -> Option<String> {
+        let pattern = self.messages.get(key)?;
+        let mut out = String::new();
+        self.format_pattern(pattern, args, &mut out, 0);
+        Some(out)
+    }
+
+    fn plural_key(&self, n: f64) -> String {
+        match &self.plural_rule {
+            LocalePluralRule::Cldr(language) => plural_category(language, n).to_string(),
+            LocalePluralRule::Gettext(expr) => expr.eval(n.abs() as u64).to_string(),
+        }
+    }
+
+    fn format_pattern(&self, pattern: &LocalePattern, args: &[(&str, LocaleArg)], out: &mut String, depth: usize) {
+        for part in &pattern.0 {
+            match part {
+                LocalePart::Text(text) => out.push_str(text),
+                LocalePart::Var(name) => match args.iter().find( | (n, _) | n == name) {
+                    Some((_, arg)) => out.push_str(&arg.to_string()),
+                    None => {
+                        out.push('{');
+                        out.push_str(name);
+                        out.push('}');
+                    }
+                },
+                LocalePart::Ref(key) => match self.messages.get(key) {
+                    // references can't recurse forever
+                    Some(pattern) if depth < 8 => self.format_pattern(pattern, args, out, depth + 1),
+                    _ => out.push_str(key),
+                },
+                LocalePart::Select {var, variants, default} => {
+                    let arg = args.iter().find( | (n, _) | n == var).map( | (_, arg) | arg);
+                    let index = match arg {
+                        Some(LocaleArg::Number(n)) => {
+                            // gettext variants are keyed by plural index, not by value
+                            let exact = match self.plural_rule {
+                                LocalePluralRule::Cldr(_) => variants.iter().position( | (key, _) | key.parse::<f64>().ok() == Some(*n)),
+                                LocalePluralRule::Gettext(_) => None,
+                            };
+                            let category = self.plural_key(*n);
+                            exact.or_else( || variants.iter().position( | (key, _) | *key == category))
+                        }
+                        Some(LocaleArg::Str(s)) => variants.iter().position( | (key, _) | key == s),
+                        None => None,
+                    }.unwrap_or(*default);
+                    if let Some((_, pattern)) = variants.get(index) {
+                        self.format_pattern(pattern, args, out, depth);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The CLDR cardinal plural category of `n` for the common languages.
+/// Languages without a rule here use the English one.
+pub fn plural_category(language: &str, n: f64) -> &'static str {
+    let lang = language.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+    let integer = n.fract() == 0.0;
+    let i = n.abs().trunc() as u64;
+    match lang.as_str() {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" | "lo" | "my" => "other",
+        "fr" | "hy" | "kab" => if i == 0 || i == 1 {"one"} else {"other"},
+        "ru" | "uk" | "be" => {
+            if !integer {"other"}
+            else if i % 10 == 1 && i % 100 != 11 {"one"}
+            else if (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {"few"}
+            else {"many"}
+        }
+        "pl" => {
+            if !integer {"other"}
+            else if i == 1 {"one"}
+            else if (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {"few"}
+            else {"many"}
+        }
+        "cs" | "sk" => {
+            if !integer {"many"}
+            else if i == 1 {"one"}
+            else if (2..=4).contains(&i) {"few"}
+            else {"other"}
+        }
+        "ar" => {
+            if !integer {"other"}
+            else if i == 0 {"zero"}
+            else if i == 1 {"one"}
+            else if i == 2 {"two"}
+            else if (3..=10).contains(&(i % 100)) {"few"}
+            else if (11..=99).contains(&(i % 100)) {"many"}
+            else {"other"}
+        }
+        "he" => {
+            if integer && i == 1 {"one"}
+            else if integer && i == 2 {"two"}
+            else {"other"}
+        }
+        _ => if integer && i == 1 {"one"} else {"other"},
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '-')
+        && chars.all( | c | c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+fn parse_fluent(source: &str, messages: &mut HashMap<String, LocalePattern>) -> Result<(), LocaleError> {
+    // group lines into entries, continuation lines are indented
+    let mut entries: Vec<(usize, String, Vec<&str>)> = Vec::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let line_nr = line_index + 1;
+        // the closing brace of a select may sit at the start of a line
+        if line.starts_with(' ') || line.starts_with('\t') || line.starts_with('}') {
+            match entries.last_mut() {
+                Some((_, _, lines)) => lines.push(line),
+                None if line.trim().is_empty() => (),
+                None => return Err(LocaleError {line: line_nr, message: "indented line outside a message".to_string()}),
+            }
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            if let Some((_, _, lines)) = entries.last_mut() {
+                lines.push("");
+            }
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(LocaleError {line: line_nr, message: format!("expected `key = value`, found `{}`", trimmed)})
+        };
+        let key = key.trim();
+        if !is_identifier(key) {
+            return Err(LocaleError {line: line_nr, message: format!("invalid message identifier `{}`", key)})
+        }
+        entries.push((line_nr, key.to_string(), vec![value]));
+    }
+
+    for (line_nr, key, lines) in entries {
+        // attributes (`.label = ...`) become `key.label`
+        let mut current_key = key.clone();
+        let mut current: Vec<&str> = Vec::new();
+        let mut values: Vec<(String, Vec<&str>)> = Vec::new();
+        for (index, line) in lines.into_iter().enumerate() {
+            let trimmed = line.trim_start();
+            if index > 0 && trimmed.starts_with('.') {
+                if let Some((attr, value)) = trimmed[1..].split_once('=') {
+                    if is_identifier(attr.trim()) {
+                        values.push((current_key, std::mem::take(&mut current)));
+                        current_key = format!("{}.{}", key, attr.trim());
+                        current.push(value);
+                        continue;
+                    }
+                }
+            }
+            current.push(line);
+        }
+        values.push((current_key, current));
+
+        for (key, lines) in values {
+            let text = join_fluent_lines(&lines);
+            if text.is_empty() && !key.contains('.') {
+                // a message with only attributes
+                continue;
+            }
+            let mut parser = FluentParser {chars: text.chars().collect(), pos: 0, line: line_nr};
+            let pattern = parser.parse_pattern(false)?;
+            messages.insert(key, pattern);
+        }
+    }
+    Ok(())
+}
+
+/// Joins the lines of a multiline value, removing their common indentation.
+fn join_fluent_lines(lines: &[&str]) -> String {
+    let first = lines.first().map( | l | l.trim()).unwrap_or("");
+    let rest: Vec<&str> = lines.iter().skip(1).copied().collect();
+    let last_content = rest.iter().rposition( | l | !l.trim().is_empty());
+    let rest = match last_content {
+        Some(last) => &rest[..=last],
+        None => &rest[..0],
+    };
+    let indent = rest.iter()
+        .filter( | l | !l.trim().is_empty())
+        .map( | l | l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut text = first.to_string();
+    for line in rest {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(line.get(indent..).unwrap_or("").trim_end());
+    }
+    text
+}
+
+struct FluentParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl FluentParser {
+    fn error(&self, message: String) -> LocaleError {
+        let line = self.line + self.chars[..self.pos.min(self.chars.len())].iter().filter( | c | **c == '\n').count();
+        LocaleError {line, message}
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn at_variant_start(&self) -> bool {
+        let mut pos = self.pos;
+        while matches!(self.chars.get(pos), Some(c) if c.is_whitespace()) {
+            pos += 1;
+        }
+        match self.chars.get(pos) {
+            Some('*') => self.chars.get(pos + 1) == Some(&'['),
+            Some('[') | Some('}') => true,
+            _ => false,
+        }
+    }
+
+    /// Parses text and placeables until the end, or in a select expression
+    /// until the next variant starts.
+    fn parse_pattern(&mut self, in_select: bool) -> Result<LocalePattern, LocaleError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if in_select && c == '\n' && self.at_variant_start() {
+                break;
+            }
+            if c == '{' {
+                self.pos += 1;
+                if !text.is_empty() {
+                    parts.push(LocalePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(self.parse_placeable()?);
+                continue;
+            }
+            if c == '}' {
+                return Err(self.error("unbalanced `}`".to_string()))
+            }
+            text.push(c);
+            self.pos += 1;
+        }
+        if in_select {
+            let trimmed = text.trim_end().len();
+            text.truncate(trimmed);
+        }
+        if !text.is_empty() {
+            parts.push(LocalePart::Text(text));
+        }
+        Ok(LocalePattern(parts))
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                ident.push(c);
+                self.pos += 1;
+            }
+            else {
+                break;
+            }
+        }
+        ident
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), LocaleError> {
+        self.skip_blank();
+        if self.peek() != Some(c) {
+            return Err(self.error(format!("expected `{}`", c)))
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_placeable(&mut self) -> Result<LocalePart, LocaleError> {
+        self.skip_blank();
+        let part = match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut literal = String::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => {
+                            self.pos += 1;
+                            break
+                        }
+                        Some('\\') => {
+                            self.pos += 1;
+                            if let Some(c) = self.peek() {
+                                literal.push(c);
+                                self.pos += 1;
+                            }
+                        }
+                        Some(c) => {
+                            literal.push(c);
+                            self.pos += 1;
+                        }
+                        None => return Err(self.error("unterminated string literal".to_string()))
+                    }
+                }
+                LocalePart::Text(literal)
+            }
+            Some('$') => {
+                self.pos += 1;
+                let var = self.parse_identifier();
+                if var.is_empty() {
+                    return Err(self.error("expected a variable name after `$`".to_string()))
+                }
+                self.skip_blank();
+                if self.peek() == Some('-') && self.chars.get(self.pos + 1) == Some(&'>') {
+                    self.pos += 2;
+                    return self.parse_select(var)
+                }
+                LocalePart::Var(var)
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '-' => LocalePart::Ref(self.parse_identifier()),
+            _ => return Err(self.error("expected a variable, message reference or string in `{ }`".to_string()))
+        };
+        self.expect('}')?;
+        Ok(part)
+    }
+
+    fn parse_select(&mut self, var: String) -> Result<LocalePart, LocaleError> {
+        let mut variants = Vec::new();
+        let mut default = None;
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    break
+                }
+                Some('*') => {
+                    self.pos += 1;
+                    default = Some(variants.len());
+                }
+                Some('[') => (),
+                _ => return Err(self.error("expected a `[variant]` in select expression".to_string()))
+            }
+            self.expect('[')?;
+            self.skip_blank();
+            let mut key = String::new();
+            while let Some(c) = self.peek() {
+                if c == ']' || c.is_whitespace() {
+                    break;
+                }
+                key.push(c);
+                self.pos += 1;
+            }
+            self.expect(']')?;
+            while matches!(self.peek(), Some(' ') | Some('\t')) {
+                self.pos += 1;
+            }
+            let pattern = self.parse_pattern(true)?;
+            variants.push((key, pattern));
+        }
+        let Some(default) = default else {
+            return Err(self.error("select expression needs a default `*[variant]`".to_string()))
+        };
+        Ok(LocalePart::Select {var, variants, default})
+    }
+}
+
+/// Turns a gettext `{name}` string into a pattern.
+fn parse_braced(text: &str) -> LocalePattern {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start + 1..].find('}') else {
+            break
+        };
+        let name = rest[start + 1..start + 1 + len].trim().trim_start_matches('$');
+        if !is_identifier(name) {
+            parts.push(LocalePart::Text(rest[..start + 1].to_string()));
+            rest = &rest[start + 1..];
+            continue;
+        }
+        if start > 0 {
+            parts.push(LocalePart::Text(rest[..start].to_string()));
+        }
+        parts.push(LocalePart::Var(name.to_string()));
+        rest = &rest[start + 2 + len..];
+    }
+    if !rest.is_empty() {
+        parts.push(LocalePart::Text(rest.to_string()));
+    }
+    // merge the text split off by stray braces
+    let mut merged: Vec<LocalePart> = Vec::new();
+    for part in parts {
+        match (merged.last_mut(), part) {
+            (Some(LocalePart::Text(a)), LocalePart::Text(b)) => a.push_str(&b),
+            (_, part) => merged.push(part),
+        }
+    }
+    LocalePattern(merged)
+}
+
+fn unescape_po(line: &str, line_nr: usize) -> Result<String, LocaleError> {
+    let line = line.trim();
+    if line.len() < 2 || !line.starts_with('"') || !line.ends_with('"') {
+        return Err(LocaleError {line: line_nr, message: format!("expected a quoted string, found `{}`", line)})
+    }
+    let mut out = String::new();
+    let mut chars = line[1..line.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(c) => out.push(c),
+            None => (),
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Default)]
+struct PoEntry {
+    fuzzy: bool,
+    msgid: Option<String>,
+    msgid_plural: Option<String>,
+    msgstr: Vec<(usize, String)>,
+}
+
+/// Adds the translated entries of a `.po` file. Plural entries select on
+/// `$count`. Returns the `Plural-Forms` rule from the header if there is one.
+fn parse_gettext(source: &str, messages: &mut HashMap<String, LocalePattern>) -> Result<Option<PluralExpr>, LocaleError> {
+    let mut entries = Vec::new();
+    let mut entry = PoEntry::default();
+    // which string continuation lines append to
+    enum Target {None, Id, IdPlural, Str(usize)}
+    let mut target = Target::None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_nr = line_index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(flags) = trimmed.strip_prefix("#,") {
+            if entry.msgid.is_some() {
+                entries.push(std::mem::take(&mut entry));
+            }
+            entry.fuzzy = flags.split(',').any( | f | f.trim() == "fuzzy");
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('"') {
+            let value = unescape_po(trimmed, line_nr)?;
+            match &mut target {
+                Target::Id => entry.msgid.get_or_insert_with(String::new).push_str(&value),
+                Target::IdPlural => entry.msgid_plural.get_or_insert_with(String::new).push_str(&value),
+                Target::Str(index) => {
+                    if let Some((_, s)) = entry.msgstr.iter_mut().find( | (i, _) | i == index) {
+                        s.push_str(&value);
+                    }
+                }
+                Target::None => return Err(LocaleError {line: line_nr, message: "string without a keyword".to_string()}),
+            }
+            continue;
+        }
+        let (keyword, value) = trimmed.split_once(|c: char| c.is_whitespace()).unwrap_or((trimmed, ""));
+        if keyword == "msgctxt" {
+            continue;
+        }
+        let value = unescape_po(value, line_nr)?;
+        if keyword == "msgid" {
+            if entry.msgid.is_some() {
+                entries.push(std::mem::take(&mut entry));
+            }
+            entry.msgid = Some(value);
+            target = Target::Id;
+        }
+        else if keyword == "msgid_plural" {
+            entry.msgid_plural = Some(value);
+            target = Target::IdPlural;
+        }
+        else if keyword == "msgstr" {
+            entry.msgstr.push((0, value));
+            target = Target::Str(0);
+        }
+        else if let Some(index) = keyword.strip_prefix("msgstr[").and_then( | k | k.strip_suffix(']')) {
+            let Ok(index) = index.parse::<usize>() else {
+                return Err(LocaleError {line: line_nr, message: format!("invalid plural index in `{}`", keyword)})
+            };
+            entry.msgstr.push((index, value));
+            target = Target::Str(index);
+        }
+        else {
+            return Err(LocaleError {line: line_nr, message: format!("unknown keyword `{}`", keyword)})
+        }
+    }
+    if entry.msgid.is_some() {
+        entries.push(entry);
+    }
+
+    let mut plural_rule = None;
+    for entry in entries {
+        let Some(msgid) = entry.msgid else {
+            continue
+        };
+        if msgid.is_empty() {
+            // the header
+            let header = entry.msgstr.first().map( | (_, s) | s.as_str()).unwrap_or("");
+            for line in header.lines() {
+                if let Some(forms) = line.trim().strip_prefix("Plural-Forms:") {
+                    if let Some(expr) = forms.split(';').find_map( | f | f.trim().strip_prefix("plural=")) {
+                        plural_rule = Some(PluralExpr::parse(expr).map_err( | message | LocaleError {line: 1, message})?);
+                    }
+                }
+            }
+            continue;
+        }
+        // untranslated and fuzzy entries fall back to another language
+        if entry.fuzzy || entry.msgstr.iter().all( | (_, s) | s.is_empty()) {
+            continue;
+        }
+        let pattern = if entry.msgid_plural.is_some() {
+            let mut variants: Vec<(String, LocalePattern)> = entry.msgstr.iter()
+                .map( | (index, s) | (index.to_string(), parse_braced(s)))
+                .collect();
+            variants.sort_by_key( | (index, _) | index.parse::<usize>().unwrap_or(0));
+            let default = variants.len().saturating_sub(1);
+            LocalePattern(vec![LocalePart::Select {var: "count".to_string(), variants, default}])
+        }
+        else {
+            parse_braced(&entry.msgstr[0].1)
+        };
+        messages.insert(msgid, pattern);
+    }
+    Ok(plural_rule)
+}
+
+/// A gettext `Plural-Forms` expression, a C expression over `n`.
+#[derive(Clone, Debug)]
+enum PluralExpr {
+    N,
+    Const(u64),
+    Not(Box<PluralExpr>),
+    Binary(PluralOp, Box<PluralExpr>, Box<PluralExpr>),
+    Ternary(Box<PluralExpr>, Box<PluralExpr>, Box<PluralExpr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PluralOp {Or, And, Eq, Ne, Lt, Le, Gt, Ge, Add, Sub, Mul, Div, Rem}
+
+impl PluralExpr {
+    fn parse(source: &str) -> Result<Self, String> {
+        let tokens: Vec<char> = source.chars().filter( | c | !c.is_whitespace()).collect();
+        let mut pos = 0;
+        let expr = Self::parse_ternary(&tokens, &mut pos)?;
+        if pos != tokens.len() {
+            return Err(format!("unexpected `{}` in plural expression", tokens[pos..].iter().collect::<String>()))
+        }
+        Ok(expr)
+    }
+
+    fn parse_ternary(t: &[char], pos: &mut usize) -> Result<Self, String> {
+        let cond = Self::parse_binary(t, pos, 0)?;
+        if t.get(*pos) == Some(&'?') {
+            *pos += 1;
+            let a = Self::parse_ternary(t, pos)?;
+            if t.get(*pos) != Some(&':') {
+                return Err("expected `:` in plural expression".to_string())
+            }
+            *pos += 1;
+            let b = Self::parse_ternary(t, pos)?;
+            return Ok(Self::Ternary(Box::new(cond), Box::new(a), Box::new(b)))
+        }
+        Ok(cond)
+    }
+
+    fn peek_op(t: &[char], pos: usize) -> Option<(PluralOp, usize, usize)> {
+        let two: String = t.iter().skip(pos).take(2).collect();
+        let op = match two.as_str() {
+            "||" => (PluralOp::Or, 0, 2),
+            "&&" => (PluralOp::And, 1, 2),
+            "==" => (PluralOp::Eq, 2, 2),
+            "!=" => (PluralOp::Ne, 2, 2),
+            "<=" => (PluralOp::Le, 3, 2),
+            ">=" => (PluralOp::Ge, 3, 2),
+            _ => match t.get(pos)? {
+                '<' => (PluralOp::Lt, 3, 1),
+                '>' => (PluralOp::Gt, 3, 1),
+                '+' => (PluralOp::Add, 4, 1),
+                '-' => (PluralOp::Sub, 4, 1),
+                '*' => (PluralOp::Mul, 5, 1),
+                '/' => (PluralOp::Div, 5, 1),
+                '%' => (PluralOp::Rem, 5, 1),
+                _ => return None
+            }
+        };
+        Some(op)
+    }
+
+    fn parse_binary(t: &[char], pos: &mut usize, min_prec: usize) -> Result<Self, String> {
+        let mut lhs = Self::parse_unary(t, pos)?;
+        while let Some((op, prec, len)) = Self::peek_op(t, *pos) {
+            if prec < min_prec {
+                break;
+            }
+            *pos += len;
+            let rhs = Self::parse_binary(t, pos, prec + 1)?;
+            lhs = Self::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(t: &[char], pos: &mut usize) -> Result<Self, String> {
+        match t.get(*pos) {
+            Some('!') => {
+                *pos += 1;
+                Ok(Self::Not(Box::new(Self::parse_unary(t, pos)?)))
+            }
+            Some('(') => {
+                *pos += 1;
+                let expr = Self::parse_ternary(t, pos)?;
+                if t.get(*pos) != Some(&')') {
+                    return Err("expected `)` in plural expression".to_string())
+                }
+                *pos += 1;
+                Ok(expr)
+            }
+            Some('n') => {
+                *pos += 1;
+                Ok(Self::N)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut value = 0u64;
+                while let Some(d) = t.get(*pos).and_then( | c | c.to_digit(10)) {
+                    value = value * 10 + d as u64;
+                    *pos += 1;
+                }
+                Ok(Self::Const(value))
+            }
+            _ => Err("unexpected end of plural expression".to_string())
+        }
+    }
+
+    fn eval(&self, n: u64) -> u64 {
+        match self {
+            Self::N => n,
+            Self::Const(v) => *v,
+            Self::Not(a) => (a.eval(n) == 0) as u64,
+            Self::Ternary(c, a, b) => if c.eval(n) != 0 {a.eval(n)} else {b.eval(n)},
+            Self::Binary(op, a, b) => {
+                let (a, b) = (a.eval(n), b.eval(n));
+                match op {
+                    PluralOp::Or => (a != 0 || b != 0) as u64,
+                    PluralOp::And => (a != 0 && b != 0) as u64,
+                    PluralOp::Eq => (a == b) as u64,
+                    PluralOp::Ne => (a != b) as u64,
+                    PluralOp::Lt => (a < b) as u64,
+                    PluralOp::Le => (a <= b) as u64,
+                    PluralOp::Gt => (a > b) as u64,
+                    PluralOp::Ge => (a >= b) as u64,
+                    PluralOp::Add => a.wrapping_add(b),
+                    PluralOp::Sub => a.wrapping_sub(b),
+                    PluralOp::Mul => a.wrapping_mul(b),
+                    PluralOp::Div => a.checked_div(b).unwrap_or(0),
+                    PluralOp::Rem => a.checked_rem(b).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub (crate) struct CxLocale {
+    bundles: Vec<LocaleBundle>,
+    current: Option<String>,
+    fallback: Option<String>,
+    /// Keys that were looked up but found in no bundle.
+    missing: BTreeSet<String>,
+    /// The `(language, path)` pairs `Localization` loaded last.
+    loaded: Vec<(String, String)>,
+    /// Set when the language changed, the next live edit check re-applies
+    /// the application.
+    pub (crate) changed: bool,
+}
+
+impl CxLocale {
+    fn bundle(&self, language: &str) -> Option<&LocaleBundle> {
+        self.bundles.iter().find( | b | b.language == language)
+    }
+}
+
+impl Cx {
+    /// Adds a resource to the bundle of `language`, creating it if needed.
+    pub fn add_locale_resource(&mut self, language: &str, format: LocaleFormat, source: &str) -> Result<(), LocaleError> {
+        if self.locale.bundle(language).is_none() {
+            self.locale.bundles.push(LocaleBundle::new(language));
+        }
+        let bundle = self.locale.bundles.iter_mut().find( | b | b.language == language).unwrap();
+        bundle.add_resource(format, source)?;
+        if self.locale.current.as_deref() == Some(language) {
+            self.locale.changed = true;
+        }
+        Ok(())
+    }
+
+    /// Adds a resource that was loaded as a live dependency, for instance
+    /// `dep("crate://self/resources/locale/de.ftl")`.
+    pub fn add_locale_dependency(&mut self, language: &str, path: &str) -> Result<(), LocaleError> {
+        let data = self.get_dependency(path).map_err( | message | LocaleError {line: 0, message})?;
+        let source = std::str::from_utf8(&data).map_err( | e | LocaleError {line: 0, message: e.to_string()})?;
+        self.add_locale_resource(language, LocaleFormat::from_path(path), source)
+    }
+
+    /// Drops all resources of a language.
+    pub fn remove_locale(&mut self, language: &str) {
+        self.locale.bundles.retain( | b | b.language != language);
+    }
+
+    /// Switches the language. Everything applied from `live_design!` is
+    /// re-applied, as on a live edit, so `tr(...)` strings update.
+    pub fn set_locale(&mut self, language: &str) {
+        if self.locale.current.as_deref() != Some(language) {
+            self.locale.current = Some(language.to_string());
+            self.locale.changed = true;
+        }
+    }
+
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.current.as_deref()
+    }
+
+    /// The language used for keys the current language doesn't have.
+    pub fn set_fallback_locale(&mut self, language: &str) {
+        self.locale.fallback = Some(language.to_string());
+    }
+
+    /// The languages that have resources.
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.locale.bundles.iter().map( | b | b.language.as_str())
+    }
+
+    pub fn locale_bundle(&self, language: &str) -> Option<&LocaleBundle> {
+        self.locale.bundle(language)
+    }
+
+    /// Keys that were looked up but found in neither the current nor the
+    /// fallback language.
+    pub fn missing_locale_keys(&self) -> impl Iterator<Item = &str> {
+        self.locale.missing.iter().map( | k | k.as_str())
+    }
+
+    pub fn tr(&mut self, key: &str) -> String {
+        self.tr_args(key, &[])
+    }
+
+    /// Looks `key` up in the current language, then the fallback language.
+    /// Returns the key itself when neither has it.
+    pub fn tr_args(&mut self, key: &str, args: &[(&str, LocaleArg)]) -> String {
+        let locale = &self.locale;
+        let found = locale.current.as_deref().and_then( | l | locale.bundle(l))
+            .and_then( | b | b.format(key, args))
+            .or_else( || locale.fallback.as_deref().and_then( | l | locale.bundle(l)).and_then( | b | b.format(key, args)));
+        match found {
+            Some(text) => text,
+            None => {
+                if self.locale.current.is_some() && self.locale.missing.insert(key.to_string()) {
+                    crate::log!("Missing translation for {} in {}", key, self.locale.current.as_deref().unwrap_or(""));
+                }
+                key.to_string()
+            }
+        }
+    }
+
+    /// Resolves a `tr("key")` or `tr("key", count)` value in the DSL.
+    pub (crate) fn apply_tr(&mut self, index: usize, nodes: &[LiveNode]) -> String {
+        let mut key = None;
+        let mut args = Vec::new();
+        let mut child = index + 1;
+        while !nodes[child].is_close() {
+            match (&nodes[child].value, key.is_some()) {
+                (LiveValue::Str(s), false) => key = Some(s.to_string()),
+                (LiveValue::String(s), false) => key = Some(s.to_string()),
+                (LiveValue::InlineString(s), false) => key = Some(s.as_str().to_string()),
+                (LiveValue::Int64(v), true) => args.push(("count", LocaleArg::Number(*v as f64))),
+                (LiveValue::Float64(v), true) => args.push(("count", LocaleArg::Number(*v))),
+                _ => {
+                    self.apply_error_wrong_value_type_for_primitive(live_error_origin!(), child, nodes, "tr(\"key\", count)");
+                }
+            }
+            child = nodes.skip_node(child);
+        }
+        match key {
+            Some(key) => self.tr_args(&key, &args),
+            None => String::new()
+        }
+    }
+}
+
+/// One resource file for `Localization`.
+#[derive(Live, LiveHook, Clone, Debug)]
+#[live_ignore]
+pub struct LocaleResource {
+    #[live] pub language: String,
+    #[live] pub source: LiveDependency,
+}
+
+impl LiveRegister for LocaleResource {}
+
+/// Loads translation resources from the DSL and picks the starting
+/// language. Put it on the app struct so it is applied with the rest:
+///
+/// ```text
+/// localization: {
+///     language: "en"
+///     fallback: "en"
+///     resources: [
+///         {language: "en", source: dep("crate://self/resources/locale/en.ftl")}
+///         {language: "nl", source: dep("crate://self/resources/locale/nl.po")}
+///     ]
+/// }
+/// ```
+///
+/// `language` only sets the starting language; a language picked at runtime
+/// with `Cx::set_locale` survives live edits.
+#[derive(Live, Clone, Debug)]
+#[live_ignore]
+pub struct Localization {
+    #[live] pub language: String,
+    #[live] pub fallback: String,
+    #[live] pub resources: Vec<LocaleResource>,
+}
+
+impl LiveRegister for Localization {}
+
+impl LiveHook for Localization {
+    fn after_apply(&mut self, cx: &mut Cx, _apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        if !self.fallback.is_empty() {
+            cx.set_fallback_locale(&self.fallback);
+        }
+        if cx.locale().is_none() && !self.language.is_empty() {
+            cx.set_locale(&self.language);
+        }
+        // this is applied again on every live edit, only reload when the list changed
+        let loaded: Vec<(String, String)> = self.resources.iter()
+            .map( | r | (r.language.clone(), r.source.as_str().to_string()))
+            .collect();
+        if cx.locale.loaded == loaded {
+            return
+        }
+        for (language, _) in &cx.locale.loaded.clone() {
+            cx.remove_locale(language);
+        }
+        for (language, path) in &loaded {
+            if let Err(err) = cx.add_locale_dependency(language, path) {
+                crate::log!("Error loading locale resource {}: {}", path, err);
+            }
+        }
+        cx.locale.loaded = loaded;
+        // strings applied before the resources were loaded need another pass
+        cx.locale.changed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLUENT: &str = r#"
+# the greeting on the start screen
+hello = Hello, { $name }!
+emails = { $count ->
+    [0] No new emails
+    [one] One new email
+   *[other] { $count } new emails
+}
+brand = Makepad
+welcome = Welcome to { brand }
+button = Save
+    .tooltip = Save the file
+multiline =
+    First line
+      indented
+    Last line
+braces = { "{" }literal{ "}" }
+"#;
+
+    const GETTEXT: &str = r#"
+msgid ""
+msgstr ""
+"Language: pl\n"
+"Plural-Forms: nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);\n"
+
+#. a translator comment
+msgid "Open {name}"
+msgstr "Otwórz {$name}"
+
+msgid "file"
+msgid_plural "files"
+msgstr[0] "{count} plik"
+msgstr[1] "{count} pliki"
+msgstr[2] "{count} plików"
+
+#, fuzzy
+msgid "Close"
+msgstr "Zamknij"
+
+msgid "Untranslated"
+msgstr ""
+
+msgid "multi"
+msgstr ""
+"line one\n"
+"line \"two\" {not a var}"
+"#;
+
+    fn bundle(language: &str, format: LocaleFormat, source: &str) -> LocaleBundle {
+        let mut bundle = LocaleBundle::new(language);
+        bundle.add_resource(format, source).unwrap();
+        bundle
+    }
+
+    fn count(bundle: &LocaleBundle, key: &str, n: f64) -> String {
+        bundle.format(key, &[("count", n.into())]).unwrap()
+    }
+
+    fn error(format: LocaleFormat, source: &str) -> LocaleError {
+        LocaleBundle::new("en").add_resource(format, source).unwrap_err()
+    }
+
+    #[test]
+    fn plural_categories_follow_cldr() {
+        let categories = | language: &str, numbers: &[f64] | -> Vec<&'static str> {
+            numbers.iter().map( | n | plural_category(language, *n)).collect()
+        };
+        assert_eq!(categories("en", &[0.0, 1.0, 1.5, 2.0]), ["other", "one", "other", "other"]);
+        assert_eq!(categories("fr", &[0.0, 1.5, 2.0]), ["one", "one", "other"]);
+        assert_eq!(categories("ru_RU", &[1.0, 2.0, 5.0, 11.0, 21.0, 22.0, 112.0, 1.5]), ["one", "few", "many", "many", "one", "few", "many", "other"]);
+        assert_eq!(categories("pl", &[1.0, 21.0, 22.0, 25.0]), ["one", "many", "few", "many"]);
+        assert_eq!(categories("cs", &[1.0, 3.0, 5.0, 0.5]), ["one", "few", "other", "many"]);
+        assert_eq!(categories("ar", &[0.0, 1.0, 2.0, 103.0, 111.0, 100.0]), ["zero", "one", "two", "few", "many", "other"]);
+        assert_eq!(categories("ja", &[1.0]), ["other"]);
+        // languages without a rule of their own use the English one
+        assert_eq!(categories("pt-BR", &[1.0, 2.0]), ["one", "other"]);
+    }
+
+    #[test]
+    fn fluent_messages_interpolate_select_and_reference() {
+        let en = bundle("en", LocaleFormat::Fluent, FLUENT);
+        assert_eq!(en.format("hello", &[("name", "Ann".into())]).unwrap(), "Hello, Ann!");
+        // missing arguments stay visible
+        assert_eq!(en.format("hello", &[]).unwrap(), "Hello, {name}!");
+        // an exact number wins over the plural category
+        assert_eq!(count(&en, "emails", 0.0), "No new emails");
+        assert_eq!(count(&en, "emails", 1.0), "One new email");
+        assert_eq!(count(&en, "emails", 5.0), "5 new emails");
+        assert_eq!(count(&en, "emails", 2.5), "2.5 new emails");
+        assert_eq!(en.format("emails", &[]).unwrap(), "{count} new emails");
+        assert_eq!(en.format("welcome", &[]).unwrap(), "Welcome to Makepad");
+        assert_eq!(en.format("button", &[]).unwrap(), "Save");
+        assert_eq!(en.format("button.tooltip", &[]).unwrap(), "Save the file");
+        assert_eq!(en.format("multiline", &[]).unwrap(), "First line\n  indented\nLast line");
+        assert_eq!(en.format("braces", &[]).unwrap(), "{literal}");
+        assert_eq!(en.format("nope", &[]), None);
+
+        // the plural category comes from the language of the bundle
+        let ru = bundle("ru", LocaleFormat::Fluent, "files = { $count ->\n    [one] { $count } файл\n    [few] { $count } файла\n   *[many] { $count } файлов\n}\n");
+        assert_eq!(count(&ru, "files", 21.0), "21 файл");
+        assert_eq!(count(&ru, "files", 3.0), "3 файла");
+        assert_eq!(count(&ru, "files", 11.0), "11 файлов");
+    }
+
+    #[test]
+    fn fluent_errors_point_at_their_line() {
+        let err = error(LocaleFormat::Fluent, "ok = fine\nno equals sign");
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("key = value"), "{}", err);
+        assert_eq!(error(LocaleFormat::Fluent, "  indented = 1").line, 1);
+        assert!(error(LocaleFormat::Fluent, "0key = value").message.contains("identifier"));
+        assert!(error(LocaleFormat::Fluent, "a = { $x").message.contains("`}`"));
+        assert!(error(LocaleFormat::Fluent, "a = b }").message.contains("unbalanced"));
+        let err = error(LocaleFormat::Fluent, "a = ok\nb = { $n ->\n    [one] one\n    [other] other\n}");
+        assert!(err.message.contains("default"), "{}", err);
+        // counted from the start of the message to where the select ends
+        assert_eq!(err.line, 5);
+    }
+
+    #[test]
+    fn gettext_entries_use_the_plural_forms_of_the_header() {
+        let pl = bundle("pl", LocaleFormat::Gettext, GETTEXT);
+        assert_eq!(pl.format("Open {name}", &[("name", "plik.rs".into())]).unwrap(), "Otwórz plik.rs");
+        assert_eq!(count(&pl, "file", 1.0), "1 plik");
+        assert_eq!(count(&pl, "file", 3.0), "3 pliki");
+        assert_eq!(count(&pl, "file", 5.0), "5 plików");
+        assert_eq!(count(&pl, "file", 12.0), "12 plików");
+        assert_eq!(count(&pl, "file", 22.0), "22 pliki");
+        // fuzzy and untranslated entries are left to the fallback language
+        assert!(!pl.has_message("Close"));
+        assert!(!pl.has_message("Untranslated"));
+        assert_eq!(pl.format("multi", &[]).unwrap(), "line one\nline \"two\" {not a var}");
+
+        // the last form is used when the expression picks one that isn't there
+        let en = bundle("en", LocaleFormat::Gettext, "msgid \"\"\nmsgstr \"Plural-Forms: nplurals=2; plural=n*3;\\n\"\nmsgid \"a\"\nmsgid_plural \"as\"\nmsgstr[0] \"one a\"\nmsgstr[1] \"{count} as\"\n");
+        assert_eq!(count(&en, "a", 0.0), "one a");
+        assert_eq!(count(&en, "a", 2.0), "2 as");
+    }
+
+    #[test]
+    fn gettext_errors_point_at_their_line() {
+        let err = error(LocaleFormat::Gettext, "msgid \"a\"\nmsgstr \"b\"\nmsgfoo \"c\"");
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("msgfoo"), "{}", err);
+        assert_eq!(error(LocaleFormat::Gettext, "\"dangling\"").line, 1);
+        assert_eq!(error(LocaleFormat::Gettext, "msgid \"a\"\nmsgstr[x] \"b\"").line, 2);
+        assert_eq!(error(LocaleFormat::Gettext, "msgid \"a\"\nmsgstr unquoted").line, 2);
+        let err = error(LocaleFormat::Gettext, "msgid \"\"\nmsgstr \"Plural-Forms: nplurals=2; plural=(n==1 ? 0;\\n\"");
+        assert!(err.message.contains("plural"), "{}", err);
+    }
+}
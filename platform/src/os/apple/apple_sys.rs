@@ -591,7 +591,7 @@ pub enum MTLPixelFormat {
     Depth32Float = 252,
     //Stencil8 = 253,
     //Depth24Unorm_Stencil8 = 255,
-    //Depth32Float_Stencil8 = 260,
+    Depth32Float_Stencil8 = 260,
 }
 
 #[repr(u64)]
@@ -659,6 +659,18 @@ pub enum MTLCompareFunction {
     Always = 7,
 }
 
+#[repr(u64)]
+pub enum MTLStencilOperation {
+    Keep = 0,
+    Zero = 1,
+    Replace = 2,
+    IncrementClamp = 3,
+    DecrementClamp = 4,
+    Invert = 5,
+    IncrementWrap = 6,
+    DecrementWrap = 7,
+}
+
 #[repr(u64)]
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
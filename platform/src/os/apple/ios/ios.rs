@@ -69,6 +69,8 @@ impl Cx {
     }
 
     pub (crate) fn handle_repaint(&mut self, metal_cx: &mut MetalCx) {
+        // readbacks aren't supported on this backend, they were completed with an error on request
+        self.call_readback_events();
         let mut passes_todo = Vec::new();
         self.compute_pass_repaint_order(&mut passes_todo);
         self.repaint_id += 1;
@@ -40,6 +40,7 @@ use {
             WindowGeom,
             MouseUpEvent,
             Event,
+            ThemeChangeEvent,
             NetworkResponseChannel
         },
         window::CxWindowPool,
@@ -175,11 +176,15 @@ impl Cx {
         get_macos_app_global().start_timer(0, 0.008, true);
         
         cx.borrow_mut().call_event_handler(&Event::Startup);
+        let dark_mode = get_macos_app_global().dark_mode;
+        cx.borrow_mut().call_event_handler(&Event::ThemeChange(ThemeChangeEvent {dark_mode}));
         cx.borrow_mut().redraw_all();
         MacosApp::event_loop();
     }
     
     pub (crate) fn handle_repaint(&mut self, metal_windows: &mut Vec<MetalWindow>, metal_cx: &mut MetalCx) {
+        // readbacks aren't supported on this backend, they were completed with an error on request
+        self.call_readback_events();
         let mut passes_todo = Vec::new();
         self.compute_pass_repaint_order(&mut passes_todo);
         self.repaint_id += 1;
@@ -289,6 +294,10 @@ impl Cx {
             MacosEvent::AppLostFocus => {
                 self.call_event_handler(&Event::AppLostFocus);
             }
+            MacosEvent::ThemeChange(e) => {
+                self.call_event_handler(&Event::ThemeChange(e));
+                self.redraw_all();
+            }
             MacosEvent::WindowResizeLoopStart(window_id) => {
                 if let Some(window) = metal_windows.iter_mut().find( | w | w.window_id == window_id) {
                     window.start_resize();
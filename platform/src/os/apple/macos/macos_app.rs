@@ -41,6 +41,7 @@ use {
             TextInputEvent,
             TextClipboardEvent,
             TimerEvent,
+            ThemeChangeEvent,
             KeyModifiers,
         },
         cursor::MouseCursor,
@@ -132,6 +133,8 @@ pub struct MacosApp {
     
     pub cursors: HashMap<MouseCursor, ObjcId>,
     pub current_cursor: MouseCursor,
+    /// the theme the last `ThemeChange` went out for
+    pub dark_mode: bool,
     //current_ns_event: Option<ObjcId>,
 }
 
@@ -144,13 +147,23 @@ impl MacosApp {
             let () = msg_send![ns_app, setDelegate: app_delegate_instance];
             let () = msg_send![ns_app, setActivationPolicy: NSApplicationActivationPolicy::NSApplicationActivationPolicyRegular as i64];
             
+            let timer_delegate_instance: ObjcId = msg_send![get_macos_class_global().timer_delegate, new];
+            // switching between light and dark is only announced to everyone
+            let center: ObjcId = msg_send![class!(NSDistributedNotificationCenter), defaultCenter];
+            let () = msg_send![
+                center,
+                addObserver: timer_delegate_instance
+                selector: sel!(receivedThemeChange:)
+                name: str_to_nsstring("AppleInterfaceThemeChangedNotification")
+                object: nil
+            ];
             
             // Construct the bits that are shared between windows
             MacosApp {
                 startup_focus_hack_ran: false,
                 pasteboard: msg_send![class!(NSPasteboard), generalPasteboard],
                 time_start: Instant::now(),
-                timer_delegate_instance: msg_send![get_macos_class_global().timer_delegate, new],
+                timer_delegate_instance,
                 menu_delegate_instance: msg_send![get_macos_class_global().menu_delegate, new],
                 //app_delegate_instance,
                 //signals: Mutex::new(RefCell::new(HashSet::new())),
@@ -161,6 +174,7 @@ impl MacosApp {
                 event_callback: Some(event_callback),
                 cursors: HashMap::new(),
                 current_cursor: MouseCursor::Default,
+                dark_mode: Self::is_dark_mode(),
                 //current_ns_event: None,
             }
         }
@@ -728,6 +742,24 @@ impl MacosApp {
     pub fn send_paint_event() {
         MacosApp::do_callback(MacosEvent::Paint);
     }
+    
+    pub fn send_theme_change() {
+        let dark_mode = Self::is_dark_mode();
+        if get_macos_app_global().dark_mode != dark_mode {
+            get_macos_app_global().dark_mode = dark_mode;
+            MacosApp::do_callback(MacosEvent::ThemeChange(ThemeChangeEvent {dark_mode}));
+        }
+    }
+    
+    /// Whether the system is in its dark appearance, which only sets
+    /// `AppleInterfaceStyle` when it is.
+    pub fn is_dark_mode() -> bool {
+        unsafe {
+            let defaults: ObjcId = msg_send![class!(NSUserDefaults), standardUserDefaults];
+            let style: ObjcId = msg_send![defaults, stringForKey: str_to_nsstring("AppleInterfaceStyle")];
+            style != nil && nsstring_to_string(style) == "Dark"
+        }
+    }
     /*
     #[cfg(target_os = "macos")]
     pub fn start_dragging(&mut self, items: Vec<DragItem>) {
@@ -58,6 +58,10 @@ pub fn define_macos_timer_delegate() -> *const Class {
         MacosApp::send_paint_event();
     }
     
+    extern fn received_theme_change(_this: &Object, _: Sel, _notification: ObjcId) {
+        MacosApp::send_theme_change();
+    }
+    
     let superclass = class!(NSObject);
     let mut decl = ClassDecl::new("TimerDelegate", superclass).unwrap();
     
@@ -65,6 +69,7 @@ pub fn define_macos_timer_delegate() -> *const Class {
     unsafe {
         decl.add_method(sel!(receivedTimer:), received_timer as extern fn(&Object, Sel, ObjcId));
         decl.add_method(sel!(receivedLiveResize:), received_live_resize as extern fn(&Object, Sel, ObjcId));
+        decl.add_method(sel!(receivedThemeChange:), received_theme_change as extern fn(&Object, Sel, ObjcId));
     }
 
     
@@ -11,6 +11,7 @@ use crate::{
         WindowDragQueryEvent,
         WindowCloseRequestedEvent,
         WindowClosedEvent,
+        ThemeChangeEvent,
         TextInputEvent,
         KeyEvent,
         DragEvent,
@@ -28,6 +29,7 @@ pub enum MacosEvent {
     WindowResizeLoopStop(WindowId),
     WindowGeomChange(WindowGeomChangeEvent),
     WindowClosed(WindowClosedEvent),
+    ThemeChange(ThemeChangeEvent),
     Paint,
     
     MouseDown(MouseDownEvent),
@@ -10,6 +10,11 @@ use {
         makepad_shader_compiler::{
             generate_metal,
             generate_metal::MetalGeneratedShader,
+            DrawShaderFlags,
+            BlendFactor,
+            BlendOp,
+            CompareFunc,
+            StencilOp,
         },
         makepad_math::*,
         makepad_live_id::*,
@@ -30,6 +35,7 @@ use {
             Texture,
             TexturePixel,
             TextureFormat,
+            TextureDimension,
         },
     },
     std::time::{Instant},
@@ -117,6 +123,14 @@ impl Cx {
                 }
                 let render_pipeline_state = shp.render_pipeline_state.as_id();
                 unsafe {let () = msg_send![encoder, setRenderPipelineState: render_pipeline_state];}
+                if self.passes[pass_id].depth_texture.is_some() {
+                    let depth_stencil_state = shp.depth_stencil_state.as_id();
+                    let reference = sh.mapping.flags.stencil.reference as u32;
+                    unsafe {
+                        let () = msg_send![encoder, setDepthStencilState: depth_stencil_state];
+                        let () = msg_send![encoder, setStencilReferenceValue: reference];
+                    }
+                }
                 
                 let geometry_id = if let Some(geometry_id) = draw_call.geometry_id {geometry_id}
                 else {
@@ -401,38 +415,37 @@ impl Cx {
             }
             let () = unsafe {msg_send![depth_attachment, setStoreAction: MTLStoreAction::Store]};
             
+            // the depth texture also carries the stencil
+            let stencil_attachment: ObjcId = unsafe {msg_send![render_pass_descriptor, stencilAttachment]};
+            if let Some(texture) = cxtexture.os.texture.as_ref() {
+                unsafe {msg_send![stencil_attachment, setTexture: texture.as_id()]}
+            }
+            let () = unsafe {msg_send![stencil_attachment, setStoreAction: MTLStoreAction::Store]};
+            
             match self.passes[pass_id].clear_depth {
                 PassClearDepth::InitWith(depth) => {
                     if is_initial {
                         let () = unsafe {msg_send![depth_attachment, setLoadAction: MTLLoadAction::Clear]};
                         let () = unsafe {msg_send![depth_attachment, setClearDepth: depth as f64]};
+                        let () = unsafe {msg_send![stencil_attachment, setLoadAction: MTLLoadAction::Clear]};
+                        let () = unsafe {msg_send![stencil_attachment, setClearStencil: 0u32]};
                     }
                     else {
                         let () = unsafe {msg_send![depth_attachment, setLoadAction: MTLLoadAction::Load]};
+                        let () = unsafe {msg_send![stencil_attachment, setLoadAction: MTLLoadAction::Load]};
                     }
                 },
                 PassClearDepth::ClearWith(depth) => {
                     let () = unsafe {msg_send![depth_attachment, setLoadAction: MTLLoadAction::Clear]};
                     let () = unsafe {msg_send![depth_attachment, setClearDepth: depth as f64]};
+                    let () = unsafe {msg_send![stencil_attachment, setLoadAction: MTLLoadAction::Clear]};
+                    let () = unsafe {msg_send![stencil_attachment, setClearStencil: 0u32]};
                 }
             }
-            // create depth state
-            if self.passes[pass_id].os.mtl_depth_state.is_none() {
-                
-                let desc: ObjcId = unsafe {msg_send![class!(MTLDepthStencilDescriptor), new]};
-                let () = unsafe {msg_send![desc, setDepthCompareFunction: MTLCompareFunction::LessEqual]};
-                let () = unsafe {msg_send![desc, setDepthWriteEnabled: true]};
-                let depth_stencil_state: ObjcId = unsafe {msg_send![metal_cx.device, newDepthStencilStateWithDescriptor: desc]};
-                self.passes[pass_id].os.mtl_depth_state = Some(depth_stencil_state);
-            }
         }
         
         let command_buffer: ObjcId = unsafe {msg_send![metal_cx.command_queue, commandBuffer]};
         let encoder: ObjcId = unsafe {msg_send![command_buffer, renderCommandEncoderWithDescriptor: render_pass_descriptor]};
-        
-        if let Some(depth_state) = self.passes[pass_id].os.mtl_depth_state {
-            let () = unsafe {msg_send![encoder, setDepthStencilState: depth_state]};
-        }
 
         let () = unsafe {msg_send![encoder, setViewport: MTLViewport {
             originX: 0.0,
@@ -530,13 +543,13 @@ impl Cx {
                 }
                 // lets see if we have the shader already
                 for (index, ds) in self.draw_shaders.os_shaders.iter().enumerate() {
-                    if ds.mtlsl == gen.mtlsl {
+                    if ds.mtlsl == gen.mtlsl && ds.flags.render_state_eq(&cx_shader.mapping.flags) {
                         cx_shader.os_shader_id = Some(index);
                         break;
                     }
                 }
                 if cx_shader.os_shader_id.is_none() {
-                    if let Some(shp) = CxOsDrawShader::new(metal_cx, gen) {
+                    if let Some(shp) = CxOsDrawShader::new(metal_cx, gen, cx_shader.mapping.flags) {
                         cx_shader.os_shader_id = Some(self.draw_shaders.os_shaders.len());
                         self.draw_shaders.os_shaders.push(shp);
                     }
@@ -602,7 +615,6 @@ pub struct CxOsView {
 
 #[derive(Default, Clone)]
 pub struct CxOsPass {
-    mtl_depth_state: Option<ObjcId>
 }
 
 pub enum PackType {
@@ -634,13 +646,67 @@ pub struct CxOsDrawShader {
     pass_uniform_buffer_id: Option<u64>,
     view_uniform_buffer_id: Option<u64>,
     user_uniform_buffer_id: Option<u64>,
+    depth_stencil_state: RcObjcId,
+    flags: DrawShaderFlags,
     mtlsl: String,
 }
 
+fn blend_factor_to_mtl(factor: BlendFactor) -> MTLBlendFactor {
+    match factor {
+        BlendFactor::Zero => MTLBlendFactor::Zero,
+        BlendFactor::One => MTLBlendFactor::One,
+        BlendFactor::SrcColor => MTLBlendFactor::SourceColor,
+        BlendFactor::OneMinusSrcColor => MTLBlendFactor::OneMinusSourceColor,
+        BlendFactor::SrcAlpha => MTLBlendFactor::SourceAlpha,
+        BlendFactor::OneMinusSrcAlpha => MTLBlendFactor::OneMinusSourceAlpha,
+        BlendFactor::DstColor => MTLBlendFactor::DestinationColor,
+        BlendFactor::OneMinusDstColor => MTLBlendFactor::OneMinusDestinationColor,
+        BlendFactor::DstAlpha => MTLBlendFactor::DestinationAlpha,
+        BlendFactor::OneMinusDstAlpha => MTLBlendFactor::OneMinusDestinationAlpha,
+    }
+}
+
+fn blend_op_to_mtl(op: BlendOp) -> MTLBlendOperation {
+    match op {
+        BlendOp::Add => MTLBlendOperation::Add,
+        BlendOp::Subtract => MTLBlendOperation::Subtract,
+        BlendOp::ReverseSubtract => MTLBlendOperation::ReverseSubtract,
+        BlendOp::Min => MTLBlendOperation::Min,
+        BlendOp::Max => MTLBlendOperation::Max,
+    }
+}
+
+fn compare_func_to_mtl(func: CompareFunc) -> MTLCompareFunction {
+    match func {
+        CompareFunc::Never => MTLCompareFunction::Never,
+        CompareFunc::Less => MTLCompareFunction::Less,
+        CompareFunc::Equal => MTLCompareFunction::Equal,
+        CompareFunc::LessEqual => MTLCompareFunction::LessEqual,
+        CompareFunc::Greater => MTLCompareFunction::Greater,
+        CompareFunc::NotEqual => MTLCompareFunction::NotEqual,
+        CompareFunc::GreaterEqual => MTLCompareFunction::GreaterEqual,
+        CompareFunc::Always => MTLCompareFunction::Always,
+    }
+}
+
+fn stencil_op_to_mtl(op: StencilOp) -> MTLStencilOperation {
+    match op {
+        StencilOp::Keep => MTLStencilOperation::Keep,
+        StencilOp::Zero => MTLStencilOperation::Zero,
+        StencilOp::Replace => MTLStencilOperation::Replace,
+        StencilOp::IncrementClamp => MTLStencilOperation::IncrementClamp,
+        StencilOp::DecrementClamp => MTLStencilOperation::DecrementClamp,
+        StencilOp::Invert => MTLStencilOperation::Invert,
+        StencilOp::IncrementWrap => MTLStencilOperation::IncrementWrap,
+        StencilOp::DecrementWrap => MTLStencilOperation::DecrementWrap,
+    }
+}
+
 impl CxOsDrawShader {
     pub (crate) fn new(
         metal_cx: &MetalCx,
         shader: MetalGeneratedShader,
+        flags: DrawShaderFlags,
     ) -> Option<Self> {
         let options = RcObjcId::from_owned(unsafe {msg_send![class!(MTLCompileOptions), new]});
         unsafe {
@@ -689,15 +755,21 @@ impl CxOsDrawShader {
             let color_attachments: ObjcId = msg_send![descriptor.as_id(), colorAttachments];
             let color_attachment: ObjcId = msg_send![color_attachments, objectAtIndexedSubscript: 0];
             let () = msg_send![color_attachment, setPixelFormat: MTLPixelFormat::BGRA8Unorm];
-            let () = msg_send![color_attachment, setBlendingEnabled: YES];
-            let () = msg_send![color_attachment, setRgbBlendOperation: MTLBlendOperation::Add];
-            let () = msg_send![color_attachment, setAlphaBlendOperation: MTLBlendOperation::Add];
-            let () = msg_send![color_attachment, setSourceRGBBlendFactor: MTLBlendFactor::One];
-            let () = msg_send![color_attachment, setSourceAlphaBlendFactor: MTLBlendFactor::One];
-            let () = msg_send![color_attachment, setDestinationRGBBlendFactor: MTLBlendFactor::OneMinusSourceAlpha];
-            let () = msg_send![color_attachment, setDestinationAlphaBlendFactor: MTLBlendFactor::OneMinusSourceAlpha];
+            if let Some((src, dst, op)) = flags.blend.factors() {
+                let () = msg_send![color_attachment, setBlendingEnabled: YES];
+                let () = msg_send![color_attachment, setRgbBlendOperation: blend_op_to_mtl(op)];
+                let () = msg_send![color_attachment, setAlphaBlendOperation: blend_op_to_mtl(op)];
+                let () = msg_send![color_attachment, setSourceRGBBlendFactor: blend_factor_to_mtl(src)];
+                let () = msg_send![color_attachment, setSourceAlphaBlendFactor: blend_factor_to_mtl(src)];
+                let () = msg_send![color_attachment, setDestinationRGBBlendFactor: blend_factor_to_mtl(dst)];
+                let () = msg_send![color_attachment, setDestinationAlphaBlendFactor: blend_factor_to_mtl(dst)];
+            }
+            else {
+                let () = msg_send![color_attachment, setBlendingEnabled: NO];
+            }
             
-            let () = msg_send![descriptor.as_id(), setDepthAttachmentPixelFormat: MTLPixelFormat::Depth32Float];
+            let () = msg_send![descriptor.as_id(), setDepthAttachmentPixelFormat: MTLPixelFormat::Depth32Float_Stencil8];
+            let () = msg_send![descriptor.as_id(), setStencilAttachmentPixelFormat: MTLPixelFormat::Depth32Float_Stencil8];
             
             let mut error: ObjcId = nil;
             msg_send![
@@ -724,9 +796,30 @@ impl CxOsDrawShader {
             buffer_id += 1;
         }
         
+        let depth_stencil_state = unsafe {
+            let desc = RcObjcId::from_owned(NonNull::new(msg_send![class!(MTLDepthStencilDescriptor), new]).unwrap());
+            let () = msg_send![desc.as_id(), setDepthCompareFunction: compare_func_to_mtl(flags.depth.test)];
+            let () = msg_send![desc.as_id(), setDepthWriteEnabled: flags.depth.write];
+            let stencil = &flags.stencil;
+            if stencil.is_enabled() {
+                let stencil_desc = RcObjcId::from_owned(NonNull::new(msg_send![class!(MTLStencilDescriptor), new]).unwrap());
+                let () = msg_send![stencil_desc.as_id(), setStencilCompareFunction: compare_func_to_mtl(stencil.test)];
+                let () = msg_send![stencil_desc.as_id(), setStencilFailureOperation: stencil_op_to_mtl(stencil.fail)];
+                let () = msg_send![stencil_desc.as_id(), setDepthFailureOperation: stencil_op_to_mtl(stencil.depth_fail)];
+                let () = msg_send![stencil_desc.as_id(), setDepthStencilPassOperation: stencil_op_to_mtl(stencil.pass)];
+                let () = msg_send![stencil_desc.as_id(), setReadMask: stencil.read_mask as u32];
+                let () = msg_send![stencil_desc.as_id(), setWriteMask: stencil.write_mask as u32];
+                let () = msg_send![desc.as_id(), setFrontFaceStencil: stencil_desc.as_id()];
+                let () = msg_send![desc.as_id(), setBackFaceStencil: stencil_desc.as_id()];
+            }
+            RcObjcId::from_owned(NonNull::new(msg_send![metal_cx.device, newDepthStencilStateWithDescriptor: desc.as_id()]).unwrap())
+        };
+        
         return Some(Self {
             _library: library,
             render_pipeline_state,
+            depth_stencil_state,
+            flags,
             draw_uniform_buffer_id,
             pass_uniform_buffer_id,
             view_uniform_buffer_id,
@@ -828,7 +921,8 @@ fn texture_pixel_to_mtl_pixel(pix:&TexturePixel)-> MTLPixelFormat {
          TexturePixel::Ru8  => MTLPixelFormat::R8Unorm,
          TexturePixel::RGu8  => MTLPixelFormat::RG8Unorm,
          TexturePixel::Rf32  => MTLPixelFormat::R32Float,
-         TexturePixel::D32 => MTLPixelFormat::Depth32Float,
+         // depth textures carry a stencil for the draw shaders that use one
+         TexturePixel::D32 => MTLPixelFormat::Depth32Float_Stencil8,
      }   
 }
 impl CxTexture {
@@ -844,8 +938,15 @@ impl CxTexture {
                 msg_send![class!(MTLTextureDescriptor), new]
             }).unwrap());
                         
-            let _: () = unsafe {msg_send![descriptor.as_id(), setTextureType: MTLTextureType::D2]};
-            let _: () = unsafe {msg_send![descriptor.as_id(), setDepth: 1u64]};
+            let (texture_type, depth, array_length) = match self.format.dimension() {
+                TextureDimension::D2 => (MTLTextureType::D2, 1, 1),
+                TextureDimension::Cube => (MTLTextureType::Cube, 1, 1),
+                TextureDimension::D2Array => (MTLTextureType::D2Array, 1, alloc.depth as u64),
+                TextureDimension::D3 => (MTLTextureType::D3, alloc.depth as u64, 1),
+            };
+            let _: () = unsafe {msg_send![descriptor.as_id(), setTextureType: texture_type]};
+            let _: () = unsafe {msg_send![descriptor.as_id(), setDepth: depth]};
+            let _: () = unsafe {msg_send![descriptor.as_id(), setArrayLength: array_length]};
             let _: () = unsafe {msg_send![descriptor.as_id(), setStorageMode: MTLStorageMode::Shared]};
             let _: () = unsafe {msg_send![descriptor.as_id(), setUsage: MTLTextureUsage::ShaderRead]};
             let _: () = unsafe {msg_send![descriptor.as_id(), setWidth: alloc.width as u64]};
@@ -869,8 +970,39 @@ impl CxTexture {
                     bytesPerRow: (width as u64) * bpp
                 ]};
             }
+            // cube faces and array layers are slices, a 3D texture is a single slice `depth` deep
+            fn update_slices(texture:&Option<RcObjcId>, width: usize, height: usize, depth: usize, slices: usize, bpp: u64, data: *const u8){
+                let bytes_per_image = (width * height) as u64 * bpp;
+                let region = MTLRegion {
+                    origin: MTLOrigin {x: 0, y: 0, z: 0},
+                    size: MTLSize {width: width as u64, height: height as u64, depth: depth as u64}
+                };
+                for slice in 0..slices {
+                    let () = unsafe {msg_send![
+                        texture.as_ref().unwrap().as_id(),
+                        replaceRegion: region
+                        mipmapLevel: 0
+                        slice: slice as u64
+                        withBytes: data.add(slice * bytes_per_image as usize) as *const std::ffi::c_void
+                        bytesPerRow: (width as u64) * bpp
+                        bytesPerImage: bytes_per_image
+                    ]};
+                }
+            }
             
             match &self.format{
+                TextureFormat::VecCubeBGRAu8_32{width, height, data, ..}=>{
+                    update_slices(&self.os.texture, *width, *height, 1, 6, 4, data.as_ref().unwrap().as_ptr() as *const u8);
+                }
+                TextureFormat::VecArrayBGRAu8_32{width, height, layers, data, ..}=>{
+                    update_slices(&self.os.texture, *width, *height, 1, *layers, 4, data.as_ref().unwrap().as_ptr() as *const u8);
+                }
+                TextureFormat::Vec3dBGRAu8_32{width, height, depth, data, ..}=>{
+                    update_slices(&self.os.texture, *width, *height, *depth, 1, 4, data.as_ref().unwrap().as_ptr() as *const u8);
+                }
+                TextureFormat::Vec3dRu8{width, height, depth, data, ..}=>{
+                    update_slices(&self.os.texture, *width, *height, *depth, 1, 1, data.as_ref().unwrap().as_ptr());
+                }
                 TextureFormat::VecBGRAu8_32{width, height, data, ..}=>{
                     update_data(&self.os.texture, *width, *height, 4,  data.as_ref().unwrap().as_ptr() as *const std::ffi::c_void);
                 }
@@ -34,6 +34,10 @@ impl Cx {
     }
     
     pub (crate) fn any_passes_dirty(&self) -> bool {
+        // pending readbacks are serviced during the next repaint, and completed ones delivered
+        if !self.readbacks.requests.is_empty() || !self.readbacks.completed.is_empty() {
+            return true
+        }
         for pass_id in self.passes.id_iter() {
             if self.passes[pass_id].paint_dirty {
                 return true
@@ -176,6 +180,15 @@ impl Cx {
     }
     
     pub (crate) fn call_event_handler(&mut self, event: &Event) {
+        match event {
+            Event::WindowGeomChange(e) if e.window_id.id() == 0 => {
+                self.update_window_live_conditions(&e.new_geom);
+            }
+            Event::ThemeChange(e) => {
+                self.set_dark_mode(e.dark_mode);
+            }
+            _ => ()
+        }
         self.inner_call_event_handler(event);
         self.inner_key_focus_change();
         self.handle_triggers();
@@ -206,4 +219,11 @@ impl Cx {
 
         self.call_event_handler(&Event::NextFrame(NextFrameEvent {set, time: time, frame: self.repaint_id}));
     }
+
+    pub (crate) fn call_readback_events(&mut self) {
+        let completed = std::mem::take(&mut self.readbacks.completed);
+        for readback in completed {
+            self.call_event_handler(&Event::Readback(readback));
+        }
+    }
 }
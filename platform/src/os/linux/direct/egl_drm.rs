@@ -317,6 +317,8 @@ impl Egl {
             0,
             //egl_sys::EGL_DEPTH_SIZE,
             //24,
+            egl_sys::EGL_STENCIL_SIZE,
+            8,
             egl_sys::EGL_RENDERABLE_TYPE,
             egl_sys::EGL_OPENGL_ES2_BIT,
             egl_sys::EGL_NONE
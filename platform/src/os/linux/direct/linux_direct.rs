@@ -181,6 +181,10 @@ impl Cx {
                         self.handle_media_signals();
                         self.call_event_handler(&Event::Signal);
                     }
+                    if self.handle_live_edit() {
+                        self.call_event_handler(&Event::LiveEdit);
+                        self.redraw_all();
+                    }
                     self.handle_action_receiver();
                 }
                 else {
@@ -226,15 +230,17 @@ impl Cx {
             PassClearDepth::ClearWith(depth) => depth
         };
         
+        // the depth and stencil write masks also apply to the clear
+        self.set_default_depth_and_blend_mode(pass_id);
+        
         if !self.passes[pass_id].dont_clear {
             unsafe {
                 gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
                 gl_sys::ClearDepthf(clear_depth as f32);
                 gl_sys::ClearColor(clear_color.x, clear_color.y, clear_color.z, clear_color.w);
-                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT);
+                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT | gl_sys::STENCIL_BUFFER_BIT);
             }
         }
-        Self::set_default_depth_and_blend_mode();
         
         let mut zbias = 0.0;
         let zbias_step = self.passes[pass_id].zbias_step;
@@ -246,6 +252,8 @@ impl Cx {
             zbias_step,
         );
         
+        self.opengl_read_back_pass(pass_id, 0, direct_app.drm.width as usize, direct_app.drm.height as usize);
+        
         unsafe {
             direct_app.drm.swap_buffers_and_wait(&direct_app.egl);
         }
@@ -270,6 +278,7 @@ impl Cx {
                 }
             }
         }
+        self.opengl_finish_readbacks();
     }
     
     fn handle_platform_ops(&mut self, direct_app: &mut DirectApp) -> EventFlow {
@@ -33,6 +33,7 @@ pub const EGL_GL_TEXTURE_2D_KHR: u32 = 12465;
 
 pub const EGL_PLATFORM_X11_EXT: u32 = 12757;
 pub const EGL_PLATFORM_GBM_KHR: u32 = 12759;
+pub const EGL_PLATFORM_SURFACELESS_MESA: u32 = 12765;
 
 pub const EGL_LINUX_DMA_BUF_EXT: u32 = 12912;
 pub const EGL_LINUX_DRM_FOURCC_EXT: u32 = 12913;
@@ -443,7 +444,7 @@ pub unsafe fn create_egl_context(
         EGL_DEPTH_SIZE,
         24,
         EGL_STENCIL_SIZE,
-        0,
+        8,
         EGL_NONE,
     ];
     let mut available_cfgs: Vec<EGLConfig> = vec![null_mut(); 32];
@@ -468,16 +469,19 @@ pub unsafe fn create_egl_context(
         let mut b: i32 = 0;
         let mut a: i32 = 0;
         let mut d: i32 = 0;
+        let mut s: i32 = 0;
         if (egl.eglGetConfigAttrib.unwrap())(display, *c, EGL_RED_SIZE as _, &mut r) == 1
             && (egl.eglGetConfigAttrib.unwrap())(display, *c, EGL_GREEN_SIZE as _, &mut g) == 1
             && (egl.eglGetConfigAttrib.unwrap())(display, *c, EGL_BLUE_SIZE as _, &mut b) == 1
             && (egl.eglGetConfigAttrib.unwrap())(display, *c, EGL_ALPHA_SIZE as _, &mut a) == 1
             && (egl.eglGetConfigAttrib.unwrap())(display, *c, EGL_DEPTH_SIZE as _, &mut d) == 1
+            && (egl.eglGetConfigAttrib.unwrap())(display, *c, EGL_STENCIL_SIZE as _, &mut s) == 1
             && r == 8
             && g == 8
             && b == 8
             && (alpha_size == 0 || a == alpha_size as _)
             && d == 16
+            && s == 8
         {
             exact_cfg_found = true;
             config = *c;
@@ -525,7 +529,7 @@ pub unsafe  fn create_egl_context(
         EGL_RENDERABLE_TYPE,
         EGL_OPENGL_ES2_BIT,
         EGL_DEPTH_SIZE, 0,
-        EGL_STENCIL_SIZE, 0,
+        EGL_STENCIL_SIZE, 8,
         EGL_NONE
     ];
     let available_cfgs: Vec<EGLConfig> = vec![null_mut(); 1];
@@ -34,6 +34,7 @@ pub const ONE: types::GLenum = 1;
 pub const ONE_MINUS_SRC_ALPHA: types::GLenum = 0x0303;
 pub const BLEND: types::GLenum = 0x0BE2;
 pub const FRAMEBUFFER: types::GLenum = 0x8D40;
+pub const FRAMEBUFFER_COMPLETE: types::GLenum = 0x8CD5;
 pub const COLOR_BUFFER_BIT: types::GLenum = 0x00004000;
 pub const DEPTH_BUFFER_BIT: types::GLenum = 0x00000100;
 pub const RENDERBUFFER: types::GLenum = 0x8D41;
@@ -71,10 +72,48 @@ pub const CLAMP_TO_EDGE: types::GLenum = 0x812F;
 pub const PROGRAM_BINARY_LENGTH: types::GLenum = 0x8741;
 pub const NO_ERROR: types::GLenum = 0x0;
 pub const UNPACK_ALIGNMENT: types::GLenum = 0x0CF5;
+pub const PACK_ALIGNMENT: types::GLenum = 0x0D05;
 pub const UNPACK_ROW_LENGTH: types::GLenum = 0x0CF2;
 pub const UNPACK_SKIP_PIXELS: types::GLenum = 0x0CF4;
 pub const UNPACK_SKIP_ROWS: types::GLenum = 0x0CF3;
 
+pub const ZERO: types::GLenum = 0;
+pub const SRC_COLOR: types::GLenum = 0x0300;
+pub const ONE_MINUS_SRC_COLOR: types::GLenum = 0x0301;
+pub const SRC_ALPHA: types::GLenum = 0x0302;
+pub const DST_ALPHA: types::GLenum = 0x0304;
+pub const ONE_MINUS_DST_ALPHA: types::GLenum = 0x0305;
+pub const DST_COLOR: types::GLenum = 0x0306;
+pub const ONE_MINUS_DST_COLOR: types::GLenum = 0x0307;
+pub const FUNC_SUBTRACT: types::GLenum = 0x800A;
+pub const FUNC_REVERSE_SUBTRACT: types::GLenum = 0x800B;
+pub const MIN: types::GLenum = 0x8007;
+pub const MAX: types::GLenum = 0x8008;
+pub const NEVER: types::GLenum = 0x0200;
+pub const LESS: types::GLenum = 0x0201;
+pub const EQUAL: types::GLenum = 0x0202;
+pub const GREATER: types::GLenum = 0x0204;
+pub const NOTEQUAL: types::GLenum = 0x0205;
+pub const GEQUAL: types::GLenum = 0x0206;
+pub const ALWAYS: types::GLenum = 0x0207;
+pub const KEEP: types::GLenum = 0x1E00;
+pub const REPLACE: types::GLenum = 0x1E01;
+pub const INCR: types::GLenum = 0x1E02;
+pub const DECR: types::GLenum = 0x1E03;
+pub const INVERT: types::GLenum = 0x150A;
+pub const INCR_WRAP: types::GLenum = 0x8507;
+pub const DECR_WRAP: types::GLenum = 0x8508;
+pub const STENCIL_TEST: types::GLenum = 0x0B90;
+pub const STENCIL_BUFFER_BIT: types::GLenum = 0x00000400;
+pub const DEPTH32F_STENCIL8: types::GLenum = 0x8CAD;
+pub const DEPTH_STENCIL_ATTACHMENT: types::GLenum = 0x821A;
+pub const TEXTURE_CUBE_MAP: types::GLenum = 0x8513;
+pub const TEXTURE_CUBE_MAP_POSITIVE_X: types::GLenum = 0x8515;
+pub const TEXTURE_2D_ARRAY: types::GLenum = 0x8C1A;
+pub const TEXTURE_3D: types::GLenum = 0x806F;
+pub const TEXTURE_WRAP_R: types::GLenum = 0x8072;
+pub const RGBA8: types::GLenum = 0x8058;
+
 pub const TEXTURE_EXTERNAL_OES: types::GLenum = 0x8D65;
 pub const EXTENSIONS: types::GLenum = 0x1F03;
 pub const VENDOR: types::GLenum = 0x1F00;
@@ -92,6 +131,11 @@ pub const RENDERER: types::GLenum = 0x1F01;
 #[inline] pub unsafe fn DrawElementsInstanced(mode: types::GLenum, count: types::GLsizei, type_: types::GLenum, indices: *const raw::c_void, instancecount: types::GLsizei) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLsizei, types::GLenum, *const raw::c_void, types::GLsizei) -> ()>(storage::DrawElementsInstanced.f)(mode, count, type_, indices, instancecount) }
 #[inline] pub unsafe fn Enable(cap: types::GLenum) -> () { mem::transmute::<_, extern "system" fn(types::GLenum) -> ()>(storage::Enable.f)(cap) }
 #[inline] pub unsafe fn DepthFunc(func: types::GLenum) -> () { mem::transmute::<_, extern "system" fn(types::GLenum) -> ()>(storage::DepthFunc.f)(func) }
+#[inline] pub unsafe fn DepthMask(flag: types::GLboolean) -> () { mem::transmute::<_, extern "system" fn(types::GLboolean) -> ()>(storage::DepthMask.f)(flag) }
+#[inline] pub unsafe fn StencilFunc(func: types::GLenum, ref_: types::GLint, mask: types::GLuint) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLint, types::GLuint) -> ()>(storage::StencilFunc.f)(func, ref_, mask) }
+#[inline] pub unsafe fn StencilOp(fail: types::GLenum, zfail: types::GLenum, zpass: types::GLenum) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum, types::GLenum) -> ()>(storage::StencilOp.f)(fail, zfail, zpass) }
+#[inline] pub unsafe fn StencilMask(mask: types::GLuint) -> () { mem::transmute::<_, extern "system" fn(types::GLuint) -> ()>(storage::StencilMask.f)(mask) }
+#[inline] pub unsafe fn ClearStencil(s: types::GLint) -> () { mem::transmute::<_, extern "system" fn(types::GLint) -> ()>(storage::ClearStencil.f)(s) }
 #[inline] pub unsafe fn BlendEquationSeparate(modeRGB: types::GLenum, modeAlpha: types::GLenum) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum) -> ()>(storage::BlendEquationSeparate.f)(modeRGB, modeAlpha) }
 #[inline] pub unsafe fn BlendFuncSeparate(sfactorRGB: types::GLenum, dfactorRGB: types::GLenum, sfactorAlpha: types::GLenum, dfactorAlpha: types::GLenum) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum, types::GLenum, types::GLenum) -> ()>(storage::BlendFuncSeparate.f)(sfactorRGB, dfactorRGB, sfactorAlpha, dfactorAlpha) }
 #[inline] pub unsafe fn Viewport(x: types::GLint, y: types::GLint, width: types::GLsizei, height: types::GLsizei) -> () { mem::transmute::<_, extern "system" fn(types::GLint, types::GLint, types::GLsizei, types::GLsizei) -> ()>(storage::Viewport.f)(x, y, width, height) }
@@ -106,6 +150,7 @@ pub const RENDERER: types::GLenum = 0x1F01;
 #[inline] pub unsafe fn Disable(cap: types::GLenum) -> () { mem::transmute::<_, extern "system" fn(types::GLenum) -> ()>(storage::Disable.f)(cap) }
 #[inline] pub unsafe fn FramebufferRenderbuffer(target: types::GLenum, attachment: types::GLenum, renderbuffertarget: types::GLenum, renderbuffer: types::GLuint) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum, types::GLenum, types::GLuint) -> ()>(storage::FramebufferRenderbuffer.f)(target, attachment, renderbuffertarget, renderbuffer) }
 #[inline] pub unsafe fn FramebufferTexture2D(target: types::GLenum, attachment: types::GLenum, textarget: types::GLenum, texture: types::GLuint, level: types::GLint) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum, types::GLenum, types::GLuint, types::GLint) -> ()>(storage::FramebufferTexture2D.f)(target, attachment, textarget, texture, level) }
+#[inline] pub unsafe fn FramebufferTextureLayer(target: types::GLenum, attachment: types::GLenum, texture: types::GLuint, level: types::GLint, layer: types::GLint) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum, types::GLuint, types::GLint, types::GLint) -> ()>(storage::FramebufferTextureLayer.f)(target, attachment, texture, level, layer) }
 #[inline] pub unsafe fn GetShaderiv(shader: types::GLuint, pname: types::GLenum, params: *mut types::GLint) -> () { mem::transmute::<_, extern "system" fn(types::GLuint, types::GLenum, *mut types::GLint) -> ()>(storage::GetShaderiv.f)(shader, pname, params) }
 #[inline] pub unsafe fn GetProgramiv(program: types::GLuint, pname: types::GLenum, params: *mut types::GLint) -> () { mem::transmute::<_, extern "system" fn(types::GLuint, types::GLenum, *mut types::GLint) -> ()>(storage::GetProgramiv.f)(program, pname, params) }
 #[inline] pub unsafe fn GetShaderInfoLog(shader: types::GLuint, bufSize: types::GLsizei, length: *mut types::GLsizei, infoLog: *mut types::GLchar) -> () { mem::transmute::<_, extern "system" fn(types::GLuint, types::GLsizei, *mut types::GLsizei, *mut types::GLchar) -> ()>(storage::GetShaderInfoLog.f)(shader, bufSize, length, infoLog) }
@@ -124,6 +169,9 @@ pub const RENDERER: types::GLenum = 0x1F01;
 #[inline] pub unsafe fn TexParameteri(target: types::GLenum, pname: types::GLenum, param: types::GLint) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLenum, types::GLint) -> ()>(storage::TexParameteri.f)(target, pname, param) }
 #[inline] pub unsafe fn TexImage2D(target: types::GLenum, level: types::GLint, internalformat: types::GLint, width: types::GLsizei, height: types::GLsizei, border: types::GLint, format: types::GLenum, type_: types::GLenum, pixels: *const raw::c_void) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLint, types::GLint, types::GLsizei, types::GLsizei, types::GLint, types::GLenum, types::GLenum, *const raw::c_void) -> ()>(storage::TexImage2D.f)(target, level, internalformat, width, height, border, format, type_, pixels) }
 #[inline] pub unsafe fn TexSubImage2D(target: types::GLenum, level: types::GLint, xoffset: types::GLint, yoffset: types::GLint, width: types::GLsizei, height: types::GLsizei, format: types::GLenum, type_: types::GLenum, pixels: *const raw::c_void) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLint, types::GLint, types::GLint, types::GLsizei, types::GLsizei, types::GLenum, types::GLenum, *const raw::c_void) -> ()>(storage::TexSubImage2D.f)(target, level, xoffset, yoffset, width, height, format, type_, pixels) }
+#[inline] pub unsafe fn TexImage3D(target: types::GLenum, level: types::GLint, internalformat: types::GLint, width: types::GLsizei, height: types::GLsizei, depth: types::GLsizei, border: types::GLint, format: types::GLenum, type_: types::GLenum, pixels: *const raw::c_void) -> () { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLint, types::GLint, types::GLsizei, types::GLsizei, types::GLsizei, types::GLint, types::GLenum, types::GLenum, *const raw::c_void) -> ()>(storage::TexImage3D.f)(target, level, internalformat, width, height, depth, border, format, type_, pixels) }
+#[inline] pub unsafe fn ReadPixels(x: types::GLint, y: types::GLint, width: types::GLsizei, height: types::GLsizei, format: types::GLenum, type_: types::GLenum, pixels: *mut raw::c_void) -> () { mem::transmute::<_, extern "system" fn(types::GLint, types::GLint, types::GLsizei, types::GLsizei, types::GLenum, types::GLenum, *mut raw::c_void) -> ()>(storage::ReadPixels.f)(x, y, width, height, format, type_, pixels) }
+#[inline] pub unsafe fn CheckFramebufferStatus(target: types::GLenum) -> types::GLenum { mem::transmute::<_, extern "system" fn(types::GLenum) -> types::GLenum>(storage::CheckFramebufferStatus.f)(target) }
 #[inline] pub unsafe fn GetTexLevelParameteriv(target: types::GLenum, level: types::GLint, pname: types::GLenum, params: *mut types::GLint) { mem::transmute::<_, extern "system" fn(types::GLenum, types::GLint, types::GLenum, *mut types::GLint)>(storage::GetTexLevelParameteriv.f)(target, level, pname, params) }
 #[inline] pub unsafe fn DeleteTextures(n: types::GLsizei, textures: *const types::GLuint) -> () { mem::transmute::<_, extern "system" fn(types::GLsizei, *const types::GLuint) -> ()>(storage::DeleteTextures.f)(n, textures) }
 #[inline] pub unsafe fn GenBuffers(n: types::GLsizei, buffers: *mut types::GLuint) -> () { mem::transmute::<_, extern "system" fn(types::GLsizei, *mut types::GLuint) -> ()>(storage::GenBuffers.f)(n, buffers) }
@@ -155,6 +203,11 @@ mod storage {
     pub static mut DrawElementsInstanced: FnPtr = FnPtr::default();
     pub static mut Enable: FnPtr = FnPtr::default();
     pub static mut DepthFunc: FnPtr = FnPtr::default();
+    pub static mut DepthMask: FnPtr = FnPtr::default();
+    pub static mut StencilFunc: FnPtr = FnPtr::default();
+    pub static mut StencilOp: FnPtr = FnPtr::default();
+    pub static mut StencilMask: FnPtr = FnPtr::default();
+    pub static mut ClearStencil: FnPtr = FnPtr::default();
     pub static mut BlendEquationSeparate: FnPtr = FnPtr::default();
     pub static mut BlendFuncSeparate: FnPtr = FnPtr::default();
     pub static mut Viewport: FnPtr = FnPtr::default();
@@ -170,6 +223,7 @@ mod storage {
     pub static mut Disable: FnPtr = FnPtr::default();
     pub static mut FramebufferRenderbuffer: FnPtr = FnPtr::default();
     pub static mut FramebufferTexture2D: FnPtr = FnPtr::default();
+    pub static mut FramebufferTextureLayer: FnPtr = FnPtr::default();
     pub static mut GetShaderiv: FnPtr = FnPtr::default();
     pub static mut GetProgramiv: FnPtr = FnPtr::default();
     pub static mut GetShaderInfoLog: FnPtr = FnPtr::default();
@@ -188,6 +242,9 @@ mod storage {
     pub static mut TexParameteri: FnPtr = FnPtr::default();
     pub static mut TexImage2D: FnPtr = FnPtr::default();
     pub static mut TexSubImage2D: FnPtr = FnPtr::default();
+    pub static mut TexImage3D: FnPtr = FnPtr::default();
+    pub static mut ReadPixels: FnPtr = FnPtr::default();
+    pub static mut CheckFramebufferStatus: FnPtr = FnPtr::default();
     pub static mut GetTexLevelParameteriv: FnPtr = FnPtr::default();
     pub static mut DeleteTextures: FnPtr = FnPtr::default();
     pub static mut GenBuffers: FnPtr = FnPtr::default();
@@ -219,6 +276,11 @@ pub unsafe fn load_with<F>(mut loadfn: F) where F: FnMut(&'static str) -> *const
     storage::DrawElementsInstanced = FnPtr::new(metaloadfn(&mut loadfn, "glDrawElementsInstanced", &["glDrawElementsInstancedANGLE", "glDrawElementsInstancedARB", "glDrawElementsInstancedEXT", "glDrawElementsInstancedNV"]));
     storage::Enable = FnPtr::new(metaloadfn(&mut loadfn, "glEnable", &[]));
     storage::DepthFunc = FnPtr::new(metaloadfn(&mut loadfn, "glDepthFunc", &[]));
+    storage::DepthMask = FnPtr::new(metaloadfn(&mut loadfn, "glDepthMask", &[]));
+    storage::StencilFunc = FnPtr::new(metaloadfn(&mut loadfn, "glStencilFunc", &[]));
+    storage::StencilOp = FnPtr::new(metaloadfn(&mut loadfn, "glStencilOp", &[]));
+    storage::StencilMask = FnPtr::new(metaloadfn(&mut loadfn, "glStencilMask", &[]));
+    storage::ClearStencil = FnPtr::new(metaloadfn(&mut loadfn, "glClearStencil", &[]));
     storage::BlendEquationSeparate = FnPtr::new(metaloadfn(&mut loadfn, "glBlendEquationSeparate", &["glBlendEquationSeparateEXT"]));
     storage::BlendFuncSeparate = FnPtr::new(metaloadfn(&mut loadfn, "glBlendFuncSeparate", &["glBlendFuncSeparateEXT", "glBlendFuncSeparateINGR"]));
     storage::Viewport = FnPtr::new(metaloadfn(&mut loadfn, "glViewport", &[]));
@@ -233,6 +295,7 @@ pub unsafe fn load_with<F>(mut loadfn: F) where F: FnMut(&'static str) -> *const
     storage::Disable = FnPtr::new(metaloadfn(&mut loadfn, "glDisable", &[]));
     storage::FramebufferRenderbuffer = FnPtr::new(metaloadfn(&mut loadfn, "glFramebufferRenderbuffer", &["glFramebufferRenderbufferEXT"]));
     storage::FramebufferTexture2D = FnPtr::new(metaloadfn(&mut loadfn, "glFramebufferTexture2D", &["glFramebufferTexture2DEXT"]));
+    storage::FramebufferTextureLayer = FnPtr::new(metaloadfn(&mut loadfn, "glFramebufferTextureLayer", &["glFramebufferTextureLayerEXT"]));
     storage::GetShaderiv = FnPtr::new(metaloadfn(&mut loadfn, "glGetShaderiv", &[]));
     storage::GetProgramiv = FnPtr::new(metaloadfn(&mut loadfn, "glGetProgramiv", &[]));
     storage::GetShaderInfoLog = FnPtr::new(metaloadfn(&mut loadfn, "glGetShaderInfoLog", &[]));
@@ -251,6 +314,9 @@ pub unsafe fn load_with<F>(mut loadfn: F) where F: FnMut(&'static str) -> *const
     storage::TexParameteri = FnPtr::new(metaloadfn(&mut loadfn, "glTexParameteri", &[]));
     storage::TexImage2D = FnPtr::new(metaloadfn(&mut loadfn, "glTexImage2D", &[]));
     storage::TexSubImage2D = FnPtr::new(metaloadfn(&mut loadfn, "glTexSubImage2D", &[]));
+    storage::TexImage3D = FnPtr::new(metaloadfn(&mut loadfn, "glTexImage3D", &[]));
+    storage::ReadPixels = FnPtr::new(metaloadfn(&mut loadfn, "glReadPixels", &[]));
+    storage::CheckFramebufferStatus = FnPtr::new(metaloadfn(&mut loadfn, "glCheckFramebufferStatus", &[]));
     storage::GetTexLevelParameteriv = FnPtr::new(metaloadfn(&mut loadfn, "glGetTexLevelParameteriv", &[]));
     storage::DeleteTextures = FnPtr::new(metaloadfn(&mut loadfn, "glDeleteTextures", &[]));
     storage::GenBuffers = FnPtr::new(metaloadfn(&mut loadfn, "glGenBuffers", &["glGenBuffersARB"]));
@@ -453,20 +453,24 @@ impl Cx {
             PassClearDepth::ClearWith(depth) => depth,
         };
 
+        // the depth and stencil write masks also apply to the clear
+        self.set_default_depth_and_blend_mode(pass_id);
+        
         if !self.passes[pass_id].dont_clear {
             unsafe {
                 gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
                 gl_sys::ClearDepthf(clear_depth as f32);
                 gl_sys::ClearColor(clear_color.x, clear_color.y, clear_color.z, clear_color.w);
-                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT);
+                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT | gl_sys::STENCIL_BUFFER_BIT);
             }
         }
-        Self::set_default_depth_and_blend_mode();
 
         let mut zbias = 0.0;
         let zbias_step = self.passes[pass_id].zbias_step;
 
         self.render_view(pass_id, draw_list_id, &mut zbias, zbias_step);
+        
+        self.opengl_read_back_pass(pass_id, 0, self.os.display_size.x as usize, self.os.display_size.y as usize);
 
         unsafe { self.os.display.as_mut().unwrap().swap_buffers() };
 
@@ -493,6 +497,7 @@ impl Cx {
                 }
             }
         }
+        self.opengl_finish_readbacks();
     }
 
     fn handle_platform_ops(&mut self) -> EventFlow {
@@ -9,14 +9,14 @@ use {
     self::super::gl_sys,
     crate::{
         makepad_live_id::*,
-        makepad_shader_compiler::generate_glsl,
+        makepad_shader_compiler::{generate_glsl, DrawShaderFlags, BlendFactor, BlendOp, CompareFunc, StencilOp},
         cx::{Cx, OsType, OsType::Android},
-        texture::{Texture, TextureFormat, TexturePixel, TextureUpdated, CxTexture},
+        texture::{Texture, TextureFormat, TextureDimension, TexturePixel, TextureUpdated, CxTexture},
         makepad_math::{Mat4, DVec2, Vec4},
         pass::{PassClearColor, PassClearDepth, PassId},
         draw_list::DrawListId,
         draw_shader::{CxDrawShaderMapping, DrawShaderTextureInput},
-        event::{Event, TextureHandleReadyEvent}
+        event::{Event, TextureHandleReadyEvent, ReadbackImage, CxReadbackSource}
     },
 };
 
@@ -162,6 +162,12 @@ impl Cx {
                     }
                 }
                 
+                let render_state = sh.mapping.flags;
+                if !self.passes[pass_id].os.gl_render_state.render_state_eq(&render_state) {
+                    Self::set_render_state(&render_state);
+                    self.passes[pass_id].os.gl_render_state = render_state;
+                }
+                
                 unsafe {
                     gl_sys::UseProgram(shgl.program);
                     
@@ -215,19 +221,8 @@ impl Cx {
                         let cxtexture = &mut self.textures[texture_id];
                         // get the loc
                         gl_sys::ActiveTexture(gl_sys::TEXTURE0 + i as u32);
-                        if let Some(texture) = cxtexture.os.gl_texture {
-                            // Video playback with SurfaceTexture requires TEXTURE_EXTERNAL_OES, for any other format we assume regular 2D textures
-                            match cxtexture.format {
-                                TextureFormat::VideoRGB => gl_sys::BindTexture(gl_sys::TEXTURE_EXTERNAL_OES, texture),
-                                _ => gl_sys::BindTexture(gl_sys::TEXTURE_2D, texture)     
-                            }
-                        }
-                        else {
-                            match cxtexture.format {
-                                TextureFormat::VideoRGB => gl_sys::BindTexture(gl_sys::TEXTURE_EXTERNAL_OES, 0),
-                                _ => gl_sys::BindTexture(gl_sys::TEXTURE_2D, 0)     
-                            }
-                        }
+                        // Video playback with SurfaceTexture requires TEXTURE_EXTERNAL_OES
+                        gl_sys::BindTexture(cxtexture.gl_target(), cxtexture.os.gl_texture.unwrap_or(0));
                         gl_sys::Uniform1i(shgl.textures[i].loc, i as i32);
                     }
                     
@@ -249,13 +244,86 @@ impl Cx {
         }
     }
     
-    pub fn set_default_depth_and_blend_mode() {
+    pub fn set_default_depth_and_blend_mode(&mut self, pass_id: PassId) {
         unsafe {
             gl_sys::Enable(gl_sys::DEPTH_TEST);
-            gl_sys::DepthFunc(gl_sys::LEQUAL);
-            gl_sys::BlendEquationSeparate(gl_sys::FUNC_ADD, gl_sys::FUNC_ADD);
-            gl_sys::BlendFuncSeparate(gl_sys::ONE, gl_sys::ONE_MINUS_SRC_ALPHA, gl_sys::ONE, gl_sys::ONE_MINUS_SRC_ALPHA);
-            gl_sys::Enable(gl_sys::BLEND);
+        }
+        let render_state = DrawShaderFlags::default();
+        Self::set_render_state(&render_state);
+        self.passes[pass_id].os.gl_render_state = render_state;
+    }
+    
+    // applies the blend, depth and stencil state a draw shader declared
+    fn set_render_state(flags: &DrawShaderFlags) {
+        fn blend_factor(factor: BlendFactor) -> u32 {
+            match factor {
+                BlendFactor::Zero => gl_sys::ZERO,
+                BlendFactor::One => gl_sys::ONE,
+                BlendFactor::SrcColor => gl_sys::SRC_COLOR,
+                BlendFactor::OneMinusSrcColor => gl_sys::ONE_MINUS_SRC_COLOR,
+                BlendFactor::SrcAlpha => gl_sys::SRC_ALPHA,
+                BlendFactor::OneMinusSrcAlpha => gl_sys::ONE_MINUS_SRC_ALPHA,
+                BlendFactor::DstColor => gl_sys::DST_COLOR,
+                BlendFactor::OneMinusDstColor => gl_sys::ONE_MINUS_DST_COLOR,
+                BlendFactor::DstAlpha => gl_sys::DST_ALPHA,
+                BlendFactor::OneMinusDstAlpha => gl_sys::ONE_MINUS_DST_ALPHA,
+            }
+        }
+        fn blend_op(op: BlendOp) -> u32 {
+            match op {
+                BlendOp::Add => gl_sys::FUNC_ADD,
+                BlendOp::Subtract => gl_sys::FUNC_SUBTRACT,
+                BlendOp::ReverseSubtract => gl_sys::FUNC_REVERSE_SUBTRACT,
+                BlendOp::Min => gl_sys::MIN,
+                BlendOp::Max => gl_sys::MAX,
+            }
+        }
+        fn compare_func(func: CompareFunc) -> u32 {
+            match func {
+                CompareFunc::Never => gl_sys::NEVER,
+                CompareFunc::Less => gl_sys::LESS,
+                CompareFunc::Equal => gl_sys::EQUAL,
+                CompareFunc::LessEqual => gl_sys::LEQUAL,
+                CompareFunc::Greater => gl_sys::GREATER,
+                CompareFunc::NotEqual => gl_sys::NOTEQUAL,
+                CompareFunc::GreaterEqual => gl_sys::GEQUAL,
+                CompareFunc::Always => gl_sys::ALWAYS,
+            }
+        }
+        fn stencil_op(op: StencilOp) -> u32 {
+            match op {
+                StencilOp::Keep => gl_sys::KEEP,
+                StencilOp::Zero => gl_sys::ZERO,
+                StencilOp::Replace => gl_sys::REPLACE,
+                StencilOp::IncrementClamp => gl_sys::INCR,
+                StencilOp::DecrementClamp => gl_sys::DECR,
+                StencilOp::Invert => gl_sys::INVERT,
+                StencilOp::IncrementWrap => gl_sys::INCR_WRAP,
+                StencilOp::DecrementWrap => gl_sys::DECR_WRAP,
+            }
+        }
+        unsafe {
+            if let Some((src, dst, op)) = flags.blend.factors() {
+                gl_sys::BlendEquationSeparate(blend_op(op), blend_op(op));
+                gl_sys::BlendFuncSeparate(blend_factor(src), blend_factor(dst), blend_factor(src), blend_factor(dst));
+                gl_sys::Enable(gl_sys::BLEND);
+            }
+            else {
+                gl_sys::Disable(gl_sys::BLEND);
+            }
+            gl_sys::DepthFunc(compare_func(flags.depth.test));
+            gl_sys::DepthMask(flags.depth.write as u8);
+            // the write masks also apply to clears, so they are always set
+            let stencil = &flags.stencil;
+            gl_sys::StencilFunc(compare_func(stencil.test), stencil.reference as i32, stencil.read_mask as u32);
+            gl_sys::StencilOp(stencil_op(stencil.fail), stencil_op(stencil.depth_fail), stencil_op(stencil.pass));
+            gl_sys::StencilMask(stencil.write_mask as u32);
+            if stencil.is_enabled() {
+                gl_sys::Enable(gl_sys::STENCIL_TEST);
+            }
+            else {
+                gl_sys::Disable(gl_sys::STENCIL_TEST);
+            }
         }
     }
     
@@ -362,7 +430,7 @@ impl Cx {
                     cxtexture.update_depth_stencil(size.x as usize, size.y as usize);
                     if cxtexture.take_initial(){
                         clear_depth = _clear_depth;
-                        clear_flags |= gl_sys::DEPTH_BUFFER_BIT;
+                        clear_flags |= gl_sys::DEPTH_BUFFER_BIT | gl_sys::STENCIL_BUFFER_BIT;
                     }
                 },
                 PassClearDepth::ClearWith(_clear_depth) => {
@@ -370,7 +438,12 @@ impl Cx {
                     let size = dpi_factor * pass_size;
                     cxtexture.update_depth_stencil(size.x as usize, size.y as usize);
                     clear_depth = _clear_depth;
-                    clear_flags |= gl_sys::DEPTH_BUFFER_BIT;
+                    clear_flags |= gl_sys::DEPTH_BUFFER_BIT | gl_sys::STENCIL_BUFFER_BIT;
+                }
+            }
+            if let Some(gl_renderbuffer) = self.textures[depth_texture.texture_id()].os.gl_renderbuffer {
+                unsafe {
+                    gl_sys::FramebufferRenderbuffer(gl_sys::FRAMEBUFFER, gl_sys::DEPTH_STENCIL_ATTACHMENT, gl_sys::RENDERBUFFER, gl_renderbuffer);
                 }
             }
         }
@@ -400,11 +473,10 @@ impl Cx {
         // HACK(eddyb) drain error queue, so that we can check erors below.
         while unsafe { gl_sys::GetError() } != 0 {}
 
+        let (x, mut y) = (0, 0);
+        let width = (pass_size.x * dpi_factor) as u32;
+        let height = (pass_size.y * dpi_factor) as u32;
         unsafe {
-            let (x, mut y) = (0, 0);
-            let width = (pass_size.x * dpi_factor) as u32;
-            let height = (pass_size.y * dpi_factor) as u32;
-
             // HACK(eddyb) to try and match DirectX and Metal conventions, we
             // need the viewport to be placed on the other end of the Y axis.
             if let [color_texture] = color_textures {
@@ -422,17 +494,19 @@ impl Cx {
             assert_eq!(gl_sys::GetError(), 0, "glViewport({x}, {y}, {width}, {height}) failed");
         }
 
+        // the depth and stencil write masks also apply to the clear
+        self.set_default_depth_and_blend_mode(pass_id);
+        
         if clear_flags != 0 {
             unsafe {
                 if clear_flags & gl_sys::DEPTH_BUFFER_BIT != 0 {
                     gl_sys::ClearDepthf(clear_depth);
+                    gl_sys::ClearStencil(0);
                 }
                 gl_sys::ClearColor(clear_color.x, clear_color.y, clear_color.z, clear_color.w);
                 gl_sys::Clear(clear_flags);
             }
         }
-        Self::set_default_depth_and_blend_mode();
-        
         let mut zbias = 0.0;
         let zbias_step = self.passes[pass_id].zbias_step;
         
@@ -443,12 +517,51 @@ impl Cx {
             zbias_step,
         );
         
+        self.opengl_read_back_pass(pass_id, y as i32, width as usize, height as usize);
+        
         unsafe {
             gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
             //gl_sys::Finish();
         }
     }
     
+    /// Reads the currently bound framebuffer for the readbacks requested on this pass,
+    /// call this after drawing the pass but before swapping buffers.
+    pub (crate) fn opengl_read_back_pass(&mut self, pass_id: PassId, y: i32, width: usize, height: usize) {
+        let requests = self.readbacks.take_pass_requests(pass_id);
+        if requests.is_empty() {
+            return
+        }
+        let result = opengl_read_pixels(y, width, height, true);
+        for request in requests {
+            self.readbacks.completed.push(request.complete(result.clone()));
+        }
+    }
+    
+    /// Reads back the requested textures and sends all readback results, call this at the end of a repaint.
+    pub (crate) fn opengl_finish_readbacks(&mut self) {
+        for request in self.readbacks.take_texture_requests() {
+            let result = match &request.source {
+                CxReadbackSource::Texture(texture) => self.textures[texture.texture_id()].read_back(),
+                CxReadbackSource::Pass(_) => unreachable!(),
+            };
+            self.readbacks.completed.push(request.complete(result));
+        }
+        // a pass that got repainted without serving its readbacks never drew anything
+        let mut i = 0;
+        while i < self.readbacks.requests.len() {
+            match self.readbacks.requests[i].source {
+                CxReadbackSource::Pass(pass_id) if !self.passes[pass_id].paint_dirty => {
+                    let request = self.readbacks.requests.remove(i);
+                    let name = &self.passes[pass_id].debug_name;
+                    self.readbacks.completed.push(request.complete(Err(format!("pass {} has nothing to read back", name))));
+                }
+                _ => i += 1
+            }
+        }
+        self.call_readback_events();
+    }
+    
     pub fn opengl_compile_shaders(&mut self) {
         //let p = profile_start();
         for draw_shader_ptr in &self.draw_shaders.compile_set {
@@ -806,31 +919,61 @@ impl CxOsDrawShader {
             maybe_ext_tex_extension_sampler = "vec4 sample2dOES(samplerExternalOES sampler, vec2 pos){{ return texture2D(sampler, vec2(pos.x, pos.y));}}".to_string();
         }
         
+        // uint, uvec, array and 3D samplers only exist in GLSL ES 3.00. Rather than porting every shader
+        // we map the few GLSL ES 1.00 names the generator emits onto their 3.00 counterparts.
+        let is_es3 = generate_glsl::needs_glsl_es3(vertex) || generate_glsl::needs_glsl_es3(pixel);
+        if is_es3 && !maybe_ext_tex_extension_import.is_empty() {
+            maybe_ext_tex_extension_import = "#extension GL_OES_EGL_image_external_essl3 : require\n".to_string();
+        }
+        let (vertex_version, pixel_version, pixel_output, helpers) = if is_es3 {(
+            "#version 300 es
+            #define attribute in
+            #define varying out",
+            "#version 300 es
+            #define varying in
+            #define gl_FragColor makepad_frag_color",
+            "out vec4 makepad_frag_color;",
+            "#define texture2D texture
+            #define textureCube texture
+            precision highp sampler2DArray;
+            precision highp sampler3D;
+            vec4 sample2d_array(sampler2DArray sampler, vec2 pos, float layer){return texture(sampler, vec3(pos.x, pos.y, layer));}
+            vec4 sample3d(sampler3D sampler, vec3 pos){return texture(sampler, pos);}"
+        )}
+        else {(
+            "#version 100",
+            "#version 100
+            #extension GL_OES_standard_derivatives : enable",
+            "",
+            // transpose is only built in from GLSL ES 3.00 on
+            "mat4 transpose(mat4 m){return mat4(m[0][0],m[1][0],m[2][0],m[3][0],m[0][1],m[1][1],m[2][1],m[3][1],m[0][2],m[1][2],m[2][2],m[3][3], m[3][0], m[3][1], m[3][2], m[3][3]);}
+            mat3 transpose(mat3 m){return mat3(m[0][0],m[1][0],m[2][0],m[0][1],m[1][1],m[2][1],m[0][2],m[1][2],m[2][2]);}
+            mat2 transpose(mat2 m){return mat2(m[0][0],m[1][0],m[0][1],m[1][1]);}"
+        )};
+        
         let vertex = format!("
-            #version 100
+            {}
             {}
             precision highp float;
             precision highp int;
+            {}
             vec4 sample2d(sampler2D sampler, vec2 pos){{return texture2D(sampler, vec2(pos.x, pos.y));}} 
             vec4 sample2d_rt(sampler2D sampler, vec2 pos){{return texture2D(sampler, vec2(pos.x, 1.0-pos.y));}}
-            mat4 transpose(mat4 m){{return mat4(m[0][0],m[1][0],m[2][0],m[3][0],m[0][1],m[1][1],m[2][1],m[3][1],m[0][2],m[1][2],m[2][2],m[3][3], m[3][0], m[3][1], m[3][2], m[3][3]);}}
-            mat3 transpose(mat3 m){{return mat3(m[0][0],m[1][0],m[2][0],m[0][1],m[1][1],m[2][1],m[0][2],m[1][2],m[2][2]);}}
-            mat2 transpose(mat2 m){{return mat2(m[0][0],m[1][0],m[0][1],m[1][1]);}}
-            {}\0", maybe_ext_tex_extension_import, vertex);
+            vec4 sample_cube(samplerCube sampler, vec3 dir){{return textureCube(sampler, dir);}}
+            {}\0", vertex_version, maybe_ext_tex_extension_import, helpers, vertex);
 
         let pixel = format!("
-            #version 100
-            #extension GL_OES_standard_derivatives : enable
+            {}
             {}
             precision highp float;
             precision highp int;
+            {}
+            {}
             vec4 sample2d(sampler2D sampler, vec2 pos){{return texture2D(sampler, vec2(pos.x, pos.y));}}
             vec4 sample2d_rt(sampler2D sampler, vec2 pos){{return texture2D(sampler, vec2(pos.x, 1.0-pos.y));}}
+            vec4 sample_cube(samplerCube sampler, vec3 dir){{return textureCube(sampler, dir);}}
             {}
-            mat4 transpose(mat4 m){{return mat4(m[0][0],m[1][0],m[2][0],m[3][0],m[0][1],m[1][1],m[2][1],m[3][1],m[0][2],m[1][2],m[2][2],m[3][3], m[3][0], m[3][1], m[3][2], m[3][3]);}}
-            mat3 transpose(mat3 m){{return mat3(m[0][0],m[1][0],m[2][0],m[0][1],m[1][1],m[2][1],m[0][2],m[1][2],m[2][2]);}}
-            mat2 transpose(mat2 m){{return mat2(m[0][0],m[1][0],m[0][1],m[1][1]);}}
-            {}\0", maybe_ext_tex_extension_import, maybe_ext_tex_extension_sampler, pixel);
+            {}\0", pixel_version, maybe_ext_tex_extension_import, pixel_output, helpers, maybe_ext_tex_extension_sampler, pixel);
         
             // lets fetch the uniform positions for our uniforms
         CxOsDrawShader {
@@ -848,6 +991,25 @@ impl CxOsDrawShader {
 }
 
 
+/// Reads RGBA8 pixels from the bound framebuffer, the one format every GLES
+/// implementation (including Mesa's llvmpipe) supports for glReadPixels.
+fn opengl_read_pixels(y: i32, width: usize, height: usize, bottom_up: bool) -> Result<ReadbackImage, String> {
+    if width == 0 || height == 0 {
+        return Err("nothing to read back from an empty framebuffer".to_string())
+    }
+    let mut rgba = vec![0u8; width * height * 4];
+    unsafe {
+        while gl_sys::GetError() != 0 {}
+        gl_sys::PixelStorei(gl_sys::PACK_ALIGNMENT, 1);
+        gl_sys::ReadPixels(0, y, width as i32, height as i32, gl_sys::RGBA, gl_sys::UNSIGNED_BYTE, rgba.as_mut_ptr() as *mut _);
+        let error = gl_sys::GetError();
+        if error != 0 {
+            return Err(format!("glReadPixels({y}, {width}, {height}) failed with {error:#x}"))
+        }
+    }
+    Ok(ReadbackImage::from_rgba_u8(&rgba, width, height, bottom_up))
+}
+
 fn get_gl_string(key: gl_sys::types::GLenum) -> String {
     unsafe {
         let string_ptr = gl_sys::GetString(key) as *const c_char;
@@ -938,6 +1100,16 @@ pub struct CxOsTexture {
 
 impl CxTexture {
 
+    pub fn gl_target(&self) -> gl_sys::GLenum {
+        match self.format.dimension() {
+            TextureDimension::D2 if self.format.is_video() => gl_sys::TEXTURE_EXTERNAL_OES,
+            TextureDimension::D2 => gl_sys::TEXTURE_2D,
+            TextureDimension::Cube => gl_sys::TEXTURE_CUBE_MAP,
+            TextureDimension::D2Array => gl_sys::TEXTURE_2D_ARRAY,
+            TextureDimension::D3 => gl_sys::TEXTURE_3D,
+        }
+    }
+
     /// Updates or creates a texture based on the current texture format.
     ///
     /// This method optimizes texture management by:
@@ -973,6 +1145,10 @@ impl CxTexture {
         if updated.is_empty() {
             return;
         }
+        if self.format.dimension() != TextureDimension::D2 {
+            self.update_layered_vec_texture();
+            return;
+        }
         
         unsafe {
             gl_sys::BindTexture(gl_sys::TEXTURE_2D, self.os.gl_texture.unwrap());
@@ -1071,6 +1247,76 @@ impl CxTexture {
         }
     }
 
+    /// Uploads cube, array and 3D textures. These always go up whole, a partial update re-uploads every layer.
+    fn update_layered_vec_texture(&mut self) {
+        // BGRA is only accepted by glTexImage2D (EXT_texture_format_BGRA8888), so cube faces use the
+        // same BGRA pairing as 2D textures, and array and 3D textures are swizzled to RGBA for glTexImage3D
+        fn bgra_to_rgba(data: &[u32]) -> Vec<u8> {
+            data.iter().flat_map( | px | {
+                let [b, g, r, a] = px.to_le_bytes();
+                [r, g, b, a]
+            }).collect()
+        }
+        let target = self.gl_target();
+        let swizzled;
+        let (width, height, depth, internal_format, format, data) = match &self.format {
+            TextureFormat::VecCubeBGRAu8_32{width, height, data, ..} =>
+                (*width, *height, 6, gl_sys::BGRA, gl_sys::BGRA, data.as_ref().unwrap().as_ptr() as *const u8),
+            TextureFormat::VecArrayBGRAu8_32{width, height, layers, data, ..} => {
+                swizzled = bgra_to_rgba(data.as_ref().unwrap());
+                (*width, *height, *layers, gl_sys::RGBA8, gl_sys::RGBA, swizzled.as_ptr())
+            }
+            TextureFormat::Vec3dBGRAu8_32{width, height, depth, data, ..} => {
+                swizzled = bgra_to_rgba(data.as_ref().unwrap());
+                (*width, *height, *depth, gl_sys::RGBA8, gl_sys::RGBA, swizzled.as_ptr())
+            }
+            TextureFormat::Vec3dRu8{width, height, depth, data, ..} =>
+                (*width, *height, *depth, gl_sys::R8, gl_sys::RED, data.as_ref().unwrap().as_ptr()),
+            _ => panic!("Unsupported texture format"),
+        };
+        let bytes_per_pixel = if format == gl_sys::RED {1} else {4};
+        unsafe {
+            gl_sys::BindTexture(target, self.os.gl_texture.unwrap());
+            gl_sys::PixelStorei(gl_sys::UNPACK_ALIGNMENT, bytes_per_pixel);
+            gl_sys::PixelStorei(gl_sys::UNPACK_ROW_LENGTH, 0);
+            gl_sys::PixelStorei(gl_sys::UNPACK_SKIP_PIXELS, 0);
+            gl_sys::PixelStorei(gl_sys::UNPACK_SKIP_ROWS, 0);
+            if target == gl_sys::TEXTURE_CUBE_MAP {
+                let face_size = width * height * bytes_per_pixel as usize;
+                for face in 0..6 {
+                    gl_sys::TexImage2D(
+                        gl_sys::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                        0,
+                        internal_format as i32,
+                        width as i32, height as i32,
+                        0,
+                        format,
+                        gl_sys::UNSIGNED_BYTE,
+                        data.add(face * face_size) as *const _
+                    );
+                }
+            }
+            else {
+                gl_sys::TexImage3D(
+                    target,
+                    0,
+                    internal_format as i32,
+                    width as i32, height as i32, depth as i32,
+                    0,
+                    format,
+                    gl_sys::UNSIGNED_BYTE,
+                    data as *const _
+                );
+            }
+            gl_sys::TexParameteri(target, gl_sys::TEXTURE_WRAP_S, gl_sys::CLAMP_TO_EDGE as i32);
+            gl_sys::TexParameteri(target, gl_sys::TEXTURE_WRAP_T, gl_sys::CLAMP_TO_EDGE as i32);
+            gl_sys::TexParameteri(target, gl_sys::TEXTURE_WRAP_R, gl_sys::CLAMP_TO_EDGE as i32);
+            gl_sys::TexParameteri(target, gl_sys::TEXTURE_MIN_FILTER, gl_sys::LINEAR as i32);
+            gl_sys::TexParameteri(target, gl_sys::TEXTURE_MAG_FILTER, gl_sys::LINEAR as i32);
+            gl_sys::BindTexture(target, 0);
+        }
+    }
+
     pub fn setup_video_texture(&mut self) -> bool {
         while unsafe { gl_sys::GetError() } != 0 {}
 
@@ -1103,6 +1349,34 @@ impl CxTexture {
         false
     }
     
+    /// Copies a 2D color texture back from the GPU through a temporary framebuffer.
+    pub fn read_back(&self) -> Result<ReadbackImage, String> {
+        let (Some(gl_texture), Some(alloc)) = (self.os.gl_texture, &self.alloc) else {
+            return Err("texture has not been allocated on the GPU yet".to_string())
+        };
+        if self.format.dimension() != TextureDimension::D2 || self.format.is_video() || self.format.is_depth() {
+            return Err("only 2D color textures can be read back".to_string())
+        }
+        let (width, height) = (alloc.width, alloc.height);
+        unsafe {
+            let mut gl_framebuffer = std::mem::MaybeUninit::uninit();
+            gl_sys::GenFramebuffers(1, gl_framebuffer.as_mut_ptr());
+            let gl_framebuffer = gl_framebuffer.assume_init();
+            gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, gl_framebuffer);
+            gl_sys::FramebufferTexture2D(gl_sys::FRAMEBUFFER, gl_sys::COLOR_ATTACHMENT0, gl_sys::TEXTURE_2D, gl_texture, 0);
+            let result = if gl_sys::CheckFramebufferStatus(gl_sys::FRAMEBUFFER) != gl_sys::FRAMEBUFFER_COMPLETE {
+                Err("texture format can't be attached to a framebuffer for readback".to_string())
+            }
+            else {
+                // render targets are drawn bottom up, uploaded textures start at the top row
+                opengl_read_pixels(0, width, height, self.format.is_render())
+            };
+            gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
+            gl_sys::DeleteFramebuffers(1, &gl_framebuffer);
+            result
+        }
+    }
+    
     pub fn update_render_target(&mut self, width: usize, height: usize) {
         if self.alloc_render(width, height){
             let alloc = self.alloc.as_ref().unwrap();
@@ -1188,7 +1462,7 @@ impl CxTexture {
                     gl_sys::BindRenderbuffer(gl_sys::RENDERBUFFER, self.os.gl_renderbuffer.unwrap());
                     gl_sys::RenderbufferStorage(
                         gl_sys::RENDERBUFFER,
-                        gl_sys::DEPTH_COMPONENT32F,
+                        gl_sys::DEPTH32F_STENCIL8,
                         width as i32,
                         height as i32
                     );
@@ -1217,6 +1491,7 @@ impl CxTexture {
 #[derive(Default, Clone)]
 pub struct CxOsPass {
     pub gl_framebuffer: Option<u32>,
+    pub gl_render_state: DrawShaderFlags,
 }
 
 impl CxOsPass{
@@ -1280,3 +1555,324 @@ impl OpenglBuffer {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        std::{cell::RefCell, ffi::CString, ptr, rc::Rc},
+        super::{gl_sys, opengl_read_pixels, super::egl_sys},
+        crate::{
+            cx::Cx,
+            event::{Event, ReadbackSource},
+            makepad_shader_compiler::{CompareFunc, DrawShaderFlags, StencilOp, StencilState},
+            texture::{CxTexture, Texture, TextureFormat, TextureSize, TextureUpdated},
+        },
+    };
+
+    /// A GLES3 context without a window or display server, rendering into FBOs only. Mesa
+    /// provides this through its surfaceless platform, backed by llvmpipe when there's no GPU.
+    struct HeadlessGl {
+        libegl: egl_sys::LibEgl,
+        display: egl_sys::EGLDisplay,
+        context: egl_sys::EGLContext,
+    }
+
+    impl HeadlessGl {
+        fn new() -> Option<Self> {
+            let libegl = egl_sys::LibEgl::try_load()?;
+            unsafe {
+                let display = (libegl.eglGetPlatformDisplayEXT?)(
+                    egl_sys::EGL_PLATFORM_SURFACELESS_MESA,
+                    ptr::null_mut(),
+                    ptr::null(),
+                );
+                if display.is_null() || (libegl.eglInitialize?)(display, ptr::null_mut(), ptr::null_mut()) == 0 {
+                    return None
+                }
+                (libegl.eglBindAPI?)(egl_sys::EGL_OPENGL_ES_API);
+                // the surface type defaults to windows, which a surfaceless display has no configs for
+                let cfg_attributes = [
+                    egl_sys::EGL_SURFACE_TYPE, 0,
+                    egl_sys::EGL_RENDERABLE_TYPE, egl_sys::EGL_OPENGL_ES2_BIT,
+                    egl_sys::EGL_NONE,
+                ];
+                let mut config = ptr::null_mut();
+                let mut cfg_count = 0;
+                (libegl.eglChooseConfig?)(display, cfg_attributes.as_ptr() as _, &mut config, 1, &mut cfg_count);
+                if cfg_count != 1 {
+                    return None
+                }
+                // depth stencil renderbuffers are DEPTH32F_STENCIL8, which needs GLES3
+                let ctx_attributes = [egl_sys::EGL_CONTEXT_CLIENT_VERSION, 3, egl_sys::EGL_NONE];
+                let context = (libegl.eglCreateContext?)(display, config, egl_sys::EGL_NO_CONTEXT, ctx_attributes.as_ptr() as _);
+                if context.is_null() || (libegl.eglMakeCurrent?)(display, egl_sys::EGL_NO_SURFACE, egl_sys::EGL_NO_SURFACE, context) == 0 {
+                    return None
+                }
+                gl_sys::load_with(|symbol| {
+                    let s = CString::new(symbol).unwrap();
+                    (libegl.eglGetProcAddress.unwrap())(s.as_ptr())
+                });
+                Some(Self {libegl, display, context})
+            }
+        }
+    }
+
+    impl Drop for HeadlessGl {
+        fn drop(&mut self) {
+            unsafe {
+                (self.libegl.eglMakeCurrent.unwrap())(self.display, egl_sys::EGL_NO_SURFACE, egl_sys::EGL_NO_SURFACE, egl_sys::EGL_NO_CONTEXT);
+                (self.libegl.eglDestroyContext.unwrap())(self.display, self.context);
+                (self.libegl.eglTerminate.unwrap())(self.display);
+            }
+        }
+    }
+
+    const RED: u32 = 0xFFFF0000;
+    const GREEN: u32 = 0xFF00FF00;
+    const BLUE: u32 = 0xFF0000FF;
+    const WIDTH: usize = 8;
+    const HEIGHT: usize = 4;
+
+    /// A render target bound as the framebuffer, with a depth stencil buffer when asked for.
+    struct Frame {
+        color: CxTexture,
+        depth: Option<CxTexture>,
+        framebuffer: u32,
+    }
+
+    impl Frame {
+        fn new(with_depth: bool) -> Self {
+            let size = TextureSize::Fixed {width: WIDTH, height: HEIGHT};
+            let mut color = CxTexture {
+                format: TextureFormat::RenderBGRAu8 {size: size.clone(), initial: true},
+                ..Default::default()
+            };
+            color.update_render_target(WIDTH, HEIGHT);
+            let depth = with_depth.then(|| {
+                let mut depth = CxTexture {
+                    format: TextureFormat::DepthD32 {size, initial: true},
+                    ..Default::default()
+                };
+                depth.update_depth_stencil(WIDTH, HEIGHT);
+                depth
+            });
+            let mut framebuffer = 0;
+            unsafe {
+                gl_sys::GenFramebuffers(1, &mut framebuffer);
+                gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, framebuffer);
+                gl_sys::FramebufferTexture2D(gl_sys::FRAMEBUFFER, gl_sys::COLOR_ATTACHMENT0, gl_sys::TEXTURE_2D, color.os.gl_texture.unwrap(), 0);
+                if let Some(depth) = &depth {
+                    gl_sys::FramebufferRenderbuffer(gl_sys::FRAMEBUFFER, gl_sys::DEPTH_STENCIL_ATTACHMENT, gl_sys::RENDERBUFFER, depth.os.gl_renderbuffer.unwrap());
+                }
+                assert_eq!(gl_sys::CheckFramebufferStatus(gl_sys::FRAMEBUFFER), gl_sys::FRAMEBUFFER_COMPLETE);
+                gl_sys::ClearColor(1.0, 0.0, 0.0, 1.0);
+                gl_sys::ClearStencil(0);
+                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT | gl_sys::STENCIL_BUFFER_BIT);
+            }
+            Self {color, depth, framebuffer}
+        }
+
+        /// Fills the given rectangle of the framebuffer, in GL's bottom up pixels, with a color
+        /// by drawing a quad with a shader.
+        fn fill(&self, color: &str, x: usize, y: usize, width: usize, height: usize) {
+            unsafe {
+                // reading back binds a framebuffer of its own
+                gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, self.framebuffer);
+                let program = compile_program(
+                    "attribute vec2 pos; void main() {gl_Position = vec4(pos, 0.0, 1.0);}",
+                    &format!("precision mediump float; void main() {{gl_FragColor = {};}}", color),
+                );
+                gl_sys::UseProgram(program);
+                let vertices: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+                let indices: [u32; 6] = [0, 1, 2, 2, 3, 0];
+                let mut buffers = [0; 2];
+                gl_sys::GenBuffers(2, buffers.as_mut_ptr());
+                gl_sys::BindBuffer(gl_sys::ARRAY_BUFFER, buffers[0]);
+                gl_sys::BufferData(gl_sys::ARRAY_BUFFER, std::mem::size_of_val(&vertices) as _, vertices.as_ptr() as *const _, gl_sys::STATIC_DRAW);
+                gl_sys::BindBuffer(gl_sys::ELEMENT_ARRAY_BUFFER, buffers[1]);
+                gl_sys::BufferData(gl_sys::ELEMENT_ARRAY_BUFFER, std::mem::size_of_val(&indices) as _, indices.as_ptr() as *const _, gl_sys::STATIC_DRAW);
+                let pos = gl_sys::GetAttribLocation(program, c"pos".as_ptr()) as u32;
+                gl_sys::VertexAttribPointer(pos, 2, gl_sys::FLOAT, 0, 0, ptr::null());
+                gl_sys::EnableVertexAttribArray(pos);
+                gl_sys::Viewport(x as i32, y as i32, width as i32, height as i32);
+                gl_sys::DrawElementsInstanced(gl_sys::TRIANGLES, 6, gl_sys::UNSIGNED_INT, ptr::null(), 1);
+                assert_eq!(gl_sys::GetError(), 0);
+                gl_sys::DeleteBuffers(2, buffers.as_ptr());
+            }
+        }
+
+        /// Checks the pixels read back from the render target, top row first.
+        fn assert_pixels(&self, expected: impl Fn(usize, usize) -> u32) {
+            let image = self.color.read_back().unwrap();
+            assert_eq!((image.width, image.height), (WIDTH, HEIGHT));
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    assert_eq!(image.pixel(x, y), Some(expected(x, y)), "pixel ({x}, {y})");
+                }
+            }
+        }
+    }
+
+    impl Drop for Frame {
+        fn drop(&mut self) {
+            unsafe {
+                gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
+                gl_sys::DeleteFramebuffers(1, &self.framebuffer);
+                gl_sys::DeleteTextures(1, &self.color.os.gl_texture.unwrap());
+                if let Some(gl_renderbuffer) = self.depth.as_ref().and_then(|depth| depth.os.gl_renderbuffer) {
+                    gl_sys::DeleteRenderbuffers(1, &gl_renderbuffer);
+                }
+            }
+        }
+    }
+
+    unsafe fn compile_program(vertex: &str, pixel: &str) -> u32 {
+        let program = gl_sys::CreateProgram();
+        for (kind, source) in [(gl_sys::VERTEX_SHADER, vertex), (gl_sys::FRAGMENT_SHADER, pixel)] {
+            let shader = gl_sys::CreateShader(kind);
+            let source = CString::new(source).unwrap();
+            gl_sys::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+            gl_sys::CompileShader(shader);
+            let mut status = 0;
+            gl_sys::GetShaderiv(shader, gl_sys::COMPILE_STATUS, &mut status);
+            assert_eq!(status, 1, "shader failed to compile: {}", source.to_string_lossy());
+            gl_sys::AttachShader(program, shader);
+            gl_sys::DeleteShader(shader);
+        }
+        gl_sys::LinkProgram(program);
+        let mut status = 0;
+        gl_sys::GetProgramiv(program, gl_sys::LINK_STATUS, &mut status);
+        assert_eq!(status, 1, "program failed to link");
+        program
+    }
+
+    // These need Mesa's surfaceless EGL platform, run them with `cargo test -- --ignored`.
+
+    #[test]
+    #[ignore]
+    fn rendered_and_uploaded_textures_read_back_top_row_first() {
+        let _gl = HeadlessGl::new().expect("no surfaceless EGL platform (Mesa's llvmpipe) is available");
+
+        // Render a frame: clear a render target to red, and draw a green quad into its top left
+        // quadrant. GL's viewport origin is the bottom left, so that's the upper half of the rows.
+        let frame = Frame::new(false);
+        frame.fill("vec4(0.0, 1.0, 0.0, 1.0)", 0, HEIGHT / 2, WIDTH / 2, HEIGHT / 2);
+        frame.assert_pixels(|x, y| if x < WIDTH / 2 && y < HEIGHT / 2 {GREEN} else {RED});
+
+        // Uploaded textures aren't flipped, their first row is the top one.
+        let data = vec![RED, GREEN, BLUE, 0x80FFFFFF];
+        let mut texture = CxTexture {
+            format: TextureFormat::VecBGRAu8_32 {width: 2, height: 2, data: Some(data.clone()), updated: TextureUpdated::Full},
+            ..Default::default()
+        };
+        texture.update_vec_texture();
+        assert_eq!(texture.read_back().unwrap().data, data);
+    }
+
+    #[test]
+    #[ignore]
+    fn layered_textures_upload_in_formats_gles_accepts() {
+        let _gl = HeadlessGl::new().expect("no surfaceless EGL platform (Mesa's llvmpipe) is available");
+        let faces: Vec<u32> = (0..6).flat_map( | face | [RED, GREEN, BLUE, face]).collect();
+        let mut cube = CxTexture {
+            format: TextureFormat::VecCubeBGRAu8_32 {width: 2, height: 2, data: Some(faces.clone()), updated: TextureUpdated::Full},
+            ..Default::default()
+        };
+        cube.update_vec_texture();
+        let mut array = CxTexture {
+            format: TextureFormat::VecArrayBGRAu8_32 {width: 2, height: 1, layers: 3, data: Some(faces.clone()), updated: TextureUpdated::Full},
+            ..Default::default()
+        };
+        array.update_vec_texture();
+        let mut volume = CxTexture {
+            format: TextureFormat::Vec3dBGRAu8_32 {width: 2, height: 2, depth: 2, data: Some(faces[..8].to_vec()), updated: TextureUpdated::Full},
+            ..Default::default()
+        };
+        volume.update_vec_texture();
+        let mut volume_r = CxTexture {
+            format: TextureFormat::Vec3dRu8 {width: 3, height: 2, depth: 2, data: Some(vec![0; 12]), updated: TextureUpdated::Full},
+            ..Default::default()
+        };
+        volume_r.update_vec_texture();
+        unsafe {
+            assert_eq!(gl_sys::GetError(), 0);
+            // every cube face went up in the right place with its channels in order
+            let mut framebuffer = 0;
+            gl_sys::GenFramebuffers(1, &mut framebuffer);
+            gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, framebuffer);
+            for face in 0..6 {
+                gl_sys::FramebufferTexture2D(gl_sys::FRAMEBUFFER, gl_sys::COLOR_ATTACHMENT0, gl_sys::TEXTURE_CUBE_MAP_POSITIVE_X + face, cube.os.gl_texture.unwrap(), 0);
+                let image = opengl_read_pixels(0, 2, 2, false).unwrap();
+                assert_eq!(image.data, faces[face as usize * 4..face as usize * 4 + 4]);
+            }
+            // and so did every array layer, which glTexImage3D only takes as RGBA
+            for layer in 0..3 {
+                gl_sys::FramebufferTextureLayer(gl_sys::FRAMEBUFFER, gl_sys::COLOR_ATTACHMENT0, array.os.gl_texture.unwrap(), 0, layer);
+                let image = opengl_read_pixels(0, 2, 1, false).unwrap();
+                assert_eq!(image.data, faces[layer as usize * 2..layer as usize * 2 + 2]);
+            }
+            gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
+            gl_sys::DeleteFramebuffers(1, &framebuffer);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn requested_readbacks_arrive_as_events_after_the_repaint() {
+        let _gl = HeadlessGl::new().expect("no surfaceless EGL platform (Mesa's llvmpipe) is available");
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut cx = Cx::new(Box::new({
+            let events = events.clone();
+            move | _, event | if let Event::Readback(readback) = event {
+                events.borrow_mut().push(readback.clone());
+            }
+        }));
+        let data = vec![RED, GREEN, BLUE, 0x80FFFFFF];
+        let texture = Texture::new_with_format(&mut cx, TextureFormat::VecBGRAu8_32 {
+            width: 2,
+            height: 2,
+            data: Some(data.clone()),
+            updated: TextureUpdated::Full,
+        });
+        cx.textures[texture.texture_id()].update_vec_texture();
+
+        let readback_id = texture.read_back(&mut cx);
+        assert!(cx.any_passes_dirty(), "a pending readback needs a repaint");
+        assert!(events.borrow().is_empty(), "readbacks are only serviced after a repaint");
+        cx.opengl_finish_readbacks();
+
+        let events = events.borrow();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].readback_id, readback_id);
+        assert_eq!(events[0].source, ReadbackSource::Texture(texture.texture_id()));
+        assert_eq!(events[0].result.as_ref().unwrap().data, data);
+        assert!(cx.readbacks.requests.is_empty() && cx.readbacks.completed.is_empty());
+    }
+
+    #[test]
+    #[ignore]
+    fn stencil_state_rejects_pixels() {
+        let _gl = HeadlessGl::new().expect("no surfaceless EGL platform (Mesa's llvmpipe) is available");
+        let frame = Frame::new(true);
+
+        // A shader that marks the pixels it draws, in the left half.
+        Cx::set_render_state(&DrawShaderFlags {
+            stencil: StencilState {reference: 1, pass: StencilOp::Replace, ..Default::default()},
+            ..Default::default()
+        });
+        frame.fill("vec4(0.0, 0.0, 1.0, 1.0)", 0, 0, WIDTH / 2, HEIGHT);
+
+        // One that only draws where the stencil isn't marked, over everything.
+        Cx::set_render_state(&DrawShaderFlags {
+            stencil: StencilState {test: CompareFunc::NotEqual, reference: 1, ..Default::default()},
+            ..Default::default()
+        });
+        frame.fill("vec4(0.0, 1.0, 0.0, 1.0)", 0, 0, WIDTH, HEIGHT);
+        frame.assert_pixels(|x, _| if x < WIDTH / 2 {BLUE} else {GREEN});
+
+        // And the default state that leaves the stencil alone.
+        Cx::set_render_state(&DrawShaderFlags::default());
+        frame.fill("vec4(1.0, 0.0, 0.0, 1.0)", 0, 0, WIDTH, HEIGHT);
+        frame.assert_pixels(|_, _| RED);
+    }
+}
@@ -204,6 +204,10 @@ impl Cx {
                         self.handle_media_signals();
                         self.call_event_handler(&Event::Signal);
                     }
+                    if self.handle_live_edit() {
+                        self.call_event_handler(&Event::LiveEdit);
+                        self.redraw_all();
+                    }
                     self.handle_action_receiver();
                 }
                 else{
@@ -247,6 +251,7 @@ impl Cx {
                 }
             }
         }
+        self.opengl_finish_readbacks();
     }
     
     fn handle_platform_ops(&mut self, opengl_windows: &mut Vec<OpenglWindow>, xlib_app: &mut XlibApp) -> EventFlow {
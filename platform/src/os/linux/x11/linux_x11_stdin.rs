@@ -75,6 +75,7 @@ impl Cx {
                 }
             }
         }
+        self.opengl_finish_readbacks();
     }
     
     pub fn stdin_event_loop(&mut self) {
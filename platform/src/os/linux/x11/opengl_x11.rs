@@ -61,15 +61,17 @@ impl Cx {
             PassClearDepth::ClearWith(depth) => depth
         };
         
+        // the depth and stencil write masks also apply to the clear
+        self.set_default_depth_and_blend_mode(pass_id);
+        
         if !self.passes[pass_id].dont_clear {
             unsafe {
                 gl_sys::BindFramebuffer(gl_sys::FRAMEBUFFER, 0);
                 gl_sys::ClearDepthf(clear_depth as f32);
                 gl_sys::ClearColor(clear_color.x, clear_color.y, clear_color.z, clear_color.w);
-                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT);
+                gl_sys::Clear(gl_sys::COLOR_BUFFER_BIT | gl_sys::DEPTH_BUFFER_BIT | gl_sys::STENCIL_BUFFER_BIT);
             }
         }
-        Self::set_default_depth_and_blend_mode();
         
         let mut zbias = 0.0;
         let zbias_step = self.passes[pass_id].zbias_step;
@@ -80,6 +82,8 @@ impl Cx {
             &mut zbias,
             zbias_step,
         );
+        
+        self.opengl_read_back_pass(pass_id, 0, pix_width.floor() as usize, pix_height.floor() as usize);
 
         unsafe {
             let opengl_cx = self.os.opengl_cx.as_ref().unwrap();
@@ -327,10 +331,11 @@ impl OpenglCx {
             8,
             egl_sys::EGL_ALPHA_SIZE,
             8,
-            // egl_sys::EGL_DEPTH_SIZE,
-            // 24,
-            // egl_sys::EGL_STENCIL_SIZE,
-            // 8,
+            // the per shader stencil state needs a stencil buffer on the window surface
+            egl_sys::EGL_DEPTH_SIZE,
+            24,
+            egl_sys::EGL_STENCIL_SIZE,
+            8,
             egl_sys::EGL_RENDERABLE_TYPE,
             egl_sys::EGL_OPENGL_ES2_BIT,
             egl_sys::EGL_NONE
@@ -126,6 +126,11 @@ pub struct ToWasmResizeWindow {
     pub window_info: WWindowInfo
 }
 
+#[derive(ToWasm)]
+pub struct ToWasmThemeChange {
+    pub dark_mode: bool
+}
+
 #[derive(ToWasm)]
 pub struct ToWasmAnimationFrame {
     pub time: f64
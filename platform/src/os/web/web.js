@@ -86,6 +86,7 @@ export class WasmWebBrowser extends WasmBridge {
         this.bind_mouse_and_touch();
         this.bind_keyboard();
         this.bind_screen_resize();
+        this.bind_theme_change();
         this.focus_keyboard_input();
         this.to_wasm.ToWasmRedrawAll();
         this.start_signal_poll();
@@ -757,6 +758,17 @@ export class WasmWebBrowser extends WasmBridge {
         window.addEventListener('orientationchange', _ => this.handlers.on_screen_resize())
     }
     
+    bind_theme_change() {
+        let query = window.matchMedia('(prefers-color-scheme: dark)');
+        this.handlers.on_theme_change = () => {
+            this.to_wasm.ToWasmThemeChange({dark_mode: query.matches});
+            this.do_wasm_pump();
+        }
+        query.addEventListener('change', _ => this.handlers.on_theme_change());
+        // the theme the page starts in
+        this.handlers.on_theme_change();
+    }
+    
     bind_mouse_and_touch() {
         
         var canvas = this.canvas
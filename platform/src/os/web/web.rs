@@ -30,7 +30,8 @@ use {
             TouchUpdateEvent,
             ScrollEvent,
             WindowGeom,
-            WindowGeomChangeEvent
+            WindowGeomChangeEvent,
+            ThemeChangeEvent
         },
         pass::CxPassParent,
         cx_api::{CxOsApi, CxOsOp, OpenUrlInPlace},
@@ -107,6 +108,11 @@ impl Cx {
                     }
                 }
                 
+                live_id!(ToWasmThemeChange) => {
+                    let tw = ToWasmThemeChange::read_to_wasm(&mut to_wasm);
+                    self.call_event_handler(&Event::ThemeChange(ThemeChangeEvent {dark_mode: tw.dark_mode}));
+                }
+                
                 live_id!(ToWasmAnimationFrame) => {
                     let tw = ToWasmAnimationFrame::read_to_wasm(&mut to_wasm);
                     is_animation_frame = Some(tw.time);
@@ -365,6 +371,8 @@ impl Cx {
     
          
     pub fn handle_repaint(&mut self, time: f64){
+        // readbacks aren't supported on this backend, they were completed with an error on request
+        self.call_readback_events();
         let mut passes_todo = Vec::new();
          
         self.compute_pass_repaint_order(&mut passes_todo);
@@ -526,6 +534,7 @@ impl CxOsApi for Cx {
             ToWasmGetDeps::to_js_code(),
             ToWasmInit::to_js_code(),
             ToWasmResizeWindow::to_js_code(),
+            ToWasmThemeChange::to_js_code(),
             ToWasmAnimationFrame::to_js_code(),
             
             ToWasmTouchUpdate::to_js_code(),
@@ -1,5 +1,5 @@
 use crate::{
-    makepad_shader_compiler::generate_hlsl,
+    makepad_shader_compiler::{generate_hlsl, DrawShaderFlags, BlendFactor, BlendOp, CompareFunc, StencilOp},
     makepad_math::*,
     os::{
         windows::win32_app::{TRUE, FALSE,},
@@ -14,6 +14,7 @@ use crate::{
     window::WindowId,
     texture::{ 
         TextureFormat,
+        TextureDimension,
         TexturePixel,
         TextureId,
         CxTexture
@@ -45,23 +46,57 @@ use crate::{
                     D3D11_BIND_FLAG,
                     D3D11_BIND_SHADER_RESOURCE,
                     D3D11_TEXTURE2D_DESC,
+                    D3D11_TEXTURE3D_DESC,
+                    D3D11_SHADER_RESOURCE_VIEW_DESC,
+                    D3D11_SHADER_RESOURCE_VIEW_DESC_0,
+                    D3D11_TEXCUBE_SRV,
                     D3D11_BIND_RENDER_TARGET,
                     D3D11_BIND_DEPTH_STENCIL,
                     D3D11_DEPTH_STENCIL_DESC,
                     D3D11_DEPTH_WRITE_MASK_ALL,
+                    D3D11_DEPTH_WRITE_MASK_ZERO,
+                    D3D11_COMPARISON_FUNC,
+                    D3D11_COMPARISON_NEVER,
+                    D3D11_COMPARISON_LESS,
+                    D3D11_COMPARISON_EQUAL,
                     D3D11_COMPARISON_LESS_EQUAL,
+                    D3D11_COMPARISON_GREATER,
+                    D3D11_COMPARISON_NOT_EQUAL,
+                    D3D11_COMPARISON_GREATER_EQUAL,
+                    D3D11_COMPARISON_ALWAYS,
                     D3D11_DEPTH_STENCILOP_DESC,
+                    D3D11_STENCIL_OP,
+                    D3D11_STENCIL_OP_KEEP,
+                    D3D11_STENCIL_OP_ZERO,
                     D3D11_STENCIL_OP_REPLACE,
-                    D3D11_COMPARISON_ALWAYS,
+                    D3D11_STENCIL_OP_INCR_SAT,
+                    D3D11_STENCIL_OP_DECR_SAT,
+                    D3D11_STENCIL_OP_INVERT,
+                    D3D11_STENCIL_OP_INCR,
+                    D3D11_STENCIL_OP_DECR,
                     D3D11_DEPTH_STENCIL_VIEW_DESC,
                     D3D11_DSV_DIMENSION_TEXTURE2D,
                     D3D11_CLEAR_DEPTH,
                     D3D11_CLEAR_STENCIL,
                     D3D11_BLEND_DESC,
                     D3D11_RENDER_TARGET_BLEND_DESC,
+                    D3D11_BLEND,
+                    D3D11_BLEND_ZERO,
                     D3D11_BLEND_ONE,
+                    D3D11_BLEND_SRC_COLOR,
+                    D3D11_BLEND_INV_SRC_COLOR,
+                    D3D11_BLEND_SRC_ALPHA,
                     D3D11_BLEND_INV_SRC_ALPHA,
+                    D3D11_BLEND_DEST_COLOR,
+                    D3D11_BLEND_INV_DEST_COLOR,
+                    D3D11_BLEND_DEST_ALPHA,
+                    D3D11_BLEND_INV_DEST_ALPHA,
+                    D3D11_BLEND_OP,
                     D3D11_BLEND_OP_ADD,
+                    D3D11_BLEND_OP_SUBTRACT,
+                    D3D11_BLEND_OP_REV_SUBTRACT,
+                    D3D11_BLEND_OP_MIN,
+                    D3D11_BLEND_OP_MAX,
                     D3D11_COLOR_WRITE_ENABLE_ALL,
                     D3D11_RASTERIZER_DESC,
                     D3D11_CULL_NONE,
@@ -79,6 +114,7 @@ use crate::{
                     ID3D11DeviceContext,
                     ID3D11RenderTargetView,
                     ID3D11Texture2D,
+                    ID3D11Texture3D,
                     ID3D11ShaderResourceView,
                     ID3D11DepthStencilView,
                     ID3D11BlendState,
@@ -98,6 +134,7 @@ use crate::{
                     D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
                     D3D_DRIVER_TYPE_UNKNOWN,
                     D3D_FEATURE_LEVEL_11_0,
+                    D3D_SRV_DIMENSION_TEXTURECUBE,
                 },
                 Dxgi::{
                     IDXGIFactory2,
@@ -118,8 +155,7 @@ use crate::{
                         DXGI_SAMPLE_DESC,
                         DXGI_FORMAT_R32G32B32A32_FLOAT,
                         DXGI_FORMAT_R16_FLOAT, 
-                        //DXGI_FORMAT_D32_FLOAT_S8X 24_UINT,
-                        DXGI_FORMAT_D32_FLOAT,
+                        DXGI_FORMAT_D32_FLOAT_S8X24_UINT,
                         DXGI_FORMAT_R32_UINT,
                         DXGI_FORMAT_R32_FLOAT,
                         DXGI_FORMAT_R32G32_FLOAT,
@@ -222,6 +258,10 @@ impl Cx {
                     d3d11_cx.context.IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
                     d3d11_cx.context.IASetInputLayout(&shp.input_layout);
                     
+                    let blend_factor = [0., 0., 0., 0.];
+                    d3d11_cx.context.OMSetBlendState(&shp.blend_state, Some(&blend_factor), 0xffffffff);
+                    d3d11_cx.context.OMSetDepthStencilState(&shp.depth_stencil_state, shp.flags.stencil.reference as u32);
+                    
                     let geom_ibuf = geometry.os.geom_ibuf.buffer.as_ref().unwrap();
                     d3d11_cx.context.IASetIndexBuffer(geom_ibuf, DXGI_FORMAT_R32_UINT, 0);
                     
@@ -390,7 +430,7 @@ impl Cx {
             }
             unsafe {d3d11_cx.context.OMSetRenderTargets(
                 Some(&color_textures),
-                None, //cxtexture.os.depth_stencil_view.as_ref().unwrap()
+                cxtexture.os.depth_stencil_view.as_ref()
             )}
         }
         else {
@@ -400,7 +440,7 @@ impl Cx {
             )}
         }
         
-        // create depth, blend and raster states
+        // create the raster state, blend and depth states are set per draw shader
         self.passes[pass_id].os.set_states(d3d11_cx);
         
         let cxpass = &mut self.passes[pass_id];
@@ -483,7 +523,7 @@ impl Cx {
                 }
                 // lets see if we have the shader already
                 for (index, ds) in self.draw_shaders.os_shaders.iter().enumerate() {
-                    if ds.hlsl == hlsl {
+                    if ds.hlsl == hlsl && ds.flags.render_state_eq(&cx_shader.mapping.flags) {
                         cx_shader.os_shader_id = Some(index);
                         break;
                     }
@@ -517,7 +557,7 @@ fn texture_pixel_to_dx11_pixel(pix:&TexturePixel)->DXGI_FORMAT{
         TexturePixel::Ru8  => DXGI_FORMAT_R8_UNORM,
         TexturePixel::RGu8  => DXGI_FORMAT_R8G8_UNORM,
         TexturePixel::Rf32  => DXGI_FORMAT_R32_FLOAT,
-        TexturePixel::D32 => DXGI_FORMAT_D32_FLOAT,
+        TexturePixel::D32 => DXGI_FORMAT_D32_FLOAT_S8X24_UINT,
     }   
 }
 
@@ -805,6 +845,12 @@ impl CxTexture {
     ) {
         // TODO maybe we can update the data instead of making a new texture?
         if self.alloc_vec(){}
+        if self.format.dimension() != TextureDimension::D2 {
+            if !self.take_updated().is_empty() {
+                self.update_layered_vec_texture(d3d11_cx);
+            }
+            return
+        }
         if !self.take_updated().is_empty() {
             fn get_descs(format: DXGI_FORMAT, width: usize, height: usize, bpp: usize, data: *const std::ffi::c_void)->(D3D11_SUBRESOURCE_DATA,D3D11_TEXTURE2D_DESC) {
                 let sub_data = D3D11_SUBRESOURCE_DATA {
@@ -860,6 +906,91 @@ impl CxTexture {
         }
     }
     
+    /// Cube faces and array layers become one subresource each, a 3D texture is a single subresource.
+    fn update_layered_vec_texture(&mut self, d3d11_cx: &D3d11Cx) {
+        let (format, width, height, depth, bpp, data) = match &self.format {
+            TextureFormat::VecCubeBGRAu8_32{width, height, data, ..} =>
+                (DXGI_FORMAT_B8G8R8A8_UNORM, *width, *height, 6, 4, data.as_ref().unwrap().as_ptr() as *const u8),
+            TextureFormat::VecArrayBGRAu8_32{width, height, layers, data, ..} =>
+                (DXGI_FORMAT_B8G8R8A8_UNORM, *width, *height, *layers, 4, data.as_ref().unwrap().as_ptr() as *const u8),
+            TextureFormat::Vec3dBGRAu8_32{width, height, depth, data, ..} =>
+                (DXGI_FORMAT_B8G8R8A8_UNORM, *width, *height, *depth, 4, data.as_ref().unwrap().as_ptr() as *const u8),
+            TextureFormat::Vec3dRu8{width, height, depth, data, ..} =>
+                (DXGI_FORMAT_R8_UNORM, *width, *height, *depth, 1, data.as_ref().unwrap().as_ptr()),
+            _ => panic!()
+        };
+        let slice_pitch = width * height * bpp;
+        let mut shader_resource_view = None;
+        if self.format.dimension() == TextureDimension::D3 {
+            let sub_data = D3D11_SUBRESOURCE_DATA {
+                pSysMem: data as *const _,
+                SysMemPitch: (width * bpp) as u32,
+                SysMemSlicePitch: slice_pitch as u32
+            };
+            let texture_desc = D3D11_TEXTURE3D_DESC {
+                Width: width as u32,
+                Height: height as u32,
+                Depth: depth as u32,
+                MipLevels: 1,
+                Format: format,
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as u32,
+                CPUAccessFlags: 0,
+                MiscFlags: 0,
+            };
+            let mut texture: Option<ID3D11Texture3D> = None;
+            unsafe {d3d11_cx.device.CreateTexture3D(&texture_desc, Some(&sub_data), Some(&mut texture)).unwrap()};
+            let resource: ID3D11Resource = texture.unwrap().cast().unwrap();
+            unsafe {d3d11_cx.device.CreateShaderResourceView(&resource, None, Some(&mut shader_resource_view)).unwrap()};
+            // the shader resource view keeps the 3D texture alive
+            self.os.texture = None;
+        }
+        else {
+            let sub_data: Vec<D3D11_SUBRESOURCE_DATA> = (0..depth).map( | slice | D3D11_SUBRESOURCE_DATA {
+                pSysMem: unsafe {data.add(slice * slice_pitch)} as *const _,
+                SysMemPitch: (width * bpp) as u32,
+                SysMemSlicePitch: 0
+            }).collect();
+            let is_cube = self.format.dimension() == TextureDimension::Cube;
+            let texture_desc = D3D11_TEXTURE2D_DESC {
+                Width: width as u32,
+                Height: height as u32,
+                MipLevels: 1,
+                ArraySize: depth as u32,
+                Format: format,
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0
+                },
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as u32,
+                CPUAccessFlags: 0,
+                MiscFlags: if is_cube {4} else {0}, // D3D11_RESOURCE_MISC_TEXTURECUBE
+            };
+            let mut texture = None;
+            unsafe {d3d11_cx.device.CreateTexture2D(&texture_desc, Some(sub_data.as_ptr()), Some(&mut texture)).unwrap()};
+            let resource: ID3D11Resource = texture.clone().unwrap().cast().unwrap();
+            if is_cube {
+                let view_desc = D3D11_SHADER_RESOURCE_VIEW_DESC {
+                    Format: format,
+                    ViewDimension: D3D_SRV_DIMENSION_TEXTURECUBE,
+                    Anonymous: D3D11_SHADER_RESOURCE_VIEW_DESC_0 {
+                        TextureCube: D3D11_TEXCUBE_SRV {
+                            MostDetailedMip: 0,
+                            MipLevels: 1,
+                        }
+                    },
+                };
+                unsafe {d3d11_cx.device.CreateShaderResourceView(&resource, Some(&view_desc), Some(&mut shader_resource_view)).unwrap()};
+            }
+            else {
+                unsafe {d3d11_cx.device.CreateShaderResourceView(&resource, None, Some(&mut shader_resource_view)).unwrap()};
+            }
+            self.os.texture = texture;
+        }
+        self.os.shader_resource_view = shader_resource_view;
+    }
+    
     pub fn update_render_target(
         &mut self,
         d3d11_cx: &D3d11Cx,
@@ -909,7 +1040,7 @@ impl CxTexture {
             let format;
             match alloc.pixel {
                 TexturePixel::D32 => {
-                    format = DXGI_FORMAT_D32_FLOAT;
+                    format = DXGI_FORMAT_D32_FLOAT_S8X24_UINT;
                 }
                 _ => {
                     panic!("Wrong format for update_depth_stencil");
@@ -934,7 +1065,7 @@ impl CxTexture {
             //let shader_resource_view = unsafe {d3d11_cx.device.CreateShaderResourceView(&texture, None).unwrap()};
             
             let dsv_desc = D3D11_DEPTH_STENCIL_VIEW_DESC {
-                Format: DXGI_FORMAT_D32_FLOAT,
+                Format: DXGI_FORMAT_D32_FLOAT_S8X24_UINT,
                 ViewDimension: D3D11_DSV_DIMENSION_TEXTURE2D,
                 Flags: 0,
                 ..Default::default()
@@ -1018,23 +1149,6 @@ impl CxTexture {
 impl CxOsPass {
     pub fn set_states(&mut self, d3d11_cx: &D3d11Cx,) {
         
-        if self.blend_state.is_none() {
-            
-            let mut blend_desc: D3D11_BLEND_DESC = Default::default();
-            blend_desc.AlphaToCoverageEnable = FALSE;
-            blend_desc.RenderTarget[0] = D3D11_RENDER_TARGET_BLEND_DESC {
-                BlendEnable: TRUE,
-                SrcBlend: D3D11_BLEND_ONE,
-                SrcBlendAlpha: D3D11_BLEND_ONE,
-                DestBlend: D3D11_BLEND_INV_SRC_ALPHA,
-                DestBlendAlpha: D3D11_BLEND_INV_SRC_ALPHA,
-                BlendOp: D3D11_BLEND_OP_ADD,
-                BlendOpAlpha: D3D11_BLEND_OP_ADD,
-                RenderTargetWriteMask: D3D11_COLOR_WRITE_ENABLE_ALL.0 as u8,
-            };
-            unsafe {d3d11_cx.device.CreateBlendState(&blend_desc, Some(&mut self.blend_state)).unwrap()}
-        }
-        
         if self.raster_state.is_none() {
             let raster_desc = D3D11_RASTERIZER_DESC {
                 AntialiasedLineEnable: FALSE,
@@ -1051,35 +1165,8 @@ impl CxOsPass {
             unsafe {d3d11_cx.device.CreateRasterizerState(&raster_desc, Some(&mut self.raster_state)).unwrap()}
         }
         
-        if self.depth_stencil_state.is_none() {
-            let ds_desc = D3D11_DEPTH_STENCIL_DESC {
-                DepthEnable: TRUE,
-                DepthWriteMask: D3D11_DEPTH_WRITE_MASK_ALL,
-                DepthFunc: D3D11_COMPARISON_LESS_EQUAL,
-                StencilEnable: FALSE,
-                StencilReadMask: 0xff,
-                StencilWriteMask: 0xff,
-                FrontFace: D3D11_DEPTH_STENCILOP_DESC {
-                    StencilFailOp: D3D11_STENCIL_OP_REPLACE,
-                    StencilDepthFailOp: D3D11_STENCIL_OP_REPLACE,
-                    StencilPassOp: D3D11_STENCIL_OP_REPLACE,
-                    StencilFunc: D3D11_COMPARISON_ALWAYS,
-                },
-                BackFace: D3D11_DEPTH_STENCILOP_DESC {
-                    StencilFailOp: D3D11_STENCIL_OP_REPLACE,
-                    StencilDepthFailOp: D3D11_STENCIL_OP_REPLACE,
-                    StencilPassOp: D3D11_STENCIL_OP_REPLACE,
-                    StencilFunc: D3D11_COMPARISON_ALWAYS,
-                },
-            };
-            unsafe {d3d11_cx.device.CreateDepthStencilState(&ds_desc, Some(&mut self.depth_stencil_state)).unwrap()}
-        }
-        
         unsafe {
             d3d11_cx.context.RSSetState(self.raster_state.as_ref().unwrap());
-            let blend_factor = [0., 0., 0., 0.];
-            d3d11_cx.context.OMSetBlendState(self.blend_state.as_ref().unwrap(), Some(&blend_factor), 0xffffffff);
-            d3d11_cx.context.OMSetDepthStencilState(self.depth_stencil_state.as_ref().unwrap(), 0);
         }
     }
 }
@@ -1087,9 +1174,7 @@ impl CxOsPass {
 #[derive(Default, Clone)]
 pub struct CxOsPass {
     pass_uniforms: D3d11Buffer,
-    blend_state: Option<ID3D11BlendState >,
     raster_state: Option<ID3D11RasterizerState >,
-    depth_stencil_state: Option<ID3D11DepthStencilState >
 }
 
 #[derive(Default, Clone)]
@@ -1107,7 +1192,73 @@ pub struct CxOsDrawShader {
     pub vertex_shader: ID3D11VertexShader,
     pub pixel_shader_blob: ID3DBlob,
     pub vertex_shader_blob: ID3DBlob,
-    pub input_layout: ID3D11InputLayout
+    pub input_layout: ID3D11InputLayout,
+    pub blend_state: ID3D11BlendState,
+    pub depth_stencil_state: ID3D11DepthStencilState,
+    pub flags: DrawShaderFlags,
+}
+
+fn blend_factor_to_dx11(factor: BlendFactor) -> D3D11_BLEND {
+    match factor {
+        BlendFactor::Zero => D3D11_BLEND_ZERO,
+        BlendFactor::One => D3D11_BLEND_ONE,
+        BlendFactor::SrcColor => D3D11_BLEND_SRC_COLOR,
+        BlendFactor::OneMinusSrcColor => D3D11_BLEND_INV_SRC_COLOR,
+        BlendFactor::SrcAlpha => D3D11_BLEND_SRC_ALPHA,
+        BlendFactor::OneMinusSrcAlpha => D3D11_BLEND_INV_SRC_ALPHA,
+        BlendFactor::DstColor => D3D11_BLEND_DEST_COLOR,
+        BlendFactor::OneMinusDstColor => D3D11_BLEND_INV_DEST_COLOR,
+        BlendFactor::DstAlpha => D3D11_BLEND_DEST_ALPHA,
+        BlendFactor::OneMinusDstAlpha => D3D11_BLEND_INV_DEST_ALPHA,
+    }
+}
+
+/// D3D11 rejects the `*_COLOR` factors for the alpha channel, so they blend with the matching
+/// alpha factor there, which is what they amount to for a single channel.
+fn alpha_blend_factor_to_dx11(factor: BlendFactor) -> D3D11_BLEND {
+    blend_factor_to_dx11(match factor {
+        BlendFactor::SrcColor => BlendFactor::SrcAlpha,
+        BlendFactor::OneMinusSrcColor => BlendFactor::OneMinusSrcAlpha,
+        BlendFactor::DstColor => BlendFactor::DstAlpha,
+        BlendFactor::OneMinusDstColor => BlendFactor::OneMinusDstAlpha,
+        factor => factor,
+    })
+}
+
+fn blend_op_to_dx11(op: BlendOp) -> D3D11_BLEND_OP {
+    match op {
+        BlendOp::Add => D3D11_BLEND_OP_ADD,
+        BlendOp::Subtract => D3D11_BLEND_OP_SUBTRACT,
+        BlendOp::ReverseSubtract => D3D11_BLEND_OP_REV_SUBTRACT,
+        BlendOp::Min => D3D11_BLEND_OP_MIN,
+        BlendOp::Max => D3D11_BLEND_OP_MAX,
+    }
+}
+
+fn compare_func_to_dx11(func: CompareFunc) -> D3D11_COMPARISON_FUNC {
+    match func {
+        CompareFunc::Never => D3D11_COMPARISON_NEVER,
+        CompareFunc::Less => D3D11_COMPARISON_LESS,
+        CompareFunc::Equal => D3D11_COMPARISON_EQUAL,
+        CompareFunc::LessEqual => D3D11_COMPARISON_LESS_EQUAL,
+        CompareFunc::Greater => D3D11_COMPARISON_GREATER,
+        CompareFunc::NotEqual => D3D11_COMPARISON_NOT_EQUAL,
+        CompareFunc::GreaterEqual => D3D11_COMPARISON_GREATER_EQUAL,
+        CompareFunc::Always => D3D11_COMPARISON_ALWAYS,
+    }
+}
+
+fn stencil_op_to_dx11(op: StencilOp) -> D3D11_STENCIL_OP {
+    match op {
+        StencilOp::Keep => D3D11_STENCIL_OP_KEEP,
+        StencilOp::Zero => D3D11_STENCIL_OP_ZERO,
+        StencilOp::Replace => D3D11_STENCIL_OP_REPLACE,
+        StencilOp::IncrementClamp => D3D11_STENCIL_OP_INCR_SAT,
+        StencilOp::DecrementClamp => D3D11_STENCIL_OP_DECR_SAT,
+        StencilOp::Invert => D3D11_STENCIL_OP_INVERT,
+        StencilOp::IncrementWrap => D3D11_STENCIL_OP_INCR,
+        StencilOp::DecrementWrap => D3D11_STENCIL_OP_DECR,
+    }
 }
 
 impl CxOsDrawShader {
@@ -1278,6 +1429,50 @@ impl CxOsDrawShader {
         let mut const_table_uniforms = D3d11Buffer::default();
         const_table_uniforms.update_with_f32_constant_data(d3d11_cx, mapping.const_table.table.as_ref());
         
+        let flags = mapping.flags;
+        
+        let mut blend_desc: D3D11_BLEND_DESC = Default::default();
+        blend_desc.AlphaToCoverageEnable = FALSE;
+        let (src, dst, op) = flags.blend.factors().unwrap_or((BlendFactor::One, BlendFactor::Zero, BlendOp::Add));
+        blend_desc.RenderTarget[0] = D3D11_RENDER_TARGET_BLEND_DESC {
+            BlendEnable: if flags.blend.factors().is_some() {TRUE} else {FALSE},
+            SrcBlend: blend_factor_to_dx11(src),
+            SrcBlendAlpha: alpha_blend_factor_to_dx11(src),
+            DestBlend: blend_factor_to_dx11(dst),
+            DestBlendAlpha: alpha_blend_factor_to_dx11(dst),
+            BlendOp: blend_op_to_dx11(op),
+            BlendOpAlpha: blend_op_to_dx11(op),
+            RenderTargetWriteMask: D3D11_COLOR_WRITE_ENABLE_ALL.0 as u8,
+        };
+        let mut blend_state = None;
+        if let Err(err) = unsafe {d3d11_cx.device.CreateBlendState(&blend_desc, Some(&mut blend_state))} {
+            println!("Cannot create blend state for {:?}: {}", flags.blend, err);
+            return None
+        }
+        
+        let stencil = &flags.stencil;
+        let stencil_op_desc = D3D11_DEPTH_STENCILOP_DESC {
+            StencilFailOp: stencil_op_to_dx11(stencil.fail),
+            StencilDepthFailOp: stencil_op_to_dx11(stencil.depth_fail),
+            StencilPassOp: stencil_op_to_dx11(stencil.pass),
+            StencilFunc: compare_func_to_dx11(stencil.test),
+        };
+        let ds_desc = D3D11_DEPTH_STENCIL_DESC {
+            DepthEnable: TRUE,
+            DepthWriteMask: if flags.depth.write {D3D11_DEPTH_WRITE_MASK_ALL} else {D3D11_DEPTH_WRITE_MASK_ZERO},
+            DepthFunc: compare_func_to_dx11(flags.depth.test),
+            StencilEnable: if stencil.is_enabled() {TRUE} else {FALSE},
+            StencilReadMask: stencil.read_mask,
+            StencilWriteMask: stencil.write_mask,
+            FrontFace: stencil_op_desc,
+            BackFace: stencil_op_desc,
+        };
+        let mut depth_stencil_state = None;
+        if let Err(err) = unsafe {d3d11_cx.device.CreateDepthStencilState(&ds_desc, Some(&mut depth_stencil_state))} {
+            println!("Cannot create depth stencil state: {}", err);
+            return None
+        }
+        
         Some(Self {
             hlsl,
             const_table_uniforms,
@@ -1286,7 +1481,10 @@ impl CxOsDrawShader {
             vertex_shader: vs.unwrap(),
             pixel_shader_blob: ps_blob,
             vertex_shader_blob: vs_blob,
-            input_layout: input_layout.unwrap()
+            input_layout: input_layout.unwrap(),
+            blend_state: blend_state.unwrap(),
+            depth_stencil_state: depth_stencil_state.unwrap(),
+            flags,
         })
     }
 }
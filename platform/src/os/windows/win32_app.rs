@@ -81,6 +81,11 @@ use {
                 },
                 System::{
                     Threading::ExitProcess,
+                    Registry::{
+                        RegGetValueW,
+                        HKEY_CURRENT_USER,
+                        RRF_RT_REG_DWORD,
+                    },
                     LibraryLoader::{
                         GetModuleHandleW,
                         LoadLibraryA,
@@ -158,6 +163,8 @@ pub struct Win32App {
     pub currently_clicked_window_id: Option<WindowId >,
     pub start_dragging_items: Option<Vec<DragItem >>,
     pub is_dragging_internal: Cell<bool>,
+    /// the theme the last `ThemeChange` went out for
+    pub dark_mode: bool,
 }
 
 #[derive(Clone)]
@@ -221,6 +228,7 @@ impl Win32App {
             current_cursor: None,
             currently_clicked_window_id: None,
             is_dragging_internal: Cell::new(false),
+            dark_mode: Self::is_dark_mode(),
         };
         win32_app.dpi_functions.become_dpi_aware();
         
@@ -462,6 +470,26 @@ impl Win32App {
         self.start_dragging_items = Some(items);
     }
     
+    /// Whether apps are set to the dark theme, which is what the
+    /// `ImmersiveColorSet` setting change is about.
+    pub fn is_dark_mode() -> bool {
+        let key = encode_wide("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize");
+        let value = encode_wide("AppsUseLightTheme");
+        let mut light = 1u32;
+        let mut size = mem::size_of::<u32>() as u32;
+        let result = unsafe {RegGetValueW(
+            HKEY_CURRENT_USER,
+            PCWSTR(key.as_ptr()),
+            PCWSTR(value.as_ptr()),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut light as *mut u32 as *mut _),
+            Some(&mut size as *mut u32),
+        )};
+        // without the value windows is in its light theme
+        result.0 == 0 && light == 0
+    }
+    
     pub fn time_now(&self) -> f64 {
         unsafe {
             let mut time_now = 0i64;
@@ -11,6 +11,7 @@ use {
             WindowDragQueryEvent,
             WindowCloseRequestedEvent,
             WindowClosedEvent,
+            ThemeChangeEvent,
             TextInputEvent,
             KeyEvent,
             DragEvent,
@@ -29,6 +30,7 @@ pub enum Win32Event {
     WindowResizeLoopStop(WindowId),
     WindowGeomChange(WindowGeomChangeEvent),
     WindowClosed(WindowClosedEvent),
+    ThemeChange(ThemeChangeEvent),
     Paint,
     
     MouseDown(MouseDownEvent),
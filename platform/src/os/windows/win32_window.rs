@@ -123,6 +123,7 @@ use {
                         WM_SIZE,
                         WM_DPICHANGED,
                         WM_DESTROY,
+                        WM_SETTINGCHANGE,
                         HTTOPLEFT,
                         HTBOTTOMLEFT,
                         HTLEFT,
@@ -631,6 +632,16 @@ impl Win32Window {
             WM_SIZE | WM_DPICHANGED => {
                 window.send_change_event();
             },
+            WM_SETTINGCHANGE => {
+                // every top level window gets this, so only the first one to see a new theme sends it
+                if lparam.0 != 0 && PCWSTR(lparam.0 as *const u16).to_string().is_ok_and( | s | s == "ImmersiveColorSet") {
+                    let dark_mode = Win32App::is_dark_mode();
+                    if get_win32_app_global().dark_mode != dark_mode {
+                        get_win32_app_global().dark_mode = dark_mode;
+                        window.do_callback(Win32Event::ThemeChange(ThemeChangeEvent {dark_mode}));
+                    }
+                }
+            },
             WM_CLOSE => { // close requested
                 let accept_close = Rc::new(Cell::new(true));
                 window.do_callback(Win32Event::WindowCloseRequested(WindowCloseRequestedEvent {
@@ -62,6 +62,8 @@ impl Cx {
         }));
         get_win32_app_global().start_timer(0, 0.008, true);
         cx.borrow_mut().call_event_handler(&Event::Startup);
+        let dark_mode = get_win32_app_global().dark_mode;
+        cx.borrow_mut().call_event_handler(&Event::ThemeChange(ThemeChangeEvent {dark_mode}));
         cx.borrow_mut().redraw_all();
         get_win32_app_global().start_signal_poll();
         Win32App::event_loop();
@@ -110,6 +112,10 @@ impl Cx {
             Win32Event::AppLostFocus => {
                 self.call_event_handler(&Event::AppLostFocus);
             }
+            Win32Event::ThemeChange(e) => {
+                self.call_event_handler(&Event::ThemeChange(e));
+                self.redraw_all();
+            }
             Win32Event::WindowResizeLoopStart(window_id) => {
                 if let Some(window) = d3d11_windows.iter_mut().find( | w | w.window_id == window_id) {
                     window.start_resize();
@@ -258,7 +264,8 @@ impl Cx {
     }
     
     pub (crate) fn handle_repaint(&mut self, d3d11_windows: &mut Vec<D3d11Window>, d3d11_cx: &mut D3d11Cx) {
-        
+        // readbacks aren't supported on this backend, they were completed with an error on request
+        self.call_readback_events();
         let mut passes_todo = Vec::new();
         self.compute_pass_repaint_order(&mut passes_todo);
         self.repaint_id += 1;
@@ -18,7 +18,8 @@ use crate::{
     live_traits::*,
     texture::{
         Texture,
-    }
+    },
+    event::{ReadbackId, CxReadbackSource},
 };
 
 #[derive(Debug)]
@@ -202,6 +203,12 @@ impl Pass {
         cxpass.debug = debug;
     }
     
+    /// Reads back what the pass renders on its next repaint, the pixels arrive as an `Event::Readback`
+    pub fn read_back(&self, cx: &mut Cx) -> ReadbackId {
+        cx.repaint_pass(self.pass_id());
+        cx.readbacks.request(CxReadbackSource::Pass(self.pass_id()))
+    }
+    
 }
 
 #[derive(Clone)]
@@ -4,6 +4,7 @@ use {
         cx::Cx,
         makepad_math::*,
         os::CxOsTexture,
+        event::{ReadbackId, CxReadbackSource},
     },
     std::rc::Rc,
 };
@@ -106,6 +107,11 @@ pub enum TextureFormat {
     VecRu8{width:usize, height:usize, data:Option<Vec<u8>>, unpack_row_length:Option<usize>, updated: TextureUpdated},
     VecRGu8{width:usize, height:usize, data:Option<Vec<u8>>, unpack_row_length:Option<usize>, updated: TextureUpdated},
     VecRf32{width:usize, height:usize, data:Option<Vec<f32>>, updated: TextureUpdated},
+    /// Six faces of `width * height` pixels, in +X, -X, +Y, -Y, +Z, -Z order.
+    VecCubeBGRAu8_32{width:usize, height:usize, data:Option<Vec<u32>>, updated: TextureUpdated},
+    VecArrayBGRAu8_32{width:usize, height:usize, layers:usize, data:Option<Vec<u32>>, updated: TextureUpdated},
+    Vec3dBGRAu8_32{width:usize, height:usize, depth:usize, data:Option<Vec<u32>>, updated: TextureUpdated},
+    Vec3dRu8{width:usize, height:usize, depth:usize, data:Option<Vec<u8>>, updated: TextureUpdated},
     DepthD32{size:TextureSize, initial: bool},
     RenderBGRAu8{size:TextureSize, initial: bool},
     RenderRGBAf16{size:TextureSize, initial: bool},
@@ -121,6 +127,16 @@ pub(crate) struct TextureAlloc{
     pub pixel: TexturePixel,
     pub width: usize,
     pub height: usize,
+    pub depth: usize,
+}
+
+/// The shape of a texture as seen by a shader, 2D textures being everything but the `Vec` cube, array and 3D formats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextureDimension {
+    D2,
+    Cube,
+    D2Array,
+    D3,
 }
 
 #[allow(unused)]    
@@ -195,6 +211,10 @@ impl CxTexture{
             TextureFormat::VecRu8 { updated, .. } => updated,
             TextureFormat::VecRGu8 { updated, .. } => updated,
             TextureFormat::VecRf32 { updated, .. } => updated,
+            TextureFormat::VecCubeBGRAu8_32 { updated, .. } => updated,
+            TextureFormat::VecArrayBGRAu8_32 { updated, .. } => updated,
+            TextureFormat::Vec3dBGRAu8_32 { updated, .. } => updated,
+            TextureFormat::Vec3dRu8 { updated, .. } => updated,
             _ => panic!(),
         }
     }
@@ -218,6 +238,10 @@ impl CxTexture{
             TextureFormat::VecRu8 { updated, .. } => updated,
             TextureFormat::VecRGu8 { updated, .. } => updated,
             TextureFormat::VecRf32 { updated, .. } => updated,
+            TextureFormat::VecCubeBGRAu8_32 { updated, .. } => updated,
+            TextureFormat::VecArrayBGRAu8_32 { updated, .. } => updated,
+            TextureFormat::Vec3dBGRAu8_32 { updated, .. } => updated,
+            TextureFormat::Vec3dRu8 { updated, .. } => updated,
             _ => panic!(),
         } = updated;
     }
@@ -314,6 +338,10 @@ impl TextureFormat{
             Self::VecRu8{..}=>true,
             Self::VecRGu8{..}=>true,
             Self::VecRf32{..}=>true,
+            Self::VecCubeBGRAu8_32{..}=>true,
+            Self::VecArrayBGRAu8_32{..}=>true,
+            Self::Vec3dBGRAu8_32{..}=>true,
+            Self::Vec3dRu8{..}=>true,
             _=>false
         }
     }
@@ -342,6 +370,16 @@ impl TextureFormat{
         false
     }
 
+    pub fn dimension(&self)->TextureDimension{
+        match self{
+            Self::VecCubeBGRAu8_32{..}=>TextureDimension::Cube,
+            Self::VecArrayBGRAu8_32{..}=>TextureDimension::D2Array,
+            Self::Vec3dBGRAu8_32{..}=>TextureDimension::D3,
+            Self::Vec3dRu8{..}=>TextureDimension::D3,
+            _=>TextureDimension::D2
+        }
+    }
+
     pub fn vec_width_height(&self)->Option<(usize,usize)>{
         match self{
             Self::VecBGRAu8_32{width, height, .. }=>Some((*width,*height)),
@@ -350,6 +388,10 @@ impl TextureFormat{
             Self::VecRu8{width, height, ..}=>Some((*width,*height)),
             Self::VecRGu8{width, height, ..}=>Some((*width,*height)),
             Self::VecRf32{width, height,..}=>Some((*width,*height)),
+            Self::VecCubeBGRAu8_32{width, height,..}=>Some((*width,*height)),
+            Self::VecArrayBGRAu8_32{width, height,..}=>Some((*width,*height)),
+            Self::Vec3dBGRAu8_32{width, height,..}=>Some((*width,*height)),
+            Self::Vec3dRu8{width, height,..}=>Some((*width,*height)),
             _=>None
         }
     }
@@ -359,39 +401,73 @@ impl TextureFormat{
             Self::VecBGRAu8_32{width,height,..}=>Some(TextureAlloc{
                 width:*width,
                 height:*height,
+                depth:1,
                 pixel:TexturePixel::BGRAu8,
                 category: TextureCategory::Vec,
             }),
             Self::VecMipBGRAu8_32{width,height,..}=>Some(TextureAlloc{
                 width:*width,
                 height:*height,
+                depth:1,
                 pixel:TexturePixel::BGRAu8,
                 category: TextureCategory::Vec,
             }),
             Self::VecRGBAf32{width,height,..}=>Some(TextureAlloc{
                 width:*width,
                 height:*height,
+                depth:1,
                 pixel:TexturePixel::RGBAf32,
                 category: TextureCategory::Vec,
             }),
             Self::VecRu8{width,height,..}=>Some(TextureAlloc{
                 width:*width,
                 height:*height,
+                depth:1,
                 pixel:TexturePixel::Ru8,
                 category: TextureCategory::Vec,
             }),
             Self::VecRGu8{width,height,..}=>Some(TextureAlloc{
                 width:*width,
                 height:*height,
+                depth:1,
                 pixel:TexturePixel::RGu8,
                 category: TextureCategory::Vec,
             }),
             Self::VecRf32{width,height,..}=>Some(TextureAlloc{
                 width:*width,
                 height:*height,
+                depth:1,
                 pixel:TexturePixel::Rf32,
                 category: TextureCategory::Vec,
             }),
+            Self::VecCubeBGRAu8_32{width,height,..}=>Some(TextureAlloc{
+                width:*width,
+                height:*height,
+                depth:6,
+                pixel:TexturePixel::BGRAu8,
+                category: TextureCategory::Vec,
+            }),
+            Self::VecArrayBGRAu8_32{width,height,layers,..}=>Some(TextureAlloc{
+                width:*width,
+                height:*height,
+                depth:*layers,
+                pixel:TexturePixel::BGRAu8,
+                category: TextureCategory::Vec,
+            }),
+            Self::Vec3dBGRAu8_32{width,height,depth,..}=>Some(TextureAlloc{
+                width:*width,
+                height:*height,
+                depth:*depth,
+                pixel:TexturePixel::BGRAu8,
+                category: TextureCategory::Vec,
+            }),
+            Self::Vec3dRu8{width,height,depth,..}=>Some(TextureAlloc{
+                width:*width,
+                height:*height,
+                depth:*depth,
+                pixel:TexturePixel::Ru8,
+                category: TextureCategory::Vec,
+            }),
             _=>None
         }
     }
@@ -403,6 +479,7 @@ impl TextureFormat{
                 Some(TextureAlloc{
                     width,
                     height,
+                    depth:1,
                     pixel:TexturePixel::BGRAu8,
                     category: TextureCategory::Render,
                 })
@@ -412,6 +489,7 @@ impl TextureFormat{
                 Some(TextureAlloc{
                     width,
                     height,
+                    depth:1,
                     pixel:TexturePixel::RGBAf16,
                     category: TextureCategory::Render,
                 })
@@ -421,6 +499,7 @@ impl TextureFormat{
                 Some(TextureAlloc{
                     width,
                     height,
+                    depth:1,
                     pixel:TexturePixel::RGBAf32,
                     category: TextureCategory::Render,
                 })
@@ -436,6 +515,7 @@ impl TextureFormat{
                 Some(TextureAlloc{
                     width,
                     height,
+                    depth:1,
                     pixel:TexturePixel::D32,
                     category: TextureCategory::DepthBuffer,
                 })
@@ -452,6 +532,7 @@ impl TextureFormat{
                 Some(TextureAlloc{
                     width: 0,
                     height: 0,
+                    depth: 1,
                     pixel:TexturePixel::VideoRGB,
                     category: TextureCategory::Video,
                 })
@@ -467,6 +548,7 @@ impl TextureFormat{
                 Some(TextureAlloc{
                     width:*width,
                     height:*height,
+                    depth:1,
                     pixel:TexturePixel::BGRAu8,
                     category: TextureCategory::Shared,
                 })
@@ -505,10 +587,18 @@ impl Texture {
         &mut cx.textures[self.texture_id()].format
     }
 
+    /// Reads the texture back from the GPU after the next repaint, the pixels arrive as an `Event::Readback`
+    pub fn read_back(&self, cx: &mut Cx) -> ReadbackId {
+        cx.readbacks.request(CxReadbackSource::Texture(self.clone()))
+    }
+
     pub fn take_vec_u32(&self, cx: &mut Cx) -> Vec<u32> {
         let cx_texture = &mut cx.textures[self.texture_id()];
         let data = match &mut cx_texture.format {
             TextureFormat::VecBGRAu8_32 { data, .. } => data,
+            TextureFormat::VecCubeBGRAu8_32 { data, .. } => data,
+            TextureFormat::VecArrayBGRAu8_32 { data, .. } => data,
+            TextureFormat::Vec3dBGRAu8_32 { data, .. } => data,
             _ => panic!("incorrect texture format for u32 image data"),
         };
         data.take().expect("image data already taken")
@@ -518,6 +608,9 @@ impl Texture {
         let cx_texture = &mut cx.textures[self.texture_id()];
         let (data, updated) = match &mut cx_texture.format {
             TextureFormat::VecBGRAu8_32 { data, updated, .. } => (data, updated),
+            TextureFormat::VecCubeBGRAu8_32 { data, updated, .. } => (data, updated),
+            TextureFormat::VecArrayBGRAu8_32 { data, updated, .. } => (data, updated),
+            TextureFormat::Vec3dBGRAu8_32 { data, updated, .. } => (data, updated),
             _ => panic!("incorrect texture format for u32 image data"),
         };
         assert!(data.is_none(), "image data not taken or already put back");
@@ -530,6 +623,7 @@ impl Texture {
         let data = match &mut cx_texture.format {
             TextureFormat::VecRu8 { data, .. } => data,
             TextureFormat::VecRGu8 { data, .. } => data,
+            TextureFormat::Vec3dRu8 { data, .. } => data,
             _ => panic!("incorrect texture format for u32 image data"),
         };
         data.take().expect("image data already taken")
@@ -540,6 +634,7 @@ impl Texture {
         let (data, updated) = match &mut cx_texture.format {
             TextureFormat::VecRu8 { data, updated, .. } => (data, updated),
             TextureFormat::VecRGu8 { data,updated, .. } => (data, updated),
+            TextureFormat::Vec3dRu8 { data, updated, .. } => (data, updated),
             _ => panic!("incorrect texture format for u8 image data"),
         };
         assert!(data.is_none(), "image data not taken or already put back");
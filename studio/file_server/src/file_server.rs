@@ -1,5 +1,6 @@
 use {
     crate::{
+        file_watcher::FileWatcher,
         makepad_file_protocol::{
             DirectoryEntry,
             FileNodeData,
@@ -14,11 +15,9 @@ use {
         },
     },
     std::{
-        thread,
         cmp::Ordering,
         fmt,
         fs,
-        time::Duration,
         path::{Path, PathBuf},
         sync::{Arc, RwLock, Mutex},
     },
@@ -54,7 +53,7 @@ impl FileServer {
             shared: self.shared.clone(),
             _notification_sender: notification_sender,
             open_files: Default::default(),
-            stop_observation: Default::default()
+            watcher: Default::default()
         }
     }
 }
@@ -68,7 +67,9 @@ pub struct FileServerConnection {
     // Used to send notifications for this connection.
     _notification_sender: Box<dyn NotificationSender>,
     open_files: Arc<Mutex<Vec<(String, u64, Vec<u8>)>>>,
-    stop_observation: Arc<Mutex<bool>>,
+    // Watches the open files for changes made by other processes. Created when the first file is
+    // opened.
+    watcher: Mutex<Option<FileWatcher>>,
 }
 
 impl FileServerConnection {
@@ -173,45 +174,41 @@ impl FileServerConnection {
         path
     }
     
-    fn start_observation(&self) {
-        let open_files = self.open_files.clone();
-        let shared = self.shared.clone();
-        let notification_sender = self._notification_sender.clone();
-        let stop_observation = self.stop_observation.clone();
-        
-        thread::spawn(move || {
-            let stop = *stop_observation.lock().unwrap();
-            while !stop{
+    fn watch_file(&self, child_path: &String) {
+        let mut watcher = self.watcher.lock().unwrap();
+        let watcher = watcher.get_or_insert_with(|| {
+            let open_files = self.open_files.clone();
+            let shared = self.shared.clone();
+            let notification_sender = self._notification_sender.clone();
+            FileWatcher::new(Box::new(move |full_path| {
+                let root_path = shared.read().unwrap().root_path.clone();
                 let mut files = open_files.lock().unwrap();
                 for (path, file_id, last_content) in files.iter_mut() {
-                    let full_path = {
-                        let shared = shared.read().unwrap();
-                        shared.root_path.join(&path)
-                    };
-                    if let Ok(bytes) = fs::read(&full_path) {
+                    if root_path.join(&path) != full_path {
+                        continue;
+                    }
+                    // Our own saves update `last_content` before writing the file, so they end
+                    // up here as well, but are filtered out by the comparison.
+                    if let Ok(bytes) = fs::read(full_path) {
                         if bytes.len() > 0 && bytes != *last_content {
                             let new_data = String::from_utf8_lossy(&bytes);
                             let old_data = String::from_utf8_lossy(&last_content);
-                            // Send notification of external file change.
-                            notification_sender
-                            .send_notification(FileNotification::FileChangedOnDisk(
-                                SaveFileResponse{
+                            notification_sender.send_notification(
+                                FileNotification::FileChangedOnDisk(SaveFileResponse {
                                     path: path.to_string(),
                                     new_data: new_data.to_string(),
                                     old_data: old_data.to_string(),
                                     kind: SaveKind::Observation,
-                                    id: *file_id
-                                }
-                            ));
+                                    id: *file_id,
+                                }),
+                            );
                             *last_content = bytes;
                         }
                     }
                 }
-            }
-                
-            // Sleep for 500ms.
-            thread::sleep(Duration::from_millis(100));
+            }))
         });
+        watcher.watch(&self.make_full_path(child_path));
     }
     
     // Handles an `OpenFile` request.
@@ -227,10 +224,8 @@ impl FileServerConnection {
         if open_files.iter().find(|(cp,_,_)| *cp == child_path).is_none(){
             open_files.push((child_path.clone(), id, bytes.clone()));
         }
-        
-        if open_files.len() == 1 {
-            self.start_observation();
-        }
+        drop(open_files);
+        self.watch_file(&child_path);
         // Converts the file contents to a `Text`. This is necessarily a lossy conversion
         // because `Text` assumes everything is UTF-8 encoded, and this isn't always the
         // case for files on disk (is this a problem?)
@@ -263,6 +258,8 @@ impl FileServerConnection {
         else{
             open_files.push((child_path.clone(), id, new_data.as_bytes().to_vec()));
         }
+        drop(open_files);
+        self.watch_file(&child_path);
         
         let path = self.make_full_path(&child_path);
         
@@ -0,0 +1,324 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Watches files for changes made by other processes, such as `cargo fmt`, a git checkout, or
+/// another editor.
+///
+/// On Linux this uses inotify on the directories that contain the watched files, because tools
+/// that save atomically replace a file by renaming another file over it, after which a watch on
+/// the file itself would no longer fire. Other platforms poll the modification times instead.
+///
+/// Dropping the watcher stops its background thread and waits for it to finish.
+pub struct FileWatcher {
+    inner: imp::FileWatcher,
+}
+
+impl FileWatcher {
+    /// Creates a new file watcher. `on_change` is called from a background thread with the path of
+    /// each watched file that may have changed.
+    pub fn new(on_change: Box<dyn FnMut(&Path) + Send>) -> Self {
+        Self {
+            inner: imp::FileWatcher::new(Arc::new(Mutex::new(on_change))),
+        }
+    }
+
+    pub fn watch(&self, path: &Path) {
+        self.inner.watch(path);
+    }
+
+    pub fn unwatch(&self, path: &Path) {
+        self.inner.unwatch(path);
+    }
+}
+
+type OnChange = Arc<Mutex<Box<dyn FnMut(&Path) + Send>>>;
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use {
+        super::OnChange,
+        std::{
+            collections::{HashMap, HashSet},
+            ffi::{CString, OsStr},
+            os::{
+                raw::{c_char, c_int, c_ulong, c_void},
+                unix::ffi::OsStrExt,
+            },
+            path::{Path, PathBuf},
+            sync::{Arc, Mutex},
+            thread::{self, JoinHandle},
+        },
+    };
+
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const O_CLOEXEC: c_int = 0o2000000;
+    const IN_CLOSE_WRITE: u32 = 0x0000_0008;
+    const IN_MOVED_TO: u32 = 0x0000_0080;
+    const IN_CREATE: u32 = 0x0000_0100;
+    const POLLIN: i16 = 0x0001;
+    const EINTR: c_int = 4;
+    // The size of `struct inotify_event` without the name that follows it.
+    const EVENT_HEADER_SIZE: usize = 16;
+
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: i16,
+        revents: i16,
+    }
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+        fn inotify_rm_watch(fd: c_int, wd: c_int) -> c_int;
+        fn pipe2(fds: *mut c_int, flags: c_int) -> c_int;
+        fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+        fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize;
+        fn close(fd: c_int) -> c_int;
+        fn __errno_location() -> *mut c_int;
+    }
+
+    #[derive(Default)]
+    struct Watches {
+        // The watched files in each watched directory, by watch descriptor.
+        dirs: HashMap<c_int, (PathBuf, HashSet<PathBuf>)>,
+    }
+
+    pub struct FileWatcher {
+        fd: c_int,
+        watches: Arc<Mutex<Watches>>,
+        // The write end of a pipe the thread also polls, closed when the watcher is dropped.
+        shutdown_fd: c_int,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl FileWatcher {
+        pub fn new(on_change: OnChange) -> Self {
+            let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+            let watches = Arc::new(Mutex::new(Watches::default()));
+            let mut shutdown_fds = [-1; 2];
+            if fd < 0 || unsafe { pipe2(shutdown_fds.as_mut_ptr(), O_CLOEXEC) } < 0 {
+                return Self {
+                    fd,
+                    watches,
+                    shutdown_fd: -1,
+                    thread: None,
+                };
+            }
+            let [shutdown_read_fd, shutdown_fd] = shutdown_fds;
+            let thread = thread::spawn({
+                let watches = watches.clone();
+                move || {
+                    let mut buf = vec![0u8; 64 * 1024];
+                    loop {
+                        let mut poll_fds = [
+                            PollFd {
+                                fd,
+                                events: POLLIN,
+                                revents: 0,
+                            },
+                            PollFd {
+                                fd: shutdown_read_fd,
+                                events: POLLIN,
+                                revents: 0,
+                            },
+                        ];
+                        if unsafe { poll(poll_fds.as_mut_ptr(), 2, -1) } < 0 {
+                            if unsafe { *__errno_location() } == EINTR {
+                                continue;
+                            }
+                            break;
+                        }
+                        if poll_fds[1].revents != 0 {
+                            break;
+                        }
+                        let len = unsafe { read(fd, buf.as_mut_ptr() as *mut c_void, buf.len()) };
+                        if len <= 0 {
+                            break;
+                        }
+                        let mut changed_paths = Vec::new();
+                        let watches = watches.lock().unwrap();
+                        let mut offset = 0;
+                        while offset + EVENT_HEADER_SIZE <= len as usize {
+                            let header = &buf[offset..offset + EVENT_HEADER_SIZE];
+                            let wd = c_int::from_ne_bytes(header[0..4].try_into().unwrap());
+                            let name_len =
+                                u32::from_ne_bytes(header[12..16].try_into().unwrap()) as usize;
+                            let name_start = offset + EVENT_HEADER_SIZE;
+                            let name = &buf[name_start..name_start + name_len];
+                            // The name is padded with nul bytes.
+                            let name =
+                                &name[..name.iter().position(|&b| b == 0).unwrap_or(name_len)];
+                            if let Some((dir, files)) = watches.dirs.get(&wd) {
+                                let path = dir.join(OsStr::from_bytes(name));
+                                if files.contains(&path) && !changed_paths.contains(&path) {
+                                    changed_paths.push(path);
+                                }
+                            }
+                            offset = name_start + name_len;
+                        }
+                        drop(watches);
+                        let mut on_change = on_change.lock().unwrap();
+                        for path in changed_paths {
+                            on_change(&path);
+                        }
+                    }
+                    unsafe { close(shutdown_read_fd) };
+                }
+            });
+            Self {
+                fd,
+                watches,
+                shutdown_fd,
+                thread: Some(thread),
+            }
+        }
+
+        pub fn watch(&self, path: &Path) {
+            let Some(dir) = path.parent() else {
+                return;
+            };
+            let mut watches = self.watches.lock().unwrap();
+            if let Some((_, files)) = watches.dirs.values_mut().find(|(d, _)| d == dir) {
+                files.insert(path.to_path_buf());
+                return;
+            }
+            if self.fd < 0 {
+                return;
+            }
+            let Ok(dir_cstr) = CString::new(dir.as_os_str().as_bytes()) else {
+                return;
+            };
+            let wd = unsafe {
+                inotify_add_watch(
+                    self.fd,
+                    dir_cstr.as_ptr(),
+                    IN_CLOSE_WRITE | IN_MOVED_TO | IN_CREATE,
+                )
+            };
+            if wd >= 0 {
+                let mut files = HashSet::new();
+                files.insert(path.to_path_buf());
+                watches.dirs.insert(wd, (dir.to_path_buf(), files));
+            }
+        }
+
+        pub fn unwatch(&self, path: &Path) {
+            let mut watches = self.watches.lock().unwrap();
+            let Some(wd) = watches
+                .dirs
+                .iter_mut()
+                .find_map(|(wd, (_, files))| files.remove(path).then_some(*wd))
+            else {
+                return;
+            };
+            if watches.dirs[&wd].1.is_empty() {
+                watches.dirs.remove(&wd);
+                unsafe { inotify_rm_watch(self.fd, wd) };
+            }
+        }
+    }
+
+    impl Drop for FileWatcher {
+        fn drop(&mut self) {
+            if let Some(thread) = self.thread.take() {
+                // The read end of the pipe hangs up, which wakes the thread.
+                unsafe { close(self.shutdown_fd) };
+                let _ = thread.join();
+            }
+            // Closing the inotify descriptor removes all of its watches.
+            if self.fd >= 0 {
+                unsafe { close(self.fd) };
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use {
+        super::OnChange,
+        std::{
+            collections::HashMap,
+            fs,
+            path::{Path, PathBuf},
+            sync::{
+                mpsc::{self, RecvTimeoutError, Sender},
+                Arc, Mutex,
+            },
+            thread::{self, JoinHandle},
+            time::{Duration, SystemTime},
+        },
+    };
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub struct FileWatcher {
+        // The last seen modification time of each watched file.
+        files: Arc<Mutex<HashMap<PathBuf, Option<SystemTime>>>>,
+        // Dropped to stop the thread, which then no longer times out waiting for a message.
+        shutdown_sender: Option<Sender<()>>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl FileWatcher {
+        pub fn new(on_change: OnChange) -> Self {
+            let files: Arc<Mutex<HashMap<PathBuf, Option<SystemTime>>>> = Default::default();
+            let (shutdown_sender, shutdown_receiver) = mpsc::channel::<()>();
+            let thread = thread::spawn({
+                let files = files.clone();
+                move || {
+                    while let Err(RecvTimeoutError::Timeout) =
+                        shutdown_receiver.recv_timeout(POLL_INTERVAL)
+                    {
+                        let mut changed_paths = Vec::new();
+                        for (path, last_modified) in files.lock().unwrap().iter_mut() {
+                            let modified = modified(path);
+                            if modified != *last_modified {
+                                *last_modified = modified;
+                                changed_paths.push(path.clone());
+                            }
+                        }
+                        let mut on_change = on_change.lock().unwrap();
+                        for path in changed_paths {
+                            on_change(&path);
+                        }
+                    }
+                }
+            });
+            Self {
+                files,
+                shutdown_sender: Some(shutdown_sender),
+                thread: Some(thread),
+            }
+        }
+
+        pub fn watch(&self, path: &Path) {
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), modified(path));
+        }
+
+        pub fn unwatch(&self, path: &Path) {
+            self.files.lock().unwrap().remove(path);
+        }
+    }
+
+    impl Drop for FileWatcher {
+        fn drop(&mut self) {
+            self.shutdown_sender.take();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+}
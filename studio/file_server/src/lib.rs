@@ -2,6 +2,8 @@
 #[cfg(not(target_arch = "wasm32"))]
 pub mod file_server;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod file_watcher;
+#[cfg(not(target_arch = "wasm32"))]
 pub use file_server::*;
 
 pub use makepad_micro_serde;
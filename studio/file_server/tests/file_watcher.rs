@@ -0,0 +1,47 @@
+use {
+    makepad_file_server::file_watcher::FileWatcher,
+    std::{
+        fs,
+        path::PathBuf,
+        sync::mpsc::{self, RecvTimeoutError},
+        time::Duration,
+    },
+};
+
+// Long enough for the watcher on platforms that poll.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn reports_changes_until_dropped() {
+    let dir = std::env::temp_dir().join(format!("makepad_file_watcher_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("watched.rs");
+    fs::write(&path, "fn main() {}").unwrap();
+
+    let (sender, receiver) = mpsc::channel::<PathBuf>();
+    let watcher = FileWatcher::new(Box::new(move |path| {
+        let _ = sender.send(path.to_path_buf());
+    }));
+    watcher.watch(&path);
+
+    // A write in place and an atomic save both count.
+    fs::write(&path, "fn main() { }").unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(path.clone()));
+    let temp_path = dir.join("watched.rs.tmp");
+    fs::write(&temp_path, "fn main() {  }").unwrap();
+    fs::rename(&temp_path, &path).unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(path.clone()));
+
+    // Dropping the watcher stops its thread, which drops the callback and its sender with it.
+    drop(watcher);
+    let error = loop {
+        match receiver.recv_timeout(TIMEOUT) {
+            Ok(_) => continue,
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error, RecvTimeoutError::Disconnected);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -3,6 +3,7 @@ use crate::{
     makepad_code_editor::selection::Affinity,
     makepad_code_editor::session::SelectionMode,
     makepad_code_editor::history::NewGroup,
+    makepad_code_editor::keymap::Keymap,
     makepad_widgets::*,
     makepad_micro_serde::*,
     makepad_widgets::file_tree::*,
@@ -13,6 +14,7 @@ use crate::{
     makepad_platform::studio::{JumpToFile,EditFile, PatchFile},
     run_list::*,
     log_list::*,
+    collab::CollabManager,
     makepad_code_editor::text::{Position},
     build_manager::{
         build_manager::{
@@ -24,6 +26,7 @@ use crate::{
 use std::fs::File;
 use std::io::Write;
 use std::env;
+use std::rc::Rc;
   
 live_design!{
     import crate::app_ui::*;
@@ -93,12 +96,25 @@ impl App {
             }
         }
     }
+    
+    // The keymap file adds to and overrides the default key bindings.
+    pub fn load_keymap(&mut self){
+        let mut keymap = Keymap::default();
+        if let Ok(contents) = std::fs::read_to_string("makepad_keymap.txt") {
+            if let Err(e) = keymap.load(&contents){
+                println!("ERR makepad_keymap.txt {}", e);
+            }
+        }
+        self.data.keymap = Rc::new(keymap);
+    }
 }
 
 #[derive(Default)]
 pub struct AppData{ 
     pub build_manager: BuildManager,
     pub file_system: FileSystem,
+    pub collab_manager: CollabManager,
+    pub keymap: Rc<Keymap>,
 }
 
 // all global app commands coming in from keybindings, and UI components
@@ -132,8 +148,10 @@ impl MatchEvent for App{
                 
         self.data.file_system.init(cx, &root_path);
         self.data.build_manager.init(cx, &root_path);
+        self.load_keymap();
         //self.data.build_manager.discover_external_ip(cx);
         self.data.build_manager.start_http_server();
+        self.data.collab_manager.init(cx);
         // lets load the tabs
         
     }
@@ -510,6 +528,7 @@ impl AppMain for App {
 
         // process events on all run_views
         let dock = self.ui.dock(id!(dock));
+        self.data.collab_manager.handle_event(cx, event, &mut self.data.file_system, &dock);
         /*
         if let Some(mut dock) = dock.borrow_mut() {
             for (id, (_, item)) in dock.items().iter() {
@@ -1,6 +1,7 @@
 use {
     crate::{
         app::AppAction,
+        collab::{CollabRelay, COLLAB_PATH_PREFIX},
         build_manager::{build_client::BuildClient, build_protocol::*},
         file_system::file_system::FileSystem,
         makepad_micro_serde::*,
@@ -589,6 +590,7 @@ impl BuildManager {
                 ("/".to_string(), "".to_string()),
             ];
             let mut socket_id_to_build_id = HashMap::new();
+            let mut collab_relay = CollabRelay::from_args();
             while let Ok(message) = rx_request.recv() {
                 // only store last change, fix later
                 match message {
@@ -597,7 +599,16 @@ impl BuildManager {
                         response_sender,
                         headers,
                     } => {
-                        if let Some(id) = headers.path.rsplit("/").next() {
+                        if let Some(room) = headers.path.strip_prefix(COLLAB_PATH_PREFIX) {
+                            if !collab_relay.connect(
+                                web_socket_id,
+                                room,
+                                headers.search.as_deref(),
+                                response_sender,
+                            ) {
+                                log!("Refused a collab web socket from {}", headers.addr);
+                            }
+                        } else if let Some(id) = headers.path.rsplit("/").next() {
                             if let Ok(id) = id.parse::<u64>() {
                                 socket_id_to_build_id.insert(web_socket_id, LiveId(id));
                                 active_build_websockets
@@ -609,6 +620,9 @@ impl BuildManager {
                         }
                     }
                     HttpServerRequest::DisconnectWebSocket { web_socket_id } => {
+                        if collab_relay.disconnect(web_socket_id) {
+                            continue;
+                        }
                         socket_id_to_build_id.remove(&web_socket_id);
                         active_build_websockets
                             .lock()
@@ -621,6 +635,9 @@ impl BuildManager {
                         response_sender: _,
                         data,
                     } => {
+                        if collab_relay.handle_binary_message(web_socket_id, &data) {
+                            continue;
+                        }
                         if let Some(id) = socket_id_to_build_id.get(&web_socket_id) {
                             if let Ok(msg) = AppToStudioVec::deserialize_bin(&data) {
                                 let _ = studio_sender.send((*id, msg));
@@ -0,0 +1,303 @@
+use {
+    crate::{
+        file_system::file_system::{FileSystem, OpenDoc},
+        makepad_code_editor::{
+            collab::{ClientMessage, Collab, CollabServer, ServerMessage, SiteId},
+            text::Text,
+        },
+        makepad_micro_serde::*,
+        makepad_widgets::*,
+    },
+    std::{
+        collections::{hash_map::RandomState, HashMap},
+        hash::{BuildHasher, Hasher},
+        sync::mpsc::Sender,
+        time::{Duration, Instant},
+    },
+};
+
+/// The path prefix of the web sockets that the collaboration relay serves. The rest of the path
+/// names the file being edited, relative to the root of the project.
+pub const COLLAB_PATH_PREFIX: &str = "/$collab/";
+
+/// Relays the messages between the peers editing a file together. Every Studio runs one as part
+/// of its http server, which hands it the web sockets under `COLLAB_PATH_PREFIX`.
+///
+/// The http server listens on every interface, so a web socket is only let into a room if its url
+/// carries the token of the relay as `?token=<token>`. The token is passed to Studio with
+/// `--collab-token=<token>`, or made up and logged at startup if it is not.
+pub struct CollabRelay {
+    token: String,
+    rooms: HashMap<String, CollabServer>,
+    web_sockets: HashMap<u64, CollabSocket>,
+}
+
+struct CollabSocket {
+    room: String,
+    site_id: SiteId,
+    sender: Sender<Vec<u8>>,
+}
+
+impl CollabRelay {
+    pub fn new(token: String) -> Self {
+        Self {
+            token,
+            rooms: HashMap::new(),
+            web_sockets: HashMap::new(),
+        }
+    }
+
+    /// Creates a relay with the token passed with `--collab-token=<token>`, or a random one.
+    pub fn from_args() -> Self {
+        let token = collab_token_arg().unwrap_or_else(|| {
+            let token = random_token();
+            log!("Collab relay token: {}", token);
+            token
+        });
+        Self::new(token)
+    }
+
+    /// Lets the web socket into `room` if `search`, the query of its url, has the token of the
+    /// relay. Otherwise the web socket is closed and false is returned.
+    pub fn connect(
+        &mut self,
+        web_socket_id: u64,
+        room: &str,
+        search: Option<&str>,
+        sender: Sender<Vec<u8>>,
+    ) -> bool {
+        let token = search
+            .and_then(|search| search.strip_prefix('?'))
+            .and_then(|search| {
+                search
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("token="))
+            });
+        if !token.is_some_and(|token| is_same_token(token, &self.token)) {
+            // An empty message makes the http server close the web socket.
+            let _ = sender.send(Vec::new());
+            return false;
+        }
+        let site_id = self.rooms.entry(room.to_string()).or_default().connect();
+        self.web_sockets.insert(
+            web_socket_id,
+            CollabSocket {
+                room: room.to_string(),
+                site_id,
+                sender,
+            },
+        );
+        true
+    }
+
+    /// Returns false if the web socket is not one of the relay.
+    pub fn disconnect(&mut self, web_socket_id: u64) -> bool {
+        let Some(socket) = self.web_sockets.remove(&web_socket_id) else {
+            return false;
+        };
+        let server = self.rooms.get_mut(&socket.room).unwrap();
+        let messages = server.disconnect(socket.site_id);
+        if server.is_empty() {
+            self.rooms.remove(&socket.room);
+        }
+        self.send(&socket.room, messages);
+        true
+    }
+
+    /// Returns false if the web socket is not one of the relay.
+    pub fn handle_binary_message(&mut self, web_socket_id: u64, data: &[u8]) -> bool {
+        let Some(socket) = self.web_sockets.get(&web_socket_id) else {
+            return false;
+        };
+        let room = socket.room.clone();
+        match ClientMessage::deserialize_bin(data) {
+            Ok(message) => {
+                let messages = self
+                    .rooms
+                    .get_mut(&room)
+                    .unwrap()
+                    .handle_message(socket.site_id, message);
+                self.send(&room, messages);
+            }
+            Err(err) => log!("Invalid collab message for {}: {}", room, err),
+        }
+        true
+    }
+
+    fn send(&self, room: &str, messages: Vec<(SiteId, ServerMessage)>) {
+        for (site_id, message) in messages {
+            if let Some(socket) = self
+                .web_sockets
+                .values()
+                .find(|socket| socket.room == room && socket.site_id == site_id)
+            {
+                let _ = socket.sender.send(message.serialize_bin());
+            }
+        }
+    }
+}
+
+fn collab_token_arg() -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix("--collab-token=").map(str::to_string))
+}
+
+fn random_token() -> String {
+    // Every `RandomState` is seeded with fresh random keys.
+    let random = || RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+// Compares every byte, so that how long the comparison takes says nothing about the token.
+fn is_same_token(token: &str, expected: &str) -> bool {
+    token.len() == expected.len()
+        && token
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Shares the open files with the other Studios connected to the same relay. Studio joins the
+/// relay of the Studio at `address` when it is started with `--collab=<address>`, for instance
+/// `--collab=192.168.1.10:8001`, together with `--collab-token=<token>` for the token of that
+/// relay.
+#[derive(Default)]
+pub struct CollabManager {
+    address: Option<String>,
+    token: String,
+    connections: HashMap<LiveId, CollabConnection>,
+    timer: Timer,
+}
+
+struct CollabConnection {
+    web_socket: WebSocket,
+    collab: Collab,
+    // When the web socket failed, if it did. The connection is opened again after
+    // `RECONNECT_DELAY`.
+    failed_at: Option<Instant>,
+}
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+impl CollabManager {
+    pub fn init(&mut self, cx: &mut Cx) {
+        for arg in std::env::args() {
+            if let Some(address) = arg.strip_prefix("--collab=") {
+                self.address = Some(address.to_string());
+                self.token = collab_token_arg().unwrap_or_default();
+                self.timer = cx.start_interval(0.016);
+                break;
+            }
+        }
+    }
+
+    pub fn handle_event(
+        &mut self,
+        cx: &mut Cx,
+        event: &Event,
+        file_system: &mut FileSystem,
+        dock: &DockRef,
+    ) {
+        if self.timer.is_event(event).is_none() {
+            return;
+        }
+        let Some(address) = &self.address else {
+            return;
+        };
+
+        // Stop sharing the files that are no longer open in a tab. Dropping the connection closes
+        // its web socket, which makes the relay tell the other peers that we left.
+        self.connections.retain(|file_id, _| {
+            matches!(
+                file_system.open_documents.get(file_id),
+                Some(OpenDoc::Document(_))
+            ) && file_system.file_node_id_to_tab_id(*file_id).is_some()
+        });
+
+        for (file_id, open_doc) in &file_system.open_documents {
+            let OpenDoc::Document(document) = open_doc else {
+                continue;
+            };
+            if file_system.file_node_id_to_tab_id(*file_id).is_none() {
+                continue;
+            }
+            // Edits made since the file was loaded are merged with the shared text on joining, so
+            // the text on disk is the base. After a failure, it is the text we were last in sync
+            // with instead, so that nothing is merged twice.
+            let base = match self.connections.get(file_id) {
+                Some(CollabConnection {
+                    failed_at: Some(failed_at),
+                    collab,
+                    ..
+                }) if failed_at.elapsed() >= RECONNECT_DELAY => collab.synced_text().clone(),
+                Some(_) => continue,
+                None => match file_system.disk_data.get(file_id) {
+                    Some(data) => Text::from(data.as_str()),
+                    None => document.as_text().clone(),
+                },
+            };
+            // The old connection stops sharing the document when it is dropped, so drop it
+            // before the new one starts.
+            self.connections.remove(file_id);
+            let path = file_system.file_node_path(*file_id);
+            let path = path.strip_prefix(&file_system.root_path).unwrap_or(&path);
+            let url = format!(
+                "http://{}{}{}?token={}",
+                address, COLLAB_PATH_PREFIX, path, self.token
+            );
+            self.connections.insert(
+                *file_id,
+                CollabConnection {
+                    web_socket: WebSocket::open(HttpRequest::new(url, HttpMethod::GET)),
+                    collab: Collab::new_with_base(document.clone(), base),
+                    failed_at: None,
+                },
+            );
+        }
+
+        // The sessions have to be up to date with the edits in their document, both before their
+        // selections are sent and after remote edits were applied.
+        file_system.handle_sessions();
+        for (file_id, connection) in &mut self.connections {
+            if connection.failed_at.is_some() {
+                continue;
+            }
+            let mut has_changed = false;
+            while let Ok(message) = connection.web_socket.try_recv() {
+                match message {
+                    WebSocketMessage::Binary(data) => match ServerMessage::deserialize_bin(&data) {
+                        Ok(message) => {
+                            connection.collab.handle_message(message);
+                            has_changed = true;
+                        }
+                        Err(err) => log!("Invalid collab message: {}", err),
+                    },
+                    WebSocketMessage::Error(err) => {
+                        log!("Collab connection error: {}", err);
+                        connection.failed_at = Some(Instant::now());
+                    }
+                    WebSocketMessage::Closed => {
+                        log!("Collab connection closed");
+                        connection.failed_at = Some(Instant::now());
+                    }
+                    WebSocketMessage::Opened | WebSocketMessage::String(_) => {}
+                }
+            }
+            if connection.failed_at.is_none() {
+                let selections = file_system
+                    .file_node_id_to_tab_id(*file_id)
+                    .and_then(|tab_id| file_system.tab_id_to_session.get(&tab_id))
+                    .map(|session| session.selections().to_vec())
+                    .unwrap_or_default();
+                connection.collab.update(&selections);
+                for message in connection.collab.take_messages() {
+                    let _ = connection.web_socket.send_binary(message.serialize_bin());
+                }
+            }
+            if has_changed {
+                file_system.handle_sessions();
+                file_system.redraw_view_by_file_id(cx, *file_id, dock);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use {
     std::collections::{HashMap, hash_map},
     std::path::Path,
     crate::{
-        makepad_code_editor::{Document, decoration::{Decoration, DecorationSet}, Session},
+        makepad_code_editor::{Document, decoration::{Decoration, DecorationSet}, language::language_for_path, merge::merge, Session},
         makepad_platform::makepad_live_compiler::LiveFileChange,
         makepad_widgets::*,
         makepad_widgets::file_tree::*,
@@ -29,7 +29,10 @@ pub struct FileSystem {
     pub path_to_file_node_id: HashMap<String, LiveId>,
     pub tab_id_to_file_node_id: HashMap<LiveId, LiveId>,
     pub tab_id_to_session: HashMap<LiveId, Session>,
-    pub open_documents: HashMap<LiveId, OpenDoc>
+    pub open_documents: HashMap<LiveId, OpenDoc>,
+    // The last contents of each open file that we know to be on disk, which is the base when
+    // merging changes made on disk with unsaved changes in the editor.
+    pub disk_data: HashMap<LiveId, String>,
 }
 
 pub enum OpenDoc {
@@ -130,7 +133,9 @@ impl FileSystem {
                                     }
                                     if let Some(OpenDoc::Decorations(dec)) = self.open_documents.get(&file_id) {
                                         let dec = dec.clone();
-                                        self.open_documents.insert(file_id, OpenDoc::Document(Document::new(response.data.into(), dec)));
+                                        let language = language_for_path(self.file_node_path(file_id));
+                                        self.disk_data.insert(file_id, response.data.clone());
+                                        self.open_documents.insert(file_id, OpenDoc::Document(Document::new_with_language(response.data.into(), dec, language)));
                                     }else {panic!()}
                                     dock.redraw(cx);
                                 }
@@ -144,6 +149,9 @@ impl FileSystem {
                         }
                         FileResponse::SaveFile(result) => match result {
                             Ok(response) => {
+                                if let Some(file_id) = self.path_to_file_node_id.get(&response.path) {
+                                    self.disk_data.insert(*file_id, response.new_data.clone());
+                                }
                                 self.process_save_response(cx, response);
                             }
                             Err(_) => {}
@@ -156,17 +164,8 @@ impl FileSystem {
                         match notification{
                             FileNotification::FileChangedOnDisk(response)=>{
                                
-                                if let Some(file_id) = self.path_to_file_node_id.get(&response.path){
-                                    
-                                    if let Some(OpenDoc::Document(doc)) = self.open_documents.get_mut(&file_id){
-                                        if let Some(tab_id) = 
-                                        // lets grab a session id for our first session
-                                        self.tab_id_to_file_node_id.iter()
-                                        .find_map(|(k, v)| if v == file_id { Some(k) } else { None }){
-                                            let session = self.tab_id_to_session.get(&tab_id).unwrap();
-                                            doc.replace(session.id(), response.new_data.clone().into());
-                                        }
-                                    }
+                                if let Some(file_id) = self.path_to_file_node_id(&response.path) {
+                                    self.reload_file(file_id, &response);
                                     ui.redraw(cx);
                                 }
                                 self.process_save_response(cx, response.clone());
@@ -182,6 +181,38 @@ impl FileSystem {
         }
     }
     
+    // Brings the document of an open file up to date with the file on disk. Unsaved changes in
+    // the editor are kept by merging them with the changes on disk, and the lines where both
+    // changed are left between conflict markers for the user to resolve.
+    fn reload_file(&mut self, file_id: LiveId, response: &SaveFileResponse) {
+        let base = self
+            .disk_data
+            .insert(file_id, response.new_data.clone())
+            .unwrap_or_else(|| response.old_data.clone());
+        let Some(tab_id) = self.file_node_id_to_tab_id(file_id) else {
+            return
+        };
+        let Some(session) = self.get_session_mut(tab_id) else {
+            return
+        };
+        let current = session.document().as_text().to_string();
+        if current == base {
+            session.reload(response.new_data.clone().into());
+        } else {
+            let merge = merge(
+                &base.into(),
+                &current.into(),
+                &response.new_data.clone().into(),
+                "Editor",
+                "Disk",
+            );
+            if merge.conflict_count > 0 {
+                log!("{} conflicts merging changes on disk into {}", merge.conflict_count, response.path);
+            }
+            session.reload(merge.text);
+        }
+    }
+    
     pub fn process_save_response(&mut self, cx:&mut Cx, response:SaveFileResponse){
         // alright file has been saved
         // now we need to check if a live_design!{} changed or something outside it
@@ -3,6 +3,7 @@
 pub mod app;
 pub mod app_ui;
 pub mod build_manager;
+pub mod collab;
 pub mod file_system;
 pub mod studio_editor;
 pub mod studio_file_tree;
@@ -39,6 +39,7 @@ impl Widget for StudioEditor {
         let session_id = scope.path.from_end(1);
         let data = scope.data.get_mut::<AppData>().unwrap();
         let uid = self.widget_uid();
+        self.editor.set_keymap(data.keymap.clone());
         if let Some(session) = data.file_system.get_session_mut(session_id){
             for action in self.editor.handle_event(cx, event, &mut Scope::empty(), session){
                 cx.widget_action(uid, &scope.path, action);
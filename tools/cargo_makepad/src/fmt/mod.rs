@@ -0,0 +1,79 @@
+use {
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+    makepad_live_tokenizer::format::format_live_design_macros,
+};
+
+/// Formats the `live_design!` bodies in the .rs files under the given paths.
+/// With `--check` nothing is written and the files that would change are listed.
+pub fn handle_fmt(args: &[String]) -> Result<(), String> {
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        if arg == "--check" {
+            check = true;
+        }
+        else if arg.starts_with("--") {
+            return Err(format!("{} is not a valid option", arg))
+        }
+        else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        return Err("fmt needs a file or directory".to_string())
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_files(path, &mut files)?;
+    }
+    let mut changed = 0;
+    for path in files {
+        let source = fs::read_to_string(&path).map_err( | e | format!("Can't read {}: {}", path.display(), e))?;
+        if !source.contains("live_design!") {
+            continue;
+        }
+        let formatted = format_live_design_macros(&source);
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+        if check {
+            println!("{} is not formatted", path.display());
+        }
+        else {
+            fs::write(&path, formatted).map_err( | e | format!("Can't write {}: {}", path.display(), e))?;
+            println!("Formatted {}", path.display());
+        }
+    }
+    if check && changed > 0 {
+        return Err(format!("{} files need formatting", changed))
+    }
+    Ok(())
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if path.is_dir() {
+        let entries = fs::read_dir(path).map_err( | e | format!("Can't read {}: {}", path.display(), e))?;
+        let mut paths: Vec<PathBuf> = entries.filter_map( | e | e.ok().map( | e | e.path())).collect();
+        paths.sort();
+        for path in paths {
+            let hidden = path.file_name().is_some_and( | n | n.to_string_lossy().starts_with('.'));
+            if hidden || path.file_name().is_some_and( | n | n == "target") {
+                continue;
+            }
+            collect_files(&path, files)?;
+        }
+        return Ok(())
+    }
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()))
+    }
+    if path.extension().is_some_and( | e | e == "rs") {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+struct LocaleConfig {
+    src: PathBuf,
+    dir: PathBuf,
+    write: bool,
+}
+
+pub fn handle_locale(mut args: &[String]) -> Result<(), String> {
+    let mut config = LocaleConfig {
+        src: PathBuf::from("src"),
+        dir: PathBuf::from("resources/locale"),
+        write: false,
+    };
+
+    // pull out options
+    for i in 0..args.len() {
+        let v = &args[i];
+        if let Some(opt) = v.strip_prefix("--src=") {
+            config.src = PathBuf::from(opt);
+        }
+        else if let Some(opt) = v.strip_prefix("--dir=") {
+            config.dir = PathBuf::from(opt);
+        }
+        else if v == "--write" {
+            config.write = true;
+        }
+        else {
+            args = &args[i..];
+            break
+        }
+    }
+
+    match args.first().map( | v | v.as_str()) {
+        Some("extract") => extract(&config),
+        Some(cmd) => Err(format!("{} is not a valid command or option", cmd)),
+        None => Err("locale needs a command".to_string())
+    }
+}
+
+/// Lists the keys used with `tr("key")` in the sources that a resource in
+/// the locale directory doesn't translate. With `--write` empty entries are
+/// appended to the resource files for translators to fill in.
+fn extract(config: &LocaleConfig) -> Result<(), String> {
+    let mut used = BTreeMap::new();
+    scan_sources(&config.src, &mut used)?;
+    println!("Found {} keys in {}", used.len(), config.src.display());
+
+    let entries = fs::read_dir(&config.dir).map_err( | e | format!("Can't read locale directory {}: {}", config.dir.display(), e))?;
+    let mut files: Vec<PathBuf> = entries.filter_map( | e | e.ok().map( | e | e.path()))
+        .filter( | p | matches!(p.extension().and_then( | e | e.to_str()), Some("ftl") | Some("po")))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err(format!("No .ftl or .po files in {}", config.dir.display()))
+    }
+
+    for path in files {
+        let source = fs::read_to_string(&path).map_err( | e | format!("Can't read {}: {}", path.display(), e))?;
+        let is_po = path.extension().is_some_and( | e | e == "po");
+        let translated = if is_po {po_keys(&source)} else {fluent_keys(&source)};
+        let missing: Vec<(&String, &String)> = used.iter().filter( | (key, _) | !translated.iter().any( | k | k == *key)).collect();
+        if missing.is_empty() {
+            println!("{}: complete", path.display());
+            continue;
+        }
+        println!("{}: {} untranslated", path.display(), missing.len());
+        for (key, location) in &missing {
+            println!("    {}    ({})", key, location);
+        }
+        if config.write {
+            let mut out = source.clone();
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            for (key, location) in &missing {
+                // a key already present with an empty translation doesn't need another entry
+                if is_po && source.contains(&format!("msgid \"{}\"", escape_po(key))) {
+                    continue;
+                }
+                if is_po {
+                    out.push_str(&format!("\n#: {}\nmsgid \"{}\"\nmsgstr \"\"\n", location, escape_po(key)));
+                }
+                else if !source.lines().any( | l | l.split_once('=').is_some_and( | (k, _) | k.trim() == key.as_str())) {
+                    out.push_str(&format!("# {}\n{} =\n", location, key));
+                }
+            }
+            fs::write(&path, out).map_err( | e | format!("Can't write {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn scan_sources(path: &Path, used: &mut BTreeMap<String, String>) -> Result<(), String> {
+    if path.is_dir() {
+        let entries = fs::read_dir(path).map_err( | e | format!("Can't read {}: {}", path.display(), e))?;
+        let mut paths: Vec<PathBuf> = entries.filter_map( | e | e.ok().map( | e | e.path())).collect();
+        paths.sort();
+        for path in paths {
+            scan_sources(&path, used)?;
+        }
+        return Ok(())
+    }
+    if path.extension().is_none_or( | e | e != "rs") {
+        return Ok(())
+    }
+    let source = fs::read_to_string(path).map_err( | e | format!("Can't read {}: {}", path.display(), e))?;
+    for (line_index, line) in source.lines().enumerate() {
+        for key in find_tr_keys(line) {
+            used.entry(key).or_insert_with( || format!("{}:{}", path.display(), line_index + 1));
+        }
+    }
+    Ok(())
+}
+
+/// Finds the string literal keys of `tr("..")`, `tr_args("..")` and
+/// `.tr("..")` calls on a line.
+fn find_tr_keys(line: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let bytes = line.as_bytes();
+    for pattern in ["tr(", "tr_args("] {
+        let mut start = 0;
+        while let Some(pos) = line[start..].find(pattern) {
+            let at = start + pos;
+            start = at + pattern.len();
+            if at > 0 && (bytes[at - 1].is_ascii_alphanumeric() || bytes[at - 1] == b'_') {
+                continue;
+            }
+            let rest = line[start..].trim_start();
+            let Some(rest) = rest.strip_prefix('"') else {
+                continue
+            };
+            let mut key = String::new();
+            let mut chars = rest.chars();
+            let mut closed = false;
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => {
+                        closed = true;
+                        break
+                    }
+                    '\\' => if let Some(c) = chars.next() {
+                        key.push(c)
+                    },
+                    c => key.push(c),
+                }
+            }
+            if closed && !key.is_empty() {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+/// Message and attribute keys with a value in a Fluent file.
+fn fluent_keys(source: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut current: Option<String> = None;
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        if line.starts_with(char::is_whitespace) {
+            if let (Some(message), Some(attr)) = (&current, trimmed.strip_prefix('.')) {
+                if let Some((name, value)) = attr.split_once('=') {
+                    if !value.trim().is_empty() {
+                        keys.push(format!("{}.{}", message, name.trim()));
+                    }
+                }
+            }
+            else if current.is_some() && !trimmed.is_empty() && !keys.contains(current.as_ref().unwrap()) {
+                // a value on the following lines
+                keys.push(current.clone().unwrap());
+            }
+            continue;
+        }
+        current = None;
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            if !value.trim().is_empty() {
+                keys.push(key.clone());
+            }
+            current = Some(key);
+        }
+    }
+    keys
+}
+
+/// Msgids with a non-empty translation in a gettext file.
+fn po_keys(source: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut msgid: Option<String> = None;
+    let mut in_msgid = false;
+    let mut translated = false;
+    let flush = | msgid: &mut Option<String>, translated: bool, keys: &mut Vec<String> | {
+        if let Some(id) = msgid.take() {
+            if translated && !id.is_empty() {
+                keys.push(id);
+            }
+        }
+    };
+    for line in source.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("msgid ") {
+            flush(&mut msgid, translated, &mut keys);
+            msgid = Some(unquote_po(value));
+            in_msgid = true;
+            translated = false;
+        }
+        else if line.starts_with("msgid_plural") {
+            in_msgid = false;
+        }
+        else if line.starts_with("msgstr") {
+            in_msgid = false;
+            if let Some((_, value)) = line.split_once(' ') {
+                translated |= !unquote_po(value).is_empty();
+            }
+        }
+        else if line.starts_with('"') {
+            if in_msgid {
+                if let Some(id) = &mut msgid {
+                    id.push_str(&unquote_po(line));
+                }
+            }
+            else {
+                translated |= !unquote_po(line).is_empty();
+            }
+        }
+    }
+    flush(&mut msgid, translated, &mut keys);
+    keys
+}
+
+fn unquote_po(value: &str) -> String {
+    let value = value.trim();
+    let inner = value.strip_prefix('"').and_then( | v | v.strip_suffix('"')).unwrap_or(value);
+    inner.replace("\\\"", "\"").replace("\\n", "\n").replace("\\\\", "\\")
+}
+
+fn escape_po(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
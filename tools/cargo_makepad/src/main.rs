@@ -3,10 +3,14 @@ mod wasm;
 mod utils;
 mod apple;
 mod check;
+mod locale;
+mod fmt;
 use android::*;
 use wasm::*;
 use apple::*;
 use check::*;
+use locale::*;
+use fmt::*;
 pub use makepad_shell;
 pub use makepad_http;
 pub use makepad_wasm_strip;
@@ -74,6 +78,21 @@ println!("    wasm [options] run <cargo args>              Build and run a wasm
     println!("    android [options] expand-sdk");
     println!("    android [options] remove-sdk-sources");
     println!();
+    println!("Locale commands:");
+    println!();
+    println!("    locale [options] extract                     Lists tr(\"key\") keys from the sources that a .ftl/.po file doesn't translate");
+    println!();
+    println!("    [options] with its default value:");
+    println!();
+    println!("       --src=./src                               The directory with the sources to scan");
+    println!("       --dir=./resources/locale                  The directory with the .ftl and .po files");
+    println!("       --write                                   Append empty entries for the missing keys to each file");
+    println!();
+    println!("Format commands:");
+    println!();
+    println!("    fmt [--check] <paths>                        Format the live_design! blocks in the .rs files under the paths");
+    println!("                                                 --check lists the files that need formatting without changing them");
+    println!();
     println!("Linux commands:");
     println!();
     println!("    linux apt-get-install-makepad-deps           Call apt-get install with all dependencies needed for makepad.");
@@ -112,6 +131,14 @@ fn main() {
         "check" => if let Err(e) = handle_check(&args[1..]){
             println!("Got error: {}", e);
         }
+        "locale" => if let Err(e) = handle_locale(&args[1..]){
+            println!("Got error: {}", e);
+        }
+        "fmt" => if let Err(e) = handle_fmt(&args[1..]){
+            println!("Got error: {}", e);
+            // --check is used in CI, so unformatted files have to fail it
+            std::process::exit(1);
+        }
         _=> show_help("not implemented yet")
     }
 }
\ No newline at end of file
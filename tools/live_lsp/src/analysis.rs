@@ -0,0 +1,379 @@
+//! Answers for an open document: diagnostics from the live parser, and
+//! completion, hover and definitions from the workspace index.
+
+use {
+    std::path::Path,
+    crate::{
+        dsl_index::{is_type_marker, DslBase, DslDef},
+        lexer::{lex, live_design_body, Token},
+        protocol::*,
+        rust_index::{RustField, RustType, RustTypeKind},
+        workspace::{placeholder_type_info, Owner, Prop, Workspace},
+    },
+    makepad_live_compiler::{
+        live_error::LiveErrorSpan,
+        live_parser::LiveParser,
+        makepad_live_tokenizer::Delim,
+        makepad_micro_serde::JsonValue,
+        LiveFileId, LiveRegistry, LiveToken, TextPos, TextSpan,
+    },
+};
+
+// completion item kinds from the protocol
+const KIND_PROPERTY: u32 = 10;
+const KIND_CLASS: u32 = 7;
+const KIND_ENUM_MEMBER: u32 = 20;
+const KIND_KEYWORD: u32 = 14;
+
+/// A document with its `live_design!` body located.
+pub struct Document<'a> {
+    pub path: &'a Path,
+    pub text: &'a str,
+    tokens: Vec<Token>,
+    body: std::ops::Range<usize>,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(path: &'a Path, text: &'a str) -> Self {
+        let tokens: Vec<Token> = lex(text).into_iter().filter( | t | !t.token.is_comment()).collect();
+        let body = live_design_body(&tokens).unwrap_or(0..0);
+        Self {path, text, tokens, body}
+    }
+
+    fn in_body(&self, pos: Position) -> bool {
+        // a body always comes after `live_design ! {`
+        if self.body.start == 0 {
+            return false
+        }
+        let start = self.tokens[self.body.start - 1].end;
+        let end = self.tokens.get(self.body.end).map( | t | t.start).unwrap_or(Position::new(u32::MAX, 0));
+        start <= pos && pos <= end
+    }
+
+    /// What the innermost brace around `pos` is the body of.
+    fn owner_at(&self, pos: Position) -> Owner {
+        let tokens = &self.tokens;
+        let mut stack = vec![Owner::Root];
+        let mut pending = None;
+        let mut in_fn = false;
+        let mut index = self.body.start;
+        while index < self.body.end && tokens[index].start < pos {
+            let token = &tokens[index];
+            let top = stack.last().cloned().unwrap_or(Owner::Root);
+            if top != Owner::Code {
+                if is_type_marker(tokens, index) {
+                    pending = Some(Owner::Type(tokens[index + 2].text.clone()));
+                    index += 5;
+                    continue;
+                }
+                if token.is_punct("<") && tokens.get(index + 2).is_some_and( | t | t.is_punct(">")) {
+                    pending = Some(Owner::Class(tokens[index + 1].text.clone()));
+                    index += 3;
+                    continue;
+                }
+            }
+            if token.is_keyword("fn") {
+                in_fn = true;
+            }
+            if token.is_open(Delim::Brace) {
+                let owner = if in_fn || top == Owner::Code {
+                    Owner::Code
+                }
+                else if let Some(owner) = pending.take() {
+                    owner
+                }
+                else if index >= 2 && (tokens[index - 1].is_punct(":") || tokens[index - 1].is_punct("=")) && tokens[index - 2].is_ident() {
+                    Owner::Field(Box::new(top), tokens[index - 2].text.clone())
+                }
+                else {
+                    Owner::Other
+                };
+                in_fn = false;
+                stack.push(owner);
+            }
+            else if token.is_close(Delim::Brace) && stack.len() > 1 {
+                stack.pop();
+            }
+            pending = None;
+            index += 1;
+        }
+        stack.pop().unwrap_or(Owner::Root)
+    }
+
+    /// The identifier token under `pos`, if it is inside the DSL.
+    fn ident_at(&self, pos: Position) -> Option<usize> {
+        (self.body.start..self.body.end).find( | index | {
+            let token = &self.tokens[*index];
+            token.is_ident() && token.contains(pos)
+        })
+    }
+
+    fn token_is(&self, index: Option<usize>, f: impl Fn(&Token) -> bool) -> bool {
+        index.and_then( | index | self.tokens.get(index)).is_some_and(f)
+    }
+}
+
+/// Parses the `live_design!` block, reporting the first error the parser hits.
+pub fn diagnostics(text: &str, encoding: PositionEncoding) -> Vec<JsonValue> {
+    let file_id = LiveFileId::new(0);
+    let tokens = match LiveRegistry::tokenize_from_str_live_design(text, TextPos::default(), file_id, None) {
+        Ok(tokens) => tokens,
+        Err(err) => return vec![diagnostic(text, encoding, &[], err.span, &err.message)]
+    };
+    // the parser only looks up `{{Type}}` by its index, so placeholders will do
+    let markers = tokens.windows(2).filter( | w | w[0].token == LiveToken::Open(Delim::Brace) && w[1].token == LiveToken::Open(Delim::Brace)).count();
+    let infos = vec![placeholder_type_info(); markers];
+    let mut parser = LiveParser::new(&tokens, &infos, file_id);
+    match parser.parse_live_document() {
+        Ok(_) => Vec::new(),
+        Err(err) => vec![diagnostic(text, encoding, &tokens, err.span, &err.message)]
+    }
+}
+
+fn diagnostic(text: &str, encoding: PositionEncoding, tokens: &[makepad_live_compiler::TokenWithSpan], span: LiveErrorSpan, message: &str) -> JsonValue {
+    let span = match span {
+        LiveErrorSpan::Text(span) => span,
+        LiveErrorSpan::Token(span) => tokens.get(span.token_id.token_index()).map( | t | t.span).unwrap_or_default(),
+    };
+    let TextSpan {start, end, ..} = span;
+    let end = if end == start {TextPos {line: end.line, column: end.column + 1}} else {end};
+    let start = encoding.chars_to_client(text, Position::new(start.line, start.column));
+    let end = encoding.chars_to_client(text, Position::new(end.line, end.column));
+    object(vec![
+        ("range", range(start, end)),
+        ("severity", number(1)),
+        ("source", string("live_design")),
+        ("message", string(message)),
+    ])
+}
+
+/// What is typed before the cursor decides what to complete: a class after
+/// `<`, a value after `:` or `=`, and a property name anywhere else.
+pub fn completion(workspace: &Workspace, doc: &Document, pos: Position) -> Vec<JsonValue> {
+    if !doc.in_body(pos) {
+        return Vec::new()
+    }
+    let line: Vec<char> = doc.text.lines().nth(pos.line as usize).unwrap_or("").chars().take(pos.column as usize).collect();
+    let is_ident = | c: char | c.is_alphanumeric() || c == '_';
+    let prefix_start = line.iter().rposition( | c | !is_ident(*c)).map( | i | i + 1).unwrap_or(0);
+    let before: String = line[..prefix_start].iter().collect();
+    let before = before.trim_end();
+
+    if before.ends_with('<') {
+        return complete_classes(workspace, doc.path)
+    }
+    if (before.ends_with(':') && !before.ends_with("::")) || (before.ends_with('=') && !before.ends_with("==")) {
+        let name: String = before[..before.len() - 1].trim_end().chars().rev().take_while( | c | is_ident(*c)).collect::<String>().chars().rev().collect();
+        return complete_values(workspace, doc, pos, &name)
+    }
+    let owner = doc.owner_at(pos);
+    let chain = workspace.resolve(doc.path, &owner);
+    let mut items: Vec<JsonValue> = Vec::new();
+    let mut seen = Vec::new();
+    // list rust fields before the keys classes set, so the type shows
+    let mut props = workspace.props(&chain);
+    props.sort_by_key( | prop | matches!(prop, Prop::Key(..)));
+    for prop in props {
+        if seen.contains(&prop.name()) {
+            continue;
+        }
+        seen.push(prop.name());
+        items.push(match prop {
+            Prop::Field(_, owner, field) => object(vec![
+                ("label", string(&field.name)),
+                ("kind", number(KIND_PROPERTY)),
+                ("detail", string(&field.ty)),
+                ("documentation", markdown(field_markdown(owner, field))),
+            ]),
+            Prop::Key(_, def, key) => object(vec![
+                ("label", string(&key.name)),
+                ("kind", number(KIND_PROPERTY)),
+                ("detail", string(format!("set in {}", def.name))),
+            ]),
+        });
+    }
+    items
+}
+
+fn complete_classes(workspace: &Workspace, path: &Path) -> Vec<JsonValue> {
+    workspace.visible_defs(path).into_iter().filter( | (_, def) | def.base != DslBase::Value).map( | (_, def) | {
+        object(vec![
+            ("label", string(&def.name)),
+            ("kind", number(KIND_CLASS)),
+            ("detail", string(base_text(def))),
+        ])
+    }).collect()
+}
+
+fn complete_values(workspace: &Workspace, doc: &Document, pos: Position, name: &str) -> Vec<JsonValue> {
+    let owner = doc.owner_at(pos);
+    let chain = workspace.resolve(doc.path, &owner);
+    let props = workspace.props(&chain);
+    let Some(prop) = props.iter().find( | p | matches!(p, Prop::Field(..)) && p.name() == name) else {
+        return Vec::new()
+    };
+    if let Prop::Field(_, _, field) = prop {
+        if field.value_type() == "bool" {
+            return ["true", "false"].iter().map( | v | object(vec![
+                ("label", string(*v)),
+                ("kind", number(KIND_KEYWORD)),
+            ])).collect()
+        }
+    }
+    let Some((_, ty)) = workspace.prop_type(prop) else {
+        return Vec::new()
+    };
+    ty.variants().iter().map( | variant | {
+        let mut fields = vec![
+            ("label", string(&variant.name)),
+            ("kind", number(KIND_ENUM_MEMBER)),
+            ("detail", string(format!("{}::{}{}", ty.name, variant.name, variant.payload))),
+        ];
+        if !variant.doc.is_empty() {
+            fields.push(("documentation", markdown(variant.doc.clone())));
+        }
+        if variant.pick {
+            fields.push(("preselect", JsonValue::Bool(true)));
+        }
+        object(fields)
+    }).collect()
+}
+
+/// The thing under the cursor, as far as the index knows it.
+enum Target<'a> {
+    Def(&'a Path, &'a DslDef),
+    Type(&'a Path, &'a RustType),
+    Prop(Prop<'a>),
+    Variant(&'a Path, &'a RustType, usize),
+}
+
+fn target_at<'a>(workspace: &'a Workspace, doc: &Document, pos: Position) -> Option<Target<'a >> {
+    let index = doc.ident_at(pos)?;
+    let name = doc.tokens[index].text.as_str();
+    let prev = index.checked_sub(1);
+    let next = Some(index + 1);
+
+    // <Class>
+    if doc.token_is(prev, | t | t.is_punct("<")) && doc.token_is(next, | t | t.is_punct(">")) {
+        let (path, def) = workspace.find_def(doc.path, name, false)?;
+        return Some(Target::Def(path, def))
+    }
+    // {{Type}}
+    if index >= 2 && is_type_marker(&doc.tokens, index - 2) {
+        let (path, ty) = workspace.find_type(doc.path, name)?;
+        return Some(Target::Type(path, ty))
+    }
+    let owner = doc.owner_at(doc.tokens[index].start);
+    // name: value or name = value
+    if doc.token_is(next, | t | t.is_punct(":") || t.is_punct("=")) {
+        if owner == Owner::Root {
+            let (path, def) = workspace.find_def(doc.path, name, false)?;
+            return Some(Target::Def(path, def))
+        }
+        let chain = workspace.resolve(doc.path, &owner);
+        let props = workspace.props(&chain);
+        let prop = props.iter().find( | p | matches!(p, Prop::Field(..)) && p.name() == name)
+            .or_else( || props.iter().find( | p | p.name() == name))?;
+        return Some(Target::Prop(*prop))
+    }
+    // an enum variant as the value of a field
+    if doc.token_is(prev, | t | t.is_punct(":") || t.is_punct("=")) && index >= 2 && doc.tokens[index - 2].is_ident() {
+        let field_name = doc.tokens[index - 2].text.as_str();
+        let chain = workspace.resolve(doc.path, &owner);
+        let props = workspace.props(&chain);
+        let prop = props.iter().find( | p | matches!(p, Prop::Field(..)) && p.name() == field_name)?;
+        let (path, ty) = workspace.prop_type(prop)?;
+        let variant = ty.variants().iter().position( | v | v.name == name)?;
+        return Some(Target::Variant(path, ty, variant))
+    }
+    None
+}
+
+pub fn hover(workspace: &Workspace, doc: &Document, pos: Position) -> Option<JsonValue> {
+    let text = match target_at(workspace, doc, pos)? {
+        Target::Def(path, def) => {
+            let mut text = format!("```rust\n{}\n```", base_text(def));
+            if let Some(index) = workspace.files.get(path) {
+                text.push_str(&format!("\n\nin `{}`", index.module));
+            }
+            let mut chain = Vec::new();
+            workspace.def_chain(path, def, &mut chain, 0);
+            if let Some(crate::workspace::Resolved::Type(_, ty)) = chain.last() {
+                text.push_str(&format!("\n\n{}", type_markdown(ty)));
+            }
+            text
+        }
+        Target::Type(_, ty) => type_markdown(ty),
+        Target::Prop(Prop::Field(_, owner, field)) => field_markdown(owner, field),
+        Target::Prop(Prop::Key(_, def, key)) => format!("```rust\n{}\n```\n\nset in `{}`", key.name, def.name),
+        Target::Variant(_, ty, index) => {
+            let variant = &ty.variants()[index];
+            let mut text = format!("```rust\n{}::{}{}\n```", ty.name, variant.name, variant.payload);
+            if !variant.doc.is_empty() {
+                text.push_str(&format!("\n\n{}", variant.doc));
+            }
+            text
+        }
+    };
+    Some(object(vec![("contents", markdown(text))]))
+}
+
+/// Where a definition is, counted in characters.
+pub struct Location<'a> {
+    pub path: &'a Path,
+    pub start: Position,
+    pub end: Position,
+}
+
+pub fn definition<'a>(workspace: &'a Workspace, doc: &Document, pos: Position) -> Option<Location<'a>> {
+    let (path, start, len) = match target_at(workspace, doc, pos)? {
+        Target::Def(path, def) => (path, def.pos, def.name.chars().count()),
+        Target::Type(path, ty) => (path, ty.pos, ty.name.chars().count()),
+        Target::Prop(Prop::Field(path, _, field)) => (path, field.pos, field.name.chars().count()),
+        Target::Prop(Prop::Key(path, _, key)) => (path, key.pos, key.name.chars().count()),
+        Target::Variant(path, ty, index) => {
+            let variant = &ty.variants()[index];
+            (path, variant.pos, variant.name.chars().count())
+        }
+    };
+    Some(Location {path, start, end: Position::new(start.line, start.column + len as u32)})
+}
+
+fn base_text(def: &DslDef) -> String {
+    match &def.base {
+        DslBase::Class(base) => format!("{} = <{}>", def.name, base),
+        DslBase::Type(ty) => format!("{} = {{{{{}}}}}", def.name, ty),
+        DslBase::Value => def.name.clone(),
+    }
+}
+
+fn type_markdown(ty: &RustType) -> String {
+    let keyword = match ty.kind {
+        RustTypeKind::Struct(_) => "struct",
+        RustTypeKind::Enum(_) => "enum",
+    };
+    let mut text = format!("```rust\n{} {}\n```", keyword, ty.name);
+    if !ty.doc.is_empty() {
+        text.push_str(&format!("\n\n{}", ty.doc));
+    }
+    text
+}
+
+fn field_markdown(owner: &RustType, field: &RustField) -> String {
+    let mut text = format!("```rust\n{}: {}\n```", field.name, field.ty);
+    if let Some(default) = &field.default {
+        text.push_str(&format!("\n\ndefault: `{}`", default));
+    }
+    text.push_str(&format!("\n\nfield of `{}`", owner.name));
+    if !field.doc.is_empty() {
+        text.push_str(&format!("\n\n{}", field.doc));
+    }
+    text
+}
+
+fn markdown(value: String) -> JsonValue {
+    object(vec![
+        ("kind", string("markdown")),
+        ("value", string(value)),
+    ])
+}
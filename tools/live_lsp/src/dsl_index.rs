@@ -0,0 +1,128 @@
+//! The top level definitions of a `live_design!` block, which is what other
+//! files can refer to by name, read from the document the live parser made of
+//! it in the registry.
+
+use {
+    crate::{
+        lexer::Token,
+        protocol::Position,
+    },
+    makepad_live_compiler::{
+        makepad_live_tokenizer::Delim,
+        live_node_vec::LiveNodeSliceApi,
+        LiveId, LiveOriginal, LiveToken, LiveValue, TextPos,
+    },
+};
+
+#[derive(Clone, Debug)]
+pub struct DslFile {
+    pub defs: Vec<DslDef>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DslDef {
+    pub name: String,
+    pub base: DslBase,
+    pub pos: Position,
+    /// the properties set in the body, which classes inheriting from this
+    /// one can override
+    pub keys: Vec<DslKey>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DslBase {
+    /// `Name = <Class> {..}`
+    Class(String),
+    /// `Name = {{Type}} {..}`
+    Type(String),
+    /// anything else, like a plain value
+    Value,
+}
+
+#[derive(Clone, Debug)]
+pub struct DslKey {
+    pub name: String,
+    pub pos: Position,
+}
+
+/// Indexes a parsed `live_design!` body whose first character is at `start`
+/// in its file.
+pub fn scan_dsl(original: &LiveOriginal, start: Position) -> DslFile {
+    let nodes = &original.nodes;
+    let mut file = DslFile {defs: Vec::new()};
+    if nodes.is_empty() {
+        return file
+    }
+    let mut child = nodes.first_child(0);
+    while let Some(index) = child {
+        child = nodes.next_child(index);
+        let node = &nodes[index];
+        let base = match &node.value {
+            LiveValue::Import(_) | LiveValue::Condition {..} => continue,
+            LiveValue::Clone {clone, ..} => DslBase::Class(clone.to_string()),
+            LiveValue::Class {..} | LiveValue::Deref {..} => match type_name(original, index) {
+                Some(name) => DslBase::Type(name),
+                None => DslBase::Value
+            },
+            _ => DslBase::Value
+        };
+        let mut keys = Vec::new();
+        if node.is_open() {
+            let mut key = nodes.first_child(index);
+            while let Some(key_index) = key {
+                key = nodes.next_child(key_index);
+                if let Some(pos) = name_pos(original, key_index, start) {
+                    keys.push(DslKey {name: nodes[key_index].id.to_string(), pos});
+                }
+            }
+        }
+        let Some(pos) = name_pos(original, index, start) else {
+            continue
+        };
+        file.defs.push(DslDef {name: node.id.to_string(), base, pos, keys});
+    }
+    file
+}
+
+/// Where the name of a node is. Its origin is the token it starts at, which
+/// comes before the name for `fn name` and `instance name: ..`.
+fn name_pos(original: &LiveOriginal, index: usize, start: Position) -> Option<Position> {
+    let node = &original.nodes[index];
+    let token_index = node.origin.token_id()?.token_index();
+    let name = original.tokens.get(token_index..)?.iter().take(3)
+        .find( | t | t.token == LiveToken::Ident(node.id))?;
+    Some(to_file_pos(name.span.start, start))
+}
+
+/// The `Type` in `Name = {{Type}}`, which the parser only keeps as an index
+/// into the type infos.
+fn type_name(original: &LiveOriginal, index: usize) -> Option<String> {
+    let token_index = original.nodes[index].origin.token_id()?.token_index();
+    let tokens = original.tokens.get(token_index..)?;
+    let tokens = &tokens[..tokens.len().min(6)];
+    let open = tokens.windows(2).position( | w | w[0].token == LiveToken::Open(Delim::Brace) && w[1].token == LiveToken::Open(Delim::Brace))?;
+    match tokens.get(open + 2)?.token {
+        LiveToken::Ident(id) if id != LiveId::empty() => Some(id.to_string()),
+        _ => None
+    }
+}
+
+/// Turns a position in a `live_design!` body into one in its file.
+pub fn to_file_pos(pos: TextPos, start: Position) -> Position {
+    if pos.line == 0 {
+        Position::new(start.line, start.column + pos.column)
+    }
+    else {
+        Position::new(start.line + pos.line, pos.column)
+    }
+}
+
+/// Whether `{{Type}}` starts at `index`.
+pub fn is_type_marker(tokens: &[Token], index: usize) -> bool {
+    let get = | offset: usize | tokens.get(index + offset);
+    get(0).is_some_and( | t | t.is_open(Delim::Brace))
+        && get(1).is_some_and( | t | t.is_open(Delim::Brace))
+        && get(2).is_some_and( | t | t.is_ident())
+        && get(3).is_some_and( | t | t.is_close(Delim::Brace))
+        && get(4).is_some_and( | t | t.is_close(Delim::Brace))
+}
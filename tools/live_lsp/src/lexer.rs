@@ -0,0 +1,148 @@
+//! Tokens with their text and position, on top of the live tokenizer. The
+//! indexes and the analysis work on these rather than on `LiveToken`s as they
+//! need comments, the text of identifiers and the parts of a file outside of
+//! `live_design!`.
+
+use {
+    crate::protocol::Position,
+    makepad_live_compiler::makepad_live_tokenizer::{Cursor, Delim, FullToken, State},
+};
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub token: FullToken,
+    pub text: String,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Token {
+    pub fn is_ident(&self) -> bool {
+        matches!(self.token, FullToken::Ident(_))
+    }
+
+    pub fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.token, FullToken::Punct(_)) && self.text == punct
+    }
+
+    pub fn is_open(&self, delim: Delim) -> bool {
+        self.token == FullToken::Open(delim)
+    }
+
+    pub fn is_close(&self, delim: Delim) -> bool {
+        self.token == FullToken::Close(delim)
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.is_ident() && self.text == keyword
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos <= self.end
+    }
+}
+
+/// Splits a file into tokens, leaving out whitespace.
+pub fn lex(source: &str) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut scratch = String::new();
+    let mut cursor = Cursor::new(&chars, &mut scratch);
+    let mut state = State::default();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut pos = Position::default();
+    loop {
+        let (next_state, token) = state.next(&mut cursor);
+        let Some(token) = token else {
+            break
+        };
+        let text: String = chars[index..index + token.len].iter().collect();
+        let start = pos;
+        for c in text.chars() {
+            if c == '\n' {
+                pos.line += 1;
+                pos.column = 0;
+            }
+            else {
+                pos.column += 1;
+            }
+        }
+        index += token.len;
+        if !token.token.is_whitespace() {
+            tokens.push(Token {token: token.token, text, start, end: pos});
+        }
+        state = next_state;
+    }
+    tokens
+}
+
+/// The byte offset of a position in `source`.
+pub fn offset(source: &str, pos: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..pos.line {
+        match source[line_start..].find('\n') {
+            Some(end) => line_start += end + 1,
+            None => return source.len()
+        }
+    }
+    let line = &source[line_start..];
+    line_start + line.char_indices().nth(pos.column as usize).map_or(line.len(), | (index, _) | index)
+}
+
+/// The index of the token closing the group opened at `open`, or the end of
+/// the tokens when it isn't closed.
+pub fn matching_close(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.token {
+            FullToken::Open(_) => depth += 1,
+            FullToken::Close(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    return index
+                }
+            }
+            _ => ()
+        }
+    }
+    tokens.len()
+}
+
+/// The range of tokens inside the braces of `live_design!{..}`.
+pub fn live_design_body(tokens: &[Token]) -> Option<std::ops::Range<usize>> {
+    let mut index = 0;
+    while index + 2 < tokens.len() {
+        if tokens[index].is_keyword("live_design")
+            && tokens[index + 1].is_punct("!")
+            && tokens[index + 2].is_open(Delim::Brace) {
+            let close = matching_close(tokens, index + 2);
+            return Some(index + 3..close)
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Joins tokens back into source text, with a space only where two words
+/// would otherwise run into each other.
+pub fn join(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut last_word = false;
+    for token in tokens {
+        if token.token.is_comment() {
+            continue;
+        }
+        let word = token.text.starts_with( | c: char | c.is_alphanumeric() || c == '_' || c == '\'');
+        if word && last_word {
+            out.push(' ');
+        }
+        if token.is_punct(",") {
+            out.push_str(", ");
+            last_word = false;
+            continue;
+        }
+        out.push_str(&token.text);
+        last_word = token.text.ends_with( | c: char | c.is_alphanumeric() || c == '_');
+    }
+    out.trim_end().to_string()
+}
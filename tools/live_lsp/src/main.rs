@@ -0,0 +1,195 @@
+//! A language server for the `live_design!` DSL. It loads the DSL of every
+//! crate under the workspace root into a `LiveRegistry` and indexes the Rust
+//! types deriving `Live`, and uses them for completion, hover and go to
+//! definition. Errors the live parser finds are published as diagnostics.
+//!
+//! Run it from an editor as a stdio language server for Rust files, next to
+//! rust-analyzer. Positions are counted in UTF-16 code units unless the client
+//! offers to count characters.
+
+mod analysis;
+mod dsl_index;
+mod lexer;
+mod protocol;
+mod rust_index;
+mod workspace;
+
+use {
+    std::{
+        collections::HashMap,
+        fs,
+        io::{self, BufReader},
+        path::{Path, PathBuf},
+    },
+    crate::{
+        analysis::Document,
+        protocol::*,
+        workspace::Workspace,
+    },
+    makepad_live_compiler::makepad_micro_serde::*,
+};
+
+#[derive(Default)]
+struct Server {
+    workspace: Workspace,
+    /// the text of open documents, which wins over what is on disk
+    documents: HashMap<PathBuf, String>,
+    encoding: PositionEncoding,
+    shutdown: bool,
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let mut input = BufReader::new(stdin.lock());
+    let mut output = io::stdout();
+    let mut server = Server::default();
+    while let Some(body) = read_message(&mut input) {
+        let Ok(message) = JsonValue::deserialize_json(&body) else {
+            continue
+        };
+        let method = message.key("method").and_then( | m | m.string()).cloned().unwrap_or_default();
+        let params = message.key("params").cloned().unwrap_or(JsonValue::Null);
+        if method == "exit" {
+            std::process::exit(if server.shutdown {0} else {1});
+        }
+        let mut outgoing = Vec::new();
+        let result = server.handle(&method, &params, &mut outgoing);
+        if let Some(id) = message.key("id") {
+            outgoing.insert(0, match result {
+                Ok(result) => response(id.clone(), result),
+                Err((code, message)) => error_response(id.clone(), code, &message),
+            });
+        }
+        for message in outgoing {
+            write_message(&mut output, &message);
+        }
+    }
+}
+
+impl Server {
+    /// Handles a request or notification; notifications to send back go into `outgoing`.
+    fn handle(&mut self, method: &str, params: &JsonValue, outgoing: &mut Vec<JsonValue>) -> Result<JsonValue, (i64, String)> {
+        match method {
+            "initialize" => {
+                let root = params.key("rootUri").and_then( | u | u.string()).and_then( | u | uri_to_path(u))
+                    .or_else( || params.key("rootPath").and_then( | p | p.string()).map(PathBuf::from));
+                if let Some(root) = root {
+                    self.workspace.scan(&root);
+                }
+                self.encoding = PositionEncoding::negotiate(params.key("capabilities"));
+                Ok(object(vec![
+                    ("capabilities", object(vec![
+                        ("positionEncoding", string(self.encoding.name())),
+                        ("textDocumentSync", number(1)),
+                        ("hoverProvider", JsonValue::Bool(true)),
+                        ("definitionProvider", JsonValue::Bool(true)),
+                        ("completionProvider", object(vec![
+                            ("triggerCharacters", JsonValue::Array(vec![string("<"), string(":")])),
+                        ])),
+                    ])),
+                    ("serverInfo", object(vec![
+                        ("name", string("makepad-live-lsp")),
+                        ("version", string(env!("CARGO_PKG_VERSION"))),
+                    ])),
+                ]))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(JsonValue::Null)
+            }
+            "textDocument/didOpen" => {
+                let document = params.key("textDocument");
+                let uri = document.and_then( | d | d.key("uri")).and_then( | u | u.string());
+                let text = document.and_then( | d | d.key("text")).and_then( | t | t.string());
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.update_document(uri, text.clone(), outgoing);
+                }
+                Ok(JsonValue::Null)
+            }
+            "textDocument/didChange" => {
+                let uri = params.key("textDocument").and_then( | d | d.key("uri")).and_then( | u | u.string());
+                // full sync, so the last change holds the whole text
+                let text = match params.key("contentChanges") {
+                    Some(JsonValue::Array(changes)) => changes.last().and_then( | c | c.key("text")).and_then( | t | t.string()),
+                    _ => None
+                };
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.update_document(uri, text.clone(), outgoing);
+                }
+                Ok(JsonValue::Null)
+            }
+            "textDocument/didClose" => {
+                let uri = params.key("textDocument").and_then( | d | d.key("uri")).and_then( | u | u.string());
+                if let Some((uri, path)) = uri.and_then( | uri | Some((uri, uri_to_path(uri)?))) {
+                    self.documents.remove(&path);
+                    match fs::read_to_string(&path) {
+                        Ok(source) => self.workspace.update_file(&path, &source),
+                        Err(_) => self.workspace.remove_file(&path)
+                    }
+                    outgoing.push(publish_diagnostics(uri, Vec::new()));
+                }
+                Ok(JsonValue::Null)
+            }
+            "textDocument/completion" => {
+                let (path, text, pos) = self.document_at(params)?;
+                let doc = Document::new(&path, &text);
+                Ok(JsonValue::Array(analysis::completion(&self.workspace, &doc, pos)))
+            }
+            "textDocument/hover" => {
+                let (path, text, pos) = self.document_at(params)?;
+                let doc = Document::new(&path, &text);
+                Ok(analysis::hover(&self.workspace, &doc, pos).unwrap_or(JsonValue::Null))
+            }
+            "textDocument/definition" => {
+                let (path, text, pos) = self.document_at(params)?;
+                let doc = Document::new(&path, &text);
+                let Some(location) = analysis::definition(&self.workspace, &doc, pos) else {
+                    return Ok(JsonValue::Null)
+                };
+                let text = self.text_of(location.path);
+                Ok(object(vec![
+                    ("uri", string(path_to_uri(location.path))),
+                    ("range", range(self.encoding.chars_to_client(&text, location.start), self.encoding.chars_to_client(&text, location.end))),
+                ]))
+            }
+            _ if method.starts_with("$/") || method == "initialized" || method.starts_with("textDocument/did") => Ok(JsonValue::Null),
+            _ => Err((-32601, format!("Method not found: {}", method)))
+        }
+    }
+
+    fn update_document(&mut self, uri: &str, text: String, outgoing: &mut Vec<JsonValue>) {
+        let Some(path) = uri_to_path(uri) else {
+            return
+        };
+        self.workspace.update_file(&path, &text);
+        outgoing.push(publish_diagnostics(uri, analysis::diagnostics(&text, self.encoding)));
+        self.documents.insert(path, text);
+    }
+
+    /// The document and position a request is about, with the position
+    /// counted in characters.
+    fn document_at(&self, params: &JsonValue) -> Result<(PathBuf, String, Position), (i64, String)> {
+        let path = params.key("textDocument").and_then( | d | d.key("uri")).and_then( | u | u.string()).and_then( | u | uri_to_path(u));
+        let pos = params.key("position").and_then(Position::from_json);
+        let (Some(path), Some(pos)) = (path, pos) else {
+            return Err((-32602, "Expected a textDocument and position".to_string()))
+        };
+        let text = self.text_of(&path);
+        let pos = self.encoding.client_to_chars(&text, pos);
+        Ok((path, text, pos))
+    }
+
+    fn text_of(&self, path: &Path) -> String {
+        match self.documents.get(path) {
+            Some(text) => text.clone(),
+            None => fs::read_to_string(path).unwrap_or_default()
+        }
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<JsonValue>) -> JsonValue {
+    notification("textDocument/publishDiagnostics", object(vec![
+        ("uri", string(uri)),
+        ("diagnostics", JsonValue::Array(diagnostics)),
+    ]))
+}
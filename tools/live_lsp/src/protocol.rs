@@ -0,0 +1,292 @@
+//! The bits of the language server protocol this server speaks: messages
+//! framed with a `Content-Length` header on stdio, carrying JSON-RPC.
+
+use {
+    std::{
+        collections::HashMap,
+        io::{BufRead, Write},
+    },
+    makepad_live_compiler::makepad_micro_serde::*,
+};
+
+/// Reads one message body, or `None` once the client closed the stream.
+pub fn read_message(input: &mut impl BufRead) -> Option<String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break
+            }
+            continue
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0u8; length?];
+    input.read_exact(&mut body).ok()?;
+    String::from_utf8(body).ok()
+}
+
+pub fn write_message(output: &mut impl Write, message: &JsonValue) {
+    let body = to_json(message);
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = output.flush();
+}
+
+/// Serializes a value as compact JSON. micro_serde's own serializer isn't
+/// used for this as it doesn't handle empty arrays and objects.
+pub fn to_json(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_json(value, &mut out);
+    out
+}
+
+fn write_json(value: &JsonValue, out: &mut String) {
+    match value {
+        JsonValue::String(v) | JsonValue::BareIdent(v) => write_json_string(v, out),
+        JsonValue::Char(v) => write_json_string(&v.to_string(), out),
+        JsonValue::U64(v) => out.push_str(&v.to_string()),
+        JsonValue::I64(v) => out.push_str(&v.to_string()),
+        JsonValue::F64(v) => out.push_str(&v.to_string()),
+        JsonValue::Bool(v) => out.push_str(if *v {"true"} else {"false"}),
+        JsonValue::Null | JsonValue::Undefined => out.push_str("null"),
+        JsonValue::Object(map) => {
+            out.push('{');
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_json_string(key, out);
+                out.push(':');
+                write_json(&map[key], out);
+            }
+            out.push('}');
+        }
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_json(item, out);
+            }
+            out.push(']');
+        }
+    }
+}
+
+fn write_json_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+pub fn object(fields: Vec<(&str, JsonValue)>) -> JsonValue {
+    JsonValue::Object(fields.into_iter().map( | (k, v) | (k.to_string(), v)).collect::<HashMap<_, _ >> ())
+}
+
+pub fn string(value: impl Into<String>) -> JsonValue {
+    JsonValue::String(value.into())
+}
+
+pub fn number(value: u32) -> JsonValue {
+    JsonValue::U64(value as u64)
+}
+
+pub fn response(id: JsonValue, result: JsonValue) -> JsonValue {
+    object(vec![
+        ("jsonrpc", string("2.0")),
+        ("id", id),
+        ("result", result),
+    ])
+}
+
+pub fn error_response(id: JsonValue, code: i64, message: &str) -> JsonValue {
+    object(vec![
+        ("jsonrpc", string("2.0")),
+        ("id", id),
+        ("error", object(vec![
+            ("code", JsonValue::I64(code)),
+            ("message", string(message)),
+        ])),
+    ])
+}
+
+pub fn notification(method: &str, params: JsonValue) -> JsonValue {
+    object(vec![
+        ("jsonrpc", string("2.0")),
+        ("method", string(method)),
+        ("params", params),
+    ])
+}
+
+/// What the client counts the columns of positions in. The server itself
+/// counts characters, and converts at the edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PositionEncoding {
+    Utf8,
+    /// what clients that don't say otherwise use
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Picks from the `general.positionEncodings` the client offers, counting
+    /// characters where it can so nothing needs converting.
+    pub fn negotiate(capabilities: Option<&JsonValue>) -> Self {
+        let offered: Vec<&String> = match capabilities.and_then( | c | c.key("general")).and_then( | g | g.key("positionEncodings")) {
+            Some(JsonValue::Array(encodings)) => encodings.iter().filter_map( | e | e.string()).collect(),
+            _ => Vec::new()
+        };
+        [Self::Utf32, Self::Utf16, Self::Utf8].into_iter()
+            .find( | encoding | offered.iter().any( | name | *name == encoding.name()))
+            .unwrap_or_default()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16 => "utf-16",
+            Self::Utf32 => "utf-32",
+        }
+    }
+
+    fn len(self, c: char) -> u32 {
+        match self {
+            Self::Utf8 => c.len_utf8() as u32,
+            Self::Utf16 => c.len_utf16() as u32,
+            Self::Utf32 => 1,
+        }
+    }
+
+    /// Turns a position from the client into one counted in characters.
+    pub fn client_to_chars(self, text: &str, pos: Position) -> Position {
+        let line = text.lines().nth(pos.line as usize).unwrap_or("");
+        let mut units = 0;
+        let mut column = 0;
+        for c in line.chars() {
+            if units >= pos.column {
+                break
+            }
+            units += self.len(c);
+            column += 1;
+        }
+        Position::new(pos.line, column)
+    }
+
+    /// Turns a position counted in characters into one for the client.
+    pub fn chars_to_client(self, text: &str, pos: Position) -> Position {
+        let line = text.lines().nth(pos.line as usize).unwrap_or("");
+        let column: u32 = line.chars().take(pos.column as usize).map( | c | self.len(c)).sum();
+        // past the end of the line, like the end of an error at the end of a line
+        let past = pos.column.saturating_sub(line.chars().count() as u32);
+        Position::new(pos.line, column + past)
+    }
+}
+
+/// A zero based line and column, counted in characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Position {
+    pub fn new(line: u32, column: u32) -> Self {
+        Self {line, column}
+    }
+
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        Some(Self {
+            line: json_u32(value.key("line")?)?,
+            column: json_u32(value.key("character")?)?,
+        })
+    }
+
+    pub fn to_json(self) -> JsonValue {
+        object(vec![
+            ("line", number(self.line)),
+            ("character", number(self.column)),
+        ])
+    }
+}
+
+pub fn range(start: Position, end: Position) -> JsonValue {
+    object(vec![
+        ("start", start.to_json()),
+        ("end", end.to_json()),
+    ])
+}
+
+pub fn json_u32(value: &JsonValue) -> Option<u32> {
+    match value {
+        JsonValue::U64(v) => Some(*v as u32),
+        JsonValue::I64(v) => Some(*v as u32),
+        JsonValue::F64(v) => Some(*v as u32),
+        _ => None
+    }
+}
+
+/// Turns a `file://` uri into a path, undoing percent escapes.
+pub fn uri_to_path(uri: &str) -> Option<std::path::PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(v) = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then( | hex | u8::from_str_radix(hex, 16).ok()) {
+                out.push(v);
+                i += 3;
+                continue
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8(out).ok()?;
+    // windows paths come as /C:/...
+    let path = if path.len() > 2 && path.as_bytes()[2] == b':' {&path[1..]} else {&path[..]};
+    Some(std::path::PathBuf::from(path))
+}
+
+pub fn path_to_uri(path: &std::path::Path) -> String {
+    let mut uri = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for c in path.chars() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '/' | '-' | '_' | '.' | '~' | ':' => uri.push(c),
+            c => {
+                let mut buf = [0u8; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    uri.push_str(&format!("%{:02X}", b));
+                }
+            }
+        }
+    }
+    uri
+}
@@ -0,0 +1,312 @@
+//! Finds the structs and enums that derive `Live` in Rust source, so the DSL
+//! can be checked against their fields and variants without compiling them.
+
+use {
+    crate::{
+        lexer::{join, matching_close, Token},
+        protocol::Position,
+    },
+    makepad_live_compiler::makepad_live_tokenizer::{Delim, FullToken},
+};
+
+#[derive(Clone, Debug)]
+pub struct RustType {
+    pub name: String,
+    pub doc: String,
+    pub pos: Position,
+    pub kind: RustTypeKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum RustTypeKind {
+    Struct(Vec<RustField>),
+    Enum(Vec<RustVariant>),
+}
+
+/// How a field takes part in the DSL, after its attribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RustFieldKind {
+    Live,
+    Calc,
+    Animator,
+    /// the fields of a `Walk`, set directly on the owner
+    Walk,
+    /// the fields of a `Layout`, set directly on the owner
+    Layout,
+    /// the base the owner forwards properties it doesn't have to
+    Deref,
+}
+
+#[derive(Clone, Debug)]
+pub struct RustField {
+    pub name: String,
+    pub ty: String,
+    pub kind: RustFieldKind,
+    /// the expression in `#[live(..)]`
+    pub default: Option<String>,
+    pub doc: String,
+    pub pos: Position,
+}
+
+#[derive(Clone, Debug)]
+pub struct RustVariant {
+    pub name: String,
+    /// `(f64)` or `{..}` for variants with data
+    pub payload: String,
+    pub pick: bool,
+    pub doc: String,
+    pub pos: Position,
+}
+
+impl RustType {
+    pub fn fields(&self) -> &[RustField] {
+        match &self.kind {
+            RustTypeKind::Struct(fields) => fields,
+            RustTypeKind::Enum(_) => &[]
+        }
+    }
+
+    pub fn variants(&self) -> &[RustVariant] {
+        match &self.kind {
+            RustTypeKind::Struct(_) => &[],
+            RustTypeKind::Enum(variants) => variants
+        }
+    }
+}
+
+impl RustField {
+    /// The name of the type the DSL value maps to, looking through the
+    /// wrappers that don't change what is written in the DSL.
+    pub fn value_type(&self) -> String {
+        let mut ty = self.ty.as_str();
+        loop {
+            let inner = ["Option<", "Box<", "Rc<", "Arc<"].iter().find_map( | wrapper | ty.strip_prefix(wrapper));
+            match inner {
+                Some(inner) => ty = inner.strip_suffix('>').unwrap_or(inner),
+                None => break
+            }
+        }
+        let ty = ty.split('<').next().unwrap_or(ty);
+        ty.rsplit("::").next().unwrap_or(ty).trim().to_string()
+    }
+}
+
+struct Attr {
+    name: String,
+    args: Vec<Token>,
+}
+
+/// The types deriving `Live` in a tokenized Rust file.
+pub fn scan_rust_types(tokens: &[Token]) -> Vec<RustType> {
+    let mut types = Vec::new();
+    let mut attrs = Vec::new();
+    let mut doc = String::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if token.token.is_comment() {
+            push_doc(&mut doc, token);
+            index += 1;
+            continue;
+        }
+        if let Some((attr, next)) = parse_attr(tokens, index) {
+            attrs.extend(attr);
+            index = next;
+            continue;
+        }
+        if token.is_keyword("pub") {
+            index = skip_visibility(tokens, index);
+            continue;
+        }
+        if (token.is_keyword("struct") || token.is_keyword("enum")) && derives_live(&attrs) {
+            let is_struct = token.is_keyword("struct");
+            if let Some((ty, next)) = parse_type(tokens, index + 1, is_struct, std::mem::take(&mut doc)) {
+                types.push(ty);
+                index = next;
+            }
+            else {
+                index += 1;
+            }
+            attrs.clear();
+            continue;
+        }
+        attrs.clear();
+        doc.clear();
+        index += 1;
+    }
+    types
+}
+
+fn push_doc(doc: &mut String, token: &Token) {
+    if let Some(line) = token.text.strip_prefix("///") {
+        if !doc.is_empty() {
+            doc.push('\n');
+        }
+        doc.push_str(line.strip_prefix(' ').unwrap_or(line));
+    }
+}
+
+/// Parses `#[name(args)]`, returning `None` in place of the attribute for
+/// inner ones.
+fn parse_attr(tokens: &[Token], index: usize) -> Option<(Option<Attr>, usize)> {
+    if !tokens[index].is_punct("#") {
+        return None
+    }
+    let mut open = index + 1;
+    let inner = tokens.get(open)?.is_punct("!");
+    if inner {
+        open += 1;
+    }
+    if !tokens.get(open)?.is_open(Delim::Bracket) {
+        return None
+    }
+    let close = matching_close(tokens, open);
+    if inner || open + 1 >= close {
+        return Some((None, close + 1))
+    }
+    let name = tokens[open + 1].text.clone();
+    let args = if tokens.get(open + 2).is_some_and( | t | t.is_open(Delim::Paren)) {
+        tokens[open + 3..close.saturating_sub(1).max(open + 3)].to_vec()
+    }
+    else {
+        Vec::new()
+    };
+    Some((Some(Attr {name, args}), close + 1))
+}
+
+fn skip_visibility(tokens: &[Token], index: usize) -> usize {
+    if tokens.get(index + 1).is_some_and( | t | t.is_open(Delim::Paren)) {
+        matching_close(tokens, index + 1) + 1
+    }
+    else {
+        index + 1
+    }
+}
+
+fn derives_live(attrs: &[Attr]) -> bool {
+    attrs.iter().any( | attr | attr.name == "derive" && attr.args.iter().any( | t | t.is_keyword("Live")))
+}
+
+fn parse_type(tokens: &[Token], index: usize, is_struct: bool, doc: String) -> Option<(RustType, usize)> {
+    let name = tokens.get(index).filter( | t | t.is_ident())?;
+    // skip generics and where clauses up to the body
+    let mut open = index + 1;
+    while open < tokens.len() && !tokens[open].is_open(Delim::Brace) {
+        if tokens[open].is_punct(";") || tokens[open].is_open(Delim::Paren) {
+            return None
+        }
+        open += 1;
+    }
+    if open >= tokens.len() {
+        return None
+    }
+    let close = matching_close(tokens, open);
+    let items = split_items(&tokens[open + 1..close.min(tokens.len())]);
+    let kind = if is_struct {
+        RustTypeKind::Struct(items.iter().filter_map( | item | parse_field(item)).collect())
+    }
+    else {
+        RustTypeKind::Enum(items.iter().filter_map( | item | parse_variant(item)).collect())
+    };
+    Some((RustType {
+        name: name.text.clone(),
+        doc,
+        pos: name.start,
+        kind
+    }, close + 1))
+}
+
+/// Splits the tokens of a struct or enum body on the commas between items.
+fn split_items(tokens: &[Token]) -> Vec<&[Token]> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match &token.token {
+            FullToken::Open(_) => depth += 1,
+            FullToken::Close(_) => depth -= 1,
+            FullToken::Punct(_) if token.text == "<" => depth += 1,
+            FullToken::Punct(_) if token.text == ">" => depth -= 1,
+            FullToken::Punct(_) if token.text == ">>" => depth -= 2,
+            FullToken::Punct(_) if token.text == "," && depth == 0 => {
+                items.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => ()
+        }
+    }
+    if start < tokens.len() {
+        items.push(&tokens[start..]);
+    }
+    items
+}
+
+/// Leading doc comments and attributes of an item, and where the rest starts.
+fn item_prefix(tokens: &[Token]) -> (String, Vec<Attr>, usize) {
+    let mut doc = String::new();
+    let mut attrs = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        if tokens[index].token.is_comment() {
+            push_doc(&mut doc, &tokens[index]);
+            index += 1;
+        }
+        else if let Some((attr, next)) = parse_attr(tokens, index) {
+            attrs.extend(attr);
+            index = next;
+        }
+        else {
+            break
+        }
+    }
+    (doc, attrs, index)
+}
+
+fn parse_field(tokens: &[Token]) -> Option<RustField> {
+    let (doc, attrs, mut index) = item_prefix(tokens);
+    let (kind, attr) = attrs.iter().find_map( | attr | {
+        let kind = match attr.name.as_str() {
+            "live" => RustFieldKind::Live,
+            "calc" => RustFieldKind::Calc,
+            "animator" => RustFieldKind::Animator,
+            "walk" => RustFieldKind::Walk,
+            "layout" => RustFieldKind::Layout,
+            "deref" => RustFieldKind::Deref,
+            _ => return None
+        };
+        Some((kind, attr))
+    }) ?;
+    if tokens.get(index)?.is_keyword("pub") {
+        index = skip_visibility(tokens, index);
+    }
+    let name = tokens.get(index).filter( | t | t.is_ident())?;
+    if !tokens.get(index + 1)?.is_punct(":") {
+        return None
+    }
+    Some(RustField {
+        name: name.text.clone(),
+        ty: join(&tokens[index + 2..]),
+        kind,
+        default: if attr.args.is_empty() {None} else {Some(join(&attr.args))},
+        doc,
+        pos: name.start,
+    })
+}
+
+fn parse_variant(tokens: &[Token]) -> Option<RustVariant> {
+    let (doc, attrs, index) = item_prefix(tokens);
+    let name = tokens.get(index).filter( | t | t.is_ident())?;
+    let payload = match tokens.get(index + 1) {
+        Some(t) if t.is_open(Delim::Paren) || t.is_open(Delim::Brace) => {
+            join(&tokens[index + 1..(matching_close(tokens, index + 1) + 1).min(tokens.len())])
+        }
+        _ => String::new()
+    };
+    Some(RustVariant {
+        name: name.text.clone(),
+        payload,
+        pick: attrs.iter().any( | attr | attr.name == "pick"),
+        doc,
+        pos: name.start,
+    })
+}
@@ -0,0 +1,468 @@
+//! The index of every Rust file under the workspace roots. The `live_design!`
+//! bodies are loaded into a `LiveRegistry`, which parses and expands them, and
+//! names in the DSL are resolved through it the way the app would: the file
+//! itself first, then its imports. The Rust types deriving `Live` only exist
+//! in the registry of a running app, so those are indexed from the source.
+
+use {
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs,
+        path::{Path, PathBuf},
+    },
+    crate::{
+        dsl_index::{is_type_marker, scan_dsl, DslBase, DslDef, DslFile, DslKey},
+        lexer::{lex, live_design_body, offset},
+        rust_index::{scan_rust_types, RustField, RustFieldKind, RustType},
+    },
+    makepad_live_compiler::{
+        live_node_vec::LiveNodeSliceApi,
+        LiveFileId, LiveId, LiveModuleId, LiveRegistry, LiveTypeInfo, LiveValue, TextPos,
+    },
+    makepad_toml_parser::Toml,
+};
+
+const MAX_DEPTH: usize = 16;
+
+pub struct FileIndex {
+    pub crate_name: String,
+    /// the module path the registry knows this file by, like `makepad_widgets::button`
+    pub module: String,
+    pub types: Vec<RustType>,
+    pub dsl: Option<DslFile>,
+}
+
+/// What a brace in the DSL is the body of.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Owner {
+    /// the top level of `live_design!`
+    Root,
+    /// `<Class> {..}`
+    Class(String),
+    /// `{{Type}} {..}`
+    Type(String),
+    /// `field: {..}` inside another owner
+    Field(Box<Owner>, String),
+    /// a shader function body
+    Code,
+    /// anything the index can't say anything about, like `Forward {..}`
+    Other,
+}
+
+/// One step of the chain an owner resolves to, from the most derived class
+/// down to the Rust type.
+#[derive(Clone, Copy)]
+pub enum Resolved<'a> {
+    Def(&'a Path, &'a DslDef),
+    Type(&'a Path, &'a RustType),
+}
+
+/// A property that can be set on an owner.
+#[derive(Clone, Copy)]
+pub enum Prop<'a> {
+    Field(&'a Path, &'a RustType, &'a RustField),
+    Key(&'a Path, &'a DslDef, &'a DslKey),
+}
+
+impl<'a> Prop<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            Prop::Field(_, _, field) => &field.name,
+            Prop::Key(_, _, key) => &key.name,
+        }
+    }
+}
+
+pub struct Workspace {
+    pub files: BTreeMap<PathBuf, FileIndex>,
+    crates: HashMap<PathBuf, Option<(String, PathBuf)>>,
+    /// holds the `live_design!` bodies by file path
+    registry: LiveRegistry,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        let mut registry = LiveRegistry::default();
+        // the expander looks up the type of every `{{Type}}`
+        let info = placeholder_type_info();
+        registry.live_type_infos.insert(info.live_type, info);
+        Self {
+            files: BTreeMap::new(),
+            crates: HashMap::new(),
+            registry,
+        }
+    }
+}
+
+/// Stands in for the Rust type of a `{{Type}}` marker, which the parser only
+/// needs to be there.
+pub fn placeholder_type_info() -> LiveTypeInfo {
+    LiveTypeInfo {
+        live_type: std::any::TypeId::of::<()>(),
+        type_name: LiveId(0),
+        module_id: LiveModuleId(LiveId(0), LiveId(0)),
+        live_ignore: true,
+        fields: Vec::new(),
+    }
+}
+
+impl Workspace {
+    /// Indexes the Rust files under `root`, leaving out build output and
+    /// hidden directories.
+    pub fn scan(&mut self, root: &Path) {
+        self.scan_dir(root);
+        self.expand();
+    }
+
+    fn scan_dir(&mut self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return
+        };
+        let mut paths: Vec<PathBuf> = entries.filter_map( | e | e.ok().map( | e | e.path())).collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_name().map( | n | n.to_string_lossy().to_string()).unwrap_or_default();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                    self.scan_dir(&path);
+                }
+            }
+            else if name.ends_with(".rs") {
+                if let Ok(source) = fs::read_to_string(&path) {
+                    self.index_file(&path, &source);
+                }
+            }
+        }
+    }
+
+    pub fn update_file(&mut self, path: &Path, source: &str) {
+        self.index_file(path, source);
+        self.expand();
+    }
+
+    pub fn remove_file(&mut self, path: &Path) {
+        self.files.remove(path);
+        self.unload(path);
+        self.expand();
+    }
+
+    fn index_file(&mut self, path: &Path, source: &str) {
+        let tokens = lex(source);
+        let types = scan_rust_types(&tokens);
+        let (crate_name, module) = self.module_path(path);
+        let dsl = match live_design_body(&tokens) {
+            Some(body) => {
+                let start = tokens[body.start - 1].end;
+                let end = match tokens.get(body.end) {
+                    Some(close) => offset(source, close.start),
+                    None => source.len()
+                };
+                let markers = (body.clone()).filter( | index | is_type_marker(&tokens, *index)).count();
+                match self.load(path, &module, &source[offset(source, start)..end], markers) {
+                    Some(file_id) => Some(scan_dsl(&self.registry.file_id_to_file(file_id).original, start)),
+                    // keep what was there while the body doesn't parse
+                    None => self.files.get(path).and_then( | index | index.dsl.clone())
+                }
+            }
+            None => {
+                self.unload(path);
+                None
+            }
+        };
+        if types.is_empty() && dsl.is_none() {
+            self.files.remove(path);
+            return
+        }
+        self.files.insert(path.to_path_buf(), FileIndex {crate_name, module, types, dsl});
+    }
+
+    /// Loads a `live_design!` body into the registry, returning its file id
+    /// if it parses.
+    fn load(&mut self, path: &Path, module: &str, body: &str, markers: usize) -> Option<LiveFileId> {
+        let file_name = path.to_string_lossy();
+        let module_id = LiveModuleId::from_str(module).ok()?;
+        let infos = vec![placeholder_type_info(); markers];
+        match self.registry.file_ids().get(file_name.as_ref()).cloned() {
+            Some(file_id) => {
+                self.registry.file_id_to_file_mut(file_id).live_type_infos = infos;
+                self.registry.load_live_document(&file_name, module_id, body).ok()
+            }
+            // the registry holds one document per module
+            None if self.registry.module_id_to_file_id.contains_key(&module_id) => None,
+            None => self.registry.register_live_file(&file_name, "", module_id, body.to_string(), infos, TextPos::default()).ok()
+        }
+    }
+
+    /// Empties the document of a file that no longer has a `live_design!`.
+    fn unload(&mut self, path: &Path) {
+        let file_name = path.to_string_lossy();
+        if let Some(file_id) = self.registry.file_ids().get(file_name.as_ref()).cloned() {
+            let module_id = self.registry.file_id_to_file(file_id).module_id;
+            let _ = self.registry.load_live_document(&file_name, module_id, "");
+        }
+    }
+
+    /// Expands the documents that changed and the ones importing them, which
+    /// is what imports are resolved against.
+    fn expand(&mut self) {
+        let mut errors = Vec::new();
+        self.registry.expand_all_documents(&mut errors);
+    }
+
+    /// The crate name and module path of a file, from the nearest Cargo.toml.
+    fn module_path(&mut self, path: &Path) -> (String, String) {
+        let stem = path.file_stem().map( | s | s.to_string_lossy().to_string()).unwrap_or_default();
+        let Some((crate_name, crate_dir)) = path.parent().and_then( | dir | self.find_crate(dir)) else {
+            return (stem.clone(), stem)
+        };
+        let src = crate_dir.join("src");
+        let Ok(relative) = path.strip_prefix(&src) else {
+            return (crate_name.clone(), format!("{}::{}", crate_name, stem))
+        };
+        let mut module = crate_name.clone();
+        let components: Vec<String> = relative.components().map( | c | c.as_os_str().to_string_lossy().to_string()).collect();
+        for (index, component) in components.iter().enumerate() {
+            let last = index + 1 == components.len();
+            let name = if last {
+                match stem.as_str() {
+                    "lib" | "main" | "mod" => continue,
+                    _ => stem.as_str()
+                }
+            }
+            else {
+                component.as_str()
+            };
+            module.push_str("::");
+            module.push_str(name);
+        }
+        (crate_name, module)
+    }
+
+    fn find_crate(&mut self, dir: &Path) -> Option<(String, PathBuf)> {
+        if let Some(found) = self.crates.get(dir) {
+            return found.clone()
+        }
+        let manifest = dir.join("Cargo.toml");
+        let found = match fs::read_to_string(&manifest).ok().and_then( | s | makepad_toml_parser::parse_toml(&s).ok()) {
+            Some(toml) => {
+                let name = ["lib.name", "package.name"].iter().find_map( | key | match toml.get(*key) {
+                    Some(Toml::Str(name, _)) => Some(name.replace('-', "_")),
+                    _ => None
+                });
+                match name {
+                    Some(name) => Some((name, dir.to_path_buf())),
+                    // a workspace manifest, keep looking upwards
+                    None => dir.parent().and_then( | parent | self.find_crate(parent))
+                }
+            }
+            None => dir.parent().and_then( | parent | self.find_crate(parent))
+        };
+        self.crates.insert(dir.to_path_buf(), found.clone());
+        found
+    }
+
+    /// Finds the definition `<name>` refers to from `from`. With `skip_local`
+    /// the file itself is left out, for `Name = <Name> {..}` overriding an
+    /// imported definition.
+    pub fn find_def(&self, from: &Path, name: &str, skip_local: bool) -> Option<(&Path, &DslDef)> {
+        if !skip_local {
+            if let Some(found) = self.def_in(from, name) {
+                return Some(found)
+            }
+        }
+        let id = LiveId::from_str(name);
+        for (import_id, module_id) in self.imports(from) {
+            let target = if import_id == LiveId::empty() {id} else if import_id == id {self.import_target(from, id)} else {continue};
+            let Some(ptr) = self.registry.module_id_and_name_to_ptr(module_id, target) else {
+                continue
+            };
+            let path = Path::new(self.registry.file_id_to_file_name(ptr.file_id));
+            if let Some(found) = self.def_in(path, &target.to_string()) {
+                return Some(found)
+            }
+        }
+        // not imported, which the registry would reject; still point somewhere useful
+        self.files.iter()
+            .filter( | (path, _) | path.as_path() != from)
+            .find_map( | (path, index) | index.dsl.as_ref()?.defs.iter().find( | d | d.name == name).map( | def | (path.as_path(), def)))
+    }
+
+    /// The imports of a file as the registry parsed them: the name they bring
+    /// in, empty for `*`, and the module.
+    fn imports(&self, path: &Path) -> Vec<(LiveId, LiveModuleId)> {
+        let Some(file_id) = self.registry.file_ids().get(path.to_string_lossy().as_ref()) else {
+            return Vec::new()
+        };
+        let nodes = &self.registry.file_id_to_file(*file_id).original.nodes;
+        let mut imports = Vec::new();
+        let mut child = if nodes.is_empty() {None} else {nodes.first_child(0)};
+        while let Some(index) = child {
+            if let LiveValue::Import(import) = &nodes[index].value {
+                imports.push((nodes[index].id, import.module_id));
+            }
+            child = nodes.next_child(index);
+        }
+        imports
+    }
+
+    /// The name `import module::Name as Alias` imports for `Alias`.
+    fn import_target(&self, path: &Path, alias: LiveId) -> LiveId {
+        let Some(file_id) = self.registry.file_ids().get(path.to_string_lossy().as_ref()) else {
+            return alias
+        };
+        self.registry.file_id_to_file(*file_id).original.nodes.iter().find_map( | node | match &node.value {
+            LiveValue::Import(import) if node.id == alias => Some(import.import_id),
+            _ => None
+        }).unwrap_or(alias)
+    }
+
+    fn def_in(&self, path: &Path, name: &str) -> Option<(&Path, &DslDef)> {
+        let (path, index) = self.files.get_key_value(path)?;
+        let def = index.dsl.as_ref()?.defs.iter().find( | d | d.name == name)?;
+        Some((path.as_path(), def))
+    }
+
+    /// Finds the Rust type `name` from `from`: the file, then its crate, then anywhere.
+    pub fn find_type(&self, from: &Path, name: &str) -> Option<(&Path, &RustType)> {
+        let crate_name = self.files.get(from).map( | f | f.crate_name.as_str());
+        let find = | filter: &dyn Fn(&Path, &FileIndex) -> bool | {
+            self.files.iter()
+                .filter( | (path, index) | filter(path, index))
+                .find_map( | (path, index) | index.types.iter().find( | t | t.name == name).map( | ty | (path.as_path(), ty)))
+        };
+        find(&| path, _ | path == from)
+            .or_else( || find(&| _, index | Some(index.crate_name.as_str()) == crate_name))
+            .or_else( || find(&| _, _ | true))
+    }
+
+    /// The definitions `<..>` can refer to from `from`.
+    pub fn visible_defs(&self, from: &Path) -> Vec<(&Path, &DslDef)> {
+        let mut defs = Vec::new();
+        let Some((path, index)) = self.files.get_key_value(from) else {
+            return defs
+        };
+        for def in index.dsl.iter().flat_map( | dsl | dsl.defs.iter()) {
+            defs.push((path.as_path(), def));
+        }
+        for (import_id, module_id) in self.imports(from) {
+            let Some(file_id) = self.registry.module_id_to_file_id(module_id) else {
+                continue
+            };
+            let Some((path, index)) = self.files.get_key_value(Path::new(self.registry.file_id_to_file_name(file_id))) else {
+                continue
+            };
+            let target = self.import_target(from, import_id);
+            for def in index.dsl.iter().flat_map( | dsl | dsl.defs.iter()) {
+                if import_id == LiveId::empty() || LiveId::from_str(&def.name) == target {
+                    defs.push((path.as_path(), def));
+                }
+            }
+        }
+        defs
+    }
+
+    /// The chain of definitions and types an owner at `from` stands for.
+    pub fn resolve(&self, from: &Path, owner: &Owner) -> Vec<Resolved<'_>> {
+        let mut chain = Vec::new();
+        self.resolve_into(from, owner, &mut chain, 0);
+        chain
+    }
+
+    fn resolve_into<'a>(&'a self, from: &Path, owner: &Owner, chain: &mut Vec<Resolved<'a >>, depth: usize) {
+        if depth > MAX_DEPTH {
+            return
+        }
+        match owner {
+            Owner::Class(name) => {
+                if let Some((path, def)) = self.find_def(from, name, false) {
+                    self.def_chain(path, def, chain, depth);
+                }
+            }
+            Owner::Type(name) => {
+                if let Some((path, ty)) = self.find_type(from, name) {
+                    chain.push(Resolved::Type(path, ty));
+                }
+            }
+            Owner::Field(parent, name) => {
+                let parent_chain = self.resolve(from, parent);
+                let field = self.props(&parent_chain).into_iter().find_map( | prop | match prop {
+                    Prop::Field(path, _, field) if field.name == *name => Some((path, field)),
+                    _ => None
+                });
+                if let Some((path, field)) = field {
+                    if let Some((path, ty)) = self.find_type(path, &field.value_type()) {
+                        chain.push(Resolved::Type(path, ty));
+                    }
+                }
+            }
+            Owner::Root | Owner::Code | Owner::Other => ()
+        }
+    }
+
+    /// A definition followed by the classes it inherits from.
+    pub fn def_chain<'a>(&'a self, path: &'a Path, def: &'a DslDef, chain: &mut Vec<Resolved<'a >>, depth: usize) {
+        if depth > MAX_DEPTH {
+            return
+        }
+        chain.push(Resolved::Def(path, def));
+        match &def.base {
+            DslBase::Class(base) => {
+                if let Some((base_path, base_def)) = self.find_def(path, base, *base == def.name) {
+                    self.def_chain(base_path, base_def, chain, depth + 1);
+                }
+            }
+            DslBase::Type(ty) => {
+                if let Some((ty_path, ty)) = self.find_type(path, ty) {
+                    chain.push(Resolved::Type(ty_path, ty));
+                }
+            }
+            DslBase::Value => ()
+        }
+    }
+
+    /// Every property that can be set on a resolved chain, most derived first.
+    /// The same name can appear more than once.
+    pub fn props<'a>(&'a self, chain: &[Resolved<'a>]) -> Vec<Prop<'a >> {
+        let mut props = Vec::new();
+        for resolved in chain {
+            match *resolved {
+                Resolved::Def(path, def) => {
+                    for key in &def.keys {
+                        props.push(Prop::Key(path, def, key));
+                    }
+                }
+                Resolved::Type(path, ty) => self.type_props(path, ty, &mut props, 0),
+            }
+        }
+        props
+    }
+
+    /// The fields of a type as the DSL sees them, with walk, layout and
+    /// deref fields replaced by the fields they bring in.
+    fn type_props<'a>(&'a self, path: &'a Path, ty: &'a RustType, props: &mut Vec<Prop<'a >>, depth: usize) {
+        if depth > MAX_DEPTH {
+            return
+        }
+        for field in ty.fields() {
+            match field.kind {
+                RustFieldKind::Live | RustFieldKind::Calc | RustFieldKind::Animator => {
+                    props.push(Prop::Field(path, ty, field));
+                }
+                RustFieldKind::Walk | RustFieldKind::Layout | RustFieldKind::Deref => {
+                    if let Some((inner_path, inner)) = self.find_type(path, &field.value_type()) {
+                        self.type_props(inner_path, inner, props, depth + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The type a property's value has, if it is a Live type in the index.
+    pub fn prop_type<'a>(&'a self, prop: &Prop<'a>) -> Option<(&'a Path, &'a RustType)> {
+        match *prop {
+            Prop::Field(path, _, field) => self.find_type(path, &field.value_type()),
+            Prop::Key(..) => None
+        }
+    }
+
+}
@@ -0,0 +1,389 @@
+use {
+    std::{
+        fs,
+        io::{BufRead, BufReader, Read, Write},
+        path::PathBuf,
+        process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    },
+    makepad_live_compiler::makepad_micro_serde::*,
+};
+
+const SAMPLE: &str = r#"use makepad_widgets::*;
+
+live_design!{
+    Badge = {{Badge}} {
+        label: "new"
+        shape: Round
+    }
+
+    RedBadge = <Badge> {
+        // overrides the color only
+        color: #f00
+    }
+}
+
+/// A small label drawn on top of a button.
+#[derive(Live, LiveHook)]
+pub struct Badge {
+    /// The text shown in the badge.
+    #[live] label: String,
+    #[live] shape: BadgeShape,
+    #[live] color: Vec4,
+}
+
+#[derive(Live, LiveHook)]
+pub enum BadgeShape {
+    /// Rounded at both ends.
+    #[pick] Round,
+    Square,
+}
+"#;
+
+/// A crate on disk holding `SAMPLE`, removed again when dropped.
+struct SampleCrate {
+    root: PathBuf,
+}
+
+impl SampleCrate {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("makepad_live_lsp_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"sample\"\nversion = \"0.1.0\"\n").unwrap();
+        fs::write(root.join("src/lib.rs"), SAMPLE).unwrap();
+        Self {root}
+    }
+
+    /// Adds a file next to `src/lib.rs`, returning its uri.
+    fn write(&self, name: &str, text: &str) -> String {
+        let path = self.root.join("src").join(name);
+        fs::write(&path, text).unwrap();
+        format!("file://{}", path.display())
+    }
+
+    fn lib_uri(&self) -> String {
+        format!("file://{}", self.root.join("src/lib.rs").display())
+    }
+}
+
+impl Drop for SampleCrate {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Talks to the server binary over stdio, like an editor would.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    /// notifications received while waiting for responses
+    notifications: Vec<JsonValue>,
+}
+
+impl Client {
+    fn start(sample: &SampleCrate) -> Self {
+        Self::start_with(sample, "{}").0
+    }
+
+    /// Starts the server with the given client capabilities, returning what
+    /// it answered to `initialize` as well.
+    fn start_with(sample: &SampleCrate, capabilities: &str) -> (Self, JsonValue) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_makepad-live-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Self {child, stdin, stdout, next_id: 1, notifications: Vec::new()};
+        let root = json_string(&format!("file://{}", sample.root.display()));
+        let result = client.request("initialize", &format!(r#"{{"rootUri":{},"capabilities":{}}}"#, root, capabilities));
+        client.notify("initialized", "{}");
+        (client, result)
+    }
+
+    fn send(&mut self, body: &str) {
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> JsonValue {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            assert!(self.stdout.read_line(&mut line).unwrap() > 0, "server closed its output");
+            let line = line.trim_end();
+            if line.is_empty() {
+                break
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        JsonValue::deserialize_json(std::str::from_utf8(&body).unwrap()).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: &str) {
+        self.send(&format!(r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#, method, params));
+    }
+
+    fn request(&mut self, method: &str, params: &str) -> JsonValue {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&format!(r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#, id, method, params));
+        loop {
+            let message = self.receive();
+            match message.key("id") {
+                Some(JsonValue::U64(v)) if *v == id => {
+                    assert!(message.key("error").is_none(), "{} failed: {:?}", method, message);
+                    return message.key("result").cloned().unwrap_or(JsonValue::Null)
+                }
+                _ => self.notifications.push(message)
+            }
+        }
+    }
+
+    /// Waits for the next diagnostics published for `uri`.
+    fn diagnostics(&mut self, uri: &str) -> Vec<JsonValue> {
+        loop {
+            let message = match self.notifications.is_empty() {
+                true => self.receive(),
+                false => self.notifications.remove(0),
+            };
+            let params = message.key("params");
+            if message.key("method").and_then( | m | m.string()).map(String::as_str) == Some("textDocument/publishDiagnostics")
+                && params.and_then( | p | p.key("uri")).and_then( | u | u.string()).map(String::as_str) == Some(uri) {
+                match params.and_then( | p | p.key("diagnostics")) {
+                    Some(JsonValue::Array(diagnostics)) => return diagnostics.clone(),
+                    _ => panic!("diagnostics aren't an array: {:?}", message)
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) {
+        self.notify("textDocument/didOpen", &format!(
+            r#"{{"textDocument":{{"uri":{},"languageId":"rust","version":1,"text":{}}}}}"#,
+            json_string(uri),
+            json_string(text)
+        ));
+    }
+
+    fn change(&mut self, uri: &str, text: &str) {
+        self.notify("textDocument/didChange", &format!(
+            r#"{{"textDocument":{{"uri":{},"version":2}},"contentChanges":[{{"text":{}}}]}}"#,
+            json_string(uri),
+            json_string(text)
+        ));
+    }
+
+    fn at(&mut self, method: &str, uri: &str, (line, character): (u32, u32)) -> JsonValue {
+        self.request(method, &format!(
+            r#"{{"textDocument":{{"uri":{}}},"position":{{"line":{},"character":{}}}}}"#,
+            json_string(uri),
+            line,
+            character
+        ))
+    }
+
+    fn hover(&mut self, uri: &str, pos: (u32, u32)) -> String {
+        let result = self.at("textDocument/hover", uri, pos);
+        result.key("contents").and_then( | c | c.key("value")).and_then( | v | v.string()).cloned()
+            .unwrap_or_else( || panic!("no hover at {:?}: {:?}", pos, result))
+    }
+
+    /// Returns the uri and the start of the range a definition points at.
+    fn definition(&mut self, uri: &str, pos: (u32, u32)) -> (String, (u32, u32)) {
+        let result = self.at("textDocument/definition", uri, pos);
+        let target = result.key("uri").and_then( | u | u.string()).cloned()
+            .unwrap_or_else( || panic!("no definition at {:?}: {:?}", pos, result));
+        let start = result.key("range").and_then( | r | r.key("start")).unwrap();
+        (target, (number(start.key("line")), number(start.key("character"))))
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let _ = self.child.kill();
+            return
+        }
+        self.request("shutdown", "null");
+        self.notify("exit", "null");
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn number(value: Option<&JsonValue>) -> u32 {
+    match value {
+        Some(JsonValue::U64(v)) => *v as u32,
+        value => panic!("not a number: {:?}", value)
+    }
+}
+
+/// The line and column of the `nth` occurrence of `needle` in `text`, in
+/// UTF-16 code units like the server counts them by default.
+fn find(text: &str, needle: &str, nth: usize) -> (u32, u32) {
+    let (line, before) = line_before(text, needle, nth);
+    (line, before.encode_utf16().count() as u32)
+}
+
+/// Like `find`, counting characters.
+fn find_chars(text: &str, needle: &str, nth: usize) -> (u32, u32) {
+    let (line, before) = line_before(text, needle, nth);
+    (line, before.chars().count() as u32)
+}
+
+fn line_before<'a>(text: &'a str, needle: &str, nth: usize) -> (u32, &'a str) {
+    let (offset, _) = text.match_indices(needle).nth(nth).unwrap();
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    (line as u32, &before[before.rfind('\n').map( | i | i + 1).unwrap_or(0)..])
+}
+
+#[test]
+fn hover_describes_fields_variants_and_classes() {
+    let sample = SampleCrate::new("hover");
+    let uri = sample.lib_uri();
+    let mut client = Client::start(&sample);
+    client.open(&uri, SAMPLE);
+
+    let field = client.hover(&uri, find(SAMPLE, "label: \"new\"", 0));
+    assert!(field.contains("label: String"), "{}", field);
+    assert!(field.contains("field of `Badge`"), "{}", field);
+    assert!(field.contains("The text shown in the badge."), "{}", field);
+
+    let (line, column) = find(SAMPLE, "Round", 0);
+    let variant = client.hover(&uri, (line, column + 2));
+    assert!(variant.contains("BadgeShape::Round"), "{}", variant);
+    assert!(variant.contains("Rounded at both ends."), "{}", variant);
+
+    let (line, column) = find(SAMPLE, "<Badge>", 0);
+    let class = client.hover(&uri, (line, column + 1));
+    assert!(class.contains("Badge = {{Badge}}"), "{}", class);
+    assert!(class.contains("in `sample`"), "{}", class);
+    assert!(class.contains("A small label drawn on top of a button."), "{}", class);
+
+    // Nothing is said about Rust code outside the DSL.
+    assert!(matches!(client.at("textDocument/hover", &uri, find(SAMPLE, "makepad_widgets", 0)), JsonValue::Null));
+}
+
+#[test]
+fn definition_jumps_to_rust_and_dsl_declarations() {
+    let sample = SampleCrate::new("definition");
+    let uri = sample.lib_uri();
+    let mut client = Client::start(&sample);
+    client.open(&uri, SAMPLE);
+
+    let (line, column) = find(SAMPLE, "<Badge>", 0);
+    assert_eq!(client.definition(&uri, (line, column + 1)), (uri.clone(), find(SAMPLE, "Badge = ", 0)));
+
+    let (line, column) = find(SAMPLE, "{{Badge}}", 0);
+    assert_eq!(client.definition(&uri, (line, column + 2)), (uri.clone(), find(SAMPLE, "Badge {", 0)));
+
+    // Fields set on a class that inherits them still lead to the Rust field.
+    assert_eq!(client.definition(&uri, find(SAMPLE, "color: #f00", 0)), (uri.clone(), find(SAMPLE, "color: Vec4", 0)));
+    assert_eq!(client.definition(&uri, find(SAMPLE, "shape: Round", 0)), (uri.clone(), find(SAMPLE, "shape: BadgeShape", 0)));
+
+    let (line, column) = find(SAMPLE, "Round", 0);
+    assert_eq!(client.definition(&uri, (line, column)), (uri.clone(), find(SAMPLE, "Round,", 0)));
+}
+
+#[test]
+fn diagnostics_follow_the_open_document() {
+    let sample = SampleCrate::new("diagnostics");
+    let uri = sample.lib_uri();
+    let mut client = Client::start(&sample);
+    client.open(&uri, SAMPLE);
+    assert!(client.diagnostics(&uri).is_empty());
+
+    let broken = SAMPLE.replace("label: \"new\"", "label: \"new\" ]");
+    client.change(&uri, &broken);
+    let diagnostics = client.diagnostics(&uri);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let start = diagnostics[0].key("range").and_then( | r | r.key("start")).unwrap();
+    assert_eq!(number(start.key("line")), find(&broken, "]", 0).0);
+    assert_eq!(diagnostics[0].key("source").and_then( | s | s.string()).map(String::as_str), Some("live_design"));
+
+    client.change(&uri, SAMPLE);
+    assert!(client.diagnostics(&uri).is_empty());
+
+    // Closing the document clears its diagnostics.
+    client.notify("textDocument/didClose", &format!(r#"{{"textDocument":{{"uri":{}}}}}"#, json_string(&uri)));
+    assert!(client.diagnostics(&uri).is_empty());
+}
+
+#[test]
+fn definitions_follow_the_imports_of_the_dsl() {
+    let sample = SampleCrate::new("imports");
+    sample.write("theme.rs", "live_design!{\n    Pill = {{Badge}} {shape: Square}\n}\n");
+    // the same name, which isn't imported
+    sample.write("other.rs", "live_design!{\n    Pill = {{Badge}} {}\n}\n");
+    let theme_uri = format!("file://{}", sample.root.join("src/theme.rs").display());
+    let app = "live_design!{\n    import crate::theme::*;\n    import crate::theme::Pill as Chip;\n    Tag = <Pill> {}\n    Sticker = <Chip> {}\n}\n";
+    let app_uri = sample.write("app.rs", app);
+    let mut client = Client::start(&sample);
+    client.open(&app_uri, app);
+
+    let (line, column) = find(app, "<Pill>", 0);
+    assert_eq!(client.definition(&app_uri, (line, column + 1)), (theme_uri.clone(), (1, 4)));
+    let (line, column) = find(app, "<Chip>", 0);
+    assert_eq!(client.definition(&app_uri, (line, column + 1)), (theme_uri.clone(), (1, 4)));
+    let hover = client.hover(&app_uri, (line, column + 1));
+    assert!(hover.contains("Pill = {{Badge}}"), "{}", hover);
+    assert!(hover.contains("in `sample::theme`"), "{}", hover);
+
+    // Editing the imported file moves the definition with it.
+    client.open(&theme_uri, "live_design!{\n\n    Pill = {{Badge}} {}\n}\n");
+    assert_eq!(client.definition(&app_uri, (line, column + 1)), (theme_uri.clone(), (2, 4)));
+}
+
+#[test]
+fn positions_are_counted_in_utf16_unless_the_client_counts_characters() {
+    let sample = SampleCrate::new("encoding");
+    let uri = sample.lib_uri();
+    // characters outside the basic plane take two UTF-16 code units
+    let text = SAMPLE
+        .replace("    Badge = {{Badge}}", "    /* 🎉 */ Badge = {{Badge}}")
+        .replace("label: \"new\"\n        shape: Round", "label: \"🎉\" shape: Round");
+
+    let (mut client, result) = Client::start_with(&sample, "{}");
+    let encoding = result.key("capabilities").and_then( | c | c.key("positionEncoding")).and_then( | e | e.string()).cloned();
+    assert_eq!(encoding.as_deref(), Some("utf-16"));
+    client.open(&uri, &text);
+    let (line, column) = find(&text, "Round", 0);
+    assert_ne!((line, column), find_chars(&text, "Round", 0));
+    let variant = client.hover(&uri, (line, column + 4));
+    assert!(variant.contains("BadgeShape::Round"), "{}", variant);
+    let (line, column) = find(&text, "<Badge>", 0);
+    assert_eq!(client.definition(&uri, (line, column + 1)), (uri.clone(), find(&text, "Badge = ", 0)));
+    drop(client);
+
+    let (mut client, result) = Client::start_with(&sample, r#"{"general":{"positionEncodings":["utf-8","utf-32"]}}"#);
+    let encoding = result.key("capabilities").and_then( | c | c.key("positionEncoding")).and_then( | e | e.string()).cloned();
+    assert_eq!(encoding.as_deref(), Some("utf-32"));
+    client.open(&uri, &text);
+    let (line, column) = find_chars(&text, "Round", 0);
+    let variant = client.hover(&uri, (line, column + 4));
+    assert!(variant.contains("BadgeShape::Round"), "{}", variant);
+    let (line, column) = find_chars(&text, "<Badge>", 0);
+    assert_eq!(client.definition(&uri, (line, column + 1)), (uri.clone(), find_chars(&text, "Badge = ", 0)));
+}
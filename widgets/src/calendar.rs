@@ -0,0 +1,407 @@
+use crate::{
+    makepad_derive_widget::*,
+    makepad_draw::*,
+    widget::*,
+    date_time::{Date, Weekday},
+};
+
+live_design!{
+    DrawCalendarCell = {{DrawCalendarCell}} {}
+    CalendarBase = {{Calendar}} {}
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawCalendarCell {
+    #[deref] draw_super: DrawQuad,
+    #[live] selected: f32,
+    #[live] in_range: f32,
+    #[live] today: f32,
+    #[live] focus: f32,
+    #[live] hover: f32,
+    #[live] disabled: f32,
+    #[live] outside: f32,
+}
+
+#[derive(Copy, Clone, Debug, Live, LiveHook)]
+#[live_ignore]
+pub enum FirstDayOfWeek {
+    #[pick] Locale,
+    Monday,
+    Sunday,
+    Saturday,
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum CalendarAction {
+    Select(Date),
+    SelectRange(Date, Date),
+    MonthChanged(Date),
+    None
+}
+
+/// A month grid of six weeks with a header to page through months.
+/// With `range_selection` the first pick sets the anchor and the second one
+/// completes the range. Dates outside `min_date`/`max_date` (`YYYY-MM-DD`)
+/// are drawn disabled and can't be picked or navigated to.
+#[derive(Live, Widget)]
+pub struct Calendar {
+    #[redraw] #[live] draw_bg: DrawQuad,
+    #[live] draw_cell: DrawCalendarCell,
+    #[live] draw_day: DrawText,
+    #[live] draw_header: DrawText,
+    #[live] draw_weekday: DrawText,
+
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+
+    #[live(32.0)] cell_size: f64,
+    #[live] first_day_of_week: FirstDayOfWeek,
+    #[live] locale: String,
+    #[live] range_selection: bool,
+    #[live] min_date: String,
+    #[live] max_date: String,
+    #[live] month_names: Vec<String>,
+
+    #[rust] min: Option<Date>,
+    #[rust] max: Option<Date>,
+    #[rust] today: Date,
+    #[rust] view_month: Date,
+    #[rust] cursor: Date,
+    #[rust] selection: Option<(Date, Date)>,
+    #[rust] range_anchor: Option<Date>,
+    #[rust] hover: Option<Date>,
+    #[rust] header_rect: Rect,
+    #[rust] grid_rect: Rect,
+}
+
+impl LiveHook for Calendar {
+    fn after_new_from_doc(&mut self, _cx: &mut Cx) {
+        self.today = Date::today();
+        self.cursor = self.today.clamp_to(self.min, self.max);
+        self.view_month = self.cursor.first_of_month();
+    }
+
+    fn after_apply(&mut self, cx: &mut Cx, _apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        self.min = Date::parse_iso(&self.min_date);
+        self.max = Date::parse_iso(&self.max_date);
+        self.draw_bg.redraw(cx);
+    }
+}
+
+const GRID_ROWS: usize = 6;
+
+impl Calendar {
+
+    pub fn first_day(&self) -> Weekday {
+        match self.first_day_of_week {
+            FirstDayOfWeek::Locale => Weekday::first_day_for_locale(&self.locale),
+            FirstDayOfWeek::Monday => Weekday::Monday,
+            FirstDayOfWeek::Sunday => Weekday::Sunday,
+            FirstDayOfWeek::Saturday => Weekday::Saturday,
+        }
+    }
+
+    fn month_name(&self, month: u32) -> String {
+        const NAMES: [&str; 12] = [
+            "January", "February", "March", "April", "May", "June",
+            "July", "August", "September", "October", "November", "December"
+        ];
+        let index = (month as usize).saturating_sub(1);
+        match self.month_names.get(index) {
+            Some(name) => name.clone(),
+            None => NAMES[index % 12].to_string()
+        }
+    }
+
+    pub fn selection(&self) -> Option<(Date, Date)> {
+        self.selection
+    }
+
+    pub fn selected_date(&self) -> Option<Date> {
+        self.selection.map( | (start, _) | start)
+    }
+
+    pub fn set_selected_date(&mut self, cx: &mut Cx, date: Option<Date>) {
+        self.selection = date.map( | date | (date, date));
+        self.range_anchor = None;
+        if let Some(date) = date {
+            self.cursor = date;
+            self.view_month = date.first_of_month();
+        }
+        self.draw_bg.redraw(cx);
+    }
+
+    pub fn set_selected_range(&mut self, cx: &mut Cx, start: Date, end: Date) {
+        self.selection = Some((start.min(end), start.max(end)));
+        self.range_anchor = None;
+        self.cursor = start;
+        self.view_month = start.first_of_month();
+        self.draw_bg.redraw(cx);
+    }
+
+    pub fn show_month(&mut self, cx: &mut Cx, scope: &mut Scope, month: Date) {
+        let month = month.first_of_month();
+        if month != self.view_month {
+            self.view_month = month;
+            cx.widget_action(self.widget_uid(), &scope.path, CalendarAction::MonthChanged(month));
+            self.draw_bg.redraw(cx);
+        }
+    }
+
+    /// Moves the keyboard cursor, staying inside the bounds and
+    /// following it with the visible month.
+    fn move_cursor(&mut self, cx: &mut Cx, scope: &mut Scope, date: Date) {
+        self.cursor = date.clamp_to(self.min, self.max);
+        self.show_month(cx, scope, self.cursor);
+        self.draw_bg.redraw(cx);
+    }
+
+    fn pick(&mut self, cx: &mut Cx, scope: &mut Scope, date: Date) {
+        if !date.is_within(self.min, self.max) {
+            return
+        }
+        let uid = self.widget_uid();
+        self.cursor = date;
+        if self.range_selection {
+            if let Some(anchor) = self.range_anchor.take() {
+                let (start, end) = (anchor.min(date), anchor.max(date));
+                self.selection = Some((start, end));
+                cx.widget_action(uid, &scope.path, CalendarAction::SelectRange(start, end));
+            }
+            else {
+                self.range_anchor = Some(date);
+                self.selection = Some((date, date));
+            }
+        }
+        else {
+            self.selection = Some((date, date));
+            cx.widget_action(uid, &scope.path, CalendarAction::Select(date));
+        }
+        self.show_month(cx, scope, date);
+        self.draw_bg.redraw(cx);
+    }
+
+    fn date_at(&self, abs: DVec2) -> Option<Date> {
+        if !self.grid_rect.contains(abs) {
+            return None
+        }
+        let rel = abs - self.grid_rect.pos;
+        let col = (rel.x / self.cell_size) as i64;
+        let row = (rel.y / self.cell_size) as i64;
+        if col >= 7 || row >= GRID_ROWS as i64 {
+            return None
+        }
+        let start = self.view_month.month_grid_start(self.first_day());
+        Some(start.add_days(row * 7 + col))
+    }
+
+    fn prev_rect(&self) -> Rect {
+        Rect {pos: self.header_rect.pos, size: dvec2(self.cell_size, self.cell_size)}
+    }
+
+    fn next_rect(&self) -> Rect {
+        Rect {
+            pos: self.header_rect.pos + dvec2(self.header_rect.size.x - self.cell_size, 0.0),
+            size: dvec2(self.cell_size, self.cell_size)
+        }
+    }
+
+    fn is_in_selection(&self, date: Date) -> bool {
+        // while picking a range, preview it up to the hovered day
+        if let (Some(anchor), Some(hover)) = (self.range_anchor, self.hover) {
+            return date >= anchor.min(hover) && date <= anchor.max(hover)
+        }
+        match self.selection {
+            Some((start, end)) => date >= start && date <= end,
+            None => false
+        }
+    }
+
+    fn draw_text_centered(cx: &mut Cx2d, draw_text: &mut DrawText, rect: Rect, text: &str) {
+        cx.begin_turtle(Walk::abs_rect(rect), Layout::default().with_align_x(0.5).with_align_y(0.5));
+        draw_text.draw_walk(cx, Walk::fit(), Align::default(), text);
+        cx.end_turtle();
+    }
+
+    pub fn draw_calendar(&mut self, cx: &mut Cx2d, walk: Walk) {
+        let first_day = self.first_day();
+        let cell = self.cell_size;
+        let width = cell * 7.0;
+
+        self.draw_bg.begin(cx, walk, self.layout);
+
+        // month header with prev/next arrows in the outer cells
+        self.header_rect = cx.walk_turtle(Walk::fixed(width, cell));
+        let title = format!("{} {}", self.month_name(self.view_month.month), self.view_month.year);
+        let (prev, next) = (self.prev_rect(), self.next_rect());
+        Self::draw_text_centered(cx, &mut self.draw_header, prev, "<");
+        Self::draw_text_centered(cx, &mut self.draw_header, self.header_rect, &title);
+        Self::draw_text_centered(cx, &mut self.draw_header, next, ">");
+
+        let weekday_rect = cx.walk_turtle(Walk::fixed(width, cell * 0.75));
+        for col in 0..7 {
+            let weekday = Weekday::from_index(first_day.index() + col);
+            let rect = Rect {
+                pos: weekday_rect.pos + dvec2(col as f64 * cell, 0.0),
+                size: dvec2(cell, weekday_rect.size.y)
+            };
+            Self::draw_text_centered(cx, &mut self.draw_weekday, rect, weekday.short_name());
+        }
+
+        self.grid_rect = cx.walk_turtle(Walk::fixed(width, cell * GRID_ROWS as f64));
+        let start = self.view_month.month_grid_start(first_day);
+        let has_focus = cx.has_key_focus(self.draw_bg.area());
+        for index in 0..GRID_ROWS * 7 {
+            let date = start.add_days(index as i64);
+            let rect = Rect {
+                pos: self.grid_rect.pos + dvec2((index % 7) as f64 * cell, (index / 7) as f64 * cell),
+                size: dvec2(cell, cell)
+            };
+            let selected = self.selection.is_some_and( | (s, e) | date == s || date == e);
+            self.draw_cell.selected = if selected {1.0} else {0.0};
+            self.draw_cell.in_range = if self.is_in_selection(date) {1.0} else {0.0};
+            self.draw_cell.today = if date == self.today {1.0} else {0.0};
+            self.draw_cell.focus = if has_focus && date == self.cursor {1.0} else {0.0};
+            self.draw_cell.hover = if self.hover == Some(date) {1.0} else {0.0};
+            self.draw_cell.disabled = if date.is_within(self.min, self.max) {0.0} else {1.0};
+            self.draw_cell.outside = if date.month == self.view_month.month {0.0} else {1.0};
+            self.draw_cell.draw_abs(cx, rect);
+            Self::draw_text_centered(cx, &mut self.draw_day, rect, &date.day.to_string());
+        }
+
+        self.draw_bg.end(cx);
+        cx.add_nav_stop(self.draw_bg.area(), NavRole::DropDown, Margin::default());
+    }
+}
+
+impl Widget for Calendar {
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        match event.hits(cx, self.draw_bg.area()) {
+            Hit::FingerHoverIn(fe) | Hit::FingerHoverOver(fe) => {
+                let hover = self.date_at(fe.abs);
+                if hover != self.hover {
+                    self.hover = hover;
+                    self.draw_bg.redraw(cx);
+                }
+                cx.set_cursor(if hover.is_some() {MouseCursor::Hand} else {MouseCursor::Default});
+            }
+            Hit::FingerHoverOut(_) => {
+                self.hover = None;
+                self.draw_bg.redraw(cx);
+            }
+            Hit::FingerDown(fe) => {
+                cx.set_key_focus(self.draw_bg.area());
+                if self.prev_rect().contains(fe.abs) {
+                    self.show_month(cx, scope, self.view_month.add_months(-1));
+                }
+                else if self.next_rect().contains(fe.abs) {
+                    self.show_month(cx, scope, self.view_month.add_months(1));
+                }
+                else if let Some(date) = self.date_at(fe.abs) {
+                    self.pick(cx, scope, date);
+                }
+            }
+            Hit::FingerScroll(fs) => {
+                if fs.scroll.y > 0.0 {
+                    self.show_month(cx, scope, self.view_month.add_months(1));
+                }
+                else if fs.scroll.y < 0.0 {
+                    self.show_month(cx, scope, self.view_month.add_months(-1));
+                }
+            }
+            Hit::KeyFocus(_) | Hit::KeyFocusLost(_) => {
+                self.draw_bg.redraw(cx);
+            }
+            Hit::KeyDown(ke) => {
+                let first_day = self.first_day();
+                match ke.key_code {
+                    KeyCode::ArrowLeft => self.move_cursor(cx, scope, self.cursor.add_days(-1)),
+                    KeyCode::ArrowRight => self.move_cursor(cx, scope, self.cursor.add_days(1)),
+                    KeyCode::ArrowUp => self.move_cursor(cx, scope, self.cursor.add_days(-7)),
+                    KeyCode::ArrowDown => self.move_cursor(cx, scope, self.cursor.add_days(7)),
+                    KeyCode::PageUp if ke.modifiers.shift => self.move_cursor(cx, scope, self.cursor.add_years(-1)),
+                    KeyCode::PageDown if ke.modifiers.shift => self.move_cursor(cx, scope, self.cursor.add_years(1)),
+                    KeyCode::PageUp => self.move_cursor(cx, scope, self.cursor.add_months(-1)),
+                    KeyCode::PageDown => self.move_cursor(cx, scope, self.cursor.add_months(1)),
+                    KeyCode::Home => self.move_cursor(cx, scope, self.cursor.start_of_week(first_day)),
+                    KeyCode::End => self.move_cursor(cx, scope, self.cursor.start_of_week(first_day).add_days(6)),
+                    KeyCode::ReturnKey | KeyCode::Space => self.pick(cx, scope, self.cursor),
+                    KeyCode::Escape if self.range_anchor.is_some() => {
+                        self.range_anchor = None;
+                        self.selection = None;
+                        self.draw_bg.redraw(cx);
+                    }
+                    _ => ()
+                }
+            }
+            _ => ()
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, _scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.draw_calendar(cx, walk);
+        DrawStep::done()
+    }
+
+    fn text(&self) -> String {
+        match self.selection {
+            Some((start, end)) if self.range_selection && start != end => format!("{}/{}", start, end),
+            Some((start, _)) => start.to_string(),
+            None => String::new()
+        }
+    }
+
+    fn set_text(&mut self, v: &str) {
+        let mut parts = v.splitn(2, '/');
+        let start = parts.next().and_then(Date::parse_iso);
+        let end = parts.next().and_then(Date::parse_iso);
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                self.selection = Some((start.min(end), start.max(end)));
+                self.view_month = start.first_of_month();
+            }
+            (Some(start), None) => {
+                self.selection = Some((start, start));
+                self.view_month = start.first_of_month();
+            }
+            _ => self.selection = None
+        }
+        self.range_anchor = None;
+    }
+}
+
+impl CalendarRef {
+    pub fn selected(&self, actions: &Actions) -> Option<Date> {
+        if let CalendarAction::Select(date) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some(date)
+        }
+        None
+    }
+
+    pub fn selected_range(&self, actions: &Actions) -> Option<(Date, Date)> {
+        if let CalendarAction::SelectRange(start, end) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some((start, end))
+        }
+        None
+    }
+
+    pub fn selected_date(&self) -> Option<Date> {
+        if let Some(inner) = self.borrow() {
+            return inner.selected_date()
+        }
+        None
+    }
+
+    pub fn set_selected_date(&self, cx: &mut Cx, date: Option<Date>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_selected_date(cx, date);
+        }
+    }
+
+    pub fn set_selected_range(&self, cx: &mut Cx, start: Date, end: Date) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_selected_range(cx, start, end);
+        }
+    }
+}
@@ -0,0 +1,690 @@
+use {
+    std::borrow::Cow,
+    crate::{
+        makepad_derive_widget::*,
+        makepad_draw::{*, turtle::Axis2},
+        widget::*,
+        touch_gesture::*,
+        chart_data::*,
+    },
+};
+
+live_design!{
+    DrawChartLine = {{DrawChartLine}} {}
+    DrawChartArea = {{DrawChartArea}} {}
+    DrawChartMark = {{DrawChartMark}} {}
+    ChartBase = {{Chart}} {}
+}
+
+/// One segment of a line series, `p0` and `p1` relative to the quad.
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawChartLine {
+    #[deref] draw_super: DrawQuad,
+    #[live] p0: Vec2,
+    #[live] p1: Vec2,
+    #[live] line_width: f32,
+    #[live] color: Vec4,
+}
+
+/// The fill between one segment of an area series and `baseline`.
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawChartArea {
+    #[deref] draw_super: DrawQuad,
+    #[live] p0: Vec2,
+    #[live] p1: Vec2,
+    #[live] baseline: f32,
+    #[live] color: Vec4,
+}
+
+/// A bar or a point; which one is up to the shader.
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawChartMark {
+    #[deref] draw_super: DrawQuad,
+    #[live] color: Vec4,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Live, LiveHook)]
+#[live_ignore]
+pub enum ChartKind {
+    #[pick] Line,
+    Area,
+    Bar,
+    Scatter,
+    /// Counts the y values of the series into `histogram_bins` bins.
+    Histogram,
+}
+
+/// A named set of points drawn as one `ChartKind`. Line, area and bar series
+/// expect their points in ascending x order, as streamed data arrives.
+#[derive(Clone, Debug)]
+pub struct ChartSeries {
+    pub name: String,
+    pub kind: ChartKind,
+    /// Picked from the chart's `palette` when not set.
+    pub color: Option<Vec4>,
+    pub data: ChartRingBuffer,
+}
+
+impl ChartSeries {
+    pub fn new(name: impl Into<String>, kind: ChartKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            color: None,
+            data: ChartRingBuffer::default(),
+        }
+    }
+
+    pub fn with_color(mut self, color: Vec4) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Keeps only the newest `capacity` points, for realtime data.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.data.set_capacity(capacity);
+        self
+    }
+
+    pub fn with_points(mut self, points: impl IntoIterator<Item = DVec2>) -> Self {
+        self.data.extend(points);
+        self
+    }
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum ChartAction {
+    /// The view was panned or zoomed, or reset to fit the data.
+    ViewChanged(ChartBounds),
+    /// The pointer is over a point of the series at this index.
+    Hover(usize, DVec2),
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ChartHover {
+    series: usize,
+    point: DVec2,
+}
+
+/// Plots `ChartSeries` with axes, a legend and a tooltip for the point under
+/// the pointer. Drag to pan along x, scroll or pinch to zoom (with shift the
+/// y axis) and double click to fit the data again. Data can be streamed in
+/// from another thread through `ChartRef::stream_sender`; with `follow` the
+/// view keeps the newest `x_window` of it in sight.
+#[derive(Live, LiveHook, Widget)]
+pub struct Chart {
+    #[redraw] #[live] draw_bg: DrawQuad,
+    #[live] draw_grid: DrawColor,
+    #[live] draw_axis: DrawColor,
+    #[live] draw_label: DrawText,
+    #[live] draw_legend: DrawText,
+    #[live] draw_swatch: DrawColor,
+    #[live] draw_line: DrawChartLine,
+    #[live] draw_area: DrawChartArea,
+    #[live] draw_bar: DrawChartMark,
+    #[live] draw_point: DrawChartMark,
+    #[live] draw_marker: DrawChartMark,
+    #[live] draw_tooltip_bg: DrawColor,
+    #[live] draw_tooltip: DrawText,
+
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+    #[live] tooltip_layout: Layout,
+
+    #[live] palette: Vec<Vec4>,
+    #[live(2.0)] line_width: f64,
+    #[live(5.0)] point_size: f64,
+    #[live(0.8)] bar_width: f64,
+    #[live(20usize)] histogram_bins: usize,
+    #[live(8usize)] max_x_ticks: usize,
+    #[live(6usize)] max_y_ticks: usize,
+    #[live(48.0)] y_axis_width: f64,
+    #[live(16.0)] hover_radius: f64,
+    #[live(0.002)] zoom_speed: f64,
+    #[live(true)] show_grid: bool,
+    #[live(true)] show_legend: bool,
+    #[live] follow: bool,
+    #[live] x_window: f64,
+
+    #[rust] series: Vec<ChartSeries>,
+    #[rust] stream: ToUIReceiver<ChartBlock>,
+    #[rust] view: Option<ChartBounds>,
+    #[rust] plot_rect: Rect,
+    #[rust] hover: Option<ChartHover>,
+    #[rust] touch_gesture: Option<TouchGesture>,
+    #[rust] last_scrolled_at: f64,
+    #[rust] scratch: Vec<DVec2>,
+}
+
+impl Chart {
+
+    pub fn add_series(&mut self, cx: &mut Cx, series: ChartSeries) -> usize {
+        self.series.push(series);
+        self.draw_bg.redraw(cx);
+        self.series.len() - 1
+    }
+
+    pub fn series(&self, index: usize) -> Option<&ChartSeries> {
+        self.series.get(index)
+    }
+
+    /// Remember to `redraw` after changing the data.
+    pub fn series_mut(&mut self, index: usize) -> Option<&mut ChartSeries> {
+        self.series.get_mut(index)
+    }
+
+    pub fn clear_series(&mut self, cx: &mut Cx) {
+        self.series.clear();
+        self.hover = None;
+        self.draw_bg.redraw(cx);
+    }
+
+    /// A sender for blocks of points that can be moved to another thread,
+    /// such as an audio callback. Blocks for series that don't exist are dropped.
+    pub fn stream_sender(&self) -> ToUISender<ChartBlock> {
+        self.stream.sender()
+    }
+
+    /// The visible range, or `None` to fit the data.
+    pub fn set_view(&mut self, cx: &mut Cx, view: Option<ChartBounds>) {
+        self.view = view.map( | view | view.non_empty());
+        self.draw_bg.redraw(cx);
+    }
+
+    pub fn view(&self) -> ChartBounds {
+        self.view.unwrap_or_else( || self.fit_bounds())
+    }
+
+    fn series_color(&self, index: usize) -> Vec4 {
+        if let Some(color) = self.series[index].color {
+            return color
+        }
+        if self.palette.is_empty() {
+            return vec4(0.4, 0.7, 1.0, 1.0)
+        }
+        self.palette[index % self.palette.len()]
+    }
+
+    /// The points a series is drawn from; bin centers and counts for histograms.
+    fn series_points<'a>(&self, series: &'a ChartSeries) -> Cow<'a, ChartRingBuffer> {
+        if series.kind != ChartKind::Histogram {
+            return Cow::Borrowed(&series.data)
+        }
+        let mut points = ChartRingBuffer::default();
+        if let Some(bounds) = ChartBounds::from_points(series.data.iter().map( | p | dvec2(p.y, 0.0))) {
+            let bounds = bounds.non_empty();
+            let bins = self.histogram_bins.max(1);
+            let bin_width = bounds.size().x / bins as f64;
+            let counts = histogram(series.data.iter().map( | p | p.y), bounds.min.x, bounds.max.x, bins);
+            points.extend(counts.iter().enumerate().map( | (bin, count) | {
+                dvec2(bounds.min.x + (bin as f64 + 0.5) * bin_width, *count as f64)
+            }));
+        }
+        Cow::Owned(points)
+    }
+
+    /// The distance between neighbouring points of a bar series, in data units.
+    fn bar_slot(points: &ChartRingBuffer) -> f64 {
+        match (points.get(0), points.last()) {
+            (Some(first), Some(last)) if points.len() > 1 && last.x > first.x => {
+                (last.x - first.x) / (points.len() - 1) as f64
+            }
+            _ => 1.0
+        }
+    }
+
+    fn fit_bounds(&self) -> ChartBounds {
+        let mut bounds: Option<ChartBounds> = None;
+        for series in &self.series {
+            let points = self.series_points(series);
+            let Some(mut series_bounds) = points.bounds() else {
+                continue
+            };
+            if matches!(series.kind, ChartKind::Bar | ChartKind::Histogram) {
+                let half_slot = 0.5 * Self::bar_slot(&points);
+                series_bounds.min.x -= half_slot;
+                series_bounds.max.x += half_slot;
+                series_bounds = series_bounds.union_point(dvec2(series_bounds.min.x, 0.0));
+            }
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.union(series_bounds),
+                None => series_bounds,
+            });
+        }
+        let mut bounds = bounds.unwrap_or(ChartBounds {min: dvec2(0.0, 0.0), max: dvec2(1.0, 1.0)});
+        if self.follow && self.x_window > 0.0 {
+            bounds.min.x = bounds.max.x - self.x_window;
+        }
+        bounds.non_empty().pad_y(0.05)
+    }
+
+    fn drain_stream(&mut self, cx: &mut Cx) {
+        let mut changed = false;
+        while let Ok(block) = self.stream.try_recv() {
+            if let Some(series) = self.series.get_mut(block.series) {
+                series.data.extend(block.points);
+                changed = true;
+            }
+        }
+        if changed {
+            self.draw_bg.redraw(cx);
+        }
+    }
+
+    fn change_view(&mut self, cx: &mut Cx, scope: &mut Scope, view: ChartBounds) {
+        self.view = Some(view);
+        cx.widget_action(self.widget_uid(), &scope.path, ChartAction::ViewChanged(view));
+        self.draw_bg.redraw(cx);
+    }
+
+    fn zoom(&mut self, cx: &mut Cx, scope: &mut Scope, abs: DVec2, factor: DVec2) {
+        if self.plot_rect.size.x <= 0.0 || self.plot_rect.size.y <= 0.0 {
+            return
+        }
+        let view = self.view();
+        let center = view.from_screen(self.plot_rect, abs);
+        self.change_view(cx, scope, view.zoom(center, factor));
+    }
+
+    fn find_hover(&self, abs: DVec2) -> Option<ChartHover> {
+        if !self.plot_rect.contains(abs) {
+            return None
+        }
+        let view = self.view();
+        let x = view.from_screen(self.plot_rect, abs).x;
+        let mut best: Option<(f64, ChartHover)> = None;
+        for (index, series) in self.series.iter().enumerate() {
+            let points = self.series_points(series);
+            let mut consider = | point: DVec2 | {
+                let distance = (view.to_screen(self.plot_rect, point) - abs).length();
+                if series.kind == ChartKind::Scatter && distance > self.hover_radius {
+                    return
+                }
+                if best.is_none_or( | (best_distance, _) | distance < best_distance) {
+                    best = Some((distance, ChartHover {series: index, point}));
+                }
+            };
+            if series.kind == ChartKind::Scatter {
+                points.iter().for_each(consider);
+            }
+            else {
+                let after = points.partition_x(x);
+                for index in [after.wrapping_sub(1), after] {
+                    if let Some(point) = points.get(index) {
+                        consider(point);
+                    }
+                }
+            }
+        }
+        best.map( | (_, hover) | hover)
+    }
+
+    fn draw_text_in(cx: &mut Cx2d, draw_text: &mut DrawText, rect: Rect, align: Align, text: &str) {
+        cx.begin_turtle(Walk::abs_rect(rect), Layout::default().with_align_x(align.x).with_align_y(align.y));
+        draw_text.draw_walk(cx, Walk::fit(), Align::default(), text);
+        cx.end_turtle();
+    }
+
+    fn draw_axes(&mut self, cx: &mut Cx2d, view: ChartBounds) {
+        let plot = self.plot_rect;
+        let line_height = self.draw_label.line_height(cx);
+
+        let (x_ticks, x_step) = nice_ticks(view.min.x, view.max.x, self.max_x_ticks);
+        for x in x_ticks {
+            let pos_x = view.to_screen(plot, dvec2(x, view.min.y)).x.floor();
+            if self.show_grid {
+                self.draw_grid.draw_abs(cx, Rect {pos: dvec2(pos_x, plot.pos.y), size: dvec2(1.0, plot.size.y)});
+            }
+            let label_rect = Rect {
+                pos: dvec2(pos_x - 50.0, plot.pos.y + plot.size.y + 2.0),
+                size: dvec2(100.0, line_height),
+            };
+            Self::draw_text_in(cx, &mut self.draw_label, label_rect, Align {x: 0.5, y: 0.0}, &format_tick(x, x_step));
+        }
+
+        let (y_ticks, y_step) = nice_ticks(view.min.y, view.max.y, self.max_y_ticks);
+        for y in y_ticks {
+            let pos_y = view.to_screen(plot, dvec2(view.min.x, y)).y.floor();
+            if self.show_grid {
+                self.draw_grid.draw_abs(cx, Rect {pos: dvec2(plot.pos.x, pos_y), size: dvec2(plot.size.x, 1.0)});
+            }
+            let label_rect = Rect {
+                pos: dvec2(plot.pos.x - self.y_axis_width, pos_y - 0.5 * line_height),
+                size: dvec2(self.y_axis_width - 4.0, line_height),
+            };
+            Self::draw_text_in(cx, &mut self.draw_label, label_rect, Align {x: 1.0, y: 0.5}, &format_tick(y, y_step));
+        }
+
+        self.draw_axis.draw_abs(cx, Rect {pos: plot.pos, size: dvec2(1.0, plot.size.y)});
+        self.draw_axis.draw_abs(cx, Rect {pos: dvec2(plot.pos.x, plot.pos.y + plot.size.y - 1.0), size: dvec2(plot.size.x, 1.0)});
+    }
+
+    fn draw_series(&mut self, cx: &mut Cx2d, view: ChartBounds, index: usize, bar_index: usize, bar_count: usize) {
+        let plot = self.plot_rect;
+        let color = self.series_color(index);
+        let series = &self.series[index];
+        let points = self.series_points(series);
+        let baseline = view.to_screen(plot, dvec2(view.min.x, 0.0f64.clamp(view.min.y, view.max.y))).y;
+        let x_scale = plot.size.x / view.size().x;
+
+        match series.kind {
+            ChartKind::Line | ChartKind::Area => {
+                // one point beyond each edge so the line runs out of view
+                let start = points.partition_x(view.min.x).saturating_sub(1);
+                let end = (points.partition_x(view.max.x) + 1).min(points.len());
+                let visible = (start..end).filter_map( | index | points.get(index));
+                decimate_m4(visible, view.min.x, x_scale, &mut self.scratch);
+                let screen: Vec<DVec2> = self.scratch.iter().map( | p | view.to_screen(plot, *p)).collect();
+
+                if series.kind == ChartKind::Area {
+                    self.draw_area.color = color;
+                    self.draw_area.begin_many_instances(cx);
+                    for pair in screen.windows(2) {
+                        let (a, b) = (pair[0], pair[1]);
+                        let top = a.y.min(b.y).min(baseline);
+                        let rect = Rect {
+                            pos: dvec2(a.x.min(b.x), top),
+                            size: dvec2((b.x - a.x).abs().max(1.0), (a.y.max(b.y).max(baseline) - top).max(1.0)),
+                        };
+                        self.draw_area.p0 = (a - rect.pos).into_vec2();
+                        self.draw_area.p1 = (b - rect.pos).into_vec2();
+                        self.draw_area.baseline = (baseline - rect.pos.y) as f32;
+                        self.draw_area.draw_abs(cx, rect);
+                    }
+                    self.draw_area.end_many_instances(cx);
+                }
+
+                let pad = self.line_width;
+                self.draw_line.color = color;
+                self.draw_line.line_width = self.line_width as f32;
+                self.draw_line.begin_many_instances(cx);
+                for pair in screen.windows(2) {
+                    let (a, b) = (pair[0], pair[1]);
+                    let min = dvec2(a.x.min(b.x) - pad, a.y.min(b.y) - pad);
+                    let max = dvec2(a.x.max(b.x) + pad, a.y.max(b.y) + pad);
+                    self.draw_line.p0 = (a - min).into_vec2();
+                    self.draw_line.p1 = (b - min).into_vec2();
+                    self.draw_line.draw_abs(cx, Rect {pos: min, size: max - min});
+                }
+                self.draw_line.end_many_instances(cx);
+            }
+            ChartKind::Scatter => {
+                let size = self.point_size;
+                self.draw_point.color = color;
+                self.draw_point.begin_many_instances(cx);
+                for point in points.iter() {
+                    let pos = view.to_screen(plot, point);
+                    if !plot.contains(pos) {
+                        continue;
+                    }
+                    self.draw_point.draw_abs(cx, Rect {
+                        pos: pos - dvec2(0.5 * size, 0.5 * size),
+                        size: dvec2(size, size),
+                    });
+                }
+                self.draw_point.end_many_instances(cx);
+            }
+            ChartKind::Bar | ChartKind::Histogram => {
+                let (bar_index, bar_count) = if series.kind == ChartKind::Bar {(bar_index, bar_count)} else {(0, 1)};
+                let slot = Self::bar_slot(&points) * x_scale;
+                let width = (slot * self.bar_width / bar_count as f64).max(1.0);
+                let offset = (bar_index as f64 - 0.5 * (bar_count - 1) as f64) * width;
+                let start = points.partition_x(view.min.x).saturating_sub(1);
+                let end = (points.partition_x(view.max.x) + 1).min(points.len());
+                self.draw_bar.color = color;
+                self.draw_bar.begin_many_instances(cx);
+                for point in (start..end).filter_map( | index | points.get(index)) {
+                    let pos = view.to_screen(plot, point);
+                    let top = pos.y.min(baseline);
+                    self.draw_bar.draw_abs(cx, Rect {
+                        pos: dvec2(pos.x + offset - 0.5 * width, top),
+                        size: dvec2(width, (pos.y - baseline).abs()),
+                    });
+                }
+                self.draw_bar.end_many_instances(cx);
+            }
+        }
+    }
+
+    fn draw_legend(&mut self, cx: &mut Cx2d, rect: Rect) {
+        let line_height = self.draw_legend.line_height(cx);
+        let legend_rect = Rect {
+            pos: dvec2(self.plot_rect.pos.x, rect.pos.y + self.layout.padding.top),
+            size: dvec2(self.plot_rect.size.x, line_height),
+        };
+        cx.begin_turtle(Walk::abs_rect(legend_rect), Layout::flow_right().with_align_y(0.5));
+        for index in 0..self.series.len() {
+            self.draw_swatch.color = self.series_color(index);
+            self.draw_swatch.draw_walk(cx, Walk::fixed(10.0, 10.0).with_margin_right(4.0));
+            let name = &self.series[index].name;
+            self.draw_legend.draw_walk(cx, Walk::fit().with_margin_right(12.0), Align::default(), name);
+        }
+        cx.end_turtle();
+    }
+
+    fn draw_tooltip(&mut self, cx: &mut Cx2d, view: ChartBounds) {
+        let Some(hover) = self.hover else {
+            return
+        };
+        let Some(series) = self.series.get(hover.series) else {
+            return
+        };
+        let plot = self.plot_rect;
+        let pos = view.to_screen(plot, hover.point);
+        if !plot.contains(pos) {
+            return
+        }
+        let (_, x_step) = nice_ticks(view.min.x, view.max.x, self.max_x_ticks);
+        let (_, y_step) = nice_ticks(view.min.y, view.max.y, self.max_y_ticks);
+        let text = format!(
+            "{}\nx: {}\ny: {}",
+            series.name,
+            format_tick(hover.point.x, x_step / 10.0),
+            format_tick(hover.point.y, y_step / 10.0)
+        );
+
+        // keep the tooltip on the side of the point with the most room
+        let gap = 12.0;
+        let (region, align_x) = if pos.x > plot.pos.x + 0.5 * plot.size.x {
+            (Rect {pos: dvec2(plot.pos.x, pos.y + gap), size: dvec2(pos.x - gap - plot.pos.x, plot.size.y)}, 1.0)
+        }
+        else {
+            (Rect {pos: dvec2(pos.x + gap, pos.y + gap), size: dvec2(plot.pos.x + plot.size.x - pos.x - gap, plot.size.y)}, 0.0)
+        };
+        cx.begin_turtle(Walk::abs_rect(region), Layout::default().with_align_x(align_x));
+        self.draw_tooltip_bg.begin(cx, Walk::fit(), self.tooltip_layout);
+        self.draw_tooltip.draw_walk(cx, Walk::fit(), Align::default(), &text);
+        self.draw_tooltip_bg.end(cx);
+        cx.end_turtle();
+    }
+}
+
+impl Widget for Chart {
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        if let Event::Signal = event {
+            self.drain_stream(cx);
+        }
+
+        let area = self.draw_bg.area();
+        if let Some(touch_gesture) = self.touch_gesture.as_mut() {
+            match touch_gesture.handle_event(cx, event, area) {
+                TouchMotionChange::ScrolledAtChanged => {
+                    let delta = touch_gesture.scrolled_at - self.last_scrolled_at;
+                    self.last_scrolled_at = touch_gesture.scrolled_at;
+                    if self.plot_rect.size.x > 0.0 {
+                        let view = self.view();
+                        let shift = delta * view.size().x / self.plot_rect.size.x;
+                        self.change_view(cx, scope, view.translate(dvec2(shift, 0.0)));
+                    }
+                }
+                TouchMotionChange::Pinched => {
+                    let factor = 1.0 / touch_gesture.pinch_factor;
+                    let center = touch_gesture.pinch_center;
+                    self.zoom(cx, scope, center, dvec2(factor, 1.0));
+                }
+                _ => ()
+            }
+        }
+
+        match event.hits(cx, area) {
+            Hit::FingerHoverIn(fe) | Hit::FingerHoverOver(fe) => {
+                cx.set_cursor(MouseCursor::Default);
+                let hover = self.find_hover(fe.abs);
+                if hover != self.hover {
+                    self.hover = hover;
+                    if let Some(hover) = hover {
+                        cx.widget_action(self.widget_uid(), &scope.path, ChartAction::Hover(hover.series, hover.point));
+                    }
+                    self.draw_bg.redraw(cx);
+                }
+            }
+            Hit::FingerHoverOut(_) if self.hover.is_some() => {
+                self.hover = None;
+                self.draw_bg.redraw(cx);
+            }
+            Hit::FingerScroll(fs) => {
+                let factor = (fs.scroll.y * self.zoom_speed).exp();
+                let factor = if fs.modifiers.shift {
+                    dvec2(1.0, factor)
+                }
+                else if fs.modifiers.control || fs.modifiers.logo {
+                    dvec2(factor, factor)
+                }
+                else {
+                    dvec2(factor, 1.0)
+                };
+                self.zoom(cx, scope, fs.abs, factor);
+            }
+            Hit::FingerUp(fe) if fe.tap_count == 2 && fe.was_tap() => {
+                self.view = None;
+                cx.widget_action(self.widget_uid(), &scope.path, ChartAction::ViewChanged(self.fit_bounds()));
+                self.draw_bg.redraw(cx);
+            }
+            _ => ()
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, _scope: &mut Scope, walk: Walk) -> DrawStep {
+        if self.touch_gesture.is_none() {
+            let mut touch_gesture = TouchGesture::new();
+            touch_gesture.set_axis(Axis2::Horizontal);
+            touch_gesture.set_mode(ScrollMode::Swipe);
+            self.touch_gesture = Some(touch_gesture);
+        }
+
+        let rect = cx.walk_turtle(walk);
+        self.draw_bg.draw_abs(cx, rect);
+
+        let padding = self.layout.padding;
+        let line_height = self.draw_label.line_height(cx);
+        let legend_height = if self.show_legend && !self.series.is_empty() {
+            self.draw_legend.line_height(cx) + 6.0
+        }
+        else {
+            0.0
+        };
+        self.plot_rect = Rect {
+            pos: rect.pos + dvec2(padding.left + self.y_axis_width, padding.top + legend_height),
+            size: rect.size - dvec2(
+                padding.left + padding.right + self.y_axis_width,
+                padding.top + padding.bottom + legend_height + line_height + 4.0
+            ),
+        };
+        if self.plot_rect.size.x <= 0.0 || self.plot_rect.size.y <= 0.0 {
+            return DrawStep::done()
+        }
+
+        let view = self.view();
+        self.draw_axes(cx, view);
+
+        let bar_count = self.series.iter().filter( | series | series.kind == ChartKind::Bar).count();
+        cx.begin_turtle(Walk::abs_rect(self.plot_rect), Layout::default());
+        let mut bar_index = 0;
+        for index in 0..self.series.len() {
+            self.draw_series(cx, view, index, bar_index, bar_count);
+            if self.series[index].kind == ChartKind::Bar {
+                bar_index += 1;
+            }
+        }
+        if let Some(hover) = self.hover {
+            let pos = view.to_screen(self.plot_rect, hover.point);
+            let size = 2.0 * self.point_size;
+            self.draw_marker.color = self.series_color(hover.series.min(self.series.len().saturating_sub(1)));
+            self.draw_marker.draw_abs(cx, Rect {
+                pos: pos - dvec2(0.5 * size, 0.5 * size),
+                size: dvec2(size, size),
+            });
+        }
+        cx.end_turtle();
+
+        if legend_height > 0.0 {
+            self.draw_legend(cx, rect);
+        }
+        self.draw_tooltip(cx, view);
+        DrawStep::done()
+    }
+}
+
+impl ChartRef {
+    pub fn add_series(&self, cx: &mut Cx, series: ChartSeries) -> Option<usize> {
+        if let Some(mut inner) = self.borrow_mut() {
+            return Some(inner.add_series(cx, series))
+        }
+        None
+    }
+
+    /// Replaces the points of a series.
+    pub fn set_points(&self, cx: &mut Cx, series: usize, points: impl IntoIterator<Item = DVec2>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            if let Some(series) = inner.series_mut(series) {
+                series.data.clear();
+                series.data.extend(points);
+            }
+            inner.redraw(cx);
+        }
+    }
+
+    pub fn push_points(&self, cx: &mut Cx, series: usize, points: impl IntoIterator<Item = DVec2>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            if let Some(series) = inner.series_mut(series) {
+                series.data.extend(points);
+            }
+            inner.redraw(cx);
+        }
+    }
+
+    pub fn clear_series(&self, cx: &mut Cx) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.clear_series(cx);
+        }
+    }
+
+    pub fn stream_sender(&self) -> Option<ToUISender<ChartBlock>> {
+        self.borrow().map( | inner | inner.stream_sender())
+    }
+
+    pub fn set_view(&self, cx: &mut Cx, view: Option<ChartBounds>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_view(cx, view);
+        }
+    }
+
+    pub fn view_changed(&self, actions: &Actions) -> Option<ChartBounds> {
+        if let ChartAction::ViewChanged(view) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some(view)
+        }
+        None
+    }
+
+    pub fn hovered(&self, actions: &Actions) -> Option<(usize, DVec2)> {
+        if let ChartAction::Hover(series, point) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some((series, point))
+        }
+        None
+    }
+}
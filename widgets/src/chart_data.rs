@@ -0,0 +1,425 @@
+//! Data handling for `Chart`: a ring buffer that streamed samples are written
+//! into, tick generation for the axes, and the reductions that keep large
+//! series cheap to draw. Nothing here needs a `Cx`.
+
+use crate::makepad_draw::*;
+
+/// A buffer of points that overwrites the oldest ones once `capacity` is
+/// reached. A capacity of 0 lets it grow without bound.
+#[derive(Clone, Debug, Default)]
+pub struct ChartRingBuffer {
+    points: Vec<DVec2>,
+    head: usize,
+    capacity: usize,
+}
+
+impl ChartRingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            points: Vec::with_capacity(capacity),
+            head: 0,
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the capacity, keeping the newest points.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let mut points: Vec<DVec2> = self.iter().collect();
+        if capacity > 0 && points.len() > capacity {
+            points.drain(..points.len() - capacity);
+        }
+        self.points = points;
+        self.head = 0;
+        self.capacity = capacity;
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.head = 0;
+    }
+
+    pub fn push(&mut self, point: DVec2) {
+        if self.capacity == 0 || self.points.len() < self.capacity {
+            self.points.push(point);
+        }
+        else {
+            self.points[self.head] = point;
+            self.head = (self.head + 1) % self.capacity;
+        }
+    }
+
+    pub fn extend(&mut self, points: impl IntoIterator<Item = DVec2>) {
+        for point in points {
+            self.push(point);
+        }
+    }
+
+    /// The point at `index`, counting from the oldest.
+    pub fn get(&self, index: usize) -> Option<DVec2> {
+        if index >= self.points.len() {
+            return None
+        }
+        Some(self.points[(self.head + index) % self.points.len()])
+    }
+
+    pub fn last(&self) -> Option<DVec2> {
+        self.len().checked_sub(1).and_then( | index | self.get(index))
+    }
+
+    /// The points from oldest to newest, in two parts because of the wrap.
+    pub fn as_slices(&self) -> (&[DVec2], &[DVec2]) {
+        (&self.points[self.head..], &self.points[..self.head])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = DVec2> + '_ {
+        let (older, newer) = self.as_slices();
+        older.iter().chain(newer.iter()).copied()
+    }
+
+    pub fn bounds(&self) -> Option<ChartBounds> {
+        ChartBounds::from_points(self.iter())
+    }
+
+    /// The first index whose x is not below `x`, for points pushed in
+    /// ascending x order as streamed data is.
+    pub fn partition_x(&self, x: f64) -> usize {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.get(mid).unwrap().x < x {
+                low = mid + 1;
+            }
+            else {
+                high = mid;
+            }
+        }
+        low
+    }
+}
+
+/// A batch of points for one series of a `Chart`. Send these from another
+/// thread with the sender from `ChartRef::stream_sender`.
+#[derive(Clone, Debug, Default)]
+pub struct ChartBlock {
+    pub series: usize,
+    pub points: Vec<DVec2>,
+}
+
+impl ChartBlock {
+    /// Evenly spaced samples, such as an audio callback hands out, starting
+    /// at `x_start` and `x_step` apart.
+    pub fn from_samples(series: usize, x_start: f64, x_step: f64, samples: &[f32]) -> Self {
+        Self {
+            series,
+            points: samples.iter().enumerate().map( | (index, sample) | {
+                dvec2(x_start + index as f64 * x_step, *sample as f64)
+            }).collect(),
+        }
+    }
+}
+
+/// A rectangle in data space.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChartBounds {
+    pub min: DVec2,
+    pub max: DVec2,
+}
+
+impl ChartBounds {
+    pub fn from_points(points: impl IntoIterator<Item = DVec2>) -> Option<Self> {
+        let mut bounds: Option<ChartBounds> = None;
+        for point in points {
+            if !point.x.is_finite() || !point.y.is_finite() {
+                continue;
+            }
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.union_point(point),
+                None => ChartBounds {min: point, max: point},
+            });
+        }
+        bounds
+    }
+
+    pub fn union(self, other: ChartBounds) -> Self {
+        self.union_point(other.min).union_point(other.max)
+    }
+
+    pub fn union_point(self, point: DVec2) -> Self {
+        Self {
+            min: dvec2(self.min.x.min(point.x), self.min.y.min(point.y)),
+            max: dvec2(self.max.x.max(point.x), self.max.y.max(point.y)),
+        }
+    }
+
+    pub fn size(&self) -> DVec2 {
+        self.max - self.min
+    }
+
+    /// Widens empty ranges so there is something to map onto the screen.
+    pub fn non_empty(mut self) -> Self {
+        if self.max.x <= self.min.x {
+            self.min.x -= 0.5;
+            self.max.x += 0.5;
+        }
+        if self.max.y <= self.min.y {
+            self.min.y -= 0.5;
+            self.max.y += 0.5;
+        }
+        self
+    }
+
+    /// Adds `fraction` of the height above and below.
+    pub fn pad_y(mut self, fraction: f64) -> Self {
+        let pad = self.size().y * fraction;
+        self.min.y -= pad;
+        self.max.y += pad;
+        self
+    }
+
+    /// Scales around `center` by `factor` along each axis; a factor below 1 zooms in.
+    pub fn zoom(self, center: DVec2, factor: DVec2) -> Self {
+        Self {
+            min: center + (self.min - center) * factor,
+            max: center + (self.max - center) * factor,
+        }
+    }
+
+    pub fn translate(self, delta: DVec2) -> Self {
+        Self {
+            min: self.min + delta,
+            max: self.max + delta,
+        }
+    }
+
+    /// Maps a point in data space into `rect`, with y growing upwards.
+    pub fn to_screen(&self, rect: Rect, point: DVec2) -> DVec2 {
+        let size = self.size();
+        dvec2(
+            rect.pos.x + (point.x - self.min.x) / size.x * rect.size.x,
+            rect.pos.y + rect.size.y - (point.y - self.min.y) / size.y * rect.size.y,
+        )
+    }
+
+    pub fn from_screen(&self, rect: Rect, pos: DVec2) -> DVec2 {
+        let size = self.size();
+        dvec2(
+            self.min.x + (pos.x - rect.pos.x) / rect.size.x * size.x,
+            self.min.y + (rect.pos.y + rect.size.y - pos.y) / rect.size.y * size.y,
+        )
+    }
+}
+
+/// Tick positions at a "nice" step (1, 2 or 5 times a power of ten) that
+/// cover `min..=max` with about `max_ticks` of them. Returns the step too so
+/// labels can be formatted with `format_tick`.
+pub fn nice_ticks(min: f64, max: f64, max_ticks: usize) -> (Vec<f64>, f64) {
+    if !min.is_finite() || !max.is_finite() || max <= min || max_ticks < 2 {
+        return (Vec::new(), 0.0)
+    }
+    let range = nice_number(max - min, false);
+    let step = nice_number(range / (max_ticks - 1) as f64, true);
+    let mut ticks = Vec::new();
+    let mut index = (min / step).ceil();
+    while index * step <= max + step * 1e-9 {
+        ticks.push(index * step);
+        index += 1.0;
+    }
+    (ticks, step)
+}
+
+fn nice_number(value: f64, round: bool) -> f64 {
+    let exponent = value.log10().floor();
+    let fraction = value / 10f64.powf(exponent);
+    let nice = if round {
+        if fraction < 1.5 {1.0} else if fraction < 3.0 {2.0} else if fraction < 7.0 {5.0} else {10.0}
+    }
+    else if fraction <= 1.0 {1.0} else if fraction <= 2.0 {2.0} else if fraction <= 5.0 {5.0} else {10.0};
+    nice * 10f64.powf(exponent)
+}
+
+/// Formats a tick value with as many decimals as `step` needs.
+pub fn format_tick(value: f64, step: f64) -> String {
+    let decimals = if step > 0.0 {(-step.log10().floor()).max(0.0) as usize} else {0};
+    let text = format!("{:.*}", decimals, value);
+    // don't show -0 for values that round to zero
+    if text.starts_with('-') && text[1..].chars().all( | c | c == '0' || c == '.') {
+        text[1..].to_string()
+    }
+    else {
+        text
+    }
+}
+
+/// Reduces points sorted by x to at most four per pixel column: the first,
+/// lowest, highest and last. A line through the result looks the same as
+/// one through all of them. `x_scale` is pixels per data unit.
+pub fn decimate_m4(points: impl IntoIterator<Item = DVec2>, x_min: f64, x_scale: f64, out: &mut Vec<DVec2>) {
+    out.clear();
+    let mut column = None;
+    let (mut first, mut low, mut high, mut last) = (DVec2::default(), DVec2::default(), DVec2::default(), DVec2::default());
+    let flush = | out: &mut Vec<DVec2>, first: DVec2, low: DVec2, high: DVec2, last: DVec2 | {
+        out.push(first);
+        let (a, b) = if low.x <= high.x {(low, high)} else {(high, low)};
+        for point in [a, b, last] {
+            if out.last() != Some(&point) {
+                out.push(point);
+            }
+        }
+    };
+    for point in points {
+        let point_column = ((point.x - x_min) * x_scale).floor() as i64;
+        if column == Some(point_column) {
+            if point.y < low.y {
+                low = point;
+            }
+            if point.y > high.y {
+                high = point;
+            }
+            last = point;
+            continue;
+        }
+        if column.is_some() {
+            flush(out, first, low, high, last);
+        }
+        column = Some(point_column);
+        first = point;
+        low = point;
+        high = point;
+        last = point;
+    }
+    if column.is_some() {
+        flush(out, first, low, high, last);
+    }
+}
+
+/// Counts `values` into `bins` equally wide bins spanning `min..=max`.
+/// Values outside the range are left out.
+pub fn histogram(values: impl IntoIterator<Item = f64>, min: f64, max: f64, bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    if bins == 0 || max <= min {
+        return counts
+    }
+    let scale = bins as f64 / (max - min);
+    for value in values {
+        // NaN isn't in any bin either
+        if !(min..=max).contains(&value) {
+            continue;
+        }
+        let bin = (((value - min) * scale) as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xs(points: impl IntoIterator<Item = DVec2>) -> Vec<f64> {
+        points.into_iter().map( | p | p.x).collect()
+    }
+
+    fn labels(min: f64, max: f64, max_ticks: usize) -> Vec<String> {
+        let (ticks, step) = nice_ticks(min, max, max_ticks);
+        ticks.iter().map( | tick | format_tick(*tick, step)).collect()
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_newest_points() {
+        let mut buffer = ChartRingBuffer::new(3);
+        buffer.extend((1..=5).map( | x | dvec2(x as f64, 0.0)));
+        assert_eq!(xs(buffer.iter()), [3.0, 4.0, 5.0]);
+        assert_eq!(buffer.get(0), Some(dvec2(3.0, 0.0)));
+        assert_eq!(buffer.get(3), None);
+        assert_eq!(buffer.last(), Some(dvec2(5.0, 0.0)));
+        assert_eq!(buffer.partition_x(0.0), 0);
+        assert_eq!(buffer.partition_x(4.0), 1);
+        assert_eq!(buffer.partition_x(4.5), 2);
+        assert_eq!(buffer.partition_x(10.0), 3);
+
+        buffer.set_capacity(2);
+        assert_eq!(xs(buffer.iter()), [4.0, 5.0]);
+        buffer.push(dvec2(6.0, 0.0));
+        assert_eq!(xs(buffer.iter()), [5.0, 6.0]);
+        // without a capacity it grows
+        buffer.set_capacity(0);
+        buffer.extend([dvec2(7.0, 0.0), dvec2(8.0, 0.0)]);
+        assert_eq!(xs(buffer.iter()), [5.0, 6.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn ticks_step_by_one_two_or_five_times_a_power_of_ten() {
+        assert_eq!(nice_ticks(0.0, 10.0, 5), (vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0], 2.0));
+        assert_eq!(labels(-0.3, 0.7, 6), ["-0.2", "0.0", "0.2", "0.4", "0.6"]);
+        assert_eq!(labels(1234.0, 5678.0, 5), ["2000", "3000", "4000", "5000"]);
+        assert_eq!(labels(0.0, 0.001, 3), ["0.0000", "0.0005", "0.0010"]);
+        // nothing to cover
+        assert_eq!(nice_ticks(1.0, 1.0, 5), (vec![], 0.0));
+        assert_eq!(nice_ticks(0.0, f64::INFINITY, 5), (vec![], 0.0));
+        assert_eq!(nice_ticks(0.0, 1.0, 1), (vec![], 0.0));
+        assert_eq!(format_tick(-0.0001, 0.1), "0.0");
+    }
+
+    #[test]
+    fn histogram_counts_values_into_equal_bins() {
+        let values = [0.0, 0.5, 1.0, 2.5, 3.0, -1.0, 4.0, f64::NAN];
+        // the maximum falls into the last bin, values outside the range and NaN are left out
+        assert_eq!(histogram(values, 0.0, 3.0, 3), [2, 1, 2]);
+        assert_eq!(histogram(values, 0.0, 3.0, 1), [5]);
+        assert_eq!(histogram(values, 0.0, 3.0, 0), []);
+        assert_eq!(histogram(values, 3.0, 3.0, 2), [0, 0]);
+    }
+
+    #[test]
+    fn decimation_keeps_first_lowest_highest_and_last_per_column() {
+        let points = [
+            dvec2(0.0, 1.0),
+            dvec2(0.1, 2.0),
+            dvec2(0.2, 5.0),
+            dvec2(0.3, 0.0),
+            dvec2(0.4, -2.0),
+            dvec2(0.6, 3.0),
+            dvec2(1.0, 0.0),
+            dvec2(1.5, 0.0),
+        ];
+        let mut out = Vec::new();
+        decimate_m4(points, 0.0, 1.0, &mut out);
+        assert_eq!(xs(out.iter().copied()), [0.0, 0.2, 0.4, 0.6, 1.0, 1.5]);
+        // with a column per point nothing is left out
+        decimate_m4(points, 0.0, 100.0, &mut out);
+        assert_eq!(out.len(), points.len());
+        decimate_m4([], 0.0, 1.0, &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn bounds_map_between_data_and_screen() {
+        let bounds = ChartBounds::from_points([dvec2(0.0, 100.0), dvec2(f64::NAN, 0.0), dvec2(10.0, 0.0)]).unwrap();
+        assert_eq!(bounds, ChartBounds {min: dvec2(0.0, 0.0), max: dvec2(10.0, 100.0)});
+        let rect = Rect {pos: dvec2(100.0, 50.0), size: dvec2(200.0, 100.0)};
+        // y grows upwards in data space and downwards on the screen
+        assert_eq!(bounds.to_screen(rect, dvec2(5.0, 25.0)), dvec2(200.0, 125.0));
+        assert_eq!(bounds.from_screen(rect, dvec2(200.0, 125.0)), dvec2(5.0, 25.0));
+
+        let zoomed = bounds.zoom(dvec2(5.0, 50.0), dvec2(0.5, 0.5));
+        assert_eq!(zoomed, ChartBounds {min: dvec2(2.5, 25.0), max: dvec2(7.5, 75.0)});
+        assert_eq!(zoomed.translate(dvec2(-2.5, 0.0)).min, dvec2(0.0, 25.0));
+        assert_eq!(bounds.pad_y(0.1).min.y, -10.0);
+
+        let point = ChartBounds::from_points([dvec2(1.0, 1.0)]).unwrap().non_empty();
+        assert_eq!(point, ChartBounds {min: dvec2(0.5, 0.5), max: dvec2(1.5, 1.5)});
+        assert_eq!(ChartBounds::from_points([]), None);
+    }
+}
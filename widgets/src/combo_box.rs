@@ -0,0 +1,641 @@
+use {
+    std::ops::Range,
+    std::sync::Arc,
+    crate::{
+        makepad_derive_widget::*,
+        makepad_draw::*,
+        widget::*,
+        drop_down::{DropDownAction, PopupMenuGlobal},
+        popup_menu::{PopupMenu, PopupMenuAction},
+        text_input::{TextInput, TextInputAction},
+    }
+};
+
+live_design!{
+    ComboBoxBase = {{ComboBox}} {}
+}
+
+/// A `DropDown` you can type into. The text input filters `labels` with a fuzzy
+/// matcher and shows the matches in a popup menu below the input. When
+/// `query_debounce` is non-zero a `ComboBoxAction::Query` is emitted after the
+/// user stops typing, so the app can fetch remote suggestions and hand them
+/// back with `ComboBoxRef::set_suggestions`.
+#[derive(Live, Widget)]
+pub struct ComboBox {
+    #[redraw] #[live] text_input: TextInput,
+
+    #[walk] walk: Walk,
+
+    #[live] bind: String,
+
+    #[live] popup_menu: Option<LivePtr>,
+
+    #[live] labels: Vec<String>,
+    #[live] values: Vec<LiveValue>,
+
+    #[live] selected_item: usize,
+    #[live] allow_free_text: bool,
+    #[live(50usize)] max_items: usize,
+    #[live] query_debounce: f64,
+
+    #[rust] is_open: bool,
+    #[rust] matches: Vec<ComboBoxMatch>,
+    #[rust] highlighted: usize,
+    #[rust] suggestions: Vec<String>,
+    #[rust] query_timer: Timer,
+}
+
+/// One visible entry of the popup, either an index into `labels`
+/// or into the current suggestions.
+#[derive(Clone, Debug)]
+struct ComboBoxMatch {
+    item: ComboBoxItem,
+    fuzzy: FuzzyMatch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ComboBoxItem {
+    Label(usize),
+    Suggestion(usize),
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum ComboBoxAction {
+    /// An entry of `labels` was picked, same payload as `DropDownAction::Select`.
+    Select(usize, LiveValue),
+    /// A suggestion delivered through `set_suggestions` was picked.
+    SelectSuggestion(String),
+    /// Return was pressed on text that matched nothing while `allow_free_text` is set.
+    FreeText(String),
+    /// The query settled; reply with `ComboBoxRef::set_suggestions`.
+    Query(String),
+    None
+}
+
+impl LiveHook for ComboBox {
+    fn after_apply(&mut self, cx: &mut Cx, apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        if apply.from.is_from_doc() {
+            self.sync_text_to_selected();
+        }
+        if self.popup_menu.is_none() || !apply.from.is_from_doc() {
+            return
+        }
+        let global = cx.global::<PopupMenuGlobal>().clone();
+        let mut map = global.map.borrow_mut();
+
+        // when live styling clean up old style references
+        map.retain( | k, _ | cx.live_registry.borrow().generation_valid(*k));
+
+        let list_box = self.popup_menu.unwrap();
+        map.get_or_insert(cx, list_box, | cx | {
+            PopupMenu::new_from_ptr(cx, Some(list_box))
+        });
+    }
+}
+
+impl ComboBox {
+
+    fn sync_text_to_selected(&mut self) {
+        if let Some(label) = self.labels.get(self.selected_item) {
+            self.text_input.text = label.clone();
+        }
+    }
+
+    fn label_for(&self, item: ComboBoxItem) -> &str {
+        match item {
+            ComboBoxItem::Label(index) => &self.labels[index],
+            ComboBoxItem::Suggestion(index) => &self.suggestions[index],
+        }
+    }
+
+    /// Recomputes the filtered popup entries for the current text.
+    fn update_matches(&mut self) {
+        let query = self.text_input.text.as_str();
+        let mut matches = Vec::new();
+        for (index, label) in self.labels.iter().enumerate() {
+            if let Some(fuzzy) = fuzzy_match(query, label) {
+                matches.push(ComboBoxMatch {item: ComboBoxItem::Label(index), fuzzy});
+            }
+        }
+        for (index, label) in self.suggestions.iter().enumerate() {
+            if self.labels.iter().any( | l | l == label) {
+                continue;
+            }
+            if let Some(fuzzy) = fuzzy_match(query, label) {
+                matches.push(ComboBoxMatch {item: ComboBoxItem::Suggestion(index), fuzzy});
+            }
+        }
+        // stable sort keeps the original order for equal scores
+        matches.sort_by_key( | m | std::cmp::Reverse(m.fuzzy.score));
+        matches.truncate(self.max_items);
+        self.matches = matches;
+        self.highlighted = 0;
+    }
+
+    pub fn set_open(&mut self, cx: &mut Cx) {
+        if self.popup_menu.is_none() {
+            return
+        }
+        self.is_open = true;
+        self.select_highlighted(cx);
+        self.text_input.redraw(cx);
+    }
+
+    /// Shows the highlighted entry as selected in the popup, like DropDown does on open.
+    fn select_highlighted(&mut self, cx: &mut Cx) {
+        let global = cx.global::<PopupMenuGlobal>().clone();
+        let mut map = global.map.borrow_mut();
+        let popup_menu = map.get_mut(&self.popup_menu.unwrap()).unwrap();
+        popup_menu.init_select_item(LiveId(self.highlighted as u64).into());
+    }
+
+    pub fn set_closed(&mut self, cx: &mut Cx) {
+        self.is_open = false;
+        self.text_input.redraw(cx);
+    }
+
+    fn move_highlight(&mut self, cx: &mut Cx, delta: isize) {
+        if self.matches.is_empty() {
+            return
+        }
+        let last = self.matches.len() as isize - 1;
+        self.highlighted = (self.highlighted as isize + delta).max(0).min(last) as usize;
+        self.select_highlighted(cx);
+        self.text_input.redraw(cx);
+    }
+
+    fn commit(&mut self, cx: &mut Cx, scope: &mut Scope, item: ComboBoxItem) {
+        let uid = self.widget_uid();
+        match item {
+            ComboBoxItem::Label(index) => {
+                self.selected_item = index;
+                let value = self.values.get(index).cloned().unwrap_or(LiveValue::None);
+                cx.widget_action(uid, &scope.path, DropDownAction::Select(index, value.clone()));
+                cx.widget_action(uid, &scope.path, ComboBoxAction::Select(index, value));
+            }
+            ComboBoxItem::Suggestion(index) => {
+                let label = self.suggestions[index].clone();
+                cx.widget_action(uid, &scope.path, ComboBoxAction::SelectSuggestion(label));
+            }
+        }
+        self.text_input.text = self.label_for(item).to_string();
+        self.text_input.select_all();
+        self.set_closed(cx);
+    }
+
+    fn commit_text(&mut self, cx: &mut Cx, scope: &mut Scope) {
+        if let Some(m) = self.matches.get(self.highlighted) {
+            if self.is_open {
+                let item = m.item;
+                self.commit(cx, scope, item);
+                return
+            }
+        }
+        if self.allow_free_text {
+            let text = self.text_input.text.clone();
+            cx.widget_action(self.widget_uid(), &scope.path, ComboBoxAction::FreeText(text));
+        }
+        else {
+            self.sync_text_to_selected();
+        }
+        self.set_closed(cx);
+    }
+
+    fn draw_popup(&mut self, cx: &mut Cx2d) {
+        let global = cx.global::<PopupMenuGlobal>().clone();
+        let mut map = global.map.borrow_mut();
+        let popup_menu = map.get_mut(&self.popup_menu.unwrap()).unwrap();
+
+        popup_menu.begin(cx);
+        for (i, m) in self.matches.iter().enumerate() {
+            let label = match m.item {
+                ComboBoxItem::Label(index) => &self.labels[index],
+                ComboBoxItem::Suggestion(index) => &self.suggestions[index],
+            };
+            popup_menu.draw_item_with_ranges(cx, LiveId(i as u64).into(), label, &m.fuzzy.ranges);
+        }
+
+        let area = self.text_input.area();
+        let shift = DVec2 {
+            x: 0.0,
+            y: area.rect(cx).size.y,
+        };
+        popup_menu.end(cx, area, shift);
+    }
+}
+
+impl Widget for ComboBox {
+
+    fn widget_to_data(&self, _cx: &mut Cx, actions: &Actions, nodes: &mut LiveNodeVec, path: &[LiveId]) -> bool {
+        for action in actions.filter_widget_actions_cast::<ComboBoxAction>(self.widget_uid()) {
+            match action {
+                ComboBoxAction::Select(_, value) => {
+                    nodes.write_field_value(path, value);
+                    return true
+                }
+                ComboBoxAction::SelectSuggestion(text) | ComboBoxAction::FreeText(text) => {
+                    nodes.write_field_value(path, LiveValue::String(Arc::new(text)));
+                    return true
+                }
+                _ => ()
+            }
+        }
+        false
+    }
+
+    fn data_to_widget(&mut self, cx: &mut Cx, nodes: &[LiveNode], path: &[LiveId]) {
+        if let Some(value) = nodes.read_field_value(path) {
+            if let Some(index) = self.values.iter().position( | v | v == value) {
+                if self.selected_item != index {
+                    self.selected_item = index;
+                    self.sync_text_to_selected();
+                    self.redraw(cx);
+                }
+            }
+            else if self.allow_free_text {
+                let text = match value {
+                    LiveValue::String(s) => s.as_str(),
+                    LiveValue::InlineString(s) => s.as_str(),
+                    _ => return
+                };
+                if self.text_input.text != text {
+                    self.text_input.text = text.to_string();
+                    self.redraw(cx);
+                }
+            }
+        }
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        let uid = self.widget_uid();
+
+        if self.query_timer.is_event(event).is_some() {
+            self.query_timer = Timer::empty();
+            cx.widget_action(uid, &scope.path, ComboBoxAction::Query(self.text_input.text.clone()));
+        }
+
+        if self.is_open && self.popup_menu.is_some() {
+            let global = cx.global::<PopupMenuGlobal>().clone();
+            let mut map = global.map.borrow_mut();
+            let menu = map.get_mut(&self.popup_menu.unwrap()).unwrap();
+            let mut selected = None;
+            menu.handle_event_with(cx, event, self.text_input.area(), &mut | _, action | {
+                if let PopupMenuAction::WasSelected(node_id) = action {
+                    selected = Some(node_id.0.0 as usize);
+                }
+            });
+            let clicked_outside = if let Event::MouseDown(e) = event {
+                !menu.menu_contains_pos(cx, e.abs) && !self.text_input.area().rect(cx).contains(e.abs)
+            }
+            else {
+                false
+            };
+            drop(map);
+            if let Some(item) = selected.and_then( | i | self.matches.get(i)).map( | m | m.item) {
+                self.commit(cx, scope, item);
+            }
+            else if clicked_outside {
+                self.commit_text(cx, scope);
+            }
+        }
+
+        // the popup keys have to be taken before the text input sees them
+        if let Event::KeyDown(ke) = event {
+            if cx.has_key_focus(self.text_input.area()) {
+                match ke.key_code {
+                    KeyCode::ArrowDown if !self.is_open => {
+                        self.update_matches();
+                        self.set_open(cx);
+                        return
+                    }
+                    KeyCode::ArrowDown => {
+                        self.move_highlight(cx, 1);
+                        return
+                    }
+                    KeyCode::ArrowUp if self.is_open => {
+                        self.move_highlight(cx, -1);
+                        return
+                    }
+                    KeyCode::PageDown if self.is_open => {
+                        self.move_highlight(cx, 10);
+                        return
+                    }
+                    KeyCode::PageUp if self.is_open => {
+                        self.move_highlight(cx, -10);
+                        return
+                    }
+                    KeyCode::Escape if self.is_open => {
+                        self.set_closed(cx);
+                        return
+                    }
+                    _ => ()
+                }
+            }
+        }
+
+        for action in cx.capture_actions( | cx | self.text_input.handle_event(cx, event, scope)) {
+            match action.as_widget_action().cast() {
+                TextInputAction::Change(_) => {
+                    self.update_matches();
+                    self.set_open(cx);
+                    if self.query_debounce > 0.0 {
+                        cx.stop_timer(self.query_timer);
+                        self.query_timer = cx.start_timeout(self.query_debounce);
+                    }
+                }
+                TextInputAction::Return(_) => {
+                    self.commit_text(cx, scope);
+                }
+                TextInputAction::KeyFocus => {
+                    self.text_input.select_all();
+                }
+                TextInputAction::KeyFocusLost => {
+                    if self.is_open {
+                        self.commit_text(cx, scope);
+                    }
+                    else if !self.allow_free_text {
+                        self.sync_text_to_selected();
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.text_input.draw_walk(cx, scope, walk) ?;
+        if self.is_open && self.popup_menu.is_some() && !self.matches.is_empty() {
+            self.draw_popup(cx);
+        }
+        DrawStep::done()
+    }
+
+    fn text(&self) -> String {
+        self.text_input.text.clone()
+    }
+
+    fn set_text(&mut self, v: &str) {
+        self.text_input.set_text(v);
+        if let Some(index) = self.labels.iter().position( | l | l == v) {
+            self.selected_item = index;
+        }
+    }
+}
+
+impl ComboBoxRef {
+    pub fn set_labels(&self, labels: Vec<String>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.labels = labels;
+            inner.update_matches();
+        }
+    }
+
+    pub fn set_labels_and_redraw(&self, cx: &mut Cx, labels: Vec<String>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.labels = labels;
+            inner.update_matches();
+            inner.redraw(cx);
+        }
+    }
+
+    /// Hands back the result of a `ComboBoxAction::Query`. Results for a query that
+    /// no longer matches the text in the input are stale and get dropped.
+    pub fn set_suggestions(&self, cx: &mut Cx, query: &str, suggestions: Vec<String>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            if inner.text_input.text != query {
+                return
+            }
+            inner.suggestions = suggestions;
+            inner.update_matches();
+            inner.redraw(cx);
+        }
+    }
+
+    pub fn selected(&self, actions: &Actions) -> Option<usize> {
+        for action in actions.filter_widget_actions_cast::<ComboBoxAction>(self.widget_uid()) {
+            if let ComboBoxAction::Select(index, _) = action {
+                return Some(index)
+            }
+        }
+        None
+    }
+
+    pub fn selected_suggestion(&self, actions: &Actions) -> Option<String> {
+        for action in actions.filter_widget_actions_cast::<ComboBoxAction>(self.widget_uid()) {
+            if let ComboBoxAction::SelectSuggestion(text) = action {
+                return Some(text)
+            }
+        }
+        None
+    }
+
+    pub fn free_text(&self, actions: &Actions) -> Option<String> {
+        for action in actions.filter_widget_actions_cast::<ComboBoxAction>(self.widget_uid()) {
+            if let ComboBoxAction::FreeText(text) = action {
+                return Some(text)
+            }
+        }
+        None
+    }
+
+    pub fn query(&self, actions: &Actions) -> Option<String> {
+        for action in actions.filter_widget_actions_cast::<ComboBoxAction>(self.widget_uid()) {
+            if let ComboBoxAction::Query(text) = action {
+                return Some(text)
+            }
+        }
+        None
+    }
+
+    pub fn selected_item(&self) -> usize {
+        if let Some(inner) = self.borrow() {
+            return inner.selected_item
+        }
+        0
+    }
+
+    pub fn set_selected_item_and_redraw(&self, cx: &mut Cx, item: usize) {
+        if let Some(mut inner) = self.borrow_mut() {
+            let new_selected = item.min(inner.labels.len().max(1) - 1);
+            inner.selected_item = new_selected;
+            inner.sync_text_to_selected();
+            inner.redraw(cx);
+        }
+    }
+}
+
+/// The result of `fuzzy_match`: a score to rank candidates by and the byte
+/// ranges of `candidate` that matched the pattern, for highlighting.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Matches `pattern` as a case-insensitive subsequence of `candidate`.
+/// Consecutive characters, characters at the start of a word and characters
+/// whose case matches too score higher, gaps and late starts score lower. An
+/// empty pattern matches everything.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    // both sides are lowercased the same way, char by char. That can turn one
+    // char into several, so each lowercased char keeps the char it came from.
+    let pattern: Vec<(char, char)> = pattern.chars()
+        .flat_map( | c | c.to_lowercase().map(move | l | (l, c)))
+        .collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch::default())
+    }
+    let chars: Vec<(usize, char)> = candidate.char_indices().collect();
+    let lower: Vec<(char, usize)> = chars.iter().enumerate()
+        .flat_map( | (i, (_, c)) | c.to_lowercase().map(move | l | (l, i)))
+        .collect();
+
+    let is_word_start = | j: usize | {
+        let i = lower[j].1;
+        if j > 0 && lower[j - 1].1 == i {
+            return false
+        }
+        if i == 0 {
+            return true
+        }
+        let (prev, cur) = (chars[i - 1].1, chars[i].1);
+        !prev.is_alphanumeric() || (prev.is_lowercase() && cur.is_uppercase())
+    };
+
+    // try every start position of the first pattern char and keep the best greedy run
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in 0..lower.len() {
+        if lower[start].0 != pattern[0].0 {
+            continue
+        }
+        let mut positions = vec![start];
+        let mut i = start + 1;
+        for (pc, _) in &pattern[1..] {
+            // prefer a word start for the next char if there is one before a plain match
+            let mut found = None;
+            let mut j = i;
+            while j < lower.len() {
+                if lower[j].0 == *pc {
+                    if j == i || is_word_start(j) {
+                        found = Some(j);
+                        break
+                    }
+                    if found.is_none() {
+                        found = Some(j);
+                    }
+                }
+                j += 1;
+            }
+            match found {
+                Some(j) => {
+                    positions.push(j);
+                    i = j + 1;
+                }
+                None => break
+            }
+        }
+        if positions.len() != pattern.len() {
+            // later starts can't find more characters either
+            break
+        }
+        // gaps and late starts are counted in chars of the candidate
+        let mut score = -(lower[start].1 as i64);
+        for (n, &pos) in positions.iter().enumerate() {
+            score += 1;
+            if is_word_start(pos) {
+                score += 8;
+            }
+            if chars[lower[pos].1].1 == pattern[n].1 {
+                score += 1;
+            }
+            if n > 0 {
+                let gap = lower[pos].1.saturating_sub(lower[positions[n - 1]].1 + 1);
+                if gap == 0 {
+                    score += 6;
+                }
+                else {
+                    score -= gap.min(8) as i64;
+                }
+            }
+        }
+        if best.as_ref().is_none_or( | (s, _) | score > *s) {
+            best = Some((score, positions));
+        }
+    }
+
+    let (score, positions) = best?;
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for pos in positions {
+        let (byte, c) = chars[lower[pos].1];
+        let end = byte + c.len_utf8();
+        match ranges.last_mut() {
+            // several lowercased chars of the same char matched
+            Some(last) if last.end > byte => {}
+            Some(last) if last.end == byte => last.end = end,
+            _ => ranges.push(byte..end),
+        }
+    }
+    Some(FuzzyMatch {score, ranges})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank<'a>(pattern: &str, candidates: &[&'a str]) -> Vec<&'a str> {
+        let mut matches: Vec<(i64, &str)> = candidates
+            .iter()
+            .filter_map( | candidate | Some((fuzzy_match(pattern, candidate)?.score, *candidate)))
+            .collect();
+        matches.sort_by_key( | (score, _) | std::cmp::Reverse(*score));
+        matches.into_iter().map( | (_, candidate) | candidate).collect()
+    }
+
+    #[test]
+    fn fuzzy_match_ranks_prefixes_and_word_starts_first() {
+        assert_eq!(
+            rank("fb", &["a fooBar", "fbx", "f_b", "xfxxxb"]),
+            ["f_b", "fbx", "a fooBar", "xfxxxb"]
+        );
+        assert_eq!(rank("ap", &["grape", "apple", "Apricot"]), ["apple", "Apricot", "grape"]);
+        // a matching case only decides between otherwise equal matches
+        assert_eq!(rank("fo", &["Formatter", "for"]), ["for", "Formatter"]);
+        assert_eq!(rank("Fo", &["for", "Formatter"]), ["Formatter", "for"]);
+    }
+
+    #[test]
+    fn fuzzy_match_returns_the_matched_ranges() {
+        let fuzzy = fuzzy_match("fb", "fooBar").unwrap();
+        assert_eq!(fuzzy.ranges, [0..1, 3..4]);
+        let fuzzy = fuzzy_match("FOO", "xfoo").unwrap();
+        assert_eq!(fuzzy.ranges, [1..4]);
+        let fuzzy = fuzzy_match("é", "café").unwrap();
+        assert_eq!(fuzzy.ranges, [3..5]);
+    }
+
+    #[test]
+    fn fuzzy_match_lowercases_pattern_and_candidate_alike() {
+        // `İ` lowercases to `i` followed by a combining dot
+        let fuzzy = fuzzy_match("İ", "İstanbul").unwrap();
+        assert_eq!(fuzzy.ranges, [0..2]);
+        let fuzzy = fuzzy_match("ist", "İstanbul").unwrap();
+        assert_eq!(fuzzy.ranges, [0..4]);
+        // the dot is not a gap, only the case of the `i` differs
+        assert_eq!(fuzzy.score, fuzzy_match("ist", "istanbul").unwrap().score - 1);
+        let fuzzy = fuzzy_match("i\u{307}s", "xİs").unwrap();
+        assert_eq!(fuzzy.ranges, [1..4]);
+    }
+
+    #[test]
+    fn fuzzy_match_rejects_non_matches() {
+        assert_eq!(fuzzy_match("abc", "acb"), None);
+        assert_eq!(fuzzy_match("x", ""), None);
+        assert_eq!(fuzzy_match("apples", "apple"), None);
+    }
+
+    #[test]
+    fn fuzzy_match_matches_everything_with_an_empty_pattern() {
+        assert_eq!(fuzzy_match("", "anything"), Some(FuzzyMatch::default()));
+        assert_eq!(fuzzy_match("", ""), Some(FuzzyMatch::default()));
+    }
+}
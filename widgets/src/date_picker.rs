@@ -0,0 +1,250 @@
+use std::sync::Arc;
+use crate::{
+    makepad_derive_widget::*,
+    makepad_draw::*,
+    widget::*,
+    calendar::{Calendar, CalendarAction},
+    date_time::Date,
+};
+
+live_design!{
+    DatePickerBase = {{DatePicker}} {}
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum DatePickerAction {
+    Select(Date),
+    SelectRange(Date, Date),
+    None
+}
+
+/// A field showing the picked date that pops up a `Calendar` below itself,
+/// drawn in an overlay the same way `PopupMenu` is for `DropDown`.
+#[derive(Live, Widget)]
+pub struct DatePicker {
+    #[animator] animator: Animator,
+
+    #[redraw] #[live] draw_bg: DrawQuad,
+    #[live] draw_text: DrawText,
+
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+
+    #[live] bind: String,
+    #[live] empty_message: String,
+
+    #[live] calendar: Calendar,
+    #[live] draw_list: DrawList2d,
+    #[live] draw_popup: DrawQuad,
+    #[live] popup_layout: Layout,
+
+    #[rust] is_open: bool,
+    #[rust] focus_calendar: bool,
+}
+
+impl LiveHook for DatePicker {
+    fn after_apply(&mut self, cx: &mut Cx, _apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        self.draw_list.redraw(cx);
+    }
+}
+
+impl DatePicker {
+
+    pub fn set_open(&mut self, cx: &mut Cx) {
+        self.is_open = true;
+        self.draw_bg.apply_over(cx, live!{open: 1.0});
+        self.calendar.set_selected_date(cx, self.calendar.selected_date());
+        // the calendar only has an area once the popup is drawn
+        self.focus_calendar = true;
+        self.draw_bg.redraw(cx);
+        self.draw_list.redraw(cx);
+    }
+
+    pub fn set_closed(&mut self, cx: &mut Cx) {
+        self.is_open = false;
+        self.draw_bg.apply_over(cx, live!{open: 0.0});
+        self.draw_bg.redraw(cx);
+        self.draw_list.redraw(cx);
+    }
+
+    fn label(&self) -> String {
+        let text = self.calendar.text();
+        if text.is_empty() {
+            self.empty_message.clone()
+        }
+        else {
+            text.replace('/', " - ")
+        }
+    }
+
+    fn draw_popup(&mut self, cx: &mut Cx2d, scope: &mut Scope) {
+        self.draw_list.begin_overlay_reuse(cx);
+        cx.begin_pass_sized_turtle(Layout::flow_down());
+        self.draw_popup.begin(cx, Walk::fit(), self.popup_layout);
+        let walk = self.calendar.walk(cx);
+        let _ = self.calendar.draw_walk(cx, scope, walk);
+        self.draw_popup.end(cx);
+
+        let area = self.draw_bg.area();
+        let shift = DVec2 {
+            x: 0.0,
+            y: area.rect(cx).size.y,
+        };
+        cx.end_pass_sized_turtle_with_shift(area, shift);
+        self.draw_list.end(cx);
+
+        if self.focus_calendar {
+            self.focus_calendar = false;
+            cx.set_key_focus(self.calendar.area());
+        }
+    }
+}
+
+impl Widget for DatePicker {
+
+    fn widget_to_data(&self, _cx: &mut Cx, actions: &Actions, nodes: &mut LiveNodeVec, path: &[LiveId]) -> bool {
+        match actions.find_widget_action_cast(self.widget_uid()) {
+            DatePickerAction::Select(_) | DatePickerAction::SelectRange(_, _) => {
+                nodes.write_field_value(path, LiveValue::String(Arc::new(self.calendar.text())));
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn data_to_widget(&mut self, cx: &mut Cx, nodes: &[LiveNode], path: &[LiveId]) {
+        if let Some(value) = nodes.read_field_value(path) {
+            let text = match value {
+                LiveValue::String(s) => s.as_str(),
+                LiveValue::InlineString(s) => s.as_str(),
+                _ => return
+            };
+            self.calendar.set_text(text);
+            self.redraw(cx);
+        }
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        self.animator_handle_event(cx, event);
+        let uid = self.widget_uid();
+
+        if self.is_open {
+            for action in cx.capture_actions( | cx | self.calendar.handle_event(cx, event, scope)) {
+                match action.as_widget_action().cast() {
+                    CalendarAction::Select(date) => {
+                        cx.widget_action(uid, &scope.path, DatePickerAction::Select(date));
+                        self.set_closed(cx);
+                    }
+                    CalendarAction::SelectRange(start, end) => {
+                        cx.widget_action(uid, &scope.path, DatePickerAction::SelectRange(start, end));
+                        self.set_closed(cx);
+                    }
+                    _ => ()
+                }
+            }
+            match event {
+                Event::MouseDown(e) if !self.calendar.area().clipped_rect(cx).contains(e.abs)
+                    && !self.draw_bg.area().rect(cx).contains(e.abs) => {
+                    self.set_closed(cx);
+                }
+                Event::KeyDown(KeyEvent {key_code: KeyCode::Escape, ..}) => {
+                    self.set_closed(cx);
+                    cx.set_key_focus(self.draw_bg.area());
+                }
+                _ => ()
+            }
+        }
+
+        match event.hits(cx, self.draw_bg.area()) {
+            Hit::KeyFocus(_) => {
+                self.animator_play(cx, id!(focus.on));
+            }
+            Hit::KeyFocusLost(_) => {
+                self.animator_play(cx, id!(focus.off));
+            }
+            Hit::KeyDown(ke) => match ke.key_code {
+                KeyCode::ArrowDown | KeyCode::ReturnKey | KeyCode::Space => {
+                    self.set_open(cx);
+                }
+                _ => ()
+            }
+            Hit::FingerDown(_) => {
+                if self.is_open {
+                    self.set_closed(cx);
+                }
+                else {
+                    self.set_open(cx);
+                }
+                self.animator_play(cx, id!(hover.pressed));
+            }
+            Hit::FingerHoverIn(_) => {
+                cx.set_cursor(MouseCursor::Hand);
+                self.animator_play(cx, id!(hover.on));
+            }
+            Hit::FingerHoverOut(_) => {
+                self.animator_play(cx, id!(hover.off));
+            }
+            Hit::FingerUp(fe) => {
+                if fe.is_over && fe.device.has_hovers() {
+                    self.animator_play(cx, id!(hover.on));
+                }
+                else {
+                    self.animator_play(cx, id!(hover.off));
+                }
+            }
+            _ => ()
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, scope: &mut Scope, walk: Walk) -> DrawStep {
+        self.draw_bg.begin(cx, walk, self.layout);
+        let label = self.label();
+        self.draw_text.draw_walk(cx, Walk::fit(), Align::default(), &label);
+        self.draw_bg.end(cx);
+
+        cx.add_nav_stop(self.draw_bg.area(), NavRole::DropDown, Margin::default());
+
+        if self.is_open {
+            self.draw_popup(cx, scope);
+        }
+        DrawStep::done()
+    }
+
+    fn text(&self) -> String {
+        self.calendar.text()
+    }
+
+    fn set_text(&mut self, v: &str) {
+        self.calendar.set_text(v);
+    }
+}
+
+impl DatePickerRef {
+    pub fn selected(&self, actions: &Actions) -> Option<Date> {
+        if let DatePickerAction::Select(date) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some(date)
+        }
+        None
+    }
+
+    pub fn selected_range(&self, actions: &Actions) -> Option<(Date, Date)> {
+        if let DatePickerAction::SelectRange(start, end) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some((start, end))
+        }
+        None
+    }
+
+    pub fn date(&self) -> Option<Date> {
+        if let Some(inner) = self.borrow() {
+            return inner.calendar.selected_date()
+        }
+        None
+    }
+
+    pub fn set_date(&self, cx: &mut Cx, date: Option<Date>) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.calendar.set_selected_date(cx, date);
+            inner.draw_bg.redraw(cx);
+        }
+    }
+}
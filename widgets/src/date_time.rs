@@ -0,0 +1,381 @@
+//! Plain calendar arithmetic for the `Calendar`, `DatePicker` and `TimePicker` widgets.
+//! Dates are proleptic Gregorian and carry no timezone; everything here is pure so it
+//! can be used and tested without a `Cx`.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// Zero based index with Monday as 0, as in ISO 8601.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Weekday {
+        Self::ALL[index % 7]
+    }
+
+    /// The number of days from `first` to `self` going forward, in 0..7.
+    pub fn days_since(self, first: Weekday) -> usize {
+        (self.index() + 7 - first.index()) % 7
+    }
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Weekday::Monday => "Mo",
+            Weekday::Tuesday => "Tu",
+            Weekday::Wednesday => "We",
+            Weekday::Thursday => "Th",
+            Weekday::Friday => "Fr",
+            Weekday::Saturday => "Sa",
+            Weekday::Sunday => "Su",
+        }
+    }
+
+    /// The first day of the week customary for a BCP 47 style locale such as
+    /// `en-US` or `fa_IR`. Only the region subtag is looked at; locales without a
+    /// region, or with one we don't know, start on Monday as ISO 8601 does.
+    pub fn first_day_for_locale(locale: &str) -> Weekday {
+        let region = locale
+            .split(['-', '_', '.'])
+            .skip(1)
+            .find(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(|part| part.to_ascii_uppercase());
+        let Some(region) = region else {
+            return Weekday::Monday
+        };
+        const SUNDAY: &[&str] = &[
+            "AG", "AS", "AU", "BD", "BR", "BS", "BT", "BW", "BZ", "CA", "CN", "CO", "DM",
+            "DO", "ET", "GT", "GU", "HK", "HN", "ID", "IL", "IN", "JM", "JP", "KE", "KH",
+            "KR", "LA", "MH", "MM", "MO", "MT", "MX", "MZ", "NI", "NP", "PA", "PE", "PH",
+            "PK", "PR", "PT", "PY", "SA", "SG", "SV", "TH", "TT", "TW", "UM", "US", "VE",
+            "VI", "WS", "YE", "ZA", "ZW",
+        ];
+        const SATURDAY: &[&str] = &[
+            "AE", "AF", "BH", "DJ", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA",
+            "SD", "SY",
+        ];
+        if SUNDAY.contains(&region.as_str()) {
+            Weekday::Sunday
+        }
+        else if SATURDAY.contains(&region.as_str()) {
+            Weekday::Saturday
+        }
+        else {
+            Weekday::Monday
+        }
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+impl Date {
+    /// Returns `None` if the month or day is out of range.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None
+        }
+        Some(Date {year, month, day})
+    }
+
+    /// Days since 1970-01-01, negative before it.
+    pub fn to_days(self) -> i64 {
+        // Howard Hinnant's days_from_civil
+        let year = self.year as i64 - if self.month <= 2 {1} else {0};
+        let era = (if year >= 0 {year} else {year - 399}) / 400;
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (month + if month > 2 {-3} else {9}) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    pub fn from_days(days: i64) -> Date {
+        // Howard Hinnant's civil_from_days
+        let days = days + 719468;
+        let era = (if days >= 0 {days} else {days - 146096}) / 146097;
+        let day_of_era = days - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 {mp + 3} else {mp - 9}) as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 {1} else {0}) as i32;
+        Date {year, month, day}
+    }
+
+    pub fn weekday(self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::from_index((self.to_days() + 3).rem_euclid(7) as usize)
+    }
+
+    pub fn add_days(self, days: i64) -> Date {
+        Date::from_days(self.to_days() + days)
+    }
+
+    /// Moves by whole months, clamping the day to the length of the target month
+    /// so that Jan 31 + 1 month is Feb 28 (or 29).
+    pub fn add_months(self, months: i32) -> Date {
+        let total = self.year as i64 * 12 + (self.month as i64 - 1) + months as i64;
+        let year = total.div_euclid(12) as i32;
+        let month = total.rem_euclid(12) as u32 + 1;
+        let day = self.day.min(days_in_month(year, month));
+        Date {year, month, day}
+    }
+
+    pub fn add_years(self, years: i32) -> Date {
+        self.add_months(years * 12)
+    }
+
+    pub fn first_of_month(self) -> Date {
+        Date {day: 1, ..self}
+    }
+
+    pub fn last_of_month(self) -> Date {
+        Date {day: days_in_month(self.year, self.month), ..self}
+    }
+
+    pub fn start_of_week(self, first_day: Weekday) -> Date {
+        self.add_days(-(self.weekday().days_since(first_day) as i64))
+    }
+
+    /// The first date shown in a six week month grid that contains `self`.
+    pub fn month_grid_start(self, first_day: Weekday) -> Date {
+        self.first_of_month().start_of_week(first_day)
+    }
+
+    pub fn days_until(self, other: Date) -> i64 {
+        other.to_days() - self.to_days()
+    }
+
+    pub fn clamp_to(self, min: Option<Date>, max: Option<Date>) -> Date {
+        let mut date = self;
+        if let Some(min) = min {
+            date = date.max(min);
+        }
+        if let Some(max) = max {
+            date = date.min(max);
+        }
+        date
+    }
+
+    pub fn is_within(self, min: Option<Date>, max: Option<Date>) -> bool {
+        min.is_none_or(|min| self >= min) && max.is_none_or(|max| self <= max)
+    }
+
+    /// Parses `YYYY-MM-DD`. Years before 1 AD are written with a sign, as in
+    /// `-0044-03-15`, where year 0 is 1 BC.
+    pub fn parse_iso(s: &str) -> Option<Date> {
+        let s = s.trim();
+        let (sign, s) = match s.strip_prefix('-') {
+            Some(s) => (-1, s),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let mut parts = s.splitn(3, '-');
+        let year = parts.next()?;
+        if !year.chars().all(|c| c.is_ascii_digit()) {
+            return None
+        }
+        let year = sign * year.parse::<i32>().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Date::new(year, month, day)
+    }
+
+    /// Today in UTC according to the system clock. Falls back to the Unix epoch
+    /// where there is no clock available.
+    pub fn today() -> Date {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Ok(duration) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+                return Date::from_days((duration.as_secs() / 86400) as i64)
+            }
+        }
+        Date::from_days(0)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.year < 0 {
+            write!(f, "-")?;
+        }
+        write!(f, "{:04}-{:02}-{:02}", self.year.unsigned_abs(), self.month, self.day)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Time {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl Time {
+    pub fn new(hour: u32, minute: u32, second: u32) -> Option<Time> {
+        if hour > 23 || minute > 59 || second > 59 {
+            return None
+        }
+        Some(Time {hour, minute, second})
+    }
+
+    pub fn to_seconds(self) -> i64 {
+        self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    /// Builds a time from seconds since midnight, wrapping around the day.
+    pub fn from_seconds(seconds: i64) -> Time {
+        let seconds = seconds.rem_euclid(86400);
+        Time {
+            hour: (seconds / 3600) as u32,
+            minute: (seconds / 60 % 60) as u32,
+            second: (seconds % 60) as u32,
+        }
+    }
+
+    pub fn add_seconds(self, seconds: i64) -> Time {
+        Time::from_seconds(self.to_seconds() + seconds)
+    }
+
+    /// Parses `HH:MM` or `HH:MM:SS`.
+    pub fn parse(s: &str) -> Option<Time> {
+        let mut parts = s.trim().splitn(3, ':');
+        let hour = parts.next()?.parse().ok()?;
+        let minute = parts.next()?.parse().ok()?;
+        let second = match parts.next() {
+            Some(second) => second.parse().ok()?,
+            None => 0,
+        };
+        Time::new(hour, minute, second)
+    }
+
+    pub fn clamp_to(self, min: Option<Time>, max: Option<Time>) -> Time {
+        let mut time = self;
+        if let Some(min) = min {
+            time = time.max(min);
+        }
+        if let Some(max) = max {
+            time = time.min(max);
+        }
+        time
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(2024));
+        assert!(!is_leap_year(2023));
+        assert!(!is_leap_year(1900));
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(0));
+        assert!(is_leap_year(-4));
+        assert!(!is_leap_year(-100));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(Date::new(2023, 2, 29), None);
+        assert_eq!(date(2024, 2, 28).add_days(1), date(2024, 2, 29));
+        assert_eq!(date(2023, 2, 28).add_days(1), date(2023, 3, 1));
+    }
+
+    #[test]
+    fn month_rollover() {
+        assert_eq!(date(2023, 12, 31).add_days(1), date(2024, 1, 1));
+        assert_eq!(date(2024, 1, 1).add_days(-1), date(2023, 12, 31));
+        assert_eq!(date(2024, 1, 31).add_months(1), date(2024, 2, 29));
+        assert_eq!(date(2023, 1, 31).add_months(1), date(2023, 2, 28));
+        assert_eq!(date(2024, 3, 15).add_months(-3), date(2023, 12, 15));
+        assert_eq!(date(2024, 11, 30).add_months(14), date(2026, 1, 30));
+        assert_eq!(date(2024, 2, 29).add_years(1), date(2025, 2, 28));
+        assert_eq!(date(2024, 2, 10).last_of_month(), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn days_and_weekdays_from_the_epoch() {
+        assert_eq!(date(1970, 1, 1).to_days(), 0);
+        assert_eq!(Date::from_days(0), date(1970, 1, 1));
+        assert_eq!(date(1970, 1, 1).weekday(), Weekday::Thursday);
+        assert_eq!(date(1969, 12, 31).to_days(), -1);
+        assert_eq!(date(1969, 12, 31).weekday(), Weekday::Wednesday);
+        assert_eq!(date(2000, 1, 1).to_days(), 10957);
+        assert_eq!(date(2000, 1, 1).weekday(), Weekday::Saturday);
+        assert_eq!(date(2024, 10, 19).weekday(), Weekday::Saturday);
+        for days in [-800_000, -146_097, -1, 0, 59, 60, 365, 146_097, 2_932_896] {
+            assert_eq!(Date::from_days(days).to_days(), days);
+        }
+        assert_eq!(date(2024, 10, 19).start_of_week(Weekday::Monday), date(2024, 10, 14));
+        assert_eq!(date(2024, 10, 19).start_of_week(Weekday::Sunday), date(2024, 10, 13));
+    }
+
+    #[test]
+    fn parse_and_format_round_trip() {
+        for text in ["2024-02-29", "1970-01-01", "0001-12-31", "0000-03-01", "-0044-03-15"] {
+            let date = Date::parse_iso(text).unwrap();
+            assert_eq!(date.to_string(), text);
+            assert_eq!(Date::parse_iso(&date.to_string()), Some(date));
+        }
+        assert_eq!(Date::parse_iso("-0044-03-15"), Some(date(-44, 3, 15)));
+        assert_eq!(Date::parse_iso(" 2024-1-5 "), Some(date(2024, 1, 5)));
+        assert_eq!(Date::parse_iso("2023-02-29"), None);
+        assert_eq!(Date::parse_iso("2024-02"), None);
+        assert_eq!(Date::parse_iso("--2024-02-01"), None);
+        for text in ["00:00:00", "23:59:59", "09:05:07"] {
+            assert_eq!(Time::parse(text).unwrap().to_string(), text);
+        }
+        assert_eq!(Time::parse("9:30"), Time::new(9, 30, 0));
+        assert_eq!(Time::parse("24:00"), None);
+        assert_eq!(Time::from_seconds(-1), Time::new(23, 59, 59).unwrap());
+    }
+}
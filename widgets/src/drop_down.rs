@@ -48,8 +48,8 @@ pub struct DropDown {
 }
 
 #[derive(Default, Clone)]
-struct PopupMenuGlobal {
-    map: Rc<RefCell<ComponentMap<LivePtr, PopupMenu >> >
+pub(crate) struct PopupMenuGlobal {
+    pub(crate) map: Rc<RefCell<ComponentMap<LivePtr, PopupMenu >> >
 }
 
 #[derive(Live, LiveHook, LiveRegister)]#[repr(C)]
@@ -13,6 +13,7 @@ pub mod image_blend;
 pub mod icon;
 pub mod link_label;
 pub mod drop_down;
+pub mod combo_box;
 pub mod popup_menu;
 pub mod check_box;
 pub mod radio_button;
@@ -54,6 +55,12 @@ pub mod flat_list;
 pub mod file_tree;
 pub mod slides_view;
 pub mod color_picker;
+pub mod date_time;
+pub mod calendar;
+pub mod date_picker;
+pub mod time_picker;
+pub mod chart_data;
+pub mod chart;
 pub mod root;
 
 pub mod debug_view;
@@ -97,6 +104,13 @@ pub use crate::{
     html::*,
     check_box::*,
     drop_down::*,
+    combo_box::*,
+    calendar::*,
+    date_picker::*,
+    time_picker::*,
+    chart_data::*,
+    chart::*,
+    date_time::{Date, Time, Weekday},
     modal::*,
     tooltip::*,
     popup_notification::*,
@@ -181,6 +195,7 @@ pub fn live_design(cx: &mut Cx) {
     crate::radio_button::live_design(cx);
     crate::popup_menu::live_design(cx);
     crate::drop_down::live_design(cx);
+    crate::combo_box::live_design(cx);
     crate::multi_window::live_design(cx);
     crate::portal_list::live_design(cx);
     crate::flat_list::live_design(cx);
@@ -189,6 +204,10 @@ pub fn live_design(cx: &mut Cx) {
     crate::tab_bar::live_design(cx);
     crate::dock::live_design(cx);
     crate::color_picker::live_design(cx);
+    crate::calendar::live_design(cx);
+    crate::date_picker::live_design(cx);
+    crate::time_picker::live_design(cx);
+    crate::chart::live_design(cx);
     crate::file_tree::live_design(cx);
     crate::slides_view::live_design(cx);
     crate::tab_close_button::live_design(cx);
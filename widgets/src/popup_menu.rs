@@ -1,4 +1,5 @@
 use {
+    std::ops::Range,
     crate::{
         makepad_derive_widget::*,
         makepad_draw::*,
@@ -16,6 +17,7 @@ pub struct PopupMenuItem {
     
     #[live] draw_bg: DrawQuad,
     #[live] draw_name: DrawText,
+    #[live] draw_match: DrawText,
     
     #[layout] layout: Layout,
     #[animator] animator: Animator,
@@ -85,6 +87,29 @@ impl PopupMenuItem {
         self.draw_bg.end(cx);
     }
     
+    /// Draws the label with the given byte ranges drawn using `draw_match`,
+    /// used to show which characters a filter query matched.
+    pub fn draw_item_with_ranges(
+        &mut self,
+        cx: &mut Cx2d,
+        label: &str,
+        ranges: &[Range<usize>],
+    ) {
+        self.draw_bg.begin(cx, self.walk, self.layout);
+        let mut last = 0;
+        for range in ranges {
+            if range.start > last {
+                self.draw_name.draw_walk(cx, Walk::fit(), Align::default(), &label[last..range.start]);
+            }
+            self.draw_match.draw_walk(cx, Walk::fit(), Align::default(), &label[range.clone()]);
+            last = range.end;
+        }
+        if last < label.len() {
+            self.draw_name.draw_walk(cx, Walk::fit(), Align::default(), &label[last..]);
+        }
+        self.draw_bg.end(cx);
+    }
+    
     pub fn handle_event_with(
         &mut self,
         cx: &mut Cx,
@@ -187,6 +212,22 @@ impl PopupMenu {
         menu_item.draw_item(cx, label);
     }
     
+    pub fn draw_item_with_ranges(
+        &mut self,
+        cx: &mut Cx2d,
+        item_id: PopupMenuItemId,
+        label: &str,
+        ranges: &[Range<usize>],
+    ) {
+        self.count += 1;
+        
+        let menu_item = self.menu_item;
+        let menu_item = self.menu_items.get_or_insert(cx, item_id, | cx | {
+            PopupMenuItem::new_from_ptr(cx, menu_item)
+        });
+        menu_item.draw_item_with_ranges(cx, label, ranges);
+    }
+    
     pub fn init_select_item(&mut self, which_id: PopupMenuItemId) {
         self.init_select_item = Some(which_id);
         self.first_tap = true;
@@ -1,7 +1,8 @@
 use {
+    std::borrow::Cow,
     crate::{
         makepad_derive_widget::*,
-        makepad_draw::*,
+        makepad_draw::{*, shader::draw_text::TextWrap},
         widget::*,
     },
     unicode_segmentation::{GraphemeCursor, UnicodeSegmentation},
@@ -9,6 +10,7 @@ use {
 
 live_design!{
     DrawLabel = {{DrawLabel}} {}
+    DrawRevealToggle = {{DrawRevealToggle}} {}
     TextInputBase = {{TextInput}} {}
 }
 
@@ -19,7 +21,28 @@ pub struct DrawLabel {
     #[live] is_empty: f32,
 }
 
-#[derive(Live, LiveHook, Widget)]
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawRevealToggle {
+    #[deref] draw_super: DrawQuad,
+    #[live] revealed: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Live, LiveHook)]
+#[live_ignore]
+pub enum NumericMode {
+    #[pick] None,
+    Integer,
+    Float,
+}
+
+/// Checks the text after every change. An `Err` is shown below the text and
+/// emitted as `TextInputAction::Invalid`.
+pub type TextInputValidator = Box<dyn Fn(&str) -> Result<(), String>>;
+
+const PASSWORD_CHAR: char = '\u{2022}';
+
+#[derive(Live, Widget)]
 pub struct TextInput {
     #[animator] animator: Animator,
     
@@ -27,20 +50,52 @@ pub struct TextInput {
     #[live] draw_text: DrawLabel,
     #[live] draw_selection: DrawQuad,
     #[live] draw_cursor: DrawQuad,
+    #[live] draw_error: DrawText,
+    #[live] draw_reveal: DrawRevealToggle,
     
     #[layout] layout: Layout,
     #[walk] walk: Walk,
     #[live] label_align: Align,
 
     #[live] cursor_width: f64,
+    #[live(16.0)] reveal_width: f64,
 
     #[live] pub is_read_only: bool,
     #[live] pub is_numeric_only: bool,
+    #[live] pub is_password: bool,
+    #[live] pub is_multiline: bool,
     #[live] pub empty_message: String,
     #[live] pub text: String,
 
+    /// Maximum number of characters, 0 for no limit.
+    #[live] pub max_length: usize,
+    /// Formats the text as it is typed. `#` takes a digit, `A` a letter and
+    /// `*` either, `\` escapes the next character and anything else is
+    /// inserted literally, e.g. `(###) ###-####` or `####-##-##`.
+    #[live] pub input_mask: String,
+
+    #[live] pub numeric_mode: NumericMode,
+    #[live] pub min: Option<f64>,
+    #[live] pub max: Option<f64>,
+    #[live(1.0)] pub step: f64,
+
     #[rust] cursor: Cursor,
     #[rust] history: History,
+    #[rust] validator: Option<TextInputValidator>,
+    #[rust] error: Option<String>,
+    #[rust] is_revealed: bool,
+    #[rust] reveal_rect: Rect,
+    #[rust] viewport_scroll: DVec2,
+    #[rust] max_scroll: f64,
+    #[rust] scroll_to_cursor: bool,
+}
+
+impl LiveHook for TextInput {
+    fn after_apply(&mut self, _cx: &mut Cx, _apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        if self.is_multiline {
+            self.draw_text.wrap = TextWrap::Word;
+        }
+    }
 }
 
 impl TextInput {
@@ -66,16 +121,168 @@ impl TextInput {
     }
 
     pub fn filter_input(&mut self, input: String) -> String {
-        if self.is_numeric_only {
-            input.chars().filter_map(|char| {
+        match self.numeric_mode {
+            NumericMode::Integer => input.chars().filter(|char| {
+                char.is_ascii_digit() || *char == '-'
+            }).collect(),
+            NumericMode::Float => input.chars().filter_map(|char| {
                 match char {
                     '.' | ',' => Some('.'),
+                    '-' => Some('-'),
                     char if char.is_ascii_digit() => Some(char),
                     _ => None,
                 }
-            }).collect()
+            }).collect(),
+            NumericMode::None if self.is_numeric_only => input.chars().filter_map(|char| {
+                match char {
+                    '.' | ',' => Some('.'),
+                    char if char.is_ascii_digit() => Some(char),
+                    _ => None,
+                }
+            }).collect(),
+            NumericMode::None if !self.accepts_newlines() => {
+                input.chars().filter(|char| *char != '\n').collect()
+            }
+            NumericMode::None => input,
+        }
+    }
+
+    fn accepts_newlines(&self) -> bool {
+        self.is_multiline || (
+            self.numeric_mode == NumericMode::None
+                && !self.is_numeric_only
+                && !self.is_password
+                && self.input_mask.is_empty()
+        )
+    }
+
+    /// Applies the filters, the input mask and the length limit to text set from code.
+    fn sanitize(&mut self, text: &str) -> String {
+        let mut text = self.filter_input(text.to_string());
+        if !self.input_mask.is_empty() {
+            let slots = parse_input_mask(&self.input_mask);
+            text = apply_input_mask(&slots, &strip_input_mask(&slots, &text, 0), 0).0;
+        }
+        if self.max_length > 0 {
+            if let Some((index, _)) = text.char_indices().nth(self.max_length) {
+                text.truncate(index);
+            }
+        }
+        text
+    }
+
+    pub fn set_validator(&mut self, validator: impl Fn(&str) -> Result<(), String> + 'static) {
+        self.validator = Some(Box::new(validator));
+    }
+
+    /// The message of the last failed validation, if the text is currently invalid.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn set_password_revealed(&mut self, cx: &mut Cx, is_revealed: bool) {
+        self.is_revealed = is_revealed;
+        self.draw_bg.redraw(cx);
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.numeric_mode != NumericMode::None && !self.text.is_empty() {
+            let value = match self.numeric_mode {
+                NumericMode::Integer => self.text.parse::<i64>()
+                    .map(|value| value as f64)
+                    .map_err(|_| "Enter a whole number".to_string())?,
+                _ => self.text.parse::<f64>()
+                    .map_err(|_| "Enter a number".to_string())?,
+            };
+            if let Some(min) = self.min.filter(|min| value < *min) {
+                return Err(format!("Must be at least {}", self.format_number(min)));
+            }
+            if let Some(max) = self.max.filter(|max| value > *max) {
+                return Err(format!("Must be at most {}", self.format_number(max)));
+            }
+        }
+        if let Some(validator) = &self.validator {
+            validator(&self.text)?;
+        }
+        Ok(())
+    }
+
+    fn emit_change(&mut self, cx: &mut Cx, scope: &mut Scope) {
+        let uid = self.widget_uid();
+        cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+        self.error = self.validate().err();
+        if let Some(error) = &self.error {
+            cx.widget_action(uid, &scope.path, TextInputAction::Invalid(error.clone()));
+        }
+    }
+
+    fn format_number(&self, value: f64) -> String {
+        match self.numeric_mode {
+            NumericMode::Integer => format!("{}", value.round() as i64),
+            _ => {
+                // show as many decimals as the step has
+                let step = self.step.to_string();
+                let decimals = step.split_once('.').map_or(0, |(_, decimals)| decimals.len());
+                format!("{:.*}", decimals, value)
+            }
+        }
+    }
+
+    /// Adds `steps` times `step` to a numeric input, clamped to `min`/`max`.
+    fn step_number(&mut self, steps: f64) {
+        let step = match self.numeric_mode {
+            NumericMode::Integer => self.step.round().max(1.0),
+            _ => self.step,
+        };
+        let mut value = self.text.trim().parse::<f64>().unwrap_or(0.0) + steps * step;
+        if let Some(min) = self.min {
+            value = value.max(min);
+        }
+        if let Some(max) = self.max {
+            value = value.min(max);
+        }
+        let text = self.format_number(value);
+        self.history.create_or_extend_edit_group(EditKind::Other, self.cursor);
+        self.apply_edit(Edit {
+            start: 0,
+            end: self.text.len(),
+            replace_with: text,
+        });
+    }
+
+    fn is_masked(&self) -> bool {
+        self.is_password && !self.is_revealed
+    }
+
+    /// The text as drawn, with every grapheme replaced by a bullet while a
+    /// password is hidden.
+    fn display_text(&self) -> Cow<'_, str> {
+        if self.is_masked() {
+            Cow::Owned(self.text.graphemes(true).map(|_| PASSWORD_CHAR).collect())
         } else {
-            input
+            Cow::Borrowed(&self.text)
+        }
+    }
+
+    fn display_index(&self, index_affinity: IndexAffinity) -> IndexAffinity {
+        if !self.is_masked() {
+            return index_affinity;
+        }
+        let graphemes = self.text[..index_affinity.index].graphemes(true).count();
+        IndexAffinity {
+            index: graphemes * PASSWORD_CHAR.len_utf8(),
+            ..index_affinity
+        }
+    }
+
+    fn text_index(&self, index_affinity: IndexAffinity) -> IndexAffinity {
+        if !self.is_masked() {
+            return index_affinity;
+        }
+        let grapheme = index_affinity.index / PASSWORD_CHAR.len_utf8();
+        IndexAffinity {
+            index: self.text.grapheme_indices(true).nth(grapheme).map_or(self.text.len(), |(index, _)| index),
+            ..index_affinity
         }
     }
 
@@ -84,23 +291,29 @@ impl TextInput {
     }
 
     fn inner_walk(&self) -> Walk {
-        if self.walk.width.is_fit() {
+        let mut walk = if self.walk.width.is_fit() {
             Walk::fit()
         } else {
             Walk::fill_fit()
+        };
+        if self.is_password {
+            // leave room for the reveal toggle
+            walk.margin.right = self.reveal_width;
         }
+        walk
     }
 
     fn position_to_index_affinity(&self, cx: &mut Cx2d, width: f64, position: DVec2) -> IndexAffinity {
         let inner_walk = self.inner_walk();
-        self.draw_text.position_to_index_affinity(
+        let index_affinity = self.draw_text.position_to_index_affinity(
             cx,
             inner_walk,
             self.label_align,
             width,
-            &self.text,
+            &self.display_text(),
             position,
-        )
+        );
+        self.text_index(index_affinity)
     }
 
     fn cursor_position(&self, cx: &mut Cx2d, width: f64) -> DVec2 {
@@ -110,8 +323,8 @@ impl TextInput {
             inner_walk,
             self.label_align,
             width,
-            &self.text,
-            self.cursor.head,
+            &self.display_text(),
+            self.display_index(self.cursor.head),
         )
     }
 
@@ -166,6 +379,7 @@ impl TextInput {
         if !is_select {
             self.cursor.tail = self.cursor.head;
         }
+        self.scroll_to_cursor = true;
         self.history.force_new_edit_group();
     }
 
@@ -215,20 +429,60 @@ impl TextInput {
     }
 
     fn apply_edit(&mut self, edit: Edit) {
-        self.cursor.head.index = edit.start + edit.replace_with.len();
+        let (edit, cursor_index) = self.constrain_edit(edit);
+        self.cursor.head.index = cursor_index;
         self.cursor.tail = self.cursor.head;
+        self.scroll_to_cursor = true;
         self.history.apply_edit(edit, &mut self.text);
     }
 
+    /// Cuts an edit down to `max_length` and, with an input mask, turns it into
+    /// a replacement of the whole text by its masked form. Returns the edit
+    /// and the cursor index after it.
+    fn constrain_edit(&self, mut edit: Edit) -> (Edit, usize) {
+        if self.max_length > 0 {
+            let kept = self.text[..edit.start].chars().count() + self.text[edit.end..].chars().count();
+            let room = self.max_length.saturating_sub(kept);
+            if let Some((index, _)) = edit.replace_with.char_indices().nth(room) {
+                edit.replace_with.truncate(index);
+            }
+        }
+        if self.input_mask.is_empty() {
+            let cursor_index = edit.start + edit.replace_with.len();
+            return (edit, cursor_index);
+        }
+        // The text around the edit is masked already, so its literals are where the mask puts
+        // them. Telling them apart by position keeps a literal such as the `1` in `+1 ###` from
+        // being taken for input.
+        let slots = parse_input_mask(&self.input_mask);
+        let mut input = strip_input_mask(&slots, &self.text[..edit.start], 0);
+        input.extend(edit.replace_with.chars().filter(|char| char.is_alphanumeric()));
+        let cursor = input.len();
+        input.extend(strip_input_mask(
+            &slots,
+            &self.text[edit.end..],
+            self.text[..edit.end].chars().count(),
+        ));
+        let (text, cursor_index) = apply_input_mask(&slots, &input, cursor);
+        let edit = Edit {
+            start: 0,
+            end: self.text.len(),
+            replace_with: text,
+        };
+        (edit, cursor_index)
+    }
+
     fn undo(&mut self) {
         if let Some(cursor) = self.history.undo(self.cursor, &mut self.text) {
             self.cursor = cursor;
+            self.scroll_to_cursor = true;
         }
     }
 
     fn redo(&mut self) {
         if let Some(cursor) = self.history.redo(self.cursor, &mut self.text) {
             self.cursor = cursor;
+            self.scroll_to_cursor = true;
         }
     }
 }
@@ -281,6 +535,19 @@ impl Widget for TextInput {
                 self.move_cursor_right( is_select);
                 self.draw_bg.redraw(cx);
             },
+            Hit::KeyDown(KeyEvent {
+                key_code: key_code @ (KeyCode::ArrowUp | KeyCode::ArrowDown),
+                modifiers: KeyModifiers {
+                    shift,
+                    ..
+                },
+                ..
+            }) if self.numeric_mode != NumericMode::None && !self.is_read_only => {
+                let steps = if key_code == KeyCode::ArrowUp {1.0} else {-1.0};
+                self.step_number(if shift {10.0 * steps} else {steps});
+                self.draw_bg.redraw(cx);
+                self.emit_change(cx, scope);
+            }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::ArrowUp,
                 modifiers: KeyModifiers {
@@ -339,21 +606,19 @@ impl Widget for TextInput {
                 key_code: KeyCode::ReturnKey,
                 modifiers: KeyModifiers {
                     shift: false,
+                    control,
+                    logo,
                     ..
                 },
                 ..
-            }) => {
+            }) if !self.is_multiline || control || logo => {
                 cx.hide_text_ime();
                 cx.widget_action(uid, &scope.path, TextInputAction::Return(self.text.clone()));
             },
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::ReturnKey,
-                modifiers: KeyModifiers {
-                    shift: true,
-                    ..
-                },
                 ..
-            }) if !self.is_read_only => {
+            }) if !self.is_read_only && self.accepts_newlines() => {
                 self.history.create_or_extend_edit_group(
                     EditKind::Other,
                     self.cursor,
@@ -364,7 +629,7 @@ impl Widget for TextInput {
                     replace_with: "\n".to_string(),
                 });
                 self.draw_bg.redraw(cx);
-                cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                self.emit_change(cx, scope);
             }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::Escape,
@@ -380,6 +645,10 @@ impl Widget for TextInput {
                 let end = self.cursor.end().index;
                 if start == end {
                     start = prev_grapheme_boundary(&self.text, start).unwrap_or(0);
+                    // the mask would put deleted separators straight back
+                    while !self.input_mask.is_empty() && start > 0 && !self.text[start..end].contains(char::is_alphanumeric) {
+                        start = prev_grapheme_boundary(&self.text, start).unwrap_or(0);
+                    }
                 }
                 self.history.create_or_extend_edit_group(EditKind::Backspace, self.cursor);
                 self.apply_edit(Edit {
@@ -388,7 +657,7 @@ impl Widget for TextInput {
                     replace_with: String::new(),
                 });
                 self.draw_bg.redraw(cx);
-                cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                self.emit_change(cx, scope);
             }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::Delete,
@@ -398,6 +667,9 @@ impl Widget for TextInput {
                 let mut end = self.cursor.end().index;
                 if start == end {
                     end = next_grapheme_boundary(&self.text, end).unwrap_or(self.text.len());
+                    while !self.input_mask.is_empty() && end < self.text.len() && !self.text[start..end].contains(char::is_alphanumeric) {
+                        end = next_grapheme_boundary(&self.text, end).unwrap_or(self.text.len());
+                    }
                 }
                 self.history.create_or_extend_edit_group(EditKind::Delete, self.cursor);
                 self.apply_edit(Edit {
@@ -406,7 +678,7 @@ impl Widget for TextInput {
                     replace_with: String::new(),
                 });
                 self.draw_bg.redraw(cx);
-                cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                self.emit_change(cx, scope);
             }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::KeyA,
@@ -437,7 +709,7 @@ impl Widget for TextInput {
             }) if !self.is_read_only => {
                 self.undo();
                 self.draw_bg.redraw(cx);
-                cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                self.emit_change(cx, scope);
             }
             Hit::KeyDown(KeyEvent {
                 key_code: KeyCode::KeyZ,
@@ -450,7 +722,7 @@ impl Widget for TextInput {
             }) if !self.is_read_only => {
                 self.redo();
                 self.draw_bg.redraw(cx);
-                cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                self.emit_change(cx, scope);
             }
             Hit::TextInput(TextInputEvent {
                 input,
@@ -479,14 +751,14 @@ impl Widget for TextInput {
                         replace_with: input,
                     });
                     self.draw_bg.redraw(cx);
-                    cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                    self.emit_change(cx, scope);
                 }
             }
-            Hit::TextCopy(event) => {
+            Hit::TextCopy(event) if !self.is_masked() => {
                 let selection = &self.text[self.cursor.start().index..self.cursor.end().index];
                 *event.response.borrow_mut() = Some(selection.to_string());
             }
-            Hit::TextCut(event) => {
+            Hit::TextCut(event) if !self.is_masked() => {
                 let selection = &self.text[self.cursor.start().index..self.cursor.end().index];
                 *event.response.borrow_mut() = Some(selection.to_string());
                 if !selection.is_empty() {
@@ -497,16 +769,40 @@ impl Widget for TextInput {
                         replace_with: String::new(),
                     });
                     self.draw_bg.redraw(cx);
-                    cx.widget_action(uid, &scope.path, TextInputAction::Change(self.text.clone()));
+                    self.emit_change(cx, scope);
                 }
             }
             Hit::FingerHoverIn(_) => {
                 cx.set_cursor(MouseCursor::Text);
                 self.animator_play(cx, id!(hover.on));
             }
+            Hit::FingerHoverOver(FingerHoverEvent {
+                abs,
+                ..
+            }) if self.is_password => {
+                if self.reveal_rect.contains(abs) {
+                    cx.set_cursor(MouseCursor::Hand);
+                } else {
+                    cx.set_cursor(MouseCursor::Text);
+                }
+            }
             Hit::FingerHoverOut(_) => {
                 self.animator_play(cx, id!(hover.off));
             },
+            Hit::FingerScroll(FingerScrollEvent {
+                scroll,
+                ..
+            }) if self.is_multiline => {
+                self.viewport_scroll.y = (self.viewport_scroll.y + scroll.y).clamp(0.0, self.max_scroll);
+                self.draw_bg.redraw(cx);
+            }
+            Hit::FingerDown(FingerDownEvent {
+                abs,
+                ..
+            }) if self.is_password && self.reveal_rect.contains(abs) => {
+                self.is_revealed = !self.is_revealed;
+                self.draw_bg.redraw(cx);
+            }
             Hit::FingerDown(FingerDownEvent {
                 abs,
                 tap_count,
@@ -517,7 +813,7 @@ impl Widget for TextInput {
                 let index_affinity = self.position_to_index_affinity(
                     &mut cx,
                     padded_rect.size.x,
-                    abs - padded_rect.pos
+                    abs - padded_rect.pos + self.viewport_scroll
                 );
                 self.move_cursor_to(index_affinity, false);
                 if tap_count == 2 {
@@ -538,7 +834,7 @@ impl Widget for TextInput {
                 let index_affinity = self.position_to_index_affinity(
                     &mut cx,
                     padded_rect.size.x,
-                    abs - padded_rect.pos
+                    abs - padded_rect.pos + self.viewport_scroll
                 );
                 self.move_cursor_to(index_affinity, true);
                 if tap_count == 2 {
@@ -553,14 +849,15 @@ impl Widget for TextInput {
     }
     
     fn draw_walk(&mut self, cx: &mut Cx2d, _scope: &mut Scope, walk: Walk) -> DrawStep {
-        self.draw_bg.begin(cx, walk, self.layout);
+        self.draw_bg.begin(cx, walk, self.layout.with_scroll(self.viewport_scroll));
 
         self.draw_selection.append_to_draw_call(cx);
 
         let inner_walk = self.inner_walk();
+        let text = self.display_text().into_owned();
 
         // Draw text
-        if self.text.is_empty() {
+        if text.is_empty() {
             self.draw_text.is_empty = 1.0;
             self.draw_text.draw_walk(
                 cx,
@@ -574,7 +871,7 @@ impl Widget for TextInput {
                 cx,
                 inner_walk,
                 self.label_align,
-                &self.text,
+                &text,
             );
         }
 
@@ -586,9 +883,9 @@ impl Widget for TextInput {
             inner_walk,
             self.label_align,
             padded_rect.size.x,
-            &self.text,
-            self.cursor.head.min(self.cursor.tail),
-            self.cursor.head.max(self.cursor.tail)
+            &text,
+            self.display_index(self.cursor.head.min(self.cursor.tail)),
+            self.display_index(self.cursor.head.max(self.cursor.tail))
         );
         for rect in rects {
             self.draw_selection.draw_abs(cx, Rect {
@@ -605,13 +902,53 @@ impl Widget for TextInput {
             size: dvec2(self.cursor_width, cursor_height)
         });
 
+        // Draw reveal toggle
+        if self.is_password {
+            self.reveal_rect = Rect {
+                pos: dvec2(padded_rect.pos.x + padded_rect.size.x - self.reveal_width, padded_rect.pos.y),
+                size: dvec2(self.reveal_width, cursor_height),
+            };
+            self.draw_reveal.revealed = if self.is_revealed {1.0} else {0.0};
+            self.draw_reveal.draw_abs(cx, self.reveal_rect);
+        }
+
+        // Draw validation error
+        if let Some(error) = &self.error {
+            cx.turtle_new_line();
+            self.draw_error.draw_walk(cx, Walk::fit(), Align::default(), error);
+        }
+
+        // Keep the cursor inside the viewport of a multi-line input
+        if self.is_multiline && !padded_rect.size.y.is_nan() {
+            let text_end = self.draw_text.index_affinity_to_position(
+                cx,
+                inner_walk,
+                self.label_align,
+                padded_rect.size.x,
+                &text,
+                IndexAffinity::new(text.len(), Affinity::After),
+            );
+            self.max_scroll = (text_end.y + cursor_height - padded_rect.size.y).max(0.0);
+            let mut scroll_y = self.viewport_scroll.y.min(self.max_scroll);
+            if self.scroll_to_cursor {
+                scroll_y = scroll_y
+                    .max(cursor_position.y + cursor_height - padded_rect.size.y)
+                    .min(cursor_position.y);
+            }
+            if scroll_y != self.viewport_scroll.y {
+                self.viewport_scroll.y = scroll_y;
+                self.draw_bg.redraw(cx);
+            }
+        }
+        self.scroll_to_cursor = false;
+
         self.draw_bg.end(cx);
 
         if cx.has_key_focus(self.draw_bg.area()) {
             let padding = dvec2(self.layout.padding.left, self.layout.padding.top);
             cx.show_text_ime(
                 self.draw_bg.area(), 
-                padding + cursor_position - self.cursor_width * 0.5
+                padding + cursor_position - self.viewport_scroll - self.cursor_width * 0.5
             );
         }
 
@@ -628,9 +965,10 @@ impl Widget for TextInput {
         if self.text == text {
             return;
         }
-        self.text = self.filter_input(text.to_string());
-        self.cursor.head.index = self.cursor.head.index.min(text.len());
-        self.cursor.tail.index = self.cursor.tail.index.min(text.len());
+        self.text = self.sanitize(text);
+        self.cursor.head.index = self.cursor.head.index.min(self.text.len());
+        self.cursor.tail.index = self.cursor.tail.index.min(self.text.len());
+        self.error = None;
         self.history.clear();
     }
 }
@@ -639,6 +977,8 @@ impl Widget for TextInput {
 pub enum TextInputAction {
     Change(String),
     Return(String),
+    /// The text failed validation, with the message shown under it.
+    Invalid(String),
     Escape,
     KeyFocus,
     KeyFocusLost,
@@ -659,6 +999,32 @@ impl TextInputRef {
         }
         None
     }
+
+    pub fn invalid(&self, actions: &Actions) -> Option<String> {
+        if let TextInputAction::Invalid(error) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some(error);
+        }
+        None
+    }
+
+    pub fn error(&self) -> Option<String> {
+        if let Some(inner) = self.borrow() {
+            return inner.error().map(String::from);
+        }
+        None
+    }
+
+    pub fn set_validator(&self, validator: impl Fn(&str) -> Result<(), String> + 'static) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_validator(validator);
+        }
+    }
+
+    pub fn set_password_revealed(&self, cx: &mut Cx, is_revealed: bool) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_password_revealed(cx, is_revealed);
+        }
+    }
     
     pub fn set_cursor(&self, head: usize, tail: usize) {
         if let Some(mut inner) = self.borrow_mut() {
@@ -895,6 +1261,87 @@ impl Edit {
     }
 }
 
+#[derive(Clone, Copy)]
+enum MaskSlot {
+    Digit,
+    Letter,
+    AlphaNumeric,
+    Literal(char),
+}
+
+impl MaskSlot {
+    fn accepts(self, char: char) -> bool {
+        match self {
+            Self::Digit => char.is_ascii_digit(),
+            Self::Letter => char.is_alphabetic(),
+            Self::AlphaNumeric => char.is_alphanumeric(),
+            Self::Literal(_) => false,
+        }
+    }
+}
+
+fn parse_input_mask(mask: &str) -> Vec<MaskSlot> {
+    let mut slots = Vec::new();
+    let mut chars = mask.chars();
+    while let Some(char) = chars.next() {
+        slots.push(match char {
+            '#' => MaskSlot::Digit,
+            'A' => MaskSlot::Letter,
+            '*' => MaskSlot::AlphaNumeric,
+            '\\' => MaskSlot::Literal(chars.next().unwrap_or('\\')),
+            char => MaskSlot::Literal(char),
+        });
+    }
+    slots
+}
+
+/// Returns the letters and digits of `text`, leaving out the literals of
+/// `slots`. The first char of `text` is at slot `offset`.
+fn strip_input_mask(slots: &[MaskSlot], text: &str, offset: usize) -> Vec<char> {
+    text.chars()
+        .enumerate()
+        .filter(|&(index, char)| {
+            !matches!(slots.get(offset + index), Some(&MaskSlot::Literal(literal)) if literal == char)
+                && char.is_alphanumeric()
+        })
+        .map(|(_, char)| char)
+        .collect()
+}
+
+/// Lays `input` out over `slots`, dropping the chars that don't fit. Returns
+/// the masked text and the index just after the first `cursor` chars of
+/// `input`.
+fn apply_input_mask(slots: &[MaskSlot], input: &[char], cursor: usize) -> (String, usize) {
+    let mut next = 0;
+    let mut output = String::new();
+    let mut output_len = 0;
+    let mut output_cursor = 0;
+    for &slot in slots {
+        if next >= input.len() {
+            break;
+        }
+        if let MaskSlot::Literal(char) = slot {
+            output.push(char);
+            continue;
+        }
+        while next < input.len() && !slot.accepts(input[next]) {
+            next += 1;
+        }
+        let Some(&char) = input.get(next) else {
+            break;
+        };
+        output.push(char);
+        next += 1;
+        output_len = output.len();
+        if next <= cursor {
+            output_cursor = output_len;
+        }
+    }
+    // no trailing separators without anything after them
+    output.truncate(output_len);
+    (output, output_cursor)
+}
+
 fn next_grapheme_boundary(string: &str, index: usize) -> Option<usize> {
     let mut cursor = GraphemeCursor::new(index, string.len(), true);
     cursor.next_boundary(string, 0).unwrap()
@@ -905,6 +1352,44 @@ fn prev_grapheme_boundary(string: &str, index: usize) -> Option<usize> {
     cursor.prev_boundary(string, 0).unwrap()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(mask: &str, text: &str) -> String {
+        let slots = parse_input_mask(mask);
+        apply_input_mask(&slots, &strip_input_mask(&slots, text, 0), 0).0
+    }
+
+    #[test]
+    fn input_mask_lays_out_input() {
+        assert_eq!(mask("(###) ###-####", "5551234567"), "(555) 123-4567");
+        assert_eq!(mask("(###) ###-####", "555"), "(555");
+        assert_eq!(mask("AA-##", "ab12"), "ab-12");
+        assert_eq!(mask("AA-##", "1a2b3"), "ab-3");
+    }
+
+    #[test]
+    fn input_mask_does_not_take_literals_for_input() {
+        assert_eq!(mask("+1 ###", "+1 555"), "+1 555");
+        assert_eq!(mask("+1 ###", "555"), "+1 555");
+        // Masking masked text again leaves it alone.
+        assert_eq!(mask("+1 ###", &mask("+1 ###", "+1 555")), "+1 555");
+    }
+
+    #[test]
+    fn input_mask_keeps_the_input_around_an_edit() {
+        let slots = parse_input_mask("+1 ###");
+        // Typing a `2` after the `5` of `+1 55`.
+        let mut input = strip_input_mask(&slots, "+1 5", 0);
+        input.push('2');
+        let cursor = input.len();
+        input.extend(strip_input_mask(&slots, "5", 4));
+        assert_eq!(input, ['5', '2', '5']);
+        assert_eq!(apply_input_mask(&slots, &input, cursor), ("+1 525".to_string(), 5));
+    }
+}
+
 /*
 use {
     crate::{
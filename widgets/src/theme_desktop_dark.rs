@@ -2612,6 +2612,13 @@ live_design! {
             }
         }
 
+        draw_match: {
+            text_style: <THEME_FONT_BOLD> {
+                font_size: (THEME_FONT_SIZE_P),
+            }
+            color: (THEME_COLOR_TEXT_HOVER)
+        }
+
         draw_bg: {
             instance selected: 0.0
             instance hover: 0.0
@@ -2877,6 +2884,350 @@ live_design! {
         }
     }
 
+    ComboBox = <ComboBoxBase> {
+        width: 200, height: Fit,
+
+        text_input: <TextInput> {
+            width: Fill,
+            empty_message: "Search...",
+            draw_text: { wrap: Line }
+        }
+
+        popup_menu: <PopupMenu> { width: 200. }
+
+        max_items: 50,
+        query_debounce: 0.15,
+        allow_free_text: false,
+    }
+
+        Calendar = <CalendarBase> {
+        width: Fit, height: Fit,
+        padding: <THEME_MSPACE_2> {}
+        cell_size: 28.0,
+        first_day_of_week: Locale,
+
+        draw_bg: {
+            fn pixel(self) -> vec4 {
+                return vec4(0.0, 0.0, 0.0, 0.0)
+            }
+        }
+
+        draw_header: {
+            text_style: <THEME_FONT_BOLD> {
+                font_size: (THEME_FONT_SIZE_P)
+            }
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+
+        draw_weekday: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_P * 0.85)
+            }
+            color: (THEME_COLOR_TEXT_META)
+        }
+
+        draw_day: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_P)
+            }
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+
+        draw_cell: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(1., 1., self.rect_size.x - 2., self.rect_size.y - 2., THEME_CORNER_RADIUS)
+                let bg = mix(
+                    mix(
+                        mix(THEME_COLOR_U_HIDDEN, THEME_COLOR_CTRL_HOVER, self.hover),
+                        THEME_COLOR_BG_HIGHLIGHT_INLINE,
+                        self.in_range
+                    ),
+                    THEME_COLOR_MAKEPAD,
+                    self.selected
+                );
+                sdf.fill_keep(mix(bg, THEME_COLOR_D_HIDDEN, self.outside * 0.5 + self.disabled * 0.5))
+                sdf.stroke(
+                    mix(
+                        mix(THEME_COLOR_U_HIDDEN, THEME_COLOR_TEXT_META, self.today),
+                        THEME_COLOR_TEXT_FOCUSED,
+                        self.focus
+                    ),
+                    1.0
+                )
+                return sdf.result
+            }
+        }
+    }
+
+    DatePicker = <DatePickerBase> {
+        width: Fit, height: Fit,
+        padding: <THEME_MSPACE_2> { left: (THEME_SPACE_2), right: 22.5 }
+        empty_message: "Pick a date",
+
+        draw_text: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_P)
+            }
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+
+        draw_bg: {
+            instance hover: 0.0
+            instance focus: 0.0
+            instance pressed: 0.0
+            instance open: 0.0
+
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(1., 1., self.rect_size.x - 2.0, self.rect_size.y - 2.0, THEME_CORNER_RADIUS)
+                sdf.fill_keep(mix(
+                    mix(THEME_COLOR_U_HIDDEN, THEME_COLOR_CTRL_HOVER, self.hover),
+                    THEME_COLOR_CTRL_HOVER,
+                    max(self.focus, self.open)
+                ))
+                sdf.stroke(mix(THEME_COLOR_BEVEL_SHADOW, THEME_COLOR_BEVEL_LIGHT, self.focus), THEME_BEVELING)
+
+                // a little triangle in the corner like the DropDown
+                let c = vec2(self.rect_size.x - 10.0, self.rect_size.y * 0.5)
+                let sz = 3.;
+                sdf.move_to(c.x - sz, c.y - sz + 1.);
+                sdf.line_to(c.x + sz, c.y - sz + 1.);
+                sdf.line_to(c.x, c.y + sz * 0.25 + 1.);
+                sdf.close_path();
+                sdf.fill(mix(THEME_COLOR_TEXT_DEFAULT, THEME_COLOR_TEXT_HOVER, self.hover));
+                return sdf.result
+            }
+        }
+
+        popup_layout: { padding: <THEME_MSPACE_1> {} }
+        draw_popup: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size)
+                sdf.box(1., 1., self.rect_size.x - 2., self.rect_size.y - 2., 2.0)
+                sdf.fill_keep(THEME_COLOR_FLOATING_BG)
+                sdf.stroke(THEME_COLOR_BEVEL_LIGHT, THEME_BEVELING)
+                return sdf.result;
+            }
+        }
+
+        calendar: <Calendar> {}
+
+        animator: {
+            hover = {
+                default: off,
+                off = {
+                    from: {all: Forward {duration: 0.1}}
+                    apply: { draw_bg: {pressed: 0.0, hover: 0.0} }
+                }
+                on = {
+                    from: {all: Forward {duration: 0.1}}
+                    apply: { draw_bg: {pressed: 0.0, hover: 1.0} }
+                }
+                pressed = {
+                    from: {all: Forward {duration: 0.2}}
+                    apply: { draw_bg: {pressed: 1.0, hover: 1.0} }
+                }
+            }
+            focus = {
+                default: off
+                off = {
+                    from: {all: Forward {duration: 0.2}}
+                    apply: { draw_bg: {focus: 0.0} }
+                }
+                on = {
+                    from: {all: Snap}
+                    apply: { draw_bg: {focus: 1.0} }
+                }
+            }
+        }
+    }
+
+    TimePicker = <TimePickerBase> {
+        width: Fit, height: Fit,
+        padding: <THEME_MSPACE_1> { left: (THEME_SPACE_2), right: (THEME_SPACE_1) }
+        align: { y: 0.5 }
+        time: "12:00",
+        minute_step: 1,
+        segment_width: 22.0,
+        stepper_width: 12.0,
+
+        draw_text: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_P)
+            }
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+
+        draw_bg: {
+            instance hover: 0.0
+            instance focus: 0.0
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(1., 1., self.rect_size.x - 2.0, self.rect_size.y - 2.0, THEME_CORNER_RADIUS)
+                sdf.fill_keep(mix(THEME_COLOR_INSET_DEFAULT, THEME_COLOR_CTRL_ACTIVE, self.focus))
+                sdf.stroke(mix(THEME_COLOR_BEVEL_SHADOW, THEME_COLOR_BEVEL_LIGHT, self.hover), THEME_BEVELING)
+                return sdf.result
+            }
+        }
+
+        draw_segment: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0., 0., self.rect_size.x, self.rect_size.y, 2.0)
+                sdf.fill(mix(THEME_COLOR_U_HIDDEN, THEME_COLOR_BG_HIGHLIGHT_INLINE, self.focus))
+                return sdf.result
+            }
+        }
+
+        draw_stepper: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                let c = self.rect_size * 0.5;
+                let sz = 3.;
+                sdf.move_to(c.x - sz, c.y - 2.);
+                sdf.line_to(c.x + sz, c.y - 2.);
+                sdf.line_to(c.x, c.y - 2. - sz);
+                sdf.close_path();
+                sdf.move_to(c.x - sz, c.y + 2.);
+                sdf.line_to(c.x + sz, c.y + 2.);
+                sdf.line_to(c.x, c.y + 2. + sz);
+                sdf.close_path();
+                sdf.fill(THEME_COLOR_TEXT_DEFAULT);
+                return sdf.result
+            }
+        }
+
+        animator: {
+            hover = {
+                default: off
+                off = {
+                    from: {all: Forward {duration: 0.1}}
+                    apply: { draw_bg: {hover: 0.0} }
+                }
+                on = {
+                    from: {all: Snap}
+                    apply: { draw_bg: {hover: 1.0} }
+                }
+            }
+            focus = {
+                default: off
+                off = {
+                    from: {all: Forward {duration: 0.2}}
+                    apply: { draw_bg: {focus: 0.0} }
+                }
+                on = {
+                    from: {all: Snap}
+                    apply: { draw_bg: {focus: 1.0} }
+                }
+            }
+        }
+    }
+
+    Chart = <ChartBase> {
+        width: Fill, height: 200.0,
+        padding: <THEME_MSPACE_2> {}
+        palette: [#4FC3F7, #FFB74D, #81C784, #E57373, #BA68C8, #FFF176],
+        line_width: 1.5,
+        point_size: 5.0,
+        bar_width: 0.8,
+        y_axis_width: 48.0,
+        tooltip_layout: { padding: <THEME_MSPACE_1> {} }
+
+        draw_bg: {
+            fn pixel(self) -> vec4 {
+                return THEME_COLOR_BG_CONTAINER
+            }
+        }
+        draw_grid: { color: (THEME_COLOR_DIVIDER) }
+        draw_axis: { color: (THEME_COLOR_TEXT_META) }
+        draw_swatch: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0., 0., self.rect_size.x, self.rect_size.y, 2.0)
+                sdf.fill(self.color)
+                return sdf.result
+            }
+        }
+        draw_label: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_BASE)
+            }
+            color: (THEME_COLOR_TEXT_META)
+        }
+        draw_legend: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_BASE)
+            }
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+
+        draw_line: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.move_to(self.p0.x, self.p0.y);
+                sdf.line_to(self.p1.x, self.p1.y);
+                sdf.stroke(self.color, self.line_width);
+                return sdf.result
+            }
+        }
+        draw_area: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.move_to(self.p0.x, self.p0.y);
+                sdf.line_to(self.p1.x, self.p1.y);
+                sdf.line_to(self.p1.x, self.baseline);
+                sdf.line_to(self.p0.x, self.baseline);
+                sdf.close_path();
+                sdf.fill(vec4(self.color.xyz, self.color.w * 0.35));
+                return sdf.result
+            }
+        }
+        draw_bar: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0., 0., self.rect_size.x, self.rect_size.y, 1.0)
+                sdf.fill(self.color)
+                return sdf.result
+            }
+        }
+        draw_point: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                let c = self.rect_size * 0.5;
+                sdf.circle(c.x, c.y, c.x - 0.5)
+                sdf.fill(self.color)
+                return sdf.result
+            }
+        }
+        draw_marker: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                let c = self.rect_size * 0.5;
+                sdf.circle(c.x, c.y, c.x - 1.5)
+                sdf.fill_keep(THEME_COLOR_BG_CONTAINER)
+                sdf.stroke(self.color, 1.5)
+                return sdf.result
+            }
+        }
+
+        draw_tooltip_bg: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                sdf.box(0., 0., self.rect_size.x, self.rect_size.y, THEME_CORNER_RADIUS)
+                sdf.fill_keep(THEME_COLOR_BG_APP)
+                sdf.stroke(THEME_COLOR_DIVIDER, 1.0)
+                return sdf.result
+            }
+        }
+        draw_tooltip: {
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_BASE)
+            }
+            color: (THEME_COLOR_TEXT_DEFAULT)
+        }
+    }
+
     FileTreeNode = <FileTreeNodeBase> {
         align: { y: 0.5 }
         padding: { left: (THEME_SPACE_1) },
@@ -3502,6 +3853,8 @@ live_design! {
 
         is_read_only: false,
         is_numeric_only: false,
+        is_password: false,
+        is_multiline: false,
         empty_message: "0",
 
         animator: {
@@ -3657,6 +4010,31 @@ live_design! {
                 return sdf.result
             }
         }
+
+        draw_error: {
+            color: (THEME_COLOR_ERROR),
+            text_style: <THEME_FONT_REGULAR> {
+                font_size: (THEME_FONT_SIZE_BASE)
+            }
+        }
+
+        draw_reveal: {
+            fn pixel(self) -> vec4 {
+                let sdf = Sdf2d::viewport(self.pos * self.rect_size);
+                let c = self.rect_size * 0.5;
+                let color = mix(THEME_COLOR_TEXT_PLACEHOLDER, THEME_COLOR_TEXT_DEFAULT, self.revealed);
+                sdf.circle(c.x, c.y, c.x * 0.6);
+                sdf.stroke(color, 1.0);
+                sdf.circle(c.x, c.y, c.x * 0.25);
+                sdf.fill(color);
+                if self.revealed < 0.5 {
+                    sdf.move_to(c.x * 0.2, c.y * 1.8);
+                    sdf.line_to(c.x * 1.8, c.y * 0.2);
+                    sdf.stroke(color, 1.0);
+                }
+                return sdf.result
+            }
+        }
     }
 
     Slider = <SliderBase> {
@@ -0,0 +1,333 @@
+use std::sync::Arc;
+use crate::{
+    makepad_derive_widget::*,
+    makepad_draw::*,
+    widget::*,
+    date_time::Time,
+};
+
+live_design!{
+    DrawTimeSegment = {{DrawTimeSegment}} {}
+    TimePickerBase = {{TimePicker}} {}
+}
+
+#[derive(Live, LiveHook, LiveRegister)]
+#[repr(C)]
+pub struct DrawTimeSegment {
+    #[deref] draw_super: DrawQuad,
+    #[live] focus: f32,
+}
+
+#[derive(Clone, Debug, DefaultNone)]
+pub enum TimePickerAction {
+    Change(Time),
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Segment {
+    Hour,
+    Minute,
+    Second,
+}
+
+/// A spinner for a time of day. Click a segment or move between them with
+/// left/right, then step it with up/down, the scroll wheel or the stepper arrows,
+/// or type the digits. Minutes step by `minute_step` and the value stays
+/// within `min_time`/`max_time` (`HH:MM[:SS]`).
+#[derive(Live, Widget)]
+pub struct TimePicker {
+    #[animator] animator: Animator,
+
+    #[redraw] #[live] draw_bg: DrawQuad,
+    #[live] draw_text: DrawText,
+    #[live] draw_segment: DrawTimeSegment,
+    #[live] draw_stepper: DrawQuad,
+
+    #[walk] walk: Walk,
+    #[layout] layout: Layout,
+
+    #[live] bind: String,
+    #[live] time: String,
+    #[live] min_time: String,
+    #[live] max_time: String,
+    #[live] show_seconds: bool,
+    #[live(1usize)] minute_step: usize,
+    #[live(24.0)] segment_width: f64,
+    #[live(12.0)] stepper_width: f64,
+
+    #[rust] value: Time,
+    #[rust] min: Option<Time>,
+    #[rust] max: Option<Time>,
+    #[rust] segment: usize,
+    #[rust] typed: String,
+    #[rust] segment_rects: Vec<Rect>,
+    #[rust] stepper_rect: Rect,
+}
+
+impl LiveHook for TimePicker {
+    fn after_apply(&mut self, cx: &mut Cx, apply: &mut Apply, _index: usize, _nodes: &[LiveNode]) {
+        self.min = Time::parse(&self.min_time);
+        self.max = Time::parse(&self.max_time);
+        if apply.from.is_from_doc() {
+            if let Some(time) = Time::parse(&self.time) {
+                self.value = time;
+            }
+        }
+        self.value = self.value.clamp_to(self.min, self.max);
+        self.draw_bg.redraw(cx);
+    }
+}
+
+impl TimePicker {
+
+    fn segments(&self) -> &'static [Segment] {
+        if self.show_seconds {
+            &[Segment::Hour, Segment::Minute, Segment::Second]
+        }
+        else {
+            &[Segment::Hour, Segment::Minute]
+        }
+    }
+
+    fn focused_segment(&self) -> Segment {
+        let segments = self.segments();
+        segments[self.segment.min(segments.len() - 1)]
+    }
+
+    pub fn value(&self) -> Time {
+        self.value
+    }
+
+    pub fn set_value(&mut self, cx: &mut Cx, time: Time) {
+        self.value = time.clamp_to(self.min, self.max);
+        self.draw_bg.redraw(cx);
+    }
+
+    fn change(&mut self, cx: &mut Cx, scope: &mut Scope, time: Time) {
+        let time = time.clamp_to(self.min, self.max);
+        if time != self.value {
+            self.value = time;
+            cx.widget_action(self.widget_uid(), &scope.path, TimePickerAction::Change(time));
+        }
+        self.draw_bg.redraw(cx);
+    }
+
+    fn step(&mut self, cx: &mut Cx, scope: &mut Scope, steps: i64) {
+        self.typed.clear();
+        let seconds = match self.focused_segment() {
+            Segment::Hour => 3600,
+            Segment::Minute => 60 * self.minute_step.max(1) as i64,
+            Segment::Second => 1,
+        };
+        let time = self.value.add_seconds(steps * seconds);
+        self.change(cx, scope, time);
+    }
+
+    /// Collects up to two typed digits into the focused segment, moving on
+    /// to the next segment once it is full.
+    fn type_digit(&mut self, cx: &mut Cx, scope: &mut Scope, digit: u32) {
+        self.typed.push(char::from_digit(digit, 10).unwrap());
+        let typed: u32 = self.typed.parse().unwrap_or(0);
+        let mut time = self.value;
+        match self.focused_segment() {
+            Segment::Hour => time.hour = typed.min(23),
+            Segment::Minute => time.minute = typed.min(59),
+            Segment::Second => time.second = typed.min(59),
+        }
+        self.change(cx, scope, time);
+        if self.typed.len() >= 2 {
+            self.typed.clear();
+            self.segment = (self.segment + 1).min(self.segments().len() - 1);
+        }
+    }
+
+    fn focus_segment(&mut self, cx: &mut Cx, segment: usize) {
+        self.typed.clear();
+        self.segment = segment.min(self.segments().len() - 1);
+        self.draw_bg.redraw(cx);
+    }
+
+    fn draw_text_centered(cx: &mut Cx2d, draw_text: &mut DrawText, rect: Rect, text: &str) {
+        cx.begin_turtle(Walk::abs_rect(rect), Layout::default().with_align_x(0.5).with_align_y(0.5));
+        draw_text.draw_walk(cx, Walk::fit(), Align::default(), text);
+        cx.end_turtle();
+    }
+}
+
+impl Widget for TimePicker {
+
+    fn widget_to_data(&self, _cx: &mut Cx, actions: &Actions, nodes: &mut LiveNodeVec, path: &[LiveId]) -> bool {
+        match actions.find_widget_action_cast(self.widget_uid()) {
+            TimePickerAction::Change(_) => {
+                nodes.write_field_value(path, LiveValue::String(Arc::new(self.text())));
+                true
+            }
+            _ => false
+        }
+    }
+
+    fn data_to_widget(&mut self, cx: &mut Cx, nodes: &[LiveNode], path: &[LiveId]) {
+        if let Some(value) = nodes.read_field_value(path) {
+            let text = match value {
+                LiveValue::String(s) => s.as_str(),
+                LiveValue::InlineString(s) => s.as_str(),
+                _ => return
+            };
+            self.set_text(text);
+            self.redraw(cx);
+        }
+    }
+
+    fn handle_event(&mut self, cx: &mut Cx, event: &Event, scope: &mut Scope) {
+        self.animator_handle_event(cx, event);
+
+        match event.hits(cx, self.draw_bg.area()) {
+            Hit::KeyFocus(_) => {
+                self.animator_play(cx, id!(focus.on));
+                self.draw_bg.redraw(cx);
+            }
+            Hit::KeyFocusLost(_) => {
+                self.animator_play(cx, id!(focus.off));
+                self.typed.clear();
+                self.draw_bg.redraw(cx);
+            }
+            Hit::KeyDown(ke) => {
+                let digit = match ke.key_code {
+                    KeyCode::Key0 => Some(0),
+                    KeyCode::Key1 => Some(1),
+                    KeyCode::Key2 => Some(2),
+                    KeyCode::Key3 => Some(3),
+                    KeyCode::Key4 => Some(4),
+                    KeyCode::Key5 => Some(5),
+                    KeyCode::Key6 => Some(6),
+                    KeyCode::Key7 => Some(7),
+                    KeyCode::Key8 => Some(8),
+                    KeyCode::Key9 => Some(9),
+                    _ => None
+                };
+                if let Some(digit) = digit {
+                    self.type_digit(cx, scope, digit);
+                    return
+                }
+                match ke.key_code {
+                    KeyCode::ArrowUp => self.step(cx, scope, 1),
+                    KeyCode::ArrowDown => self.step(cx, scope, -1),
+                    KeyCode::PageUp => self.step(cx, scope, 10),
+                    KeyCode::PageDown => self.step(cx, scope, -10),
+                    KeyCode::ArrowLeft if self.segment > 0 => {
+                        self.focus_segment(cx, self.segment - 1)
+                    }
+                    KeyCode::ArrowRight => self.focus_segment(cx, self.segment + 1),
+                    KeyCode::Home => {
+                        if let Some(min) = self.min {
+                            self.change(cx, scope, min);
+                        }
+                    }
+                    KeyCode::End => {
+                        if let Some(max) = self.max {
+                            self.change(cx, scope, max);
+                        }
+                    }
+                    _ => ()
+                }
+            }
+            Hit::FingerDown(fe) => {
+                cx.set_key_focus(self.draw_bg.area());
+                if self.stepper_rect.contains(fe.abs) {
+                    let up = fe.abs.y < self.stepper_rect.pos.y + 0.5 * self.stepper_rect.size.y;
+                    self.step(cx, scope, if up {1} else {-1});
+                }
+                else if let Some(index) = self.segment_rects.iter().position( | r | r.contains(fe.abs)) {
+                    self.focus_segment(cx, index);
+                }
+            }
+            Hit::FingerScroll(fs) => {
+                if let Some(index) = self.segment_rects.iter().position( | r | r.contains(fs.abs)) {
+                    self.segment = index;
+                }
+                if fs.scroll.y < 0.0 {
+                    self.step(cx, scope, 1);
+                }
+                else if fs.scroll.y > 0.0 {
+                    self.step(cx, scope, -1);
+                }
+            }
+            Hit::FingerHoverIn(_) => {
+                cx.set_cursor(MouseCursor::Default);
+                self.animator_play(cx, id!(hover.on));
+            }
+            Hit::FingerHoverOut(_) => {
+                self.animator_play(cx, id!(hover.off));
+            }
+            _ => ()
+        }
+    }
+
+    fn draw_walk(&mut self, cx: &mut Cx2d, _scope: &mut Scope, walk: Walk) -> DrawStep {
+        let has_focus = cx.has_key_focus(self.draw_bg.area());
+        let height = self.draw_text.line_height(cx);
+
+        self.draw_bg.begin(cx, walk, self.layout);
+        self.segment_rects.clear();
+        let segments = self.segments();
+        for (index, segment) in segments.iter().enumerate() {
+            if index > 0 {
+                self.draw_text.draw_walk(cx, Walk::fit(), Align::default(), ":");
+            }
+            let rect = cx.walk_turtle(Walk::fixed(self.segment_width, height));
+            self.draw_segment.focus = if has_focus && index == self.segment {1.0} else {0.0};
+            self.draw_segment.draw_abs(cx, rect);
+            let value = match segment {
+                Segment::Hour => self.value.hour,
+                Segment::Minute => self.value.minute,
+                Segment::Second => self.value.second,
+            };
+            Self::draw_text_centered(cx, &mut self.draw_text, rect, &format!("{:02}", value));
+            self.segment_rects.push(rect);
+        }
+        self.stepper_rect = cx.walk_turtle(Walk::fixed(self.stepper_width, height));
+        self.draw_stepper.draw_abs(cx, self.stepper_rect);
+        self.draw_bg.end(cx);
+
+        cx.add_nav_stop(self.draw_bg.area(), NavRole::Slider, Margin::default());
+        DrawStep::done()
+    }
+
+    fn text(&self) -> String {
+        if self.show_seconds {
+            self.value.to_string()
+        }
+        else {
+            format!("{:02}:{:02}", self.value.hour, self.value.minute)
+        }
+    }
+
+    fn set_text(&mut self, v: &str) {
+        if let Some(time) = Time::parse(v) {
+            self.value = time.clamp_to(self.min, self.max);
+        }
+    }
+}
+
+impl TimePickerRef {
+    pub fn changed(&self, actions: &Actions) -> Option<Time> {
+        if let TimePickerAction::Change(time) = actions.find_widget_action_cast(self.widget_uid()) {
+            return Some(time)
+        }
+        None
+    }
+
+    pub fn value(&self) -> Option<Time> {
+        if let Some(inner) = self.borrow() {
+            return Some(inner.value())
+        }
+        None
+    }
+
+    pub fn set_value(&self, cx: &mut Cx, time: Time) {
+        if let Some(mut inner) = self.borrow_mut() {
+            inner.set_value(cx, time);
+        }
+    }
+}
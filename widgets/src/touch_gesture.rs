@@ -1,6 +1,9 @@
 use crate::{
+    makepad_draw::turtle::Axis2,
+    makepad_platform::event::TouchState,
     Area,
     Cx,
+    DVec2,
     Event,
     Hit,
     MouseCursor,
@@ -35,6 +38,8 @@ pub enum TouchMotionChange {
     None,
     ScrollStateChanged,
     ScrolledAtChanged,
+    /// Two fingers moved apart or together, see `pinch_factor` and `pinch_center`.
+    Pinched,
 }
 
 #[derive(Default, Clone)]
@@ -51,7 +56,13 @@ pub struct TouchGesture {
     max_scrolled_at: f64,
     pulldown_maximum: f64,
 
+    horizontal: bool,
+    pinch_span: Option<f64>,
+
     pub scrolled_at: f64,
+    /// How much the distance between two touches changed with the last `Pinched`.
+    pub pinch_factor: f64,
+    pub pinch_center: DVec2,
 }
 
 impl TouchGesture {
@@ -69,6 +80,11 @@ impl TouchGesture {
             min_scrolled_at: f64::MIN,
             max_scrolled_at: f64::MAX,
             pulldown_maximum: 60.0,
+
+            horizontal: false,
+            pinch_span: None,
+            pinch_factor: 1.0,
+            pinch_center: DVec2::default(),
         }
     }
 
@@ -80,6 +96,15 @@ impl TouchGesture {
         self.scroll_mode = scroll_mode;
     }
 
+    /// Scrolls along the vertical axis unless set to `Axis2::Horizontal`.
+    pub fn set_axis(&mut self, axis: Axis2) {
+        self.horizontal = matches!(axis, Axis2::Horizontal);
+    }
+
+    fn axis_abs(&self, abs: DVec2) -> f64 {
+        if self.horizontal {abs.x} else {abs.y}
+    }
+
     pub fn set_range(&mut self, min_offset: f64, max_offset: f64) {
         self.min_scrolled_at = min_offset;
         self.max_scrolled_at = max_offset;
@@ -176,19 +201,38 @@ impl TouchGesture {
             _=>()
         }
 
+        if let Event::TouchUpdate(e) = event {
+            let rect = area.clipped_rect(cx);
+            let touches: Vec<_> = e.touches.iter().filter( | t | {
+                !matches!(t.state, TouchState::Stop) && rect.contains(t.abs)
+            }).collect();
+            if touches.len() == 2 {
+                let span = (touches[0].abs - touches[1].abs).length();
+                let last_span = self.pinch_span.replace(span);
+                self.scroll_state = ScrollState::Stopped;
+                if let Some(last_span) = last_span.filter( | last_span | *last_span > 0.0) {
+                    self.pinch_factor = span / last_span;
+                    self.pinch_center = (touches[0].abs + touches[1].abs) * 0.5;
+                    return TouchMotionChange::Pinched
+                }
+                return TouchMotionChange::None
+            }
+            self.pinch_span = None;
+        }
+
         match event.hits_with_capture_overload(cx, area, true) {
             Hit::FingerDown(e) => {
                 self.scroll_state = ScrollState::Drag {
-                    samples: vec![ScrollSample{abs: e.abs.y, time: e.time}]
+                    samples: vec![ScrollSample{abs: self.axis_abs(e.abs), time: e.time}]
                 };
 
                 return TouchMotionChange::ScrollStateChanged
             }
             Hit::FingerMove(e) => {
                 cx.set_cursor(MouseCursor::Default);
+                let new_abs = self.axis_abs(e.abs);
                 match &mut self.scroll_state {
                     ScrollState::Drag {samples}=>{
-                        let new_abs = e.abs.y;
                         let old_sample = *samples.last().unwrap();
                         samples.push(ScrollSample{abs: new_abs, time: e.time});
                         if samples.len() > 4 {